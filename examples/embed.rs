@@ -0,0 +1,42 @@
+//! Embedding the simulation in another macroquad app. The host draws its own screen, then
+//! hands the window to a pre-seeded run with no HUD that the viewer can only watch; Escape
+//! hands it back.
+//!
+//!     cargo run --example embed
+
+use conways_game_of_life::grid::Position;
+use conways_game_of_life::rule::Rule;
+use conways_game_of_life::themes::ColorTheme;
+use conways_game_of_life::ui::{run_simulation_with, Controls, SimulationConfig};
+use macroquad::prelude::*;
+
+#[macroquad::main("Embedded Life")]
+async fn main() {
+    let mut last_run = None;
+    loop {
+        clear_background(DARKGRAY);
+        draw_text("Host app", 40.0, 80.0, 48.0, WHITE);
+        draw_text("Enter: watch an R-pentomino | Esc: come back here", 40.0, 130.0, 24.0, LIGHTGRAY);
+        if let Some(generation) = last_run {
+            draw_text(&format!("The last run reached generation {}", generation), 40.0, 170.0, 24.0, LIGHTGRAY);
+        }
+
+        if is_key_pressed(KeyCode::Enter) {
+            // Cells given directly, in the middle of a 160x100 torus
+            let r_pentomino = [(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)].map(|(x, y)| Position::new(80 + x, 50 + y));
+            let config = SimulationConfig::new()
+                .grid(160, 100)
+                .wrap(true)
+                .cells(r_pentomino)
+                .rule(Rule::LIFE)
+                .speed(30.0)
+                .theme(ColorTheme::Neon)
+                .paused(false)
+                .hud(false)
+                .controls(Controls::NONE);
+            let summary = run_simulation_with(config).await;
+            last_run = Some(summary.generation);
+        }
+        next_frame().await;
+    }
+}
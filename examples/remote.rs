@@ -0,0 +1,87 @@
+//! Driving a run from outside the render loop, the way a chat bot would. Each line typed on
+//! stdin becomes a command applied between generations; every hundredth generation and any
+//! rejected command are printed as they come back.
+//!
+//!     cargo run --example remote
+//!
+//! Commands: `set X Y [alive|dead]`, `stamp NAME X Y`, `clear`, `speed N`, `pause`, `resume`,
+//! `query [X Y W H]`.
+
+use std::io::BufRead;
+use std::sync::mpsc;
+use std::thread;
+
+use conways_game_of_life::grid::{Position, Rect};
+use conways_game_of_life::remote::{self, Command, Event, Query, Remote, Source};
+use conways_game_of_life::ui::{run_simulation_with, SimulationConfig};
+
+#[macroquad::main("Remote Life")]
+async fn main() {
+    let (Remote { commands, events }, endpoint) = remote::channel();
+
+    thread::spawn(move || {
+        for event in events {
+            match event {
+                Event::Generation(s) if s.generation % 100 == 0 => {
+                    println!("generation {}: {} cells (+{} -{}), hash {:016x}", s.generation, s.population, s.born, s.died, s.hash);
+                }
+                Event::Generation(_) => {}
+                Event::Rejected(reason) => println!("rejected: {}", reason),
+            }
+        }
+    });
+
+    thread::spawn(move || {
+        for line in std::io::stdin().lock().lines().map_while(Result::ok) {
+            let command = match parse(&line) {
+                Ok(command) => command,
+                Err(err) => {
+                    println!("{}", err);
+                    continue;
+                }
+            };
+            // A query waits here for its answer; everything else is fire and forget
+            let (command, answer) = match command {
+                Command::Query(query, _) => {
+                    let (reply, answer) = mpsc::channel();
+                    (Command::Query(query, reply), Some(answer))
+                }
+                other => (other, None),
+            };
+            if commands.send(command).is_err() {
+                break;
+            }
+            if let Some(Ok(snapshot)) = answer.map(|a| a.recv()) {
+                println!("generation {}: {} cells, hash {:016x}{}", snapshot.generation, snapshot.population, snapshot.hash, if snapshot.paused { " (paused)" } else { "" });
+                if !snapshot.cells.is_empty() {
+                    let cells: Vec<String> = snapshot.cells.iter().map(|p| format!("({}, {})", p.x(), p.y())).collect();
+                    println!("  {}", cells.join(" "));
+                }
+            }
+        }
+    });
+
+    let config = SimulationConfig::new().grid(160, 100).paused(false).remote(endpoint);
+    run_simulation_with(config).await;
+}
+
+/// One stdin line as a command; a query's reply sender is filled in by the caller
+fn parse(line: &str) -> Result<Command, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let number = |word: &str| word.parse::<i32>().map_err(|_| format!("not a number: {}", word));
+    match words.as_slice() {
+        ["set", x, y] => Ok(Command::SetCell(Position::new(number(x)?, number(y)?), true)),
+        ["set", x, y, state @ ("alive" | "dead")] => Ok(Command::SetCell(Position::new(number(x)?, number(y)?), *state == "alive")),
+        ["stamp", name @ .., x, y] if !name.is_empty() => Ok(Command::Stamp(Source::Named(name.join(" ")), Position::new(number(x)?, number(y)?))),
+        ["clear"] => Ok(Command::Clear),
+        ["speed", n] => n.parse().map(Command::SetSpeed).map_err(|_| format!("not a speed: {}", n)),
+        ["pause"] => Ok(Command::Pause(true)),
+        ["resume"] => Ok(Command::Pause(false)),
+        ["query"] => Ok(Command::Query(Query::default(), mpsc::channel().0)),
+        ["query", x, y, w, h] => {
+            let region = Rect::new(number(x)?, number(y)?, number(w)?, number(h)?);
+            Ok(Command::Query(Query { region: Some(region) }, mpsc::channel().0))
+        }
+        _ => Err("expected set X Y [alive|dead], stamp NAME X Y, clear, speed N, pause, resume or query [X Y W H]".to_string()),
+    }
+}
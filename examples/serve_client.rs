@@ -0,0 +1,60 @@
+//! Following a served board from another process, the way an LED matrix driver would. Start a
+//! window with `cargo run --release --features serve -- --serve 127.0.0.1:7878`, then:
+//!
+//!     cargo run --example serve_client [address]
+//!
+//! The client keeps its own copy of the board from the `full` and `delta` lines, prints the top
+//! left 32x16 cells as a stand-in for the matrix every 50 generations, and sends each line typed
+//! on stdin (`pause`, `resume`, `speed 30`, `stamp glider 20 20`) to the server.
+
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::thread;
+
+use conways_game_of_life::grid::Position;
+use conways_game_of_life::serve::Message;
+
+/// Cells shown, as on a 32x16 matrix
+const MATRIX: (i32, i32) = (32, 16);
+
+fn main() -> std::io::Result<()> {
+    let address = std::env::args().nth(1).unwrap_or_else(|| "127.0.0.1:7878".to_string());
+    let stream = TcpStream::connect(&address)?;
+    let mut commands = stream.try_clone()?;
+    thread::spawn(move || {
+        for line in std::io::stdin().lock().lines().map_while(Result::ok) {
+            if writeln!(commands, "{}", line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut board: HashSet<Position> = HashSet::new();
+    let mut generation = 0;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        match Message::parse(&line) {
+            Ok(Message::Full { generation: at, cells, .. }) => {
+                println!("whole board at generation {}", at);
+                (generation, board) = (at, cells);
+            }
+            Ok(Message::Delta { generation: at, born, died }) => {
+                board.retain(|p| !died.contains(p));
+                board.extend(born);
+                generation = at;
+            }
+            Ok(Message::Rejected(reason)) => println!("rejected: {}", reason),
+            Err(_) => println!("{}", line),
+        }
+        if generation % 50 == 0 {
+            println!("generation {}: {} cells", generation, board.len());
+            for y in 0..MATRIX.1 {
+                let row: String = (0..MATRIX.0).map(|x| if board.contains(&Position::new(x, y)) { '#' } else { '.' }).collect();
+                println!("{}", row);
+            }
+        }
+    }
+    println!("the server closed the connection");
+    Ok(())
+}
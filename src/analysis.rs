@@ -0,0 +1,480 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use crate::config::{CLEANUP_MIN_SIZE, GROWTH_MIN_SAMPLES, GROWTH_WINDOW};
+use crate::grid::{Grid, Position, NEIGHBOR_OFFSETS};
+use crate::patterns::{Interest, Transform};
+use crate::simulation::map_bytes;
+
+/// Cell offsets of one phase of an object
+type Shape = &'static [(i32, i32)];
+
+/// Small objects recognized by the census: name, period and one phase's cells
+const KNOWN_OBJECTS: [(&str, u32, Shape); 13] = [
+    ("Block", 1, &[(0, 0), (1, 0), (0, 1), (1, 1)]),
+    ("Beehive", 1, &[(1, 0), (2, 0), (0, 1), (3, 1), (1, 2), (2, 2)]),
+    ("Loaf", 1, &[(1, 0), (2, 0), (0, 1), (3, 1), (1, 2), (3, 2), (2, 3)]),
+    ("Boat", 1, &[(0, 0), (1, 0), (0, 1), (2, 1), (1, 2)]),
+    ("Ship", 1, &[(0, 0), (1, 0), (0, 1), (2, 1), (1, 2), (2, 2)]),
+    ("Tub", 1, &[(1, 0), (0, 1), (2, 1), (1, 2)]),
+    ("Pond", 1, &[(1, 0), (2, 0), (0, 1), (3, 1), (0, 2), (3, 2), (1, 3), (2, 3)]),
+    ("Barge", 1, &[(1, 0), (0, 1), (2, 1), (1, 2), (3, 2), (2, 3)]),
+    ("Long boat", 1, &[(0, 0), (1, 0), (0, 1), (2, 1), (1, 2), (3, 2), (2, 3)]),
+    ("Blinker", 2, &[(0, 0), (1, 0), (2, 0)]),
+    ("Toad", 2, &[(1, 0), (2, 0), (3, 0), (0, 1), (1, 1), (2, 1)]),
+    ("Beacon", 2, &[(0, 0), (1, 0), (0, 1), (3, 2), (2, 3), (3, 3)]),
+    ("Glider", 4, &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]),
+];
+
+/// Result of classifying every connected object on the board
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Census {
+    pub counts: BTreeMap<&'static str, usize>, // Recognized objects by name
+    pub unidentified: Vec<usize>,              // Sizes of unrecognized components, largest first
+}
+
+impl Census {
+    /// Total number of connected components found
+    pub fn total_objects(&self) -> usize {
+        self.counts.values().sum::<usize>() + self.unidentified.len()
+    }
+}
+
+/// Translation- and symmetry-independent form of a set of cells
+type Canonical = Vec<(i32, i32)>;
+
+/// Shift cells so the minimum corner sits at the origin, sorted for comparison
+fn normalize(cells: &[(i32, i32)]) -> Canonical {
+    let min_x = cells.iter().map(|c| c.0).min().unwrap_or(0);
+    let min_y = cells.iter().map(|c| c.1).min().unwrap_or(0);
+    let mut out: Vec<_> = cells.iter().map(|&(x, y)| (x - min_x, y - min_y)).collect();
+    out.sort_unstable();
+    out
+}
+
+/// What isolated cells turn into: run them on an unbounded plane for up to `max` generations
+/// and look for the starting shape again. `None` if it never comes back (or dies out) in time,
+/// or for more than `max_cells` cells.
+pub fn classify(cells: &[(i32, i32)], max: u32, max_cells: usize) -> Option<Interest> {
+    if cells.is_empty() || cells.len() > max_cells {
+        return None;
+    }
+    let corner = |cells: &[(i32, i32)]| {
+        (cells.iter().map(|c| c.0).min().unwrap_or(0), cells.iter().map(|c| c.1).min().unwrap_or(0))
+    };
+    let (start, origin) = (normalize(cells), corner(cells));
+    let mut board = cells.to_vec();
+    for period in 1..=max {
+        board = step_unbounded(&board);
+        if board.is_empty() {
+            return None;
+        }
+        if board.len() == start.len() && normalize(&board) == start {
+            let (x, y) = corner(&board);
+            let shift = (x - origin.0).unsigned_abs().max((y - origin.1).unsigned_abs());
+            return Some(match (shift, period) {
+                (0, 1) => Interest::StillLife,
+                (0, period) => Interest::Oscillator { period },
+                (shift, period) => Interest::Spaceship { period, shift },
+            });
+        }
+    }
+    None
+}
+
+/// Smallest normalized form over the 8 rotations/reflections
+pub fn canonicalize(cells: &[(i32, i32)]) -> Canonical {
+    Transform::all()
+        .iter()
+        .map(|t| normalize(&cells.iter().map(|&(x, y)| t.apply(x, y)).collect::<Vec<_>>()))
+        .min()
+        .unwrap_or_default()
+}
+
+/// One generation of the standard rules on an unbounded plane
+pub(crate) fn step_unbounded(cells: &[(i32, i32)]) -> Vec<(i32, i32)> {
+    let live: HashSet<_> = cells.iter().copied().collect();
+    let mut counts: HashMap<(i32, i32), u8> = HashMap::new();
+    for &(x, y) in cells {
+        for (dx, dy) in NEIGHBOR_OFFSETS {
+            *counts.entry((x + dx, y + dy)).or_insert(0) += 1;
+        }
+    }
+    counts
+        .into_iter()
+        .filter(|(p, n)| *n == 3 || (*n == 2 && live.contains(p)))
+        .map(|(p, _)| p)
+        .collect()
+}
+
+/// Canonical forms of every phase of every known object
+fn dictionary() -> HashMap<Canonical, &'static str> {
+    let mut dict = HashMap::new();
+    for (name, period, cells) in KNOWN_OBJECTS {
+        let mut phase = cells.to_vec();
+        for _ in 0..period {
+            dict.insert(canonicalize(&phase), name);
+            phase = step_unbounded(&phase);
+        }
+    }
+    dict
+}
+
+/// Split the live cells into 8-connected components under the grid's topology.
+/// Components are returned in unwrapped coordinates so objects straddling a torus
+/// seam keep their shape.
+pub fn components(live: &HashSet<Position>, grid: &Grid) -> Vec<Vec<(i32, i32)>> {
+    let mut seen: HashSet<Position> = HashSet::with_capacity(live.len());
+    let mut out = Vec::new();
+    for &start in live {
+        if !seen.insert(start) { continue; }
+        let mut component = Vec::new();
+        let mut queue = VecDeque::from([(start, (start.x(), start.y()))]);
+        while let Some((pos, (ux, uy))) = queue.pop_front() {
+            component.push((ux, uy));
+            for (dx, dy) in NEIGHBOR_OFFSETS {
+                let Some(n) = grid.resolve(pos.x() + dx, pos.y() + dy) else { continue; };
+                if live.contains(&n) && seen.insert(n) {
+                    queue.push_back((n, (ux + dx, uy + dy)));
+                }
+            }
+        }
+        out.push(component);
+    }
+    out
+}
+
+/// Count known still lifes, oscillators and spaceships on the board
+pub fn census(live: &HashSet<Position>, grid: &Grid) -> Census {
+    let dict = dictionary();
+    let mut result = Census::default();
+    for component in components(live, grid) {
+        match dict.get(&canonicalize(&component)) {
+            Some(name) => *result.counts.entry(name).or_insert(0) += 1,
+            None => result.unidentified.push(component.len()),
+        }
+    }
+    result.unidentified.sort_unstable_by(|a, b| b.cmp(a));
+    result
+}
+
+/// What a cleanup removes: objects with fewer than `min_size` cells, and with `known` set any
+/// still life or period-2 oscillator the census recognizes, whatever its size
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cleanup {
+    pub min_size: usize, // 0 removes nothing by size
+    pub known: bool,
+}
+
+impl Default for Cleanup {
+    fn default() -> Self {
+        Self { min_size: CLEANUP_MIN_SIZE, known: true }
+    }
+}
+
+/// The objects `cleanup` would remove, each as the board cells it covers
+pub fn debris(live: &HashSet<Position>, grid: &Grid, cleanup: Cleanup) -> Vec<Vec<Position>> {
+    let settled: HashSet<Canonical> = if cleanup.known {
+        KNOWN_OBJECTS.iter()
+            .filter(|(_, period, _)| *period <= 2)
+            .flat_map(|&(_, period, cells)| {
+                let mut phase = cells.to_vec();
+                (0..period).map(move |_| {
+                    let form = canonicalize(&phase);
+                    phase = step_unbounded(&phase);
+                    form
+                })
+            })
+            .collect()
+    } else {
+        HashSet::new()
+    };
+    let largest = settled.iter().map(Vec::len).max().unwrap_or(0);
+    components(live, grid).into_iter()
+        .filter(|c| c.len() < cleanup.min_size || (c.len() <= largest && settled.contains(&canonicalize(c))))
+        // Components come unwrapped; resolving puts cells across a torus seam back on the board
+        .map(|c| c.into_iter().filter_map(|(x, y)| grid.resolve(x, y)).collect())
+        .collect()
+}
+
+/// Cells of the objects that hold still, each classified on its own as the census sees it
+pub fn still_lifes(live: &HashSet<Position>, grid: &Grid) -> HashSet<Position> {
+    components(live, grid).into_iter()
+        .filter(|c| classify(c, 1, usize::MAX) == Some(Interest::StillLife))
+        // Components come unwrapped; resolving puts cells across a torus seam back on the board
+        .flat_map(|c| c.into_iter().filter_map(|(x, y)| grid.resolve(x, y)))
+        .collect()
+}
+
+/// Age ranges of the histogram buckets, inclusive; a newborn cell has age 1
+pub const AGE_BUCKETS: [(u64, u64); 5] = [(1, 1), (2, 5), (6, 20), (21, 100), (101, u64::MAX)];
+
+/// Live cells grouped by age, plus the stable/churning split
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AgeHistogram {
+    pub counts: [usize; 5], // Cells per `AGE_BUCKETS` entry
+    pub stable: usize,      // Cells older than the stability threshold
+    pub total: usize,
+}
+
+impl AgeHistogram {
+    /// Bucket label such as "2-5" or "100+"
+    pub fn label(bucket: usize) -> String {
+        match AGE_BUCKETS[bucket] {
+            (lo, hi) if lo == hi => lo.to_string(),
+            (lo, u64::MAX) => format!("{}+", lo - 1),
+            (lo, hi) => format!("{}-{}", lo, hi),
+        }
+    }
+}
+
+/// Birth generation of every live cell. Only births and deaths touch the counts, so the
+/// histogram is read from how many cells were born in each generation rather than by rescanning ages.
+#[derive(Clone, Debug, Default)]
+pub struct AgeTracker {
+    born_at: HashMap<Position, u64>,
+    births: BTreeMap<u64, usize>, // Live cells per birth generation
+}
+
+impl AgeTracker {
+    /// Start tracking with every current cell counted as newborn
+    pub fn new(live: &HashSet<Position>, generation: u64) -> Self {
+        let mut tracker = Self::default();
+        tracker.sync(live, generation);
+        tracker
+    }
+
+    /// Bring the tracker in line with `live`: cells it hasn't seen are born at `generation`,
+    /// cells no longer alive are dropped. Edits between generations are absorbed the same way.
+    pub fn sync(&mut self, live: &HashSet<Position>, generation: u64) {
+        let births = &mut self.births;
+        self.born_at.retain(|p, born| {
+            let alive = live.contains(p);
+            if !alive {
+                let n = births.get_mut(born).expect("birth count for tracked cell");
+                *n -= 1;
+                if *n == 0 { births.remove(born); }
+            }
+            alive
+        });
+        for &p in live {
+            if let std::collections::hash_map::Entry::Vacant(e) = self.born_at.entry(p) {
+                e.insert(generation);
+                *self.births.entry(generation).or_default() += 1;
+            }
+        }
+    }
+
+    /// Approximate heap size: the map of birth generations, and a tree node per birth generation
+    pub fn bytes(&self) -> usize {
+        map_bytes(&self.born_at) + self.births.len() * 4 * std::mem::size_of::<(u64, usize)>()
+    }
+
+    /// Age of the cell at `p` at `generation`, 1 for a newborn; `None` for a cell not tracked
+    pub fn age(&self, p: Position, generation: u64) -> Option<u64> {
+        self.born_at.get(&p).map(|&born| generation.saturating_sub(born) + 1)
+    }
+
+    /// Histogram at `generation`; cells older than `stable_age` count as stable
+    pub fn histogram(&self, generation: u64, stable_age: u64) -> AgeHistogram {
+        let mut h = AgeHistogram::default();
+        for (&born, &n) in &self.births {
+            let age = generation.saturating_sub(born) + 1;
+            if let Some(bucket) = AGE_BUCKETS.iter().position(|&(lo, hi)| (lo..=hi).contains(&age)) {
+                h.counts[bucket] += n;
+            }
+            if age > stable_age { h.stable += n; }
+            h.total += n;
+        }
+        h
+    }
+}
+
+/// Figures gathered while a run plays, for the summary shown when it ends
+#[derive(Clone, Debug, Default)]
+pub struct RunStats {
+    pub peak: usize,               // Largest population seen, sampled once per frame
+    pub peak_generation: u64,
+    pub extinct_at: Option<u64>,   // Generation the board died out by stepping, while it stays empty
+    pub running_seconds: f64,      // Wall-clock time spent unpaused
+    pub generations: u64,          // Generations stepped, counting again any replayed after a rewind
+    last_generation: u64,
+    last_population: usize,
+}
+
+impl RunStats {
+    /// Record the board's generation and population
+    pub fn observe(&mut self, generation: u64, population: usize) {
+        if population > self.peak {
+            self.peak = population;
+            self.peak_generation = generation;
+        }
+        self.generations += generation.saturating_sub(self.last_generation);
+        if population > 0 || generation < self.last_generation {
+            // Alive again, or cleared back to generation 0
+            self.extinct_at = None;
+        } else if self.last_population > 0 && generation > self.last_generation {
+            self.extinct_at = Some(generation);
+        }
+        self.last_generation = generation;
+        self.last_population = population;
+    }
+
+    /// Carry on from a board restored at `generation`. A peak reached later belonged to the
+    /// abandoned branch, so it gives way to the restored population; time spent running is kept.
+    pub fn rewind(&mut self, generation: u64, population: usize) {
+        if self.peak_generation > generation {
+            self.peak = population;
+            self.peak_generation = generation;
+        }
+        self.extinct_at = None;
+        self.last_generation = generation;
+        self.last_population = population;
+    }
+}
+
+/// How the population trends over the growth window
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Growth {
+    Constant,  // Flat, or oscillating around a level
+    Linear,    // A steady rate, like a gun adding gliders
+    Quadratic, // An accelerating rate, like a breeder adding guns
+}
+
+impl Growth {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Growth::Constant => "flat",
+            Growth::Linear => "linear",
+            Growth::Quadratic => "quadratic",
+        }
+    }
+}
+
+/// A classified trend. `rate` is in cells per generation, or per generation squared when
+/// the growth is quadratic.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GrowthTrend {
+    pub growth: Growth,
+    pub rate: f64,
+}
+
+impl std::fmt::Display for GrowthTrend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.growth {
+            Growth::Constant => write!(f, "{}", self.growth.name()),
+            Growth::Linear => write!(f, "{} {:+.3}/gen", self.growth.name(), self.rate),
+            Growth::Quadratic => write!(f, "{} {:+.5}/gen²", self.growth.name(), self.rate),
+        }
+    }
+}
+
+/// Least-squares polynomial of `degree` through the points: coefficients from the constant
+/// term up, and the sum of squared residuals
+fn fit(points: &[(f64, f64)], degree: usize) -> ([f64; 3], f64) {
+    let n = degree + 1;
+    // Normal equations, solved by Gaussian elimination; x stays within [-1, 1] so they are well conditioned
+    let mut m = [[0.0; 4]; 3];
+    for &(x, y) in points {
+        let powers = [1.0, x, x * x];
+        for i in 0..n {
+            for j in 0..n {
+                m[i][j] += powers[i] * powers[j];
+            }
+            m[i][3] += powers[i] * y;
+        }
+    }
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&a, &b| m[a][col].abs().total_cmp(&m[b][col].abs())).unwrap_or(col);
+        m.swap(col, pivot);
+        if m[col][col].abs() < 1e-12 {
+            continue;
+        }
+        let pivot = m[col];
+        for (r, row) in m.iter_mut().enumerate().take(n) {
+            if r != col {
+                let k = row[col] / pivot[col];
+                row.iter_mut().zip(pivot).skip(col).for_each(|(v, p)| *v -= k * p);
+            }
+        }
+    }
+    let mut coef = [0.0; 3];
+    for i in 0..n {
+        if m[i][i].abs() >= 1e-12 { coef[i] = m[i][3] / m[i][i]; }
+    }
+    let rss = points.iter().map(|&(x, y)| (y - (coef[0] + coef[1] * x + coef[2] * x * x)).powi(2)).sum();
+    (coef, rss)
+}
+
+/// Classify a population series by fitting a constant, a line and a parabola. A higher
+/// degree is only taken when it explains most of what the lower one leaves over, and that
+/// leftover is more than a cell on average, so oscillators and guns aren't mistaken for
+/// curves. `None` with fewer than three distinct generations.
+pub fn classify_growth(samples: &[(u64, usize)]) -> Option<GrowthTrend> {
+    let (first, last) = (samples.iter().map(|s| s.0).min()?, samples.iter().map(|s| s.0).max()?);
+    if samples.len() < 3 || last == first {
+        return None;
+    }
+    // Generations mapped onto [-1, 1]; rates are scaled back at the end
+    let (mid, half) = ((first + last) as f64 / 2.0, (last - first) as f64 / 2.0);
+    let points: Vec<(f64, f64)> = samples.iter().map(|&(g, p)| ((g as f64 - mid) / half, p as f64)).collect();
+    let n = points.len() as f64;
+    let (_, rss0) = fit(&points, 0);
+    let (line, rss1) = fit(&points, 1);
+    let (parabola, rss2) = fit(&points, 2);
+    let explains = |lower: f64, higher: f64| lower / n > 1.0 && higher < 0.25 * lower;
+    let trend = if parabola[2] > 0.0 && explains(rss1, rss2) {
+        GrowthTrend { growth: Growth::Quadratic, rate: 2.0 * parabola[2] / (half * half) }
+    } else if explains(rss0, rss1) {
+        GrowthTrend { growth: Growth::Linear, rate: line[1] / half }
+    } else {
+        GrowthTrend { growth: Growth::Constant, rate: line[1] / half }
+    };
+    Some(trend)
+}
+
+/// Population over the last `GROWTH_WINDOW` generations, one sample per generation seen
+#[derive(Clone, Debug, Default)]
+pub struct GrowthTracker {
+    samples: VecDeque<(u64, usize)>,
+}
+
+impl GrowthTracker {
+    /// Record the board's generation and population. Going back in time, by a restart or a
+    /// restored bookmark, starts the history over.
+    pub fn observe(&mut self, generation: u64, population: usize) {
+        match self.samples.back() {
+            Some(&(last, _)) if generation == last => return,
+            Some(&(last, _)) if generation < last => self.samples.clear(),
+            _ => {}
+        }
+        self.samples.push_back((generation, population));
+        while self.samples.front().is_some_and(|&(g, _)| g + GROWTH_WINDOW < generation) {
+            self.samples.pop_front();
+        }
+    }
+
+    /// The last `n` samples of (generation, population), oldest first
+    pub fn recent(&self, n: usize) -> Vec<(u64, usize)> {
+        self.samples.iter().skip(self.samples.len().saturating_sub(n)).copied().collect()
+    }
+
+    /// The trend over the window, once it holds enough generations to judge
+    pub fn trend(&self) -> Option<GrowthTrend> {
+        if self.samples.len() < GROWTH_MIN_SAMPLES {
+            return None;
+        }
+        classify_growth(&self.samples.iter().copied().collect::<Vec<_>>())
+    }
+}
+
+/// Smallest period up to `max` after which the board repeats exactly, if it does
+pub fn period(grid: &Grid, live: &HashSet<Position>, max: u32) -> Option<u32> {
+    let mut board = live.clone();
+    for p in 1..=max {
+        board = grid.next_generation(&board);
+        if board == *live {
+            return Some(p);
+        }
+    }
+    None
+}
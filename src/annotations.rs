@@ -0,0 +1,191 @@
+//! Notes pinned to cells, such as "this glider arrives at gen 212". They belong to a board's
+//! session, not its simulation, so stepping, hashing and exports never see them. They are kept
+//! in grid coordinates and drawn through the board view, so they pan and zoom with the cells.
+//! Debug dumps save them with the board.
+
+use macroquad::color::Color;
+
+use crate::grid::Position;
+
+/// Marker color of a note
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum NoteColor {
+    #[default]
+    Yellow,
+    Cyan,
+    Pink,
+    Green,
+    White,
+}
+
+impl NoteColor {
+    pub const ALL: [NoteColor; 5] = [NoteColor::Yellow, NoteColor::Cyan, NoteColor::Pink, NoteColor::Green, NoteColor::White];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            NoteColor::Yellow => "yellow",
+            NoteColor::Cyan => "cyan",
+            NoteColor::Pink => "pink",
+            NoteColor::Green => "green",
+            NoteColor::White => "white",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// The next color in `ALL`, wrapping around
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&c| c == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            NoteColor::Yellow => Color::from_rgba(255, 214, 10, 255),
+            NoteColor::Cyan => Color::from_rgba(40, 210, 240, 255),
+            NoteColor::Pink => Color::from_rgba(255, 105, 180, 255),
+            NoteColor::Green => Color::from_rgba(80, 220, 100, 255),
+            NoteColor::White => Color::from_rgba(240, 240, 240, 255),
+        }
+    }
+}
+
+/// One note and the cell it is pinned to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Annotation {
+    pub at: Position,
+    pub text: String,
+    pub color: NoteColor,
+}
+
+/// A board's notes, oldest first
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Annotations {
+    pub notes: Vec<Annotation>,
+}
+
+impl Annotations {
+    pub fn len(&self) -> usize {
+        self.notes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+
+    /// Pin a note to `at`, returning its index
+    pub fn add(&mut self, at: Position, text: impl Into<String>, color: NoteColor) -> usize {
+        self.notes.push(Annotation { at, text: text.into(), color });
+        self.notes.len() - 1
+    }
+
+    /// The newest note pinned to `at`
+    pub fn at(&self, at: Position) -> Option<usize> {
+        self.notes.iter().rposition(|n| n.at == at)
+    }
+
+    /// Re-pin note `i` to another cell
+    pub fn move_to(&mut self, i: usize, at: Position) {
+        if let Some(note) = self.notes.get_mut(i) {
+            note.at = at;
+        }
+    }
+
+    /// Move every note with the board when a crop or a grown grid moves its cells
+    pub fn shift(&mut self, dx: i32, dy: i32) {
+        for note in &mut self.notes {
+            note.at = Position::new(note.at.x() + dx, note.at.y() + dy);
+        }
+    }
+
+    pub fn remove(&mut self, i: usize) -> Option<Annotation> {
+        (i < self.notes.len()).then(|| self.notes.remove(i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debug_dump::DebugDump;
+    use crate::deltas::DeltaLog;
+    use crate::grid::board_hash;
+    use crate::patterns::GliderPattern;
+    use crate::simulation::Simulation;
+
+    /// Three notes, two on the same cell, with text that needs escaping
+    fn sample() -> Annotations {
+        let mut notes = Annotations::default();
+        notes.add(Position::new(3, 4), "glider arrives at gen 212", NoteColor::default());
+        notes.add(Position::new(-2, 40), "\"quoted\" \\ tab\there\nand é ✓", NoteColor::Cyan);
+        notes.add(Position::new(3, 4), "newer note on the same cell", NoteColor::White);
+        notes
+    }
+
+    #[test]
+    fn notes_are_found_moved_and_removed() {
+        let mut notes = sample();
+        assert_eq!(notes.at(Position::new(3, 4)), Some(2), "the newest note on a cell wins");
+        assert_eq!(notes.at(Position::new(0, 0)), None);
+        notes.move_to(0, Position::new(10, 11));
+        assert_eq!(notes.at(Position::new(10, 11)), Some(0));
+        notes.shift(-1, 2);
+        assert_eq!(notes.at(Position::new(9, 13)), Some(0));
+        assert_eq!(notes.remove(1).map(|n| n.color), Some(NoteColor::Cyan));
+        assert!(notes.remove(5).is_none());
+        assert_eq!(notes.len(), 2);
+    }
+
+    #[test]
+    fn colors_cycle_and_have_names() {
+        let mut color = NoteColor::default();
+        for _ in 0..5 {
+            assert_eq!(NoteColor::from_name(color.name()), Some(color));
+            color = color.next();
+        }
+        assert_eq!(color, NoteColor::Yellow);
+        assert_eq!(NoteColor::White.next(), NoteColor::Yellow);
+        assert_eq!(NoteColor::from_name("mauve"), None);
+    }
+
+    #[test]
+    fn notes_follow_their_cell_through_a_crop() {
+        let mut cropped = Simulation::new(40, 40);
+        cropped.live.insert(Position::new(20, 20));
+        let mut pinned = Annotations::default();
+        pinned.add(Position::new(20, 20), "on the cell", NoteColor::Green);
+        let (dx, dy) = cropped.crop_to_content(2).unwrap();
+        pinned.shift(dx, dy);
+        assert!(cropped.live.contains(&pinned.notes[0].at), "the note is at {:?}, away from its cell", pinned.notes[0].at);
+    }
+
+    #[test]
+    fn notes_survive_gold_files_and_debug_dumps() {
+        let notes = sample();
+        let mut sim = Simulation::new(16, 16);
+        sim.place(&GliderPattern).unwrap();
+        let hash = board_hash(&sim.live);
+        let mut log = DeltaLog::new(&sim);
+        log.annotations = notes.notes.clone();
+        assert_eq!(DeltaLog::from_bytes(&log.to_bytes()).unwrap().annotations, notes.notes);
+
+        let dump = DebugDump { annotations: notes.notes.clone(), ..DebugDump::capture(&sim, Vec::new(), String::new(), "") };
+        let read = DebugDump::parse(&dump.to_json()).unwrap();
+        assert_eq!(read, dump);
+        assert_eq!(board_hash(&read.simulation().unwrap().live), hash, "notes changed the rebuilt board");
+        let json = dump.to_json();
+        assert!(DebugDump::parse(&json.replace("\"cyan\"", "\"mauve\"")).is_err());
+        assert!(DebugDump::parse(&json.replace("\"x\": -2", "\"x\": 1.5")).is_err());
+    }
+
+    #[test]
+    fn dumps_written_before_notes_read_with_none() {
+        let mut sim = Simulation::new(16, 16);
+        sim.place(&GliderPattern).unwrap();
+        let bare = DebugDump::capture(&sim, Vec::new(), String::new(), "").to_json();
+        let older = bare.replace("  \"annotations\": [],\n", "");
+        assert_ne!(older, bare);
+        assert!(DebugDump::parse(&older).unwrap().annotations.is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+//! Bookmarks: full snapshots of generations picked by the user. They are kept apart from the
+//! delta recording and the time-lapse gallery, so one can be returned to whatever has been
+//! recorded or thinned out since.
+
+use crate::config::BOOKMARK_MAX;
+use crate::grid::{Grid, Position, Topology};
+use crate::simulation::{vec_bytes, Simulation};
+use crate::themes::ColorTheme;
+use crate::timelapse::{rasterize, Thumbnail};
+
+/// One saved board
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bookmark {
+    pub label: String,
+    pub generation: u64,
+    pub width: i32,
+    pub height: i32,
+    pub topology: Topology,
+    pub seed: u64,           // Edge spawning draws from it, so the branch plays out the same way
+    pub live: Vec<Position>, // Sorted by row, as in delta logs
+}
+
+impl Bookmark {
+    /// Snapshot the board as it is now
+    pub fn capture(sim: &Simulation, label: impl Into<String>) -> Self {
+        let mut live: Vec<Position> = sim.live.iter().copied().collect();
+        live.sort_by_key(|p| (p.y(), p.x()));
+        Self {
+            label: label.into(),
+            generation: sim.generation,
+            width: sim.grid.width,
+            height: sim.grid.height,
+            topology: sim.grid.topology,
+            seed: sim.seed,
+            live,
+        }
+    }
+
+    /// Label used when none is typed
+    pub fn auto_label(generation: u64) -> String {
+        format!("Generation {}", generation)
+    }
+
+    /// Grid the board was captured on
+    pub fn grid(&self) -> Grid {
+        let mut grid = Grid::new(self.width, self.height);
+        grid.topology = self.topology;
+        grid
+    }
+
+    /// Put the board back exactly: grid, seed, cells and generation. The rule stays the current
+    /// one. Diffs and cell ages start over from here, as after any jump in time.
+    pub fn restore(&self, sim: &mut Simulation) {
+        sim.grid = Grid { rule: sim.grid.rule, ..self.grid() };
+        sim.seed = self.seed;
+        sim.restore(self.live.iter().copied().collect(), self.generation);
+    }
+
+    /// The board as a simulation of its own, for thumbnails and checks
+    pub fn board(&self) -> Simulation {
+        let mut sim = Simulation::new(self.width, self.height);
+        self.restore(&mut sim);
+        sim
+    }
+}
+
+/// A bookmark with its thumbnail
+pub struct Entry {
+    pub bookmark: Bookmark,
+    pub thumbnail: Thumbnail,
+    used: u64, // Tick of the last capture or restore, for evicting the least recently used
+}
+
+/// The run's bookmarks, oldest first, at most `BOOKMARK_MAX` of them
+#[derive(Default)]
+pub struct Bookmarks {
+    pub entries: Vec<Entry>,
+    tick: u64,
+}
+
+impl Bookmarks {
+    /// Add a bookmark. When full, the least recently used one makes room and is returned.
+    pub fn add(&mut self, bookmark: Bookmark, theme: ColorTheme) -> Option<Bookmark> {
+        let evicted = (self.entries.len() >= BOOKMARK_MAX)
+            .then(|| self.entries.iter().enumerate().min_by_key(|(_, e)| e.used).map(|(i, _)| i))
+            .flatten()
+            .map(|i| self.entries.remove(i).bookmark);
+        self.tick += 1;
+        let thumbnail = Thumbnail::new(bookmark.generation, rasterize(&bookmark.board(), theme));
+        self.entries.push(Entry { bookmark, thumbnail, used: self.tick });
+        evicted
+    }
+
+    /// Bookmark `i`, marked as just used
+    pub fn touch(&mut self, i: usize) -> Option<&Bookmark> {
+        self.tick += 1;
+        let entry = self.entries.get_mut(i)?;
+        entry.used = self.tick;
+        Some(&entry.bookmark)
+    }
+
+    pub fn remove(&mut self, i: usize) -> Option<Bookmark> {
+        (i < self.entries.len()).then(|| self.entries.remove(i).bookmark)
+    }
+
+    /// The bookmarks without thumbnails, for saving
+    pub fn to_vec(&self) -> Vec<Bookmark> {
+        self.entries.iter().map(|e| e.bookmark.clone()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Approximate heap size of the saved boards and their thumbnails
+    pub fn bytes(&self) -> usize {
+        self.entries.iter().map(|e| vec_bytes(&e.bookmark.live) + e.bookmark.label.len() + e.thumbnail.bytes()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
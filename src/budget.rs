@@ -0,0 +1,74 @@
+//! Keeping a run inside its memory budget. What can be rebuilt, or only helps to look back, goes
+//! first: the last step's diff, then the time-lapse thumbnails, then the oldest recorded history.
+//! Only when what is left is still over the budget does the run have to pause.
+
+use crate::deltas::DeltaLog;
+use crate::simulation::{MemoryUsage, Simulation};
+use crate::timelapse::Gallery;
+
+/// What one `enforce` freed, in bytes, by what held it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Shed {
+    pub diff: usize,
+    pub timelapse: usize,
+    pub deltas: usize,
+    pub generations: usize, // Recorded generations folded into the delta log's reference
+}
+
+impl Shed {
+    pub fn total(&self) -> usize {
+        self.diff + self.timelapse + self.deltas
+    }
+
+    /// What went, for a toast, e.g. "time-lapse thumbnails (3.1 MB)"; `None` when nothing did
+    pub fn describe(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.timelapse > 0 {
+            parts.push(format!("time-lapse thumbnails ({})", MemoryUsage::format(self.timelapse)));
+        }
+        if self.generations > 0 {
+            parts.push(format!("the oldest {} recorded generations ({})", self.generations, MemoryUsage::format(self.deltas)));
+        }
+        if parts.is_empty() && self.diff > 0 {
+            parts.push(format!("the last step's diff ({})", MemoryUsage::format(self.diff)));
+        }
+        (!parts.is_empty()).then(|| parts.join(" and "))
+    }
+}
+
+/// Free the expendable parts of a run whose `usage` is over `budget`, the cheapest to lose
+/// first, stopping once it fits. Returns what went and the usage after.
+pub fn enforce(budget: usize, mut usage: MemoryUsage, sim: &mut Simulation, gallery: &mut Gallery, recorder: Option<&mut DeltaLog>) -> (Shed, MemoryUsage) {
+    let mut shed = Shed::default();
+    if usage.total() <= budget {
+        return (shed, usage);
+    }
+    // Rebuilt by the next step
+    if let Some(diff) = sim.diff.take() {
+        shed.diff = diff.bytes();
+        usage.diff = 0;
+    }
+    if usage.total() > budget && !gallery.shots.is_empty() {
+        shed.timelapse = gallery.bytes();
+        gallery.clear();
+        usage.timelapse = 0;
+    }
+    if let Some(log) = recorder.filter(|_| usage.total() > budget) {
+        // Oldest first until the excess is covered; the newest stays so recording carries on
+        let excess = usage.total() - budget;
+        let (mut freed, mut count) = (0, 0);
+        for delta in &log.deltas[..log.deltas.len().saturating_sub(1)] {
+            if freed >= excess {
+                break;
+            }
+            freed += delta.bytes();
+            count += 1;
+        }
+        let before = log.bytes();
+        log.drop_oldest(count);
+        usage.deltas = log.bytes();
+        shed.deltas = before.saturating_sub(usage.deltas);
+        shed.generations = count;
+    }
+    (shed, usage)
+}
@@ -0,0 +1,260 @@
+//! Follow camera: keeps the live cells, or one object picked by the user, framed as the board
+//! evolves. The camera eases toward its target with a critically damped spring, which settles
+//! without overshooting or wobbling.
+
+use std::collections::HashSet;
+use macroquad::math::{vec2, Vec2};
+
+use crate::config::{CELL_SIZE_MAX, CELL_SIZE_MIN, FOLLOW_MAX_REACH, FOLLOW_NEAR, ZOOM_LEVELS};
+use crate::grid::{Grid, Position, Rect};
+use crate::render::BoardView;
+use crate::simulation::Simulation;
+
+/// Why following an object stopped on its own
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Lost {
+    Died,    // No live cells left where the object was
+    Merged,  // It touched another object and became part of it
+    Skipped, // Too many generations passed at once to match it up (fast-forward, reset)
+}
+
+impl Lost {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Lost::Died => "Follow stopped: the object died",
+            Lost::Merged => "Follow stopped: the object merged with another",
+            Lost::Skipped => "Follow stopped: the object was lost while skipping generations",
+        }
+    }
+}
+
+/// One connected object, matched from generation to generation by overlap
+#[derive(Clone, Debug)]
+pub struct Tracked {
+    pub cells: HashSet<Position>,
+    near: HashSet<Position>, // Other live cells close by, to tell a collision from the object's own growth
+    generation: u64,         // Generation `cells` belongs to
+}
+
+impl Tracked {
+    /// Start tracking the object containing cell (x, y), if that cell is alive
+    pub fn at(sim: &Simulation, x: i32, y: i32) -> Option<Self> {
+        let start = sim.grid.resolve(x, y).filter(|p| sim.live.contains(p))?;
+        let cells = flood(&sim.live, &sim.grid, start);
+        let near = neighbours(&cells, sim);
+        Some(Self { cells, near, generation: sim.generation })
+    }
+
+    /// Match the object on the board's current generation. Where it split, the piece that
+    /// overlaps most where it could have reached is followed.
+    pub fn update(&mut self, sim: &Simulation) -> Result<(), Lost> {
+        if sim.generation == self.generation {
+            return Ok(());
+        }
+        let elapsed = sim.generation.checked_sub(self.generation).ok_or(Lost::Skipped)?;
+        if elapsed > FOLLOW_MAX_REACH as u64 {
+            return Err(Lost::Skipped);
+        }
+        // Nothing travels faster than one cell per generation
+        let reach = elapsed as i32;
+        let around = dilate(&self.cells, &sim.grid, reach);
+        let mut seen = HashSet::new();
+        let mut best: Option<(usize, HashSet<Position>)> = None;
+        for &start in around.iter().filter(|p| sim.live.contains(p)) {
+            if seen.contains(&start) { continue; }
+            let piece = flood(&sim.live, &sim.grid, start);
+            let overlap = piece.iter().filter(|p| around.contains(p)).count();
+            seen.extend(piece.iter().copied());
+            if best.as_ref().is_none_or(|(o, _)| overlap > *o) {
+                best = Some((overlap, piece));
+            }
+        }
+        let (_, piece) = best.ok_or(Lost::Died)?;
+        // Merged: the piece holds a neighbour's cell, or cells only a neighbour could have reached
+        let theirs = dilate(&self.near, &sim.grid, reach);
+        if piece.iter().any(|p| self.near.contains(p) || (!around.contains(p) && theirs.contains(p))) {
+            return Err(Lost::Merged);
+        }
+        self.near = neighbours(&piece, sim);
+        self.cells = piece;
+        self.generation = sim.generation;
+        Ok(())
+    }
+
+    /// Center of the object in cells. On a torus it is measured across the seam, then wrapped.
+    pub fn center(&self, grid: &Grid) -> Vec2 {
+        let Some(&anchor) = self.cells.iter().next() else { return Vec2::ZERO };
+        let (mut lo, mut hi) = ((0, 0), (0, 0));
+        for p in &self.cells {
+            let (dx, dy) = grid.offset(anchor.x(), anchor.y(), *p);
+            lo = (lo.0.min(dx), lo.1.min(dy));
+            hi = (hi.0.max(dx), hi.1.max(dy));
+        }
+        let mut center = vec2(
+            anchor.x() as f32 + (lo.0 + hi.0 + 1) as f32 / 2.0,
+            anchor.y() as f32 + (lo.1 + hi.1 + 1) as f32 / 2.0,
+        );
+        if grid.topology.wraps()
+            && let Some(cell) = grid.resolve(center.x.floor() as i32, center.y.floor() as i32)
+        {
+            // Wrap the containing cell, so a shifted seam moves the center along with it
+            center = vec2(cell.x() as f32 + center.x - center.x.floor(), cell.y() as f32 + center.y - center.y.floor());
+        }
+        center
+    }
+}
+
+/// The 8-connected live cells reachable from `start`
+fn flood(live: &HashSet<Position>, grid: &Grid, start: Position) -> HashSet<Position> {
+    let mut cells = HashSet::from([start]);
+    let mut stack = vec![start];
+    while let Some(p) = stack.pop() {
+        for n in grid.neighbors_of(p) {
+            if live.contains(&n) && cells.insert(n) {
+                stack.push(n);
+            }
+        }
+    }
+    cells
+}
+
+/// Every cell within `r` cells (Chebyshev distance) of one of `cells`
+fn dilate(cells: &HashSet<Position>, grid: &Grid, r: i32) -> HashSet<Position> {
+    let mut out = HashSet::with_capacity(cells.len() * ((2 * r + 1) * (2 * r + 1)) as usize);
+    for p in cells {
+        for dy in -r..=r {
+            for dx in -r..=r {
+                if let Some(n) = grid.resolve(p.x() + dx, p.y() + dy) {
+                    out.insert(n);
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Live cells near an object that are not part of it
+fn neighbours(cells: &HashSet<Position>, sim: &Simulation) -> HashSet<Position> {
+    dilate(cells, &sim.grid, FOLLOW_NEAR)
+        .into_iter()
+        .filter(|p| sim.live.contains(p) && !cells.contains(p))
+        .collect()
+}
+
+/// What the camera keeps in frame
+#[derive(Clone, Debug, Default)]
+pub enum Follow {
+    #[default]
+    Off,
+    Bounds,          // The bounding box of every live cell, zooming to fit it
+    Object(Tracked), // One object, at the current zoom
+}
+
+impl Follow {
+    /// Short name for the HUD, or `None` when not following
+    pub fn label(&self) -> Option<&'static str> {
+        match self {
+            Follow::Off => None,
+            Follow::Bounds => Some("box"),
+            Follow::Object(_) => Some("object"),
+        }
+    }
+}
+
+/// A value eased toward a target by a critically damped spring
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Spring {
+    pub value: f32,
+    pub velocity: f32,
+}
+
+impl Spring {
+    /// Move `dt` seconds toward `target`. `settle` is roughly the time to cover 90% of the way;
+    /// the step is exact for a fixed target, so it behaves the same at any frame rate.
+    pub fn ease(&mut self, target: f32, settle: f32, dt: f32) {
+        let omega = 4.0 / settle.max(0.01);
+        let offset = self.value - target;
+        let pull = self.velocity + omega * offset;
+        let decay = (-omega * dt).exp();
+        self.value = target + (offset + pull * dt) * decay;
+        self.velocity = (self.velocity - omega * pull * dt) * decay;
+    }
+
+    /// Jump straight to `value`, at rest
+    pub fn snap(&mut self, value: f32) {
+        *self = Self { value, velocity: 0.0 };
+    }
+}
+
+/// Follow mode plus the eased camera position
+#[derive(Clone, Debug, Default)]
+pub struct Camera {
+    pub follow: Follow,
+    pub settle: f32,          // Seconds the easing takes to settle, from settings
+    center: (Spring, Spring), // Cell at the middle of the screen
+    zoom: Spring,             // Cell size in pixels
+}
+
+impl Camera {
+    pub fn new(settle: f32) -> Self {
+        Self { settle, ..Self::default() }
+    }
+
+    /// Start following, picking up from where the view is now
+    pub fn start(&mut self, follow: Follow, view: &BoardView, screen_w: f32, screen_h: f32) {
+        let center = (vec2(screen_w, screen_h) / 2.0 - view.offset) / view.scale();
+        self.center.0.snap(center.x);
+        self.center.1.snap(center.y);
+        self.zoom.snap(view.cell as f32);
+        self.follow = follow;
+    }
+
+    /// Ease the view toward what is followed. Returns why following stopped, if it just did.
+    pub fn update(&mut self, sim: &Simulation, view: &mut BoardView, dt: f32, screen_w: f32, screen_h: f32) -> Option<Lost> {
+        let (center, cell, shrink) = match &mut self.follow {
+            Follow::Off => return None,
+            Follow::Bounds => {
+                // Nothing to frame: hold still until something appears
+                let content = sim.bounding_box()?;
+                let (cell, shrink) = BoardView::fit_zoom(content.w, content.h, screen_w, screen_h);
+                (rect_center(content), cell as f32, shrink)
+            }
+            Follow::Object(tracked) => {
+                if let Err(lost) = tracked.update(sim) {
+                    self.follow = Follow::Off;
+                    return Some(lost);
+                }
+                // The zoom stays the user's: take up whatever they set it to
+                self.zoom.snap(view.cell as f32);
+                (tracked.center(&sim.grid), view.cell as f32, view.shrink)
+            }
+        };
+        let grid = &sim.grid;
+        if (grid.topology.wraps_x() && (center.x - self.center.0.value).abs() > grid.width as f32 / 2.0)
+            || (grid.topology.wraps_y() && (center.y - self.center.1.value).abs() > grid.height as f32 / 2.0)
+        {
+            // Wrapped across the seam: gliding the long way round would sweep the whole board
+            self.center.0.snap(center.x);
+            self.center.1.snap(center.y);
+        }
+        self.center.0.ease(center.x, self.settle, dt);
+        self.center.1.ease(center.y, self.settle, dt);
+        self.zoom.ease(cell, self.settle, dt);
+        let cell = (self.zoom.value.round() as i32).clamp(CELL_SIZE_MIN, CELL_SIZE_MAX);
+        // Framing every live cell zooms through the zoom levels; an object keeps the user's size
+        let cell = match self.follow {
+            Follow::Bounds => ZOOM_LEVELS.iter().copied().min_by_key(|level| (level - cell).abs()).unwrap_or(cell),
+            _ => cell,
+        };
+        // Cells per pixel only once the eased size has come down to the smallest
+        view.shrink = if cell == CELL_SIZE_MIN { shrink } else { 1 };
+        view.cell = cell;
+        view.look_at(grid, vec2(self.center.0.value, self.center.1.value), screen_w, screen_h);
+        None
+    }
+}
+
+/// Middle of a rectangle of cells
+fn rect_center(r: Rect) -> Vec2 {
+    vec2(r.x as f32 + r.w as f32 / 2.0, r.y as f32 + r.h as f32 / 2.0)
+}
@@ -0,0 +1,113 @@
+//! Fixed-timestep simulation clock. Simulation time is an integer tick count, and generation
+//! `n` at the current speed falls due at a fixed tick, so when steps happen depends only on
+//! the ticks elapsed, never on how they were split into frames.
+
+use crate::config::TICKS_PER_SECOND;
+
+/// Speeds are held in generations per thousand seconds so 0.1 gen/s steps stay exact
+const RATE_SCALE: f32 = 1000.0;
+
+/// Clock driving a simulation's steps
+#[derive(Clone, Debug)]
+pub struct SimClock {
+    ticks: u64, // Simulation time; only advances while the simulation runs
+    rate: u64,  // Generations per thousand seconds
+    base: u64,  // Tick the current speed is measured from, moved back by progress carried over
+    taken: u64, // Steps taken since `base`
+}
+
+impl SimClock {
+    pub fn new(speed: f32) -> Self {
+        Self { ticks: 0, rate: Self::rate_of(speed), base: 0, taken: 0 }
+    }
+
+    fn rate_of(speed: f32) -> u64 {
+        (speed * RATE_SCALE).round().max(1.0) as u64
+    }
+
+    /// Whole ticks in `seconds` of frame time
+    pub fn ticks_in(seconds: f32) -> u64 {
+        (seconds.max(0.0) as f64 * TICKS_PER_SECOND as f64).round() as u64
+    }
+
+    /// Simulation time in ticks
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Simulation time in seconds, for display
+    pub fn seconds(&self) -> f64 {
+        self.ticks as f64 / TICKS_PER_SECOND as f64
+    }
+
+    /// Progress since `base`, in generations times `TICKS_PER_SECOND * RATE_SCALE`
+    fn progress_units(&self) -> u128 {
+        (self.ticks - self.base) as u128 * self.rate as u128
+    }
+
+    fn units_per_step() -> u128 {
+        TICKS_PER_SECOND as u128 * RATE_SCALE as u128
+    }
+
+    /// Change speed, keeping the way made toward the next step so the change neither
+    /// releases a burst of steps nor restarts the wait
+    pub fn set_speed(&mut self, speed: f32) {
+        let rate = Self::rate_of(speed);
+        if rate == self.rate {
+            return;
+        }
+        let owed = self.due() as u128 * Self::units_per_step();
+        let carried = owed + self.progress_units() % Self::units_per_step();
+        self.rate = rate;
+        self.base = self.ticks - ((carried / rate as u128) as u64).min(self.ticks);
+        self.taken = 0;
+    }
+
+    /// Whether `speed` is what the clock runs at
+    pub fn runs_at(&self, speed: f32) -> bool {
+        self.rate == Self::rate_of(speed)
+    }
+
+    /// Move simulation time on
+    pub fn advance(&mut self, ticks: u64) {
+        self.ticks += ticks;
+    }
+
+    /// Steps that have fallen due and not been taken
+    pub fn due(&self) -> u64 {
+        (self.progress_units() / Self::units_per_step()) as u64 - self.taken
+    }
+
+    /// Record `steps` as taken
+    pub fn take(&mut self, steps: u64) {
+        self.taken += steps;
+    }
+
+    /// Forgive every step that is due, e.g. when the machine can't keep up
+    pub fn skip(&mut self) {
+        self.taken += self.due();
+    }
+
+    /// Run due steps, at most `cap`, until `budget` seconds of `now` have passed; at least one
+    /// runs if any is due. Steps the budget cuts off stay due for the next frame, up to `cap` of
+    /// them; a backlog beyond that is forgiven. Returns the steps run.
+    pub fn run_due(&mut self, cap: u64, budget: f64, now: impl Fn() -> f64, mut step: impl FnMut()) -> u64 {
+        let start = now();
+        let mut steps = 0;
+        while steps < cap && self.due() > 0 {
+            if steps > 0 && now() - start >= budget {
+                break;
+            }
+            step();
+            self.take(1);
+            steps += 1;
+        }
+        self.taken += self.due().saturating_sub(cap);
+        steps
+    }
+
+    /// How far (0..1) simulation time is from the last due step to the next
+    pub fn fraction(&self) -> f32 {
+        (self.progress_units() % Self::units_per_step()) as f32 / Self::units_per_step() as f32
+    }
+}
@@ -0,0 +1,151 @@
+//! Comparing the board with a reference: a `.gold` save, a pattern file, a bookmark or the
+//! run's marked initial state. References that can be run on are kept at the board's
+//! generation, so the diff shows where two runs of the same start diverged.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::bookmarks::Bookmark;
+use crate::grid::Position;
+use crate::pattern_io::load_pattern_file;
+use crate::project::{self, Project};
+use crate::simulation::{set_bytes, Simulation};
+
+/// Cells of two boards sorted by which board has them
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BoardDiff {
+    pub only_a: HashSet<Position>,
+    pub only_b: HashSet<Position>,
+    pub both: HashSet<Position>,
+}
+
+/// How many cells fall in each part of a `BoardDiff`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DiffCounts {
+    pub only_a: usize,
+    pub only_b: usize,
+    pub both: usize,
+}
+
+/// Split the live cells of `a` and `b` into those only in `a`, only in `b`, and in both
+pub fn diff_boards(a: &HashSet<Position>, b: &HashSet<Position>) -> BoardDiff {
+    let (small, large, a_is_small) = if a.len() <= b.len() { (a, b, true) } else { (b, a, false) };
+    let both: HashSet<Position> = small.iter().filter(|p| large.contains(p)).copied().collect();
+    let rest = |side: &HashSet<Position>| side.iter().filter(|p| !both.contains(p)).copied().collect::<HashSet<_>>();
+    let (only_small, only_large) = (rest(small), rest(large));
+    if a_is_small {
+        BoardDiff { only_a: only_small, only_b: only_large, both }
+    } else {
+        BoardDiff { only_a: only_large, only_b: only_small, both }
+    }
+}
+
+impl BoardDiff {
+    /// The same diff with `a` and `b` trading places
+    pub fn swapped(self) -> Self {
+        Self { only_a: self.only_b, only_b: self.only_a, both: self.both }
+    }
+
+    pub fn counts(&self) -> DiffCounts {
+        DiffCounts { only_a: self.only_a.len(), only_b: self.only_b.len(), both: self.both.len() }
+    }
+
+    /// Whether the two boards were the same
+    pub fn identical(&self) -> bool {
+        self.only_a.is_empty() && self.only_b.is_empty()
+    }
+}
+
+/// A board to compare against, with the start it can be rebuilt from
+pub struct Reference {
+    pub label: String,
+    pub sim: Simulation,
+    start: Option<Bookmark>, // Rewound to when the board goes back in time; none for a pattern file, which stays as loaded
+}
+
+impl Reference {
+    /// Run on from `start` with the rule and edge behavior of `like`
+    pub fn from_bookmark(label: impl Into<String>, start: Bookmark, like: &Simulation) -> Self {
+        let mut sim = Simulation::new(start.width, start.height);
+        sim.grid.rule = like.grid.rule;
+        start.restore(&mut sim);
+        (sim.edge_spawn, sim.edge_rate, sim.lethal_margin) = (like.edge_spawn, like.edge_rate, like.lethal_margin);
+        Self { label: label.into(), sim, start: Some(start) }
+    }
+
+    /// The run's marked generation 0, or `None` while the board is still being edited
+    pub fn initial(like: &Simulation) -> Option<Self> {
+        let initial = like.initial.as_ref()?;
+        let mut sim = Simulation::new(initial.grid.width, initial.grid.height);
+        sim.grid = initial.grid.clone();
+        sim.live = initial.live.clone();
+        sim.seed = initial.seed;
+        Some(Self::from_bookmark("the initial state", Bookmark::capture(&sim, "generation 0"), like))
+    }
+
+    /// Read a reference from a file: a project or `.gold` log gives the board's generation if it
+    /// recorded it, otherwise its last one, and runs on from there; any other pattern file is centered
+    /// on a board the size of `like` and stays as loaded
+    pub fn load(path: &Path, like: &Simulation) -> Result<Self, String> {
+        let name = path.file_name().map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into_owned());
+        if project::is_project_file(path) {
+            let log = Project::open(path)?.log;
+            let generation = if log.contains(like.generation) { like.generation } else { log.last_generation() };
+            let live = log.reconstruct(generation).unwrap_or_default();
+            let mut sim = Simulation::new(log.width, log.height);
+            sim.grid = log.grid();
+            sim.live = live;
+            sim.generation = generation;
+            sim.seed = like.seed;
+            return Ok(Self::from_bookmark(name, Bookmark::capture(&sim, "saved"), like));
+        }
+        let pattern = load_pattern_file(path)?;
+        let (w, h) = (like.grid.width, like.grid.height);
+        let mut sim = Simulation::new(w, h);
+        sim.grid = like.grid.clone();
+        sim.generation = like.generation;
+        sim.apply_pattern(&pattern, w / 2, h / 2).map_err(|e| e.to_string())?;
+        Ok(Self { label: name, sim, start: None })
+    }
+
+    /// Step toward `generation`, at most `budget` generations this call, rewinding first if the
+    /// reference is ahead of it. Returns whether the reference is there.
+    pub fn catch_up(&mut self, generation: u64, budget: u64) -> bool {
+        let Some(start) = &self.start else { return true; };
+        if self.sim.generation > generation && start.generation <= generation {
+            start.restore(&mut self.sim);
+        }
+        let mut steps = 0;
+        while self.sim.generation < generation && steps < budget {
+            self.sim.next_generation();
+            steps += 1;
+        }
+        self.sim.generation == generation
+    }
+}
+
+/// Compare mode of one board: the reference, the latest diff and which side is primary
+pub struct Compare {
+    pub reference: Reference,
+    pub swapped: bool, // The reference is primary instead of the board
+    pub diff: BoardDiff, // Primary as `a`, the other side as `b`
+    pub caught_up: bool, // The reference is at the board's generation
+}
+
+impl Compare {
+    pub fn new(reference: Reference) -> Self {
+        Self { reference, swapped: false, diff: BoardDiff::default(), caught_up: false }
+    }
+
+    /// Approximate heap size of the reference board and the diff
+    pub fn bytes(&self) -> usize {
+        self.reference.sim.memory_usage().total() + set_bytes(&self.diff.only_a) + set_bytes(&self.diff.only_b) + set_bytes(&self.diff.both)
+    }
+
+    /// Bring the reference to the board's generation and diff the two
+    pub fn update(&mut self, sim: &Simulation, budget: u64) {
+        self.caught_up = self.reference.catch_up(sim.generation, budget);
+        let (a, b) = if self.swapped { (&self.reference.sim.live, &sim.live) } else { (&sim.live, &self.reference.sim.live) };
+        self.diff = diff_boards(a, b);
+    }
+}
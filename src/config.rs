@@ -1,9 +1,167 @@
 // Game configuration constants
 pub const CELL_SIZE: i32 = 10;          // Pixel size of each cell
-pub const RANDOM_DENSITY: f32 = 0.20;     // Density for random patterns
+pub const CELL_SIZE_MIN: i32 = 1;       // Smallest live-adjustable cell size
+pub const CELL_SIZE_MAX: i32 = 32;      // Largest live-adjustable cell size
+// Zoom levels in pixels per cell that Ctrl+-/= steps through, and the room the fit-board preset
+// (Ctrl+1) leaves above the board for the HUD and controls line and around it on every side
+pub const ZOOM_LEVELS: [i32; 10] = [1, 2, 3, 4, 6, 8, 12, 16, 24, 32];
+// Past 1 px per cell Ctrl+- goes on zooming out: cells per pixel along each axis, drawn as a
+// density picture in which even one live cell in a bucket shows at the faintest opacity. While
+// the board holds still its hash is checked against the picture every so many frames.
+pub const ZOOM_OUT_LEVELS: [i32; 4] = [2, 4, 8, 16];
+pub const DENSITY_FAINTEST: f32 = 0.2;
+pub const DENSITY_RECHECK_FRAMES: u32 = 15;
+pub const HUD_HEIGHT: f32 = 56.0;
+pub const ZOOM_FIT_MARGIN: f32 = 8.0;
+pub const GRID_MAX: i32 = 4096;         // Largest custom grid dimension
+pub const GRID_CACHE_MAX_PX: i32 = 4096; // Largest board (in pixels) whose grid lines are cached in a texture
+// Coordinate ruler: label font size, smallest cell size it is shown at, and the least space
+// between neighbouring labels, in pixels
+pub const RULER_FONT: f32 = 14.0;
+pub const RULER_MIN_CELL: i32 = 2;
+pub const RULER_GAP: f32 = 6.0;
+// Speed schedule timeline in the HUD's bottom-right corner: width and height in pixels
+pub const SCHEDULE_STRIP: (f32, f32) = (240.0, 40.0);
+// Density for random patterns; lower in the browser, where big soups step slowly
+#[cfg(not(target_arch = "wasm32"))]
+pub const RANDOM_DENSITY: f32 = 0.20;
+#[cfg(target_arch = "wasm32")]
+pub const RANDOM_DENSITY: f32 = 0.15;
+pub const DENSITY_MIN: f32 = 0.01;        // Lowest density reachable with Shift+-
+pub const DENSITY_MAX: f32 = 0.99;        // Highest density reachable with Shift+=
+pub const DENSITY_STEP: f32 = 0.05;       // Change per Shift+-/= press
 pub const SPEED_MIN: f32 = 1.0;           // Minimum generations per second
 pub const SPEED_MAX: f32 = 120.0;         // Maximum generations per second
-pub const SPEED_INIT: f32 = 10.0;         // Default generations per second
+// Default generations per second; lower in the browser
+#[cfg(not(target_arch = "wasm32"))]
+pub const SPEED_INIT: f32 = 10.0;
+#[cfg(target_arch = "wasm32")]
+pub const SPEED_INIT: f32 = 6.0;
+pub const SMOOTH_MAX_SPEED: f32 = 8.0;     // Above this many gen/s smooth rendering is switched off
+pub const MAX_STEPS_PER_FRAME: u32 = 8;   // Cap on generations computed in a single frame
+pub const MAX_CATCHUP_SECONDS: f32 = 0.25; // Simulation time a single slow frame may owe
+// Wall-clock time a frame may spend stepping before the rest is deferred; the browser gets
+// less because stepping shares the animation-frame callback with drawing
+#[cfg(not(target_arch = "wasm32"))]
+pub const STEP_BUDGET_SECONDS: f64 = 0.014;
+#[cfg(target_arch = "wasm32")]
+pub const STEP_BUDGET_SECONDS: f64 = 0.008;
+pub const RATE_WINDOW: f64 = 0.5;          // Seconds per achieved-rate measurement
+pub const LAG_TOLERANCE: f32 = 0.9;        // Fraction of the target rate that counts as keeping up
+pub const LAG_WARN_SECONDS: f64 = 2.0;     // Sustained lag before the HUD warns
+pub const GOTO_FRAME_BUDGET: f64 = 0.012; // Seconds per frame spent fast-forwarding to a generation
+pub const MAX_CELLS: usize = 2_000_000;   // Default limit on live cells a pattern may produce
+pub const LOAD_CHUNK: usize = 64 * 1024;  // Bytes a pattern file is read in between progress updates
+pub const SANDBOX_MARGIN: i32 = 16;       // Empty cells around an isolated selection
+pub const CROP_MARGIN: i32 = 8;           // Empty cells kept around live cells by crop-to-content
+pub const EXPAND_MARGIN: i32 = 8;         // Empty cells beyond a pattern the grid grows to take
+pub const CLEANUP_MIN_SIZE: usize = 5;   // Cleanup removes objects with fewer cells than this by default
+pub const STAMP_HISTORY_LEN: usize = 8;   // Recent stamps remembered for quick re-use
+pub const HOTBAR_SLOT: f32 = 34.0;        // Side of a hotbar slot on screen, in pixels
+pub const HOTBAR_THUMB: u16 = 28;         // Side of a hotbar slot's pattern thumbnail, in pixels
+pub const MEMORY_BUDGET_MB: usize = 1024; // Default cap on approximate simulation memory
+pub const EDGE_SPAWN_RATE: f32 = 0.01;    // Default chance per border cell per generation in edge-spawn mode
+pub const LETHAL_MARGIN: i32 = 0;         // Default width of the border band where cells die each generation; 0 is off
+pub const LETHAL_MARGIN_MAX: i32 = 64;    // Widest lethal margin accepted from settings and the console
+pub const ATTRACT_IDLE_SECONDS: f64 = 30.0; // Idle time on the resolution menu before the demo reel starts
+pub const ATTRACT_SECONDS: f64 = 20.0;    // How long each demo reel pattern runs
+pub const ATTRACT_SPEED: f32 = 15.0;      // Generations per second in the demo reel
+pub const ATTRACT_CELL: i32 = 6;          // Cell size in the demo reel
+pub const ATTRACT_PLAYLIST: &str = "Gosper Gun, R-pentomino, Acorn, Random"; // Default demo reel patterns
+pub const AGE_STABLE: u64 = 20;           // Cells older than this many generations count as stable
+
+// Heat overlay: a wash under the cells that glows where births and deaths happen and fades
+// where the board holds still
+pub const HEAT_BLOCK: i32 = 4;            // Smallest side of the square of cells one heat value covers
+pub const HEAT_MAX_BLOCKS: usize = 1 << 16; // Heat values kept at most, whatever the board size
+pub const HEAT_FADE_SECONDS: f32 = 1.0;   // Time for heat to fall to about a third without new activity
+pub const HEAT_BLUR: f32 = 1.5;           // How fast heat spreads to neighbouring blocks, per second
+pub const HEAT_SCALE: f32 = 2.0;          // Heat (changes per cell) at which the wash is about two-thirds strength
+pub const HEAT_INTENSITY: f32 = 0.5;      // Default opacity of the wash at full heat
+pub const HEAT_FAINTEST: f32 = 0.02;      // Blocks fainter than this opacity aren't drawn
+
+// Particles: cosmetic rings for births and fading squares for deaths, off by default
+pub const PARTICLE_CAPACITY: usize = 4096; // Particles shown at once; the oldest make way for new ones
+pub const PARTICLE_SECONDS: f32 = 0.4;    // How long each one lasts
+pub const PARTICLE_MAX_BIRTHS: usize = 400; // Births in one generation above which it emits none
+pub const PARTICLE_INTENSITY: f32 = 0.6;  // Default opacity of a particle as it starts
+pub const STEPPER_EDIT_QUEUE: usize = 4096; // Cells set by hand the worker may fall behind before the rest wait for its result
+pub const REMOTE_EVENT_QUEUE: usize = 1024; // Generation summaries a remote controller may fall behind before some are dropped
+pub const SERVE_UPDATE_QUEUE: usize = 256; // Board messages the server thread may fall behind before the board is sent whole
+pub const SERVE_CLIENT_QUEUE: usize = 64; // Lines a client may fall behind before its deltas are dropped and it is resynced
+pub const SERVE_FULL_EVERY: u64 = 100;    // Deltas sent between whole boards, so a client that missed something recovers
+pub const SERVE_ACCEPT_MILLIS: u64 = 50;  // How often the server checks for new clients while the board is quiet
+pub const TICKS_PER_SECOND: u64 = 1_000_000; // Resolution of the simulation clock (microseconds)
+pub const SUMMARY_PERIOD_MAX: u32 = 64;   // Longest repeat period the run summary looks for
+pub const RECORD_REPLAY_MAX: u64 = 100_000; // Longest run replayed to confirm a soup lifespan record
+
+// Experiments (`--experiment`): replicate seeds per value and generations per run unless
+// given, and the longest repeat period that counts as the board having settled
+pub const EXPERIMENT_REPLICATES: u32 = 10;
+pub const EXPERIMENT_GENERATIONS: u64 = 3000;
+pub const EXPERIMENT_PERIOD_MAX: u32 = 64;
+
+// Self-test (`--self-test`): seconds a check may run before it is reported as hung, and the
+// seeds of its engine-against-reference runs
+pub const SELFTEST_TIMEOUT_SECONDS: f64 = 30.0;
+pub const SELFTEST_SEEDS: u64 = 3;
+pub const CLASSIFY_MAX_GENERATIONS: u32 = 64; // Longest period looked for when classifying pattern files
+pub const CLASSIFY_MAX_CELLS: usize = 4096; // Pattern files larger than this are not classified
+
+// Pattern menu preview: the highlighted pattern runs on a small grid of its own at a few
+// generations per second. Larger patterns, and ones with no period within the budget, hold still.
+pub const PREVIEW_BOX: f32 = 220.0;       // Side of the preview box, in pixels
+pub const PREVIEW_SPEED: f64 = 6.0;       // Generations per second
+pub const PREVIEW_MAX_CELLS: usize = 400;
+pub const PREVIEW_MAX_SIDE: i32 = 64;     // Widest or tallest pattern animated
+pub const PREVIEW_RASTER: i32 = 208;     // Widest still picture drawn cell for cell; bigger ones are downsampled
+pub const PREVIEW_PERIOD_MAX: u32 = 64;   // Generations searched for a period
+pub const PREVIEW_TRAVEL: i32 = 16;       // Cells a spaceship travels before its preview starts over
+pub const CONSOLE_LINES: usize = 12;      // Scrollback kept by the developer console
+pub const TOAST_SECONDS: f64 = 3.0;       // How long on-screen notices stay visible
+pub const CONFIRM_SECONDS: f64 = 1.5;     // Window for the second press that confirms a destructive key on a running board
+pub const DEBUG_DUMP_HISTORY: usize = 50; // Population samples kept in a debug dump
+pub const DEBUG_DUMP_FORMAT: &str = "conways-game-of-life debug dump 1"; // Marks a debug dump file and its version
+pub const PROJECT_FORMAT: u32 = 1;    // Version written to a project manifest; newer ones are refused
+pub const PATTERN_DIR: &str = "patterns";  // Directory scanned for pattern files in the pattern menu
+
+// Growth trend: generations of population history fitted, and the fewest distinct generations
+// in that window before a trend is named
+pub const GROWTH_WINDOW: u64 = 600;
+pub const GROWTH_MIN_SAMPLES: usize = 24;
+// Frame pacing: the render FPS caps the settings accept, the rate the battery saver drops to
+// while nothing changes, how long input or a change keeps the full rate, and the frame rate
+// the per-frame step limits above are tuned for
+pub const FPS_CAP_MIN: u32 = 5;
+pub const FPS_CAP_MAX: u32 = 240;
+pub const SAVER_FPS: f32 = 10.0;
+pub const SAVER_GRACE_SECONDS: f64 = 1.0;
+pub const PACING_REFERENCE_FPS: f32 = 60.0;
+
+// Follow camera: seconds the easing takes to settle, how many generations apart a followed
+// object can still be matched up, and how close other cells must be to count as neighbours
+pub const FOLLOW_SETTLE_SECONDS: f32 = 0.6;
+pub const FOLLOW_MAX_REACH: i32 = 16;
+pub const FOLLOW_NEAR: i32 = 2;
+
+// Time-lapse gallery: default capture interval, thumbnail size in pixels and how many are kept
+pub const TIMELAPSE_EVERY: u64 = 250;
+pub const THUMB_SIZE: (u16, u16) = (160, 120);
+pub const THUMB_MAX_COUNT: usize = 64;
+pub const THUMB_MAX_BYTES: usize = 4 * 1024 * 1024;
+// SVG export: default cell size in SVG units, the live cells above which a warning says the file
+// will be large, and the most it writes at all
+pub const SVG_CELL: i32 = 10;
+pub const SVG_WARN_CELLS: usize = 100_000;
+pub const SVG_MAX_CELLS: usize = 2_000_000;
+// Rule mutation explorer: the soup every mutation runs from, how long each runs, and how many
+// generations a frame steps where the runs can't go on a worker thread
+pub const MUTATION_SIZE: (i32, i32) = (64, 48);
+pub const MUTATION_GENERATIONS: u64 = 300;
+pub const MUTATION_FRAME_GENERATIONS: u64 = 60;
+pub const BOOKMARK_MAX: usize = 12; // Bookmarks kept per run; the least recently used gives way
+pub const NOTE_TEXT_MAX: usize = 80; // Characters in one note pinned to the board
+pub const COMPARE_FRAME_GENERATIONS: u64 = 200; // Generations a compare reference runs per frame to catch up with the board
 
 // Available screen resolutions (width, height)
 pub const SCREEN_SIZES: [(i32, i32); 5] = [
@@ -12,4 +170,58 @@ pub const SCREEN_SIZES: [(i32, i32); 5] = [
     (1024, 768),
     (1280, 720),
     (1920, 1080),
-];
\ No newline at end of file
+];
+
+// Cell size presets offered after choosing a resolution (pixels)
+pub const CELL_SIZES: [i32; 5] = [4, 6, 8, 10, 16];
+
+// Fixed grid presets (width, height) letterboxed into any window
+pub const GRID_PRESETS: [(i32, i32); 3] = [(400, 60), (200, 200), (100, 100)];
+
+// Territory mode: board size, cells per player, match length and speed
+pub const TERRITORY_SIZE: (i32, i32) = (64, 40);
+pub const TERRITORY_BUDGET: u32 = 20;
+pub const TERRITORY_GENERATIONS: u64 = 500;
+pub const TERRITORY_SPEED: f32 = 30.0;
+
+// Elementary (1D) mode: starting rule, cell size in pixels and rows per second
+pub const ELEMENTARY_RULE: u8 = 30;
+pub const ELEMENTARY_CELL: i32 = 4;
+pub const ELEMENTARY_SPEED: f32 = 30.0;
+
+// Console `import-all`: empty cells between imported patterns and from the edges, by default
+pub const IMPORT_SPACING: i32 = 4;
+pub const IMPORT_SPACING_MAX: i32 = 64;
+
+// Loading patterns from a URL (the `net` feature): download size cap and time limits in seconds
+pub const FETCH_MAX_BYTES: usize = 4 * 1024 * 1024;
+pub const FETCH_TIMEOUT_SECONDS: u32 = 20;
+pub const FETCH_CONNECT_SECONDS: u32 = 10;
+
+// Theme picker: the least cell/background contrast ratio (WCAG, 1 to 21) a theme may have,
+// under normal vision and every simulated deficiency, before it is flagged
+pub const THEME_MIN_CONTRAST: f32 = 3.0;
+// Random palettes it tries for one that passes before falling back to white on black
+pub const RANDOM_PALETTE_TRIES: u32 = 200;
+
+// Theme background images: how strongly they show through the board's background, 0 to 1
+pub const BACKDROP_OPACITY: f32 = 0.15;
+
+// Script hooks (the `scripting` feature): Rhai operations, wall-clock milliseconds and nested
+// function calls one hook call may use before the hook is switched off, and how often the file
+// is checked for changes
+pub const SCRIPT_OPS: u64 = 200_000;
+pub const SCRIPT_MILLIS: u64 = 4;
+pub const SCRIPT_DEPTH: usize = 32;
+pub const SCRIPT_RELOAD_SECONDS: f32 = 0.5;
+
+// Watched resources (the `watch` feature): how often the themes file and pattern directory are checked
+pub const WATCH_SECONDS: f64 = 0.5;
+
+// Picture import (the `image-import` feature): the brightness (0 black, 1 white) below which a
+// pixel becomes a live cell, the step the preview's arrow keys move it by, the longest side in
+// cells of a picture loaded as a pattern file, and the most memory the decoder may allocate
+pub const IMAGE_THRESHOLD: f32 = 0.5;
+pub const IMAGE_THRESHOLD_STEP: f32 = 0.05;
+pub const IMAGE_FIT: i32 = 256;
+pub const IMAGE_MAX_BYTES: u64 = 256 * 1024 * 1024;
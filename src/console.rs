@@ -0,0 +1,503 @@
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+use crate::config::{FPS_CAP_MAX, FPS_CAP_MIN, IMPORT_SPACING, IMPORT_SPACING_MAX, LETHAL_MARGIN_MAX, SPEED_MAX, SPEED_MIN};
+use crate::error;
+use crate::figure::{svg_size_warning, write_board_svg, SvgOptions};
+use crate::grid::Topology;
+use crate::render::BoardView;
+use crate::rule::Rule;
+use crate::schedule::SpeedSchedule;
+use crate::simulation::Simulation;
+use crate::pattern_io::{expand_glob, load_pattern_file};
+use crate::patterns::image::{self, ImageOptions, Picture};
+use crate::patterns::{layout_patterns, validate_density, Pattern};
+use crate::themes::{ColorTheme, GridMode};
+
+/// Command names in the order `help` lists them
+pub const COMMAND_NAMES: [&str; 24] = ["help", "speed", "schedule", "seed", "fill", "goto", "step", "clear", "set", "load", "image", "import-all", "url", "script", "theme", "rule", "margin", "shift", "dump-debug", "export-svg", "export-rle", "fps", "saver", "compare"];
+
+/// A parsed console command
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Help,
+    Speed(f32),        // Target generations per second
+    Schedule(Option<SpeedSchedule>), // Speed breakpoints to follow, or none to stop following them
+    Seed(u64),         // Re-seed and refill the random soup
+    Fill(f32),         // Replace the board with a random soup of this density
+    Goto(u64),         // Fast-forward to a generation
+    Step(u64),         // Advance this many generations immediately
+    Clear,
+    Load(String),      // Pattern file placed at the center of a cleared board
+    Image(String, ImageOptions), // Picture previewed over the board, then seeded onto it
+    ImportAll(String, i32), // Pattern files matching a glob, laid out this many cells apart on a cleared board
+    Url(String),       // Pattern downloaded and armed as a stamp
+    Script(Option<String>), // Script hook file to run after each generation, or none to stop it
+    Theme(ColorTheme),
+    Rule(Option<String>), // Show the rule, or request a different one
+    Set(i32, i32, bool),  // Set a cell, in the display coordinate convention
+    Margin(i32),          // Width of the lethal margin; 0 turns it off
+    Shift(Option<Topology>), // Show the torus shift, or make the board this torus, shifted or not
+    DumpDebug(Option<String>), // Write a debug dump, to this file or one named after the generation
+    ExportSvg(Option<String>), // Write the board as SVG, to this file or one named after the generation
+    ExportRle(Option<String>), // Write the board as RLE, to this file or one named after the generation
+    Fps(u32),             // Cap on render frames per second; 0 draws as often as the display allows
+    Saver(bool),          // Battery saver on or off
+    Compare(Option<String>), // Diff the board against a .gold save or pattern file, or stop comparing
+}
+
+impl Command {
+    /// Whether the command throws the board away, which a running board asks twice for
+    pub fn destructive(&self) -> bool {
+        matches!(self, Command::Seed(_) | Command::Fill(_) | Command::Clear | Command::Load(_) | Command::ImportAll(..))
+    }
+}
+
+/// State the console cannot change itself; the caller applies it
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    Done,
+    SetSpeed(f32),
+    Schedule(Option<SpeedSchedule>),
+    GoTo(u64),
+    Fetch(String), // Download from this URL
+    Image(String, Picture, ImageOptions), // Preview this picture, named after its file, before it replaces the board
+    Script(Option<PathBuf>), // Load this script hook, or stop the running one
+    Toast(String), // Close the console and show this summary over the board
+    DumpDebug(PathBuf), // Write a debug dump of the run to this file
+    FpsCap(u32), // Hold frames to this rate, or 0 to lift the cap
+    Saver(bool), // Turn the battery saver on or off
+    Compare(Option<PathBuf>), // Compare the board with this file, or leave compare mode
+}
+
+/// Parse the argument of `name`, naming the command in the error
+fn arg<T: std::str::FromStr>(name: &str, value: Option<&str>) -> Result<T, String> {
+    let raw = value.ok_or_else(|| format!("{} expects a value", name))?;
+    raw.parse().map_err(|_| format!("invalid value '{}' for {}", raw, name))
+}
+
+/// Parse one line of console input
+pub fn parse(line: &str) -> Result<Command, String> {
+    let line = line.trim();
+    let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let rest = rest.trim();
+    let value = (!rest.is_empty()).then_some(rest);
+    let name = name.to_ascii_lowercase();
+    match name.as_str() {
+        "help" => Ok(Command::Help),
+        "speed" => {
+            let speed: f32 = arg(&name, value)?;
+            if !(SPEED_MIN..=SPEED_MAX).contains(&speed) {
+                return Err(format!("speed must be between {} and {}", SPEED_MIN, SPEED_MAX));
+            }
+            Ok(Command::Speed(speed))
+        }
+        "schedule" => match value.ok_or("schedule expects breakpoints like 0:2 100:10 500:60, or off")? {
+            "off" => Ok(Command::Schedule(None)),
+            text => Ok(Command::Schedule(Some(SpeedSchedule::parse(text)?))),
+        },
+        "seed" => Ok(Command::Seed(arg(&name, value)?)),
+        "fill" => {
+            Ok(Command::Fill(validate_density(arg(&name, value)?)?))
+        }
+        "goto" => Ok(Command::Goto(arg(&name, value)?)),
+        "step" => Ok(Command::Step(value.map_or(Ok(1), |v| arg(&name, Some(v)))?)),
+        "clear" => Ok(Command::Clear),
+        "set" => {
+            let fields: Vec<&str> = rest.split_whitespace().collect();
+            let [x, y, state] = fields[..] else { return Err("usage: set X Y alive|dead".to_string()); };
+            let alive = match state {
+                "alive" | "1" => true,
+                "dead" | "0" => false,
+                other => return Err(format!("expected alive or dead, got '{}'", other)),
+            };
+            Ok(Command::Set(arg(&name, Some(x))?, arg(&name, Some(y))?, alive))
+        }
+        "load" => Ok(Command::Load(value.ok_or("load expects a file name")?.to_string())),
+        "image" => {
+            let mut words: Vec<&str> = rest.split_whitespace().collect();
+            let mut options = ImageOptions::default();
+            if words.len() > 1 && words.last() == Some(&"invert") {
+                options.invert = true;
+                words.pop();
+            }
+            if words.len() > 1 && let Some(threshold) = words.last().and_then(|w| w.parse::<f32>().ok()) {
+                if !(0.0..=1.0).contains(&threshold) {
+                    return Err("threshold must be between 0 and 1".to_string());
+                }
+                options.threshold = threshold;
+                words.pop();
+            }
+            if words.is_empty() {
+                return Err("usage: image <file> [threshold 0-1] [invert]".to_string());
+            }
+            Ok(Command::Image(words.join(" "), options))
+        }
+        "import-all" => {
+            let usage = "usage: import-all <glob> [spacing]";
+            let (glob, spacing) = match rest.rsplit_once(char::is_whitespace) {
+                Some((glob, last)) if last.parse::<i32>().is_ok() => (glob.trim(), arg(&name, Some(last))?),
+                _ => (rest, IMPORT_SPACING),
+            };
+            if glob.is_empty() {
+                return Err(usage.to_string());
+            }
+            if !(0..=IMPORT_SPACING_MAX).contains(&spacing) {
+                return Err(format!("spacing must be between 0 and {}", IMPORT_SPACING_MAX));
+            }
+            Ok(Command::ImportAll(glob.to_string(), spacing))
+        }
+        "url" => Ok(Command::Url(value.ok_or("url expects an address")?.to_string())),
+        "script" => match value.ok_or("script expects a file name, or off")? {
+            "off" => Ok(Command::Script(None)),
+            path => Ok(Command::Script(Some(path.to_string()))),
+        },
+        "theme" => {
+            let name = value.ok_or("theme expects a name")?;
+            ColorTheme::from_name(name).map(Command::Theme).ok_or_else(|| {
+                let names: Vec<_> = ColorTheme::ALL.iter().map(|t| t.name()).collect();
+                format!("unknown theme '{}' (expected one of: {})", name, names.join(", "))
+            })
+        }
+        "rule" => Ok(Command::Rule(value.map(str::to_string))),
+        "margin" => {
+            let k: i32 = arg(&name, value)?;
+            if !(0..=LETHAL_MARGIN_MAX).contains(&k) {
+                return Err(format!("margin must be between 0 and {}", LETHAL_MARGIN_MAX));
+            }
+            Ok(Command::Margin(k))
+        }
+        "shift" => Ok(Command::Shift(value.map(Topology::parse_shift).transpose()?)),
+        "dump-debug" => Ok(Command::DumpDebug(value.map(str::to_string))),
+        "export-svg" => Ok(Command::ExportSvg(value.map(str::to_string))),
+        "export-rle" => Ok(Command::ExportRle(value.map(str::to_string))),
+        "fps" => match value.ok_or("fps expects a frame rate, or off")? {
+            "off" => Ok(Command::Fps(0)),
+            _ => {
+                let fps: u32 = arg(&name, value)?;
+                if !(FPS_CAP_MIN..=FPS_CAP_MAX).contains(&fps) {
+                    return Err(format!("fps must be between {} and {}, or off", FPS_CAP_MIN, FPS_CAP_MAX));
+                }
+                Ok(Command::Fps(fps))
+            }
+        },
+        "saver" => match value.ok_or("saver expects on or off")? {
+            "on" => Ok(Command::Saver(true)),
+            "off" => Ok(Command::Saver(false)),
+            other => Err(format!("expected on or off, got '{}'", other)),
+        },
+        "compare" => match value.ok_or("compare expects a .gold or pattern file, or off")? {
+            "off" => Ok(Command::Compare(None)),
+            path => Ok(Command::Compare(Some(path.to_string()))),
+        },
+        "" => Err("type a command, or 'help'".to_string()),
+        other => Err(format!("unknown command '{}'", other)),
+    }
+}
+
+/// Run a command against a board and its view, returning the reply and any change the caller must make
+pub fn execute(command: &Command, sim: &mut Simulation, view: &mut BoardView) -> Result<(String, Outcome), String> {
+    let done = |text: String| Ok((text, Outcome::Done));
+    match command {
+        Command::Help => done(format!("commands: {}", COMMAND_NAMES.join(", "))),
+        Command::Speed(speed) => Ok((format!("speed {} gen/s", speed), Outcome::SetSpeed(*speed))),
+        Command::Schedule(None) => Ok(("speed schedule off".to_string(), Outcome::Schedule(None))),
+        Command::Schedule(Some(schedule)) => {
+            Ok((format!("speed schedule {}", schedule), Outcome::Schedule(Some(schedule.clone()))))
+        }
+        Command::Seed(seed) => {
+            sim.seed = *seed;
+            sim.clear();
+            sim.random_fill(sim.density);
+            done(format!("random soup from seed {}", seed))
+        }
+        Command::Fill(density) => {
+            sim.clear();
+            sim.random_fill(*density);
+            done(format!("filled {} cells", sim.live.len()))
+        }
+        Command::Goto(generation) if *generation < sim.generation => {
+            Err(format!("generation {} has already passed", generation))
+        }
+        Command::Goto(generation) => Ok((format!("going to generation {}", generation), Outcome::GoTo(*generation))),
+        Command::Step(n) => {
+            for _ in 0..*n {
+                sim.next_generation();
+            }
+            done(format!("generation {}", sim.generation))
+        }
+        Command::Clear => {
+            sim.clear();
+            done("board cleared".to_string())
+        }
+        Command::Load(path) => {
+            let (w, h) = (sim.grid.width, sim.grid.height);
+            // A picture is fitted to the board rather than to the usual import size
+            let pattern = if image::AVAILABLE && image::is_image_file(path.as_ref()) {
+                image::load(path.as_ref(), w, h, ImageOptions::default())?
+            } else {
+                load_pattern_file(path.as_ref())?
+            };
+            if pattern.width > w || pattern.height > h {
+                return Err(format!("{} is {}x{}, larger than the {}x{} grid", path, pattern.width, pattern.height, w, h));
+            }
+            sim.clear();
+            sim.apply_pattern(&pattern, w / 2, h / 2).map_err(|e| e.to_string())?;
+            sim.imported = Some((pattern.name.clone(), pattern.meta.clone()));
+            // The board was cleared for it, so the file's rule can simply take over
+            match pattern.foreign_rule(sim.grid.rule) {
+                Some(Ok(rule)) => {
+                    sim.set_rule(rule)?;
+                    done(format!("loaded {} with its rule {}", pattern.name(), rule))
+                }
+                Some(Err(e)) => done(format!("loaded {} ({}; running it as {})", pattern.name(), e, sim.grid.rule)),
+                None => done(format!("loaded {}", pattern.name())),
+            }
+        }
+        Command::Image(path, options) => {
+            let picture = Picture::open(path.as_ref(), sim.grid.width, sim.grid.height)?;
+            let name = std::path::Path::new(path).file_stem().and_then(|s| s.to_str()).unwrap_or("Picture").to_string();
+            let reply = format!("previewing {} at {}x{} cells", path, picture.width, picture.height);
+            Ok((reply, Outcome::Image(name, picture, *options)))
+        }
+        Command::ImportAll(glob, spacing) => {
+            let files = expand_glob(glob);
+            if files.is_empty() {
+                return Err(format!("no pattern files match {}", glob));
+            }
+            let (mut loaded, mut unreadable) = (Vec::new(), Vec::new());
+            for file in &files {
+                match load_pattern_file(file) {
+                    Ok(pattern) => loaded.push(pattern),
+                    Err(e) => unreadable.push(e.to_string()),
+                }
+            }
+            let patterns: Vec<&dyn Pattern> = loaded.iter().map(|p| p as &dyn Pattern).collect();
+            let layout = layout_patterns(&patterns, &sim.grid, *spacing);
+            sim.clear();
+            for (at, pattern) in &layout.placed {
+                sim.apply_pattern(*pattern, at.x(), at.y()).map_err(|e| e.to_string())?;
+            }
+            let mut summary = format!("imported {} of {} patterns", layout.placed.len(), files.len());
+            if !layout.skipped.is_empty() {
+                let names: Vec<&str> = layout.skipped.iter().map(|p| p.name()).collect();
+                summary += &format!("; no room for {}", names.join(", "));
+            }
+            if !unreadable.is_empty() {
+                summary += &format!("; couldn't read {}", unreadable.join(", "));
+            }
+            Ok((summary.clone(), Outcome::Toast(summary)))
+        }
+        Command::Url(url) => Ok((format!("fetching {}", url), Outcome::Fetch(url.clone()))),
+        Command::Script(None) => Ok(("script off".to_string(), Outcome::Script(None))),
+        Command::Script(Some(path)) => Ok((format!("loading {}", path), Outcome::Script(Some(PathBuf::from(path))))),
+        Command::Set(dx, dy, alive) => {
+            let (x, y) = view.coords.from_display(&sim.grid, *dx, *dy);
+            let Some(p) = sim.grid.resolve(x, y) else {
+                return Err(format!("({}, {}) is outside the grid", dx, dy));
+            };
+            sim.set_cell(p, *alive);
+            done(format!("{} is {}", view.format_cell(&sim.grid, p.x(), p.y()), if *alive { "alive" } else { "dead" }))
+        }
+        Command::Theme(theme) => {
+            view.theme = *theme;
+            done(format!("theme {}", theme.name()))
+        }
+        Command::Rule(None) => done(format!("rule {}", sim.grid.rule)),
+        Command::Rule(Some(text)) => {
+            sim.set_rule(Rule::parse(text)?)?;
+            done(format!("rule {}", sim.grid.rule))
+        }
+        Command::Margin(0) => {
+            sim.lethal_margin = 0;
+            done("lethal margin off".to_string())
+        }
+        Command::Margin(k) => {
+            sim.lethal_margin = *k;
+            if sim.grid.topology.is_torus() {
+                done(format!("lethal margin {} cells (no effect until wrap is off)", k))
+            } else {
+                done(format!("lethal margin {} cells", k))
+            }
+        }
+        Command::Shift(None) if sim.grid.topology.is_torus() => done(format!("torus shift {}", sim.grid.topology.shift_label())),
+        Command::Shift(None) => done(format!("no torus shift ({} edges)", sim.grid.topology)),
+        Command::Shift(Some(torus)) => {
+            let was_torus = sim.grid.topology.is_torus();
+            sim.grid.topology = *torus;
+            if was_torus {
+                done(format!("torus shift {}", torus.shift_label()))
+            } else {
+                done(format!("torus shift {} (the edges now wrap as a torus)", torus.shift_label()))
+            }
+        }
+        Command::DumpDebug(path) => {
+            let path = path.clone().map_or_else(|| debug_dump_path(sim.generation), PathBuf::from);
+            Ok((format!("writing {}", path.display()), Outcome::DumpDebug(path)))
+        }
+        Command::ExportSvg(path) => {
+            let path = path.clone().map_or_else(|| PathBuf::from(format!("board-{}.svg", sim.generation)), PathBuf::from);
+            let options = SvgOptions { theme: view.theme, grid: view.grid_style.mode != GridMode::Off, ..SvgOptions::default() };
+            write_board_svg(&path, sim, &options)?;
+            let reply = format!("wrote {} cells to {}", sim.live.len(), path.display());
+            match svg_size_warning(sim.live.len()) {
+                Some(warning) => done(format!("{} ({})", reply, warning)),
+                None => done(reply),
+            }
+        }
+        Command::ExportRle(path) => {
+            let path = path.clone().map_or_else(|| PathBuf::from(format!("board-{}.rle", sim.generation)), PathBuf::from);
+            error::write(&path, sim.to_rle())?;
+            done(format!("wrote {} cells to {}", sim.live.len(), path.display()))
+        }
+        Command::Fps(0) => Ok(("frame rate uncapped".to_string(), Outcome::FpsCap(0))),
+        Command::Fps(fps) => Ok((format!("frame rate capped at {} fps", fps), Outcome::FpsCap(*fps))),
+        Command::Compare(None) => Ok(("compare mode off".to_string(), Outcome::Compare(None))),
+        Command::Compare(Some(path)) => Ok((format!("loading {}", path), Outcome::Compare(Some(PathBuf::from(path))))),
+        Command::Saver(on) => Ok((format!("battery saver {}", if *on { "on" } else { "off" }), Outcome::Saver(*on))),
+    }
+}
+
+/// Where a debug dump goes unless told otherwise: the working directory, named after the generation
+pub fn debug_dump_path(generation: u64) -> PathBuf {
+    PathBuf::from(format!("debug-{}.json", generation))
+}
+
+/// Complete a partly typed command name when exactly one command matches
+pub fn complete(input: &str) -> Option<&'static str> {
+    let prefix = input.trim_start().to_ascii_lowercase();
+    if prefix.is_empty() || prefix.contains(char::is_whitespace) {
+        return None;
+    }
+    let mut matches = COMMAND_NAMES.iter().filter(|c| c.starts_with(&prefix));
+    match (matches.next(), matches.next()) {
+        (Some(name), None) => Some(name),
+        _ => None,
+    }
+}
+
+/// Commands entered so far and their replies, oldest first
+pub struct Scrollback {
+    lines: VecDeque<(String, bool)>, // Text and whether it reports an error
+    capacity: usize,
+}
+
+impl Scrollback {
+    pub fn new(capacity: usize) -> Self {
+        Self { lines: VecDeque::with_capacity(capacity), capacity }
+    }
+
+    pub fn push(&mut self, text: impl Into<String>, error: bool) {
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+        }
+        self.lines.push_back((text.into(), error));
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &(String, bool)> {
+        self.lines.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(line: &str, sim: &mut Simulation, view: &mut BoardView) -> Result<(String, Outcome), String> {
+        execute(&parse(line)?, sim, view)
+    }
+
+    #[test]
+    fn parses_commands_and_their_arguments() {
+        assert_eq!(parse("speed 12.5"), Ok(Command::Speed(12.5)));
+        assert_eq!(parse("  SEED 7 "), Ok(Command::Seed(7)));
+        assert_eq!(parse("fill 0.5"), Ok(Command::Fill(0.5)));
+        assert_eq!(parse("goto 30"), Ok(Command::Goto(30)));
+        assert_eq!(parse("theme neon"), Ok(Command::Theme(ColorTheme::Neon)));
+        assert_eq!(parse("rule"), Ok(Command::Rule(None)));
+        assert_eq!(parse("rule B36/S23"), Ok(Command::Rule(Some("B36/S23".to_string()))));
+        assert_eq!(parse("load patterns/two-gliders.mc"), Ok(Command::Load("patterns/two-gliders.mc".to_string())));
+    }
+
+    #[test]
+    fn refuses_malformed_and_out_of_range_input() {
+        for line in ["speed fast", "speed 100000", "seed -1", "fill 1.5", "fill", "goto soon", "load", "theme plaid", "frobnicate"] {
+            assert!(parse(line).is_err(), "'{}' was accepted", line);
+        }
+    }
+
+    #[test]
+    fn rule_speed_and_goto() {
+        let (mut sim, mut view) = (Simulation::new(20, 20), BoardView::new(4));
+        let highlife = Rule::parse("B36/S23").unwrap();
+        let (reply, _) = run("rule B36/S23", &mut sim, &mut view).unwrap();
+        assert_eq!((sim.grid.rule, reply), (highlife, format!("rule {}", highlife)));
+        // Speed and goto live in the UI, so they come back for the caller to apply
+        assert_eq!(run("speed 12.5", &mut sim, &mut view).unwrap().1, Outcome::SetSpeed(12.5));
+        assert_eq!(run("goto 30", &mut sim, &mut view).unwrap().1, Outcome::GoTo(30));
+        assert_eq!(sim.generation, 0);
+    }
+
+    #[test]
+    fn seed_and_fill_replace_the_board() {
+        let (mut sim, mut view) = (Simulation::new(20, 20), BoardView::new(4));
+        run("seed 7", &mut sim, &mut view).unwrap();
+        let seeded = sim.live.clone();
+        assert_eq!(sim.seed, 7);
+        assert!(!seeded.is_empty());
+        run("fill 0.5", &mut sim, &mut view).unwrap();
+        assert!(!sim.live.is_empty() && sim.live.len() < 400 && sim.live != seeded, "{} of 400 cells", sim.live.len());
+    }
+
+    #[test]
+    fn load_and_theme() {
+        let (mut sim, mut view) = (Simulation::new(20, 20), BoardView::new(4));
+        let dir = std::env::temp_dir().join(format!("conways-game-of-life-console-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("glider.rle");
+        std::fs::write(&file, "#N Glider\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n").unwrap();
+        let (highlife, strobing) = (dir.join("highlife.rle"), dir.join("strobing.rle"));
+        std::fs::write(&highlife, "#N Replicator\nx = 3, y = 3, rule = B36/S23\nb2o$obo$2o!\n").unwrap();
+        std::fs::write(&strobing, "#N Strobe\nx = 1, y = 1, rule = B03/S23\no!\n").unwrap();
+        let loaded = run(&format!("load {}", file.display()), &mut sim, &mut view);
+        let missing = run(&format!("load {}", dir.join("missing.rle").display()), &mut sim, &mut view);
+        assert_eq!(loaded.unwrap().0, "loaded Glider");
+        assert_eq!(sim.live.len(), 5);
+        assert!(missing.is_err());
+        assert_eq!(sim.live.len(), 5);
+        // A file's own rule replaces the board's, unless the engine can't run it
+        let switched = run(&format!("load {}", highlife.display()), &mut sim, &mut view);
+        let kept = run(&format!("load {}", strobing.display()), &mut sim, &mut view);
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(switched.unwrap().0, "loaded Replicator with its rule B36/S23");
+        let kept = kept.unwrap().0;
+        assert!(kept.starts_with("loaded Strobe (B03/S23 has B0") && kept.ends_with("; running it as B36/S23)"), "{}", kept);
+        assert_eq!(sim.grid.rule, Rule::parse("B36/S23").unwrap());
+        run("theme neon", &mut sim, &mut view).unwrap();
+        assert_eq!(view.theme, ColorTheme::Neon);
+    }
+
+    #[test]
+    fn refused_commands_leave_the_board() {
+        let (mut sim, mut view) = (Simulation::new(20, 20), BoardView::new(4));
+        run("seed 3", &mut sim, &mut view).unwrap();
+        sim.next_generation();
+        let (board, rule) = (sim.live.clone(), sim.grid.rule);
+        // The rule and the generation are checked when the command runs, not when it parses
+        assert!(run("rule B9/S23", &mut sim, &mut view).is_err());
+        assert!(run("goto 0", &mut sim, &mut view).is_err());
+        assert_eq!((sim.generation, &sim.live, sim.grid.rule), (1, &board, rule));
+    }
+
+    #[test]
+    fn completes_unambiguous_prefixes() {
+        assert_eq!(complete("sp"), Some("speed"));
+        assert_eq!(complete("s"), None);
+        assert_eq!(complete("speed 3"), None);
+        let mut back = Scrollback::new(2);
+        for line in ["a", "b", "c"] {
+            back.push(line, false);
+        }
+        assert_eq!(back.iter().map(|(t, _)| t.as_str()).collect::<Vec<_>>(), ["b", "c"]);
+    }
+}
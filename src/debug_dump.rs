@@ -0,0 +1,442 @@
+//! Debug dumps for bug reports: one JSON file holding everything needed to rebuild a run exactly
+//! where it stood (grid, rule, edges, seed, generation and the board as RLE), plus what helps read
+//! it: recent populations, the settings file, the keys and the build. Notes pinned to the board
+//! are saved alongside it. `--load-debug-dump` reads one back and checks the rebuilt board
+//! against the hash it was saved with.
+
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::annotations::{Annotation, NoteColor};
+use crate::config::{DEBUG_DUMP_FORMAT, GRID_MAX};
+use crate::error::{self, GolError, Location};
+use crate::grid::{board_hash, Position, Topology};
+use crate::pattern_io::{parse_rle, write_rle};
+use crate::rule::Rule;
+use crate::simulation::Simulation;
+
+/// The state of a run, and its surroundings, at the moment it was dumped
+#[derive(Clone, Debug, PartialEq)]
+pub struct DebugDump {
+    pub version: String,  // Crate version that wrote the dump
+    pub platform: String, // Operating system and architecture
+    pub width: i32,
+    pub height: i32,
+    pub rule: Rule,
+    pub topology: Topology,
+    pub seed: u64,
+    pub density: f32,
+    pub generation: u64,
+    pub edge_spawn: bool,
+    pub edge_rate: f32,
+    pub lethal_margin: i32,
+    pub injected: u64, // Cells edge spawning has added since the last clear
+    pub origin: (i32, i32), // Top-left corner of the live cells' bounding box, where the RLE starts
+    pub board: String,      // The live cells as RLE
+    pub board_hash: u64,
+    pub population: usize,
+    pub history: Vec<(u64, usize)>, // Recent (generation, population) samples, oldest first
+    pub settings: String,           // The settings file as it would be saved
+    pub keybindings: Vec<String>,   // The controls list, one key per entry
+    pub annotations: Vec<Annotation>, // Notes pinned to the board; not part of the simulation
+}
+
+impl DebugDump {
+    /// Capture `sim` with its recent population history, the settings text and the controls list
+    pub fn capture(sim: &Simulation, history: Vec<(u64, usize)>, settings: String, keybindings: &str) -> Self {
+        let cells: Vec<(i32, i32)> = sim.live.iter().map(|p| (p.x(), p.y())).collect();
+        let origin = (cells.iter().map(|c| c.0).min().unwrap_or(0), cells.iter().map(|c| c.1).min().unwrap_or(0));
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            platform: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+            width: sim.grid.width,
+            height: sim.grid.height,
+            rule: sim.grid.rule,
+            topology: sim.grid.topology,
+            seed: sim.seed,
+            density: sim.density,
+            generation: sim.generation,
+            edge_spawn: sim.edge_spawn,
+            edge_rate: sim.edge_rate,
+            lethal_margin: sim.lethal_margin,
+            injected: sim.injected,
+            origin,
+            board: write_rle(&format!("generation {}", sim.generation), &sim.grid.rule.to_string(), &cells),
+            board_hash: board_hash(&sim.live),
+            population: sim.live.len(),
+            history,
+            settings,
+            keybindings: keybindings.split(" | ").map(str::to_string).collect(),
+            annotations: Vec::new(),
+        }
+    }
+
+    /// Rebuild the simulation, refusing a board that doesn't match the hash it was saved with
+    pub fn simulation(&self) -> Result<Simulation, GolError> {
+        if !(1..=GRID_MAX).contains(&self.width) || !(1..=GRID_MAX).contains(&self.height) {
+            return Err(GolError::invalid("grid size", format!("{}x{} is outside 1 to {}", self.width, self.height, GRID_MAX)));
+        }
+        let mut sim = Simulation::new(self.width, self.height);
+        sim.grid.rule = self.rule.runnable()?;
+        sim.grid.topology = self.topology;
+        sim.seed = self.seed;
+        sim.density = self.density;
+        sim.generation = self.generation;
+        sim.edge_spawn = self.edge_spawn;
+        sim.edge_rate = self.edge_rate;
+        sim.lethal_margin = self.lethal_margin;
+        sim.injected = self.injected;
+        let pattern = if self.population == 0 { None } else { Some(parse_rle(&self.board)?) };
+        let (left, top) = self.origin;
+        sim.live = pattern.iter().flat_map(|p| &p.cells).map(|&(x, y)| Position::new(left + x, top + y)).collect::<HashSet<_>>();
+        if sim.live.iter().any(|p| !sim.grid.in_bounds(p.x(), p.y())) {
+            return Err(GolError::invalid("debug dump", format!("the board has cells outside the {}x{} grid", self.width, self.height)));
+        }
+        if sim.live.len() != self.population || board_hash(&sim.live) != self.board_hash {
+            return Err(GolError::invalid("debug dump", format!(
+                "the rebuilt board ({} cells, hash {:016x}) doesn't match the one saved ({} cells, hash {:016x})",
+                sim.live.len(), board_hash(&sim.live), self.population, self.board_hash,
+            )));
+        }
+        Ok(sim)
+    }
+
+    /// The dump as pretty-printed JSON
+    pub fn to_json(&self) -> String {
+        let history = self.history.iter().map(|(g, n)| format!("[{}, {}]", g, n)).collect::<Vec<_>>().join(", ");
+        let keys = self.keybindings.iter().map(|k| quote(k)).collect::<Vec<_>>().join(",\n    ");
+        let notes = self.annotations.iter()
+            .map(|n| format!("{{\"x\": {}, \"y\": {}, \"color\": {}, \"text\": {}}}", n.at.x(), n.at.y(), quote(n.color.name()), quote(&n.text)))
+            .collect::<Vec<_>>();
+        let mut out = String::from("{\n");
+        let mut field = |key: &str, value: String| {
+            let _ = writeln!(out, "  {}: {},", quote(key), value);
+        };
+        field("format", quote(DEBUG_DUMP_FORMAT));
+        field("version", quote(&self.version));
+        field("platform", quote(&self.platform));
+        field("width", self.width.to_string());
+        field("height", self.height.to_string());
+        field("rule", quote(&self.rule.to_string()));
+        field("topology", quote(&self.topology.to_string()));
+        field("shift", quote(&self.topology.shift_label()));
+        field("seed", self.seed.to_string());
+        field("density", self.density.to_string());
+        field("generation", self.generation.to_string());
+        field("edge_spawn", self.edge_spawn.to_string());
+        field("edge_rate", self.edge_rate.to_string());
+        field("lethal_margin", self.lethal_margin.to_string());
+        field("injected", self.injected.to_string());
+        field("population", self.population.to_string());
+        field("board_hash", quote(&format!("{:016x}", self.board_hash)));
+        field("origin", format!("[{}, {}]", self.origin.0, self.origin.1));
+        field("board", quote(&self.board));
+        field("population_history", format!("[{}]", history));
+        field("settings", quote(&self.settings));
+        field("annotations", if notes.is_empty() { "[]".to_string() } else { format!("[\n    {}\n  ]", notes.join(",\n    ")) });
+        let _ = write!(out, "  \"keybindings\": [\n    {}\n  ]\n}}\n", keys);
+        out
+    }
+
+    /// Read a dump written by `to_json`. Unknown fields are ignored, and dumps written before
+    /// notes were saved read back with none.
+    pub fn parse(text: &str) -> Result<Self, GolError> {
+        let root = Parser { text, at: 0 }.document()?;
+        let Json::Object(fields) = &root else { return Err(bad_field("expected a JSON object")); };
+        let get = |key: &'static str| {
+            fields.iter().find(|(k, _)| k == key).map(|(_, v)| v).ok_or_else(|| bad_field(format!("missing \"{}\"", key)))
+        };
+        let string = |key: &'static str| match get(key)? {
+            Json::String(s) => Ok(s.clone()),
+            _ => Err(bad_field(format!("\"{}\" should be a string", key))),
+        };
+        let number = |key: &'static str| match get(key)? {
+            Json::Number(n) => Ok(n.clone()),
+            _ => Err(bad_field(format!("\"{}\" should be a number", key))),
+        };
+        fn num<T: std::str::FromStr>(key: &str, raw: String) -> Result<T, GolError> {
+            raw.parse().map_err(|_| bad_field(format!("bad \"{}\" value {}", key, raw)))
+        }
+        let pairs = |key: &'static str| -> Result<Vec<(String, String)>, GolError> {
+            let bad = || bad_field(format!("\"{}\" should hold pairs of numbers", key));
+            let Json::Array(items) = get(key)? else { return Err(bad()); };
+            items.iter().map(|item| match item {
+                Json::Array(pair) => match pair.as_slice() {
+                    [Json::Number(a), Json::Number(b)] => Ok((a.clone(), b.clone())),
+                    _ => Err(bad()),
+                },
+                _ => Err(bad()),
+            }).collect()
+        };
+        if string("format")? != DEBUG_DUMP_FORMAT {
+            return Err(bad_field("not a debug dump"));
+        }
+        let (ox, oy) = match get("origin")? {
+            Json::Array(xy) => match xy.as_slice() {
+                [Json::Number(x), Json::Number(y)] => (x.clone(), y.clone()),
+                _ => return Err(bad_field("\"origin\" should be [x, y]")),
+            },
+            _ => return Err(bad_field("\"origin\" should be [x, y]")),
+        };
+        let keybindings = match get("keybindings")? {
+            Json::Array(items) => items.iter().map(|k| match k {
+                Json::String(s) => Ok(s.clone()),
+                _ => Err(bad_field("\"keybindings\" should hold strings")),
+            }).collect::<Result<_, _>>()?,
+            _ => return Err(bad_field("\"keybindings\" should be a list")),
+        };
+        let annotations = match fields.iter().find(|(k, _)| k == "annotations").map(|(_, v)| v) {
+            None => Vec::new(),
+            Some(Json::Array(items)) => items.iter().map(annotation).collect::<Result<_, _>>()?,
+            Some(_) => return Err(bad_field("\"annotations\" should be a list")),
+        };
+        // Dumps from before the shift was part of the topology keep it in "shift" alone
+        let topology = Topology::parse(&string("topology")?).map_err(bad_field)?;
+        let topology = topology.with_shift_of(Topology::parse_shift(&string("shift")?).map_err(bad_field)?);
+        Ok(Self {
+            version: string("version")?,
+            platform: string("platform")?,
+            width: num("width", number("width")?)?,
+            height: num("height", number("height")?)?,
+            rule: Rule::parse(&string("rule")?)?,
+            topology,
+            seed: num("seed", number("seed")?)?,
+            density: num("density", number("density")?)?,
+            generation: num("generation", number("generation")?)?,
+            edge_spawn: match get("edge_spawn")? {
+                Json::Bool(b) => *b,
+                _ => return Err(bad_field("\"edge_spawn\" should be true or false")),
+            },
+            edge_rate: num("edge_rate", number("edge_rate")?)?,
+            lethal_margin: num("lethal_margin", number("lethal_margin")?)?,
+            injected: num("injected", number("injected")?)?,
+            origin: (num("origin", ox)?, num("origin", oy)?),
+            board: string("board")?,
+            board_hash: u64::from_str_radix(&string("board_hash")?, 16).map_err(|_| bad_field("bad \"board_hash\""))?,
+            population: num("population", number("population")?)?,
+            history: pairs("population_history")?.into_iter()
+                .map(|(g, n)| Ok((num("population_history", g)?, num("population_history", n)?)))
+                .collect::<Result<_, GolError>>()?,
+            settings: string("settings")?,
+            keybindings,
+            annotations,
+        })
+    }
+
+    /// Write the dump to `path`
+    pub fn save(&self, path: &Path) -> Result<(), GolError> {
+        error::write(path, self.to_json())
+    }
+
+    /// Read a dump from `path`, naming the file in any error
+    pub fn load(path: &Path) -> Result<Self, GolError> {
+        Self::parse(&error::read_to_string(path)?).map_err(|e| e.in_file(path))
+    }
+}
+
+/// One note of the `annotations` list: `{"x": 3, "y": 4, "color": "yellow", "text": "..."}`
+fn annotation(item: &Json) -> Result<Annotation, GolError> {
+    let bad = || bad_field("each annotation should have a whole x and y, a color and a text");
+    let Json::Object(fields) = item else { return Err(bad()); };
+    let get = |key: &str| fields.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+    let coordinate = |key: &str| match get(key) {
+        Some(Json::Number(n)) => n.parse::<i32>().map_err(|_| bad()),
+        _ => Err(bad()),
+    };
+    let color = match get("color") {
+        Some(Json::String(name)) => NoteColor::from_name(name).ok_or_else(|| bad_field(format!("unknown annotation color '{}'", name)))?,
+        _ => return Err(bad()),
+    };
+    let Some(Json::String(text)) = get("text") else { return Err(bad()); };
+    Ok(Annotation { at: Position::new(coordinate("x")?, coordinate("y")?), text: text.clone(), color })
+}
+
+/// JSON that can't be read, at a byte offset
+fn malformed(offset: usize, message: impl Into<String>) -> GolError {
+    GolError::parse("JSON", Location::Offset(offset), message)
+}
+
+/// Well-formed JSON missing a field the dump needs, or holding the wrong kind of value
+fn bad_field(message: impl Into<String>) -> GolError {
+    GolError::parse("debug dump", Location::Whole, message)
+}
+
+/// A JSON string literal
+fn quote(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out += "\\\"",
+            '\\' => out += "\\\\",
+            '\n' => out += "\\n",
+            '\r' => out += "\\r",
+            '\t' => out += "\\t",
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// A parsed JSON value. Numbers keep their text so 64-bit seeds survive exactly.
+#[derive(Debug)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+/// Recursive-descent reader for the JSON the dump writes, and hand edits of it
+struct Parser<'a> {
+    text: &'a str,
+    at: usize, // Byte offset of the next character
+}
+
+impl Parser<'_> {
+    fn document(mut self) -> Result<Json, GolError> {
+        let value = self.value(0)?;
+        self.skip_space();
+        if self.at < self.text.len() {
+            return Err(malformed(self.at, "unexpected text after the value"));
+        }
+        Ok(value)
+    }
+
+    fn skip_space(&mut self) {
+        let rest = &self.text[self.at..];
+        self.at += rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.at..].chars().next()
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), GolError> {
+        self.skip_space();
+        if self.peek() != Some(c) {
+            return Err(malformed(self.at, format!("expected '{}'", c)));
+        }
+        self.at += 1;
+        Ok(())
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, GolError> {
+        if depth > 32 {
+            return Err(malformed(self.at, "nested too deeply"));
+        }
+        self.skip_space();
+        let rest = &self.text[self.at..];
+        for (word, value) in [("null", Json::Null), ("true", Json::Bool(true)), ("false", Json::Bool(false))] {
+            if rest.starts_with(word) {
+                self.at += word.len();
+                return Ok(value);
+            }
+        }
+        match self.peek() {
+            Some('"') => self.string().map(Json::String),
+            Some('[') => {
+                self.at += 1;
+                let mut items = Vec::new();
+                self.skip_space();
+                if self.peek() == Some(']') {
+                    self.at += 1;
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value(depth + 1)?);
+                    self.skip_space();
+                    match self.peek() {
+                        Some(',') => self.at += 1,
+                        Some(']') => { self.at += 1; return Ok(Json::Array(items)); }
+                        _ => return Err(malformed(self.at, "expected ',' or ']'")),
+                    }
+                }
+            }
+            Some('{') => {
+                self.at += 1;
+                let mut fields = Vec::new();
+                self.skip_space();
+                if self.peek() == Some('}') {
+                    self.at += 1;
+                    return Ok(Json::Object(fields));
+                }
+                loop {
+                    self.skip_space();
+                    let key = self.string()?;
+                    self.expect(':')?;
+                    fields.push((key, self.value(depth + 1)?));
+                    self.skip_space();
+                    match self.peek() {
+                        Some(',') => self.at += 1,
+                        Some('}') => { self.at += 1; return Ok(Json::Object(fields)); }
+                        _ => return Err(malformed(self.at, "expected ',' or '}'")),
+                    }
+                }
+            }
+            Some('-' | '0'..='9') => {
+                let len = rest.find(|c: char| !matches!(c, '-' | '+' | '.' | 'e' | 'E' | '0'..='9')).unwrap_or(rest.len());
+                self.at += len;
+                Ok(Json::Number(rest[..len].to_string()))
+            }
+            _ => Err(malformed(self.at, "expected a value")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, GolError> {
+        if self.peek() != Some('"') {
+            return Err(malformed(self.at, "expected a string"));
+        }
+        self.at += 1;
+        let mut out = String::new();
+        let mut chars = self.text[self.at..].char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.at += i + 1;
+                    return Ok(out);
+                }
+                '\\' => {
+                    let escape = chars.next().map(|(_, e)| e);
+                    match escape {
+                        Some('"') => out.push('"'),
+                        Some('\\') => out.push('\\'),
+                        Some('/') => out.push('/'),
+                        Some('n') => out.push('\n'),
+                        Some('r') => out.push('\r'),
+                        Some('t') => out.push('\t'),
+                        Some('b') => out.push('\u{8}'),
+                        Some('f') => out.push('\u{c}'),
+                        Some('u') => {
+                            let hex = |chars: &mut std::str::CharIndices| {
+                                let digits: String = chars.by_ref().take(4).map(|(_, d)| d).collect();
+                                u32::from_str_radix(&digits, 16).ok().filter(|_| digits.len() == 4)
+                            };
+                            let unit = hex(&mut chars);
+                            // A surrogate pair is two escapes
+                            let code = match unit {
+                                Some(high @ 0xD800..=0xDBFF) => {
+                                    let low = (chars.next().map(|(_, c)| c) == Some('\\') && chars.next().map(|(_, c)| c) == Some('u'))
+                                        .then(|| hex(&mut chars)).flatten();
+                                    low.filter(|l| (0xDC00..=0xDFFF).contains(l)).map(|l| 0x10000 + ((high - 0xD800) << 10) + (l - 0xDC00))
+                                }
+                                other => other,
+                            };
+                            out.push(code.and_then(char::from_u32).ok_or_else(|| malformed(self.at + i, "bad \\u escape"))?);
+                        }
+                        _ => return Err(malformed(self.at + i, "bad escape in string")),
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+        Err(malformed(self.text.len(), "unterminated string"))
+    }
+}
@@ -0,0 +1,463 @@
+//! Compact history of a run: a reference board plus the births and deaths of each recorded
+//! generation, so any of them can be rebuilt without storing full snapshots.
+
+use std::collections::HashSet;
+
+use crate::annotations::{Annotation, NoteColor};
+use crate::bookmarks::Bookmark;
+use crate::error::{GolError, Location};
+use crate::schedule::SpeedSchedule;
+use crate::simulation::{set_bytes, vec_bytes, Simulation};
+use crate::grid::{Grid, Position, Topology};
+
+const MAGIC: &[u8; 4] = b"GOLD";
+const VERSION: u8 = 6; // Version 2 adds the bookmarks, 3 the speed schedule, 4 torus shifts, 5 cylinders and 6 notes; older files still load without them
+
+/// Changes between the previous recorded generation and `generation`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Delta {
+    pub generation: u64,
+    pub born: Vec<Position>,
+    pub died: Vec<Position>,
+}
+
+impl Delta {
+    pub fn bytes(&self) -> usize {
+        std::mem::size_of::<Delta>() + vec_bytes(&self.born) + vec_bytes(&self.died)
+    }
+}
+
+/// A reference board and the deltas recorded after it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeltaLog {
+    pub width: i32,
+    pub height: i32,
+    pub topology: Topology,
+    pub reference_generation: u64,
+    pub reference: Vec<Position>,
+    pub deltas: Vec<Delta>,
+    pub bookmarks: Vec<Bookmark>, // Saved alongside; they may lie outside the recorded range
+    pub schedule: Option<SpeedSchedule>, // Speed schedule the run was paced by, so a replay can pace it the same
+    pub annotations: Vec<Annotation>, // Notes pinned to the board, saved alongside like the bookmarks
+    current: HashSet<Position>, // Board at the last recorded generation; not serialized
+}
+
+impl DeltaLog {
+    /// Mark the simulation's current board as the reference point
+    pub fn new(sim: &Simulation) -> Self {
+        let mut reference: Vec<Position> = sim.live.iter().copied().collect();
+        reference.sort_by_key(|p| (p.y(), p.x()));
+        Self {
+            width: sim.grid.width,
+            height: sim.grid.height,
+            topology: sim.grid.topology,
+            reference_generation: sim.generation,
+            reference,
+            deltas: Vec::new(),
+            bookmarks: Vec::new(),
+            schedule: None,
+            annotations: Vec::new(),
+            current: sim.live.clone(),
+        }
+    }
+
+    /// Generation of the newest recorded board
+    pub fn last_generation(&self) -> u64 {
+        self.deltas.last().map_or(self.reference_generation, |d| d.generation)
+    }
+
+    /// Record the board if its generation moved on since the last record.
+    /// Returns false when the simulation went back in time (cleared or resized) and the log no longer applies.
+    pub fn record(&mut self, sim: &Simulation) -> bool {
+        if sim.generation < self.last_generation()
+            || sim.grid.width != self.width
+            || sim.grid.height != self.height
+        {
+            return false;
+        }
+        if sim.generation == self.last_generation() {
+            return true;
+        }
+        let mut born: Vec<Position> = sim.live.difference(&self.current).copied().collect();
+        let mut died: Vec<Position> = self.current.difference(&sim.live).copied().collect();
+        born.sort_by_key(|p| (p.y(), p.x()));
+        died.sort_by_key(|p| (p.y(), p.x()));
+        self.deltas.push(Delta { generation: sim.generation, born, died });
+        self.current = sim.live.clone();
+        true
+    }
+
+    /// Fold the oldest `count` deltas into the reference, so the log starts that much later and
+    /// holds less. The generations folded away can no longer be rebuilt.
+    pub fn drop_oldest(&mut self, count: usize) {
+        let count = count.min(self.deltas.len());
+        if count == 0 {
+            return;
+        }
+        let mut live: HashSet<Position> = self.reference.iter().copied().collect();
+        for delta in self.deltas.drain(..count) {
+            for p in &delta.died {
+                live.remove(p);
+            }
+            live.extend(delta.born);
+            self.reference_generation = delta.generation;
+        }
+        self.reference = live.into_iter().collect();
+        self.reference.sort_by_key(|p| (p.y(), p.x()));
+    }
+
+    /// Approximate heap size of the recorded boards
+    pub fn bytes(&self) -> usize {
+        vec_bytes(&self.reference) + set_bytes(&self.current) + self.deltas.iter().map(Delta::bytes).sum::<usize>()
+    }
+
+    /// Board at a recorded generation, or `None` if that generation wasn't recorded
+    pub fn reconstruct(&self, generation: u64) -> Option<HashSet<Position>> {
+        let mut live: HashSet<Position> = self.reference.iter().copied().collect();
+        if generation == self.reference_generation {
+            return Some(live);
+        }
+        for delta in &self.deltas {
+            for p in &delta.died {
+                live.remove(p);
+            }
+            live.extend(delta.born.iter().copied());
+            if delta.generation == generation {
+                return Some(live);
+            }
+        }
+        None
+    }
+
+    /// Whether `generation` was recorded, so it can be rebuilt
+    pub fn contains(&self, generation: u64) -> bool {
+        generation == self.reference_generation || self.deltas.binary_search_by_key(&generation, |d| d.generation).is_ok()
+    }
+
+    /// Drop everything recorded after `generation` and return the board there, so recording
+    /// can carry on from it; `None` if that generation wasn't recorded
+    pub fn rewind(&mut self, generation: u64) -> Option<HashSet<Position>> {
+        let live = self.reconstruct(generation)?;
+        self.deltas.retain(|d| d.generation <= generation);
+        self.current = live.clone();
+        Some(live)
+    }
+
+    /// Grid the log was recorded on
+    pub fn grid(&self) -> Grid {
+        let mut grid = Grid::new(self.width, self.height);
+        grid.topology = self.topology;
+        grid
+    }
+
+    /// Encode as little-endian binary; coordinates fit in 16 bits because grids are at most `GRID_MAX` wide
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.extend_from_slice(&(self.width as u16).to_le_bytes());
+        out.extend_from_slice(&(self.height as u16).to_le_bytes());
+        write_topology(&mut out, self.topology);
+        out.extend_from_slice(&self.reference_generation.to_le_bytes());
+        write_cells(&mut out, &self.reference);
+        out.extend_from_slice(&(self.deltas.len() as u32).to_le_bytes());
+        for delta in &self.deltas {
+            out.extend_from_slice(&delta.generation.to_le_bytes());
+            write_cells(&mut out, &delta.born);
+            write_cells(&mut out, &delta.died);
+        }
+        out.extend_from_slice(&(self.bookmarks.len() as u32).to_le_bytes());
+        for bookmark in &self.bookmarks {
+            let label = &bookmark.label.as_bytes()[..bookmark.label.len().min(u16::MAX as usize)];
+            out.extend_from_slice(&(label.len() as u16).to_le_bytes());
+            out.extend_from_slice(label);
+            out.extend_from_slice(&bookmark.generation.to_le_bytes());
+            out.extend_from_slice(&(bookmark.width as u16).to_le_bytes());
+            out.extend_from_slice(&(bookmark.height as u16).to_le_bytes());
+            write_topology(&mut out, bookmark.topology);
+            out.extend_from_slice(&bookmark.seed.to_le_bytes());
+            write_cells(&mut out, &bookmark.live);
+        }
+        // In its text form; empty for none
+        let schedule = self.schedule.as_ref().map_or(String::new(), |s| s.to_string());
+        out.extend_from_slice(&(schedule.len() as u16).to_le_bytes());
+        out.extend_from_slice(schedule.as_bytes());
+        // Signed coordinates: a crop can leave a note past the board's edges
+        out.extend_from_slice(&(self.annotations.len() as u32).to_le_bytes());
+        for note in &self.annotations {
+            out.extend_from_slice(&note.at.x().to_le_bytes());
+            out.extend_from_slice(&note.at.y().to_le_bytes());
+            out.push(NoteColor::ALL.iter().position(|&c| c == note.color).unwrap_or(0) as u8);
+            let text = &note.text.as_bytes()[..note.text.len().min(u16::MAX as usize)];
+            out.extend_from_slice(&(text.len() as u16).to_le_bytes());
+            out.extend_from_slice(text);
+        }
+        out
+    }
+
+    /// Decode the format written by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, GolError> {
+        let mut r = Reader { bytes, pos: 0 };
+        if r.take(4)? != MAGIC {
+            return Err(r.error_at(0, "not a delta log"));
+        }
+        let version = r.take(1)?[0];
+        if !(1..=VERSION).contains(&version) {
+            return Err(r.error_at(4, format!("unsupported delta log version {}", version)));
+        }
+        let width = r.u16()? as i32;
+        let height = r.u16()? as i32;
+        let topology = r.topology(version)?;
+        let reference_generation = r.u64()?;
+        let reference = r.cells(width, height)?;
+        let count = r.u32()?;
+        let mut deltas = Vec::new();
+        let mut previous = reference_generation;
+        for _ in 0..count {
+            let generation = r.u64()?;
+            if generation <= previous {
+                return Err(r.error_at(r.pos - 8, format!("delta for generation {} is out of order", generation)));
+            }
+            previous = generation;
+            let born = r.cells(width, height)?;
+            let died = r.cells(width, height)?;
+            deltas.push(Delta { generation, born, died });
+        }
+        let mut bookmarks = Vec::new();
+        for _ in 0..if version >= 2 { r.u32()? } else { 0 } {
+            let length = r.u16()? as usize;
+            let label = String::from_utf8_lossy(r.take(length)?).into_owned();
+            let generation = r.u64()?;
+            let (width, height) = (r.u16()? as i32, r.u16()? as i32);
+            let topology = r.topology(version)?;
+            let seed = r.u64()?;
+            let live = r.cells(width, height)?;
+            bookmarks.push(Bookmark { label, generation, width, height, topology, seed, live });
+        }
+        let mut schedule = None;
+        if version >= 3 {
+            let length = r.u16()? as usize;
+            let start = r.pos;
+            let text = std::str::from_utf8(r.take(length)?).map_err(|_| r.error_at(start, "the speed schedule isn't text"))?;
+            if !text.is_empty() {
+                schedule = Some(SpeedSchedule::parse(text).map_err(|e| r.error_at(start, format!("bad speed schedule: {}", e)))?);
+            }
+        }
+        let mut annotations = Vec::new();
+        for _ in 0..if version >= 6 { r.u32()? } else { 0 } {
+            let at = Position::new(r.u32()? as i32, r.u32()? as i32);
+            let byte = r.take(1)?[0];
+            let color = *NoteColor::ALL.get(byte as usize).ok_or_else(|| r.error_at(r.pos - 1, format!("unknown note color {}", byte)))?;
+            let length = r.u16()? as usize;
+            let text = String::from_utf8_lossy(r.take(length)?).into_owned();
+            annotations.push(Annotation { at, text, color });
+        }
+        if r.pos != bytes.len() {
+            return Err(r.error_at(r.pos, "trailing data at the end of the log"));
+        }
+        let mut log = Self { width, height, topology, reference_generation, reference, deltas, bookmarks, schedule, annotations, current: HashSet::new() };
+        log.current = log.reconstruct(log.last_generation()).unwrap_or_default();
+        Ok(log)
+    }
+}
+
+/// The edge byte, then a torus's shift as two `i32`s, zero for the other topologies
+fn write_topology(out: &mut Vec<u8>, topology: Topology) {
+    let (dx_on_wrap_y, dy_on_wrap_x) = topology.shift();
+    out.push(topology.to_byte());
+    out.extend_from_slice(&dx_on_wrap_y.to_le_bytes());
+    out.extend_from_slice(&dy_on_wrap_x.to_le_bytes());
+}
+
+fn write_cells(out: &mut Vec<u8>, cells: &[Position]) {
+    out.extend_from_slice(&(cells.len() as u32).to_le_bytes());
+    for p in cells {
+        out.extend_from_slice(&(p.x() as u16).to_le_bytes());
+        out.extend_from_slice(&(p.y() as u16).to_le_bytes());
+    }
+}
+
+/// Cursor over an encoded log that reports truncation instead of panicking
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn error_at(&self, offset: usize, message: impl Into<String>) -> GolError {
+        GolError::parse("delta log", Location::Offset(offset), message)
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], GolError> {
+        let end = self.pos.checked_add(n).filter(|&e| e <= self.bytes.len())
+            .ok_or_else(|| self.error_at(self.bytes.len(), "the log is truncated"))?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], GolError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn u16(&mut self) -> Result<u16, GolError> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, GolError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, GolError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    /// The edge byte and, from version 4, the torus shift: before version 5 any nonzero byte
+    /// meant a torus. A shift stored with a topology that isn't a torus is ignored.
+    fn topology(&mut self, version: u8) -> Result<Topology, GolError> {
+        let byte = self.take(1)?[0];
+        let topology = if version < 5 {
+            Topology::from_wrap(byte != 0)
+        } else {
+            Topology::from_byte(byte).ok_or_else(|| self.error_at(self.pos - 1, format!("unknown edge topology {}", byte)))?
+        };
+        if version < 4 {
+            return Ok(topology);
+        }
+        let dx_on_wrap_y = self.u32()? as i32;
+        let dy_on_wrap_x = self.u32()? as i32;
+        Ok(if topology.is_torus() { Topology::torus_shifted(dx_on_wrap_y, dy_on_wrap_x) } else { topology })
+    }
+
+    fn cells(&mut self, width: i32, height: i32) -> Result<Vec<Position>, GolError> {
+        let count = self.u32()? as usize;
+        let mut cells = Vec::with_capacity(count.min(self.bytes.len() / 4));
+        for _ in 0..count {
+            let (x, y) = (self.u16()? as i32, self.u16()? as i32);
+            if x >= width || y >= height {
+                return Err(self.error_at(self.pos - 4, format!("cell ({}, {}) is outside the {}x{} grid", x, y, width, height)));
+            }
+            cells.push(Position::new(x, y));
+        }
+        Ok(cells)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::reference::random_soup;
+
+    /// A soup recorded for `generations`, and every board it went through
+    fn recorded(topology: Topology, generations: u64) -> (DeltaLog, Vec<HashSet<Position>>) {
+        let mut sim = Simulation::new(64, 48);
+        sim.grid.topology = topology;
+        sim.live = random_soup(&sim.grid, 0.35, 3);
+        let mut log = DeltaLog::new(&sim);
+        let mut boards = vec![sim.live.clone()];
+        for _ in 0..generations {
+            sim.next_generation();
+            assert!(log.record(&sim));
+            boards.push(sim.live.clone());
+        }
+        (log, boards)
+    }
+
+    fn parse_message(bytes: &[u8]) -> String {
+        match DeltaLog::from_bytes(bytes) {
+            Err(GolError::Parse { message, .. }) => message,
+            Err(other) => panic!("not a parse error: {}", other),
+            Ok(_) => panic!("the log was accepted"),
+        }
+    }
+
+    #[test]
+    fn replay_through_bytes_rebuilds_every_generation() {
+        for topology in [Topology::Bounded, Topology::Torus, Topology::CylinderX, Topology::torus_shifted(0, 3)] {
+            let (log, boards) = recorded(topology, 120);
+            let decoded = DeltaLog::from_bytes(&log.to_bytes()).unwrap();
+            assert_eq!((decoded.topology, decoded.last_generation()), (topology, 120));
+            for (generation, board) in boards.iter().enumerate() {
+                assert_eq!(decoded.reconstruct(generation as u64).as_ref(), Some(board), "generation {} on {}", generation, topology);
+            }
+            assert_eq!(decoded.reconstruct(121), None);
+        }
+    }
+
+    #[test]
+    fn deltas_are_sorted_births_and_deaths() {
+        let (log, boards) = recorded(Topology::Torus, 5);
+        for (delta, pair) in log.deltas.iter().zip(boards.windows(2)) {
+            let born: HashSet<Position> = pair[1].difference(&pair[0]).copied().collect();
+            assert_eq!(delta.born.iter().copied().collect::<HashSet<_>>(), born);
+            assert!(delta.born.windows(2).all(|w| (w[0].y(), w[0].x()) < (w[1].y(), w[1].x())));
+            assert!(delta.died.windows(2).all(|w| (w[0].y(), w[0].x()) < (w[1].y(), w[1].x())));
+        }
+    }
+
+    #[test]
+    fn recording_stops_when_the_board_goes_back_or_changes_size() {
+        let mut sim = Simulation::new(16, 16);
+        sim.live = random_soup(&sim.grid, 0.4, 1);
+        let mut log = DeltaLog::new(&sim);
+        sim.next_generation();
+        assert!(log.record(&sim));
+        assert!(log.record(&sim), "recording the same generation twice is harmless");
+        sim.generation = 0;
+        assert!(!log.record(&sim));
+        let mut wider = Simulation::new(20, 16);
+        wider.generation = 5;
+        assert!(!log.record(&wider));
+    }
+
+    #[test]
+    fn truncated_logs_are_refused() {
+        let (log, _) = recorded(Topology::Torus, 3);
+        let bytes = log.to_bytes();
+        for end in 0..bytes.len() {
+            assert!(DeltaLog::from_bytes(&bytes[..end]).is_err(), "{} of {} bytes were accepted", end, bytes.len());
+        }
+        assert_eq!(parse_message(&bytes[..bytes.len() - 1]), "the log is truncated");
+        // The error points at the end of what was there
+        for cut in [bytes.len() - 1, bytes.len() / 2, 7] {
+            assert!(matches!(DeltaLog::from_bytes(&bytes[..cut]), Err(GolError::Parse { format: "delta log", at: Location::Offset(at), .. }) if at == cut));
+        }
+    }
+
+    #[test]
+    fn malformed_logs_are_refused() {
+        let (log, _) = recorded(Topology::Bounded, 3);
+        let bytes = log.to_bytes();
+        let mut bad = bytes.clone();
+        bad[0] = b'X';
+        assert_eq!(parse_message(&bad), "not a delta log");
+        assert!(matches!(DeltaLog::from_bytes(b"GIF89a"), Err(GolError::Parse { at: Location::Offset(0), .. })));
+        bad = bytes.clone();
+        bad[4] = VERSION + 1;
+        assert_eq!(parse_message(&bad), format!("unsupported delta log version {}", VERSION + 1));
+        bad = bytes.clone();
+        bad.push(0);
+        assert_eq!(parse_message(&bad), "trailing data at the end of the log");
+
+        let mut out_of_order = log.clone();
+        out_of_order.deltas[2].generation = 1;
+        assert_eq!(parse_message(&out_of_order.to_bytes()), "delta for generation 1 is out of order");
+        let mut outside = log.clone();
+        outside.reference.push(Position::new(64, 0));
+        assert_eq!(parse_message(&outside.to_bytes()), "cell (64, 0) is outside the 64x48 grid");
+    }
+
+    #[test]
+    fn dropping_and_rewinding_keep_later_boards() {
+        let (mut log, boards) = recorded(Topology::Torus, 10);
+        log.drop_oldest(4);
+        assert_eq!(log.reference_generation, 4);
+        assert!(!log.contains(3));
+        assert_eq!(log.reconstruct(10).as_ref(), Some(&boards[10]));
+        assert_eq!(log.rewind(6).as_ref(), Some(&boards[6]));
+        assert_eq!(log.last_generation(), 6);
+        assert_eq!(log.reconstruct(6).as_ref(), Some(&boards[6]));
+    }
+}
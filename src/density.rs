@@ -0,0 +1,163 @@
+//! Drawing a board zoomed out past one pixel per cell. Live cells are counted into square
+//! buckets of `shrink` x `shrink` cells, one screen pixel each, and the picture is painted in
+//! the theme's cell color with every pixel as opaque as its bucket is full, so it blends over
+//! the background and backdrop the way the cells would. The counts follow the board through
+//! each step's births and deaths when the simulation records them; anything else (several
+//! generations in one frame, an edit, a new board) recounts the whole board.
+
+use macroquad::color::Color;
+use macroquad::texture::{FilterMode, Image, Texture2D};
+
+use crate::config::{DENSITY_FAINTEST, DENSITY_RECHECK_FRAMES};
+use crate::grid::{board_hash, cell_hash, Position};
+use crate::simulation::Simulation;
+
+/// Live cells per bucket of a zoomed-out board, and the picture they make
+pub struct DensityMap {
+    pub shrink: i32, // Cells per pixel along each axis
+    pub cols: i32,
+    pub rows: i32,
+    counts: Vec<u16>,
+    board: (i32, i32),  // Grid size the buckets cover
+    generation: u64,    // Generation the counts are for
+    total: usize,       // Live cells counted
+    hash: u64,          // `board_hash` of the counted cells, kept up with each step's changes
+    color: Color,       // Cell color the picture is painted in
+    idle: u32,          // Frames since the board last changed or was hashed
+    image: Image,
+    texture: Option<Texture2D>, // Uploaded when first drawn to the window
+    dirty: bool,                // The picture changed since the texture was uploaded
+}
+
+impl DensityMap {
+    pub fn new(shrink: i32) -> Self {
+        Self {
+            shrink: shrink.max(1),
+            cols: 0,
+            rows: 0,
+            counts: Vec::new(),
+            board: (0, 0),
+            generation: 0,
+            total: 0,
+            hash: 0,
+            color: Color::new(0.0, 0.0, 0.0, 0.0),
+            idle: 0,
+            image: Image::empty(),
+            texture: None,
+            dirty: true,
+        }
+    }
+
+    /// Bring the counts and picture up to `sim`, painting in `color`. One step since the last
+    /// call with its births and deaths recorded costs only the cells that changed; a board
+    /// still at the same generation is trusted while its cell count holds, and hashed every
+    /// `DENSITY_RECHECK_FRAMES` frames to catch edits that kept the count.
+    pub fn sync(&mut self, sim: &Simulation, color: Color) {
+        let board = (sim.grid.width, sim.grid.height);
+        if board != self.board || color != self.color {
+            self.recount(sim, color);
+            return;
+        }
+        // Following a step costs about twice as much per changed cell as a recount does per live
+        // cell, so a step that changed more than half as many cells as are alive is recounted
+        let step = sim.diff.as_ref().filter(|d| {
+            d.generation == sim.generation && sim.generation == self.generation + 1 && 2 * (d.born.len() + d.died.len()) <= sim.live.len()
+        });
+        if let Some(d) = step {
+            let mut touched = Vec::with_capacity(d.born.len() + d.died.len());
+            for (&p, by) in d.died.iter().map(|p| (p, -1)).chain(d.born.iter().map(|p| (p, 1))) {
+                self.hash = if by > 0 { self.hash.wrapping_add(cell_hash(p)) } else { self.hash.wrapping_sub(cell_hash(p)) };
+                if let Some(i) = self.bucket(p) {
+                    self.counts[i] = (self.counts[i] as i32 + by).max(0) as u16;
+                    touched.push(i);
+                }
+            }
+            for i in touched {
+                self.paint(i);
+            }
+            self.dirty = true;
+            self.total = (self.total + d.born.len()).saturating_sub(d.died.len());
+            self.generation = sim.generation;
+            self.idle = 0;
+            if self.total != sim.live.len() {
+                self.recount(sim, color);
+            }
+        } else if sim.generation != self.generation || sim.live.len() != self.total {
+            self.recount(sim, color);
+        } else {
+            self.idle += 1;
+            if self.idle >= DENSITY_RECHECK_FRAMES {
+                self.idle = 0;
+                if board_hash(&sim.live) != self.hash {
+                    self.recount(sim, color);
+                }
+            }
+        }
+    }
+
+    /// Count every live cell afresh and repaint the whole picture
+    fn recount(&mut self, sim: &Simulation, color: Color) {
+        let s = self.shrink;
+        self.board = (sim.grid.width, sim.grid.height);
+        (self.cols, self.rows) = ((self.board.0 + s - 1) / s, (self.board.1 + s - 1) / s);
+        self.counts = vec![0; (self.cols * self.rows) as usize];
+        for p in &sim.live {
+            if let Some(i) = self.bucket(*p) {
+                self.counts[i] = self.counts[i].saturating_add(1);
+            }
+        }
+        (self.generation, self.total, self.hash, self.color, self.idle) = (sim.generation, sim.live.len(), board_hash(&sim.live), color, 0);
+        if (self.image.width(), self.image.height()) != (self.cols as usize, self.rows as usize) {
+            self.image = Image::gen_image_color(self.cols as u16, self.rows as u16, Color::new(0.0, 0.0, 0.0, 0.0));
+            self.texture = None;
+        }
+        for i in 0..self.counts.len() {
+            self.paint(i);
+        }
+        self.dirty = true;
+    }
+
+    fn bucket(&self, p: Position) -> Option<usize> {
+        let (x, y) = (p.x() / self.shrink, p.y() / self.shrink);
+        (p.x() >= 0 && p.y() >= 0 && x < self.cols && y < self.rows).then_some((y * self.cols + x) as usize)
+    }
+
+    /// How opaque a bucket with `count` live cells is drawn: its share of the bucket's cells,
+    /// but never fainter than `DENSITY_FAINTEST` while any cell is alive there
+    pub fn opacity(&self, count: u16) -> f32 {
+        match count {
+            0 => 0.0,
+            n => (n as f32 / (self.shrink * self.shrink) as f32).clamp(DENSITY_FAINTEST, 1.0),
+        }
+    }
+
+    fn paint(&mut self, i: usize) {
+        let mut color = self.color;
+        color.a *= self.opacity(self.counts[i]);
+        let (x, y) = (i as i32 % self.cols, i as i32 / self.cols);
+        self.image.set_pixel(x as u32, y as u32, color);
+    }
+
+    /// Live cells counted in the bucket at column `x`, row `y`
+    pub fn count(&self, x: i32, y: i32) -> u16 {
+        if (0..self.cols).contains(&x) && (0..self.rows).contains(&y) { self.counts[(y * self.cols + x) as usize] } else { 0 }
+    }
+
+    /// The picture, one pixel per bucket
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    /// The picture as a texture, uploaded again only when it has changed
+    pub fn texture(&mut self) -> &Texture2D {
+        if self.dirty && let Some(texture) = &self.texture {
+            texture.update(&self.image);
+        }
+        self.dirty = false;
+        self.texture.get_or_insert_with(|| {
+            let texture = Texture2D::from_image(&self.image);
+            texture.set_filter(FilterMode::Nearest);
+            texture
+        })
+    }
+}
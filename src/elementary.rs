@@ -0,0 +1,172 @@
+//! One-dimensional elementary cellular automata, drawn as a space-time diagram:
+//! each generation is the next row down, and the strip scrolls once the screen is full.
+
+use crate::grid::Position;
+use crate::rng::Rng;
+use crate::simulation::Simulation;
+
+/// Wolfram rule number: bit `n` is the next state of a cell whose left, own and right
+/// states read `n` as a 3-bit binary number
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ElementaryRule(pub u8);
+
+impl ElementaryRule {
+    /// Next state of one cell from its neighborhood
+    pub fn apply(self, left: bool, center: bool, right: bool) -> bool {
+        let n = (left as u8) << 2 | (center as u8) << 1 | right as u8;
+        (self.0 >> n) & 1 == 1
+    }
+
+    /// Next generation of a row. The ends wrap when `wrap` is set; otherwise cells past them are dead.
+    pub fn step(self, row: &[bool], wrap: bool) -> Vec<bool> {
+        let w = row.len() as i32;
+        let at = |i: i32| {
+            if (0..w).contains(&i) {
+                row[i as usize]
+            } else {
+                wrap && row[i.rem_euclid(w) as usize]
+            }
+        };
+        (0..w).map(|i| self.apply(at(i - 1), at(i), at(i + 1))).collect()
+    }
+}
+
+/// The rows drawn so far. They are kept as live cells of a `Simulation` whose y is the screen
+/// row, so the 2D renderer draws the strip unchanged.
+pub struct Strip {
+    pub rule: ElementaryRule,
+    pub sim: Simulation, // Grid width is the row length, height the rows on screen
+    row: Vec<bool>,      // Newest generation
+    rows: i32,           // Rows on screen so far, up to the grid height
+}
+
+impl Strip {
+    /// A strip of `width` cells showing `height` generations, started from one live center cell
+    pub fn new(width: i32, height: i32, rule: ElementaryRule) -> Self {
+        let mut strip = Self { rule, sim: Simulation::new(width, height), row: Vec::new(), rows: 0 };
+        strip.seed_center();
+        strip
+    }
+
+    /// Newest generation
+    pub fn row(&self) -> &[bool] {
+        &self.row
+    }
+
+    /// Restart from a single live cell in the middle of the row
+    pub fn seed_center(&mut self) {
+        let width = self.sim.grid.width;
+        self.restart((0..width).map(|x| x == width / 2).collect());
+    }
+
+    /// Restart from a random row, reproducibly from `seed`
+    pub fn seed_random(&mut self, seed: u64, density: f32) {
+        let mut rng = Rng::new(seed);
+        self.restart((0..self.sim.grid.width).map(|_| rng.next_f32() < density).collect());
+    }
+
+    /// Clear the screen and draw `row` as generation 0 on the top line
+    pub fn restart(&mut self, row: Vec<bool>) {
+        self.sim.clear();
+        self.row = row;
+        self.rows = 0;
+        self.push_row();
+    }
+
+    /// Compute the next generation and draw it below the others, scrolling up once the screen is full
+    pub fn step(&mut self) {
+        self.row = self.rule.step(&self.row, self.sim.grid.topology.wraps_x());
+        self.sim.generation += 1;
+        if self.rows == self.sim.grid.height {
+            self.sim.live = self.sim.live.iter()
+                .filter(|p| p.y() > 0)
+                .map(|p| Position::new(p.x(), p.y() - 1))
+                .collect();
+            self.rows -= 1;
+        }
+        self.push_row();
+    }
+
+    /// Draw the newest generation on the next free screen row
+    fn push_row(&mut self) {
+        let y = self.rows;
+        let cells = self.row.iter().enumerate().filter(|(_, alive)| **alive);
+        self.sim.live.extend(cells.map(|(x, _)| Position::new(x as i32, y)));
+        self.rows += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Topology;
+    use crate::grid::reference::{from_ascii, to_ascii};
+
+    /// Run `strip` from the top row of `expected` and compare what it draws
+    fn draws(mut strip: Strip, steps: u32, expected: &str) {
+        let topology = strip.sim.grid.topology;
+        let (grid, live) = from_ascii(expected, topology);
+        let first = (0..grid.width).map(|x| live.contains(&Position::new(x, 0))).collect();
+        strip.restart(first);
+        for _ in 0..steps {
+            strip.step();
+        }
+        assert_eq!(to_ascii(&strip.sim.grid, &strip.sim.live), to_ascii(&grid, &live));
+    }
+
+    #[test]
+    fn rule_number_bits_are_the_neighborhoods() {
+        let rule90 = ElementaryRule(90);
+        for n in 0..8u8 {
+            let (l, c, r) = (n & 4 != 0, n & 2 != 0, n & 1 != 0);
+            assert_eq!(rule90.apply(l, c, r), l ^ r, "neighborhood {:03b}", n);
+            assert!(ElementaryRule(1 << n).apply(l, c, r));
+            assert!(!ElementaryRule(!(1 << n)).apply(l, c, r));
+        }
+    }
+
+    #[test]
+    fn rule_30_and_110_from_one_cell() {
+        draws(Strip::new(15, 8, ElementaryRule(30)), 7, "\
+            .......#......./......###....../.....##..#...../....##.####..../\
+            ...##..#...#.../..##.####.###../.##..#....#..#./##.####..######");
+        draws(Strip::new(15, 8, ElementaryRule(110)), 7, "\
+            ..............#/.............##/............###/...........##.#/\
+            ..........#####/.........##...#/........###..##/.......##.#.###");
+    }
+
+    #[test]
+    fn ends_wrap_on_a_wrapping_strip() {
+        let mut strip = Strip::new(8, 5, ElementaryRule(30));
+        strip.sim.grid.topology = Topology::Torus;
+        draws(strip, 4, "..#...../.###..../##..#.../#.####.#/..#....#");
+        assert_eq!(ElementaryRule(30).step(&[true, false, false], false), [true, true, false]);
+        assert_eq!(ElementaryRule(30).step(&[true, false, false], true), [true, true, true]);
+    }
+
+    #[test]
+    fn strip_scrolls_once_the_screen_is_full() {
+        let mut strip = Strip::new(15, 3, ElementaryRule(30));
+        for _ in 0..5 {
+            strip.step();
+        }
+        let (grid, live) = from_ascii("....##.####..../...##..#...#.../..##.####.###..", Topology::Bounded);
+        assert_eq!(to_ascii(&strip.sim.grid, &strip.sim.live), to_ascii(&grid, &live));
+        assert_eq!(strip.sim.generation, 5);
+        assert_eq!(strip.row().len(), 15);
+    }
+
+    #[test]
+    fn random_rows_follow_the_seed() {
+        let mut a = Strip::new(64, 4, ElementaryRule(30));
+        let mut b = Strip::new(64, 4, ElementaryRule(30));
+        a.seed_random(9, 0.5);
+        b.seed_random(9, 0.5);
+        assert_eq!(a.row(), b.row());
+        b.seed_random(10, 0.5);
+        assert_ne!(a.row(), b.row());
+        a.seed_center();
+        assert_eq!(a.row().iter().filter(|alive| **alive).count(), 1);
+        assert!(a.row()[32]);
+    }
+}
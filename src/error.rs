@@ -0,0 +1,259 @@
+//! The error type for everything read from or written to outside the program: pattern files,
+//! saves, settings, exports, downloads and the rulestrings and sizes typed into them. Each
+//! error reads as a sentence fit for a toast.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Where in its input a parse error was found
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Location {
+    Line(usize),                           // 1-based
+    Column { line: usize, column: usize }, // Both 1-based, the column counted in characters
+    Offset(usize),                         // Byte offset into binary data
+    Whole,                                 // The input as a whole, e.g. a size that doesn't fit
+}
+
+/// Why reading, writing or checking something from outside the program failed
+#[derive(Debug)]
+pub enum GolError {
+    /// Input that isn't in the format it should be
+    Parse { format: &'static str, path: Option<PathBuf>, at: Location, message: String },
+    /// A file that couldn't be read or written
+    Io { path: PathBuf, source: io::Error },
+    /// Well-formed but unacceptable, like a B0 rule or a zero-size grid. `what` names the kind
+    /// of value and `message` says what is wrong with it.
+    Invalid { what: &'static str, message: String },
+    /// More live cells than a pattern may have
+    TooLarge { limit: usize, path: Option<PathBuf> },
+    /// Not available in this build or on this platform, or a format the loader doesn't know
+    Unsupported(String),
+    /// A download that failed before there was anything to parse
+    Network(String),
+}
+
+impl GolError {
+    pub fn parse(format: &'static str, at: Location, message: impl Into<String>) -> Self {
+        GolError::Parse { format, path: None, at, message: message.into() }
+    }
+
+    pub fn invalid(what: &'static str, message: impl Into<String>) -> Self {
+        GolError::Invalid { what, message: message.into() }
+    }
+
+    pub fn io(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        GolError::Io { path: path.into(), source }
+    }
+
+    /// Name the file a parse or size error came from, unless it already names one
+    pub fn in_file(mut self, file: &Path) -> Self {
+        if let GolError::Parse { path: path @ None, .. } | GolError::TooLarge { path: path @ None, .. } = &mut self {
+            *path = Some(file.to_path_buf());
+        }
+        self
+    }
+}
+
+impl fmt::Display for GolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GolError::Parse { format, path, at, message } => {
+                if let Some(path) = path {
+                    write!(f, "{}: ", path.display())?;
+                }
+                match at {
+                    Location::Line(line) => write!(f, "{} line {}: {}", format, line, message),
+                    Location::Column { line, column } => write!(f, "{} line {}, column {}: {}", format, line, column, message),
+                    Location::Offset(offset) => write!(f, "{} at byte {}: {}", format, offset, message),
+                    Location::Whole => write!(f, "{}: {}", format, message),
+                }
+            }
+            GolError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            GolError::Invalid { message, .. } => write!(f, "{}", message),
+            GolError::TooLarge { limit, path } => {
+                if let Some(path) = path {
+                    write!(f, "{}: ", path.display())?;
+                }
+                write!(f, "pattern has more than {} live cells", limit)
+            }
+            GolError::Unsupported(message) | GolError::Network(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for GolError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GolError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// For code that reports errors as plain messages, such as console commands
+impl From<GolError> for String {
+    fn from(err: GolError) -> Self {
+        err.to_string()
+    }
+}
+
+/// Read a text file, naming it in any error
+pub fn read_to_string(path: &Path) -> Result<String, GolError> {
+    std::fs::read_to_string(path).map_err(|e| GolError::io(path, e))
+}
+
+/// Read a file's bytes, naming it in any error
+pub fn read(path: &Path) -> Result<Vec<u8>, GolError> {
+    std::fs::read(path).map_err(|e| GolError::io(path, e))
+}
+
+/// Write a file all at once, creating its directory first, naming it in any error. The contents
+/// go to a temporary file beside it, are synced to disk, then renamed over it, so a crash or a
+/// full disk mid-write leaves the old file whole instead of a truncated one.
+pub fn write(path: &Path, contents: impl AsRef<[u8]>) -> Result<(), GolError> {
+    let dir = path.parent().filter(|d| !d.as_os_str().is_empty());
+    if let Some(dir) = dir {
+        std::fs::create_dir_all(dir).map_err(|e| GolError::io(dir, e))?;
+    }
+    let temporary = sibling(path, &format!(".tmp{}", std::process::id()));
+    let written = std::fs::File::create(&temporary)
+        .and_then(|mut file| {
+            file.write_all(contents.as_ref())?;
+            file.sync_all()
+        })
+        .and_then(|()| std::fs::rename(&temporary, path));
+    if let Err(err) = written {
+        let _ = std::fs::remove_file(&temporary);
+        return Err(GolError::io(path, err));
+    }
+    // The rename is only durable once the directory is synced; where that isn't possible the
+    // file is still whole, just possibly the old one after a power cut
+    #[cfg(unix)]
+    if let Ok(dir) = std::fs::File::open(dir.unwrap_or(Path::new("."))) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+/// `path` with `suffix` added to its file name, e.g. `settings.txt.bak`
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Where a kept file's backup lives: beside it, with `.bak` added to its name
+pub fn backup_path(path: &Path) -> PathBuf {
+    sibling(path, ".bak")
+}
+
+/// Write a file that is read back on later runs, such as the settings, as `write` does, then
+/// copy it to its backup so a later read has something to fall back on
+pub fn write_kept(path: &Path, contents: impl AsRef<[u8]>) -> Result<(), GolError> {
+    write(path, contents.as_ref())?;
+    write(&backup_path(path), contents)
+}
+
+/// A file read by `read_kept`, and a notice when its backup had to stand in for it
+#[derive(Debug)]
+pub struct Kept<T> {
+    pub value: T,
+    pub notice: Option<String>,
+}
+
+impl<T> Kept<T> {
+    /// A value that didn't need recovering, such as the defaults for a file not written yet
+    pub fn fresh(value: T) -> Self {
+        Self { value, notice: None }
+    }
+}
+
+/// Read a file written by `write_kept`, parsed by `parse`. An empty file, one that isn't text,
+/// or one `parse` rejects is damaged, and its backup is read instead with a notice saying so.
+/// A missing file is `Ok(None)`. When both copies are damaged, the error is the file's own.
+pub fn read_kept<T>(path: &Path, parse: impl Fn(&str) -> Result<T, GolError>) -> Result<Option<Kept<T>>, GolError> {
+    let read_one = |path: &Path| -> Result<T, GolError> {
+        let bytes = read(path)?;
+        let text = std::str::from_utf8(&bytes).map_err(|e| GolError::parse("text", Location::Offset(e.valid_up_to()), "the file isn't text"))?;
+        if text.trim().is_empty() || text.contains('\0') {
+            return Err(GolError::parse("text", Location::Whole, "the file is empty or has been overwritten with zeros"));
+        }
+        parse(text)
+    };
+    if !path.exists() {
+        return Ok(None);
+    }
+    let damage = match read_one(path) {
+        Ok(value) => return Ok(Some(Kept::fresh(value))),
+        Err(err) => err,
+    };
+    let backup = backup_path(path);
+    match read_one(&backup) {
+        Ok(value) => {
+            let name = path.file_name().map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into_owned());
+            let notice = format!("{} was damaged ({}), so the copy from its last save was loaded", name, damage);
+            Ok(Some(Kept { value, notice: Some(notice) }))
+        }
+        Err(_) => Err(damage.in_file(path)),
+    }
+}
+
+/// Check text made of `key = value` lines, blank lines and `#` comments, as the settings and
+/// records files are. A line with no `=` or no key is what a cut-off or scrambled file leaves.
+pub fn check_key_values(format: &'static str, text: &str) -> Result<(), GolError> {
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') { continue; }
+        match line.split_once('=') {
+            Some((key, _)) if !key.trim().is_empty() && key.trim().chars().all(|c| c.is_ascii_alphanumeric() || c == '_') => {}
+            _ => return Err(GolError::parse(format, Location::Line(i + 1), "expected 'key = value'")),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_say_where_the_input_went_wrong() {
+        let cases = [
+            (Location::Line(3), "RLE line 3: bad run"),
+            (Location::Column { line: 4, column: 2 }, "RLE line 4, column 2: bad run"),
+            (Location::Offset(17), "RLE at byte 17: bad run"),
+            (Location::Whole, "RLE: bad run"),
+        ];
+        for (at, expected) in cases {
+            assert_eq!(GolError::parse("RLE", at, "bad run").to_string(), expected);
+        }
+        let named = GolError::parse("RLE", Location::Line(1), "bad run").in_file(Path::new("a.rle"));
+        assert_eq!(named.to_string(), "a.rle: RLE line 1: bad run");
+        assert_eq!(named.in_file(Path::new("b.rle")).to_string(), "a.rle: RLE line 1: bad run", "the first file named is kept");
+        let large = GolError::TooLarge { limit: 10, path: None }.in_file(Path::new("big.rle"));
+        assert_eq!(large.to_string(), "big.rle: pattern has more than 10 live cells");
+        assert_eq!(String::from(GolError::invalid("rule", "no B0")), "no B0");
+    }
+
+    #[test]
+    fn io_errors_name_the_file_and_keep_their_source() {
+        let missing = std::env::temp_dir().join(format!("conways-game-of-life-missing-{}.txt", std::process::id()));
+        let err = read_to_string(&missing).unwrap_err();
+        assert!(matches!(&err, GolError::Io { path, .. } if *path == missing));
+        assert!(err.to_string().starts_with(&missing.display().to_string()));
+        assert!(std::error::Error::source(&err).is_some());
+        assert!(std::error::Error::source(&GolError::Network("offline".into())).is_none());
+    }
+
+    #[test]
+    fn key_value_files_are_checked_line_by_line() {
+        assert!(check_key_values("settings", "# comment\n\nspeed = 10\ntheme_name = Dark\n").is_ok());
+        for (text, line) in [("speed = 10\nspeed\n", 2), ("= 3\n", 1), ("a = 1\n\nbad key = 2\n", 3)] {
+            match check_key_values("settings", text) {
+                Err(GolError::Parse { format: "settings", at: Location::Line(at), .. }) => assert_eq!(at, line, "{:?}", text),
+                other => panic!("{:?} read as {:?}", text, other),
+            }
+        }
+    }
+}
@@ -0,0 +1,230 @@
+//! Initial-conditions sweeps: one parameter of a random soup is varied over a list of values,
+//! each value is run from the same replicate seeds, and every run reports how it ended.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::mpsc;
+use std::thread;
+
+use crate::analysis::{self, RunStats};
+use crate::config::EXPERIMENT_PERIOD_MAX;
+use crate::grid::{board_hash, Topology};
+use crate::patterns::validate_density;
+use crate::rule::Rule;
+use crate::simulation::Simulation;
+
+/// The soup setting an experiment sweeps
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Parameter {
+    Density, // Fill density of the soup
+    Size,    // Width and height of a square grid
+    Rule,    // Rulestring, listed with `--values`
+}
+
+impl Parameter {
+    pub fn parse(name: &str) -> Result<Parameter, String> {
+        match name {
+            "density" => Ok(Parameter::Density),
+            "size" => Ok(Parameter::Size),
+            "rule" => Ok(Parameter::Rule),
+            _ => Err(format!("unknown experiment parameter '{}' (expected density, size or rule)", name)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Parameter::Density => "density",
+            Parameter::Size => "size",
+            Parameter::Rule => "rule",
+        }
+    }
+
+    /// One value of this parameter from its text
+    pub fn value(&self, text: &str) -> Result<Value, String> {
+        let text = text.trim();
+        let invalid = || format!("invalid {} '{}'", self.name(), text);
+        match self {
+            Parameter::Density => Ok(Value::Density(validate_density(text.parse().map_err(|_| invalid())?)?)),
+            Parameter::Size => match text.parse() {
+                Ok(n @ 1..) => Ok(Value::Size(n)),
+                _ => Err(invalid()),
+            },
+            Parameter::Rule => Ok(Value::Rule(Rule::parse(text)?.runnable()?)),
+        }
+    }
+
+    /// Values from `from` to `to` inclusive, `step` apart. Rules have no order to step through.
+    pub fn range(&self, from: f64, to: f64, step: f64) -> Result<Vec<Value>, String> {
+        if *self == Parameter::Rule {
+            return Err("rules can't be stepped through; list them with --values".to_string());
+        }
+        if step.is_nan() || step <= 0.0 || to < from {
+            return Err(format!("no values from {} to {} in steps of {}", from, to, step));
+        }
+        // Counted rather than accumulated, so 0.05 + 11 * 0.05 still lands on 0.6
+        let count = ((to - from) / step + 1e-9).floor() as usize + 1;
+        (0..count).map(|i| self.value(&format!("{}", from + i as f64 * step))).collect()
+    }
+}
+
+/// One setting of the swept parameter
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Value {
+    Density(f32),
+    Size(i32),
+    Rule(Rule),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // Rounded so a density built from 0.1 + 0.2 prints as 0.3
+            Value::Density(d) => write!(f, "{}", (d * 1e4).round() / 1e4),
+            Value::Size(n) => write!(f, "{}", n),
+            Value::Rule(rule) => write!(f, "{}", rule),
+        }
+    }
+}
+
+/// A sweep: every value run from seeds `seed`, `seed + 1`, ... on the base board
+#[derive(Clone, Debug)]
+pub struct Experiment {
+    pub values: Vec<Value>,
+    pub replicates: u32,
+    pub generations: u64,
+    pub seed: u64,
+    pub width: i32, // Base board, for the parameters not swept
+    pub height: i32,
+    pub density: f32,
+    pub rule: Rule,
+    pub topology: Topology,
+}
+
+/// How one run ended
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Outcome {
+    pub value: Value,
+    pub seed: u64,
+    pub final_population: usize,
+    pub peak: usize,
+    pub lifespan: Option<u64>, // Generation the board died out or started repeating, if it did
+    pub period: Option<u32>,   // Repeat period from then on; 1 for a still or empty board
+}
+
+impl Outcome {
+    pub const CSV_HEADER: &'static str = "value,seed,final_population,peak_population,lifespan,period";
+
+    /// One CSV row; a board that never settled leaves lifespan and period empty
+    pub fn csv_row(&self) -> String {
+        let value = self.value.to_string();
+        // Rulestrings have no commas, but quote anyway in case one ever does
+        let value = if value.contains(',') { format!("\"{}\"", value) } else { value };
+        format!(
+            "{},{},{},{},{},{}",
+            value, self.seed, self.final_population, self.peak,
+            self.lifespan.map_or(String::new(), |g| g.to_string()),
+            self.period.map_or(String::new(), |p| p.to_string()),
+        )
+    }
+}
+
+impl Experiment {
+    /// Every run, values in order and replicates within each
+    fn jobs(&self) -> Vec<(Value, u64)> {
+        self.values.iter()
+            .flat_map(|&value| (0..self.replicates as u64).map(move |r| (value, self.seed.wrapping_add(r))))
+            .collect()
+    }
+
+    /// Run one soup until it settles into a cycle of at most EXPERIMENT_PERIOD_MAX generations,
+    /// or for `generations`. Each board is hashed and matched against the recent ones; a match
+    /// is confirmed by stepping, so a hash collision can't end a run early.
+    pub fn run_one(&self, value: Value, seed: u64) -> Outcome {
+        let (mut width, mut height, mut density, mut rule) = (self.width, self.height, self.density, self.rule);
+        match value {
+            Value::Density(d) => density = d,
+            Value::Size(n) => (width, height) = (n, n),
+            Value::Rule(r) => rule = r,
+        }
+        let mut sim = Simulation::new(width, height);
+        sim.grid.topology = self.topology;
+        sim.grid.rule = rule;
+        sim.seed = seed;
+        sim.random_fill(density);
+        let mut stats = RunStats::default();
+        stats.observe(0, sim.live.len());
+        let mut seen: HashMap<u64, u64> = HashMap::new(); // Board hash to the generation it was last seen
+        let mut settled = None;
+        loop {
+            let hash = board_hash(&sim.live);
+            if let Some(&first) = seen.get(&hash) {
+                let period = (sim.generation - first) as u32;
+                if analysis::period(&sim.grid, &sim.live, period) == Some(period) {
+                    settled = Some((first, period));
+                    break;
+                }
+            }
+            seen.insert(hash, sim.generation);
+            seen.retain(|_, g| *g + EXPERIMENT_PERIOD_MAX as u64 >= sim.generation);
+            if sim.generation >= self.generations {
+                break;
+            }
+            sim.next_generation();
+            stats.observe(sim.generation, sim.live.len());
+        }
+        let (lifespan, period) = settled.unzip();
+        Outcome { value, seed, final_population: sim.live.len(), peak: stats.peak, lifespan, period }
+    }
+
+    /// Run every job on up to `threads` threads. `progress` is called on this thread after each
+    /// run with the number done and the total. Outcomes come back in job order either way.
+    pub fn run(&self, threads: usize, mut progress: impl FnMut(usize, usize, &Outcome)) -> Vec<Outcome> {
+        let jobs = self.jobs();
+        let total = jobs.len();
+        let mut outcomes: Vec<Option<Outcome>> = vec![None; total];
+        if threads <= 1 {
+            for (i, &(value, seed)) in jobs.iter().enumerate() {
+                let outcome = self.run_one(value, seed);
+                progress(i + 1, total, &outcome);
+                outcomes[i] = Some(outcome);
+            }
+        } else {
+            let (tx, rx) = mpsc::channel();
+            thread::scope(|scope| {
+                for worker in 0..threads.min(total) {
+                    let (tx, jobs) = (tx.clone(), &jobs);
+                    scope.spawn(move || {
+                        for (i, &(value, seed)) in jobs.iter().enumerate().skip(worker).step_by(threads) {
+                            let _ = tx.send((i, self.run_one(value, seed)));
+                        }
+                    });
+                }
+                drop(tx);
+                for (done, (i, outcome)) in rx.iter().enumerate() {
+                    progress(done + 1, total, &outcome);
+                    outcomes[i] = Some(outcome);
+                }
+            });
+        }
+        outcomes.into_iter().flatten().collect()
+    }
+}
+
+/// The outcomes as a CSV table with a header row
+pub fn to_csv(outcomes: &[Outcome]) -> String {
+    let mut csv = format!("{}\n", Outcome::CSV_HEADER);
+    for outcome in outcomes {
+        csv += &outcome.csv_row();
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Threads to run replicates on: every core with the `parallel` feature on a native build, one otherwise
+pub fn default_threads() -> usize {
+    if cfg!(all(feature = "parallel", not(target_arch = "wasm32"))) {
+        thread::available_parallelism().map_or(1, |n| n.get())
+    } else {
+        1
+    }
+}
@@ -0,0 +1,207 @@
+//! Board figures: a set of cells rasterized to PNG entirely on the CPU, with no window, for
+//! scripts and headless runs. Drawing goes through `render::draw_board` like the window does,
+//! and board diffs through `render::draw_diff`.
+//! For posters and plotters the whole board can also be written as SVG.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::path::Path;
+use std::rc::Rc;
+use macroquad::color::Color;
+use macroquad::texture::Image;
+
+use crate::compare::BoardDiff;
+use crate::config::{SVG_CELL, SVG_MAX_CELLS, SVG_WARN_CELLS};
+use crate::error::{self, GolError, Location};
+use crate::grid::Position;
+use crate::render::{draw_board, draw_diff, BoardView};
+use crate::simulation::Simulation;
+use crate::themes::{Backdrop, ColorTheme, GridMode};
+
+/// How a figure is drawn
+#[derive(Clone, Debug, PartialEq)]
+pub struct FigureOptions {
+    pub theme: ColorTheme,
+    pub cell: i32,   // Pixels per cell; lowered if the image would not fit 65535 pixels
+    pub grid: bool,  // Draw minor grid lines
+    pub margin: i32, // Empty cells kept around the live cells
+    pub backdrop: Option<Rc<Backdrop>>, // Background image under the cells; None leaves the flat color
+}
+
+impl Default for FigureOptions {
+    fn default() -> Self {
+        Self { theme: ColorTheme::Classic, cell: 8, grid: false, margin: 1, backdrop: None }
+    }
+}
+
+/// Rasterize the live cells' bounding box, plus `margin` empty cells on every side
+pub fn rasterize_board(cells: &HashSet<Position>, options: &FigureOptions) -> Image {
+    let (lo, mut sim, view, mut image) = figure_setup(cells.iter(), options);
+    sim.live = cells.iter().map(|p| Position::new(p.x() - lo.0, p.y() - lo.1)).collect();
+    draw_board(&mut image, &sim, &view, 1.0);
+    image
+}
+
+/// Rasterize a board diff over the bounding box of both boards, plus `margin` empty cells on
+/// every side, in the same colors as compare mode in the window
+pub fn rasterize_diff(diff: &BoardDiff, options: &FigureOptions) -> Image {
+    let (lo, sim, view, mut image) = figure_setup(diff.only_a.iter().chain(&diff.only_b).chain(&diff.both), options);
+    let shift = |cells: &HashSet<Position>| cells.iter().map(|p| Position::new(p.x() - lo.0, p.y() - lo.1)).collect();
+    let shifted = BoardDiff { only_a: shift(&diff.only_a), only_b: shift(&diff.only_b), both: shift(&diff.both) };
+    draw_diff(&mut image, &sim.grid, &view, &shifted);
+    image
+}
+
+/// An empty board and view sized to `cells` plus the margin, a blank image to draw them on,
+/// and the board cell that sits where the top left of the margin is
+fn figure_setup<'a>(cells: impl Iterator<Item = &'a Position>, options: &FigureOptions) -> ((i32, i32), Simulation, BoardView, Image) {
+    let margin = options.margin.max(0);
+    let (lo, hi) = cells.fold(None, |acc: Option<((i32, i32), (i32, i32))>, p| {
+        let ((x0, y0), (x1, y1)) = acc.unwrap_or(((p.x(), p.y()), (p.x(), p.y())));
+        Some(((x0.min(p.x()), y0.min(p.y())), (x1.max(p.x()), y1.max(p.y()))))
+    }).unwrap_or(((0, 0), (-1, -1)));
+    let (w, h) = ((hi.0 - lo.0 + 1 + 2 * margin).max(1), (hi.1 - lo.1 + 1 + 2 * margin).max(1));
+    let sim = Simulation::new(w, h);
+
+    let cell = options.cell.clamp(1, u16::MAX as i32 / w.max(h));
+    let mut view = BoardView::new(cell);
+    view.theme = options.theme;
+    view.grid_style.mode = if options.grid { GridMode::Minor } else { GridMode::Off };
+    view.grid_style.min_cell = 1;
+    view.backdrop = options.backdrop.clone();
+    let image = Image::gen_image_color((w * cell) as u16, (h * cell) as u16, options.theme.colors().background);
+    ((lo.0 - margin, lo.1 - margin), sim, view, image)
+}
+
+/// Rasterize cells as with `rasterize_board` and encode the result as PNG
+pub fn render_board_to_png(cells: &HashSet<Position>, options: &FigureOptions) -> Vec<u8> {
+    encode_png(&rasterize_board(cells, options))
+}
+
+/// Render cells to a PNG file
+pub fn write_board_png(path: &Path, cells: &HashSet<Position>, options: &FigureOptions) -> Result<(), GolError> {
+    error::write(path, render_board_to_png(cells, options))
+}
+
+/// Render a board diff to a PNG file
+pub fn write_diff_png(path: &Path, diff: &BoardDiff, options: &FigureOptions) -> Result<(), GolError> {
+    error::write(path, encode_png(&rasterize_diff(diff, options)))
+}
+
+/// Encode an RGBA image as PNG
+pub fn encode_png(image: &Image) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, image.width as u32, image.height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    // Writing to memory only fails for an image that doesn't match its own size
+    let mut writer = encoder.write_header().expect("PNG header for an in-memory image");
+    writer.write_image_data(&image.bytes).expect("PNG data for an in-memory image");
+    writer.finish().expect("PNG end for an in-memory image");
+    out
+}
+
+/// Decode an 8-bit RGBA PNG, such as one written by `encode_png`
+pub fn decode_png(bytes: &[u8]) -> Result<Image, GolError> {
+    let malformed = |e: png::DecodingError| GolError::parse("PNG", Location::Whole, e.to_string());
+    let mut reader = png::Decoder::new(bytes).read_info().map_err(malformed)?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).map_err(malformed)?;
+    if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+        return Err(GolError::Unsupported(format!("expected 8-bit RGBA, found {:?} at {:?}", info.color_type, info.bit_depth)));
+    }
+    let (width, height) = (u16::try_from(info.width), u16::try_from(info.height));
+    let (Ok(width), Ok(height)) = (width, height) else {
+        return Err(GolError::invalid("image", format!("{}x{} is too large", info.width, info.height)));
+    };
+    buf.truncate(info.buffer_size());
+    Ok(Image { bytes: buf, width, height })
+}
+
+/// How an SVG export is drawn
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SvgOptions {
+    pub theme: ColorTheme, // Colors for the background, cells, grid lines and border
+    pub cell: i32,         // SVG units per cell
+    pub grid: bool,        // Draw a line between every row and column
+    pub border: bool,      // Outline the board
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self { theme: ColorTheme::Classic, cell: SVG_CELL, grid: false, border: true }
+    }
+}
+
+/// Live cells in each row merged into runs `(x, y, length)`, top to bottom and left to right
+pub fn row_runs(cells: &HashSet<Position>) -> Vec<(i32, i32, i32)> {
+    let mut sorted: Vec<(i32, i32)> = cells.iter().map(|p| (p.y(), p.x())).collect();
+    sorted.sort_unstable();
+    let mut runs: Vec<(i32, i32, i32)> = Vec::new();
+    for (y, x) in sorted {
+        match runs.last_mut() {
+            Some((rx, ry, len)) if *ry == y && *rx + *len == x => *len += 1,
+            _ => runs.push((x, y, 1)),
+        }
+    }
+    runs
+}
+
+/// What to tell the user before writing an SVG of `cells` live cells, if it will be large
+pub fn svg_size_warning(cells: usize) -> Option<String> {
+    (cells > SVG_WARN_CELLS).then(|| format!("{} live cells make a large SVG; viewers and plotters may be slow with it", cells))
+}
+
+/// The whole board as an SVG document: the background, one rect per horizontal run of live
+/// cells, then grid lines and the border if asked for. Comments record the generation and rule.
+/// Boards over `SVG_MAX_CELLS` live cells are refused.
+pub fn board_to_svg(sim: &Simulation, options: &SvgOptions) -> Result<String, GolError> {
+    if sim.live.len() > SVG_MAX_CELLS {
+        return Err(GolError::invalid("SVG export", format!("{} live cells is more than the {} an SVG may hold", sim.live.len(), SVG_MAX_CELLS)));
+    }
+    let colors = options.theme.colors();
+    let c = options.cell.max(1);
+    let (w, h) = (sim.grid.width * c, sim.grid.height * c);
+    let mut svg = String::new();
+    // Writing to a String can't fail
+    let _ = writeln!(svg, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(svg, "<!-- Conway's Game of Life, generation {} -->", sim.generation);
+    let _ = writeln!(svg, "<!-- rule {}, {}x{} {} grid, {} live cells -->", sim.grid.rule, sim.grid.width, sim.grid.height, sim.grid.topology, sim.live.len());
+    let _ = writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#);
+    let _ = writeln!(svg, r#"<rect width="{}" height="{}" {}/>"#, w, h, paint("fill", colors.background));
+    let _ = writeln!(svg, r#"<g {}>"#, paint("fill", colors.cell));
+    for (x, y, len) in row_runs(&sim.live) {
+        let _ = writeln!(svg, r#"<rect x="{}" y="{}" width="{}" height="{}"/>"#, x * c, y * c, len * c, c);
+    }
+    let _ = writeln!(svg, "</g>");
+    if options.grid {
+        let mut path = String::new();
+        for x in 1..sim.grid.width {
+            let _ = write!(path, "M{} 0V{}", x * c, h);
+        }
+        for y in 1..sim.grid.height {
+            let _ = write!(path, "M0 {}H{}", y * c, w);
+        }
+        let width = (c as f32 / 10.0).max(0.1);
+        let _ = writeln!(svg, r#"<path d="{}" {} stroke-width="{}" fill="none"/>"#, path, paint("stroke", colors.grid), width);
+    }
+    if options.border {
+        // Half the stroke falls outside the picture, like the window's border
+        let width = (c as f32 / 2.0).max(1.0);
+        let _ = writeln!(svg, r#"<rect width="{}" height="{}" {} stroke-width="{}" fill="none"/>"#, w, h, paint("stroke", colors.border), width);
+    }
+    let _ = writeln!(svg, "</svg>");
+    Ok(svg)
+}
+
+/// Write the board as an SVG file
+pub fn write_board_svg(path: &Path, sim: &Simulation, options: &SvgOptions) -> Result<(), GolError> {
+    error::write(path, board_to_svg(sim, options)?)
+}
+
+/// A fill or stroke attribute for a color, with its opacity when it isn't opaque
+fn paint(attribute: &str, color: Color) -> String {
+    let [r, g, b, a]: [u8; 4] = color.into();
+    let hex = format!(r##"{}="#{:02x}{:02x}{:02x}""##, attribute, r, g, b);
+    if a == 255 { hex } else { format!(r#"{} {}-opacity="{:.3}""#, hex, attribute, a as f32 / 255.0) }
+}
@@ -0,0 +1,255 @@
+//! Deliberately simple dense stepper used to cross-check the production engine.
+//! It visits every cell on every step, so keep it to small boards.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use super::{Grid, Position, Topology, NEIGHBOR_OFFSETS};
+use crate::config::RANDOM_DENSITY;
+use crate::rule::Rule;
+use crate::rng::Rng;
+
+/// Full-grid board: one bool per cell, row-major
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DenseBoard {
+    pub width: i32,
+    pub height: i32,
+    pub topology: Topology,
+    pub rule: Rule,
+    cells: Vec<bool>,
+}
+
+impl DenseBoard {
+    /// Copy a sparse live set onto a dense board with the grid's dimensions and topology
+    pub fn from_cells(grid: &Grid, live: &HashSet<Position>) -> Self {
+        let mut cells = vec![false; (grid.width * grid.height) as usize];
+        for p in live {
+            cells[(p.y() * grid.width + p.x()) as usize] = true;
+        }
+        Self { width: grid.width, height: grid.height, topology: grid.topology, rule: grid.rule, cells }
+    }
+
+    #[inline]
+    fn get(&self, x: i32, y: i32) -> bool {
+        let (x, y) = match self.topology {
+            Topology::Torus | Topology::TorusShifted { .. } => {
+                // Step back onto the board one edge at a time, sliding along it by the shift
+                let (dx_on_wrap_y, dy_on_wrap_x) = self.topology.shift_within(self.width, self.height);
+                let (mut x, mut y) = (x, y);
+                while x < 0 { x += self.width; y -= dy_on_wrap_x; }
+                while x >= self.width { x -= self.width; y += dy_on_wrap_x; }
+                while y < 0 { y += self.height; x -= dx_on_wrap_y; }
+                while y >= self.height { y -= self.height; x += dx_on_wrap_y; }
+                (x.rem_euclid(self.width), y.rem_euclid(self.height))
+            }
+            // A cylinder wraps one axis with no shift; past the walls is dead
+            Topology::CylinderX if y >= 0 && y < self.height => (x.rem_euclid(self.width), y),
+            Topology::CylinderY if x >= 0 && x < self.width => (x, y.rem_euclid(self.height)),
+            Topology::Bounded if x >= 0 && y >= 0 && x < self.width && y < self.height => (x, y),
+            _ => return false,
+        };
+        self.cells[(y * self.width + x) as usize]
+    }
+
+    /// One generation of the board's rule over every cell
+    pub fn step(&self) -> Self {
+        let mut next = vec![false; self.cells.len()];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let n = NEIGHBOR_OFFSETS
+                    .iter()
+                    .filter(|&&(dx, dy)| self.get(x + dx, y + dy))
+                    .count();
+                next[(y * self.width + x) as usize] = self.rule.next(self.get(x, y), n as u8);
+            }
+        }
+        Self { cells: next, ..self.clone() }
+    }
+
+    /// Live cells as a sparse set
+    pub fn to_cells(&self) -> HashSet<Position> {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| Position::new(x, y)))
+            .filter(|p| self.cells[(p.y() * self.width + p.x()) as usize])
+            .collect()
+    }
+}
+
+/// First generation where the engine and the reference disagree
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    pub generation: u64,
+    pub engine_only: usize,    // Live in the engine but dead in the reference
+    pub reference_only: usize, // Live in the reference but dead in the engine
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "engine diverged at generation {} ({} extra cells, {} missing cells)",
+            self.generation, self.engine_only, self.reference_only,
+        )
+    }
+}
+
+/// Parse a board drawn in ASCII: `#` is live, any other character dead.
+/// Rows are separated by newlines or `/`; the grid takes the size of the drawing.
+pub fn from_ascii(text: &str, topology: Topology) -> (Grid, HashSet<Position>) {
+    let rows: Vec<&str> = text.split(['\n', '/']).map(str::trim).filter(|r| !r.is_empty()).collect();
+    let width = rows.iter().map(|r| r.chars().count()).max().unwrap_or(0) as i32;
+    let mut grid = Grid::new(width, rows.len() as i32);
+    grid.topology = topology;
+    let live = rows.iter().enumerate()
+        .flat_map(|(y, row)| row.chars().enumerate().filter(|&(_, c)| c == '#').map(move |(x, _)| Position::new(x as i32, y as i32)))
+        .collect();
+    (grid, live)
+}
+
+/// Draw a board in the format read by `from_ascii`, one row per line
+pub fn to_ascii(grid: &Grid, live: &HashSet<Position>) -> String {
+    (0..grid.height)
+        .map(|y| (0..grid.width).map(|x| if live.contains(&Position::new(x, y)) { '#' } else { '.' }).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Random soup on `grid` reproducible from `seed`
+pub fn random_soup(grid: &Grid, density: f32, seed: u64) -> HashSet<Position> {
+    let mut rng = Rng::new(seed);
+    (0..grid.height)
+        .flat_map(|y| (0..grid.width).map(move |x| Position::new(x, y)))
+        .filter(|_| rng.next_f32() < density)
+        .collect()
+}
+
+/// Run `live` through both the production engine and the reference, comparing every generation
+pub fn differential_check(grid: &Grid, live: HashSet<Position>, generations: u64) -> Result<(), Mismatch> {
+    let mut engine = live;
+    let mut reference = DenseBoard::from_cells(grid, &engine);
+    for generation in 1..=generations {
+        engine = grid.next_generation(&engine);
+        reference = reference.step();
+        let expected = reference.to_cells();
+        if engine != expected {
+            return Err(Mismatch {
+                generation,
+                engine_only: engine.difference(&expected).count(),
+                reference_only: expected.difference(&engine).count(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Board sizes every sweep covers besides the one asked for: one-cell strips each way, an odd
+/// board, and a board so small that every cell neighbors every other
+pub const SWEEP_SIZES: [(i32, i32); 4] = [(1, 17), (17, 1), (7, 5), (2, 2)];
+
+/// One board of a sweep, and the seeds the engine diverged on there
+pub struct SweepCase {
+    pub grid: Grid,
+    pub failures: Vec<(u64, Mismatch)>,
+}
+
+/// Run `runs` soups, seeded from `seed` up, on each size under every topology through
+/// `differential_check`
+pub fn differential_sweep(sizes: &[(i32, i32)], rule: Rule, seed: u64, runs: u32, generations: u64) -> Vec<SweepCase> {
+    let mut cases = Vec::new();
+    for &(width, height) in sizes {
+        for topology in Topology::ALL {
+            let mut grid = Grid::new(width, height);
+            (grid.topology, grid.rule) = (topology, rule);
+            let failures = (0..runs as u64)
+                .map(|run| seed.wrapping_add(run))
+                .filter_map(|seed| differential_check(&grid, random_soup(&grid, RANDOM_DENSITY.max(0.35), seed), generations).err().map(|m| (seed, m)))
+                .collect();
+            cases.push(SweepCase { grid, failures });
+        }
+    }
+    cases
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step_ascii(text: &str, topology: Topology, generations: u64) -> String {
+        let (grid, live) = from_ascii(text, topology);
+        let mut board = DenseBoard::from_cells(&grid, &live);
+        for _ in 0..generations {
+            board = board.step();
+        }
+        to_ascii(&grid, &board.to_cells())
+    }
+
+    #[test]
+    fn reference_steps_known_objects() {
+        // Checked by hand, so the reference is trusted on its own before it judges the engine
+        assert_eq!(step_ascii("...../..#../..#../..#../.....", Topology::Bounded, 1), ".....\n.....\n.###.\n.....\n.....");
+        assert_eq!(step_ascii("..../.##./.##./....", Topology::Bounded, 3), "....\n.##.\n.##.\n....");
+        let glider = ".#..../..#.../###.../....../....../......";
+        assert_eq!(step_ascii(glider, Topology::Torus, 4), "......\n..#...\n...#..\n.###..\n......\n......");
+        assert_eq!(step_ascii(glider, Topology::Torus, 24), to_ascii(&from_ascii(glider, Topology::Torus).0, &from_ascii(glider, Topology::Torus).1));
+    }
+
+    #[test]
+    fn reference_walls_and_seams() {
+        // A blinker on the left wall loses its outer cell to the wall, or wraps it to the right
+        assert_eq!(step_ascii("...../#..../#..../#..../.....", Topology::Bounded, 1), ".....\n.....\n##...\n.....\n.....");
+        assert_eq!(step_ascii("...../#..../#..../#..../.....", Topology::Torus, 1), ".....\n.....\n##..#\n.....\n.....");
+        assert_eq!(step_ascii("...../#..../#..../#..../.....", Topology::CylinderY, 1), ".....\n.....\n##...\n.....\n.....");
+    }
+
+    #[test]
+    fn ascii_round_trip() {
+        let text = "#..#\n.##.\n....";
+        let (grid, live) = from_ascii(text, Topology::Bounded);
+        assert_eq!((grid.width, grid.height, live.len()), (4, 3, 4));
+        assert_eq!(to_ascii(&grid, &live), text);
+        assert_eq!(from_ascii("#..#/.##./....", Topology::Bounded).1, live);
+    }
+
+    #[test]
+    fn engine_matches_reference() {
+        let sizes = [&[(32, 24)][..], &SWEEP_SIZES[..]].concat();
+        for case in differential_sweep(&sizes, Rule::LIFE, 1, 4, 120) {
+            assert!(case.failures.is_empty(), "{}x{} {}: {:?}", case.grid.width, case.grid.height, case.grid.topology, case.failures);
+        }
+    }
+
+    #[test]
+    fn engine_matches_reference_on_other_rules() {
+        for text in ["B36/S23", "B3678/S34678", "B2/S", "B35678/S5678", "B3/S012345678"] {
+            let rule = Rule::parse(text).unwrap();
+            for case in differential_sweep(&[(24, 18), (7, 5)], rule, 11, 2, 60) {
+                assert!(case.failures.is_empty(), "{} on {}x{} {}: {:?}", text, case.grid.width, case.grid.height, case.grid.topology, case.failures);
+            }
+        }
+    }
+
+    #[test]
+    fn engine_matches_reference_on_shifted_tori() {
+        for shift in ["x+3", "y+5", "x-1", "y+2147483647", "x-2147483648"] {
+            let mut grid = Grid::new(20, 15);
+            grid.topology = Topology::parse_shift(shift).unwrap();
+            for seed in 0..3 {
+                if let Err(mismatch) = differential_check(&grid, random_soup(&grid, 0.35, seed), 80) {
+                    panic!("torus {} seed {}: {}", shift, seed, mismatch);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn huge_shifts_wrap_like_their_remainder() {
+        let mut grid = Grid::new(20, 15);
+        let far = [(i32::MIN, i32::MIN), (i32::MAX, -1), (-1, i32::MAX), (20, 15)];
+        for (huge, reduced) in [("y+2147483647", "y+7"), ("x-2147483648", "x+12")] {
+            grid.topology = Topology::parse_shift(huge).unwrap();
+            let wrapped = far.map(|(x, y)| grid.wrap(x, y));
+            grid.topology = Topology::parse_shift(reduced).unwrap();
+            assert_eq!(wrapped, far.map(|(x, y)| grid.wrap(x, y)), "{} against {}", huge, reduced);
+        }
+    }
+}
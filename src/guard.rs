@@ -0,0 +1,56 @@
+//! Protection against throwing away a running board by accident. While the board runs, a
+//! destructive action only goes ahead when asked for twice within a moment; paused boards and
+//! users who turned the protection off get the action at once.
+
+use crate::config::CONFIRM_SECONDS;
+
+/// Actions that discard or rewrite the board
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Destructive {
+    Clear,           // C
+    Reseed,          // Shift+R: a fresh random soup
+    Cleanup,         // X
+    Command(String), // A console line such as `load` or `fill`, keyed by its text
+    CloseTab,        // Ctrl+W: the tab's board is gone for good, so it asks even when paused
+}
+
+impl Destructive {
+    /// What to do again to confirm
+    pub fn hint(&self) -> String {
+        let (again, what) = match self {
+            Destructive::Clear => ("C", "clear the board"),
+            Destructive::Reseed => ("Shift+R", "fill a new random soup"),
+            Destructive::Cleanup => ("X", "clean up debris"),
+            Destructive::Command(line) => ("Enter", line.as_str()),
+            Destructive::CloseTab => ("Ctrl+W", "close this tab and its board"),
+        };
+        let lead = if *self == Destructive::CloseTab { "Press" } else { "Still running: press" };
+        format!("{} {} again within {} s to {}", lead, again, CONFIRM_SECONDS, what)
+    }
+}
+
+/// The action waiting for its second press
+#[derive(Clone, Debug, Default)]
+pub struct Guard {
+    pub enabled: bool,
+    armed: Option<(Destructive, f64)>, // Action and the time of its first press
+}
+
+impl Guard {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled, armed: None }
+    }
+
+    /// Whether `action` goes ahead at time `now` (in seconds). On a running board with the
+    /// guard on, the first press only arms it and a second press of the same action within
+    /// `CONFIRM_SECONDS` confirms it.
+    pub fn allow(&mut self, action: Destructive, running: bool, now: f64) -> bool {
+        let confirmed = matches!(&self.armed, Some((armed, at)) if *armed == action && now - at <= CONFIRM_SECONDS);
+        if !self.enabled || !running || confirmed {
+            self.armed = None;
+            return true;
+        }
+        self.armed = Some((action, now));
+        false
+    }
+}
@@ -0,0 +1,4824 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::{mpsc, Arc};
+use macroquad::color::{Color, BLACK, GREEN};
+use macroquad::input::{KeyCode, MouseButton};
+use macroquad::math::Vec2;
+use macroquad::texture::Image;
+
+use crate::analysis::{self, census, classify_growth, Cleanup, Growth, GrowthTracker, RunStats};
+use crate::bookmarks::{Bookmark, Bookmarks};
+use crate::camera::{Lost, Spring, Tracked};
+use crate::clock::SimClock;
+use crate::compare::{diff_boards, BoardDiff, Compare, DiffCounts, Reference};
+use crate::annotations::{Annotations, NoteColor};
+use crate::debug_dump::DebugDump;
+use crate::density::DensityMap;
+use crate::guard::{Destructive, Guard};
+use crate::config::{BACKDROP_OPACITY, HEAT_BLOCK, HEAT_FADE_SECONDS, HEAT_MAX_BLOCKS, PARTICLE_MAX_BIRTHS, PARTICLE_SECONDS, BOOKMARK_MAX, CONFIRM_SECONDS, DEBUG_DUMP_HISTORY, EXPAND_MARGIN, EXPERIMENT_GENERATIONS, EXPERIMENT_REPLICATES, GRID_MAX, CELL_SIZE, HUD_HEIGHT, MUTATION_GENERATIONS, MUTATION_SIZE, RULER_GAP, SAVER_FPS, SAVER_GRACE_SECONDS, SPEED_MAX, STEP_BUDGET_SECONDS, SVG_WARN_CELLS, TICKS_PER_SECOND, RANDOM_DENSITY, SUMMARY_PERIOD_MAX, THUMB_MAX_BYTES, THUMB_MAX_COUNT, THUMB_SIZE, ZOOM_FIT_MARGIN, ZOOM_LEVELS, ZOOM_OUT_LEVELS, DENSITY_FAINTEST, DENSITY_RECHECK_FRAMES, PREVIEW_MAX_SIDE, PREVIEW_RASTER, PREVIEW_TRAVEL, LOAD_CHUNK};
+use crate::deltas::DeltaLog;
+use crate::budget;
+use crate::project::{self, Project};
+use crate::error::{self, GolError, Location};
+use crate::heat::HeatField;
+use crate::particles::{Effect, Particles};
+use crate::hotbar::{self, Hotbar, Slot};
+use crate::serve;
+use crate::remote::{self, Command as RemoteCommand, Control, Event as RemoteEvent, Source, Summary as RemoteSummary};
+use crate::experiment::{self, Experiment, Outcome, Parameter, Value};
+use crate::mutation::{self, mutations, Explorer};
+use crate::records::{self, Records, RunResult, RunSetup};
+use crate::figure::{board_to_svg, decode_png, encode_png, rasterize_diff, render_board_to_png, row_runs, svg_size_warning, write_board_png, write_board_svg, write_diff_png, FigureOptions, SvgOptions};
+use crate::overlay::{Frame, Input, InputConsumed, Layer, Overlay, OverlayStack, Scripted};
+use crate::render::{draw_board, BoardView, DIFF_ONLY_PRIMARY, DIFF_ONLY_SECONDARY, CONTROLS, Help, Ruler, WindowFit};
+use crate::simulation::{CellVerdict, ClearScope, GenerationDiff, Simulation};
+use crate::themes::{Backdrop, Backdrops, ColorTheme, CustomThemes, GridMode, Palette};
+use crate::timelapse::{self, Gallery};
+#[cfg(all(feature = "watch", not(target_arch = "wasm32")))]
+use crate::watch::Watched;
+use crate::ui::{BoardSize, ClearMenu, Controls, GameMode, MenuScreen, MenuStack, PatternChoice, Setup, SimulationConfig, Start, ThemeEvent, ThemePicker};
+use crate::grid::{board_hash, CellFate, CoordSystem, Grid, Offset, Position, Rect, Topology, NEIGHBOR_OFFSETS};
+use crate::grid::reference::{differential_check, differential_sweep, from_ascii, random_soup, to_ascii, DenseBoard, SWEEP_SIZES};
+use crate::rng::Rng;
+use crate::rule::Rule;
+use crate::schedule::SpeedSchedule;
+use crate::script::ScriptHook;
+use crate::preview::Preview;
+use crate::loading::{Load, Stage};
+use crate::selftest::{self, BOUNDARY_CASES};
+use crate::settings::Settings;
+use crate::pacing::{Limit, Pacer};
+use crate::stepper::Stepper;
+use crate::net;
+use crate::console;
+use crate::pattern_io::{self, load_pattern_file, parse_pattern_text, LoadedPattern, Metadata};
+use crate::patterns::image::{self, ImageOptions, Picture};
+use crate::patterns::{self, default_registry, layout_patterns, validate_density, GliderPattern, Interest, Layout, OnClip, Pattern, PatternInfo, PatternRegistry, PatternSort, Placement, Transform};
+
+/// Command-line modes that run without opening a window
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Command {
+    Census, // Print an object census of the board after the requested generations
+    Verify, // Cross-check the engine against the dense reference stepper
+    ReplayDeltas, // Rebuild a generation from a delta log and check it against direct simulation
+    Fuzz,   // Check engine invariants on many random small boards
+    Render, // Write the board after the requested generations as a PNG figure
+    ExportSvg, // Write the whole board after the requested generations as SVG
+    ExportRle, // Write the board after the requested generations as RLE
+    Bench,  // Time the engine's stepping against a hand-rolled neighbor loop
+    Hashes, // Print the board hash every `hash_every` generations
+    Cleanup, // Remove debris after the requested generations and report it
+    Experiment, // Sweep a soup parameter over replicate seeds and write a CSV of outcomes
+    DebugDump, // Rebuild the run saved in a debug dump, describe it and run it on
+    Diff,   // Count the cells two pattern files share and the ones only each has
+    SelfTest, // Run the self-test battery and print a table of its results
+}
+
+/// Board setup shared by every headless command
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub width: i32,
+    pub height: i32,
+    pub pattern: String,
+    pub seed: u64,
+    pub generations: u64,
+    pub topology: Topology,
+    pub lethal_margin: i32, // Cells culled each generation along the walled edges
+    pub runs: u32, // Seeds tried per configuration by --verify
+    pub input: Option<String>, // Pattern file loaded instead of `pattern`
+    pub density: f32,          // Fill density for the Random pattern
+    pub delta_log: Option<String>, // Log read by --replay-deltas
+    pub iterations: u64,           // Random boards tried by --fuzz
+    pub seconds: Option<f64>,      // Stop --fuzz after this long instead
+    pub output: Option<String>,    // PNG written by --render, or SVG by --export-svg
+    pub figure: FigureOptions,     // How --render draws the board; its theme, cell size and grid also apply to SVG
+    pub border: bool,              // --export-svg outlines the board
+    pub backdrop: Option<String>,  // Image --render draws behind the cells
+    pub backdrop_opacity: f32,
+    pub script: Option<String>,    // Script whose hooks run after each generation
+    pub hash_every: u64,           // Print the board hash at every multiple of this generation; 0 is off
+    pub cleanup: Option<usize>,    // Remove objects under this many cells, and settled ones, before the output
+    pub experiment: Option<Parameter>, // Parameter swept by --experiment
+    pub sweep: (Option<f64>, Option<f64>, Option<f64>), // --from, --to and --step
+    pub values: Option<String>,    // Comma-separated values swept instead of a range
+    pub replicates: u32,           // Seeds run per value
+    pub debug_dump: Option<String>, // Debug dump whose board replaces the pattern and board options
+    pub open: Option<String>,      // Project or `.gold` save whose board replaces them likewise
+    pub on_clip: OnClip,           // What to do with a pattern that hangs past a wall
+    pub diff: Option<(String, String)>, // Pattern files compared by --diff, drawn instead of the board by --render
+    pub serve: Option<String>,     // Address the window mirrors its board on; no headless command serves
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            width: 128,
+            height: 96,
+            pattern: "Random".to_string(),
+            seed: 1,
+            generations: 0,
+            topology: Topology::Bounded,
+            lethal_margin: 0,
+            runs: 10,
+            input: None,
+            density: RANDOM_DENSITY,
+            delta_log: None,
+            iterations: 1000,
+            seconds: None,
+            output: None,
+            figure: FigureOptions::default(),
+            border: true,
+            backdrop: None,
+            backdrop_opacity: BACKDROP_OPACITY,
+            script: None,
+            hash_every: 0,
+            cleanup: None,
+            experiment: None,
+            sweep: (None, None, None),
+            values: None,
+            replicates: EXPERIMENT_REPLICATES,
+            debug_dump: None,
+            open: None,
+            on_clip: OnClip::Error,
+            diff: None,
+            serve: None,
+        }
+    }
+}
+
+impl Options {
+    /// Build the starting board described by these options
+    pub fn build_game(&self) -> Result<Simulation, String> {
+        if let Some(path) = &self.debug_dump {
+            return DebugDump::load(path.as_ref())?.simulation().map_err(|e| format!("{}: {}", path, e));
+        }
+        if let Some(path) = &self.open {
+            // What the project doesn't store comes from the options
+            let mut sim = Simulation::new(self.width, self.height);
+            (sim.seed, sim.density, sim.lethal_margin) = (self.seed, self.density, self.lethal_margin);
+            Project::open(path.as_ref()).and_then(|project| project.restore(&mut sim))?;
+            return Ok(sim);
+        }
+        if self.width <= 0 || self.height <= 0 {
+            return Err(format!("invalid grid size {}x{}", self.width, self.height));
+        }
+        let registry = default_registry();
+        let mut rule = Rule::LIFE;
+        let loaded;
+        let pattern: &dyn Pattern = match &self.input {
+            // A picture is fitted to the grid
+            Some(path) if image::AVAILABLE && image::is_image_file(path.as_ref()) => {
+                loaded = image::load(path.as_ref(), self.width, self.height, ImageOptions::default())?;
+                &loaded
+            }
+            Some(path) => {
+                loaded = load_pattern_file(path.as_ref()).map_err(|e| format!("{}: {}", path, e))?;
+                match loaded.foreign_rule(rule) {
+                    Some(Ok(own)) => rule = own,
+                    Some(Err(e)) => eprintln!("warning: {}: {}; running it as {}", path, e, rule),
+                    None => {}
+                }
+                &loaded
+            }
+            None => registry.get(&self.pattern).ok_or_else(|| {
+                format!("unknown pattern '{}' (expected one of: {})", self.pattern, registry.names())
+            })?,
+        };
+        // Past a wall the clip policy decides; across a wrapping edge the pattern would run into itself
+        if let Some((w, h)) = pattern.bounds()
+            && ((w > self.width && self.topology.wraps_x()) || (h > self.height && self.topology.wraps_y()))
+        {
+            return Err(format!("pattern is {}x{} but the grid is only {}x{}", w, h, self.width, self.height));
+        }
+        let mut sim = Simulation::new(self.width, self.height);
+        sim.grid.topology = self.topology;
+        sim.grid.rule = rule;
+        sim.lethal_margin = self.lethal_margin;
+        sim.seed = self.seed;
+        sim.density = self.density;
+        let (x, y) = pattern.placement().anchor(pattern, self.width, self.height);
+        let (mut cells, overhang) = sim.placement(pattern, Transform::IDENTITY, 0, x, y).map_err(|e| e.to_string())?;
+        if overhang.clips() {
+            match self.on_clip {
+                OnClip::Error => {
+                    return Err(format!("{} hangs {} of the {}x{} grid (--on-clip clip or expand to place it anyway)",
+                        pattern.name(), overhang, self.width, self.height));
+                }
+                OnClip::Clip => eprintln!("warning: clipped {}", overhang),
+                OnClip::Expand => {
+                    let (dx, dy) = sim.expand_for(overhang, EXPAND_MARGIN)?;
+                    cells = sim.pattern_cells(pattern, Transform::IDENTITY, 0, x + dx, y + dy).map_err(|e| e.to_string())?;
+                    eprintln!("grew the grid to {}x{} to fit {}", sim.grid.width, sim.grid.height, pattern.name());
+                }
+            }
+        }
+        sim.live.extend(cells);
+        sim.imported = pattern.metadata().map(|meta| (pattern.name().to_string(), meta.clone()));
+        Ok(sim)
+    }
+}
+
+/// Parse the value following a flag
+fn value<T: std::str::FromStr>(flag: &str, next: Option<&String>) -> Result<T, String> {
+    let raw = next.ok_or_else(|| format!("{} expects a value", flag))?;
+    raw.parse().map_err(|_| format!("invalid value '{}' for {}", raw, flag))
+}
+
+/// Parse command-line arguments. Returns `Ok(None)` when no headless command was given.
+pub fn parse_args(args: &[String]) -> Result<Option<(Command, Options)>, String> {
+    let mut command = None;
+    let mut opts = Options::default();
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--census" => command = Some(Command::Census),
+            "--verify" => command = Some(Command::Verify),
+            "--self-test" => command = Some(Command::SelfTest),
+            "--replay-deltas" => {
+                command = Some(Command::ReplayDeltas);
+                opts.delta_log = Some(value(arg, it.next())?);
+            }
+            "--fuzz" => command = Some(Command::Fuzz),
+            "--bench" => command = Some(Command::Bench),
+            "--render" => {
+                command = Some(Command::Render);
+                opts.output = Some(value(arg, it.next())?);
+            }
+            "--export-svg" => {
+                command = Some(Command::ExportSvg);
+                opts.output = Some(value(arg, it.next())?);
+            }
+            "--export-rle" => {
+                command = Some(Command::ExportRle);
+                opts.output = Some(value(arg, it.next())?);
+            }
+            "--no-border" => opts.border = false,
+            "--cell" => opts.figure.cell = value(arg, it.next())?,
+            "--margin" => opts.figure.margin = value(arg, it.next())?,
+            "--grid" => opts.figure.grid = true,
+            "--backdrop" => opts.backdrop = Some(value(arg, it.next())?),
+            "--backdrop-opacity" => opts.backdrop_opacity = validate_density(value(arg, it.next())?)?,
+            "--theme" => {
+                let name: String = value(arg, it.next())?;
+                opts.figure.theme = ColorTheme::from_name(&name).ok_or_else(|| format!("unknown theme '{}'", name))?;
+            }
+            "--iterations" => opts.iterations = value(arg, it.next())?,
+            "--seconds" => opts.seconds = Some(value(arg, it.next())?),
+            "--runs" => opts.runs = value(arg, it.next())?,
+            "--width" => opts.width = value(arg, it.next())?,
+            "--height" => opts.height = value(arg, it.next())?,
+            "--pattern" => opts.pattern = value(arg, it.next())?,
+            "--input" => opts.input = Some(value(arg, it.next())?),
+            "--density" => opts.density = validate_density(value(arg, it.next())?)?,
+            "--seed" => opts.seed = value(arg, it.next())?,
+            "--generations" => opts.generations = value(arg, it.next())?,
+            "--wrap" => opts.topology = Topology::Torus,
+            "--topology" => opts.topology = Topology::parse(&value::<String>(arg, it.next())?)?,
+            "--shift" => opts.topology = Topology::parse_shift(&value::<String>(arg, it.next())?)?,
+            "--lethal-margin" => opts.lethal_margin = value(arg, it.next())?,
+            "--script" => opts.script = Some(value(arg, it.next())?),
+            "--hash-every" => opts.hash_every = value(arg, it.next())?,
+            "--cleanup" => opts.cleanup = Some(value(arg, it.next())?),
+            "--experiment" => {
+                command = Some(Command::Experiment);
+                opts.experiment = Some(Parameter::parse(&value::<String>(arg, it.next())?)?);
+            }
+            "--from" => opts.sweep.0 = Some(value(arg, it.next())?),
+            "--to" => opts.sweep.1 = Some(value(arg, it.next())?),
+            "--step" => opts.sweep.2 = Some(value(arg, it.next())?),
+            "--values" => opts.values = Some(value(arg, it.next())?),
+            "--replicates" => opts.replicates = value(arg, it.next())?,
+            "--on-clip" => opts.on_clip = OnClip::parse(&value::<String>(arg, it.next())?)?,
+            "--load-debug-dump" => opts.debug_dump = Some(value(arg, it.next())?),
+            "--open" => opts.open = Some(value(arg, it.next())?),
+            "--diff" => opts.diff = Some((value(arg, it.next())?, value(arg, it.next())?)),
+            "--serve" => opts.serve = Some(value(arg, it.next())?),
+            other => return Err(format!("unknown argument '{}'", other)),
+        }
+    }
+    if opts.cleanup.is_some() && command.is_none() {
+        command = Some(Command::Cleanup);
+    } else if opts.hash_every > 0 && command.is_none() {
+        command = Some(Command::Hashes);
+    } else if opts.debug_dump.is_some() && command.is_none() {
+        command = Some(Command::DebugDump);
+    } else if opts.diff.is_some() && command.is_none() {
+        command = Some(Command::Diff);
+    }
+    if opts.serve.is_some() && command.is_some() {
+        return Err("--serve mirrors the window's board; run it without a headless command".to_string());
+    }
+    Ok(command.map(|c| (c, opts)))
+}
+
+/// Run the requested number of generations, with the script's hooks after each one. Script
+/// output goes to stderr; a hook that fails is switched off and the run carries on. With
+/// `hash_every` set, the board hash is printed at generation 0 and every multiple of it, and
+/// with `cleanup` set, debris is removed at the end.
+fn advance(sim: &mut Simulation, opts: &Options) -> Result<(), String> {
+    let mut speed = crate::config::SPEED_INIT;
+    let mut hook = match &opts.script {
+        Some(path) => Some(ScriptHook::load(path.as_ref(), sim, speed)?),
+        None => None,
+    };
+    print_hash(sim, opts.hash_every);
+    for _ in 0..opts.generations {
+        sim.next_generation();
+        print_hash(sim, opts.hash_every);
+        if let Some(hook) = hook.as_mut() {
+            hook.after_generation(sim, speed);
+            speed = hook.take_speed().unwrap_or(speed);
+            for message in hook.take_messages() {
+                eprintln!("script: {}", message);
+            }
+        }
+    }
+    if let Some(min_size) = opts.cleanup {
+        let cleaned = sim.clean_up(Cleanup { min_size, known: true });
+        println!("generation {}: cleanup {}, {} cells left", sim.generation, cleaned, sim.live.len());
+    }
+    Ok(())
+}
+
+/// Print `board_hash` when the generation is a multiple of `every`
+fn print_hash(sim: &Simulation, every: u64) {
+    if every > 0 && sim.generation.is_multiple_of(every) {
+        println!("generation {}: hash {:016x}", sim.generation, board_hash(&sim.live));
+    }
+}
+
+/// Print the census of the board after the requested number of generations
+fn run_census(opts: &Options) -> Result<(), String> {
+    let mut sim = opts.build_game()?;
+    advance(&mut sim, opts)?;
+    let result = census(&sim.live, &sim.grid);
+    println!("generation {}: {} cells, {} objects", sim.generation, sim.live.len(), result.total_objects());
+    for (name, count) in &result.counts {
+        println!("{:>8}  {}", count, name);
+    }
+    if !result.unidentified.is_empty() {
+        let sizes: Vec<_> = result.unidentified.iter().map(|s| s.to_string()).collect();
+        println!("{:>8}  unidentified (sizes: {})", result.unidentified.len(), sizes.join(", "));
+    }
+    Ok(())
+}
+
+/// Describe the run saved in a debug dump, rebuilt and checked against its board hash, then run
+/// it on for the requested generations
+fn run_debug_dump(opts: &Options) -> Result<(), String> {
+    let Some(path) = &opts.debug_dump else { return Err("--load-debug-dump expects a file".to_string()); };
+    let dump = DebugDump::load(path.as_ref())?;
+    let mut sim = dump.simulation().map_err(|e| format!("{}: {}", path, e))?;
+    println!("written by version {} on {}", dump.version, dump.platform);
+    println!("{}x{} {}, rule {}", sim.grid.width, sim.grid.height, sim.grid.topology, sim.grid.rule);
+    println!("seed {}, density {}, edge spawn {}, lethal margin {}", sim.seed, sim.density, if sim.edge_spawn { "on" } else { "off" }, sim.lethal_margin);
+    println!("generation {}: {} cells, hash {:016x} (matches the dump)", sim.generation, sim.live.len(), board_hash(&sim.live));
+    if let (Some(first), Some(last)) = (dump.history.first(), dump.history.last()) {
+        println!("population history: {} samples, {} cells at generation {} to {} at generation {}", dump.history.len(), first.1, first.0, last.1, last.0);
+    }
+    for note in &dump.annotations {
+        println!("note at ({}, {}), {}: {}", note.at.x(), note.at.y(), note.color.name(), note.text);
+    }
+    if opts.generations > 0 || opts.script.is_some() {
+        advance(&mut sim, opts)?;
+        println!("generation {}: {} cells, hash {:016x}", sim.generation, sim.live.len(), board_hash(&sim.live));
+    }
+    Ok(())
+}
+
+/// Generations timed by `--bench` when no `--generations` is given
+const BENCH_GENERATIONS: u64 = 500;
+
+/// One generation counted with `x + dx` over `NEIGHBOR_OFFSETS` inline, as the engine did before
+/// `Grid::neighbors_of`; `--bench` times the engine against it
+fn step_hand_rolled(grid: &Grid, live: &HashSet<Position>) -> HashSet<Position> {
+    let mut counts: HashMap<Position, u8> = HashMap::with_capacity(live.len() * 8 + 8);
+    for &cell in live {
+        for (dx, dy) in NEIGHBOR_OFFSETS {
+            if let Some(p) = grid.resolve(cell.x() + dx, cell.y() + dy) {
+                *counts.entry(p).or_insert(0) += 1;
+            }
+        }
+    }
+    let mut next = HashSet::with_capacity(live.len());
+    if grid.rule.survives(0) {
+        next.extend(live.iter().filter(|p| !counts.contains_key(p)));
+    }
+    for (pos, n) in counts {
+        if grid.rule.next(live.contains(&pos), n) {
+            next.insert(pos);
+        }
+    }
+    next
+}
+
+/// Time `Grid::next_generation` against the hand-rolled loop and against its two stages run
+/// with kept scratch maps, on the same board, alternating rounds so all see the same machine
+/// load, and check they end on the same cells
+fn run_bench(opts: &Options) -> Result<(), String> {
+    let sim = opts.build_game()?;
+    let generations = if opts.generations == 0 { BENCH_GENERATIONS } else { opts.generations };
+    let (mut engine, mut baseline, mut staged) = (sim.live.clone(), sim.live.clone(), sim.live.clone());
+    let (mut engine_time, mut baseline_time) = (std::time::Duration::ZERO, std::time::Duration::ZERO);
+    let (mut count_time, mut apply_time) = (std::time::Duration::ZERO, std::time::Duration::ZERO);
+    let (mut scratch, mut next) = (HashMap::new(), HashSet::new());
+    let mut cells = 0;
+    for _ in 0..generations {
+        let started = std::time::Instant::now();
+        engine = sim.grid.next_generation(&engine);
+        engine_time += started.elapsed();
+        let started = std::time::Instant::now();
+        baseline = step_hand_rolled(&sim.grid, &baseline);
+        baseline_time += started.elapsed();
+        let started = std::time::Instant::now();
+        let counts = sim.grid.count_neighbors(&staged, &mut scratch);
+        count_time += started.elapsed();
+        let started = std::time::Instant::now();
+        sim.grid.apply(counts, &staged, &mut next);
+        apply_time += started.elapsed();
+        std::mem::swap(&mut staged, &mut next);
+        cells += engine.len();
+    }
+    if engine != baseline {
+        return Err(format!("the engine and the hand-rolled loop disagree after {} generations", generations));
+    }
+    if engine != staged {
+        return Err(format!("the engine and its stages disagree after {} generations", generations));
+    }
+    let rate = |time: std::time::Duration| generations as f64 / time.as_secs_f64().max(1e-9);
+    println!("{} generations, {} cells on average", generations, cells / generations as usize);
+    println!("engine       {:>10.1} generations/s", rate(engine_time));
+    println!("hand-rolled  {:>10.1} generations/s", rate(baseline_time));
+    println!("engine takes {:.2}x the hand-rolled time", engine_time.as_secs_f64() / baseline_time.as_secs_f64().max(1e-9));
+    let per_generation = |time: std::time::Duration| time.as_secs_f64() * 1000.0 / generations as f64;
+    println!(
+        "stages, scratch kept: {:.3} ms/generation counting, {:.3} applying ({:.3} in all, {:.3} in one call)",
+        per_generation(count_time),
+        per_generation(apply_time),
+        per_generation(count_time + apply_time),
+        per_generation(engine_time)
+    );
+
+    // A frame zoomed out to a few cells per pixel, following each step's changes or counting
+    // the whole board again
+    let shrink = ZOOM_OUT_LEVELS[1];
+    let (mut zoomed, cell) = (opts.build_game()?, ColorTheme::Classic.colors().cell);
+    zoomed.track_diff = true;
+    let mut map = DensityMap::new(shrink);
+    map.sync(&zoomed, cell);
+    let (mut follow_time, mut recount_time) = (std::time::Duration::ZERO, std::time::Duration::ZERO);
+    for _ in 0..generations {
+        zoomed.next_generation();
+        let started = std::time::Instant::now();
+        map.sync(&zoomed, cell);
+        follow_time += started.elapsed();
+        let started = std::time::Instant::now();
+        DensityMap::new(shrink).sync(&zoomed, cell);
+        recount_time += started.elapsed();
+    }
+    let per_frame = |time: std::time::Duration| time.as_secs_f64() * 1000.0 / generations as f64;
+    println!("zoomed out to 1/{}: {:.3} ms/frame following each step, {:.3} ms/frame recounting", shrink, per_frame(follow_time), per_frame(recount_time));
+    Ok(())
+}
+
+/// Generations per seed when `--verify` is run without `--generations`
+const VERIFY_GENERATIONS: u64 = 300;
+
+/// Compare two boards drawn with `to_ascii`
+fn same_board(expected: String, actual: String) -> Result<(), String> {
+    if expected == actual { Ok(()) } else { Err(format!("expected:\n{}\ngot:\n{}", expected, actual)) }
+}
+
+/// Print one named check, returning 1 on failure so results can be summed
+fn report(name: &str, result: Result<(), String>) -> u32 {
+    match result {
+        Ok(()) => {
+            println!("ok   {}", name);
+            0
+        }
+        Err(detail) => {
+            println!("FAIL {}\n{}", name, detail);
+            1
+        }
+    }
+}
+
+/// Edge behavior: walls, gliders running into each corner, and patterns placed partly off the grid
+fn run_boundary_cases() -> u32 {
+    let mut failures = 0;
+    for (name, topology, generations, start, expected) in BOUNDARY_CASES {
+        let (grid, mut live) = from_ascii(start, topology);
+        for _ in 0..generations {
+            live = grid.next_generation(&live);
+        }
+        let (_, want) = from_ascii(expected, topology);
+        failures += report(&format!("{} ({})", name, topology), same_board(to_ascii(&grid, &want), to_ascii(&grid, &live)));
+    }
+
+    // A glider crosses an 8x8 torus in 32 generations and must come back unchanged;
+    // on a bounded grid it hits the corner and must match the reference stepper
+    for rotation in 0..4 {
+        for topology in [Topology::Bounded, Topology::Torus] {
+            let mut sim = Simulation::new(8, 8);
+            sim.grid.topology = topology;
+            let transform = Transform { rotation, flipped: false };
+            let _ = sim.stamp(&GliderPattern, transform, 0, 4, 4);
+            let name = format!("glider into corner, {}", transform.name());
+            let result = if topology.wraps() {
+                let start = to_ascii(&sim.grid, &sim.live);
+                for _ in 0..32 {
+                    sim.next_generation();
+                }
+                same_board(start, to_ascii(&sim.grid, &sim.live))
+            } else {
+                differential_check(&sim.grid, sim.live.clone(), 40).map_err(|m| m.to_string())
+            };
+            failures += report(&format!("{} ({})", name, topology), result);
+        }
+    }
+
+    // On a cylinder a glider crosses the wrapping seam just as it would on a torus, while one
+    // flown into a walled edge crashes there and settles. The glider heads down and right: placed
+    // near the right edge it meets the seam of CylinderX and the wall of CylinderY, and the other
+    // way round near the bottom edge.
+    for topology in [Topology::CylinderX, Topology::CylinderY] {
+        for (x, y) in [(13, 6), (6, 13)] {
+            let crosses = (topology == Topology::CylinderX) == (x == 13);
+            let generations = if crosses { 16 } else { 40 };
+            let mut torus = Simulation::new(16, 16);
+            torus.grid.topology = Topology::Torus;
+            let _ = torus.stamp(&GliderPattern, Transform::IDENTITY, 0, x, y);
+            let mut sim = Simulation::new(16, 16);
+            sim.grid.topology = topology;
+            sim.live = torus.live.clone();
+            let start = sim.live.clone();
+            for _ in 0..generations {
+                torus.next_generation();
+                sim.next_generation();
+            }
+            let result = if crosses {
+                same_board(to_ascii(&torus.grid, &torus.live), to_ascii(&sim.grid, &sim.live))
+            } else if sim.live == torus.live || sim.grid.next_generation(&sim.live) != sim.live {
+                Err(format!("the glider didn't stop at the wall:\n{}", to_ascii(&sim.grid, &sim.live)))
+            } else {
+                differential_check(&sim.grid, start, generations).map_err(|m| m.to_string())
+            };
+            let name = if crosses { "glider crosses the seam" } else { "glider dies at the wall" };
+            failures += report(&format!("{} ({})", name, topology), result);
+        }
+    }
+
+    // Stamping at (-1, -1) puts two of the glider's cells off the top-left corner
+    for (topology, expected) in [(Topology::Bounded, ".#../##../..../...."), (Topology::Torus, ".#../##.#/..../#...")] {
+        let mut sim = Simulation::new(4, 4);
+        sim.grid.topology = topology;
+        let _ = sim.apply_pattern(&GliderPattern, -1, -1);
+        let (_, want) = from_ascii(expected, topology);
+        let result = same_board(to_ascii(&sim.grid, &want), to_ascii(&sim.grid, &sim.live));
+        failures += report(&format!("glider stamped off the corner ({})", topology), result);
+    }
+
+    // A phase-advanced stamp evolves the pattern on its own: four phases of a glider are the
+    // glider moved one cell diagonally, whatever else is on the board or however close the edge is
+    for (rotation, topology) in [(0, Topology::Bounded), (1, Topology::Torus)] {
+        let transform = Transform { rotation, flipped: false };
+        let mut reference = Simulation::new(10, 10);
+        reference.grid.topology = topology;
+        let _ = reference.stamp(&GliderPattern, transform, 0, 1, 1);
+        for _ in 0..4 {
+            reference.next_generation();
+        }
+        let mut sim = Simulation::new(10, 10);
+        sim.grid.topology = topology;
+        sim.add_cell(8, 8);
+        let _ = sim.stamp(&GliderPattern, transform, 4, 1, 1);
+        sim.live.remove(&Position::new(8, 8));
+        let result = same_board(to_ascii(&sim.grid, &reference.live), to_ascii(&sim.grid, &sim.live));
+        failures += report(&format!("glider stamped at phase 4, {} ({})", transform.name(), topology), result);
+    }
+
+    // Stamping into a full board: every glider cell overlaps, and a clearing stamp empties its
+    // 3x3 box (cut at a wall, or carried across a wrapped corner) before placing the glider
+    for topology in [Topology::Bounded, Topology::Torus] {
+        let mut sim = Simulation::new(10, 10);
+        sim.grid.topology = topology;
+        sim.live = (0..10).flat_map(|y| (0..10).map(move |x| Position::new(x, y))).collect();
+        let result = sim.pattern_cells(&GliderPattern, Transform::IDENTITY, 0, 8, 8)
+            .map_err(|err| err.to_string())
+            .and_then(|cells| {
+                let footprint = sim.footprint(&cells, 8, 8);
+                let boxed = if topology.wraps() { footprint.len() == 9 } else { footprint.len() < 9 };
+                if cells.is_empty() || sim.overlap(&cells) != cells.len() || !boxed || !cells.is_subset(&footprint) {
+                    return Err(format!("{} overlapping of {}, footprint of {}", sim.overlap(&cells), cells.len(), footprint.len()));
+                }
+                let _ = sim.stamp_clean(&GliderPattern, Transform::IDENTITY, 0, 8, 8);
+                let cleared: Vec<_> = footprint.difference(&cells).filter(|p| sim.live.contains(p)).collect();
+                if !cleared.is_empty() || sim.live.len() != 100 - footprint.len() + cells.len() {
+                    return Err(format!("{} live cells left in the footprint, {} on the board", cleared.len(), sim.live.len()));
+                }
+                Ok(())
+            });
+        failures += report(&format!("stamp overlap and clear footprint ({})", topology), result);
+    }
+    failures
+}
+
+/// Position arithmetic and `Grid::neighbors_of` on random grids and cells: 3 neighbors in a
+/// corner, 5 along an edge and 8 inside a bounded grid at least 2 cells across, fewer on a
+/// single row or column, always 8 on a torus, and every one of them on the board
+fn check_neighbors(seed: u64) -> u32 {
+    let mut rng = Rng::new(seed);
+    let result = (|| {
+        for _ in 0..2000 {
+            let (w, h) = (rng.gen_range(1, 13), rng.gen_range(1, 13));
+            let p = Position::new(rng.gen_range(0, w), rng.gen_range(0, h));
+            let q = Position::new(rng.gen_range(-50, 50), rng.gen_range(-50, 50));
+            let d = Offset(rng.gen_range(-50, 50), rng.gen_range(-50, 50));
+            if p + d - d != p || q + (p - q) != p || (p + d) - p != d {
+                return Err(format!("arithmetic on {:?}, {:?} and {:?} doesn't add up", p, q, d));
+            }
+            if p.neighbors().iter().any(|&n| { let Offset(dx, dy) = n - p; dx.abs().max(dy.abs()) != 1 }) {
+                return Err(format!("{:?} has a neighbor that isn't one step away", p));
+            }
+
+            let mut grid = Grid::new(w, h);
+            let got: Vec<Position> = grid.neighbors_of(p).collect();
+            // Cells of the 3x3 block around `p` that are on the board, less `p` itself
+            let span = |v: i32, size: i32| (v + 1).min(size - 1) - (v - 1).max(0) + 1;
+            let want = (span(p.x(), w) * span(p.y(), h) - 1) as usize;
+            let edges = [p.x() == 0, p.x() == w - 1, p.y() == 0, p.y() == h - 1].iter().filter(|&&e| e).count();
+            let placed = match edges { 0 => 8, 1 => 5, _ => 3 };
+            if got.len() != want || (w >= 2 && h >= 2 && got.len() != placed) {
+                return Err(format!("{:?} on a bounded {}x{} grid has {} neighbors, expected {}", p, w, h, got.len(), want));
+            }
+            if let Some(n) = got.iter().find(|n| !grid.in_bounds(n.x(), n.y())) {
+                return Err(format!("{:?} on a bounded {}x{} grid has {:?} as a neighbor", p, w, h, n));
+            }
+
+            grid.topology = Topology::Torus;
+            let got: Vec<Position> = grid.neighbors_of(p).collect();
+            if got.len() != 8 || got.iter().any(|n| !grid.in_bounds(n.x(), n.y())) {
+                return Err(format!("{:?} on a {}x{} torus has neighbors {:?}", p, w, h, got));
+            }
+        }
+        Ok(())
+    })();
+    report("neighbors_of on bounded grids and tori", result)
+}
+
+/// Shifted tori: the text forms and the topology cycle, gliders crossing each seam, the reference stepper on random
+/// soups, offsets across the seam, and the shift surviving a delta log with a bookmark
+fn check_torus_shift(seed: u64) -> u32 {
+    let mut failures = 0;
+    failures += report("torus shift text forms", (|| {
+        for text in ["x+3", "y-2", "off"] {
+            let torus = Topology::parse_shift(text)?;
+            if torus.shift_label() != text || Topology::parse(&torus.to_string())? != torus {
+                return Err(format!("'{}' came back as '{}' and '{}'", text, torus.shift_label(), torus));
+            }
+        }
+        let shifted = Topology::parse("torus:x+3")?;
+        if shifted != (Topology::TorusShifted { dx_on_wrap_y: 3, dy_on_wrap_x: 0 }) || Topology::parse_shift("x+0")? != Topology::Torus {
+            return Err(format!("torus:x+3 read as {:?}", shifted));
+        }
+        if !shifted.is_torus() || !shifted.wraps_x() || !shifted.wraps_y() || shifted.next() != Topology::CylinderX {
+            return Err("a shifted torus doesn't wrap or cycle as a torus".to_string());
+        }
+        match ["z+1", "x", "y+two", "torus:", "cylinder-x:x+1"].iter().find(|t| Topology::parse_shift(t).is_ok() || Topology::parse(t).is_ok()) {
+            Some(text) => Err(format!("'{}' was accepted", text)),
+            None => Ok(()),
+        }
+    })());
+
+    failures += report("torus shift from older records and the console", (|| {
+        let old = RunSetup::parse("seed=1 density=0.3 size=8x8 topology=torus shift=y-2 rule=B3/S23 pattern=Random")?;
+        if old.topology != Topology::torus_shifted(0, -2) || !old.to_string().contains("topology=torus:y-2 ") {
+            return Err(format!("an older record read as {} and wrote '{}'", old.topology, old));
+        }
+        let mut sim = Simulation::new(8, 8);
+        let mut view = BoardView::new(4);
+        let reply = console::execute(&console::parse("shift x+3")?, &mut sim, &mut view)?.0;
+        if sim.grid.topology != Topology::torus_shifted(3, 0) || !reply.contains("now wrap") {
+            return Err(format!("'shift x+3' on a bounded board left {} ('{}')", sim.grid.topology, reply));
+        }
+        console::execute(&console::parse("shift off")?, &mut sim, &mut view)?;
+        match sim.grid.topology {
+            Topology::Torus => Ok(()),
+            other => Err(format!("'shift off' left {}", other)),
+        }
+    })());
+
+    // A glider on a 12x12 torus against the same glider on an open plane: cells past the right
+    // edge come back on the left `dy_on_wrap_x` rows lower, cells past the bottom come back on
+    // the top `dx_on_wrap_y` columns along
+    type Onto = fn(i32, i32) -> Position;
+    let cases: [(&str, i32, i32, Onto); 2] = [
+        ("y+5", 8, 2, |x, y| {
+            let (x, y) = if x >= 12 { (x - 12, y + 5) } else { (x, y) };
+            Position::new(x, y.rem_euclid(12))
+        }),
+        ("x-3", 2, 8, |x, y| {
+            let (x, y) = if y >= 12 { (x - 3, y - 12) } else { (x, y) };
+            Position::new(x.rem_euclid(12), y)
+        }),
+    ];
+    for (text, x, y, onto) in cases {
+        let result = (|| {
+            let mut torus = Simulation::new(12, 12);
+            torus.grid.topology = Topology::parse_shift(text)?;
+            let mut plane = Simulation::new(40, 40);
+            for sim in [&mut torus, &mut plane] {
+                sim.stamp(&GliderPattern, Transform::IDENTITY, 0, x, y).map_err(|e| e.to_string())?;
+            }
+            for generation in 1..=24 {
+                torus.next_generation();
+                plane.next_generation();
+                let want: HashSet<Position> = plane.live.iter().map(|p| onto(p.x(), p.y())).collect();
+                if generation % 8 == 0 && want != torus.live {
+                    return same_board(to_ascii(&torus.grid, &want), to_ascii(&torus.grid, &torus.live))
+                        .map_err(|e| format!("generation {}\n{}", generation, e));
+                }
+            }
+            Ok(())
+        })();
+        failures += report(&format!("glider across a torus shifted {}", text), result);
+    }
+
+    let mut ok = true;
+    for (w, h, text) in [(7, 5, "x+3"), (7, 5, "y-2"), (12, 9, "x-13"), (12, 9, "y+4"), (2, 2, "x+1")] {
+        let mut grid = Grid::new(w, h);
+        grid.topology = Topology::parse_shift(text).unwrap();
+        for run in 0..4 {
+            let soup = random_soup(&grid, 0.35, seed.wrapping_add(run));
+            if let Err(mismatch) = differential_check(&grid, soup, VERIFY_GENERATIONS) {
+                println!("FAIL {}x{} torus shifted {} seed {}: {}", w, h, text, seed.wrapping_add(run), mismatch);
+                failures += 1;
+                ok = false;
+            }
+        }
+    }
+    if ok {
+        println!("ok   shifted tori match the reference stepper");
+    }
+
+    failures += report("offsets across a shifted seam", (|| {
+        let mut grid = Grid::new(10, 10);
+        grid.topology = Topology::parse_shift("y+3")?;
+        let pairs = [((9, 0), (0, 3), (1, 0)), ((0, 3), (9, 0), (-1, 0)), ((5, 9), (5, 0), (0, 1)), ((9, 9), (0, 2), (1, 0))];
+        for ((x, y), (tx, ty), want) in pairs {
+            let got = grid.offset(x, y, Position::new(tx, ty));
+            if got != want {
+                return Err(format!("({}, {}) to ({}, {}) gave {:?}, expected {:?}", x, y, tx, ty, got, want));
+            }
+        }
+        Ok(())
+    })());
+
+    failures += report("torus shift saved in delta logs and bookmarks", (|| {
+        let mut sim = Simulation::new(16, 12);
+        sim.grid.topology = Topology::parse_shift("x-5")?;
+        sim.live = random_soup(&sim.grid, 0.35, seed);
+        let mut log = DeltaLog::new(&sim);
+        log.bookmarks.push(Bookmark::capture(&sim, "start"));
+        sim.next_generation();
+        log.record(&sim);
+        let decoded = DeltaLog::from_bytes(&log.to_bytes())?;
+        if decoded.grid().topology != sim.grid.topology || decoded.bookmarks[0].grid().topology != sim.grid.topology {
+            return Err(format!("saved {}, loaded {} and {}", sim.grid.topology, decoded.grid().topology, decoded.bookmarks[0].grid().topology));
+        }
+        Ok(())
+    })());
+    failures
+}
+
+/// Restoring a bookmark brings back the exact board, generation and future; bookmarks survive
+/// a save and load, and the least recently used one makes room when the list is full
+fn check_bookmarks(seed: u64) -> u32 {
+    let mut failures = 0;
+    for topology in Topology::ALL {
+        let result = (|| {
+            let mut sim = Simulation::new(64, 48);
+            sim.grid.topology = topology;
+            sim.edge_spawn = true;
+            sim.seed = seed;
+            sim.live = random_soup(&sim.grid, 0.35, seed);
+            for _ in 0..30 {
+                sim.next_generation();
+            }
+            let bookmark = Bookmark::capture(&sim, "soup");
+            let (marked, generation) = (sim.live.clone(), sim.generation);
+            let mut ahead = bookmark.board();
+            ahead.edge_spawn = true;
+            for _ in 0..40 {
+                sim.next_generation();
+                ahead.next_generation();
+            }
+            // Branch off an edited, resized, reseeded board
+            sim.seed ^= 1;
+            sim.grid = Grid::new(20, 20);
+            sim.grid.topology = topology.next();
+            sim.crop_to(sim.grid.bounds());
+            bookmark.restore(&mut sim);
+            if sim.generation != generation || sim.live != marked || sim.grid.topology != topology {
+                return Err(format!("restored generation {} with {} cells, expected {} with {}", sim.generation, sim.live.len(), generation, marked.len()));
+            }
+            for _ in 0..40 {
+                sim.next_generation();
+            }
+            same_board(to_ascii(&ahead.grid, &ahead.live), to_ascii(&sim.grid, &sim.live))
+        })();
+        failures += report(&format!("bookmark restore replays the run ({})", topology), result);
+    }
+
+    let result = (|| {
+        let mut sim = Simulation::new(32, 24);
+        sim.live = random_soup(&sim.grid, 0.35, seed);
+        let mut log = DeltaLog::new(&sim);
+        log.bookmarks = vec![Bookmark::capture(&sim, "start \u{2014} \"quoted\""), Bookmark::capture(&sim, "")];
+        let bytes = log.to_bytes();
+        let decoded = DeltaLog::from_bytes(&bytes)?;
+        if decoded.bookmarks != log.bookmarks {
+            return Err("bookmarks changed through a save and load".to_string());
+        }
+        // A version 1 log: the same layout, without the torus shift after the header's wrap
+        // flag or the bookmark count, schedule and notes after the deltas
+        log.bookmarks.clear();
+        let mut old = log.to_bytes();
+        old.truncate(old.len() - 4 - 2 - 4);
+        old.drain(10..18);
+        old[4] = 1;
+        match DeltaLog::from_bytes(&old) {
+            Ok(decoded) if decoded.bookmarks.is_empty() && decoded.reference == log.reference => Ok(()),
+            Ok(_) => Err("version 1 log loaded with the wrong contents".to_string()),
+            Err(err) => Err(format!("version 1 log no longer loads: {}", err)),
+        }
+    })();
+    failures += report("bookmarks saved with the delta log", result);
+
+    let result = (|| {
+        let mut sim = Simulation::new(16, 16);
+        let mut bookmarks = Bookmarks::default();
+        for generation in 0..BOOKMARK_MAX as u64 {
+            sim.generation = generation;
+            bookmarks.add(Bookmark::capture(&sim, Bookmark::auto_label(generation)), ColorTheme::Classic);
+        }
+        bookmarks.touch(0);
+        sim.generation = 99;
+        let evicted = bookmarks.add(Bookmark::capture(&sim, "new"), ColorTheme::Classic);
+        if bookmarks.len() != BOOKMARK_MAX || evicted.as_ref().map(|b| b.generation) != Some(1) {
+            return Err(format!("{} kept, evicted {:?}", bookmarks.len(), evicted.map(|b| b.label)));
+        }
+        let mut stats = RunStats::default();
+        stats.observe(10, 5);
+        stats.observe(50, 40);
+        stats.observe(60, 0);
+        stats.rewind(20, 7);
+        stats.observe(21, 8);
+        if stats.peak != 8 || stats.peak_generation != 21 || stats.extinct_at.is_some() {
+            return Err(format!("after restoring: peak {} at {}, extinct {:?}", stats.peak, stats.peak_generation, stats.extinct_at));
+        }
+        Ok(())
+    })();
+    failures += report("bookmark eviction and run statistics", result);
+    failures
+}
+
+/// A lethal margin of 0, or any margin on a torus, must match the engine exactly. With one set,
+/// nothing survives in the margin, a blinker well inside is untouched and the board plays out
+/// as a grid shrunk by the margin.
+fn check_lethal_margin(seed: u64) -> u32 {
+    let mut failures = 0;
+    for topology in [Topology::Bounded, Topology::Torus] {
+        let result = (|| {
+            let mut off = Simulation::new(64, 48);
+            off.grid.topology = topology;
+            off.live = random_soup(&off.grid, 0.35, seed);
+            let mut torus = Simulation::new(64, 48);
+            torus.grid.topology = Topology::Torus;
+            torus.lethal_margin = 3;
+            torus.live = off.live.clone();
+            let mut bare = off.live.clone();
+            let mut bare_torus = off.live.clone();
+            for generation in 1..=VERIFY_GENERATIONS {
+                off.next_generation();
+                torus.next_generation();
+                bare = off.grid.next_generation(&bare);
+                bare_torus = torus.grid.next_generation(&bare_torus);
+                if off.live != bare || torus.live != bare_torus {
+                    return Err(format!("generation {} differs from the engine without a margin", generation));
+                }
+            }
+            Ok(())
+        })();
+        failures += report(&format!("lethal margin 0 matches the engine ({})", topology), result);
+    }
+
+    let result = (|| {
+        let mut sim = Simulation::new(64, 48);
+        sim.lethal_margin = 4;
+        sim.live = random_soup(&sim.grid, 0.35, seed);
+        for generation in 1..=VERIFY_GENERATIONS {
+            sim.next_generation();
+            if let Some(p) = sim.live.iter().find(|p| sim.in_lethal_margin(**p)) {
+                return Err(format!("generation {}: ({}, {}) alive in the margin", generation, p.x(), p.y()));
+            }
+        }
+        let (grid, blinker) = from_ascii("......../......../......../...###../......../......../........", Topology::Bounded);
+        let mut sim = Simulation::new(grid.width, grid.height);
+        sim.lethal_margin = 2;
+        sim.live = blinker.clone();
+        sim.next_generation();
+        sim.next_generation();
+        if sim.live != blinker {
+            return Err("a blinker clear of the margin changed".to_string());
+        }
+        // Cells in the margin are dead whenever a generation is computed, so it acts exactly as a
+        // wall `k` cells in: a glider runs into it and leaves the same block as on the smaller grid
+        let k = 3;
+        let mut sim = Simulation::new(24, 24);
+        sim.lethal_margin = k;
+        sim.apply_pattern(&GliderPattern, 8, 8).map_err(|e| e.to_string())?;
+        let mut inner = Simulation::new(24 - 2 * k, 24 - 2 * k);
+        inner.live = sim.live.iter().map(|p| Position::new(p.x() - k, p.y() - k)).collect();
+        for _ in 0..120 {
+            sim.next_generation();
+            inner.next_generation();
+        }
+        let shifted: HashSet<Position> = inner.live.iter().map(|p| Position::new(p.x() + k, p.y() + k)).collect();
+        same_board(to_ascii(&sim.grid, &shifted), to_ascii(&sim.grid, &sim.live))
+    })();
+    failures += report("lethal margin culls its band", result);
+    failures
+}
+
+/// Rulestrings parse to their canonical form, other rules match the reference stepper on both
+/// topologies (including S0, where isolated cells survive), and B0 is refused
+fn check_rules(seed: u64) -> u32 {
+    let mut failures = 0;
+    let result = (|| {
+        let cases = [("B3/S23", "B3/S23"), ("b36/s23", "B36/S23"), ("23/3", "B3/S23"), ("S23/B36", "B36/S23"), ("B2/S", "B2/S")];
+        for (text, canonical) in cases {
+            let rule = Rule::parse(text)?;
+            if rule.to_string() != canonical {
+                return Err(format!("'{}' read as {}, expected {}", text, rule, canonical));
+            }
+        }
+        if let Some(bad) = ["B9/S23", "life", "B3S23", "B3/S2x"].into_iter().find(|t| Rule::parse(t).is_ok()) {
+            return Err(format!("'{}' was accepted", bad));
+        }
+        let mut sim = Simulation::new(8, 8);
+        if sim.set_rule(Rule::parse("B03/S23")?).is_ok() || sim.grid.rule != Rule::LIFE {
+            return Err(format!("B0 was accepted; the board now runs {}", sim.grid.rule));
+        }
+        let verdict = |alive, n| CellVerdict::new(alive, n, Rule::LIFE).clause;
+        if (verdict(true, 1), verdict(true, 4), verdict(false, 3)) != ("dies (underpopulation)", "dies (overpopulation)", "birth") {
+            return Err("Life's clauses changed".to_string());
+        }
+        let fate = |alive, n| CellVerdict::new(alive, n, Rule::LIFE).fate;
+        if (fate(false, 3), fate(true, 2), fate(true, 4), fate(false, 2)) != (CellFate::Born, CellFate::Survives, CellFate::Dies, CellFate::StaysDead) {
+            return Err("Life's fates changed".to_string());
+        }
+        Ok(())
+    })();
+    failures += report("rulestrings and B0", result);
+
+    for text in ["B36/S23", "B2/S", "B3678/S34678", "B1/S012345678"] {
+        for topology in [Topology::Bounded, Topology::Torus] {
+            let mut grid = Grid::new(24, 20);
+            grid.topology = topology;
+            grid.rule = Rule::parse(text).unwrap_or_default();
+            let result = differential_check(&grid, random_soup(&grid, 0.2, seed), 30).map_err(|m| m.to_string());
+            failures += report(&format!("rule {} matches the reference ({})", text, topology), result);
+        }
+    }
+    failures
+}
+
+/// Each text format is recognized by its content and reads the same glider; web pages are
+/// refused; LifeWiki links resolve to the pattern's RLE file. No network is used.
+fn check_pattern_formats() -> u32 {
+    let result = (|| {
+        let glider = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+        let texts = [
+            ("RLE", "#N Glider\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n"),
+            ("plaintext", "!Name: Glider\n.O.\n..O\nOOO\n"),
+            ("Life 1.05", "#Life 1.05\n#D Glider\n#P -1 -1\n.*.\n..*\n***\n"),
+            ("Life 1.06", "#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1\n"),
+        ];
+        for (format, text) in texts {
+            let pattern = parse_pattern_text(text).map_err(|e| format!("{}: {}", format, e))?;
+            let mut cells = pattern.cells.clone();
+            cells.sort_by_key(|&(x, y)| (y, x));
+            if cells != glider || (pattern.width, pattern.height) != (3, 3) {
+                return Err(format!("{} read as {:?} in {}x{}", format, cells, pattern.width, pattern.height));
+            }
+        }
+        if parse_pattern_text("<!DOCTYPE html>\n<html><body>Not found</body></html>").is_ok() {
+            return Err("a web page was read as a pattern".to_string());
+        }
+        let resolved = net::resolve("https://conwaylife.com/wiki/Gosper_glider_gun")?;
+        if resolved != "https://conwaylife.com/patterns/gosperglidergun.rle" {
+            return Err(format!("wiki link resolved to {}", resolved));
+        }
+        if net::resolve("ftp://example.org/glider.rle").is_ok() {
+            return Err("a non-http address was accepted".to_string());
+        }
+        let named = net::parse_reply("https://example.org/p/glider.cells?raw=1", b".O.\n..O\nOOO\n")?;
+        if named.name != "glider" {
+            return Err(format!("unnamed download was called '{}'", named.name));
+        }
+        Ok(())
+    })();
+    report("pattern text formats and URL handling", result)
+}
+
+/// Macrocell trees that reuse one node at every level: a glider in the far corner of a tree 40
+/// levels deep reads back as the glider, and a full tree of that shape is refused as too many
+/// cells before any of it is expanded, rather than walking every empty or full quadrant.
+fn check_macrocell() -> u32 {
+    let result = (|| {
+        // Node 1 is an empty leaf, node 2 a glider; each level then pairs an empty node with one
+        // that keeps the glider in its south-east quadrant
+        let mut text = "[M2]\n$\n.*$..*$***$\n4 1 1 1 1\n4 1 1 1 2\n".to_string();
+        for level in 5..=40 {
+            let (empty, glider) = (2 * level - 7, 2 * level - 6);
+            text.push_str(&format!("{} {} {} {} {}\n{} {} {} {} {}\n", level, empty, empty, empty, empty, level, empty, empty, empty, glider));
+        }
+        let pattern = parse_pattern_text(&text).map_err(|e| format!("deep tree: {}", e))?;
+        let mut cells = pattern.cells.clone();
+        cells.sort_by_key(|&(x, y)| (y, x));
+        if cells != [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] || (pattern.width, pattern.height) != (3, 3) {
+            return Err(format!("deep tree read as {:?} in {}x{}", cells, pattern.width, pattern.height));
+        }
+
+        let mut full = "[M2]\n********$********$********$********$********$********$********$********$\n".to_string();
+        for level in 4..=40 {
+            let below = level - 3;
+            full.push_str(&format!("{} {} {} {} {}\n", level, below, below, below, below));
+        }
+        match parse_pattern_text(&full) {
+            Err(GolError::Parse { .. }) => Ok(()),
+            Err(e) => Err(format!("full tree failed with {}", e)),
+            Ok(p) => Err(format!("full tree read as {} cells", p.cells.len())),
+        }
+    })();
+    report("macrocell trees are expanded without revisiting shared nodes", result)
+}
+
+/// RLE runs and positions past what an `i32` holds are parse errors, not overflows, while a cell
+/// at the far end of the range still reads
+fn check_rle_limits() -> u32 {
+    let result = (|| {
+        for data in ["9223372036854775807bo!", "99999999999999999999o!", "2147483648o!", "2147483647b2o!", "2147483647$2147483647$o!"] {
+            match pattern_io::parse_rle(&format!("x = 1, y = 1\n{}\n", data)) {
+                Err(GolError::Parse { .. }) => {}
+                other => return Err(format!("'{}' read as {:?}", data, other.map(|p| p.cells.len()))),
+            }
+        }
+        let far = pattern_io::parse_rle("x = 1, y = 1\n2147483646bo2147483646$o!\n").map_err(|e| e.to_string())?;
+        if far.cells != [(2147483646, 0), (0, 2147483646)] {
+            return Err(format!("cells at the edge of the range read as {:?}", far.cells));
+        }
+        Ok(())
+    })();
+    report("RLE run counts and positions stay within range", result)
+}
+
+/// Picture import on tiny fixtures: a PNG whose background is transparent black reads as white,
+/// a BMP's gray pixel follows the threshold, inverting swaps live and dead, and a wide picture
+/// shrinks to fit the grid. Without the `image-import` feature every import says so.
+fn check_image_import() -> u32 {
+    const PNG: &[u8] = include_bytes!("../fixtures/glider-alpha.png");
+    const BMP: &[u8] = include_bytes!("../fixtures/glider.bmp");
+    let mut failures = 0;
+    if !image::AVAILABLE {
+        let result = match (image::from_image(PNG, 7, 7, ImageOptions::default()), load_pattern_file("glider.png".as_ref())) {
+            (Err(GolError::Unsupported(_)), Err(GolError::Unsupported(_))) => Ok(()),
+            other => Err(format!("importing without the feature gave {:?}", other)),
+        };
+        return report("picture import needs the image-import feature", result);
+    }
+    let cells = |list: &[(i32, i32)]| list.iter().map(|&(x, y)| Position::new(x, y)).collect::<HashSet<_>>();
+    let glider = cells(&[(3, 2), (4, 3), (2, 4), (3, 4), (4, 4)]);
+    let with_corner = cells(&[(3, 2), (4, 3), (2, 4), (3, 4), (4, 4), (0, 6)]);
+    let at = |threshold: f32, invert: bool| ImageOptions { threshold, invert };
+    let cases = [
+        ("transparent PNG", PNG, at(0.5, false), glider.clone()),
+        ("transparent PNG, half-covered corner", PNG, at(0.7, false), with_corner.clone()),
+        ("BMP", BMP, at(0.5, false), with_corner.clone()),
+        ("BMP, gray corner above the threshold", BMP, at(0.3, false), glider.clone()),
+        ("BMP inverted", BMP, at(0.5, true), (0..7).flat_map(|y| (0..7).map(move |x| Position::new(x, y))).filter(|p| !with_corner.contains(p)).collect()),
+    ];
+    for (name, bytes, options, want) in cases {
+        let grid = Grid::new(7, 7);
+        let result = image::from_image(bytes, 7, 7, options).map_err(|e| e.to_string())
+            .and_then(|live| same_board(to_ascii(&grid, &want), to_ascii(&grid, &live)));
+        failures += report(&format!("picture import: {}", name), result);
+    }
+
+    let result = (|| {
+        // 400x100, the left half black: fitted to 40x40 it is 40x10, centered, with the left half live
+        let bytes: Vec<u8> = (0..100).flat_map(|_| (0..400).flat_map(|x| if x < 200 { [0, 0, 0, 255] } else { [255; 4] })).collect();
+        let png = encode_png(&Image { bytes, width: 400, height: 100 });
+        let picture = Picture::decode(&png, 40, 40).map_err(|e| e.to_string())?;
+        if (picture.width, picture.height) != (40, 10) {
+            return Err(format!("fitted to {}x{}, expected 40x10", picture.width, picture.height));
+        }
+        let live = picture.place(40, 40, ImageOptions::default());
+        if live.len() != 200 || live.iter().any(|p| p.x() >= 20 || !(15..25).contains(&p.y())) {
+            return Err(format!("{} live cells, not the 20x10 left half", live.len()));
+        }
+        // Placing the picture as a pattern at the center puts the cells where the preview shows them
+        let mut sim = Simulation::new(40, 40);
+        sim.apply_pattern(&picture.to_pattern("wide", ImageOptions::default()), 20, 20).map_err(|e| e.to_string())?;
+        if sim.live != live {
+            return Err("the placed pattern and the preview disagree".to_string());
+        }
+        match (Picture::decode(b"not a picture", 40, 40), Picture::decode(&png, 0, 40)) {
+            (Err(GolError::Parse { .. }), Err(GolError::Invalid { .. })) => Ok(()),
+            other => Err(format!("bad input gave {:?}", other)),
+        }
+    })();
+    failures += report("picture import shrinks to fit the grid", result);
+    failures
+}
+
+/// A reloaded themes file moves a showing custom theme to its new colors and is refused whole
+/// when a line is bad; watched paths notice new and edited files, and a script that fails to
+/// reload leaves the running version
+fn check_live_reload() -> u32 {
+    let mut failures = 0;
+    let result = (|| {
+        let sea = "Sea = #001020 #40c0ff #102030 #80a0c0";
+        let before = CustomThemes::parse_strict(sea)?;
+        let after = CustomThemes::parse_strict(&format!("# edited\n\n{}\nSea = #001020 #ffc040 #102030 #80a0c0\n", sea))?;
+        let showing = ColorTheme::Custom(before.entries[0].1);
+        match after.updated(&before, showing) {
+            ColorTheme::Custom(p) if p.cell == [255, 192, 64] => {}
+            other => return Err(format!("the showing theme became {:?}", other)),
+        }
+        let unsaved = ColorTheme::Custom(Palette { cell: [1, 2, 3], ..before.entries[0].1 });
+        if after.updated(&before, unsaved) != unsaved || after.updated(&before, ColorTheme::Neon) != ColorTheme::Neon {
+            return Err("a theme without a saved name changed".to_string());
+        }
+        let half_edited = format!("{}\nSea = #001020 #ffc0\n", sea);
+        match CustomThemes::parse_strict(&half_edited) {
+            Err(GolError::Parse { at: Location::Line(2), .. }) => {}
+            other => return Err(format!("a bad line read as {:?}", other.map(|t| t.entries.len()))),
+        }
+        if CustomThemes::parse(&half_edited) != before {
+            return Err("the lenient parser dropped the good line".to_string());
+        }
+        Ok(())
+    })();
+    failures += report("reloaded themes", result);
+
+    #[cfg(all(feature = "watch", not(target_arch = "wasm32")))]
+    {
+        let result = (|| {
+            let dir = std::env::temp_dir().join(format!("conways-game-of-life-watch-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+            let mut watched = Watched::new(&dir);
+            let mut now = 0.0;
+            let mut step = |watched: &mut Watched| {
+                now += 1.0;
+                watched.changed(now)
+            };
+            let file = dir.join("glider.cells");
+            let steps = [
+                step(&mut watched),
+                { crate::error::write(&file, ".O.\n..O\nOOO\n")?; step(&mut watched) },
+                step(&mut watched),
+                { crate::error::write(&file, "OO\nOO\n")?; step(&mut watched) },
+                { std::fs::remove_file(&file).map_err(|e| e.to_string())?; step(&mut watched) },
+            ];
+            let _ = std::fs::remove_dir_all(&dir);
+            if steps != [false, true, false, true, true] {
+                return Err(format!("changes seen as {:?}", steps));
+            }
+            Ok(())
+        })();
+        failures += report("watched directories notice new, edited and removed files", result);
+    }
+
+    #[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+    {
+        let result = (|| {
+            let path = std::env::temp_dir().join(format!("conways-game-of-life-reload-{}.gol", std::process::id()));
+            let mut sim = Simulation::new(20, 20);
+            crate::error::write(&path, "fn on_generation() { set(1, 1, true); }")?;
+            let mut hook = ScriptHook::load(&path, &mut sim, 30.0)?;
+            crate::error::write(&path, "fn on_generation() { set(1, 1, ; }")?;
+            let message = hook.reload_now(&mut sim, 30.0);
+            let _ = std::fs::remove_file(&path);
+            if !message.starts_with("Kept the running version") || !hook.enabled() {
+                return Err(format!("a broken save left the hook {} with '{}'", if hook.enabled() { "on" } else { "off" }, message));
+            }
+            hook.after_generation(&mut sim, 30.0);
+            if !sim.live.contains(&Position::new(1, 1)) {
+                return Err("the kept version didn't run".to_string());
+            }
+            Ok(())
+        })();
+        failures += report("a script that fails to reload keeps running", result);
+    }
+    failures
+}
+
+/// Bounding box (left, top, right, bottom) of the live cells
+fn live_bounds(live: &HashSet<Position>) -> Option<(i32, i32, i32, i32)> {
+    live.iter().fold(None, |b, p| {
+        let (l, t, r, btm) = b.unwrap_or((p.x(), p.y(), p.x(), p.y()));
+        Some((l.min(p.x()), t.min(p.y()), r.max(p.x()), btm.max(p.y())))
+    })
+}
+
+/// A glider stamped half off each edge and a corner of a bounded board reports what it would
+/// lose; clipping keeps the cells inside, expanding grows just those walls and keeps the rest of
+/// the board in place. A cylinder only clips at its walls, and `--on-clip` makes the same choices.
+fn check_clipping() -> u32 {
+    let mut failures = 0;
+    let glider = pattern_io::parse_rle("x = 3, y = 3\nbo$2bo$3o!").expect("valid RLE");
+    let shape = |cells: &HashSet<Position>| {
+        let (left, top, _, _) = live_bounds(cells).unwrap_or_default();
+        let mut offsets: Vec<(i32, i32)> = cells.iter().map(|p| (p.x() - left, p.y() - top)).collect();
+        offsets.sort_unstable();
+        offsets
+    };
+    let block = [(5, 5), (6, 5), (5, 6), (6, 6)];
+    // Anchor and the edges the glider hangs over; it spans one cell either side of the anchor
+    let cases = [((0, 5), "left"), ((5, 0), "top"), ((11, 5), "right"), ((5, 11), "bottom"), ((0, 11), "left and bottom")];
+    for ((x, y), edges) in cases {
+        let result = (|| {
+            let board = || {
+                let mut sim = Simulation::new(12, 12);
+                sim.live.extend(block.map(|(x, y)| Position::new(x, y)));
+                sim
+            };
+            let mut sim = board();
+            let (cells, overhang) = sim.placement(&glider, Transform::IDENTITY, 0, x, y).map_err(|e| e.to_string())?;
+            let lost = glider.cells.iter().filter(|&&(cx, cy)| !sim.grid.in_bounds(x - 1 + cx, y - 1 + cy)).count();
+            if overhang.cells != lost || cells.len() + lost != 5 || overhang.edges() != edges {
+                return Err(format!("overhang {:?} ({} edges), expected {} cells lost past the {}", overhang, overhang.edges(), lost, edges));
+            }
+            // Clip: what fits goes on, nothing else moves
+            sim.stamp(&glider, Transform::IDENTITY, 0, x, y).map_err(|e| e.to_string())?;
+            if sim.live.len() != 4 + cells.len() {
+                return Err(format!("clipping left {} cells", sim.live.len()));
+            }
+            // Expand: the whole glider lands, the block keeps its place relative to it
+            let mut sim = board();
+            let (dx, dy) = sim.expand_for(overhang, EXPAND_MARGIN).map_err(|e| e.to_string())?;
+            sim.stamp(&glider, Transform::IDENTITY, 0, x + dx, y + dy).map_err(|e| e.to_string())?;
+            let grown = |n: i32| if n > 0 { n + EXPAND_MARGIN } else { 0 };
+            let want = (12 + grown(overhang.left) + grown(overhang.right), 12 + grown(overhang.top) + grown(overhang.bottom));
+            if (sim.grid.width, sim.grid.height) != want || (dx, dy) != (grown(overhang.left), grown(overhang.top)) {
+                return Err(format!("grew to {}x{} moving ({}, {}), expected {}x{}", sim.grid.width, sim.grid.height, dx, dy, want.0, want.1));
+            }
+            let mut open = Simulation::new(64, 64);
+            open.live.extend(block.map(|(bx, by)| Position::new(bx + 20, by + 20)));
+            open.stamp(&glider, Transform::IDENTITY, 0, x + 20, y + 20).map_err(|e| e.to_string())?;
+            if sim.live.len() != 9 || shape(&sim.live) != shape(&open.live) {
+                return Err(format!("after growing, the board holds {:?}", shape(&sim.live)));
+            }
+            Ok(())
+        })();
+        failures += report(&format!("stamp hanging past the {} edge{}", edges, if edges.contains(" and ") { "s" } else { "" }), result);
+    }
+
+    let result = (|| {
+        // Off the left of a cylinder joined left to right: it wraps; off its top wall: clipped
+        let mut sim = Simulation::new(12, 12);
+        sim.grid.topology = Topology::CylinderX;
+        let (_, seam) = sim.placement(&glider, Transform::IDENTITY, 0, 0, 5).map_err(|e| e.to_string())?;
+        let (_, wall) = sim.placement(&glider, Transform::IDENTITY, 0, 0, 0).map_err(|e| e.to_string())?;
+        if seam.clips() || wall.edges() != "top" {
+            return Err(format!("seam {:?}, wall {:?}", seam, wall));
+        }
+        // The same choices from the command line, for a glider on a board too small to hold it
+        let opts = |policy: &str| Options { width: 2, height: 2, pattern: "Glider".to_string(), on_clip: OnClip::parse(policy).expect("known policy"), ..Options::default() };
+        match opts("error").build_game() {
+            Err(err) if err.contains("--on-clip") => {}
+            other => return Err(format!("--on-clip error gave {:?}", other.map(|s| s.live.len()))),
+        }
+        let clipped = opts("clip").build_game()?;
+        let grown = opts("expand").build_game()?;
+        if clipped.live.len() >= 5 || (clipped.grid.width, clipped.grid.height) != (2, 2) || grown.live.len() != 5 || grown.grid.width < 3 || grown.grid.height < 3 {
+            return Err(format!("clip kept {} cells; expand made {}x{} with {}", clipped.live.len(), grown.grid.width, grown.grid.height, grown.live.len()));
+        }
+        if OnClip::parse("wrap").is_ok() {
+            return Err("an unknown policy was accepted".to_string());
+        }
+        Ok(())
+    })();
+    failures += report("clipping on a cylinder and from the command line", result);
+    failures
+}
+
+/// Placement hints on the default 1280x720 board: the gun starts top-left with room for its
+/// glider stream, other patterns are centered, and the recommended grid flags too-small boards
+fn check_placement() -> u32 {
+    let result = (|| {
+        let registry = default_registry();
+        let get = |name: &str| registry.get(name).ok_or(format!("no {} pattern", name));
+        let (width, height) = (1280 / CELL_SIZE, 720 / CELL_SIZE);
+
+        // The stream flies down-right; until it reaches the far walls the bounded board runs
+        // exactly as an open field would
+        let gun = get("Gosper Gun")?;
+        let mut sim = Simulation::new(width, height);
+        sim.place(gun).map_err(|e| e.to_string())?;
+        let (left, top, right, bottom) = live_bounds(&sim.live).ok_or("the gun placed no cells")?;
+        if (left, top) != (2, 2) || width - 1 - right < 48 || height - 1 - bottom < 48 {
+            return Err(format!("gun spans ({}, {})-({}, {}) on {}x{}", left, top, right, bottom, width, height));
+        }
+        let mut open = Simulation::new(1024, 1024);
+        open.live = sim.live.clone();
+        for generation in 1..=180 {
+            sim.next_generation();
+            open.next_generation();
+            if sim.live != open.live {
+                return Err(format!("the glider stream hit a wall by generation {}", generation));
+            }
+        }
+
+        let penta = get("Pentadecathlon")?;
+        let info = PatternInfo::measure(penta).ok_or("Pentadecathlon has no size")?;
+        let mut sim = Simulation::new(width, height);
+        sim.place(penta).map_err(|e| e.to_string())?;
+        let (left, top, _, _) = live_bounds(&sim.live).ok_or("Pentadecathlon placed no cells")?;
+        if (left, top) != ((width - info.width) / 2, (height - info.height) / 2) {
+            return Err(format!("Pentadecathlon starts at ({}, {}), not centered", left, top));
+        }
+        let (cx, cy) = Placement::Center.anchor(penta, width, height);
+        if Placement::Offset(3, -2).anchor(penta, width, height) != (cx + 3, cy - 2) {
+            return Err("an offset placement did not shift the centered one".to_string());
+        }
+        if (Placement::UpperLeft { margin: 7 }).anchor(get("Random")?, width, height) != (width / 2, height / 2) {
+            return Err("a grid-filling pattern moved off the center anchor".to_string());
+        }
+
+        let fits = |name: &str| get(name).map(|p| p.recommended_grid().is_none_or(|(w, h)| width >= w && height >= h));
+        if !fits("Gosper Gun")? || fits("Acorn")? || !fits("Glider")? {
+            return Err(format!("recommended grids misjudge the {}x{} default", width, height));
+        }
+        Ok(())
+    })();
+    report("pattern placement hints", result)
+}
+
+/// Whether a pattern fills the grid is its own property, not its place in the registry: the
+/// built-ins registered in reverse are classified and placed exactly as in the default order,
+/// and every anchored one lands inside the default board
+fn check_fill_by_property() -> u32 {
+    let result = (|| {
+        let registry = default_registry();
+        let mut reversed = PatternRegistry::new();
+        #[allow(deprecated)]
+        for i in (0..patterns::PATTERN_COUNT).rev() {
+            reversed.append(patterns::get_pattern_by_index(i));
+        }
+        let filling = |r: &PatternRegistry| r.iter().filter(|p| p.fills_grid()).map(|p| p.name().to_string()).collect::<HashSet<_>>();
+        if filling(&registry) != HashSet::from(["Random".to_string()]) || filling(&reversed) != filling(&registry) {
+            return Err(format!("grid-filling patterns {:?} in order, {:?} reversed", filling(&registry), filling(&reversed)));
+        }
+        let (width, height) = (1280 / CELL_SIZE, 720 / CELL_SIZE);
+        let placed = |pattern: &dyn Pattern| -> Result<HashSet<Position>, String> {
+            let mut sim = Simulation::new(width, height);
+            sim.seed = 3;
+            sim.place(pattern).map_err(|e| e.to_string())?;
+            Ok(sim.live)
+        };
+        for pattern in registry.iter() {
+            let other = reversed.get(pattern.name()).ok_or(format!("{} missing when reversed", pattern.name()))?;
+            let cells = placed(pattern)?;
+            if cells != placed(other)? {
+                return Err(format!("{} is placed differently when the registry is reversed", pattern.name()));
+            }
+            let (left, top, right, bottom) = live_bounds(&cells).ok_or(format!("{} placed no cells", pattern.name()))?;
+            let spread = right - left >= width / 2 && bottom - top >= height / 2;
+            if pattern.fills_grid() != spread {
+                return Err(format!("{} spans ({}, {})-({}, {}) on {}x{}", pattern.name(), left, top, right, bottom, width, height));
+            }
+        }
+        Ok(())
+    })();
+    report("grid-filling patterns by property, not registry order", result)
+}
+
+/// `import-all`: patterns are laid out in rows `spacing` cells apart and from the edges, start
+/// a new row when the current one is full, and are reported rather than placed once out of room
+fn check_import_layout() -> u32 {
+    let result = (|| {
+        let block = |name: &str, w: usize, h: usize| {
+            parse_pattern_text(&format!("!Name: {}\n{}", name, format!("{}\n", "O".repeat(w)).repeat(h))).map_err(|e| e.to_string())
+        };
+        // Top-left corner of each placed box
+        let corners = |layout: &Layout| -> Vec<(i32, i32)> {
+            layout.placed.iter().map(|(at, p)| {
+                let (left_of, above, _, _) = patterns::footprint(*p).unwrap_or_default();
+                (at.x() - left_of, at.y() - above)
+            }).collect()
+        };
+
+        // Fitting: three 5x3 blocks on one row, two cells apart and in from the edges
+        let small: Vec<LoadedPattern> = (0..3).map(|i| block(&format!("small {}", i), 5, 3)).collect::<Result<_, _>>()?;
+        let refs: Vec<&dyn Pattern> = small.iter().map(|p| p as &dyn Pattern).collect();
+        let grid = Grid::new(40, 20);
+        let layout = layout_patterns(&refs, &grid, 2);
+        if corners(&layout) != [(2, 2), (9, 2), (16, 2)] || !layout.skipped.is_empty() {
+            return Err(format!("small blocks went to {:?}", corners(&layout)));
+        }
+        let mut sim = Simulation::new(grid.width, grid.height);
+        for (at, pattern) in &layout.placed {
+            sim.apply_pattern(*pattern, at.x(), at.y()).map_err(|e| e.to_string())?;
+        }
+        if sim.live.len() != 3 * 15 {
+            return Err(format!("placed blocks overlap or were clipped: {} cells", sim.live.len()));
+        }
+
+        // Spacing: wider gaps push the third block to a second row below the tallest of the first
+        let layout = layout_patterns(&refs, &Grid::new(24, 24), 6);
+        if corners(&layout) != [(6, 6), (6, 15)] || layout.skipped.len() != 1 {
+            return Err(format!("spacing 6 on 24x24 gave {:?} with {} skipped", corners(&layout), layout.skipped.len()));
+        }
+
+        // Overflow: a block wider than the grid and one with no rows left are skipped, and the
+        // small one after them still goes on the first row
+        let mixed = [block("wide", 50, 2)?, block("a", 10, 5)?, block("b", 10, 5)?, block("c", 10, 5)?, block("d", 10, 5)?, block("dot", 1, 1)?];
+        let refs: Vec<&dyn Pattern> = mixed.iter().map(|p| p as &dyn Pattern).collect();
+        let layout = layout_patterns(&refs, &Grid::new(41, 10), 2);
+        let skipped: Vec<&str> = layout.skipped.iter().map(|p| p.name()).collect();
+        if skipped != ["wide", "d"] || corners(&layout) != [(2, 2), (14, 2), (26, 2), (38, 2)] {
+            return Err(format!("overflow placed {:?} and skipped {:?}", corners(&layout), skipped));
+        }
+
+        for (glob, name, expected) in [("*.rle", "gun.rle", true), ("*.rle", "gun.mc", false), ("g?n*", "gun.rle", true), ("g?n", "gun", true), ("*a*b", "xaab", true), ("a*", "ba", false)] {
+            if pattern_io::wildcard_match(glob, name) != expected {
+                return Err(format!("'{}' matching '{}' should be {}", glob, name, expected));
+            }
+        }
+        let parsed = console::parse("import-all some dir/*.rle 6")?;
+        if parsed != console::Command::ImportAll("some dir/*.rle".to_string(), 6) || console::parse("import-all").is_ok() {
+            return Err(format!("import-all parsed as {:?}", parsed));
+        }
+        Ok(())
+    })();
+    report("batch import layout", result)
+}
+
+/// Zooming out past 1 px per cell: stepping on through cells per pixel, fitting a board wider
+/// than the window, the density counts kept up step by step, and the picture drawn with each
+/// pixel as opaque as its bucket is full
+fn check_zoom_out() -> u32 {
+    let result = (|| {
+        let mut view = BoardView::new(2);
+        let mut seen = Vec::new();
+        for _ in 0..6 {
+            view.set_zoom(view.zoom_stepped(-1));
+            seen.push((view.cell, view.shrink));
+        }
+        if seen != [(1, 1), (1, 2), (1, 4), (1, 8), (1, 16), (1, 16)] || view.zoom_label() != "1/16" {
+            return Err(format!("zooming out from 2 px went {:?}", seen));
+        }
+        if view.zoom_stepped(4) != (1, 1) || view.zoom_stepped(5) != (2, 1) {
+            return Err(format!("zooming back in from 1/16 gave {:?} and {:?}", view.zoom_stepped(4), view.zoom_stepped(5)));
+        }
+
+        let grid = Grid::new(GRID_MAX, GRID_MAX);
+        view.fit_board(&grid, 1280.0, 800.0);
+        let board = view.board_size(&grid);
+        if (view.cell, view.shrink) != (1, 8) || board.y > 800.0 - HUD_HEIGHT - 2.0 * ZOOM_FIT_MARGIN {
+            return Err(format!("a {0}x{0} board fitted to 1280x800 at {1}x{2}, {3:?} px", GRID_MAX, view.cell, view.shrink, board));
+        }
+        let anchor = Vec2::new(613.7, 402.2);
+        let point = (anchor - view.offset) / view.scale();
+        view.zoom_at(&grid, (1, 2), anchor, 1280.0, 800.0);
+        let moved = view.offset + point * view.scale() - anchor;
+        if moved.x.abs() > 0.5 || moved.y.abs() > 0.5 {
+            return Err(format!("zooming out kept the point under the mouse {:?} off", moved));
+        }
+        Ok(())
+    })();
+    let mut failures = report("zoom: past 1 px per cell", result);
+
+    let result = (|| {
+        let brute = |sim: &Simulation, shrink: i32| {
+            let mut counts = HashMap::new();
+            for p in &sim.live {
+                *counts.entry((p.x() / shrink, p.y() / shrink)).or_insert(0u16) += 1;
+            }
+            counts
+        };
+        let matches = |map: &DensityMap, sim: &Simulation| {
+            let counts = brute(sim, map.shrink);
+            (0..map.rows).all(|y| (0..map.cols).all(|x| map.count(x, y) == counts.get(&(x, y)).copied().unwrap_or(0)))
+        };
+        let cell = ColorTheme::Classic.colors().cell;
+        let mut sim = Simulation::new(203, 150);
+        sim.seed = 5;
+        sim.random_fill(0.35);
+        sim.track_diff = true;
+        let mut map = DensityMap::new(4);
+        map.sync(&sim, cell);
+        if (map.cols, map.rows) != (51, 38) || !matches(&map, &sim) {
+            return Err(format!("a 203x150 board in 4x4 buckets counted {}x{} buckets, or miscounted", map.cols, map.rows));
+        }
+        for generation in 1..=40 {
+            sim.next_generation();
+            if generation % 10 == 0 {
+                // Several generations in one frame: no single step to follow
+                sim.next_generation();
+            }
+            map.sync(&sim, cell);
+            if !matches(&map, &sim) {
+                return Err(format!("the counts drifted from the board at generation {}", sim.generation));
+            }
+        }
+        let moved = *sim.live.iter().next().ok_or("the soup died out")?;
+        let free = (0..203).map(|x| Position::new(x, 0)).find(|p| !sim.live.contains(p)).ok_or("the top row is full")?;
+        sim.live.remove(&moved);
+        sim.live.insert(free);
+        for _ in 0..DENSITY_RECHECK_FRAMES {
+            map.sync(&sim, cell);
+        }
+        if !matches(&map, &sim) {
+            return Err("an edit that kept the cell count should show once the board is rehashed".to_string());
+        }
+        Ok(())
+    })();
+    failures += report("zoom: density counts follow the board", result);
+
+    let result = (|| {
+        // One full bucket, one with a single cell and an empty one, at 2 cells per pixel and
+        // clear of the border drawn inside the board's edge pixels
+        let mut sim = Simulation::new(12, 8);
+        sim.live = [(2, 2), (3, 2), (2, 3), (3, 3), (5, 3)].map(|(x, y)| Position::new(x, y)).into();
+        let mut view = BoardView::new(1);
+        view.shrink = 2;
+        view.offset = Vec2::new(4.0, 4.0);
+        let colors = view.theme.colors();
+        let mut image = Image::gen_image_color(12, 9, colors.background);
+        draw_board(&mut image, &sim, &view, 1.0);
+        let quarter = Color::new(
+            colors.cell.r * 0.25 + colors.background.r * 0.75,
+            colors.cell.g * 0.25 + colors.background.g * 0.75,
+            colors.cell.b * 0.25 + colors.background.b * 0.75,
+            1.0,
+        );
+        let near = |a: Color, b: Color| (a.r - b.r).abs() < 0.02 && (a.g - b.g).abs() < 0.02 && (a.b - b.b).abs() < 0.02;
+        let (full, lone, empty) = (image.get_pixel(5, 5), image.get_pixel(6, 5), image.get_pixel(7, 5));
+        if !near(full, colors.cell) || !near(lone, quarter) || !near(empty, colors.background) {
+            return Err(format!("full, lone and empty buckets drew as {:?}, {:?} and {:?}", full, lone, empty));
+        }
+        if DensityMap::new(16).opacity(1) != DENSITY_FAINTEST || DensityMap::new(16).opacity(256) != 1.0 {
+            return Err("one cell in 256 should still show at the faintest opacity".to_string());
+        }
+        Ok(())
+    })();
+    failures += report("zoom: density picture in the theme's colors", result);
+    failures
+}
+
+/// Zoom levels: stepping through them, the fit-board preset, zooming around the mouse, and the
+/// board rasterized at every level with each cell on whole pixels, so neighbours never leave a
+/// seam or overlap
+fn check_zoom() -> u32 {
+    // From, steps, expected; 10 and 5 sit between levels
+    let steps = [(10, 1, 12), (10, -1, 8), (12, 1, 16), (32, 1, 32), (1, -1, 1), (5, -2, 3), (1, 9, 32)];
+    let result = steps.iter().try_for_each(|&(cell, by, want)| match BoardView::zoom_step(cell, by) {
+        got if got == want => Ok(()),
+        got => Err(format!("{} stepped by {} gave {}, expected {}", cell, by, got, want)),
+    });
+    let mut failures = report("zoom: steps through the levels", result);
+
+    let result = (|| {
+        // Grid, window and expected level: 784 px across the margins fits 7 px cells, which is
+        // the 6 px level; the tall board is limited by the 528 px left below the HUD
+        for ((w, h), (win_w, win_h), expected) in [((100, 60), (800.0, 600.0), 6), ((40, 100), (800.0, 600.0), 4)] {
+            let grid = Grid::new(w, h);
+            let mut view = BoardView::new(10);
+            view.pan = Vec2::new(33.0, -7.0);
+            view.fit_board(&grid, win_w, win_h);
+            let board = view.board_size(&grid);
+            let case = format!("{}x{} grid in a {}x{} window", w, h, win_w, win_h);
+            if view.cell != expected {
+                return Err(format!("{}: fitted at {} px, expected {}", case, view.cell, expected));
+            }
+            if view.offset.x < ZOOM_FIT_MARGIN || view.offset.y < HUD_HEIGHT + ZOOM_FIT_MARGIN
+                || view.offset.x + board.x > win_w - ZOOM_FIT_MARGIN || view.offset.y + board.y > win_h - ZOOM_FIT_MARGIN
+            {
+                return Err(format!("{}: board at {:?} overlaps the HUD or margins", case, view.offset));
+            }
+        }
+        Ok(())
+    })();
+    failures += report("zoom: fit board leaves room for the HUD", result);
+
+    let result = (|| {
+        let grid = Grid::new(64, 48);
+        let anchor = Vec2::new(413.7, 291.2);
+        for level in ZOOM_LEVELS {
+            let mut view = BoardView::new(10);
+            view.center_view(&grid, 1000.0, 700.0);
+            let point = (anchor - view.offset) / view.cell as f32;
+            view.zoom_at(&grid, (level, 1), anchor, 1000.0, 700.0);
+            let moved = view.offset + point * view.cell as f32 - anchor;
+            if view.offset != view.offset.floor() || moved.x.abs() > 0.5 || moved.y.abs() > 0.5 {
+                return Err(format!("at {} px the point under the mouse moved by {:?} (offset {:?})", level, moved, view.offset));
+            }
+        }
+        Ok(())
+    })();
+    failures += report("zoom: keeps the point under the mouse", result);
+    failures += check_zoom_out();
+
+    // A solid block beside a checkerboard, away from the border's band
+    let mut sim = Simulation::new(12, 9);
+    sim.live = (2..10).flat_map(|x| (2..7).map(move |y| Position::new(x, y)))
+        .filter(|p| p.x() < 6 || (p.x() + p.y()) % 2 == 0)
+        .collect();
+    let result = ZOOM_LEVELS.iter().try_for_each(|&level| {
+        let (image_w, image_h) = (420, 320);
+        let mut view = BoardView::new(10);
+        view.grid_style.mode = GridMode::Off;
+        view.center_view(&sim.grid, image_w as f32, image_h as f32);
+        view.zoom_at(&sim.grid, (level, 1), Vec2::new(201.3, 147.6), image_w as f32, image_h as f32);
+        let colors = view.theme.colors();
+        let mut image = Image::gen_image_color(image_w, image_h, colors.background);
+        draw_board(&mut image, &sim, &view, 1.0);
+        let key = |c: Color| -> [u8; 4] { c.into() };
+        if view.offset != view.offset.floor() {
+            return Err(format!("at {} px the board sits at {:?}, off whole pixels", level, view.offset));
+        }
+        let (mut wrong, mut lit) = (0, 0);
+        for py in 0..image_h as usize {
+            for px in 0..image_w as usize {
+                let (x, y) = view.screen_to_cell(px as f32 + 0.5, py as f32 + 0.5);
+                if !(2..sim.grid.width - 2).contains(&x) || !(2..sim.grid.height - 2).contains(&y) {
+                    continue;
+                }
+                let want = if sim.live.contains(&Position::new(x, y)) { colors.cell } else { colors.background };
+                let got = image.get_image_data()[py * image_w as usize + px];
+                wrong += (got != key(want)) as usize;
+                lit += (got == key(colors.cell)) as usize;
+            }
+        }
+        // Every cell exactly level x level pixels: a seam loses some, an overlap paints extra
+        let expected = sim.live.len() * (level * level) as usize;
+        if wrong == 0 && lit == expected {
+            Ok(())
+        } else {
+            Err(format!("at {} px per cell {} pixels are wrong and {} are lit, expected {}", level, wrong, lit, expected))
+        }
+    });
+    failures += report("zoom: cells land on whole pixels at every level", result);
+    failures
+}
+
+/// A window granted at a different size than requested: the board is refitted so it shows in
+/// full, and the screen-to-cell conversion still lands on the cell drawn under each point
+fn check_window_fit() -> u32 {
+    let result = (|| {
+        // Requested window, grid, chosen cell, window granted, expected cell
+        let cases = [
+            ((1280.0, 720.0), (128, 72), 10, (1280.0, 720.0), 10),
+            ((1280.0, 720.0), (128, 72), 10, (1000.0, 700.0), 7),
+            ((1280.0, 720.0), (128, 72), 10, (1920.0, 1080.0), 10),
+            ((1280.0, 720.0), (128, 72), 10, (800.0, 1000.0), 6),
+            ((1024.0, 768.0), (100, 60), 7, (701.0, 419.0), 6),
+            ((1920.0, 1080.0), (1920, 1080), 1, (1366.0, 768.0), 1),
+        ];
+        for ((req_w, req_h), (w, h), chosen, (win_w, win_h), expected) in cases {
+            let grid = Grid::new(w, h);
+            let mut view = BoardView::new(chosen);
+            view.pan = Vec2::new(40.0, -25.0);
+            let mut fit = WindowFit::new(chosen, Vec2::new(req_w, req_h));
+            let refitted = fit.update(&mut view, &grid, win_w, win_h);
+            let case = format!("{}x{} grid at cell {} in a {}x{} window", w, h, chosen, win_w, win_h);
+            if refitted != ((req_w, req_h) != (win_w, win_h)) || view.cell != expected {
+                return Err(format!("{}: cell {}, refitted {}", case, view.cell, refitted));
+            }
+            if fit.update(&mut view, &grid, win_w, win_h) {
+                return Err(format!("{}: refitted again without a resize", case));
+            }
+            view.center_view(&grid, win_w, win_h);
+            let board = view.board_size(&grid);
+            // An unchanged window keeps the user's camera shift; a refit resets it
+            let fits_at_all = w <= win_w as i32 && h <= win_h as i32;
+            if refitted && fits_at_all && (view.offset.x < 0.0 || view.offset.y < 0.0 || view.offset.x + board.x > win_w || view.offset.y + board.y > win_h) {
+                return Err(format!("{}: board at {:?} size {:?} is cropped", case, view.offset, board));
+            }
+            for (x, y) in [(0, 0), (w - 1, h - 1), (w / 2, h / 3), (w - 1, 0), (0, h - 1)] {
+                let corner = view.cell_to_screen(x, y);
+                let inside = [(0.0, 0.0), (0.5, 0.5), (0.999, 0.999)];
+                for (fx, fy) in inside {
+                    let at = corner + Vec2::new(fx, fy) * view.cell as f32;
+                    if view.screen_to_cell(at.x, at.y) != (x, y) {
+                        return Err(format!("{}: a click at {:?} missed cell ({}, {})", case, at, x, y));
+                    }
+                }
+            }
+        }
+        Ok(())
+    })();
+    report("window size mismatch", result)
+}
+
+/// Seconds of simulation time a run paced by `schedule` takes to reach `target`, stepping on
+/// 60 Hz frames and setting the speed from the generation reached, as a run does
+fn paced_seconds(schedule: &SpeedSchedule, target: u64) -> f64 {
+    let mut clock = SimClock::new(schedule.speed_at(0));
+    let mut generation = 0;
+    while generation < target {
+        clock.set_speed(schedule.speed_at(generation));
+        clock.advance(TICKS_PER_SECOND / 60);
+        let due = clock.due().min(target - generation);
+        clock.take(due);
+        generation += due;
+    }
+    clock.seconds()
+}
+
+/// Speed schedules: parsing, linear and step interpolation, pacing a clock, and surviving the
+/// settings file and a delta log unchanged so replays pace the same
+fn check_speed_schedule() -> u32 {
+    let result = (|| {
+        let linear = SpeedSchedule::parse("0:2 100:10 500:60")?;
+        for (generation, expected) in [(0, 2.0), (50, 6.0), (100, 10.0), (300, 35.0), (500, 60.0), (9000, 60.0)] {
+            if (linear.speed_at(generation) - expected).abs() > 1e-4 {
+                return Err(format!("linear speed at {} is {}, not {}", generation, linear.speed_at(generation), expected));
+            }
+        }
+        let step = SpeedSchedule::parse("10:5 100:60 STEP")?;
+        if (step.speed_at(0), step.speed_at(99), step.speed_at(100)) != (5.0, 5.0, 60.0) || step.next_after(10) != Some((100, 60.0)) {
+            return Err("step schedule holds the wrong speeds".to_string());
+        }
+        for bad in ["", "step", "100:10 50:2", "0:500", "0:2 fast", "0:2 step 100:60", "-1:5"] {
+            if SpeedSchedule::parse(bad).is_ok() {
+                return Err(format!("'{}' parsed as a schedule", bad));
+            }
+        }
+        if SpeedSchedule::parse(&step.to_string())? != step || SpeedSchedule::parse(&linear.to_string())? != linear {
+            return Err(format!("'{}' did not read back as itself", step));
+        }
+
+        // 2 gen/s for the first 100 generations, then 60: 50 s to the switch, a second for the next 60
+        let ramp = SpeedSchedule::parse("0:2 100:60 step")?;
+        let (slow, fast) = (paced_seconds(&ramp, 100), paced_seconds(&ramp, 160));
+        if (slow - 50.0).abs() > 0.05 || (fast - slow - 1.0).abs() > 0.05 {
+            return Err(format!("the ramp reached 100 after {:.2} s and 160 after {:.2} s", slow, fast));
+        }
+        // Ramping evenly, each generation takes 1/speed at the generation it starts from
+        let even = SpeedSchedule::parse("0:2 100:60")?;
+        let expected: f64 = (0..100).map(|g| 1.0 / even.speed_at(g) as f64).sum();
+        let even = paced_seconds(&even, 100);
+        if (even - expected).abs() > 0.05 {
+            return Err(format!("a linear ramp took {:.2} s to generation 100", even));
+        }
+
+        let settings = Settings { schedule: Some(ramp.clone()), ..Settings::default() };
+        if Settings::parse(&settings.to_text()) != settings || Settings::parse("speed_schedule = 0:2 1:0").schedule.is_some() {
+            return Err("the schedule setting did not round-trip".to_string());
+        }
+        let sim = Simulation::new(16, 16);
+        let mut log = DeltaLog::new(&sim);
+        log.schedule = Some(ramp.clone());
+        let decoded = DeltaLog::from_bytes(&log.to_bytes())?;
+        if decoded.schedule.as_ref() != Some(&ramp) || paced_seconds(&ramp, 160) != fast {
+            return Err("the schedule changed through a saved delta log".to_string());
+        }
+        // A version 2 log has no torus shift in its header and no schedule or notes after the bookmarks
+        log.schedule = None;
+        let mut old = log.to_bytes();
+        old.truncate(old.len() - 2 - 4);
+        old.drain(10..18);
+        old[4] = 2;
+        match DeltaLog::from_bytes(&old) {
+            Ok(decoded) if decoded.schedule.is_none() => {}
+            Ok(_) => return Err("version 2 log loaded with a schedule".to_string()),
+            Err(err) => return Err(format!("version 2 log no longer loads: {}", err)),
+        }
+        match console::parse("schedule 0:2 100:60 step")? {
+            console::Command::Schedule(Some(parsed)) if parsed == ramp => {}
+            other => return Err(format!("schedule command parsed as {:?}", other)),
+        }
+        if console::parse("schedule off")? != console::Command::Schedule(None) || console::parse("schedule").is_ok() {
+            return Err("schedule off or a bare schedule parsed wrongly".to_string());
+        }
+        Ok(())
+    })();
+    report("speed schedule", result)
+}
+
+/// `SimulationConfig` builds the board `run_simulation` always has, and resolves grid and cell
+/// sizes the way the board menu does
+fn check_simulation_config() -> u32 {
+    let result = (|| {
+        let board = BoardSize { width: 90, height: 50, cell: 7, topology: Topology::CylinderX };
+        let config = SimulationConfig::new().window(1280, 720).board(board);
+        let built = config.board_size(1280, 720);
+        if (built.width, built.height, built.cell, built.topology) != (90, 50, 7, Topology::CylinderX) || config.window != Some((1280, 720)) {
+            return Err(format!("the menu's board came back as {}x{} at {} px", built.width, built.height, built.cell));
+        }
+        let defaults = SimulationConfig::new();
+        if defaults.speed != crate::config::SPEED_INIT || !defaults.paused || !defaults.hud || defaults.controls != Controls::ALL
+            || defaults.rule != Rule::LIFE || defaults.theme != ColorTheme::Classic || defaults.window.is_some()
+        {
+            return Err("defaults differ from the interactive app".to_string());
+        }
+        let sizes = [
+            (SimulationConfig::new().grid(160, 100).board_size(800, 600), BoardSize::fit_window(160, 100, 800, 600)),
+            (SimulationConfig::new().cell_size(4).board_size(800, 600), BoardSize::fill_window(800, 600, 4)),
+            (SimulationConfig::new().board_size(800, 600), BoardSize::fill_window(800, 600, CELL_SIZE)),
+        ];
+        for (got, expected) in sizes {
+            if (got.width, got.height, got.cell) != (expected.width, expected.height, expected.cell) {
+                return Err(format!("{}x{} at {} px, expected {}x{} at {} px", got.width, got.height, got.cell, expected.width, expected.height, expected.cell));
+            }
+        }
+        let clamped = SimulationConfig::new().speed(1000.0).wrap(true).board_size(800, 600);
+        if clamped.topology != Topology::Torus || SimulationConfig::new().speed(1000.0).speed != crate::config::SPEED_MAX {
+            return Err("wrap or the speed clamp was lost".to_string());
+        }
+        Ok(())
+    })();
+    report("simulation config builder", result)
+}
+
+/// With edge spawning off, or on a torus where it does nothing, stepping must match the bare engine exactly
+fn check_edge_spawn_off(seed: u64) -> u32 {
+    let mut failures = 0;
+    for topology in [Topology::Bounded, Topology::Torus] {
+        let mut sim = Simulation::new(64, 48);
+        sim.grid.topology = topology;
+        sim.edge_spawn = topology.wraps();
+        sim.live = random_soup(&sim.grid, 0.35, seed);
+        let mut bare = sim.live.clone();
+        let mut result = Ok(());
+        for generation in 1..=VERIFY_GENERATIONS {
+            sim.next_generation();
+            bare = sim.grid.next_generation(&bare);
+            if sim.live != bare || sim.injected != 0 {
+                result = Err(format!("generation {} differs from the engine without edge spawning", generation));
+                break;
+            }
+        }
+        let name = if topology.wraps() { "edge spawn on has no effect" } else { "edge spawn off is unchanged" };
+        failures += report(&format!("{} ({})", name, topology), result);
+    }
+    failures
+}
+
+/// Reset to initial: a rerun with edge spawning (drawn from the seed) must repeat the first run exactly
+fn check_reset_to_initial(seed: u64) -> u32 {
+    report("reset to initial replays the run", replay_from_initial(seed))
+        + report("restart, re-mark and re-roll of a seeded soup", restart_seeded_soup(seed))
+}
+
+/// What R, U and Ctrl+R do to the board: restart gives back generation 0 cell for cell, a
+/// re-mark moves generation 0, and a re-roll draws a new soup that the next restart returns to
+fn restart_seeded_soup(seed: u64) -> Result<(), String> {
+    let registry = default_registry();
+    let random = registry.get("Random").ok_or("no Random pattern")?;
+    let mut sim = Simulation::new(64, 48);
+    sim.seed = seed;
+    sim.apply_pattern(random, 32, 24).map_err(|e| e.to_string())?;
+    sim.mark_initial();
+    let start = to_ascii(&sim.grid, &sim.live);
+    for _ in 0..VERIFY_GENERATIONS {
+        sim.next_generation();
+    }
+    sim.reset_to_initial();
+    same_board(start.clone(), to_ascii(&sim.grid, &sim.live))?;
+
+    for _ in 0..10 {
+        sim.next_generation();
+    }
+    sim.mark_initial();
+    let marked = to_ascii(&sim.grid, &sim.live);
+    sim.next_generation();
+    sim.reset_to_initial();
+    if sim.generation != 0 {
+        return Err(format!("restart after a re-mark left generation {}", sim.generation));
+    }
+    same_board(marked, to_ascii(&sim.grid, &sim.live))?;
+
+    sim.reroll(random).map_err(|e| e.to_string())?;
+    let rolled = to_ascii(&sim.grid, &sim.live);
+    if rolled == start || sim.seed == seed {
+        return Err("re-roll kept the same soup".to_string());
+    }
+    for _ in 0..VERIFY_GENERATIONS {
+        sim.next_generation();
+    }
+    sim.reset_to_initial();
+    same_board(rolled, to_ascii(&sim.grid, &sim.live))
+}
+
+fn replay_from_initial(seed: u64) -> Result<(), String> {
+    let mut sim = Simulation::new(64, 48);
+    sim.seed = seed;
+    sim.edge_spawn = true;
+    sim.random_fill(RANDOM_DENSITY);
+    if !sim.editing() || sim.reset_to_initial() {
+        return Err("a new board must be in edit mode with nothing to reset to".to_string());
+    }
+    sim.mark_initial();
+    for _ in 0..VERIFY_GENERATIONS {
+        sim.next_generation();
+    }
+    let (first, first_injected) = (to_ascii(&sim.grid, &sim.live), sim.injected);
+
+    // Re-seeding and editing after the start must not leak into the rerun
+    sim.next_seed();
+    sim.toggle_cell(0, 0);
+    sim.reset_to_initial();
+    if sim.generation != 0 || sim.seed != seed {
+        return Err(format!("reset left generation {} and seed {}", sim.generation, sim.seed));
+    }
+    for _ in 0..VERIFY_GENERATIONS {
+        sim.next_generation();
+    }
+    same_board(first, to_ascii(&sim.grid, &sim.live))?;
+    if sim.injected != first_injected {
+        return Err(format!("injected {} cells, first run {}", sim.injected, first_injected));
+    }
+    Ok(())
+}
+
+/// Personal records: the file format survives a round trip, keeps lines it doesn't know and is
+/// left alone when newer; runs only set records they beat; and a soup's lifespan is confirmed
+/// by replaying it, the same soup the Random pattern places
+fn check_records(seed: u64) -> u32 {
+    let run = |pattern: &str, seed: u64| RunSetup {
+        pattern: pattern.to_string(),
+        seed,
+        density: 0.3,
+        width: 32,
+        height: 24,
+        topology: Topology::parse_shift("x+3").expect("valid shift"),
+        rule: Rule::parse("B36/S23").expect("valid rule"),
+    };
+    let mut failures = 0;
+
+    let result = (|| {
+        let setup = run("Gosper Gun", 7);
+        if RunSetup::parse(&setup.to_string()).as_ref() != Ok(&setup) {
+            return Err(format!("'{}' did not read back", setup));
+        }
+        let mut records = Records::default();
+        let result = |run: RunSetup, peak: usize, generations: u64, soup_lifespan: Option<u64>| RunResult { run, peak, generations, soup_lifespan };
+        let set = records.add(&result(run("Random", 1), 500, 900, Some(300)));
+        if set.len() != 3 || records.total_generations != 900 {
+            return Err(format!("the first run set {:?} with {} generations in total", set, records.total_generations));
+        }
+        // Lower figures set nothing; a longer-lived soup at another density gets its own record
+        let set = records.add(&result(run("Acorn", 2), 400, 100, None));
+        let mut other = run("Random", 3);
+        other.density = 0.35;
+        let more = records.add(&result(other, 10, 10, Some(50)));
+        if !set.is_empty() || more.len() != 1 || records.soups.len() != 2 || records.total_generations != 1010 {
+            return Err(format!("lower runs set {:?} and {:?} ({} soup records)", set, more, records.soups.len()));
+        }
+        if !records.beats_peak(501) || records.beats_peak(500) || !records.beats_longest_run(901) || Records::default().beats_peak(1) {
+            return Err("beating a record was misjudged".to_string());
+        }
+        let text = records.to_text()?;
+        let reread = Records::parse(&text);
+        if reread.to_text()? != text || reread.soup(30).map(|r| r.value) != Some(300) || reread.peak != records.peak {
+            return Err(format!("records did not read back:\n{}", text));
+        }
+        // A line from a later version is written back as it was; a newer file is never overwritten
+        let future = format!("{}fastest_ship = 12 | {}\n", text, setup);
+        if !Records::parse(&future).to_text()?.contains("fastest_ship = 12") {
+            return Err("a line from a later version was dropped".to_string());
+        }
+        if Records::parse(&text.replace("version = 1", "version = 2")).to_text().is_ok() {
+            return Err("a newer records file would be overwritten".to_string());
+        }
+        Ok(())
+    })();
+    failures += report("records: file and record keeping", result);
+
+    let result = (|| {
+        let mut stats = RunStats::default();
+        for (generation, population) in [(0, 5), (5, 6), (10, 7)] { stats.observe(generation, population); }
+        stats.rewind(3, 5);
+        stats.observe(8, 5);
+        if stats.generations != 15 {
+            return Err(format!("{} generations counted, expected 15", stats.generations));
+        }
+        Ok(())
+    })();
+    failures += report("records: generations counted per run", result);
+
+    let result = (|| {
+        // The soup the app places from the Random pattern, and a seed of it that settles
+        let registry = default_registry();
+        let mut placed = Simulation::new(32, 24);
+        placed.grid.topology = Topology::Torus;
+        (placed.seed, placed.density) = (seed, 0.3);
+        placed.place(registry.get("Random").ok_or("no Random pattern")?).map_err(|e| e.to_string())?;
+        placed.mark_initial();
+        let mut setup = RunSetup::of(&placed, "Random").ok_or("no run setup once started")?;
+        setup.rule = Rule::LIFE;
+        if setup.soup().live != placed.live {
+            return Err("the replayed soup differs from the one placed".to_string());
+        }
+        let experiment = Experiment {
+            values: Vec::new(), replicates: 1, generations: 5000, seed,
+            width: 32, height: 24, density: 0.3, rule: Rule::LIFE, topology: Topology::Torus,
+        };
+        let settled = (seed..seed + 20).find_map(|s| {
+            let lifespan = experiment.run_one(Value::Density(0.3), s).lifespan?;
+            Some((RunSetup { seed: s, ..setup.clone() }, lifespan))
+        });
+        let Some((setup, lifespan)) = settled else { return Err("no soup settled to test with".to_string()) };
+        let mut sim = setup.soup();
+        let end = lifespan + 37;
+        while sim.generation < end { sim.next_generation(); }
+        let hash = board_hash(&sim.live);
+        if records::soup_lifespan(&setup, end, hash) != Some(lifespan) {
+            return Err(format!("seed {} settled at {} but the replay found {:?}", setup.seed, lifespan, records::soup_lifespan(&setup, end, hash)));
+        }
+        // An edited board, a run left before it settled, or another pattern sets no record
+        let edited = hash ^ 1;
+        let early = (lifespan > 0).then(|| records::soup_lifespan(&setup, lifespan - 1, hash)).flatten();
+        let acorn = RunSetup { pattern: "Acorn".to_string(), ..setup.clone() };
+        if records::soup_lifespan(&setup, end, edited).is_some() || early.is_some() || records::soup_lifespan(&acorn, end, hash).is_some() {
+            return Err("a disturbed, unsettled or non-soup run was given a lifespan".to_string());
+        }
+        Ok(())
+    })();
+    failures += report("records: soup lifespan confirmed by replay", result);
+    failures
+}
+
+/// Sweeps on tiny boards: value lists and ranges, CSV rows, the same outcomes on one thread or
+/// several, and a lifespan and period that replaying the soup confirms
+fn check_experiment(seed: u64) -> u32 {
+    let mut failures = 0;
+    let result = (|| {
+        let densities = Parameter::Density.range(0.05, 0.6, 0.05)?;
+        let last = densities.last().map(Value::to_string);
+        if densities.len() != 12 || last.as_deref() != Some("0.6") {
+            return Err(format!("0.05 to 0.6 gave {} values ending at {:?}", densities.len(), last));
+        }
+        if Parameter::Rule.range(1.0, 2.0, 1.0).is_ok() || Parameter::Rule.value("B0/S23").is_ok() || Parameter::Size.value("0").is_ok() {
+            return Err("a rule range, a B0 rule or a zero size was accepted".to_string());
+        }
+        let row = Outcome { value: Value::Rule(Rule::LIFE), seed: 4, final_population: 0, peak: 9, lifespan: None, period: None }.csv_row();
+        if row != "B3/S23,4,0,9,," {
+            return Err(format!("row '{}'", row));
+        }
+        Ok(())
+    })();
+    failures += report("experiment values and CSV rows", result);
+
+    let result = (|| {
+        let experiment = Experiment {
+            values: Parameter::Density.range(0.1, 0.3, 0.1)?,
+            replicates: 3,
+            generations: 400,
+            seed,
+            width: 12,
+            height: 12,
+            density: RANDOM_DENSITY,
+            rule: Rule::LIFE,
+            topology: Topology::Bounded,
+        };
+        let serial = experiment.run(1, |_, _, _| {});
+        let mut progress = Vec::new();
+        let threaded = experiment.run(3, |done, total, _| progress.push((done, total)));
+        if serial.len() != 9 || serial != threaded || progress != (1..=9).map(|d| (d, 9)).collect::<Vec<_>>() {
+            return Err(format!("{} serial and {} threaded outcomes, progress {:?}", serial.len(), threaded.len(), progress));
+        }
+        if serial.iter().all(|o| o.lifespan.is_none()) {
+            return Err("no soup settled".to_string());
+        }
+        let csv = experiment::to_csv(&serial);
+        if csv.lines().count() != 10 || !csv.starts_with(Outcome::CSV_HEADER) {
+            return Err(format!("CSV:\n{}", csv));
+        }
+        for outcome in &serial {
+            let (Some(lifespan), Some(period)) = (outcome.lifespan, outcome.period) else { continue };
+            let Value::Density(density) = outcome.value else { continue };
+            let mut sim = Simulation::new(12, 12);
+            sim.seed = outcome.seed;
+            sim.random_fill(density);
+            let mut before = None;
+            for _ in 0..lifespan {
+                before = Some(sim.live.clone());
+                sim.next_generation();
+            }
+            let settled = analysis::period(&sim.grid, &sim.live, period) == Some(period);
+            let early = before.is_some_and(|b| analysis::period(&sim.grid, &b, period).is_some());
+            if !settled || early || sim.live.len() != outcome.final_population {
+                return Err(format!("density {} seed {}: settled at {} with period {} doesn't replay", density, outcome.seed, lifespan, period));
+            }
+        }
+        Ok(())
+    })();
+    failures += report("experiment sweep on 12x12 soups", result);
+    failures
+}
+
+/// A debug dump of a run on each topology reads back field for field, rebuilds the board with the
+/// same hash and carries on exactly as the original, edge spawns included. A tampered board,
+/// broken JSON and a file from something else are refused.
+fn check_debug_dump(seed: u64) -> u32 {
+    let mut failures = 0;
+    for topology in Topology::ALL {
+        let result = (|| {
+            let mut sim = Simulation::new(40, 30);
+            sim.grid.topology = topology;
+            sim.grid.rule = Rule::parse("B36/S23").map_err(|e| e.to_string())?;
+            sim.seed = seed ^ u64::MAX; // Beyond what a JSON reader keeping f64 could hold
+            sim.random_fill(0.35);
+            sim.edge_spawn = topology != Topology::Torus;
+            sim.edge_rate = 0.05;
+            let mut growth = GrowthTracker::default();
+            for _ in 0..60 {
+                sim.next_generation();
+                growth.observe(sim.generation, sim.live.len());
+            }
+            let settings = "# \"quoted\" \\ settings\ttabbed\nsmooth_render = false\n".to_string();
+            let dump = DebugDump::capture(&sim, growth.recent(DEBUG_DUMP_HISTORY), settings, CONTROLS);
+            if dump.history.len() != DEBUG_DUMP_HISTORY || dump.history.last() != Some(&(60, sim.live.len())) {
+                return Err(format!("kept {} history samples ending {:?}", dump.history.len(), dump.history.last()));
+            }
+            let path = std::env::temp_dir().join(format!("conways-game-of-life-dump-{}-{}.json", std::process::id(), topology));
+            dump.save(&path).map_err(|e| e.to_string())?;
+            let read = DebugDump::load(&path).map_err(|e| e.to_string());
+            let _ = std::fs::remove_file(&path);
+            let read = read?;
+            if read != dump {
+                return Err(format!("read back as {:?}", read));
+            }
+            let mut rebuilt = read.simulation().map_err(|e| e.to_string())?;
+            if rebuilt.generation != sim.generation || board_hash(&rebuilt.live) != board_hash(&sim.live) {
+                return Err(format!("rebuilt generation {} hash {:016x}", rebuilt.generation, board_hash(&rebuilt.live)));
+            }
+            for _ in 0..40 {
+                sim.next_generation();
+                rebuilt.next_generation();
+            }
+            if board_hash(&rebuilt.live) != board_hash(&sim.live) || rebuilt.injected != sim.injected {
+                return Err(format!("the rebuilt run drifted by generation {}", sim.generation));
+            }
+            Ok(())
+        })();
+        failures += report(&format!("debug dump round trip ({})", topology), result);
+    }
+
+    let result = (|| {
+        let mut sim = Simulation::new(16, 16);
+        sim.place(&GliderPattern).map_err(|e| e.to_string())?;
+        let dump = DebugDump::capture(&sim, Vec::new(), String::new(), "");
+        let empty = DebugDump::capture(&Simulation::new(8, 8), Vec::new(), String::new(), "");
+        if DebugDump::parse(&empty.to_json()).map_err(|e| e.to_string())?.simulation().map(|s| s.live.len()).ok() != Some(0) {
+            return Err("an empty board didn't come back empty".to_string());
+        }
+        let mut tampered = dump.clone();
+        tampered.board = tampered.board.replacen("bo$", "o$", 1);
+        let json = dump.to_json();
+        match (
+            DebugDump::parse(&tampered.to_json()).map_err(|e| e.to_string())?.simulation().map(|s| s.live.len()),
+            DebugDump::parse(&json[..json.len() / 2]),
+            DebugDump::parse(&json.replace("debug dump 1", "debug dump 9")),
+        ) {
+            (Err(GolError::Invalid { .. }), Err(GolError::Parse { .. }), Err(GolError::Parse { .. })) => Ok(()),
+            other => Err(format!("bad dumps gave {:?}", other)),
+        }
+    })();
+    failures += report("debug dump refuses a tampered or broken file", result);
+    failures
+}
+
+/// Board diffs: the three parts partition both boards and swap cleanly; a reference run on from
+/// the initial state or a `.gold` save matches the board stepped directly, and rewinds when the
+/// board goes back; diff figures use compare mode's colors; `--diff` parses
+fn check_compare() -> u32 {
+    let result = (|| {
+        let a: HashSet<Position> = [(0, 0), (1, 0), (2, 0), (5, 5)].map(|(x, y)| Position::new(x, y)).into();
+        let b: HashSet<Position> = [(1, 0), (2, 0), (3, 0)].map(|(x, y)| Position::new(x, y)).into();
+        let diff = diff_boards(&a, &b);
+        if diff.counts() != (DiffCounts { only_a: 2, only_b: 1, both: 2 }) || diff.identical() {
+            return Err(format!("diff counts {:?}", diff.counts()));
+        }
+        let rebuilt = |x: &HashSet<Position>, y: &HashSet<Position>| x.union(y).copied().collect::<HashSet<_>>();
+        if rebuilt(&diff.only_a, &diff.both) != a || rebuilt(&diff.only_b, &diff.both) != b || !diff.only_a.is_disjoint(&diff.only_b) {
+            return Err("the diff's parts should rebuild both boards without overlapping".to_string());
+        }
+        if diff_boards(&b, &a) != diff.clone().swapped() || !diff_boards(&a, &a).identical() || !diff_boards(&HashSet::new(), &HashSet::new()).identical() {
+            return Err("swapping the boards should swap the diff, and a board should match itself".to_string());
+        }
+
+        let mut sim = Simulation::new(48, 48);
+        sim.seed = 7;
+        sim.random_fill(0.3);
+        sim.mark_initial();
+        let mut compare = Compare::new(Reference::initial(&sim).ok_or("a marked board should have an initial state")?);
+        for _ in 0..60 {
+            sim.next_generation();
+        }
+        compare.update(&sim, 25);
+        if compare.caught_up || compare.reference.sim.generation != 25 {
+            return Err(format!("after one frame the reference is at {}, expected 25 and still catching up", compare.reference.sim.generation));
+        }
+        compare.update(&sim, 100);
+        if !compare.caught_up || !compare.diff.identical() || compare.diff.counts().both != sim.live.len() {
+            return Err(format!("the initial state run to generation 60 differs from the board: {:?}", compare.diff.counts()));
+        }
+        let drawn = (0..48).map(|x| Position::new(x, 0)).find(|p| !sim.live.contains(p)).ok_or("the top row is full")?;
+        sim.live.insert(drawn);
+        compare.swapped = true;
+        compare.update(&sim, 100);
+        if compare.diff.only_b != HashSet::from([drawn]) || !compare.diff.only_a.is_empty() {
+            return Err(format!("swapped, a cell drawn on the board should be the only one in the second part: {:?}", compare.diff.counts()));
+        }
+        let mut back = Simulation::new(48, 48);
+        back.seed = 7;
+        back.random_fill(0.3);
+        for _ in 0..20 {
+            back.next_generation();
+        }
+        compare.update(&back, 100);
+        if !compare.caught_up || compare.reference.sim.generation != 20 || !compare.diff.identical() {
+            return Err(format!("going back to generation 20 left the reference at {}", compare.reference.sim.generation));
+        }
+
+        let mut saved = Simulation::new(32, 32);
+        saved.place(&GliderPattern).map_err(|e| e.to_string())?;
+        let mut log = DeltaLog::new(&saved);
+        for _ in 0..8 {
+            saved.next_generation();
+            log.record(&saved);
+        }
+        let path = std::env::temp_dir().join(format!("conways-game-of-life-compare-{}.gold", std::process::id()));
+        std::fs::write(&path, log.to_bytes()).map_err(|e| e.to_string())?;
+        let mut board = Simulation::new(32, 32);
+        board.place(&GliderPattern).map_err(|e| e.to_string())?;
+        for _ in 0..4 {
+            board.next_generation();
+        }
+        let loaded = Reference::load(&path, &board);
+        let _ = std::fs::remove_file(&path);
+        let mut compare = Compare::new(loaded?);
+        compare.update(&board, 100);
+        if compare.reference.sim.generation != 4 || !compare.diff.identical() {
+            return Err(format!("a .gold reference loaded at generation {} with {:?}", compare.reference.sim.generation, compare.diff.counts()));
+        }
+        for _ in 0..6 {
+            board.next_generation();
+        }
+        compare.update(&board, 100);
+        if compare.reference.sim.generation != 10 || !compare.diff.identical() {
+            return Err("a .gold reference should run on past the end of its log".to_string());
+        }
+
+        let image = rasterize_diff(&diff, &FigureOptions { cell: 4, margin: 0, ..FigureOptions::default() });
+        let pixel = |x: i32, y: i32| -> [u8; 4] { image.get_pixel(x as u32 * 4 + 1, y as u32 * 4 + 1).into() };
+        let want: [[u8; 4]; 4] = [DIFF_ONLY_PRIMARY.into(), DIFF_ONLY_SECONDARY.into(), ColorTheme::Classic.colors().cell.into(), ColorTheme::Classic.colors().background.into()];
+        let got = [pixel(0, 0), pixel(3, 0), pixel(1, 0), pixel(4, 4)];
+        if (image.width, image.height) != (24, 24) || got != want {
+            return Err(format!("diff figure {}x{} with pixels {:?}, expected 24x24 with {:?}", image.width, image.height, got, want));
+        }
+
+        let args: Vec<String> = ["--diff", "a.rle", "b.rle", "--render", "diff.png"].iter().map(|s| s.to_string()).collect();
+        match parse_args(&args)? {
+            Some((Command::Render, opts)) if opts.diff == Some(("a.rle".to_string(), "b.rle".to_string())) => {}
+            _ => return Err("--diff with --render should render the diff".to_string()),
+        }
+        if !matches!(parse_args(&args[..3])?, Some((Command::Diff, _))) || parse_args(&args[..2]).is_ok() {
+            return Err("--diff alone should count the diff, and needs two files".to_string());
+        }
+        Ok(())
+    })();
+    report("compare: board diffs, references catching up and rewinding, diff figures", result)
+}
+
+/// The served line protocol round-trips; the feed sends deltas for consecutive generations and
+/// the whole board after an edit or a dropped message; a client whose queue fills is resynced
+/// with the whole board once it has room, and ends up with the same cells as one that kept up.
+/// With the `serve` feature, a real client gets the board and its commands reach the endpoint.
+fn check_serve() -> u32 {
+    let result = (|| {
+        let cells: HashSet<Position> = [(-3, 2), (0, 0), (7, 1), (7, 2)].map(|(x, y)| Position::new(x, y)).into();
+        let messages = [
+            serve::Message::Full { generation: 12, width: 40, height: 30, rule: Rule::LIFE.to_string(), cells: cells.clone() },
+            serve::Message::Delta { generation: 13, born: cells.clone(), died: HashSet::new() },
+            serve::Message::Rejected("stamp: unknown pattern 'x'".to_string()),
+        ];
+        for message in &messages {
+            let line = message.encode();
+            if line.contains('\n') || serve::Message::parse(&line).as_ref() != Ok(message) {
+                return Err(format!("{:?} came back from '{}' as {:?}", message, line, serve::Message::parse(&line)));
+            }
+        }
+        match serve::parse_command("stamp Gosper glider gun 10 20")? {
+            RemoteCommand::Stamp(Source::Named(name), at) if name == "Gosper glider gun" && at == Position::new(10, 20) => {}
+            other => return Err(format!("stamp parsed as {:?}", other)),
+        }
+        if let Some(line) = ["speed fast", "speed NaN", "speed inf", "speed -infinity", "clear", "stamp 1 2"].iter().find(|l| serve::parse_command(l).is_ok()) {
+            return Err(format!("'{}' should be refused", line));
+        }
+        if !matches!(serve::parse_command("speed 12.5")?, RemoteCommand::SetSpeed(12.5)) {
+            return Err("'speed 12.5' was not read as a speed".to_string());
+        }
+
+        let mut sim = Simulation::new(32, 32);
+        sim.place(&GliderPattern).map_err(|e| e.to_string())?;
+        sim.track_diff = true;
+        sim.mark_initial();
+        let (mut feed, updates) = serve::feed(4);
+        let mut hub = serve::Hub::default();
+        let (kept, kept_lines) = mpsc::sync_channel::<Arc<str>>(1000);
+        let (slow, slow_lines) = mpsc::sync_channel::<Arc<str>>(2);
+        hub.join(kept);
+        hub.join(slow);
+        let follow = |board: &mut HashSet<Position>, line: &str| -> Result<u64, String> {
+            match serve::Message::parse(line)? {
+                serve::Message::Full { generation, cells, .. } => {
+                    *board = cells;
+                    Ok(generation)
+                }
+                serve::Message::Delta { generation, born, died } => {
+                    board.retain(|p| !died.contains(p));
+                    board.extend(born);
+                    Ok(generation)
+                }
+                serve::Message::Rejected(_) => Err("no command was sent".to_string()),
+            }
+        };
+        let mut kinds = Vec::new();
+        let (mut kept_board, mut slow_board) = (HashSet::new(), HashSet::new());
+        let forward = |feed: &mut serve::Feed, sim: &Simulation, hub: &mut serve::Hub, kinds: &mut Vec<&'static str>| {
+            feed.publish(sim);
+            for message in updates.try_iter() {
+                kinds.push(if matches!(message, serve::Message::Full { .. }) { "full" } else { "delta" });
+                hub.update(message);
+            }
+        };
+        forward(&mut feed, &sim, &mut hub, &mut kinds);
+        sim.next_generation();
+        forward(&mut feed, &sim, &mut hub, &mut kinds);
+        forward(&mut feed, &sim, &mut hub, &mut kinds);
+        sim.set_cell(Position::new(30, 30), true);
+        forward(&mut feed, &sim, &mut hub, &mut kinds);
+        for _ in 0..6 {
+            sim.next_generation();
+            forward(&mut feed, &sim, &mut hub, &mut kinds);
+        }
+        if kinds[..4] != ["full", "delta", "full", "delta"] || kinds.len() != 9 {
+            return Err(format!("feed sent {:?}, expected a full board, a delta, a full board after the edit, then deltas", kinds));
+        }
+        for _ in 0..5 {
+            sim.next_generation();
+            feed.publish(&sim);
+        }
+        let mut queued: Vec<_> = updates.try_iter().collect();
+        sim.next_generation();
+        feed.publish(&sim);
+        queued.extend(updates.try_iter());
+        if queued.len() != 5 || !matches!(queued[4], serve::Message::Full { .. }) {
+            return Err(format!("{} queued, and the message after a dropped one should be the whole board", queued.len()));
+        }
+        for message in queued {
+            hub.update(message);
+        }
+
+        let mut slow_generations = Vec::new();
+        for line in slow_lines.try_iter() {
+            slow_generations.push(follow(&mut slow_board, &line)?);
+        }
+        let mut resynced = Vec::new();
+        for _ in 0..3 {
+            sim.next_generation();
+            forward(&mut feed, &sim, &mut hub, &mut kinds);
+            resynced.extend(slow_lines.try_iter().map(|l| l.to_string()));
+        }
+        if slow_generations.len() != 2 || resynced.first().is_none_or(|l| !l.starts_with("full ")) {
+            return Err(format!("the slow client got {} lines, then {:?}; expected 2 and then the whole board", slow_generations.len(), resynced));
+        }
+        for line in &resynced {
+            follow(&mut slow_board, line)?;
+        }
+        for line in kept_lines.try_iter() {
+            follow(&mut kept_board, &line)?;
+        }
+        if kept_board != sim.live || slow_board != sim.live {
+            return Err(format!("clients hold {} and {} cells, the board {}", kept_board.len(), slow_board.len(), sim.live.len()));
+        }
+        drop(slow_lines);
+        sim.next_generation();
+        forward(&mut feed, &sim, &mut hub, &mut kinds);
+        if hub.clients() != 1 {
+            return Err(format!("{} clients after one hung up, expected 1", hub.clients()));
+        }
+
+        if !serve::AVAILABLE {
+            return match serve::start("127.0.0.1:0") {
+                Ok(_) => Err("a build without the serve feature shouldn't serve".to_string()),
+                Err(_) => Ok(()),
+            };
+        }
+        let mut serving = serve::start("127.0.0.1:0")?;
+        let stream = std::net::TcpStream::connect(serving.address).map_err(|e| e.to_string())?;
+        stream.set_read_timeout(Some(std::time::Duration::from_secs(5))).map_err(|e| e.to_string())?;
+        let mut writer = stream.try_clone().map_err(|e| e.to_string())?;
+        let mut reader = std::io::BufReader::new(stream);
+        serving.feed.publish(&sim);
+        let mut line = String::new();
+        std::io::BufRead::read_line(&mut reader, &mut line).map_err(|e| e.to_string())?;
+        let mut board = HashSet::new();
+        if follow(&mut board, &line)? != sim.generation || board != sim.live {
+            return Err(format!("a client's first line was '{}'", line.trim()));
+        }
+        std::io::Write::write_all(&mut writer, b"jump\nspeed NaN\npause\n").map_err(|e| e.to_string())?;
+        for sent in ["jump", "speed NaN"] {
+            line.clear();
+            std::io::BufRead::read_line(&mut reader, &mut line).map_err(|e| e.to_string())?;
+            if !line.starts_with("error ") {
+                return Err(format!("'{}' was answered with '{}'", sent, line.trim()));
+            }
+        }
+        let mut control = Control { paused: false, speed: 10.0 };
+        let started = std::time::Instant::now();
+        while !control.paused && started.elapsed().as_secs() < 5 {
+            serving.endpoint.apply(&mut sim, &mut control);
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        if !control.paused || control.speed != 10.0 {
+            return Err(format!("after a refused speed and a pause the board was {} at speed {}", if control.paused { "paused" } else { "running" }, control.speed));
+        }
+        Ok(())
+    })();
+    report("serve: line protocol, deltas and resyncs, slow clients", result)
+}
+
+/// A board on the remote worker thread applies commands in the order sent, reports a rejected
+/// stamp, and publishes one summary per generation; a query after pausing agrees with the last
+/// summary, with the board handed back, and with the same cells stepped here
+fn check_remote() -> u32 {
+    let result = (|| {
+        let run = remote::spawn(Simulation::new(32, 32), SPEED_MAX, true)?;
+        let r = &run.remote;
+        // The last word on a cell wins, and off-grid cells are ignored
+        for (x, y, alive) in [(5, 5, true), (6, 5, true), (6, 5, false), (7, 5, true), (6, 5, true), (40, 5, true)] {
+            r.send(RemoteCommand::SetCell(Position::new(x, y), alive));
+        }
+        let q = r.query(Some(Rect::new(0, 0, 32, 32))).ok_or("no answer to a query")?;
+        let blinker: Vec<Position> = [(5, 5), (6, 5), (7, 5)].map(|(x, y)| Position::new(x, y)).to_vec();
+        if q.cells != blinker || q.population != 3 || q.generation != 0 || !q.paused {
+            return Err(format!("after setting cells the query saw {:?}", q));
+        }
+        r.send(RemoteCommand::Stamp(Source::Named("glider".to_string()), Position::new(20, 20)));
+        r.send(RemoteCommand::Stamp(Source::Named("No Such Pattern".to_string()), Position::new(20, 20)));
+        let q = r.query(None).ok_or("no answer to a query")?;
+        let rejected = r.events.try_iter().filter(|e| matches!(e, RemoteEvent::Rejected(_))).count();
+        if q.population != 8 || rejected != 1 || !q.cells.is_empty() {
+            return Err(format!("after two stamps: {} cells, {} rejected", q.population, rejected));
+        }
+
+        // Run to generation 30, then stop and compare
+        r.send(RemoteCommand::Pause(false));
+        let mut last = None;
+        let mut check = |event: RemoteEvent| match event {
+            RemoteEvent::Generation(summary) if summary.generation == last.map_or(1, |s: RemoteSummary| s.generation + 1) => {
+                last = Some(summary);
+                Ok(summary.generation)
+            }
+            other => Err(format!("after generation {:?} came {:?}", last.map(|s| s.generation), other)),
+        };
+        loop {
+            let event = r.events.recv_timeout(std::time::Duration::from_secs(5)).map_err(|_| "the run stopped publishing")?;
+            if check(event)? >= 30 {
+                r.send(RemoteCommand::Pause(true));
+                break;
+            }
+        }
+        let q = r.query(None).ok_or("no answer to a query")?;
+        for event in r.events.try_iter() {
+            check(event)?;
+        }
+        let last = last.ok_or("no generations were published")?;
+        if (last.generation, last.population, last.hash) != (q.generation, q.population, q.hash) || !q.paused {
+            return Err(format!("the last summary {:?} disagrees with the query {:?}", last, q));
+        }
+        let mut local = Simulation::new(32, 32);
+        local.live.extend(blinker);
+        local.stamp(default_registry().get("Glider").ok_or("no glider")?, Transform::IDENTITY, 0, 20, 20).map_err(|e| e.to_string())?;
+        for _ in 0..q.generation {
+            local.next_generation();
+        }
+        let sim = run.finish();
+        if board_hash(&local.live) != q.hash || sim.generation != q.generation || board_hash(&sim.live) != q.hash {
+            return Err(format!("generation {}: the query's board differs from the one handed back or stepped here", q.generation));
+        }
+        Ok(())
+    })();
+    report("remote commands apply in order and queries match the events", result)
+}
+
+/// An element of a parsed SVG: its name, how deeply it is nested and its attributes
+type SvgElement = (String, usize, Vec<(String, String)>);
+
+/// Just enough of an XML reader to check an exported SVG: every tag closes in order, attribute
+/// values are quoted and only whitespace sits between tags. Returns the elements and comments.
+fn parse_svg(text: &str) -> Result<(Vec<SvgElement>, Vec<String>), String> {
+    let (mut elements, mut comments, mut open) = (Vec::new(), Vec::new(), Vec::<String>::new());
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        if !rest[..start].trim().is_empty() {
+            return Err(format!("text outside a tag: {:?}", &rest[..start]));
+        }
+        rest = &rest[start..];
+        if let Some(body) = rest.strip_prefix("<!--") {
+            let end = body.find("-->").ok_or("an unclosed comment")?;
+            comments.push(body[..end].trim().to_string());
+            rest = &body[end + 3..];
+            continue;
+        }
+        let end = rest.find('>').ok_or("an unclosed tag")?;
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+        if tag.starts_with('?') {
+            continue;
+        }
+        if let Some(name) = tag.strip_prefix('/') {
+            if open.pop().as_deref() != Some(name) {
+                return Err(format!("</{}> closes the wrong element", name));
+            }
+            continue;
+        }
+        let closed = tag.ends_with('/');
+        let tag = tag.trim_end_matches('/');
+        let (name, mut attrs_text) = tag.split_once(' ').unwrap_or((tag, ""));
+        let mut attrs = Vec::new();
+        while let Some((key, after)) = attrs_text.trim_start().split_once("=\"") {
+            let (value, after) = after.split_once('"').ok_or_else(|| format!("unquoted value for {}", key))?;
+            attrs.push((key.to_string(), value.to_string()));
+            attrs_text = after;
+        }
+        if !attrs_text.trim().is_empty() {
+            return Err(format!("stray text in <{}>: {:?}", name, attrs_text));
+        }
+        elements.push((name.to_string(), open.len(), attrs));
+        if !closed {
+            open.push(name.to_string());
+        }
+    }
+    if !rest.trim().is_empty() || !open.is_empty() {
+        return Err(format!("unclosed elements {:?}", open));
+    }
+    Ok((elements, comments))
+}
+
+/// An SVG export of a glider and a block parses as XML with one rect per run of cells in a row,
+/// the background and border around them, a path for grid lines when asked, and the generation
+/// and rule in its comments
+fn check_svg() -> u32 {
+    let result = (|| {
+        let mut sim = Simulation::new(12, 10);
+        sim.live.extend([(2, 1), (3, 2), (1, 3), (2, 3), (3, 3), (7, 6), (8, 6), (7, 7), (8, 7)].map(|(x, y)| Position::new(x, y)));
+        sim.next_generation();
+        let count = |elements: &[SvgElement], name: &str, depth: usize| elements.iter().filter(|(n, d, _)| n == name && *d == depth).count();
+        let svg = board_to_svg(&sim, &SvgOptions::default())?;
+        let (elements, comments) = parse_svg(&svg)?;
+        // After one generation the glider is O.O / .OO / .O.: two runs in its top row, one in each
+        // of the others, and one in each of the block's two rows
+        let runs = row_runs(&sim.live);
+        if runs.len() != 6 || count(&elements, "rect", 2) != 6 || count(&elements, "rect", 1) != 2 || count(&elements, "path", 1) != 0 {
+            return Err(format!("{} runs, {} cell rects, {} other rects", runs.len(), count(&elements, "rect", 2), count(&elements, "rect", 1)));
+        }
+        let attr = |element: &SvgElement, key: &str| element.2.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone()).unwrap_or_default();
+        let cell_rects: Vec<String> = elements.iter().filter(|e| e.0 == "rect" && e.1 == 2)
+            .map(|e| format!("{},{},{},{}", attr(e, "x"), attr(e, "y"), attr(e, "width"), attr(e, "height")))
+            .collect();
+        let want: Vec<String> = runs.iter().map(|(x, y, n)| format!("{},{},{},{}", x * 10, y * 10, n * 10, 10)).collect();
+        let root = &elements[0];
+        if cell_rects != want || root.0 != "svg" || attr(root, "width") != "120" || attr(root, "height") != "100" {
+            return Err(format!("cell rects {:?}, expected {:?}", cell_rects, want));
+        }
+        if !comments.iter().any(|c| c.contains("generation 1")) || !comments.iter().any(|c| c.contains("B3/S23")) {
+            return Err(format!("comments {:?} miss the generation or rule", comments));
+        }
+        let svg = board_to_svg(&sim, &SvgOptions { grid: true, border: false, cell: 4, theme: ColorTheme::Neon })?;
+        let (elements, _) = parse_svg(&svg)?;
+        if count(&elements, "path", 1) != 1 || count(&elements, "rect", 1) != 1 || count(&elements, "rect", 2) != 6 {
+            return Err("grid lines without a border drew the wrong shapes".to_string());
+        }
+        if svg_size_warning(SVG_WARN_CELLS).is_some() || svg_size_warning(SVG_WARN_CELLS + 1).is_none() {
+            return Err("the size warning starts at the wrong cell count".to_string());
+        }
+        Ok(())
+    })();
+    report("SVG export", result)
+}
+
+/// The mutations of a rule are every runnable rule one neighbour count away, and the explorer
+/// runs them the same on its worker thread as a frame at a time, matching a direct run
+fn check_mutations() -> u32 {
+    let result = (|| {
+        for base in [Rule::LIFE, Rule::parse("B36/S23")?, Rule::parse("B2/S")?] {
+            let rules = mutations(base);
+            let distinct: HashSet<Rule> = rules.iter().copied().collect();
+            let one_away = rules.iter().all(|r| ((r.birth ^ base.birth).count_ones() + (r.survive ^ base.survive).count_ones()) == 1);
+            if rules.len() != 17 || distinct.len() != 17 || !one_away || rules.iter().any(|r| r.strobes()) {
+                return Err(format!("{} has {} mutations, {} distinct, one count away: {}", base, rules.len(), distinct.len(), one_away));
+            }
+        }
+        for (rule, want) in [("B36/S23", "+B6"), ("B3/S2", "-S3"), ("B3/S23", "same")] {
+            let got = mutation::change(Rule::LIFE, Rule::parse(rule)?);
+            if got != want {
+                return Err(format!("{} from B3/S23 is '{}', expected '{}'", rule, got, want));
+            }
+        }
+        let (seed, density) = (11, 0.35);
+        let mut threaded = Explorer::start(Rule::LIFE, seed, density, Topology::Torus, ColorTheme::Classic);
+        let mut inline = Explorer::inline(Rule::LIFE, seed, density, Topology::Torus, ColorTheme::Classic);
+        inline.update();
+        if inline.done() != 0 {
+            return Err(format!("one frame finished {} runs of {} generations", inline.done(), MUTATION_GENERATIONS));
+        }
+        threaded.wait();
+        inline.wait();
+        let populations = |e: &Explorer| e.trials.iter().map(|t| t.as_ref().map(|t| t.population)).collect::<Vec<_>>();
+        if threaded.done() != 17 || populations(&threaded) != populations(&inline) {
+            return Err(format!("threaded runs {:?} differ from inline runs {:?}", populations(&threaded), populations(&inline)));
+        }
+        let (i, rule) = (4, threaded.rules[4]);
+        let mut sim = Simulation::new(MUTATION_SIZE.0, MUTATION_SIZE.1);
+        sim.grid.topology = Topology::Torus;
+        sim.grid.rule = rule;
+        sim.seed = seed;
+        sim.random_fill(density);
+        for _ in 0..MUTATION_GENERATIONS {
+            sim.next_generation();
+        }
+        let trial = threaded.trials[i].as_ref().ok_or("a finished run is missing")?;
+        if trial.rule != rule || trial.population != sim.live.len() {
+            return Err(format!("{} ended with {} cells, a direct run with {}", rule, trial.population, sim.live.len()));
+        }
+        let image = &trial.thumbnail.image;
+        if (image.width, image.height) != THUMB_SIZE {
+            return Err(format!("thumbnail is {}x{}, expected {:?}", image.width, image.height, THUMB_SIZE));
+        }
+        Ok(())
+    })();
+    report("rule mutation explorer", result)
+}
+
+/// The heat field stays within its size cap on the largest board, warms only the blocks where
+/// cells were born or died, and cools and spreads without gaining heat until it goes cold
+fn check_heat() -> u32 {
+    let result = (|| {
+        for (w, h) in [(1, 1), (12, 12), (1280, 720), (GRID_MAX, GRID_MAX), (GRID_MAX, 1)] {
+            let field = HeatField::new(&Grid::new(w, h));
+            if field.len() > HEAT_MAX_BLOCKS || field.cols * field.block < w || field.rows * field.block < h || field.block < HEAT_BLOCK {
+                return Err(format!("{}x{} board got {} values of {} cells a side", w, h, field.len(), field.block));
+            }
+        }
+        // A blinker in the middle block of a 12x12 board flips four cells each generation
+        let mut sim = Simulation::new(12, 12);
+        sim.track_diff = true;
+        sim.live.extend([(4, 5), (5, 5), (6, 5)].map(|(x, y)| Position::new(x, y)));
+        let mut field = HeatField::new(&sim.grid);
+        sim.next_generation();
+        field.record(sim.diff.as_ref().ok_or("no diff was recorded")?);
+        let per_cell = 1.0 / (field.block * field.block) as f32;
+        if (field.at(5, 5) - 4.0 * per_cell).abs() > 1e-6 || (field.total() - 4.0 * per_cell).abs() > 1e-6 {
+            return Err(format!("blinker warmed its block to {} of {} in total", field.at(5, 5), field.total()));
+        }
+        let before = field.total();
+        field.cool(0.1);
+        let kept = (-0.1 / HEAT_FADE_SECONDS).exp();
+        if (field.total() - before * kept).abs() > 1e-5 || field.at(0, 5) <= 0.0 || field.at(0, 0) != 0.0 || field.at(5, 5) >= before * kept {
+            return Err(format!("cooling left {} in total, {} next door, {} in a corner", field.total(), field.at(0, 5), field.at(0, 0)));
+        }
+        for _ in 0..1000 {
+            field.cool(0.1);
+        }
+        if field.total() != 0.0 || !field.fits(&sim.grid) || field.fits(&Grid::new(13, 12)) {
+            return Err(format!("a quiet field still holds {}", field.total()));
+        }
+        let settings = Settings::parse("heat_overlay = true\nheat_intensity = 0.8");
+        if Settings::default().heat || !settings.heat || settings.heat_intensity != 0.8 || Settings::parse(&settings.to_text()) != settings {
+            return Err("the heat settings don't survive the settings file".to_string());
+        }
+        Ok(())
+    })();
+    report("heat overlay field", result)
+}
+
+/// Particles come from a generation's births and deaths, never outgrow their pool, fade out
+/// on time, skip a generation with too many births, and are off unless the settings say so
+fn check_particles() -> u32 {
+    let result = (|| {
+        // A blinker flips two cells each way every generation
+        let mut sim = Simulation::new(12, 12);
+        sim.track_diff = true;
+        sim.live.extend([(4, 5), (5, 5), (6, 5)].map(|(x, y)| Position::new(x, y)));
+        let mut particles = Particles::default();
+        sim.next_generation();
+        particles.record(sim.diff.as_ref().ok_or("no diff was recorded")?);
+        let births = particles.active().filter(|p| p.effect == Effect::Birth).count();
+        let deaths = particles.active().filter(|p| p.effect == Effect::Death).count();
+        if (births, deaths) != (2, 2) || particles.active().any(|p| p.progress() != 0.0) {
+            return Err(format!("a blinker step emitted {} births and {} deaths", births, deaths));
+        }
+        particles.update(PARTICLE_SECONDS / 2.0);
+        if particles.active().any(|p| (p.progress() - 0.5).abs() > 1e-6) {
+            return Err("particles didn't age with the frame time".to_string());
+        }
+        particles.update(PARTICLE_SECONDS);
+        if particles.active().next().is_some() {
+            return Err("particles outlived their lifetime".to_string());
+        }
+
+        // A full pool makes way for new particles rather than growing
+        let mut small = Particles::with_capacity(3);
+        for _ in 0..4 {
+            sim.next_generation();
+            small.record(sim.diff.as_ref().ok_or("no diff was recorded")?);
+        }
+        if small.capacity() != 3 || small.active().count() != 3 {
+            return Err(format!("a pool of 3 held {} of capacity {}", small.active().count(), small.capacity()));
+        }
+
+        // A burst of births above the limit emits nothing and says so
+        let burst = GenerationDiff {
+            generation: 1,
+            born: (0..=PARTICLE_MAX_BIRTHS as i32).map(|x| Position::new(x, 0)).collect(),
+            died: vec![Position::new(0, 1)],
+        };
+        let mut particles = Particles::default();
+        particles.record(&burst);
+        if !particles.skipped || particles.active().next().is_some() {
+            return Err(format!("a burst of {} births emitted {} particles", burst.born.len(), particles.active().count()));
+        }
+
+        let settings = Settings::parse("particles = true\nparticle_intensity = 0.3");
+        if Settings::default().particles || !settings.particles || settings.particle_intensity != 0.3 || Settings::parse(&settings.to_text()) != settings {
+            return Err("the particle settings don't survive the settings file".to_string());
+        }
+        Ok(())
+    })();
+    report("birth and death particles", result)
+}
+
+/// Each smart clear scope removes exactly its cells from a torus holding an old block across
+/// the seam, an old blinker, and a young block and glider; putting the cells back undoes it,
+/// and the menu refuses scopes the board can't use
+fn check_smart_clear() -> u32 {
+    let result = (|| {
+        let cells = |list: &[(i32, i32)]| -> HashSet<Position> { list.iter().map(|&(x, y)| Position::new(x, y)).collect() };
+        let seam_block = cells(&[(39, 10), (0, 10), (39, 11), (0, 11)]);
+        let blinker = cells(&[(10, 20), (11, 20), (12, 20)]);
+        let young_block = cells(&[(25, 5), (26, 5), (25, 6), (26, 6)]);
+        let glider = cells(&[(31, 15), (32, 16), (30, 17), (31, 17), (32, 17)]);
+        let compose = || {
+            let mut sim = Simulation::new(40, 30);
+            sim.grid.topology = Topology::Torus;
+            sim.live.extend(seam_block.iter().chain(&blinker));
+            sim.set_age_tracking(true);
+            for _ in 0..4 {
+                sim.next_generation();
+            }
+            sim.live.extend(young_block.iter().chain(&glider));
+            if let Some(ages) = sim.ages.as_mut() {
+                ages.sync(&sim.live, sim.generation);
+            }
+            sim
+        };
+        let mut sim = compose();
+        let board = sim.live.clone();
+        let selection = Rect::new(24, 4, 12, 15);
+        let union = |sets: &[&HashSet<Position>]| -> HashSet<Position> { sets.iter().flat_map(|s| s.iter().copied()).collect() };
+        let blinker_ends = cells(&[(10, 20), (12, 20)]);
+        let cases = [
+            (ClearScope::All, board.clone()),
+            (ClearScope::Outside(selection), union(&[&seam_block, &blinker])),
+            (ClearScope::Inside(selection), union(&[&young_block, &glider])),
+            (ClearScope::ExceptStillLifes, union(&[&blinker, &glider])),
+            (ClearScope::ExceptOlderThan(3), union(&[&blinker_ends, &young_block, &glider])),
+            (ClearScope::Inside(Rect::new(15, 22, 5, 5)), HashSet::new()),
+        ];
+        for (scope, expected) in cases {
+            let mut cleared = compose();
+            let removed = cleared.clear_scope(scope);
+            if removed != expected || cleared.live != board.difference(&expected).copied().collect() {
+                return Err(format!("{:?} removed {} cells, expected {}", scope, removed.len(), expected.len()));
+            }
+            if (scope == ClearScope::All) != (cleared.generation == 0) {
+                return Err(format!("{:?} left the board at generation {}", scope, cleared.generation));
+            }
+            cleared.live.extend(removed);
+            if board_hash(&cleared.live) != board_hash(&board) {
+                return Err(format!("putting back what {:?} removed didn't restore the board", scope));
+            }
+        }
+
+        // Scopes the board can't use are refused with a reason
+        let menu = ClearMenu::new();
+        let usable = |sim: &Simulation, selection| (0..ClearMenu::ROWS).map(|i| menu.scope(i, sim, selection).is_ok()).collect::<Vec<_>>();
+        if usable(&sim, Some(selection)) != [true; 5] {
+            return Err(format!("with a selection and ages the menu offered {:?}", usable(&sim, Some(selection))));
+        }
+        sim.set_age_tracking(false);
+        if usable(&sim, None) != [true, false, false, true, false] {
+            return Err(format!("without a selection or ages the menu offered {:?}", usable(&sim, None)));
+        }
+        if menu.scope(4, &sim, None).is_ok() || sim.clear_plan(ClearScope::ExceptOlderThan(3)) != sim.live {
+            return Err("without ages, old cells were kept".to_string());
+        }
+        Ok(())
+    })();
+    report("smart clear scopes", result)
+}
+
+/// A selection copied in one tab pastes into another, differently sized, board as the same
+/// cells around where it is stamped, and closing a tab asks for a second Ctrl+W
+fn check_tab_copy() -> u32 {
+    let result = (|| {
+        let mut source = Simulation::new(40, 30);
+        source.live.extend([(11, 10), (12, 11), (10, 12), (11, 12), (12, 12), (30, 20)].map(|(x, y)| Position::new(x, y)));
+        let region = Rect::new(9, 9, 5, 5); // The glider with a margin; the stray cell is left out
+        let pattern = source.copy_region(region, "Selection 5x5");
+        if pattern.cells.len() != 5 || (pattern.width, pattern.height) != (5, 5) {
+            return Err(format!("copied {} cells in {}x{}", pattern.cells.len(), pattern.width, pattern.height));
+        }
+        let mut target = Simulation::new(64, 64);
+        target.stamp(&pattern, Transform::IDENTITY, 0, 40, 40).map_err(|e| e.to_string())?;
+        // Stamps are centered, so the region's center cell (11, 11) lands on (40, 40)
+        let want: HashSet<Position> = source.live.iter()
+            .filter(|p| region.contains(**p))
+            .map(|p| Position::new(p.x() + 29, p.y() + 29))
+            .collect();
+        if target.live != want {
+            return Err(format!("pasted {} cells, {} of them where expected", target.live.len(), target.live.intersection(&want).count()));
+        }
+        let mut guard = Guard::new(true);
+        if guard.allow(Destructive::CloseTab, true, 0.0) || !guard.allow(Destructive::CloseTab, true, 0.5) {
+            return Err("closing a tab didn't take two presses".to_string());
+        }
+        if !Destructive::CloseTab.hint().starts_with("Press Ctrl+W again") {
+            return Err(format!("close hint reads '{}'", Destructive::CloseTab.hint()));
+        }
+        Ok(())
+    })();
+    report("selections copy across tabs", result)
+}
+
+/// Destructive keys on a running board go ahead only on a second press of the same action in
+/// time; paused boards, edit mode and a guard turned off never ask, and the settings survive a save
+fn check_guard() -> u32 {
+    let result = (|| {
+        let command = |line: &str| Destructive::Command(line.to_string());
+        // (action, running, seconds, goes ahead)
+        let presses = [
+            (Destructive::Clear, false, 0.0, true),
+            (Destructive::Clear, true, 1.0, false),
+            (Destructive::Clear, true, 2.0, true),
+            (Destructive::Clear, true, 2.2, false), // Confirming disarms it
+            (Destructive::Clear, true, 4.0, false), // Too late: asks again
+            (Destructive::Reseed, true, 4.5, false), // A different key replaces the armed one
+            (Destructive::Clear, true, 4.6, false),
+            (Destructive::Clear, false, 4.7, true), // Paused since: no question
+            (command("fill 0.3"), true, 5.0, false),
+            (command("clear"), true, 5.5, false),
+            (command("clear"), true, 6.0, true),
+            (Destructive::Cleanup, true, 8.0, false),
+            (Destructive::Cleanup, true, 8.0 + CONFIRM_SECONDS, true),
+        ];
+        let mut guard = Guard::new(true);
+        for (i, (action, running, now, want)) in presses.into_iter().enumerate() {
+            let label = format!("{:?}", action);
+            if guard.allow(action, running, now) != want {
+                return Err(format!("press {} ({} at {} s) went ahead: {}", i, label, now, !want));
+            }
+        }
+        let mut off = Guard::new(false);
+        if !off.allow(Destructive::Clear, true, 0.0) || !off.allow(Destructive::Reseed, true, 0.1) {
+            return Err("a guard turned off still asked".to_string());
+        }
+        let destructive: Vec<bool> = ["clear", "fill 0.3", "seed 7", "load x.rle", "import-all *.rle", "speed 10", "step 5", "goto 100", "image x.png"]
+            .iter().map(|line| console::parse(line).is_ok_and(|c| c.destructive())).collect();
+        if destructive != [true, true, true, true, true, false, false, false, false] {
+            return Err(format!("console commands marked destructive: {:?}", destructive));
+        }
+        let defaults = Settings::default();
+        if !defaults.confirm_destructive || !defaults.pause_on_edit {
+            return Err("the protection is off by default".to_string());
+        }
+        let settings = Settings::parse("confirm_destructive = false\npause_on_edit = false");
+        if settings.confirm_destructive || settings.pause_on_edit || Settings::parse(&settings.to_text()) != settings {
+            return Err("the protection settings don't survive the settings file".to_string());
+        }
+        Ok(())
+    })();
+    report("destructive keys confirm while running", result)
+}
+
+/// Cleanup removes small objects and settled ones by the census's shapes, leaves gliders and
+/// larger unknown objects alone, takes whole objects across a torus seam and can be put back
+fn check_cleanup() -> u32 {
+    let mut failures = 0;
+    // Blinker, block, beehive, pond, a lone pair, an R-pentomino and a glider, well apart
+    let board = "\
+        ...................................../\
+        .###...##...##.......##.............../\
+        .......##..#..#.....#..#....#......#../\
+        ............##......#..#....#.......#./\
+        .....................##.............###/\
+        ...................................../\
+        ..............##...................../\
+        .............##....................../\
+        ..............#....................../";
+    let (grid, live) = from_ascii(board, Topology::Bounded);
+    let mut sim = Simulation::new(grid.width, grid.height);
+    sim.live = live.clone();
+    for (cleanup, objects, cells, kept) in [
+        (Cleanup::default(), 5, 3 + 4 + 6 + 8 + 2, 10),
+        (Cleanup { min_size: 5, known: false }, 3, 3 + 4 + 2, 24),
+        (Cleanup { min_size: 0, known: true }, 4, 3 + 4 + 6 + 8, 12),
+    ] {
+        let mut sim = Simulation::new(grid.width, grid.height);
+        sim.live = live.clone();
+        let cleaned = sim.clean_up(cleanup);
+        let result = if cleaned.objects != objects || cleaned.cells.len() != cells || sim.live.len() != kept {
+            Err(format!("{}, {} cells left; expected {} objects, {} cells and {} left", cleaned, sim.live.len(), objects, cells, kept))
+        } else {
+            sim.live.extend(cleaned.cells);
+            if sim.live == live { Ok(()) } else { Err("putting the cells back didn't restore the board".to_string()) }
+        };
+        let name = match (cleanup.min_size, cleanup.known) {
+            (0, _) => "cleanup of settled objects".to_string(),
+            (n, known) => format!("cleanup under {} cells{}", n, if known { " and settled objects" } else { "" }),
+        };
+        failures += report(&name, result);
+    }
+    let result = match sim.debris(Cleanup::default()).to_string().as_str() {
+        "removed 5 objects (23 cells)" => Ok(()),
+        other => Err(other.to_string()),
+    };
+    failures += report("cleanup report", result);
+
+    // A block split over all four corners of a torus is still one object
+    let (grid, live) = from_ascii("#..#/..../..../#..#", Topology::Torus);
+    let mut sim = Simulation::new(grid.width, grid.height);
+    sim.grid.topology = Topology::Torus;
+    sim.live = live;
+    let cleaned = sim.clean_up(Cleanup::default());
+    let result = if cleaned.objects == 1 && cleaned.cells.len() == 4 && sim.live.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("{}, {} cells left", cleaned, sim.live.len()))
+    };
+    failures += report("cleanup across a torus seam", result);
+    failures
+}
+
+/// Growth classification of synthetic population series: a blinker's flat count, an
+/// oscillator's, a gun's sawtooth climb, a breeder-like parabola and a decay that curves the
+/// other way, plus a tracker that starts over when the run goes back in time
+fn check_growth() -> u32 {
+    let mut failures = 0;
+    // Small deterministic wobble, as oscillating parts of a real pattern give
+    let wobble = |g: u64| ((g * 7) % 11) as f64 - 5.0;
+    let series = |from: u64, f: &dyn Fn(u64) -> f64| (from..from + 600).map(|g| (g, f(g).round().max(0.0) as usize)).collect::<Vec<_>>();
+    let cases = [
+        ("blinker", series(0, &|_| 3.0), Growth::Constant, None),
+        ("pulsar", series(0, &|g| [48.0, 56.0, 72.0][g as usize % 3]), Growth::Constant, None),
+        ("gun", series(0, &|g| 36.0 + 5.0 * (g / 30) as f64 + wobble(g)), Growth::Linear, Some(5.0 / 30.0)),
+        ("late gun", series(20_000, &|g| 36.0 + 5.0 * (g / 30) as f64 + wobble(g)), Growth::Linear, Some(5.0 / 30.0)),
+        ("breeder", series(1000, &|g| (g * g) as f64 / 200.0 + wobble(g)), Growth::Quadratic, Some(0.01)),
+        ("decay", series(0, &|g| 5000.0 - (g * g) as f64 / 200.0), Growth::Linear, None),
+    ];
+    for (name, samples, growth, rate) in cases {
+        let result = match classify_growth(&samples) {
+            Some(trend) if trend.growth != growth => Err(format!("classified as {}", trend)),
+            Some(trend) if rate.is_some_and(|r| (trend.rate - r).abs() > 0.1 * r) => Err(format!("rate {} (expected {:?})", trend.rate, rate)),
+            Some(_) => Ok(()),
+            None => Err("not classified".to_string()),
+        };
+        failures += report(&format!("growth trend: {}", name), result);
+    }
+
+    // The real gun, far enough from the walls that no glider reaches one
+    let result = (|| {
+        let registry = default_registry();
+        let mut sim = Simulation::new(400, 400);
+        sim.place(registry.get("Gosper Gun").ok_or("no Gosper Gun")?).map_err(|e| e.to_string())?;
+        let mut samples = vec![(0, sim.live.len())];
+        for _ in 0..600 {
+            sim.next_generation();
+            samples.push((sim.generation, sim.live.len()));
+        }
+        match classify_growth(&samples) {
+            Some(trend) if trend.growth == Growth::Linear && (trend.rate - 5.0 / 30.0).abs() < 0.02 => Ok(()),
+            other => Err(format!("classified as {:?}", other)),
+        }
+    })();
+    failures += report("growth trend: Gosper gun run", result);
+
+    let result = (|| {
+        let mut tracker = GrowthTracker::default();
+        for g in 0..800 {
+            tracker.observe(g, 10 + g as usize / 4);
+            tracker.observe(g, 0); // A second frame on the same generation is ignored
+        }
+        match tracker.trend() {
+            Some(trend) if trend.growth == Growth::Linear && (trend.rate - 0.25).abs() < 0.01 => {}
+            other => return Err(format!("a steady climb gave {:?}", other)),
+        }
+        tracker.observe(5, 10);
+        if tracker.trend().is_some() {
+            return Err("history survived going back to generation 5".to_string());
+        }
+        Ok(())
+    })();
+    failures += report("growth tracker window", result);
+    failures
+}
+
+/// Run summary figures: extinction by stepping versus clearing, and repeat periods
+fn check_run_stats() -> u32 {
+    let mut failures = 0;
+    let result = (|| {
+        let mut stats = RunStats::default();
+        let (grid, mut live) = from_ascii("....../..##../..#.../......", Topology::Bounded);
+        for generation in 0..=3 {
+            stats.observe(generation, live.len());
+            live = grid.next_generation(&live);
+        }
+        if stats.peak != 4 || stats.extinct_at.is_some() {
+            return Err(format!("L-tromino: peak {}, extinct {:?}", stats.peak, stats.extinct_at));
+        }
+        let mut stats = RunStats::default();
+        stats.observe(0, 1);
+        stats.observe(1, 0);
+        if stats.extinct_at != Some(1) {
+            return Err(format!("lone cell should die out at generation 1, got {:?}", stats.extinct_at));
+        }
+        stats.observe(0, 0);
+        if stats.extinct_at.is_some() {
+            return Err("clearing the board counted as dying out".to_string());
+        }
+        Ok(())
+    })();
+    failures += report("run summary extinction", result);
+
+    let cases = [("blinker", "...../.###./.....", Some(2)), ("block", "..../.##./.##./....", Some(1))];
+    for (name, board, expected) in cases {
+        let (grid, live) = from_ascii(board, Topology::Bounded);
+        let found = analysis::period(&grid, &live, SUMMARY_PERIOD_MAX);
+        let result = if found == expected { Ok(()) } else { Err(format!("expected {:?}, got {:?}", expected, found)) };
+        failures += report(&format!("run summary period ({})", name), result);
+    }
+    failures
+}
+
+/// Run a soup under a clock fed the given frame lengths in ticks, noting the generation
+/// after every frame that ends on a multiple of `every` ticks
+fn run_clocked(seed: u64, speed: f32, frames: &[u64], every: u64) -> (Vec<(u64, u64)>, String) {
+    let mut sim = Simulation::new(48, 48);
+    sim.live = random_soup(&sim.grid, 0.35, seed);
+    let mut clock = SimClock::new(speed);
+    let mut seen = Vec::new();
+    for &ticks in frames {
+        clock.advance(ticks);
+        let due = clock.due();
+        clock.take(due);
+        for _ in 0..due {
+            sim.next_generation();
+        }
+        if clock.ticks().is_multiple_of(every) {
+            seen.push((clock.ticks(), sim.generation));
+        }
+    }
+    (seen, to_ascii(&sim.grid, &sim.live))
+}
+
+/// Split `total` ticks into frames, cutting at every multiple of `every` as well
+fn frame_script(total: u64, every: u64, mut next_len: impl FnMut() -> u64) -> Vec<u64> {
+    let mut frames = Vec::new();
+    let mut at = 0;
+    while at < total {
+        let end = (at + next_len().max(1)).min((at / every + 1) * every).min(total);
+        frames.push(end - at);
+        at = end;
+    }
+    frames
+}
+
+/// Fixed-timestep clock: steady and ragged frame times reach the same generation at the same
+/// tick, and speed changes carry progress over instead of bursting or restarting the wait
+fn check_clock(seed: u64) -> u32 {
+    let mut failures = 0;
+    let (total, every) = (3 * TICKS_PER_SECOND, TICKS_PER_SECOND / 20);
+    let steady = frame_script(total, every, || TICKS_PER_SECOND / 60);
+    let mut rng = Rng::new(seed);
+    let ragged = frame_script(total, every, || rng.next_u64() % (TICKS_PER_SECOND / 8));
+    let (a, board_a) = run_clocked(seed, 7.3, &steady, every);
+    let (b, board_b) = run_clocked(seed, 7.3, &ragged, every);
+    let result = if a != b {
+        Err(format!("generations by tick differ: {:?} vs {:?}", a.last(), b.last()))
+    } else if a.last() != Some(&(total, 21)) {
+        Err(format!("expected generation 21 at {} ticks, got {:?}", total, a.last()))
+    } else {
+        same_board(board_a, board_b)
+    };
+    failures += report("clock: frame splits", result);
+
+    let result = (|| {
+        let mut clock = SimClock::new(1.0);
+        clock.advance(SimClock::ticks_in(0.9));
+        clock.set_speed(60.0);
+        if clock.due() != 0 {
+            return Err(format!("speed-up released {} steps at once", clock.due()));
+        }
+        clock.advance(SimClock::ticks_in(1.0 / 600.0));
+        if clock.due() != 1 {
+            return Err(format!("speed-up kept the old wait: {} steps due", clock.due()));
+        }
+        let mut clock = SimClock::new(10.0);
+        clock.advance(SimClock::ticks_in(0.05));
+        clock.set_speed(20.0);
+        if (clock.fraction() - 0.5).abs() > 1e-3 {
+            return Err(format!("progress {:.3} after the change, expected 0.5", clock.fraction()));
+        }
+        clock.advance(SimClock::ticks_in(0.025));
+        if clock.due() != 1 {
+            return Err(format!("{} steps due a half step after the change", clock.due()));
+        }
+        clock.take(1);
+        clock.set_speed(0.5);
+        clock.advance(SimClock::ticks_in(1.0));
+        if clock.due() != 0 || clock.ticks() != SimClock::ticks_in(1.075) {
+            return Err(format!("slow-down: {} due at {} ticks", clock.due(), clock.ticks()));
+        }
+        Ok(())
+    })();
+    failures += report("clock: speed changes", result);
+    failures
+}
+
+/// Aiming stamps: two arrow presses turn a gun or a glider so what it fires or where it goes
+/// actually travels that way once placed and run
+fn check_aiming() -> u32 {
+    let mut failures = 0;
+    let registry = default_registry();
+    for (name, generations) in [("Gosper Gun", 240), ("Glider", 40)] {
+        let result = (|| {
+            let pattern = registry.get(name).ok_or("pattern missing from the registry")?;
+            let heading = patterns::heading(pattern).ok_or("no heading found")?;
+            for target in [(-1, 1), (1, 1), (-1, -1), (1, -1)] {
+                let turned = patterns::steer(Transform::IDENTITY, heading, (0, target.1))
+                    .and_then(|t| patterns::steer(t, heading, (target.0, 0)))
+                    .ok_or(format!("can't aim {}", patterns::heading_name(target)))?;
+                let mut sim = Simulation::new(300, 300);
+                sim.stamp(pattern, turned, 0, 150, 150).map_err(|e| e.to_string())?;
+                let start = sim.bounding_box().ok_or("nothing stamped")?;
+                for _ in 0..generations {
+                    sim.next_generation();
+                }
+                // Cells beyond where the pattern started, measured from its middle
+                let (cx, cy) = (start.x as f32 + start.w as f32 / 2.0, start.y as f32 + start.h as f32 / 2.0);
+                let gone: Vec<_> = sim.live.iter().filter(|p| !start.contains(**p)).collect();
+                let (sx, sy) = gone.iter().fold((0.0, 0.0), |(sx, sy), p| (sx + p.x() as f32 - cx, sy + p.y() as f32 - cy));
+                let went = (sx.signum() as i32, sy.signum() as i32);
+                if gone.is_empty() || went != target {
+                    return Err(format!("aimed {} ({}) but it went {}", patterns::heading_name(target), turned.name(), patterns::heading_name(went)));
+                }
+            }
+            Ok(())
+        })();
+        failures += report(&format!("aiming: {}", name), result);
+    }
+    let still = registry.get("Block").and_then(patterns::heading);
+    failures += report("aiming: still lifes have no heading", if still.is_none() { Ok(()) } else { Err(format!("{:?}", still)) });
+    failures
+}
+
+/// Stepping budget: with a step slowed by a sleep, a frame stops near its time budget and
+/// defers the rest instead of forcing every due step, and a backlog past the cap is forgiven
+fn check_step_budget() -> u32 {
+    let mut failures = 0;
+    let started = std::time::Instant::now();
+    let now = || started.elapsed().as_secs_f64();
+    let slow_step = std::time::Duration::from_millis(3);
+
+    let mut clock = SimClock::new(1000.0);
+    clock.advance(SimClock::ticks_in(0.1));
+    let due = clock.due();
+    let mut worst: f64 = 0.0;
+    let mut total = 0;
+    for _ in 0..5 {
+        let frame = now();
+        total += clock.run_due(1000, STEP_BUDGET_SECONDS, now, || std::thread::sleep(slow_step));
+        worst = worst.max(now() - frame);
+    }
+    let result = if worst > 2.0 * STEP_BUDGET_SECONDS {
+        Err(format!("a frame spent {:.1} ms stepping, budget {:.1} ms", worst * 1000.0, STEP_BUDGET_SECONDS * 1000.0))
+    } else if total == 0 || clock.due() != due - total {
+        Err(format!("{} of {} steps run, {} still due", total, due, clock.due()))
+    } else {
+        Ok(())
+    };
+    failures += report("step budget: slow steps are deferred", result);
+
+    let mut clock = SimClock::new(1000.0);
+    clock.advance(SimClock::ticks_in(0.1));
+    let steps = clock.run_due(8, STEP_BUDGET_SECONDS, now, || {});
+    let result = match (steps, clock.due()) {
+        (8, 8) => Ok(()),
+        (steps, left) => Err(format!("ran {} steps and left {} due, expected 8 and 8", steps, left)),
+    };
+    failures += report("step budget: backlog capped", result);
+    failures
+}
+
+/// Frame pacing: the cap and the battery saver pick the right rate, input and busy work lift
+/// the saver, longer frames step more generations, and the settings and console commands hold
+fn check_pacing() -> u32 {
+    let result = (|| {
+        let limit = |cap, saver, now, busy| Pacer::new(cap, saver).limit(now, busy).map(|l| (l.fps, l.saving));
+        if limit(0, false, 5.0, false).is_some() || limit(30, false, 5.0, false) != Some((30.0, false)) {
+            return Err(format!("uncapped gives {:?}, a cap of 30 {:?}", limit(0, false, 5.0, false), limit(30, false, 5.0, false)));
+        }
+        if limit(0, true, 5.0, false) != Some((SAVER_FPS, true)) || limit(0, true, 5.0, true).is_some() || limit(5, true, 5.0, false) != Some((5.0, true)) {
+            return Err("the saver should run at its own rate, yield to busy work and keep a lower cap".to_string());
+        }
+        let mut pacer = Pacer::new(60, true);
+        pacer.wake(5.0);
+        if pacer.limit(5.5, false) != Some(Limit { fps: 60.0, saving: false }) || !pacer.limit(5.0 + SAVER_GRACE_SECONDS, false).is_some_and(|l| l.saving) {
+            return Err("input should hold the full rate for the grace period, then let the saver back".to_string());
+        }
+        let scales = [Limit::step_scale(None), Limit::step_scale(pacer.limit(5.5, false)), Limit::step_scale(Some(Limit { fps: 10.0, saving: true })), Limit::step_scale(Some(Limit { fps: 240.0, saving: false }))];
+        if scales != [1.0, 1.0, 6.0, 1.0] {
+            return Err(format!("step scales {:?}, expected [1, 1, 6, 1]", scales));
+        }
+        let remaining = [Pacer::remaining(None, 1.0, 1.0), Pacer::remaining(Some(Limit { fps: 10.0, saving: true }), 1.0, 1.04), Pacer::remaining(Some(Limit { fps: 10.0, saving: true }), 1.0, 1.2)];
+        if remaining[0] != 0.0 || (remaining[1] - 0.06).abs() > 1e-9 || remaining[2] != 0.0 {
+            return Err(format!("time left of each frame {:?}, expected 0, 0.06 and 0", remaining));
+        }
+        if Pacer::new(30, true).limit(0.0, false).map(|l| l.label()) != Some("saver:10".to_string()) {
+            return Err("the saver's HUD label should read saver:10".to_string());
+        }
+        let settings = Settings::parse("fps_cap = 30\nbattery_saver = true");
+        if Settings::default().fps_cap != 0 || Settings::default().battery_saver || settings.fps_cap != 30 || !settings.battery_saver || Settings::parse(&settings.to_text()) != settings {
+            return Err("fps_cap and battery_saver don't read back".to_string());
+        }
+        if Settings::parse("fps_cap = 1000").fps_cap != 0 || Settings::parse("fps_cap = 2").fps_cap != 0 {
+            return Err("an fps_cap out of range should be ignored".to_string());
+        }
+        let commands = [console::parse("fps 30")?, console::parse("fps off")?, console::parse("saver on")?, console::parse("saver off")?];
+        if commands != [console::Command::Fps(30), console::Command::Fps(0), console::Command::Saver(true), console::Command::Saver(false)] {
+            return Err(format!("parsed {:?}", commands));
+        }
+        if console::parse("fps 1000").is_ok() || console::parse("saver maybe").is_ok() {
+            return Err("fps 1000 and saver maybe should be rejected".to_string());
+        }
+        Ok(())
+    })();
+    report("frame pacing: cap, battery saver, settings and console", result)
+}
+
+/// Follow camera: a glider is tracked across generations (also several at once), a collision
+/// counts as a merge, a dying object and a big skip stop the follow, and the easing settles
+/// without overshoot at any frame rate
+fn check_follow() -> u32 {
+    let mut failures = 0;
+    let result = (|| {
+        let mut sim = Simulation::new(40, 40);
+        sim.grid.topology = Topology::Torus;
+        let _ = sim.stamp(&GliderPattern, Transform::default(), 0, 10, 10);
+        let start = *sim.live.iter().next().ok_or("no glider")?;
+        let mut tracked = Tracked::at(&sim, start.x(), start.y()).ok_or("no object under a live cell")?;
+        let before = tracked.center(&sim.grid);
+        for _ in 0..8 {
+            sim.next_generation();
+            tracked.update(&sim).map_err(|lost| format!("{:?} at generation {}", lost, sim.generation))?;
+        }
+        let moved = tracked.center(&sim.grid) - before;
+        if moved.x.abs() != 2.0 || moved.y.abs() != 2.0 {
+            return Err(format!("glider center moved by {:?} in 8 generations", moved));
+        }
+        for _ in 0..50 {
+            for _ in 0..3 {
+                sim.next_generation();
+            }
+            tracked.update(&sim).map_err(|lost| format!("{:?} at generation {}", lost, sim.generation))?;
+        }
+        if tracked.cells != sim.live {
+            return Err(format!("tracking {} cells of a {}-cell glider", tracked.cells.len(), sim.live.len()));
+        }
+
+        // A block six cells ahead of the glider
+        let (dx, dy) = (moved.x.signum() as i32, moved.y.signum() as i32);
+        let ahead = tracked.center(&sim.grid).floor();
+        let (bx, by) = (ahead.x as i32 + 6 * dx, ahead.y as i32 + 6 * dy);
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            sim.toggle_cell(bx + x, by + y);
+        }
+        let glider = *tracked.cells.iter().next().ok_or("no glider")?;
+        let mut tracked = Tracked::at(&sim, glider.x(), glider.y()).ok_or("glider lost after placing the block")?;
+        for _ in 0..60 {
+            sim.next_generation();
+            match tracked.update(&sim) {
+                Ok(()) => {}
+                Err(Lost::Merged) => return Ok(()),
+                Err(lost) => return Err(format!("{:?} instead of a merge at generation {}", lost, sim.generation)),
+            }
+        }
+        Err("glider hit the block without the follow noticing".to_string())
+    })();
+    failures += report("follow: glider tracking and merge", result);
+
+    let mut sim = Simulation::new(20, 20);
+    sim.toggle_cell(5, 5);
+    let mut lone = Tracked::at(&sim, 5, 5);
+    sim.next_generation();
+    let died = lone.as_mut().map(|t| t.update(&sim));
+    sim.toggle_cell(5, 5);
+    sim.toggle_cell(5, 6);
+    sim.toggle_cell(6, 5);
+    sim.toggle_cell(6, 6);
+    let mut block = Tracked::at(&sim, 5, 5);
+    sim.generation += 40;
+    let skipped = block.as_mut().map(|t| t.update(&sim));
+    let result = match (died, skipped) {
+        (Some(Err(Lost::Died)), Some(Err(Lost::Skipped))) => Ok(()),
+        other => Err(format!("lone cell and long skip gave {:?}", other)),
+    };
+    failures += report("follow: death and skips stop it", result);
+
+    let ease = |dt: f32| {
+        let mut spring = Spring::default();
+        let mut path = Vec::new();
+        for _ in 0..(1.0 / dt).round() as u32 {
+            spring.ease(10.0, 0.5, dt);
+            path.push(spring.value);
+        }
+        path
+    };
+    let (fine, coarse) = (ease(1.0 / 120.0), ease(1.0 / 30.0));
+    let result = if fine.windows(2).any(|w| w[1] < w[0]) || fine.iter().any(|&v| v > 10.0) {
+        Err("easing overshot or went backwards".to_string())
+    } else if fine[fine.len() - 1] < 9.9 {
+        Err(format!("only reached {:.3} of 10 after twice the settle time", fine[fine.len() - 1]))
+    } else if (fine[59] - coarse[14]).abs() > 1e-3 {
+        Err(format!("half a second in: {:.4} at 120 fps, {:.4} at 30 fps", fine[59], coarse[14]))
+    } else {
+        Ok(())
+    };
+    failures += report("follow: critically damped easing", result);
+    failures
+}
+
+/// Memory use covers everything a run keeps, and going over the budget frees the diff, then
+/// the time-lapse, then the oldest recorded history, leaving the live set alone
+fn check_memory_budget(seed: u64) -> u32 {
+    let result = (|| {
+        let mut sim = Simulation::new(96, 96);
+        sim.live = random_soup(&sim.grid, 0.35, seed);
+        sim.mark_initial();
+        sim.set_age_tracking(true);
+        sim.track_diff = true;
+        let mut recorder = DeltaLog::new(&sim);
+        let mut gallery = Gallery::new(10);
+        for generation in 1..=40u64 {
+            sim.next_generation();
+            recorder.record(&sim);
+            if generation % 10 == 0 {
+                gallery.push(generation, Image::gen_image_color(THUMB_SIZE.0, THUMB_SIZE.1, Color::new(0.0, 0.0, 0.0, 1.0)));
+            }
+        }
+        let board = sim.live.clone();
+        let mut usage = sim.memory_usage();
+        (usage.timelapse, usage.deltas) = (gallery.bytes(), recorder.bytes());
+        let parts = [("live", usage.live), ("ages", usage.ages), ("diff", usage.diff), ("gen 0", usage.initial), ("time-lapse", usage.timelapse), ("deltas", usage.deltas)];
+        if let Some((name, _)) = parts.iter().find(|(_, bytes)| *bytes == 0) {
+            return Err(format!("{} isn't counted", name));
+        }
+
+        // Just over: the diff and the thumbnails go, the history stays
+        let budget = usage.total() - usage.timelapse / 2;
+        let (shed, after) = budget::enforce(budget, usage, &mut sim, &mut gallery, Some(&mut recorder));
+        if sim.diff.is_some() || !gallery.shots.is_empty() || shed.generations != 0 || recorder.deltas.len() != 40 || after.total() > budget {
+            return Err(format!("a small excess shed {:?}, leaving {} of {}", shed, after.total(), budget));
+        }
+
+        // Further over: the oldest deltas are folded into the reference, the newest kept
+        let budget = after.total() - after.deltas / 2;
+        let (shed, after) = budget::enforce(budget, after, &mut sim, &mut gallery, Some(&mut recorder));
+        if shed.generations == 0 || recorder.deltas.is_empty() || recorder.reconstruct(40).as_ref() != Some(&board) || after.total() > budget {
+            return Err(format!("a larger excess shed {:?}, leaving {} of {}", shed, after.total(), budget));
+        }
+
+        // Over with nothing left to drop: the caller has to pause, and the board is untouched
+        let budget = after.live / 2;
+        let (_, after) = budget::enforce(budget, after, &mut sim, &mut gallery, Some(&mut recorder));
+        if after.total() <= budget || sim.live != board || recorder.deltas.len() != 1 {
+            return Err("the live set was trimmed, or the budget met without it".to_string());
+        }
+        Ok(())
+    })();
+    report("memory budget frees the diff, time-lapse and old history before pausing", result)
+}
+
+/// Time-lapse thumbnails: a lone cell survives shrinking, thinning keeps both ends of the run
+/// within the caps, and a recorded generation can be rewound to
+fn check_timelapse(seed: u64) -> u32 {
+    let mut failures = 0;
+    let mut sim = Simulation::new(400, 200);
+    sim.live.insert(Position::new(123, 45));
+    let image = timelapse::rasterize(&sim, ColorTheme::Classic);
+    let cell: [u8; 4] = ColorTheme::Classic.colors().cell.into();
+    let lit = image.get_image_data().iter().filter(|&&p| p == cell).count();
+    let result = match (image.width(), image.height(), lit) {
+        (160, 80, 1..) => Ok(()),
+        (w, h, n) => Err(format!("{}x{} thumbnail with {} cell pixels", w, h, n)),
+    };
+    failures += report("time-lapse thumbnail", result);
+
+    let mut gallery = Gallery::new(10);
+    for i in 0..500 {
+        gallery.push(i * 10, Image::gen_image_color(THUMB_SIZE.0, THUMB_SIZE.1, Color::new(0.0, 0.0, 0.0, 1.0)));
+    }
+    let generations: Vec<u64> = gallery.shots.iter().map(|t| t.generation).collect();
+    let result = if gallery.shots.len() > THUMB_MAX_COUNT || gallery.bytes() > THUMB_MAX_BYTES {
+        Err(format!("{} thumbnails in {} bytes", gallery.shots.len(), gallery.bytes()))
+    } else if generations.first() != Some(&0) || generations.last() != Some(&4990) || !generations.is_sorted() {
+        Err(format!("kept generations {:?}", generations))
+    } else {
+        Ok(())
+    };
+    failures += report("time-lapse thinning", result);
+
+    let result = (|| {
+        let mut sim = Simulation::new(32, 32);
+        sim.live = random_soup(&sim.grid, 0.35, seed);
+        let mut log = DeltaLog::new(&sim);
+        let mut boards = vec![sim.live.clone()];
+        for _ in 0..10 {
+            sim.next_generation();
+            log.record(&sim);
+            boards.push(sim.live.clone());
+        }
+        let live = log.rewind(4).ok_or("generation 4 was recorded but could not be rebuilt")?;
+        if live != boards[4] {
+            return Err("rewound board differs from generation 4".to_string());
+        }
+        sim.restore(live, 4);
+        sim.next_generation();
+        if !log.record(&sim) || log.reconstruct(5).as_ref() != Some(&boards[5]) {
+            return Err("recording did not carry on after the rewind".to_string());
+        }
+        Ok(())
+    })();
+    failures += report("delta log rewind", result);
+    failures
+}
+
+/// A layer that records how often it was offered input
+struct Probe {
+    layer: Layer,
+    consumes: bool,
+    seen: u32,
+}
+
+impl Probe {
+    fn new(layer: Layer, consumes: bool) -> Self {
+        Self { layer, consumes, seen: 0 }
+    }
+}
+
+impl Overlay for Probe {
+    fn layer(&self) -> Layer {
+        self.layer
+    }
+
+    fn handle_input(&mut self, _input: &dyn Input) -> InputConsumed {
+        self.seen += 1;
+        if self.consumes { InputConsumed::Yes } else { InputConsumed::No }
+    }
+
+    fn draw(&self, _frame: &Frame) {}
+}
+
+/// Menu navigation: picks push screens onto the breadcrumb, Escape pops one level but never
+/// past the main menu, and the setup only becomes a run once the board and pattern are picked
+fn check_menu_stack() -> u32 {
+    let result = (|| {
+        let mut menus = MenuStack::new();
+        for screen in [MenuScreen::Resolution, MenuScreen::Board, MenuScreen::Pattern] {
+            menus.push(screen);
+        }
+        if menus.breadcrumb() != "Main > Window > Board > Patterns" {
+            return Err(format!("breadcrumb '{}'", menus.breadcrumb()));
+        }
+        menus.pop();
+        if menus.current() != MenuScreen::Board {
+            return Err(format!("Escape from the patterns went to {:?}", menus.current()));
+        }
+        for _ in 0..5 {
+            menus.pop();
+        }
+        if menus.current() != MenuScreen::Mode || menus.breadcrumb() != "Main" {
+            return Err(format!("popping past the main menu left '{}'", menus.breadcrumb()));
+        }
+
+        let board = BoardSize::fill_window(800, 600, 10);
+        let mut setup = Setup { mode: GameMode::Simulation, window: Some((800, 600)), board: Some(board), pattern: None };
+        if setup.config().is_some() {
+            return Err("a setup without a pattern made a run".to_string());
+        }
+        setup.pattern = Some(PatternChoice::Registered(0));
+        let config = setup.config().ok_or("a complete setup made no run")?;
+        match config.start {
+            Start::Choice(PatternChoice::Registered(0)) if config.window == Some((800, 600)) && config.grid == Some((80, 60)) => Ok(()),
+            _ => Err("the run doesn't match the choices made".to_string()),
+        }
+    })();
+    report("menu stack and setup", result)
+}
+
+/// Random palettes always pass the contrast check, palettes and the themes file survive a
+/// round trip, and the picker previews the highlighted row, reverts on Escape and saves random
+/// palettes, all without letting a key through to the board
+fn check_theme_picker(seed: u64) -> u32 {
+    let mut failures = 0;
+    let mut rng = Rng::new(seed);
+    let result = (0..500).map(|_| Palette::random(&mut rng)).find(|p| ColorTheme::Custom(*p).low_contrast().is_some())
+        .map_or(Ok(()), |p| Err(format!("{} has low contrast", p)));
+    failures += report("random palettes keep cell contrast", result);
+
+    let result = (|| {
+        let palette = Palette::parse("#0a0b0c #F0e1d2 #202020 #ff8000")?;
+        if palette.to_string() != "#0a0b0c #f0e1d2 #202020 #ff8000" {
+            return Err(format!("palette written back as {}", palette));
+        }
+        for bad in ["#0a0b0c #f0e1d2 #202020", "#0a0b0c #f0e1d2 #202020 #ff80", "0a0b0c #f0e1d2 #202020 #ff8000"] {
+            if Palette::parse(bad).is_ok() {
+                return Err(format!("'{}' parsed", bad));
+            }
+        }
+        let mut themes = CustomThemes::parse("# comment\nSunset = #0a0b0c #f0e1d2 #202020 #ff8000\nbroken = #123\n");
+        let name = themes.add(Palette::random(&mut rng));
+        if name != "Random 1" || themes.add(palette) != "Sunset" || themes.entries.len() != 2 {
+            return Err(format!("names after adding: {:?}", themes.entries.iter().map(|(n, _)| n).collect::<Vec<_>>()));
+        }
+        let reread = CustomThemes::parse(&themes.to_text());
+        if reread != themes { Err(format!("themes file changed on reload:\n{}", themes.to_text())) } else { Ok(()) }
+    })();
+    failures += report("custom themes file round trip", result);
+
+    let result = (|| {
+        let press = |key: KeyCode| Scripted { keys: vec![key], ..Scripted::default() };
+        let custom = vec![("Sunset".to_string(), Palette::parse("#0a0b0c #f0e1d2 #202020 #ff8000")?)];
+        let mut picker = ThemePicker::new(ColorTheme::Dark, custom, seed);
+        let mut board = Probe::new(Layer::Hud, false);
+        let mut step = |picker: &mut ThemePicker, key: KeyCode| {
+            OverlayStack::new(vec![&mut board, picker]).handle_input(&press(key));
+            picker.event.take()
+        };
+        step(&mut picker, KeyCode::Down);
+        if picker.preview() != ColorTheme::Pastel {
+            return Err(format!("Down from Dark previews {}", picker.preview().name()));
+        }
+        if step(&mut picker, KeyCode::Escape) != Some(ThemeEvent::Close(ColorTheme::Dark)) {
+            return Err("Escape didn't go back to Dark".to_string());
+        }
+        // Up from the first row wraps to the Randomize row, which rolls a palette to preview
+        let mut picker = ThemePicker::new(ColorTheme::Classic, Vec::new(), seed);
+        step(&mut picker, KeyCode::Up);
+        let ColorTheme::Custom(rolled) = picker.preview() else {
+            return Err(format!("the Randomize row previews {}", picker.preview().name()));
+        };
+        step(&mut picker, KeyCode::R);
+        let ColorTheme::Custom(rerolled) = picker.preview() else { return Err("R left the Randomize row".to_string()) };
+        if rolled == rerolled {
+            return Err("R rolled the same palette".to_string());
+        }
+        if step(&mut picker, KeyCode::S) != Some(ThemeEvent::Save(rerolled)) {
+            return Err("S didn't ask to save the random palette".to_string());
+        }
+        picker.saved(vec![("Random 1".to_string(), rerolled)]);
+        match step(&mut picker, KeyCode::Enter) {
+            Some(ThemeEvent::Close(ColorTheme::Custom(kept))) if kept == rerolled => {}
+            other => return Err(format!("Enter on the saved palette gave {:?}", other)),
+        }
+        if board.seen != 0 {
+            return Err(format!("the board under the picker was offered input {} times", board.seen));
+        }
+        Ok(())
+    })();
+    failures += report("theme picker previews, reverts and saves", result);
+    failures
+}
+
+/// Overlay input routing with scripted presses: the help panel swallows everything while open,
+/// and a consuming layer hides input from the layers under it whatever order they were given in
+fn check_overlay_routing() -> u32 {
+    let mut failures = 0;
+    let press = |keys: &[KeyCode], buttons: &[MouseButton]| Scripted { keys: keys.to_vec(), buttons: buttons.to_vec(), mouse: (5.0, 5.0) };
+    let (mut help, mut board) = (Help::default(), Probe::new(Layer::Hud, false));
+    let frames = [
+        (press(&[KeyCode::Space], &[]), InputConsumed::No, false, 1),
+        (press(&[KeyCode::F1], &[]), InputConsumed::Yes, true, 1),
+        (press(&[KeyCode::Space], &[MouseButton::Left]), InputConsumed::Yes, true, 1),
+        (press(&[KeyCode::Escape], &[]), InputConsumed::Yes, false, 1),
+        (press(&[KeyCode::C], &[MouseButton::Left]), InputConsumed::No, false, 2),
+    ];
+    let mut result = Ok(());
+    for (i, (input, consumed, open, seen)) in frames.iter().enumerate() {
+        let got = OverlayStack::new(vec![&mut board, &mut help]).handle_input(input);
+        if got != *consumed || help.open != *open || board.seen != *seen {
+            result = Err(format!(
+                "frame {}: {:?} with help open {} and the layer below offered {} times, expected {:?}, {}, {}",
+                i, got, help.open, board.seen, consumed, open, seen,
+            ));
+            break;
+        }
+    }
+    failures += report("overlay routing: help panel is modal", result);
+
+    let (mut low, mut first, mut second) = (Probe::new(Layer::Hud, false), Probe::new(Layer::Notices, true), Probe::new(Layer::Notices, false));
+    let got = OverlayStack::new(vec![&mut low, &mut first, &mut second]).handle_input(&Scripted::default());
+    let result = match (got, low.seen, first.seen, second.seen) {
+        (InputConsumed::Yes, 0, 1, 0) => Ok(()),
+        other => Err(format!("consumed/offered to hud, first, second: {:?}", other)),
+    };
+    failures += report("overlay routing: top-down, stops at the first consumer", result);
+    failures
+}
+
+/// RLE files in Golly's layout keep their name, author, comments and offset, and write back out
+/// byte for byte; boards exported from the app say where they came from
+fn check_rle_metadata() -> u32 {
+    const FIXTURES: [(&str, &str); 3] = selftest::RLE_FIXTURES;
+    let result = (|| {
+        selftest::rle_round_trips(0)?;
+        let gun = pattern_io::parse_rle(FIXTURES[0].1).map_err(|e| e.to_string())?;
+        if gun.name != "Gosper glider gun" || gun.meta.author.as_deref() != Some("Bill Gosper") || gun.meta.comments.len() != 3 || gun.cells.len() != 36 {
+            return Err(format!("the gun read as {:?} by {:?} with {} comments and {} cells", gun.name, gun.meta.author, gun.meta.comments.len(), gun.cells.len()));
+        }
+        let acorn = pattern_io::parse_rle(FIXTURES[1].1).map_err(|e| e.to_string())?;
+        if acorn.meta.offset != Some((-3, -1)) || acorn.meta.comments.get(1).map(String::as_str) != Some("") {
+            return Err(format!("the acorn's offset {:?} or blank comment line was lost", acorn.meta.offset));
+        }
+        if pattern_io::parse_rle("#r B36/S23\nx = 1, y = 1\no!").map_err(|e| e.to_string())?.rule.as_deref() != Some("B36/S23") {
+            return Err("a '#r' line should still set the rule".to_string());
+        }
+        if pattern_io::parse_rle("#R 3\nx = 1, y = 1\no!").is_ok() {
+            return Err("an '#R' line without both coordinates should be rejected".to_string());
+        }
+
+        let mut sim = Simulation::new(32, 32);
+        sim.seed = 11;
+        sim.random_fill(0.3);
+        for _ in 0..5 {
+            sim.next_generation();
+        }
+        let exported = sim.to_rle();
+        let comment = format!("#C Generation 5, seed 11, rule {}", sim.grid.rule);
+        if exported.lines().next() != Some(comment.as_str()) || !exported.lines().nth(1).is_some_and(|l| l.starts_with("x = ")) {
+            return Err(format!("a drawn board should export with '{}' before the header, got:\n{}", comment, exported));
+        }
+        let back = pattern_io::parse_rle(&exported).map_err(|e| e.to_string())?;
+        let (left, top) = sim.live.iter().fold((i32::MAX, i32::MAX), |(l, t), p| (l.min(p.x()), t.min(p.y())));
+        let cells: HashSet<Position> = back.cells.iter().map(|&(x, y)| Position::new(x + left, y + top)).collect();
+        if cells != sim.live {
+            return Err("the exported board should read back to the same cells".to_string());
+        }
+
+        let mut imported = Simulation::new(64, 64);
+        imported.place(&gun).map_err(|e| e.to_string())?;
+        let again = pattern_io::parse_rle(&imported.to_rle()).map_err(|e| e.to_string())?;
+        if again.name != gun.name || again.meta != gun.meta {
+            return Err(format!("an imported pattern should export with its own header lines, got {:?} {:?}", again.name, again.meta));
+        }
+        if console::parse("export-rle out.rle")? != console::Command::ExportRle(Some("out.rle".to_string())) || console::parse("export-rle")? != console::Command::ExportRle(None) {
+            return Err("'export-rle' should take an optional file".to_string());
+        }
+        Ok(())
+    })();
+    report("RLE metadata round trip", result)
+}
+
+/// Hotbar slots find their pattern by name, arm it in the transform it was assigned with, and
+/// survive a trip through the settings file
+fn check_hotbar() -> u32 {
+    let result = (|| {
+        let registry = default_registry();
+        let keys: String = (0..hotbar::SLOTS).map(Hotbar::key_label).collect();
+        if keys != "1234567890" || (0..hotbar::SLOTS).any(|i| Hotbar::slot_of(Hotbar::key_label(i)) != Some(i)) {
+            return Err(format!("slots should sit on keys 1 to 0, got {}", keys));
+        }
+        if Transform::all().into_iter().any(|t| Transform::from_name(&t.name()) != Some(t)) {
+            return Err("every transform should be found by its name".to_string());
+        }
+
+        let rot90 = Transform::IDENTITY.rotate_cw();
+        let mut bar = Hotbar::default();
+        bar.assign(2, "Glider", rot90);
+        bar.assign(9, "Gosper Gun", Transform::IDENTITY);
+        let stamp = bar.stamp(2, &registry)?;
+        if registry.get_index(stamp.pattern).map(|p| p.name()) != Some("Glider") || stamp.transform != rot90 {
+            return Err(format!("slot 3 armed {} instead of the glider turned a quarter", stamp.name(&registry)));
+        }
+        if bar.stamp(0, &registry).is_ok() {
+            return Err("an empty slot shouldn't arm anything".to_string());
+        }
+        bar.assign(4, "Not a pattern", Transform::IDENTITY);
+        if !bar.stamp(4, &registry).is_err_and(|e| e.contains("isn't loaded")) {
+            return Err("a slot whose pattern isn't loaded should say so".to_string());
+        }
+
+        let settings = Settings { hotbar: bar.clone(), ..Settings::default() };
+        let text = settings.to_text();
+        if !text.contains("hotbar_3 = rot90 Glider\n") || !text.contains("hotbar_0 = upright Gosper Gun\n") || text.contains("hotbar_1") {
+            return Err(format!("filled slots should be written as hotbar_<key> lines:\n{}", text));
+        }
+        if Settings::parse(&text) != settings {
+            return Err("the hotbar changed on the way through the settings file".to_string());
+        }
+        let odd = Settings::parse("hotbar_12 = upright Glider\nhotbar_x = upright Glider\nhotbar_4 = sideways Glider\nhotbar_5 = rot180");
+        if odd.hotbar != Hotbar::default() {
+            return Err(format!("malformed hotbar lines should be ignored, got {:?}", odd.hotbar));
+        }
+
+        // A glider has no symmetry, so its 8 transforms give 8 different shapes
+        let glider = registry.get("Glider").ok_or("no glider")?;
+        let shapes: HashSet<Vec<(i32, i32)>> = Transform::all().into_iter()
+            .filter_map(|t| patterns::shape(glider, t).map(|mut cells| { cells.sort(); cells }))
+            .collect();
+        if shapes.len() != 8 || shapes.iter().any(|c| c.len() != 5 || c.iter().any(|&(x, y)| !(0..3).contains(&x) || !(0..3).contains(&y))) {
+            return Err(format!("the glider's transforms gave {} shapes", shapes.len()));
+        }
+        let drawn = |slot: &Option<Slot>| {
+            let image = hotbar::thumbnail(slot.as_ref().unwrap(), &registry, 28, GREEN);
+            image.get_image_data().iter().filter(|p| p[3] > 0).count()
+        };
+        let (glider_px, missing_px) = (drawn(&bar.slots[2]), drawn(&bar.slots[4]));
+        if glider_px == 0 || missing_px != 0 {
+            return Err(format!("thumbnails drew {} pixels for the glider and {} for a missing pattern", glider_px, missing_px));
+        }
+        Ok(())
+    })();
+    report("hotbar slots arm, save and draw their patterns", result)
+}
+
+/// Pattern menu figures: measured sizes, interest derived for files that don't declare it,
+/// and the built-ins ordered by interest
+fn check_pattern_info() -> u32 {
+    let mut failures = 0;
+    let registry = default_registry();
+    let measured = |name: &str| registry.get(name).and_then(PatternInfo::measure).map(|i| (i.cells, i.width, i.height));
+    let result = match (measured("Glider"), measured("Gosper Gun"), measured("Random")) {
+        (Some((5, 3, 3)), Some((36, 36, 9)), None) => Ok(()),
+        other => Err(format!("glider, gun and random measured as {:?}", other)),
+    };
+    failures += report("pattern info: cell counts and sizes", result);
+
+    let file = |cells: &[(i32, i32)], w, h| LoadedPattern { name: "file".to_string(), rule: None, cells: cells.to_vec(), width: w, height: h, meta: Metadata::default() };
+    let cases = [
+        ("glider", file(&[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)], 3, 3), Some(Interest::Spaceship { period: 4, shift: 1 })),
+        ("blinker", file(&[(0, 0), (1, 0), (2, 0)], 3, 1), Some(Interest::Oscillator { period: 2 })),
+        ("block", file(&[(0, 0), (1, 0), (0, 1), (1, 1)], 2, 2), Some(Interest::StillLife)),
+        ("R-pentomino", file(&[(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)], 3, 3), None),
+    ];
+    for (name, pattern, expected) in cases {
+        let found = PatternInfo::measure(&pattern).and_then(|i| i.interest);
+        let result = if found == expected { Ok(()) } else { Err(format!("expected {:?}, got {:?}", expected, found)) };
+        failures += report(&format!("pattern info: derived interest ({})", name), result);
+    }
+
+    let rows: Vec<(&str, &str, Option<PatternInfo>)> = registry.iter().map(|p| (p.name(), p.category(), PatternInfo::measure(p))).collect();
+    let mut order: Vec<usize> = (0..rows.len()).collect();
+    order.sort_by(|&a, &b| {
+        let row = |i: usize| (rows[i].0, rows[i].1, rows[i].2.as_ref());
+        PatternSort::Interest.compare(row(a), row(b))
+    });
+    let names: Vec<&str> = order.iter().map(|&i| rows[i].0).collect();
+    let expected = ["Block", "Beacon", "Blinker", "Pentadecathlon", "Glider", "Diehard", "R-pentomino", "Acorn", "Gosper Gun", "Random"];
+    let result = if names == expected { Ok(()) } else { Err(format!("got {:?}", names)) };
+    failures += report("pattern menu sorted by interest", result);
+    failures
+}
+
+/// Menu previews run oscillators and spaceships on a grid of their own, say which way a ship
+/// heads in the orientation shown, bring a ship back to its start once it has crossed the box,
+/// and hold still for patterns that are too big or never repeat
+fn check_preview() -> u32 {
+    let result = (|| {
+        let registry = default_registry();
+        let shape = |name: &str, transform: Transform| {
+            registry.get(name).and_then(|p| patterns::shape(p, transform)).ok_or(format!("no shape for {}", name))
+        };
+        let snapshot = |p: &Preview| {
+            let (w, h) = p.size();
+            (0..h).flat_map(|y| (0..w).map(move |x| (x, y))).filter(|&(x, y)| p.alive(x, y)).collect::<Vec<_>>()
+        };
+
+        let mut blinker = Preview::new(&shape("Blinker", Transform::IDENTITY)?);
+        let start = snapshot(&blinker);
+        blinker.step();
+        let middle = snapshot(&blinker);
+        blinker.step();
+        if blinker.label != "oscillator p2" || !blinker.animated() || middle == start || snapshot(&blinker) != start {
+            return Err(format!("the blinker previewed as {:?}", blinker.label));
+        }
+
+        let mut glider = Preview::new(&shape("Glider", Transform::IDENTITY)?);
+        if glider.heading != Some((1, 1)) || glider.label != "spaceship c/4 heading down-right" {
+            return Err(format!("the glider previewed as {:?} with heading {:?}", glider.label, glider.heading));
+        }
+        let start = snapshot(&glider);
+        for _ in 0..4 {
+            glider.step();
+        }
+        if snapshot(&glider) != start.iter().map(|&(x, y)| (x + 1, y + 1)).collect::<Vec<_>>() {
+            return Err("the glider didn't move one cell down and right in 4 generations".to_string());
+        }
+        let mut steps = 4;
+        while glider.generation != 0 && steps < 1000 {
+            glider.step();
+            steps += 1;
+        }
+        let (w, h) = glider.size();
+        if snapshot(&glider) != start || steps != 4 * PREVIEW_TRAVEL + 1 || w < PREVIEW_TRAVEL || h < PREVIEW_TRAVEL {
+            return Err(format!("the glider started over after {} steps on a {}x{} grid", steps, w, h));
+        }
+        let flipped = Preview::new(&shape("Glider", Transform::IDENTITY.flip())?);
+        if flipped.heading != Some((-1, 1)) || !flipped.label.ends_with("heading down-left") {
+            return Err(format!("the flipped glider previewed as {:?}", flipped.label));
+        }
+
+        let block = Preview::new(&shape("Block", Transform::IDENTITY)?);
+        let gun = Preview::new(&shape("Gosper Gun", Transform::IDENTITY)?);
+        let wide: Vec<(i32, i32)> = (0..PREVIEW_MAX_SIDE + 10).map(|x| (x, 0)).collect();
+        let wide = Preview::new(&wide);
+        for (name, p, label) in [("block", &block, "still life"), ("gun", &gun, "no period within"), ("wide line", &wide, "too large")] {
+            if p.animated() || !p.label.starts_with(label) {
+                return Err(format!("the {} previewed as {:?}, animated: {}", name, p.label, p.animated()));
+            }
+        }
+        if gun.size() != (36, 9) || !gun.alive(0, 4) || gun.alive(0, 0) {
+            return Err(format!("the gun's still picture is {:?}", gun.size()));
+        }
+        Ok(())
+    })();
+    report("pattern menu previews animate, loop and hold still", result)
+}
+
+/// A big pattern file loads on a worker, reporting its progress, and comes back whole; its
+/// still preview is downsampled to at most `PREVIEW_RASTER` cells across
+fn check_loading() -> u32 {
+    let path = std::env::temp_dir().join(format!("conways-game-of-life-large-{}.rle", std::process::id()));
+    let result = (|| {
+        let cells: Vec<(i32, i32)> = (0..640).step_by(2).flat_map(|y| (0..640).step_by(2).map(move |x| (x, y))).collect();
+        let text = pattern_io::write_rle("Large", "B3/S23", &cells);
+        if text.len() <= LOAD_CHUNK {
+            return Err(format!("the test file is only {} bytes, one chunk", text.len()));
+        }
+        error::write(&path, &text).map_err(|e| e.to_string())?;
+        let load = Load::start(&path);
+        if let Stage::Reading(fraction) = load.stage()
+            && !(0.0..=1.0).contains(&fraction)
+        {
+            return Err(format!("progress read {}", fraction));
+        }
+        let pattern = load.wait().map_err(|e| e.to_string())?;
+        if pattern.cells.len() != cells.len() || (pattern.width, pattern.height) != (639, 639) {
+            return Err(format!("loaded {} cells in {}x{}", pattern.cells.len(), pattern.width, pattern.height));
+        }
+        let preview = Preview::new(&pattern.cells);
+        let (w, h) = preview.size();
+        if preview.animated() || preview.scale < 2 || w.max(h) > PREVIEW_RASTER || !preview.alive(0, 0) {
+            return Err(format!("the preview is {}x{} at 1:{}", w, h, preview.scale));
+        }
+        let missing = std::env::temp_dir().join("conways-game-of-life-no-such-pattern.rle");
+        let text_file = path.with_extension("txt");
+        for bad in [&missing, &text_file] {
+            if Load::start(bad).wait().is_ok() {
+                return Err(format!("{} loaded", bad.display()));
+            }
+        }
+        Ok(())
+    })();
+    let _ = std::fs::remove_file(&path);
+    report("large pattern files load in the background with a downsampled preview", result)
+}
+
+/// The cached board is redrawn only when its fingerprint changes, so any edit or step must
+/// change it, whatever order the cells were added in
+fn check_board_fingerprint(seed: u64) -> u32 {
+    let result = (|| {
+        let grid = Grid::new(48, 32);
+        let live = random_soup(&grid, 0.3, seed);
+        let mut reordered: Vec<Position> = live.iter().copied().collect();
+        reordered.reverse();
+        let print = board_hash(&live);
+        if board_hash(&reordered.into_iter().collect()) != print {
+            return Err("the same cells gave two fingerprints".to_string());
+        }
+        let mut seen = HashSet::new();
+        for (x, y) in (0..grid.height).flat_map(|y| (0..grid.width).map(move |x| (x, y))) {
+            let mut edited = live.clone();
+            if !edited.remove(&Position::new(x, y)) { edited.insert(Position::new(x, y)); }
+            let edited_print = board_hash(&edited);
+            if edited_print == print || !seen.insert(edited_print) {
+                return Err(format!("toggling ({}, {}) left the fingerprint matching another board", x, y));
+            }
+        }
+        if board_hash(&grid.next_generation(&live)) == print {
+            return Err("a step kept the fingerprint".to_string());
+        }
+        Ok(())
+    })();
+    report("cached board fingerprint tracks every edit", result)
+}
+
+/// The hash is what runs are compared by across machines, so it is pinned to a known value
+/// for a fixed board, and the stepping paths that should agree give the same hash stream:
+/// the engine, the background worker in chunks and the dense reference stepper
+fn check_hash_stream(seed: u64) -> u32 {
+    let mut failures = 0;
+    let (_, glider) = from_ascii(".#.../..#../###../...../.....", Topology::Bounded);
+    let result = match (board_hash(&glider), board_hash(&HashSet::new())) {
+        (0x7bb3_6c1f_62c1_0df0, 0) => Ok(()),
+        (a, b) => Err(format!("a glider hashed to {:016x} and an empty board to {:016x}", a, b)),
+    };
+    failures += report("board hash of a fixed glider", result);
+
+    const EVERY: u32 = 25;
+    let mut stepper = Stepper::spawn();
+    for topology in [Topology::Bounded, Topology::Torus] {
+        let mut sim = Simulation::new(64, 48);
+        sim.grid.topology = topology;
+        sim.live = random_soup(&sim.grid, 0.35, seed);
+        let mut dense = DenseBoard::from_cells(&sim.grid, &sim.live);
+        let mut worker = sim.live.clone();
+        let result = (|| {
+            for chunk in 1..=VERIFY_GENERATIONS / EVERY as u64 {
+                for _ in 0..EVERY {
+                    sim.next_generation();
+                    dense = dense.step();
+                }
+                let expected = board_hash(&sim.live);
+                let generation = chunk * EVERY as u64;
+                if board_hash(&dense.to_cells()) != expected {
+                    return Err(format!("the reference stepper's hash differs at generation {}", generation));
+                }
+                if let Some(stepper) = stepper.as_mut() {
+                    stepper.submit(&sim.grid, worker, EVERY);
+                    worker = stepper.wait().ok_or("worker stopped")?.live;
+                    if board_hash(&worker) != expected {
+                        return Err(format!("the background worker's hash differs at generation {}", generation));
+                    }
+                }
+            }
+            Ok(())
+        })();
+        failures += report(&format!("hash stream matches across steppers ({})", topology), result);
+    }
+    failures
+}
+
+/// Rasterize a 6x5 board at 4 px per cell into an image and count pixels by color
+fn check_renderer() -> u32 {
+    let mut sim = Simulation::new(6, 5);
+    sim.live = [(2, 2), (3, 2), (2, 3)].into_iter().map(|(x, y)| Position::new(x, y)).collect();
+    let mut view = BoardView::new(4);
+    view.grid_style.mode = GridMode::Off;
+    let colors = view.theme.colors();
+    // Cell, border, grid and anything else (blended), in that order
+    let count = |sim: &Simulation, view: &BoardView, t: f32| {
+        let mut image = Image::gen_image_color(24, 20, colors.background);
+        draw_board(&mut image, sim, view, t);
+        let key = |c: Color| -> [u8; 4] { c.into() };
+        let mut counts = [0usize; 4];
+        for &pixel in image.get_image_data() {
+            let slot = [colors.cell, colors.border, colors.grid].iter().position(|&c| key(c) == pixel);
+            match slot {
+                Some(i) => counts[i] += 1,
+                None if pixel != key(colors.background) => counts[3] += 1,
+                None => {}
+            }
+        }
+        counts
+    };
+    let expect = |name: &str, got: [usize; 4], want: [usize; 4]| {
+        let result = if got == want { Ok(()) } else { Err(format!("cell/border/grid/blended pixels {:?}, expected {:?}", got, want)) };
+        report(&format!("renderer: {}", name), result)
+    };
+
+    // Three whole cells inside the border, whose 1.5 px band covers one pixel on the top and
+    // left edges and two on the bottom and right (pixel centers on a band's leading edge count)
+    let mut failures = expect("cells", count(&sim, &view, 1.0), [48, 123, 0, 0]);
+
+    // Minor lines every 4 px cut each cell down to 3x3; the border covers lines on the edge
+    view.grid_style.mode = GridMode::Minor;
+    failures += expect("grid lines", count(&sim, &view, 1.0), [27, 123, 149, 0]);
+
+    // Halfway through a step a newborn and a dying cell are 2x2 and half transparent
+    view.grid_style.mode = GridMode::Off;
+    sim.generation = 1;
+    sim.diff = Some(GenerationDiff {
+        generation: 1,
+        born: HashSet::from([Position::new(3, 2)]),
+        died: vec![Position::new(4, 3)],
+    });
+    failures += expect("smooth step", count(&sim, &view, 0.5), [32, 123, 0, 8]);
+    failures
+}
+
+/// Theme backdrops: stretched over the board under the cells at their opacity, left out of
+/// figures that don't ask for one, missing files falling back to the flat background, and
+/// the per-theme paths surviving the settings file
+fn check_backdrop() -> u32 {
+    let mut failures = 0;
+    // A 2x1 image, red on the left and blue on the right, at half strength over Classic's black
+    let mut image = Image::gen_image_color(2, 1, Color::new(1.0, 0.0, 0.0, 1.0));
+    image.set_pixel(1, 0, Color::new(0.0, 0.0, 1.0, 1.0));
+    let backdrop = Rc::new(Backdrop::new(image, 0.5));
+    let mut sim = Simulation::new(4, 2);
+    sim.live.insert(Position::new(3, 1));
+    let draw = |backdrop: Option<Rc<Backdrop>>| {
+        let mut view = BoardView::new(4);
+        view.grid_style.mode = GridMode::Off;
+        view.backdrop = backdrop;
+        let mut image = Image::gen_image_color(16, 8, BLACK);
+        draw_board(&mut image, &sim, &view, 1.0);
+        [(4, 4), (11, 4), (13, 5)].map(|(x, y)| -> [u8; 4] { image.get_pixel(x, y).into() })
+    };
+    let got = draw(Some(backdrop));
+    let want = [[128, 0, 0, 255], [0, 0, 128, 255], GREEN.into()];
+    let close = |a: [u8; 4], b: [u8; 4]| a.iter().zip(b).all(|(x, y)| x.abs_diff(y) <= 1);
+    let result = match got.iter().zip(want).all(|(&g, w)| close(g, w)) {
+        true => Ok(()),
+        false => Err(format!("left, right and cell pixels {:?}, expected {:?}", got, want)),
+    };
+    failures += report("backdrop drawn under the cells", result);
+    let plain: [u8; 4] = BLACK.into();
+    let result = match draw(None) {
+        [a, b, _] if a == plain && b == plain => Ok(()),
+        got => Err(format!("background pixels {:?}", &got[..2])),
+    };
+    failures += report("figures without a backdrop keep the flat background", result);
+    failures += report("backdrops load once per theme and fall back when missing", (|| {
+        let missing = std::env::temp_dir().join("conways-game-of-life-no-such-backdrop.png");
+        let mut backdrops = Backdrops::new(vec![(ColorTheme::Neon, missing.clone())], 0.5);
+        if backdrops.get(ColorTheme::Neon).is_some() || backdrops.get(ColorTheme::Classic).is_some() {
+            return Err("a theme without a readable image got a backdrop".to_string());
+        }
+        backdrops.get(ColorTheme::Neon);
+        match backdrops.take_errors().as_slice() {
+            [GolError::Io { path, .. }] if *path == missing => {}
+            errors => return Err(format!("the missing image was reported as {:?}", errors)),
+        }
+        let text = "backdrop_neon = /tmp/logo.png\nbackdrop_Dark = gradient.jpg\nbackdrop_nope = x.png\nbackdrop_opacity = 0.3";
+        let settings = Settings::parse(text);
+        let want = vec![(ColorTheme::Neon, PathBuf::from("/tmp/logo.png")), (ColorTheme::Dark, PathBuf::from("gradient.jpg"))];
+        if settings.backdrops != want || settings.backdrop_opacity != 0.3 {
+            return Err(format!("parsed {:?} at {}", settings.backdrops, settings.backdrop_opacity));
+        }
+        if Settings::parse(&settings.to_text()) != settings {
+            return Err("backdrops changed through the settings file".to_string());
+        }
+        Ok(())
+    })());
+    failures
+}
+
+/// Reference figures checked in under `golden/`: file, contents, pattern, generations and how
+/// it is drawn. Regenerate one with `--render golden/<file> --pattern <name> ...`.
+const GOLDEN_FIGURES: [(&str, &[u8], &str, u64, FigureOptions); 3] = [
+    ("glider.png", include_bytes!("../golden/glider.png"), "Glider", 0,
+        FigureOptions { theme: ColorTheme::Classic, cell: 8, grid: true, margin: 1, backdrop: None }),
+    ("beacon-neon.png", include_bytes!("../golden/beacon-neon.png"), "Beacon", 0,
+        FigureOptions { theme: ColorTheme::Neon, cell: 6, grid: false, margin: 2, backdrop: None }),
+    ("r-pentomino-pastel.png", include_bytes!("../golden/r-pentomino-pastel.png"), "R-pentomino", 12,
+        FigureOptions { theme: ColorTheme::Pastel, cell: 5, grid: true, margin: 1, backdrop: None }),
+];
+
+/// Channels may differ by this much, and this fraction of pixels by more, before a figure fails
+const GOLDEN_CHANNEL_TOLERANCE: u8 = 2;
+const GOLDEN_PIXEL_TOLERANCE: f32 = 0.005;
+
+/// PNG figures: small boards rendered through the figure API match the checked-in references
+fn check_golden_figures() -> u32 {
+    let mut failures = 0;
+    for (file, reference, pattern, generations, options) in GOLDEN_FIGURES {
+        let result = (|| {
+            let mut sim = Simulation::new(64, 64);
+            let registry = default_registry();
+            let pattern = registry.get(pattern).ok_or("pattern missing from the registry")?;
+            sim.apply_pattern(pattern, 32, 32).map_err(|e| e.to_string())?;
+            for _ in 0..generations {
+                sim.next_generation();
+            }
+            let got = decode_png(&render_board_to_png(&sim.live, &options))?;
+            let want = decode_png(reference)?;
+            if (got.width, got.height) != (want.width, want.height) {
+                return Err(format!("{}x{} image, reference is {}x{}", got.width, got.height, want.width, want.height));
+            }
+            let off = got.get_image_data().iter().zip(want.get_image_data())
+                .filter(|(a, b)| a.iter().zip(b.iter()).any(|(x, y)| x.abs_diff(*y) > GOLDEN_CHANNEL_TOLERANCE))
+                .count();
+            if off as f32 > GOLDEN_PIXEL_TOLERANCE * got.get_image_data().len() as f32 {
+                return Err(format!("{} of {} pixels differ from the reference", off, got.get_image_data().len()));
+            }
+            Ok(())
+        })();
+        failures += report(&format!("golden figure {}", file), result);
+    }
+    failures
+}
+
+/// Centered y-up coordinates on odd and even grids: known anchor points and a full round trip
+fn check_coordinates() -> u32 {
+    type Point = (i32, i32);
+    // Grid size, then internal cells with their expected centered coordinates
+    let cases: [(Point, [(Point, Point); 3]); 4] = [
+        ((5, 5), [((2, 2), (0, 0)), ((0, 0), (-2, 2)), ((4, 4), (2, -2))]),
+        ((4, 4), [((2, 2), (0, 0)), ((0, 0), (-2, 2)), ((3, 3), (1, -1))]),
+        ((5, 4), [((2, 2), (0, 0)), ((0, 3), (-2, -1)), ((4, 0), (2, 2))]),
+        ((1, 1), [((0, 0), (0, 0)), ((0, 0), (0, 0)), ((0, 0), (0, 0))]),
+    ];
+    let centered = CoordSystem::Centered;
+    let mut failures = 0;
+    for ((w, h), points) in cases {
+        let grid = Grid::new(w, h);
+        let mut result = Ok(());
+        for ((x, y), want) in points {
+            if centered.to_display(&grid, x, y) != want {
+                result = Err(format!("({}, {}) should display as {:?}", x, y, want));
+            }
+        }
+        for (x, y) in (0..h).flat_map(|y| (0..w).map(move |x| (x, y))) {
+            let (dx, dy) = centered.to_display(&grid, x, y);
+            if centered.from_display(&grid, dx, dy) != (x, y) {
+                result = Err(format!("({}, {}) does not round-trip", x, y));
+            }
+        }
+        failures += report(&format!("centered coordinates on {}x{}", w, h), result);
+    }
+    failures
+}
+
+/// Ruler labels sit on major grid lines, never overlap, and are no sparser than they must be
+fn check_ruler() -> u32 {
+    let result = (|| {
+        for major in [5, 10] {
+            for cell in 1..=32 {
+                for extent in [8.0, 14.0, 33.0] {
+                    let every = Ruler::spacing(cell, major, extent);
+                    let fits = |n: i32| (n * cell) as f32 >= extent + RULER_GAP;
+                    if every % major != 0 || !fits(every) || (every > major && fits(every / 2)) {
+                        return Err(format!("{} px labels on {} px cells, major every {}: every {} cells", extent, cell, major, every));
+                    }
+                }
+            }
+        }
+        Ok(())
+    })();
+    report("ruler label spacing", result)
+}
+
+/// Largest board (in cells) the fuzzer also runs through the dense reference
+const FUZZ_REFERENCE_CELLS: i32 = 4096;
+
+/// Parameters of one fuzz case, all derived from its seed
+#[derive(Debug)]
+struct FuzzCase {
+    seed: u64,
+    width: i32,
+    height: i32,
+    topology: Topology,
+    density: f32,
+    generations: u64,
+}
+
+impl FuzzCase {
+    fn new(seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
+        // Degenerate 1xN and Nx1 grids are the most likely to hide edge bugs, so favour them
+        let side = |rng: &mut Rng| if rng.next_f32() < 0.2 { 1 } else { rng.gen_range(1, 49) };
+        let width = side(&mut rng);
+        let height = side(&mut rng);
+        Self {
+            seed,
+            width,
+            height,
+            topology: Topology::ALL[rng.gen_range(0, Topology::ALL.len() as i32) as usize],
+            density: 0.1 + rng.next_f32() * 0.5,
+            generations: rng.gen_range(1, 201) as u64,
+        }
+    }
+
+    /// Run the case, describing the first broken invariant
+    fn check(&self) -> Result<(), String> {
+        let mut grid = Grid::new(self.width, self.height);
+        grid.topology = self.topology;
+        let mut live = random_soup(&grid, self.density, self.seed);
+        let mut reference = (self.width * self.height <= FUZZ_REFERENCE_CELLS).then(|| DenseBoard::from_cells(&grid, &live));
+        for generation in 1..=self.generations {
+            live = grid.next_generation(&live);
+            if let Some(p) = live.iter().find(|p| !grid.in_bounds(p.x(), p.y())) {
+                return Err(format!("generation {}: cell ({}, {}) outside the grid", generation, p.x(), p.y()));
+            }
+            if live.len() > (self.width * self.height) as usize {
+                return Err(format!("generation {}: population {} exceeds the grid area", generation, live.len()));
+            }
+            if let Some(board) = reference.as_mut() {
+                *board = board.step();
+                if board.to_cells() != live {
+                    return Err(format!("generation {}: engine differs from the reference", generation));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Try random boards until the iteration count or time limit runs out, stopping at the first failure
+fn run_fuzz(opts: &Options) -> Result<(), String> {
+    let start = std::time::Instant::now();
+    let mut tried = 0u64;
+    loop {
+        let done = match opts.seconds {
+            Some(limit) => start.elapsed().as_secs_f64() >= limit,
+            None => tried >= opts.iterations,
+        };
+        if done { break; }
+        let case = FuzzCase::new(opts.seed.wrapping_add(tried));
+        if let Err(violation) = case.check() {
+            println!("FAIL {}", violation);
+            println!("     {:?}", case);
+            println!("     reproduce with: --fuzz --seed {} --iterations 1", case.seed);
+            return Err("invariant violated".to_string());
+        }
+        tried += 1;
+    }
+    println!("ok   {} random boards in {:.1}s", tried, start.elapsed().as_secs_f64());
+    Ok(())
+}
+
+/// The cells of the two `--diff` files, each at the coordinates its file gives them, sorted
+/// into those only in the first, only in the second and in both
+fn load_diff(opts: &Options) -> Result<BoardDiff, String> {
+    let Some((a, b)) = &opts.diff else { return Err("--diff expects two pattern files".to_string()); };
+    let cells = |path: &String| -> Result<HashSet<Position>, String> {
+        let pattern = load_pattern_file(path.as_ref()).map_err(|e| format!("{}: {}", path, e))?;
+        Ok(pattern.cells.iter().map(|&(x, y)| Position::new(x, y)).collect())
+    };
+    Ok(diff_boards(&cells(a)?, &cells(b)?))
+}
+
+/// Print how many cells two pattern files share and how many only each has
+fn run_diff(opts: &Options) -> Result<(), String> {
+    let counts = load_diff(opts)?.counts();
+    let (a, b) = opts.diff.as_ref().map(|(a, b)| (a.as_str(), b.as_str())).unwrap_or_default();
+    println!("only in {}: {}, only in {}: {}, in both: {}", a, counts.only_a, b, counts.only_b, counts.both);
+    Ok(())
+}
+
+/// Write the board after the requested generations as a PNG figure, or with `--diff`, the two
+/// files' diff in compare mode's colors
+fn run_render(opts: &Options) -> Result<(), String> {
+    let path = opts.output.as_deref().unwrap_or_default();
+    let mut figure = opts.figure.clone();
+    if let Some(backdrop) = &opts.backdrop {
+        figure.backdrop = Some(Rc::new(Backdrop::load(backdrop.as_ref(), opts.backdrop_opacity)?));
+    }
+    if opts.diff.is_some() {
+        let diff = load_diff(opts)?;
+        write_diff_png(path.as_ref(), &diff, &figure)?;
+        let counts = diff.counts();
+        println!("diff of {} and {} cells ({} shared) written to {}", counts.only_a + counts.both, counts.only_b + counts.both, counts.both, path);
+        return Ok(());
+    }
+    let mut sim = opts.build_game()?;
+    advance(&mut sim, opts)?;
+    write_board_png(path.as_ref(), &sim.live, &figure)?;
+    println!("generation {}: {} cells written to {}", sim.generation, sim.live.len(), path);
+    Ok(())
+}
+
+/// Write the whole board after the requested generations as SVG, warning first if it will be large
+fn run_export_svg(opts: &Options) -> Result<(), String> {
+    let path = opts.output.as_deref().unwrap_or_default();
+    let mut sim = opts.build_game()?;
+    advance(&mut sim, opts)?;
+    if let Some(warning) = svg_size_warning(sim.live.len()) {
+        eprintln!("warning: {}", warning);
+    }
+    let options = SvgOptions { theme: opts.figure.theme, cell: opts.figure.cell, grid: opts.figure.grid, border: opts.border };
+    write_board_svg(path.as_ref(), &sim, &options)?;
+    println!("generation {}: {} cells in {} rects written to {}", sim.generation, sim.live.len(), row_runs(&sim.live).len(), path);
+    Ok(())
+}
+
+/// Write the board after the requested generations as RLE
+fn run_export_rle(opts: &Options) -> Result<(), String> {
+    let path = opts.output.as_deref().unwrap_or_default();
+    let mut sim = opts.build_game()?;
+    advance(&mut sim, opts)?;
+    error::write(path.as_ref(), sim.to_rle())?;
+    println!("generation {}: {} cells written to {}", sim.generation, sim.live.len(), path);
+    Ok(())
+}
+
+/// Sweep the chosen parameter, printing progress to stderr and the CSV to stdout
+fn run_experiment(opts: &Options) -> Result<(), String> {
+    let parameter = opts.experiment.unwrap_or(Parameter::Density);
+    let values = match (&opts.values, opts.sweep) {
+        (Some(list), _) => list.split(',').map(|v| parameter.value(v)).collect::<Result<Vec<_>, _>>()?,
+        (None, (Some(from), Some(to), Some(step))) => parameter.range(from, to, step)?,
+        (None, _) => return Err(format!("--experiment {} needs --from, --to and --step, or --values", parameter.name())),
+    };
+    let experiment = Experiment {
+        values,
+        replicates: opts.replicates.max(1),
+        generations: if opts.generations == 0 { EXPERIMENT_GENERATIONS } else { opts.generations },
+        seed: opts.seed,
+        width: opts.width,
+        height: opts.height,
+        density: opts.density,
+        rule: Rule::LIFE,
+        topology: opts.topology,
+    };
+    let threads = experiment::default_threads();
+    eprintln!("{} runs on {} thread{}", experiment.values.len() * experiment.replicates as usize, threads, if threads == 1 { "" } else { "s" });
+    let outcomes = experiment.run(threads, |done, total, outcome| {
+        let ended = match (outcome.lifespan, outcome.period) {
+            (Some(g), Some(p)) => format!("settled at {} with period {}", g, p),
+            _ => "still running".to_string(),
+        };
+        eprintln!("[{}/{}] {} {} seed {}: {}", done, total, parameter.name(), outcome.value, outcome.seed, ended);
+    });
+    print!("{}", experiment::to_csv(&outcomes));
+    Ok(())
+}
+
+/// Rebuild a generation from a delta log and compare it with simulating the reference directly
+fn run_replay(opts: &Options) -> Result<(), String> {
+    let path = opts.delta_log.as_deref().unwrap_or_default();
+    let project = Project::open(path.as_ref())?;
+    let log = &project.log;
+    let target = if opts.generations == 0 { log.last_generation() } else { opts.generations };
+    let (replayed, direct) = replay(&project, target).map_err(|e| format!("{}: {}", path, e))?;
+    println!("generation {}: {} cells replayed from {} deltas", target, replayed.len(), log.deltas.len());
+    if let Some(schedule) = &log.schedule {
+        println!("paced by speed schedule {}", schedule);
+    }
+    for bookmark in &log.bookmarks {
+        println!("bookmark \"{}\": generation {}, {} cells", bookmark.label, bookmark.generation, bookmark.live.len());
+    }
+    for note in &log.annotations {
+        println!("note at ({}, {}), {}: {}", note.at.x(), note.at.y(), note.color.name(), note.text);
+    }
+    if replayed == direct {
+        println!("ok   matches direct simulation from generation {}", log.reference_generation);
+        Ok(())
+    } else {
+        Err(format!(
+            "replay differs from direct simulation by {} cells (was the board edited while recording?)",
+            replayed.symmetric_difference(&direct).count(),
+        ))
+    }
+}
+
+/// A project's board at `target` as its history replays it, and as stepping from the start of
+/// that history gives it under the project's rule and margin
+fn replay(project: &Project, target: u64) -> Result<(HashSet<Position>, HashSet<Position>), String> {
+    let log = &project.log;
+    let replayed = log.reconstruct(target).ok_or_else(|| {
+        format!("generation {} is not in the log ({}..={})", target, log.reference_generation, log.last_generation())
+    })?;
+    let mut sim = Simulation::new(log.width, log.height);
+    project.restore(&mut sim)?;
+    sim.restore(log.reconstruct(log.reference_generation).unwrap_or_default(), log.reference_generation);
+    for _ in log.reference_generation..target {
+        sim.next_generation();
+    }
+    Ok((replayed, sim.live))
+}
+
+/// Compare the engine with the reference on seeded soups, including degenerate grid shapes
+fn run_verify(opts: &Options) -> Result<(), String> {
+    let generations = if opts.generations == 0 { VERIFY_GENERATIONS } else { opts.generations };
+    if opts.width <= 0 || opts.height <= 0 {
+        return Err(format!("invalid grid size {}x{}", opts.width, opts.height));
+    }
+    let sizes = [&[(opts.width, opts.height)][..], &SWEEP_SIZES[..]].concat();
+    let mut failures = 0;
+    for case in differential_sweep(&sizes, Rule::LIFE, opts.seed, opts.runs, generations) {
+        let (w, h, topology) = (case.grid.width, case.grid.height, case.grid.topology);
+        for (seed, mismatch) in &case.failures {
+            println!("FAIL {}x{} {} B3/S23 seed {}: {}", w, h, topology, seed, mismatch);
+        }
+        if case.failures.is_empty() {
+            println!("ok   {}x{} {} B3/S23 ({} seeds x {} generations)", w, h, topology, opts.runs, generations);
+        }
+        failures += case.failures.len() as u32;
+    }
+    failures += run_boundary_cases();
+    failures += check_neighbors(opts.seed);
+    failures += check_torus_shift(opts.seed);
+    failures += check_bookmarks(opts.seed);
+    failures += check_edge_spawn_off(opts.seed);
+    failures += check_lethal_margin(opts.seed);
+    failures += check_rules(opts.seed);
+    failures += check_pattern_formats();
+    failures += check_macrocell();
+    failures += check_rle_limits();
+    failures += check_image_import();
+    failures += check_placement();
+    failures += check_fill_by_property();
+    failures += check_clipping();
+    failures += check_import_layout();
+    failures += check_window_fit();
+    failures += check_zoom();
+    failures += check_speed_schedule();
+    failures += check_simulation_config();
+    failures += check_theme_picker(opts.seed);
+    failures += check_live_reload();
+    failures += check_coordinates();
+    failures += check_ruler();
+    failures += check_reset_to_initial(opts.seed);
+    failures += check_run_stats();
+    failures += check_growth();
+    failures += check_cleanup();
+    failures += check_svg();
+    failures += check_heat();
+    failures += check_particles();
+    failures += check_smart_clear();
+    failures += check_mutations();
+    failures += check_remote();
+    failures += check_serve();
+    failures += check_guard();
+    failures += check_tab_copy();
+    failures += check_debug_dump(opts.seed);
+    failures += check_compare();
+    failures += check_rle_metadata();
+    failures += check_hotbar();
+    failures += check_experiment(opts.seed);
+    failures += check_records(opts.seed);
+    failures += check_renderer();
+    failures += check_backdrop();
+    failures += check_board_fingerprint(opts.seed);
+    failures += check_hash_stream(opts.seed);
+    failures += check_golden_figures();
+    failures += check_pattern_info();
+    failures += check_preview();
+    failures += check_loading();
+    failures += check_aiming();
+    failures += check_overlay_routing();
+    failures += check_menu_stack();
+    failures += check_timelapse(opts.seed);
+    failures += check_memory_budget(opts.seed);
+    failures += check_clock(opts.seed);
+    failures += check_step_budget();
+    failures += check_pacing();
+    failures += check_follow();
+    failures += check_kept_files();
+    failures += check_projects(opts.seed);
+    failures += check_engine_stages(opts.seed);
+    match failures {
+        0 => Ok(()),
+        n => Err(format!("{} check(s) failed", n)),
+    }
+}
+
+/// A project brings back every part of the board it saved, a plain `.gold` save opens as a
+/// project, and one missing its optional parts opens with the current values kept for them
+fn check_projects(seed: u64) -> u32 {
+    let mut failures = 0;
+    // Mid-run on a shifted torus, with history, a note, a bookmark, a schedule and a hotbar
+    let saved = || -> Result<(Simulation, Project), String> {
+        let mut sim = Simulation::new(40, 30);
+        sim.grid.topology = Topology::parse_shift("x+3")?;
+        sim.set_rule(Rule::parse("B36/S23")?)?;
+        (sim.seed, sim.density) = (seed, 0.4);
+        sim.live = random_soup(&sim.grid, 0.4, seed);
+        let mut log = DeltaLog::new(&sim);
+        for _ in 0..5 {
+            sim.next_generation();
+            log.record(&sim);
+        }
+        log.bookmarks = vec![Bookmark::capture(&sim, "five")];
+        let mut notes = Annotations::default();
+        notes.add(Position::new(3, 4), "look here", NoteColor::default());
+        log.annotations = notes.notes;
+        log.schedule = Some(SpeedSchedule::parse("0:2 100:60")?);
+        let mut settings = Settings { edge_rate: 0.25, lethal_margin: 2, ..Settings::default() };
+        settings.hotbar.assign(0, "Glider", Transform::IDENTITY);
+        let project = Project::capture(&sim, "Soup", log, settings);
+        Ok((sim, project))
+    };
+    // A board to open onto, whose rule and seed stay where the project has none
+    let current = || {
+        let mut sim = Simulation::new(8, 8);
+        sim.seed = 7;
+        sim
+    };
+
+    let result = (|| {
+        let (sim, project) = saved()?;
+        let path = std::env::temp_dir().join(format!("conways-game-of-life-project-{}.{}", std::process::id(), project::EXTENSION));
+        project.save(&path)?;
+        let read = Project::open(&path);
+        let _ = std::fs::remove_file(&path);
+        let read = read?;
+        if (&read.name, read.rule, read.seed, read.density) != (&project.name, project.rule, project.seed, project.density) {
+            return Err(format!("the manifest read back as {:?} {:?} {:?} {:?}", read.name, read.rule, read.seed, read.density));
+        }
+        if read.log != project.log || read.settings != project.settings || !read.missing().is_empty() || read.migrated {
+            return Err("the board, its history or the settings changed through a save and open".to_string());
+        }
+        let mut opened = current();
+        read.restore(&mut opened)?;
+        let grid = |sim: &Simulation| (sim.grid.width, sim.grid.height, sim.grid.topology, sim.grid.rule);
+        if opened.live != sim.live || opened.generation != 5 || grid(&opened) != grid(&sim) || opened.seed != seed {
+            return Err(format!("opened generation {} with {} cells, expected 5 with {}", opened.generation, opened.live.len(), sim.live.len()));
+        }
+        if (opened.edge_rate, opened.lethal_margin) != (0.25, 2) {
+            return Err("the project's edge rate and margin weren't applied".to_string());
+        }
+        // Plain zip, readable by any archive tool
+        let names: Vec<String> = project::unzip(&project.to_bytes())?.into_iter().map(|(name, _)| name).collect();
+        if names != ["manifest.txt", "board.gold", "settings.txt"] {
+            return Err(format!("the archive held {:?}", names));
+        }
+        Ok(())
+    })();
+    failures += report("project round-trips board, history, notes, bookmarks and settings", result);
+
+    // --replay-deltas steps the history's first board under the project's HighLife rule; the
+    // log's grid alone would step it as Life and report a mismatch
+    let result = (|| {
+        let (_, project) = saved()?;
+        let path = std::env::temp_dir().join(format!("conways-game-of-life-replay-{}.{}", std::process::id(), project::EXTENSION));
+        project.save(&path)?;
+        let read = Project::open(&path);
+        let _ = std::fs::remove_file(&path);
+        let read = read?;
+        let (replayed, direct) = replay(&read, 5)?;
+        if replayed != direct {
+            return Err(format!("replay differs from stepping by {} cells", replayed.symmetric_difference(&direct).count()));
+        }
+        let life = read.log.grid();
+        let as_life = (0..5).fold(read.log.reconstruct(0).unwrap_or_default(), |live, _| life.next_generation(&live));
+        if life.rule != Rule::LIFE || as_life == replayed {
+            return Err("the soup steps the same under Life, so the rule went untested".to_string());
+        }
+        Ok(())
+    })();
+    failures += report("a project replays under its own rule", result);
+
+    let result = (|| {
+        let (sim, project) = saved()?;
+        let read = Project::from_bytes(&project.log.to_bytes())?;
+        if !read.migrated || read.missing() != ["name", "rule", "seed", "density", "settings"] || read.log != project.log {
+            return Err(format!("a .gold save read back missing {:?}", read.missing()));
+        }
+        let mut opened = current();
+        read.restore(&mut opened)?;
+        if opened.live != sim.live || opened.generation != 5 || opened.grid.rule != Rule::LIFE || opened.seed != 7 {
+            return Err("a .gold save didn't open with its board and the current rule and seed".to_string());
+        }
+        // A version 1 log, from before bookmarks, torus shifts, schedules and notes
+        let mut log = project.log.clone();
+        (log.bookmarks, log.schedule, log.annotations, log.topology) = (Vec::new(), None, Vec::new(), Topology::Torus);
+        let mut old = log.to_bytes();
+        old.truncate(old.len() - 4 - 2 - 4);
+        old.drain(10..18);
+        old[4] = 1;
+        let read = Project::from_bytes(&old)?;
+        if read.board() != sim.live || read.log.last_generation() != 5 {
+            return Err("a version 1 .gold save opened with the wrong board".to_string());
+        }
+        Ok(())
+    })();
+    failures += report("plain .gold saves open as projects", result);
+
+    let result = (|| {
+        let (sim, project) = saved()?;
+        // Only the required parts: a manifest with just its version, and the board
+        let bytes = project::zip(&[("manifest.txt", b"format = 1\n".to_vec()), ("board.gold", project.log.to_bytes())]);
+        let read = Project::from_bytes(&bytes)?;
+        if read.migrated || read.missing() != ["name", "rule", "seed", "density", "settings"] {
+            return Err(format!("a bare project read back missing {:?}", read.missing()));
+        }
+        let mut opened = current();
+        opened.edge_rate = 0.5;
+        read.restore(&mut opened)?;
+        if opened.live != sim.live || opened.grid.rule != Rule::LIFE || opened.seed != 7 || opened.edge_rate != 0.5 {
+            return Err("a bare project didn't keep the current rule, seed and settings".to_string());
+        }
+        // Missing the board, from a newer version, damaged or cut off: refused with a reason
+        let manifest = project::zip(&[("manifest.txt", b"format = 1\n".to_vec())]);
+        let newer = project::zip(&[("manifest.txt", b"format = 99\n".to_vec()), ("board.gold", project.log.to_bytes())]);
+        let mut damaged = project.to_bytes();
+        let board = damaged.windows(4).position(|w| w == b"GOLD").ok_or("no board in the archive")?;
+        damaged[board + 20] ^= 0xff;
+        let cut = project.to_bytes()[..100].to_vec();
+        for (case, bytes, expected) in [("no board", manifest, "board.gold"), ("newer", newer, "newer"), ("damaged", damaged, "damaged"), ("cut off", cut, "cut off")] {
+            match Project::from_bytes(&bytes) {
+                Ok(_) => return Err(format!("a {} project opened", case)),
+                Err(err) if !err.to_string().contains(expected) => return Err(format!("a {} project failed with: {}", case, err)),
+                Err(_) => {}
+            }
+        }
+        Ok(())
+    })();
+    failures += report("projects missing optional parts open; broken ones are refused", result);
+    failures
+}
+
+/// The engine's stages, run with one scratch map, step exactly as `next_generation` does, and
+/// the fates they report are the births and deaths that follow
+fn check_engine_stages(seed: u64) -> u32 {
+    let mut failures = 0;
+    for text in ["B3/S23", "B36/S23", "B1/S012345678"] {
+        let result = (|| {
+            let mut grid = Grid::new(32, 24);
+            grid.topology = Topology::Torus;
+            grid.rule = Rule::parse(text)?;
+            let mut live = random_soup(&grid, 0.3, seed);
+            let (mut scratch, mut staged) = (HashMap::new(), HashSet::new());
+            for generation in 0..20 {
+                let next = grid.next_generation(&live);
+                let counts = grid.count_neighbors(&live, &mut scratch);
+                grid.apply(counts, &live, &mut staged);
+                if staged != next {
+                    return Err(format!("stages differ from next_generation at gen {}", generation));
+                }
+                let fates: HashMap<Position, CellFate> = grid.fates(counts, &live).collect();
+                let born: HashSet<Position> = next.difference(&live).copied().collect();
+                let died: HashSet<Position> = live.difference(&next).copied().collect();
+                let with = |wanted| fates.iter().filter(|(_, f)| **f == wanted).map(|(p, _)| *p).collect::<HashSet<_>>();
+                if with(CellFate::Born) != born || with(CellFate::Dies) != died {
+                    return Err(format!("fates disagree with the births and deaths at gen {}", generation));
+                }
+                if live.iter().any(|p| !fates.contains_key(p)) {
+                    return Err(format!("a live cell has no fate at gen {}", generation));
+                }
+                live = next;
+            }
+            Ok(())
+        })();
+        failures += report(&format!("engine stages match next_generation ({})", text), result);
+    }
+    failures
+}
+
+/// Settings, records and themes are written atomically with a backup, and a damaged file is
+/// replaced by that backup with a notice rather than silently reset
+fn check_kept_files() -> u32 {
+    let dir = std::env::temp_dir().join(format!("conways-game-of-life-kept-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let result = (|| {
+        // An overwrite leaves the new contents and nothing but the file and its backup
+        let path = dir.join("overwrite.txt");
+        error::write_kept(&path, "first = 1\n").map_err(|e| e.to_string())?;
+        error::write_kept(&path, "second = 2\n").map_err(|e| e.to_string())?;
+        let mut names: Vec<String> = std::fs::read_dir(&dir).map_err(|e| e.to_string())?
+            .filter_map(|entry| entry.ok().map(|e| e.file_name().to_string_lossy().into_owned()))
+            .collect();
+        names.sort();
+        if names != ["overwrite.txt", "overwrite.txt.bak"] {
+            return Err(format!("the directory held {:?}", names));
+        }
+        if error::read_to_string(&path).map_err(|e| e.to_string())? != "second = 2\n" {
+            return Err("the overwrite didn't land".to_string());
+        }
+
+        let settings = Settings { density: 0.5, ..Settings::default() };
+        let mut records = Records::default();
+        records.total_generations = 42;
+        let themes = CustomThemes::parse("Sunset = #0a0b0c #f0e1d2 #202020 #ff8000\n");
+        let settings_text = settings.to_text();
+        let records_text = records.to_text().map_err(|e| e.to_string())?;
+        let themes_text = themes.to_text();
+        // Cut partway into the second line's key, as a write stopped mid-way would
+        let truncated = |text: &str| -> Vec<u8> {
+            let second = text.find('\n').map_or(0, |i| i + 1);
+            text.as_bytes()[..second + 2].to_vec()
+        };
+        let garbage = vec![0xff, 0x00, 0xfe, b'\n', 0x00];
+        let damages: [(&str, Vec<u8>); 3] = [
+            ("truncated", truncated(&settings_text)),
+            ("garbage", garbage.clone()),
+            ("empty", Vec::new()),
+        ];
+        for (damage, bytes) in &damages {
+            let path = dir.join(format!("settings-{}.txt", damage));
+            error::write_kept(&path, &settings_text).map_err(|e| e.to_string())?;
+            std::fs::write(&path, bytes).map_err(|e| e.to_string())?;
+            let kept = Settings::read_from(&path).map_err(|e| format!("{} settings: {}", damage, e))?;
+            if kept.value != settings || !kept.notice.as_deref().is_some_and(|n| n.contains("damaged")) {
+                return Err(format!("{} settings came back as {:?} with notice {:?}", damage, kept.value.density, kept.notice));
+            }
+        }
+        let damages: [(&str, Vec<u8>); 3] = [
+            ("truncated", truncated(&records_text)),
+            ("garbage", garbage.clone()),
+            ("empty", Vec::new()),
+        ];
+        for (damage, bytes) in &damages {
+            let path = dir.join(format!("records-{}.txt", damage));
+            error::write_kept(&path, &records_text).map_err(|e| e.to_string())?;
+            std::fs::write(&path, bytes).map_err(|e| e.to_string())?;
+            let kept = Records::read_from(&path).map_err(|e| format!("{} records: {}", damage, e))?;
+            if kept.value.total_generations != 42 || kept.notice.is_none() {
+                return Err(format!("{} records came back with {} generations", damage, kept.value.total_generations));
+            }
+        }
+        // The themes file is edited by hand, so only a file that isn't text counts as damaged
+        for (damage, bytes) in [("garbage", garbage.clone()), ("empty", Vec::new())] {
+            let path = dir.join(format!("themes-{}.txt", damage));
+            error::write_kept(&path, &themes_text).map_err(|e| e.to_string())?;
+            std::fs::write(&path, bytes).map_err(|e| e.to_string())?;
+            let kept = CustomThemes::read_from(&path).map_err(|e| format!("{} themes: {}", damage, e))?;
+            if kept.value != themes || kept.notice.is_none() {
+                return Err(format!("{} themes came back with {} entries", damage, kept.value.entries.len()));
+            }
+        }
+
+        // An undamaged file needs no notice, a missing one is the defaults, and with both
+        // copies damaged the error names the file so the caller can fall back to defaults
+        let path = dir.join("settings.txt");
+        error::write_kept(&path, &settings_text).map_err(|e| e.to_string())?;
+        if Settings::read_from(&path).map_err(|e| e.to_string())?.notice.is_some() {
+            return Err("an undamaged file came with a notice".to_string());
+        }
+        if Settings::read_from(&dir.join("missing.txt")).map_err(|e| e.to_string())?.value != Settings::default() {
+            return Err("a missing file didn't give the defaults".to_string());
+        }
+        std::fs::write(&path, &garbage).map_err(|e| e.to_string())?;
+        std::fs::write(error::backup_path(&path), "no equals sign\n").map_err(|e| e.to_string())?;
+        match Settings::read_from(&path) {
+            Err(err) if err.to_string().contains("settings.txt") => Ok(()),
+            Err(err) => Err(format!("the error didn't name the file: {}", err)),
+            Ok(kept) => Err(format!("two damaged copies were read as {:?}", kept.value.density)),
+        }
+    })();
+    let _ = std::fs::remove_dir_all(&dir);
+    report("damaged settings, records and themes recover from their backups", result)
+}
+
+/// Run the self-test battery, each check on its own thread with its own time limit, and print
+/// a table of the results
+fn run_self_test(opts: &Options) -> Result<(), String> {
+    let outcomes: Vec<selftest::Outcome> = selftest::CHECKS.iter().map(|&check| selftest::run_check(check, opts.seed)).collect();
+    print!("{}", selftest::table(&outcomes));
+    match outcomes.iter().filter(|o| !o.passed()).count() {
+        0 => Ok(()),
+        n => Err(format!("{} self-test check(s) failed", n)),
+    }
+}
+
+/// Run a headless command if one was requested, returning the process exit code.
+/// Returns `None` when the graphical app should start instead.
+pub fn run(args: &[String]) -> Option<i32> {
+    let (command, opts) = match parse_args(args) {
+        Ok(Some(parsed)) => parsed,
+        Ok(None) => return None,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return Some(2);
+        }
+    };
+    let result = match command {
+        Command::Census => run_census(&opts),
+        Command::Verify => run_verify(&opts),
+        Command::SelfTest => run_self_test(&opts),
+        Command::ReplayDeltas => run_replay(&opts),
+        Command::Fuzz => run_fuzz(&opts),
+        Command::Render => run_render(&opts),
+        Command::ExportSvg => run_export_svg(&opts),
+        Command::ExportRle => run_export_rle(&opts),
+        Command::Bench => run_bench(&opts),
+        Command::Experiment => run_experiment(&opts),
+        Command::DebugDump => run_debug_dump(&opts),
+        Command::Diff => run_diff(&opts),
+        Command::Hashes | Command::Cleanup => opts.build_game().and_then(|mut sim| advance(&mut sim, &opts)),
+    };
+    match result {
+        Ok(()) => Some(0),
+        Err(err) => {
+            eprintln!("error: {}", err);
+            Some(1)
+        }
+    }
+}
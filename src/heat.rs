@@ -0,0 +1,108 @@
+//! A coarse map of where the board is busy, for the heat overlay. Each value covers a square of
+//! cells; births and deaths warm it, and every frame it cools and spreads a little into its
+//! neighbours. The square grows with the board, so the map never holds more than
+//! `HEAT_MAX_BLOCKS` values, and nothing is allocated after it is built.
+
+use crate::config::{HEAT_BLOCK, HEAT_BLUR, HEAT_FADE_SECONDS, HEAT_MAX_BLOCKS, HEAT_SCALE};
+use crate::grid::Grid;
+use crate::simulation::GenerationDiff;
+
+/// Activity per block of cells, in recent changes per cell
+#[derive(Clone, Debug)]
+pub struct HeatField {
+    pub block: i32, // Side of the square of cells each value covers
+    pub cols: i32,
+    pub rows: i32,
+    width: i32, // Grid size the field was built for
+    height: i32,
+    values: Vec<f32>,  // Row-major
+    scratch: Vec<f32>, // Spare buffer the blur writes into
+}
+
+impl HeatField {
+    /// A cold field for a `grid.width` x `grid.height` board
+    pub fn new(grid: &Grid) -> Self {
+        let (width, height) = (grid.width.max(1), grid.height.max(1));
+        let blocks = |block: i32| ((width + block - 1) / block) as usize * ((height + block - 1) / block) as usize;
+        let mut block = HEAT_BLOCK.max(((width as f64 * height as f64 / HEAT_MAX_BLOCKS as f64).sqrt().ceil()) as i32);
+        while blocks(block) > HEAT_MAX_BLOCKS {
+            block += 1;
+        }
+        let (cols, rows) = ((width + block - 1) / block, (height + block - 1) / block);
+        let len = (cols * rows) as usize;
+        Self { block, cols, rows, width: grid.width, height: grid.height, values: vec![0.0; len], scratch: vec![0.0; len] }
+    }
+
+    /// Whether the field still fits `grid`; after a resize it must be built again
+    pub fn fits(&self, grid: &Grid) -> bool {
+        (self.width, self.height) == (grid.width, grid.height)
+    }
+
+    /// Warm the blocks where a generation's births and deaths happened
+    pub fn record(&mut self, diff: &GenerationDiff) {
+        let warmth = 1.0 / (self.block * self.block) as f32;
+        for p in diff.born.iter().chain(&diff.died) {
+            if let Some(i) = self.index(p.x(), p.y()) {
+                self.values[i] += warmth;
+            }
+        }
+    }
+
+    /// Let `dt` seconds pass: every block cools and shares some heat with its four neighbours
+    pub fn cool(&mut self, dt: f32) {
+        let keep = (-dt / HEAT_FADE_SECONDS).exp();
+        let spread = (HEAT_BLUR * dt).min(0.25);
+        let (cols, rows) = (self.cols as usize, self.rows as usize);
+        let values = &self.values;
+        for y in 0..rows {
+            for x in 0..cols {
+                let i = y * cols + x;
+                // Beyond the edge, a block counts as its own neighbour
+                let at = |j: usize| values[j];
+                let left = if x > 0 { at(i - 1) } else { at(i) };
+                let right = if x + 1 < cols { at(i + 1) } else { at(i) };
+                let up = if y > 0 { at(i - cols) } else { at(i) };
+                let down = if y + 1 < rows { at(i + cols) } else { at(i) };
+                let blurred = values[i] * (1.0 - spread) + (left + right + up + down) * spread / 4.0;
+                // Flush the faint tail to zero rather than carry it forever
+                self.scratch[i] = if blurred * keep < 1e-4 { 0.0 } else { blurred * keep };
+            }
+        }
+        std::mem::swap(&mut self.values, &mut self.scratch);
+    }
+
+    /// Heat of the block holding cell (x, y)
+    pub fn at(&self, x: i32, y: i32) -> f32 {
+        self.index(x, y).map_or(0.0, |i| self.values[i])
+    }
+
+    /// How strongly block (col, row) glows, 0 to 1
+    pub fn glow(&self, col: i32, row: i32) -> f32 {
+        let heat = self.values[(row * self.cols + col) as usize];
+        1.0 - (-heat / HEAT_SCALE).exp()
+    }
+
+    /// Total heat over the field
+    pub fn total(&self) -> f32 {
+        self.values.iter().sum()
+    }
+
+    /// Heat values held, for checking the memory stays bounded
+    /// Heap size of the field and its blur buffer
+    pub fn bytes(&self) -> usize {
+        (self.values.capacity() + self.scratch.capacity()) * std::mem::size_of::<f32>()
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        let (col, row) = (x.div_euclid(self.block), y.div_euclid(self.block));
+        ((0..self.cols).contains(&col) && (0..self.rows).contains(&row)).then(|| (row * self.cols + col) as usize)
+    }
+}
@@ -0,0 +1,98 @@
+//! Quick-stamp hotbar: ten slots on the keys 1 to 9 and 0, each holding a pattern and the
+//! transform it was assigned with. Slots keep the pattern's name rather than its registry index,
+//! so they are saved in the settings file and find their pattern again in the next run even if
+//! the registry has grown since.
+
+use macroquad::color::Color;
+use macroquad::input::KeyCode;
+use macroquad::texture::Image;
+
+use crate::patterns::{shape, PatternRegistry, Stamp, Transform};
+use crate::render::Canvas;
+
+pub const SLOTS: usize = 10;
+
+/// Key of each slot, in order along the strip
+pub const KEYS: [KeyCode; SLOTS] = [
+    KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4, KeyCode::Key5,
+    KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9, KeyCode::Key0,
+];
+
+/// A pattern assigned to a slot
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Slot {
+    pub pattern: String,
+    pub transform: Transform,
+}
+
+impl Slot {
+    /// As written in the settings file: the transform, then the pattern, e.g. `rot90 Glider`
+    pub fn to_setting(&self) -> String {
+        format!("{} {}", self.transform.name(), self.pattern)
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        let (transform, pattern) = value.trim().split_once(' ')?;
+        let (pattern, transform) = (pattern.trim(), Transform::from_name(transform)?);
+        (!pattern.is_empty()).then(|| Self { pattern: pattern.to_string(), transform })
+    }
+}
+
+/// The ten slots, the first on key 1 and the last on key 0
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Hotbar {
+    pub slots: [Option<Slot>; SLOTS],
+}
+
+impl Hotbar {
+    /// Digit printed on slot `i`'s key
+    pub fn key_label(i: usize) -> char {
+        char::from_digit((i as u32 + 1) % 10, 10).unwrap_or('?')
+    }
+
+    /// Slot on the digit `key`
+    pub fn slot_of(key: char) -> Option<usize> {
+        let digit = key.to_digit(10)? as usize;
+        Some((digit + SLOTS - 1) % SLOTS)
+    }
+
+    pub fn assign(&mut self, i: usize, pattern: impl Into<String>, transform: Transform) {
+        if let Some(slot) = self.slots.get_mut(i) {
+            *slot = Some(Slot { pattern: pattern.into(), transform });
+        }
+    }
+
+    pub fn clear(&mut self, i: usize) {
+        if let Some(slot) = self.slots.get_mut(i) {
+            *slot = None;
+        }
+    }
+
+    /// A stamp of slot `i`'s pattern in its transform, or why there isn't one to arm
+    pub fn stamp(&self, i: usize, registry: &PatternRegistry) -> Result<Stamp, String> {
+        let key = Self::key_label(i);
+        let Some(slot) = self.slots.get(i).and_then(Option::as_ref) else {
+            return Err(format!("Slot {} is empty: pick a pattern with Tab, then press Shift+{}", key, key));
+        };
+        let index = registry.index_of(&slot.pattern)
+            .ok_or_else(|| format!("Slot {}: {} isn't loaded in this run", key, slot.pattern))?;
+        Ok(Stamp { transform: slot.transform, ..Stamp::new(index) })
+    }
+}
+
+/// A `size` x `size` picture of slot's pattern in its transform, cells in `color` on a clear
+/// ground, scaled to fit and centered. Blank for patterns that aren't loaded or fill the grid.
+pub fn thumbnail(slot: &Slot, registry: &PatternRegistry, size: u16, color: Color) -> Image {
+    let mut image = Image::gen_image_color(size, size, Color::new(0.0, 0.0, 0.0, 0.0));
+    let cells = registry.index_of(&slot.pattern).and_then(|i| registry.get_index(i)).and_then(|p| shape(p, slot.transform)).unwrap_or_default();
+    let (Some(w), Some(h)) = (cells.iter().map(|c| c.0 + 1).max(), cells.iter().map(|c| c.1 + 1).max()) else { return image; };
+    let side = size as f32;
+    let cell = (side / w.max(h) as f32).min(side / 4.0);
+    let (x0, y0) = ((side - w as f32 * cell) / 2.0, (side - h as f32 * cell) / 2.0);
+    // Large patterns shrink below a pixel per cell; each cell still covers at least one pixel
+    let drawn = cell.max(1.0);
+    for (x, y) in cells {
+        image.fill_rect(x0 + x as f32 * cell, y0 + y as f32 * cell, drawn, drawn, color);
+    }
+    image
+}
@@ -1,8 +1,51 @@
 // Module declarations for the Conway's Game of Life project
 
 pub mod config;
+pub mod error;
+pub mod rng;
+pub mod settings;
 pub mod themes;
+pub mod rule;
 pub mod grid;
+pub mod simulation;
+pub mod render;
+pub mod density;
+pub mod heat;
+pub mod particles;
+pub mod overlay;
 pub mod game;
+pub mod stepper;
+pub mod remote;
+pub mod serve;
+pub mod clock;
+pub mod pacing;
+pub mod guard;
+pub mod schedule;
+pub mod script;
+pub mod camera;
+pub mod figure;
 pub mod patterns;
-pub mod ui;
\ No newline at end of file
+pub mod preview;
+pub mod pattern_io;
+pub mod loading;
+pub mod net;
+pub mod deltas;
+pub mod budget;
+pub mod project;
+pub mod timelapse;
+pub mod bookmarks;
+pub mod hotbar;
+pub mod annotations;
+pub mod compare;
+pub mod territory;
+pub mod elementary;
+pub mod ui;
+pub mod console;
+pub mod analysis;
+pub mod experiment;
+pub mod mutation;
+pub mod records;
+pub mod debug_dump;
+pub mod watch;
+pub mod selftest;
+pub mod headless;
\ No newline at end of file
@@ -0,0 +1,113 @@
+//! Reading pattern files off the render thread. A big RLE (guns, computers) can take long enough
+//! to parse that the menu would freeze; a load reads the file in chunks on a worker thread,
+//! counting the bytes so the menu can show how far it has got, then parses it there. Where
+//! threads are unavailable (WASM) the file is read at once and the load is finished on return.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+
+use crate::config::LOAD_CHUNK;
+use crate::error::GolError;
+use crate::pattern_io::{check_extension, load_pattern_file, pattern_from_text, LoadedPattern};
+use crate::patterns::image;
+
+/// How far a load has got
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Stage {
+    Reading(f32), // Fraction of the file's bytes read
+    Parsing,
+}
+
+/// What a load's worker shares with the menu
+#[derive(Default)]
+struct Progress {
+    read: AtomicU64,
+    parsing: AtomicBool,
+    cancelled: AtomicBool,
+}
+
+/// A pattern file being loaded
+pub struct Load {
+    pub path: PathBuf,
+    total: u64, // Size of the file when the load started
+    progress: Arc<Progress>,
+    done: Receiver<Result<LoadedPattern, GolError>>,
+}
+
+impl Load {
+    /// Start loading `path` in the background
+    pub fn start(path: &Path) -> Load {
+        let total = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        let progress = Arc::new(Progress::default());
+        let (send, done) = mpsc::channel();
+        let load = Load { path: path.to_path_buf(), total, progress: Arc::clone(&progress), done };
+        if cfg!(target_arch = "wasm32") {
+            let _ = send.send(load_pattern_file(path));
+            return load;
+        }
+        let file = path.to_path_buf();
+        // If the thread can't start, the sender goes with the closure and `poll` says so
+        let _ = std::thread::Builder::new().name("life-loader".to_string()).spawn(move || {
+            let _ = send.send(read_and_parse(&file, &progress)); // Nobody listens after a cancel
+        });
+        load
+    }
+
+    /// The pattern, or why it failed, once the load has finished
+    pub fn poll(&self) -> Option<Result<LoadedPattern, GolError>> {
+        match self.done.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(GolError::Unsupported("the loader stopped unexpectedly".to_string()))),
+        }
+    }
+
+    /// Block until the load has finished
+    pub fn wait(self) -> Result<LoadedPattern, GolError> {
+        self.done.recv().unwrap_or_else(|_| Err(GolError::Unsupported("the loader stopped unexpectedly".to_string())))
+    }
+
+    /// How far the load has got
+    pub fn stage(&self) -> Stage {
+        if self.progress.parsing.load(Ordering::Relaxed) {
+            return Stage::Parsing;
+        }
+        let read = self.progress.read.load(Ordering::Relaxed);
+        Stage::Reading(if self.total == 0 { 0.0 } else { (read as f64 / self.total as f64).min(1.0) as f32 })
+    }
+}
+
+impl Drop for Load {
+    /// A load nobody waits for stops reading at the next chunk
+    fn drop(&mut self) {
+        self.progress.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+fn read_and_parse(path: &Path, progress: &Progress) -> Result<LoadedPattern, GolError> {
+    if image::AVAILABLE && image::is_image_file(path) {
+        progress.parsing.store(true, Ordering::Relaxed);
+        return load_pattern_file(path);
+    }
+    check_extension(path)?;
+    let mut file = File::open(path).map_err(|e| GolError::io(path, e))?;
+    let (mut bytes, mut chunk) = (Vec::new(), vec![0; LOAD_CHUNK]);
+    loop {
+        if progress.cancelled.load(Ordering::Relaxed) {
+            return Err(GolError::Unsupported("cancelled".to_string()));
+        }
+        let n = file.read(&mut chunk).map_err(|e| GolError::io(path, e))?;
+        if n == 0 {
+            break;
+        }
+        bytes.extend_from_slice(&chunk[..n]);
+        progress.read.fetch_add(n as u64, Ordering::Relaxed);
+    }
+    progress.parsing.store(true, Ordering::Relaxed);
+    let text = String::from_utf8(bytes).map_err(|e| GolError::io(path, io::Error::new(io::ErrorKind::InvalidData, e)))?;
+    pattern_from_text(path, &text)
+}
@@ -1,27 +1,130 @@
+use conways_game_of_life::config::SCREEN_SIZES;
+use conways_game_of_life::headless;
+use conways_game_of_life::patterns::default_registry;
+use conways_game_of_life::project::Project;
+use conways_game_of_life::ui::{
+    choose_board, choose_mode, choose_pattern, choose_resolution, make_room, open_project, run_elementary, run_simulation_with, run_territory,
+    show_records, show_summary, GameMode, MenuScreen, MenuStack, Setup, SimulationConfig,
+};
 
+/// Main entry point: run a headless command if one was given, otherwise open the window
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = headless::run(&args) {
+        std::process::exit(code);
+    }
+    // `--script <file>` without a headless command runs the hook in every simulation opened
+    let flag = |name: &str| args.iter().position(|a| a == name).and_then(|i| args.get(i + 1)).cloned();
+    let script = flag("--script");
+    // `--serve <address>` mirrors each simulation opened to clients on that address
+    let serve = flag("--serve");
+    // `--open <file>` starts on a saved project, or a `.gold` save, before the menus
+    let project = flag("--open").map(|path| {
+        Project::open(path.as_ref()).unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            std::process::exit(1);
+        })
+    });
+    macroquad::Window::new("Conway's Game of Life", app(script, serve, project));
+}
 
-mod config;
-mod themes;
-mod grid;
-mod game;
-mod patterns;
-mod ui;
-
-use config::SCREEN_SIZES;
-use ui::{choose_resolution, choose_pattern, run_simulation};
+/// A run with the script hook and mirroring asked for on the command line
+fn hooked<'a>(mut config: SimulationConfig<'a>, script: &Option<String>, serve: &Option<String>) -> SimulationConfig<'a> {
+    if let Some(path) = script {
+        config = config.script(path);
+    }
+    if let Some(address) = serve {
+        config = config.serve(address);
+    }
+    config
+}
 
-/// Main entry point for Conway's Game of Life
-#[macroquad::main("Conway's Game of Life")]
-async fn main() {
+/// Interactive menus and simulation. Each pass shows the screen on top of the menu stack;
+/// a pick records its choice in `setup` and pushes the next screen, Escape pops back one.
+async fn app(script: Option<String>, serve: Option<String>, project: Option<Project>) {
+    let mut registry = default_registry();
+    let mut menus = MenuStack::new();
+    let mut setup = Setup::default();
+    if let Some(project) = project {
+        let config = hooked(SimulationConfig::new().project(project), &script, &serve);
+        let summary = run_simulation_with(config.registry(&mut registry)).await;
+        show_summary(&summary).await;
+    }
     loop {
-        // Get user screen resolution selection
-        let idx = choose_resolution().await;
-        let (w, h) = SCREEN_SIZES[idx];
-        
-        // Get user pattern selection
-        if let Some(pat) = choose_pattern().await {
-            // Start simulation with selected options
-            run_simulation(w, h, pat).await;
+        let trail = menus.breadcrumb();
+        match menus.current() {
+            MenuScreen::Mode => {
+                setup = Setup { mode: choose_mode(&trail).await, ..Setup::default() };
+                menus.push(match setup.mode {
+                    GameMode::Records => MenuScreen::Records,
+                    GameMode::Open => MenuScreen::Open,
+                    _ => MenuScreen::Resolution,
+                });
+            }
+            MenuScreen::Resolution => {
+                let Some(idx) = choose_resolution(&trail).await else { menus.pop(); continue; };
+                let (w, h) = SCREEN_SIZES[idx];
+                setup.window = Some((w, h));
+                match setup.mode {
+                    GameMode::Territory => {
+                        run_territory(w, h).await;
+                        menus.reset();
+                    }
+                    GameMode::Elementary => {
+                        run_elementary(w, h).await;
+                        menus.reset();
+                    }
+                    GameMode::Simulation => menus.push(MenuScreen::Board),
+                    GameMode::Records | GameMode::Open => menus.reset(),
+                }
+            }
+            MenuScreen::Board => {
+                let Some((w, h)) = setup.window else { menus.reset(); continue; };
+                match choose_board(&trail, w, h).await {
+                    Some(board) => {
+                        setup.board = Some(board);
+                        menus.push(MenuScreen::Pattern);
+                    }
+                    None => menus.pop(),
+                }
+            }
+            MenuScreen::Pattern => match choose_pattern(&trail, &registry).await {
+                Some(choice) => {
+                    setup.pattern = Some(choice);
+                    menus.push(MenuScreen::Room);
+                }
+                None => menus.pop(),
+            },
+            MenuScreen::Records => {
+                let Some(run) = show_records(&trail).await else { menus.pop(); continue; };
+                // The record's run again, in the window as it is
+                let config = hooked(SimulationConfig::new().replay(&run), &script, &serve);
+                let summary = run_simulation_with(config.registry(&mut registry)).await;
+                show_summary(&summary).await;
+                menus.reset();
+            }
+            MenuScreen::Open => {
+                let Some(project) = open_project(&trail).await else { menus.pop(); continue; };
+                // In the window as it is, with the cell size fitted to the project's grid
+                let config = hooked(SimulationConfig::new().project(project), &script, &serve);
+                let summary = run_simulation_with(config.registry(&mut registry)).await;
+                show_summary(&summary).await;
+                menus.reset();
+            }
+            MenuScreen::Room => {
+                let (Some((w, h)), Some(board), Some(choice)) = (setup.window, setup.board, setup.pattern.as_ref()) else {
+                    menus.reset();
+                    continue;
+                };
+                // A pattern that needs more room than the board has gets a torus or a bigger board
+                let Some(board) = make_room(&trail, w, h, board, choice.pattern(&registry)).await else { menus.pop(); continue; };
+                setup.board = Some(board);
+                // Start simulation with the choices made, then show how it went
+                let Some(config) = setup.config() else { menus.reset(); continue; };
+                let summary = run_simulation_with(hooked(config, &script, &serve).registry(&mut registry)).await;
+                show_summary(&summary).await;
+                menus.reset();
+            }
         }
     }
-}
\ No newline at end of file
+}
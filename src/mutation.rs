@@ -0,0 +1,168 @@
+//! Rule mutation explorer: every rule one neighbour count away from the current one, each run
+//! from the same seeded soup so their outcomes can be compared side by side. B0 is left out,
+//! since the engine can't run it, which leaves 17 mutations of any rule the board can run.
+
+use std::collections::HashSet;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use crate::config::{MUTATION_FRAME_GENERATIONS, MUTATION_GENERATIONS, MUTATION_SIZE};
+use crate::grid::{Grid, Position, Topology};
+use crate::rule::Rule;
+use crate::simulation::Simulation;
+use crate::themes::ColorTheme;
+use crate::timelapse::{rasterize, Thumbnail};
+
+/// How one mutation's run ended
+pub struct Trial {
+    pub rule: Rule,
+    pub population: usize,
+    pub thumbnail: Thumbnail, // The board after the last generation
+}
+
+/// The rules one toggled neighbour count away from `rule`: births 0 to 8, then survivals 0 to
+/// 8, leaving out any the engine can't run
+pub fn mutations(rule: Rule) -> Vec<Rule> {
+    let births = (0..9).map(|n| Rule { birth: rule.birth ^ 1 << n, ..rule });
+    let survivals = (0..9).map(|n| Rule { survive: rule.survive ^ 1 << n, ..rule });
+    births.chain(survivals).filter(|r| r.runnable().is_ok()).collect()
+}
+
+/// How a mutation differs from its base, e.g. `+B6` or `-S2`
+pub fn change(base: Rule, rule: Rule) -> String {
+    let toggled = |from: u16, to: u16, tag: char| {
+        (0..9).filter(move |n| (from ^ to) & 1 << n != 0).map(move |n| format!("{}{}{}", if to & 1 << n != 0 { '+' } else { '-' }, tag, n))
+    };
+    let parts: Vec<String> = toggled(base.birth, rule.birth, 'B').chain(toggled(base.survive, rule.survive, 'S')).collect();
+    if parts.is_empty() { "same".to_string() } else { parts.join(" ") }
+}
+
+/// The runs still to do, stepped a slice at a time
+struct Batch {
+    grid: Grid,               // Soup board, with the base rule
+    soup: HashSet<Position>,  // Generation 0 of every run
+    rules: Vec<Rule>,
+    theme: ColorTheme,
+    next: usize,              // Index of the run in progress
+    running: Option<Simulation>,
+}
+
+impl Batch {
+    fn new(base: Rule, seed: u64, density: f32, topology: Topology, theme: ColorTheme) -> Self {
+        let mut sim = Simulation::new(MUTATION_SIZE.0, MUTATION_SIZE.1);
+        sim.grid.topology = topology;
+        sim.grid.rule = base;
+        sim.seed = seed;
+        sim.random_fill(density);
+        Self { grid: sim.grid, soup: sim.live, rules: mutations(base), theme, next: 0, running: None }
+    }
+
+    fn done(&self) -> bool {
+        self.next >= self.rules.len()
+    }
+
+    /// Run up to `budget` generations, carrying on where the last call stopped. Returns the
+    /// runs that finished on the way, by index.
+    fn advance(&mut self, mut budget: u64) -> Vec<(usize, Trial)> {
+        let mut finished = Vec::new();
+        while budget > 0 && !self.done() {
+            let sim = self.running.get_or_insert_with(|| {
+                let mut sim = Simulation::new(self.grid.width, self.grid.height);
+                sim.grid = self.grid.clone();
+                sim.grid.rule = self.rules[self.next];
+                sim.live = self.soup.clone();
+                sim
+            });
+            let steps = (MUTATION_GENERATIONS - sim.generation).min(budget);
+            for _ in 0..steps {
+                sim.next_generation();
+            }
+            budget -= steps;
+            if sim.generation >= MUTATION_GENERATIONS {
+                let thumbnail = Thumbnail::new(sim.generation, rasterize(sim, self.theme));
+                finished.push((self.next, Trial { rule: sim.grid.rule, population: sim.live.len(), thumbnail }));
+                self.running = None;
+                self.next += 1;
+            }
+        }
+        finished
+    }
+}
+
+/// Where the runs happen
+enum Work {
+    Thread(Receiver<(usize, Trial)>), // A worker sends each run as it finishes
+    Inline(Box<Batch>),               // Stepped a slice per frame, where there are no threads
+}
+
+/// The mutations of one base rule and how far their runs have got
+pub struct Explorer {
+    pub base: Rule,
+    pub seed: u64,
+    pub density: f32,
+    pub rules: Vec<Rule>,
+    pub trials: Vec<Option<Trial>>, // By mutation, once its run finishes
+    work: Work,
+}
+
+impl Explorer {
+    /// Start running every mutation of `base` from a soup seeded with `seed` at `density`, on a
+    /// worker thread where threads exist. The worker stops once the explorer is dropped.
+    pub fn start(base: Rule, seed: u64, density: f32, topology: Topology, theme: ColorTheme) -> Self {
+        if cfg!(target_arch = "wasm32") {
+            return Self::inline(base, seed, density, topology, theme);
+        }
+        let mut batch = Batch::new(base, seed, density, topology, theme);
+        let rules = batch.rules.clone();
+        let (results, finished) = mpsc::channel();
+        let spawned = thread::Builder::new().name("life-mutations".to_string()).spawn(move || {
+            while !batch.done() {
+                for result in batch.advance(MUTATION_GENERATIONS) {
+                    if results.send(result).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        match spawned {
+            Ok(_) => Self { base, seed, density, trials: rules.iter().map(|_| None).collect(), rules, work: Work::Thread(finished) },
+            Err(_) => Self::inline(base, seed, density, topology, theme),
+        }
+    }
+
+    /// Like `start`, but the runs advance only in `update` and `wait`, on the calling thread
+    pub fn inline(base: Rule, seed: u64, density: f32, topology: Topology, theme: ColorTheme) -> Self {
+        let batch = Batch::new(base, seed, density, topology, theme);
+        let rules = batch.rules.clone();
+        Self { base, seed, density, trials: rules.iter().map(|_| None).collect(), rules, work: Work::Inline(Box::new(batch)) }
+    }
+
+    /// Collect finished runs, or step the inline ones by `MUTATION_FRAME_GENERATIONS`; once a frame
+    pub fn update(&mut self) {
+        let finished = match &mut self.work {
+            Work::Thread(receiver) => receiver.try_iter().collect(),
+            Work::Inline(batch) => batch.advance(MUTATION_FRAME_GENERATIONS),
+        };
+        self.store(finished);
+    }
+
+    /// Block until every run has finished
+    pub fn wait(&mut self) {
+        let finished = match &mut self.work {
+            Work::Thread(receiver) => receiver.iter().collect(),
+            Work::Inline(batch) => batch.advance(u64::MAX),
+        };
+        self.store(finished);
+    }
+
+    /// Runs finished so far
+    pub fn done(&self) -> usize {
+        self.trials.iter().filter(|t| t.is_some()).count()
+    }
+
+    fn store(&mut self, finished: Vec<(usize, Trial)>) {
+        for (i, trial) in finished {
+            self.trials[i] = Some(trial);
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Loading a pattern from a URL. With the native-only `net` feature the download runs on a
+//! worker thread with `ureq`, so the window keeps drawing and Escape can stop it; the reply
+//! goes to the pattern parsers. Without the feature a fetch fails at once, saying why.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::sync::Arc;
+
+use crate::error::{GolError, Location};
+use crate::pattern_io::{parse_pattern_text, LoadedPattern};
+
+/// Whether this build can load from a URL
+pub const AVAILABLE: bool = cfg!(all(feature = "net", not(target_arch = "wasm32")));
+
+/// A download in progress
+pub struct Fetch {
+    pub url: String,            // Address actually fetched, after resolving wiki links
+    cancelled: Arc<AtomicBool>, // Set by `cancel`; the worker gives up at its next read
+    done: Receiver<Result<LoadedPattern, GolError>>,
+}
+
+impl Fetch {
+    /// Start downloading `url` in the background
+    pub fn start(url: &str) -> Result<Fetch, GolError> {
+        let url = resolve(url)?;
+        #[cfg(not(all(feature = "net", not(target_arch = "wasm32"))))]
+        {
+            let _ = url;
+            Err(GolError::Unsupported("this build can't load from a URL; rebuild with --features net (not available on the web)".to_string()))
+        }
+        #[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+        {
+            let (send, done) = std::sync::mpsc::channel();
+            let cancelled = Arc::new(AtomicBool::new(false));
+            let (flag, address) = (Arc::clone(&cancelled), url.clone());
+            std::thread::spawn(move || {
+                let result = http::download(&address, &flag)
+                    .map_err(GolError::Network)
+                    .and_then(|bytes| parse_reply(&address, &bytes));
+                let _ = send.send(result); // Nobody listens after a cancel
+            });
+            Ok(Fetch { url, cancelled, done })
+        }
+    }
+
+    /// The parsed pattern, or why it failed, once the download has finished
+    pub fn poll(&self) -> Option<Result<LoadedPattern, GolError>> {
+        match self.done.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(GolError::Network("the download stopped unexpectedly".to_string()))),
+        }
+    }
+
+    /// Stop the download; its worker finishes on its own and its result is dropped
+    pub fn cancel(self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Check the address and turn LifeWiki article links into the site's RLE file for the pattern
+pub fn resolve(url: &str) -> Result<String, GolError> {
+    let url = url.trim();
+    let rest = url.strip_prefix("https://").or_else(|| url.strip_prefix("http://"))
+        .ok_or_else(|| GolError::invalid("address", format!("'{}' is not an http:// or https:// address", url)))?;
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    if matches!(host, "conwaylife.com" | "www.conwaylife.com")
+        && let Some(article) = path.strip_prefix("wiki/")
+    {
+        let name: String = article.chars().filter(char::is_ascii_alphanumeric).collect::<String>().to_ascii_lowercase();
+        if !name.is_empty() {
+            return Ok(format!("https://conwaylife.com/patterns/{}.rle", name));
+        }
+    }
+    Ok(url.to_string())
+}
+
+/// Parse a downloaded reply, naming the pattern after the file when it has no name of its own
+pub fn parse_reply(url: &str, bytes: &[u8]) -> Result<LoadedPattern, GolError> {
+    let text = std::str::from_utf8(bytes)
+        .map_err(|_| GolError::parse("download", Location::Whole, "the reply isn't text, so it can't be a pattern file"))?;
+    let mut pattern = parse_pattern_text(text)?;
+    if pattern.name.is_empty() {
+        let file = url.rsplit('/').next().unwrap_or_default();
+        let stem = file.split(['?', '#']).next().unwrap_or_default();
+        pattern.name = stem.rsplit_once('.').map_or(stem, |(s, _)| s).to_string();
+        if pattern.name.is_empty() { pattern.name = "Downloaded pattern".to_string(); }
+    }
+    Ok(pattern)
+}
+
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+mod http {
+    use std::io::Read;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    use crate::config::{FETCH_CONNECT_SECONDS, FETCH_MAX_BYTES, FETCH_TIMEOUT_SECONDS};
+
+    /// Download `url`, following redirects, until the reply ends or `cancelled` is set
+    pub fn download(url: &str, cancelled: &AtomicBool) -> Result<Vec<u8>, String> {
+        let agent = ureq::AgentBuilder::new()
+            .timeout_connect(Duration::from_secs(FETCH_CONNECT_SECONDS as u64))
+            .timeout(Duration::from_secs(FETCH_TIMEOUT_SECONDS as u64))
+            .build();
+        let reply = agent.get(url).call().map_err(|e| match e {
+            ureq::Error::Status(code, reply) => format!("the server answered {} {}", code, reply.status_text()),
+            ureq::Error::Transport(err) => reason(&err),
+        })?;
+        let length = reply.header("Content-Length").and_then(|n| n.parse::<usize>().ok());
+        if length.is_some_and(|n| n > FETCH_MAX_BYTES) {
+            return Err(too_large());
+        }
+        let mut reader = reply.into_reader().take(FETCH_MAX_BYTES as u64 + 1);
+        let (mut body, mut chunk) = (Vec::new(), [0; 16 * 1024]);
+        loop {
+            if cancelled.load(Ordering::Relaxed) {
+                return Err("cancelled".to_string());
+            }
+            match reader.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(n) => body.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(reason(&e)),
+            }
+        }
+        if body.len() > FETCH_MAX_BYTES {
+            return Err(too_large());
+        }
+        Ok(body)
+    }
+
+    fn too_large() -> String {
+        format!("the file is larger than {} MB", FETCH_MAX_BYTES / (1024 * 1024))
+    }
+
+    /// Why a download failed, with timeouts, which ureq reports as bare I/O errors, said plainly
+    fn reason(err: &(dyn std::error::Error + 'static)) -> String {
+        let mut cause = Some(err);
+        while let Some(e) = cause {
+            if let Some(io) = e.downcast_ref::<std::io::Error>()
+                && matches!(io.kind(), std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock)
+            {
+                return format!("timed out after {} s", FETCH_TIMEOUT_SECONDS);
+            }
+            cause = e.source();
+        }
+        err.to_string()
+    }
+}
+
+#[cfg(all(test, feature = "net", not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    /// Fetch from a local server that answers one request with `reply`
+    fn fetch(reply: &'static str) -> Result<LoadedPattern, GolError> {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/patterns/glider.cells", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0; 4096]);
+            let _ = stream.write_all(reply.as_bytes());
+        });
+        Fetch::start(&url).unwrap().done.recv().unwrap()
+    }
+
+    #[test]
+    fn downloads_are_parsed_and_named_after_the_file() {
+        let pattern = fetch("HTTP/1.1 200 OK\r\nContent-Length: 12\r\nConnection: close\r\n\r\n.O.\n..O\nOOO\n").unwrap();
+        assert_eq!(pattern.name, "glider");
+        assert_eq!(pattern.cells.len(), 5);
+    }
+
+    #[test]
+    fn failed_downloads_say_why() {
+        let reason = |reply| match fetch(reply) {
+            Err(GolError::Network(message)) => message,
+            other => panic!("{:?}", other.map(|p| p.name)),
+        };
+        assert_eq!(reason("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"), "the server answered 404 Not Found");
+        assert_eq!(reason("HTTP/1.1 200 OK\r\nContent-Length: 99999999\r\nConnection: close\r\n\r\n"), "the file is larger than 4 MB");
+    }
+}
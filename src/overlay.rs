@@ -0,0 +1,132 @@
+//! Layers drawn over the board. Each frame's layers are stacked by `Layer`: they are drawn
+//! bottom-up, and input is offered top-down until one consumes it, so a modal panel keeps
+//! keys and clicks from reaching the layers and board beneath it.
+
+use std::cmp::Reverse;
+use macroquad::input::{is_key_pressed, is_mouse_button_pressed, mouse_position, KeyCode, MouseButton};
+
+use crate::analysis::GrowthTrend;
+use crate::compare::DiffCounts;
+use crate::pacing::Limit;
+use crate::render::BoardView;
+use crate::schedule::SpeedSchedule;
+use crate::simulation::{MemoryUsage, Simulation};
+
+/// Whether a layer used this frame's input; `Yes` stops it reaching anything below
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InputConsumed {
+    Yes,
+    No,
+}
+
+/// Where a layer sits in the stack; later variants are drawn on top and see input first
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Layer {
+    Ruler,   // Coordinate labels along the board's edges
+    Hud,     // Status lines at the top of the screen
+    Notices, // Toasts at the bottom
+    Help,    // Controls line, or the full controls panel while it is open
+    Panel,   // A modal panel opened from the run, such as the theme picker
+}
+
+/// This frame's keyboard and mouse presses
+pub trait Input {
+    fn key_pressed(&self, key: KeyCode) -> bool;
+    fn mouse_pressed(&self, button: MouseButton) -> bool;
+    fn mouse_position(&self) -> (f32, f32);
+}
+
+/// The real keyboard and mouse
+pub struct Window;
+
+impl Input for Window {
+    fn key_pressed(&self, key: KeyCode) -> bool {
+        is_key_pressed(key)
+    }
+
+    fn mouse_pressed(&self, button: MouseButton) -> bool {
+        is_mouse_button_pressed(button)
+    }
+
+    fn mouse_position(&self) -> (f32, f32) {
+        mouse_position()
+    }
+}
+
+/// Presses given up front, for driving overlays without a window
+#[derive(Default)]
+pub struct Scripted {
+    pub keys: Vec<KeyCode>,
+    pub buttons: Vec<MouseButton>,
+    pub mouse: (f32, f32),
+}
+
+impl Input for Scripted {
+    fn key_pressed(&self, key: KeyCode) -> bool {
+        self.keys.contains(&key)
+    }
+
+    fn mouse_pressed(&self, button: MouseButton) -> bool {
+        self.buttons.contains(&button)
+    }
+
+    fn mouse_position(&self) -> (f32, f32) {
+        self.mouse
+    }
+}
+
+/// What layers draw from: the board on screen and its run state
+pub struct Frame<'a> {
+    pub sim: &'a Simulation,
+    pub view: &'a BoardView,
+    pub paused: bool,
+    pub speed: f32,
+    pub time: f64, // Simulation time in seconds
+    pub follow: Option<&'static str>, // Follow camera mode, when on
+    pub schedule: Option<&'a SpeedSchedule>, // Speed schedule in force, drawn as a timeline
+    pub growth: Option<GrowthTrend>, // Population trend, once there is enough history
+    pub pacing: Option<Limit>, // Frame rate cap or battery saver in force
+    pub diff: Option<DiffCounts>, // Cells only on the primary board, only on the other, and on both, in compare mode
+    pub memory: Option<MemoryUsage>, // Everything the run holds, when the caller added it up
+}
+
+/// One layer over the board
+pub trait Overlay {
+    fn layer(&self) -> Layer;
+
+    /// React to this frame's input; the default ignores it and lets it through
+    fn handle_input(&mut self, _input: &dyn Input) -> InputConsumed {
+        InputConsumed::No
+    }
+
+    fn draw(&self, frame: &Frame);
+}
+
+/// The layers of one frame, topmost first. Layers on the same level keep the order given.
+pub struct OverlayStack<'a> {
+    layers: Vec<&'a mut dyn Overlay>,
+}
+
+impl<'a> OverlayStack<'a> {
+    pub fn new(mut layers: Vec<&'a mut dyn Overlay>) -> Self {
+        layers.sort_by_key(|l| Reverse(l.layer()));
+        Self { layers }
+    }
+
+    /// Offer input to each layer from the top down, stopping at the first that consumes it
+    pub fn handle_input(&mut self, input: &dyn Input) -> InputConsumed {
+        for layer in self.layers.iter_mut() {
+            if layer.handle_input(input) == InputConsumed::Yes {
+                return InputConsumed::Yes;
+            }
+        }
+        InputConsumed::No
+    }
+
+    /// Draw every layer from the bottom up
+    pub fn draw(&self, frame: &Frame) {
+        for layer in self.layers.iter().rev() {
+            layer.draw(frame);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! Frame pacing: an optional cap on how often the window draws, and a battery saver that drops
+//! to a trickle while nothing on screen changes. Native builds sleep out the rest of a capped
+//! frame; in the browser the animation frame already paces drawing, so nothing sleeps there.
+//! The simulation clock runs on frame time either way, and the per-frame step limits grow with
+//! longer frames, so pacing changes how often the board is drawn, not how fast it runs.
+
+use crate::config::{PACING_REFERENCE_FPS, SAVER_FPS, SAVER_GRACE_SECONDS};
+
+/// The frame rate to hold to, and why
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Limit {
+    pub fps: f32,
+    pub saving: bool, // The battery saver's rate rather than the cap
+}
+
+impl Limit {
+    /// How much to stretch the per-frame step cap and time budget so the simulation keeps up
+    /// at this frame rate
+    pub fn step_scale(limit: Option<Limit>) -> f64 {
+        limit.map_or(1.0, |l| (PACING_REFERENCE_FPS / l.fps).max(1.0) as f64)
+    }
+
+    /// HUD label, e.g. `cap:30` or `saver:10`
+    pub fn label(&self) -> String {
+        format!("{}:{:.0}", if self.saving { "saver" } else { "cap" }, self.fps)
+    }
+}
+
+/// Decides each frame's rate from the settings and whether anything is happening
+#[derive(Clone, Debug)]
+pub struct Pacer {
+    pub cap: Option<f32>, // Render frames per second; `None` draws as often as the display allows
+    pub saver: bool,
+    awake_until: f64, // Full rate until this time, after input or a change on the board
+}
+
+impl Pacer {
+    /// `cap` of 0 leaves frames uncapped
+    pub fn new(cap: u32, saver: bool) -> Self {
+        Self { cap: (cap > 0).then_some(cap as f32), saver, awake_until: 0.0 }
+    }
+
+    /// Input arrived or the board changed at `now`: full rate for a while
+    pub fn wake(&mut self, now: f64) {
+        self.awake_until = now + SAVER_GRACE_SECONDS;
+    }
+
+    /// The rate to hold a frame at `now` to. `busy` is anything that must not slow down, such
+    /// as a fast-forward; otherwise the saver takes over once nothing has woken it for a while.
+    pub fn limit(&self, now: f64, busy: bool) -> Option<Limit> {
+        let saving = self.saver && !busy && now >= self.awake_until;
+        match (saving, self.cap) {
+            (true, cap) => Some(Limit { fps: cap.map_or(SAVER_FPS, |c| c.min(SAVER_FPS)), saving: true }),
+            (false, cap) => cap.map(|fps| Limit { fps, saving: false }),
+        }
+    }
+
+    /// Seconds left of a frame that started at `start` when held to `limit`
+    pub fn remaining(limit: Option<Limit>, start: f64, now: f64) -> f64 {
+        limit.map_or(0.0, |l| (1.0 / l.fps as f64 - (now - start)).max(0.0))
+    }
+
+    /// Wait out the rest of the frame; the browser paces frames itself
+    pub fn sleep(seconds: f64) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if seconds > 0.0 {
+            std::thread::sleep(std::time::Duration::from_secs_f64(seconds));
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = seconds;
+    }
+}
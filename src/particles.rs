@@ -0,0 +1,97 @@
+//! Cosmetic births and deaths: a ring that grows out of each newborn cell and a square that
+//! shrinks away where a cell died. Particles live in a pool of fixed size, filled once, so a
+//! busy board reuses the oldest slots rather than allocating. A generation with more than
+//! `PARTICLE_MAX_BIRTHS` births emits nothing, so a chaotic soup stays cheap to draw.
+
+use crate::config::{PARTICLE_CAPACITY, PARTICLE_MAX_BIRTHS, PARTICLE_SECONDS};
+use crate::simulation::GenerationDiff;
+
+/// What a particle shows
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Effect {
+    Birth, // A ring growing out of the cell
+    Death, // The cell shrinking and fading
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Particle {
+    pub x: i32, // Cell it started from
+    pub y: i32,
+    pub effect: Effect,
+    age: f32, // Seconds since it was emitted; spent at `PARTICLE_SECONDS`
+}
+
+impl Particle {
+    const SPENT: Particle = Particle { x: 0, y: 0, effect: Effect::Birth, age: PARTICLE_SECONDS };
+
+    /// How far through its life it is, 0 to 1
+    pub fn progress(&self) -> f32 {
+        (self.age / PARTICLE_SECONDS).min(1.0)
+    }
+
+    fn spent(&self) -> bool {
+        self.age >= PARTICLE_SECONDS
+    }
+}
+
+/// The particles of one board
+#[derive(Clone, Debug)]
+pub struct Particles {
+    pool: Vec<Particle>, // Never grows; spent particles are free slots
+    next: usize,         // Slot the next particle takes, which holds the oldest one
+    pub skipped: bool,   // The last generation had too many births to show
+}
+
+impl Default for Particles {
+    fn default() -> Self {
+        Self::with_capacity(PARTICLE_CAPACITY)
+    }
+}
+
+impl Particles {
+    /// A pool holding at most `capacity` particles at once
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self { pool: vec![Particle::SPENT; capacity.max(1)], next: 0, skipped: false }
+    }
+
+    /// Emit a particle for each of a generation's births and deaths, unless it had too many births
+    pub fn record(&mut self, diff: &GenerationDiff) {
+        self.skipped = diff.born.len() > PARTICLE_MAX_BIRTHS;
+        if self.skipped {
+            return;
+        }
+        for p in &diff.born {
+            self.emit(p.x(), p.y(), Effect::Birth);
+        }
+        for p in &diff.died {
+            self.emit(p.x(), p.y(), Effect::Death);
+        }
+    }
+
+    /// Let `dt` seconds pass
+    pub fn update(&mut self, dt: f32) {
+        for p in self.pool.iter_mut().filter(|p| !p.spent()) {
+            p.age += dt;
+        }
+    }
+
+    /// The particles still showing
+    pub fn active(&self) -> impl Iterator<Item = &Particle> {
+        self.pool.iter().filter(|p| !p.spent())
+    }
+
+    /// Most particles shown at once
+    /// Heap size of the pool
+    pub fn bytes(&self) -> usize {
+        self.pool.capacity() * std::mem::size_of::<Particle>()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.pool.len()
+    }
+
+    fn emit(&mut self, x: i32, y: i32, effect: Effect) {
+        self.pool[self.next] = Particle { x, y, effect, age: 0.0 };
+        self.next = (self.next + 1) % self.pool.len();
+    }
+}
@@ -0,0 +1,654 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::config::{IMAGE_FIT, MAX_CELLS};
+use crate::error::{self, GolError, Location};
+use crate::patterns::image::{self, ImageOptions};
+use crate::patterns::{Pattern, PatternContext};
+use crate::rule::Rule;
+
+/// File extensions the loader understands
+pub const SUPPORTED_EXTENSIONS: [&str; 5] = ["mc", "rle", "cells", "lif", "life"];
+
+/// A pattern read from disk, with cells normalized so the bounding box starts at (0, 0)
+#[derive(Clone, Debug, Default)]
+pub struct LoadedPattern {
+    pub name: String,
+    pub rule: Option<String>, // Rule from the file header, if it had one
+    pub cells: Vec<(i32, i32)>,
+    pub width: i32,
+    pub height: i32,
+    pub meta: Metadata,
+}
+
+/// What a pattern file says about its pattern besides the name, rule and cells. RLE files carry
+/// all of it; other formats leave it empty.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Metadata {
+    pub author: Option<String>,     // `#O`
+    pub comments: Vec<String>,      // `#C` lines, in order
+    pub offset: Option<(i64, i64)>, // `#R x y`: where the pattern's top left corner sits
+}
+
+impl LoadedPattern {
+    /// The rule the file names, if any, or why the engine can't run it. "Life" names B3/S23.
+    pub fn file_rule(&self) -> Option<Result<Rule, GolError>> {
+        let text = self.rule.as_deref()?;
+        if text.trim().eq_ignore_ascii_case("life") {
+            return Some(Ok(Rule::LIFE));
+        }
+        Some(Rule::parse(text).and_then(Rule::runnable))
+    }
+
+    /// The file's rule when it isn't `active`, the rule the board runs, or why it can't be run
+    pub fn foreign_rule(&self, active: Rule) -> Option<Result<Rule, GolError>> {
+        self.file_rule().filter(|rule| rule.as_ref().ok() != Some(&active))
+    }
+}
+
+impl Pattern for LoadedPattern {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn apply(&self, ctx: &mut PatternContext, x: i32, y: i32) {
+        let (left, top) = (x - self.width / 2, y - self.height / 2);
+        for &(cx, cy) in &self.cells {
+            ctx.add_cell(left + cx, top + cy);
+        }
+    }
+
+    fn category(&self) -> &str {
+        "File"
+    }
+
+    fn bounds(&self) -> Option<(i32, i32)> {
+        Some((self.width, self.height))
+    }
+
+    fn metadata(&self) -> Option<&Metadata> {
+        Some(&self.meta)
+    }
+}
+
+/// Read a pattern file with a supported extension, picking the parser from its contents.
+/// With the `image-import` feature a PNG or BMP is read as a picture at most `IMAGE_FIT` cells across.
+pub fn load_pattern_file(path: &Path) -> Result<LoadedPattern, GolError> {
+    if image::AVAILABLE && image::is_image_file(path) {
+        return image::load(path, IMAGE_FIT, IMAGE_FIT, ImageOptions::default());
+    }
+    check_extension(path)?;
+    pattern_from_text(path, &error::read_to_string(path)?)
+}
+
+/// Refuse a path whose extension no text parser handles
+pub fn check_extension(path: &Path) -> Result<(), GolError> {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+    if !SUPPORTED_EXTENSIONS.contains(&ext.as_str()) {
+        return Err(GolError::Unsupported(format!("{}: unsupported pattern format '{}'", path.display(), ext)));
+    }
+    Ok(())
+}
+
+/// Parse the text of the file at `path`, naming the pattern after the file when it has no name
+pub fn pattern_from_text(path: &Path, text: &str) -> Result<LoadedPattern, GolError> {
+    let mut pattern = parse_pattern_text(text).map_err(|e| e.in_file(path))?;
+    if pattern.name.is_empty() {
+        pattern.name = path.file_stem().and_then(|s| s.to_str()).unwrap_or("Pattern").to_string();
+    }
+    Ok(pattern)
+}
+
+/// Pattern files in `dir` with a supported extension, pictures included when they can be imported,
+/// sorted by name
+pub fn scan_pattern_dir(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else { return Vec::new() };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| SUPPORTED_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+                || (image::AVAILABLE && image::is_image_file(p))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Pattern files named by `spec`: a directory (every pattern file in it), or a path whose file
+/// name may use `*` and `?`, e.g. `patterns/*.rle`. Sorted by name.
+pub fn expand_glob(spec: &str) -> Vec<PathBuf> {
+    let path = Path::new(spec.trim());
+    if path.is_dir() {
+        return scan_pattern_dir(path);
+    }
+    let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else { return Vec::new() };
+    scan_pattern_dir(dir)
+        .into_iter()
+        .filter(|p| p.file_name().and_then(|n| n.to_str()).is_some_and(|n| wildcard_match(name, n)))
+        .collect()
+}
+
+/// Whether `text` matches `pattern`, where `*` stands for any run of characters and `?` for one
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let (pattern, text): (Vec<char>, Vec<char>) = (pattern.chars().collect(), text.chars().collect());
+    let (mut p, mut t) = (0, 0);
+    let mut star = None; // Last `*` seen and where in the text it started matching
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((sp, st)) = star {
+            // Let the last `*` swallow one more character and try again
+            star = Some((sp, st + 1));
+            p = sp + 1;
+            t = st + 1;
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Parse a pattern in any supported format, recognised by its contents: macrocell, Life 1.06,
+/// Life 1.05, RLE or plaintext
+pub fn parse_pattern_text(text: &str) -> Result<LoadedPattern, GolError> {
+    let first = text.lines().map(str::trim).find(|l| !l.is_empty()).unwrap_or("");
+    let unrecognised = |message: &str| Err(GolError::parse("pattern", Location::Line(1), message));
+    if first.starts_with("[M2]") {
+        parse_macrocell(text)
+    } else if first.starts_with("#Life 1.06") {
+        parse_life106(text)
+    } else if first.starts_with("#Life 1.05") {
+        parse_life105(text)
+    } else if first.starts_with('<') {
+        unrecognised("this is a web page, not a pattern file")
+    } else if text.lines().map(str::trim).find(|l| !l.is_empty() && !l.starts_with('#')).is_some_and(|l| l.starts_with('x')) {
+        parse_rle(text)
+    } else if first.starts_with('!') || first.starts_with(['.', 'O', '*']) {
+        parse_plaintext(text)
+    } else {
+        unrecognised("not a macrocell, RLE, plaintext or Life 1.0x pattern")
+    }
+}
+
+/// Largest run count or position an RLE file may use
+const RLE_SPAN: i64 = i32::MAX as i64;
+
+/// Parse run-length encoded cells: `x = 3, y = 3, rule = B3/S23` then runs of `b`, `o` and `$`
+pub fn parse_rle(text: &str) -> Result<LoadedPattern, GolError> {
+    let (mut name, mut rule) = (String::new(), None);
+    let mut meta = Metadata::default();
+    let mut cells = Vec::new();
+    let (mut x, mut y, mut run) = (0i64, 0i64, 0i64);
+    let mut header = false;
+    'lines: for (i, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        let err = |message: String| GolError::parse("RLE", Location::Line(i + 1), message);
+        if let Some(rest) = line.strip_prefix('#') {
+            match rest.split_at_checked(1) {
+                Some(("N", value)) if name.is_empty() => name = value.trim().to_string(),
+                Some(("O", value)) if meta.author.is_none() => meta.author = Some(value.trim().to_string()),
+                Some(("C" | "c", value)) => meta.comments.push(value.trim().to_string()),
+                Some(("R" | "P", value)) => {
+                    let mut fields = value.split_whitespace().map(|f| f.parse::<i64>());
+                    let (Some(Ok(left)), Some(Ok(top)), None) = (fields.next(), fields.next(), fields.next()) else {
+                        return Err(err(format!("expected '#{} x y', got '{}'", &rest[..1], line)));
+                    };
+                    meta.offset = Some((left, top));
+                }
+                Some(("r", value)) => rule = Some(value.trim().to_string()),
+                _ => {}
+            }
+            continue;
+        }
+        if !header {
+            if line.is_empty() { continue; }
+            if !line.starts_with('x') {
+                return Err(err("missing 'x = ..., y = ...' header".to_string()));
+            }
+            for field in line.split(',') {
+                if let Some((key, value)) = field.split_once('=')
+                    && key.trim() == "rule"
+                {
+                    rule = Some(value.trim().to_string());
+                }
+            }
+            header = true;
+            continue;
+        }
+        let indent = raw.len() - raw.trim_start().len();
+        for (column, ch) in line.chars().enumerate() {
+            let at = || Location::Column { line: i + 1, column: raw[..indent].chars().count() + column + 1 };
+            // Runs and positions stay within an `i32`, beyond which no pattern could be placed
+            let past = |what: &str| GolError::parse("RLE", at(), format!("{} {}", what, RLE_SPAN));
+            let advance = |from: i64, by: i64| from.checked_add(by).filter(|&to| to <= RLE_SPAN);
+            match ch {
+                '0'..='9' => {
+                    run = run.checked_mul(10).and_then(|r| r.checked_add(i64::from(ch as u8 - b'0'))).filter(|&r| r <= RLE_SPAN).ok_or_else(|| past("run count over"))?;
+                }
+                'b' | '.' => { x = advance(x, run.max(1)).ok_or_else(|| past("row longer than"))?; run = 0; }
+                '$' => { y = advance(y, run.max(1)).ok_or_else(|| past("more rows than"))?; x = 0; run = 0; }
+                '!' => break 'lines,
+                c if c.is_ascii_alphabetic() => {
+                    // `o`, or any state letter of a multi-state file, is a live cell
+                    let end = advance(x, run.max(1)).ok_or_else(|| past("row longer than"))?;
+                    for cx in x..end {
+                        if cells.len() >= MAX_CELLS {
+                            return Err(GolError::TooLarge { limit: MAX_CELLS, path: None });
+                        }
+                        cells.push((cx, y));
+                    }
+                    x = end;
+                    run = 0;
+                }
+                c if c.is_whitespace() => {}
+                c => return Err(GolError::parse("RLE", at(), format!("unexpected '{}' in RLE data", c))),
+            }
+        }
+    }
+    if !header {
+        return Err(GolError::parse("RLE", Location::Line(1), "missing 'x = ..., y = ...' header"));
+    }
+    Ok(LoadedPattern { meta, ..normalized(name, rule, cells)? })
+}
+
+/// Write cells as RLE, shifted so their bounding box starts at (0, 0) as `parse_rle` reads them
+/// back, with `write_pattern_rle`'s layout
+pub fn write_rle(name: &str, rule: &str, cells: &[(i32, i32)]) -> String {
+    let wide: Vec<(i64, i64)> = cells.iter().map(|&(x, y)| (i64::from(x), i64::from(y))).collect();
+    // Cells that came from an `i32` board always fit
+    let pattern = normalized(name.to_string(), Some(rule.to_string()), wide).unwrap_or_default();
+    write_pattern_rle(&pattern)
+}
+
+/// Write a pattern as RLE laid out the way Golly writes it: `#N`, `#O`, `#C` and `#R` lines,
+/// the `x = .., y = .., rule = ..` header, then the runs wrapped at 70 columns and ended with
+/// `!` and a line break. A file already in that layout reads back and writes out byte for byte.
+/// The rule is B3/S23 when the pattern has none.
+pub fn write_pattern_rle(pattern: &LoadedPattern) -> String {
+    let mut sorted: Vec<(i32, i32)> = pattern.cells.iter().map(|&(x, y)| (y, x)).collect();
+    sorted.sort_unstable();
+    sorted.dedup();
+    // Runs of (count, tag), merging neighbours with the same tag
+    let mut runs: Vec<(i32, char)> = Vec::new();
+    let mut push = |count: i32, tag: char| match runs.last_mut() {
+        Some((n, last)) if *last == tag => *n += count,
+        _ if count > 0 => runs.push((count, tag)),
+        _ => {}
+    };
+    let (mut x, mut y) = (0, 0);
+    for (cy, cx) in sorted {
+        if cy > y {
+            push(cy - y, '$');
+            (x, y) = (0, cy);
+        }
+        push(cx - x, 'b');
+        push(1, 'o');
+        x = cx + 1;
+    }
+    push(1, '!');
+    let mut text = String::new();
+    let meta = &pattern.meta;
+    let tagged = |tag: char, value: &str| if value.is_empty() { format!("#{}\n", tag) } else { format!("#{} {}\n", tag, value) };
+    if !pattern.name.is_empty() {
+        text += &tagged('N', &pattern.name);
+    }
+    if let Some(author) = &meta.author {
+        text += &tagged('O', author);
+    }
+    for comment in &meta.comments {
+        text += &tagged('C', comment);
+    }
+    if let Some((left, top)) = meta.offset {
+        text += &format!("#R {} {}\n", left, top);
+    }
+    text += &format!("x = {}, y = {}, rule = {}\n", pattern.width, pattern.height, pattern.rule.as_deref().unwrap_or("B3/S23"));
+    let mut line = String::new();
+    for (count, tag) in runs {
+        let item = if count == 1 { tag.to_string() } else { format!("{}{}", count, tag) };
+        if line.len() + item.len() > 70 {
+            text += &line;
+            text.push('\n');
+            line.clear();
+        }
+        line += &item;
+    }
+    text + line.as_str() + "\n"
+}
+
+/// Parse plaintext (`.cells`): `!` comment lines, then rows of `.` for dead and `O` for live
+pub fn parse_plaintext(text: &str) -> Result<LoadedPattern, GolError> {
+    let mut name = String::new();
+    let mut cells = Vec::new();
+    let mut y = 0i64;
+    for (i, raw) in text.lines().enumerate() {
+        let line = raw.trim_end();
+        if let Some(comment) = line.strip_prefix('!') {
+            if let Some(value) = comment.strip_prefix("Name:") && name.is_empty() {
+                name = value.trim().to_string();
+            }
+            continue;
+        }
+        for (x, ch) in line.chars().enumerate() {
+            match ch {
+                'O' | '*' => {
+                    if cells.len() >= MAX_CELLS {
+                        return Err(GolError::TooLarge { limit: MAX_CELLS, path: None });
+                    }
+                    cells.push((x as i64, y));
+                }
+                '.' => {}
+                c => {
+                    let at = Location::Column { line: i + 1, column: x + 1 };
+                    return Err(GolError::parse("plaintext", at, format!("unexpected '{}' in plaintext row", c)));
+                }
+            }
+        }
+        y += 1;
+    }
+    normalized(name, None, cells)
+}
+
+/// Parse Life 1.06: a `#Life 1.06` header, then one `x y` live cell per line
+pub fn parse_life106(text: &str) -> Result<LoadedPattern, GolError> {
+    let mut cells = Vec::new();
+    for (i, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') { continue; }
+        let err = || GolError::parse("Life 1.06", Location::Line(i + 1), format!("expected 'x y', got '{}'", line));
+        let mut fields = line.split_whitespace().map(|f| f.parse::<i64>());
+        let (Some(Ok(x)), Some(Ok(y)), None) = (fields.next(), fields.next(), fields.next()) else { return Err(err()) };
+        if cells.len() >= MAX_CELLS {
+            return Err(GolError::TooLarge { limit: MAX_CELLS, path: None });
+        }
+        cells.push((x, y));
+    }
+    normalized(String::new(), None, cells)
+}
+
+/// Parse Life 1.05: a `#Life 1.05` header and `#P x y` blocks of `.` and `*` rows
+pub fn parse_life105(text: &str) -> Result<LoadedPattern, GolError> {
+    let mut rule = None;
+    let mut cells = Vec::new();
+    let (mut left, mut y) = (0i64, 0i64);
+    for (i, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        let err = |message: String| GolError::parse("Life 1.05", Location::Line(i + 1), message);
+        if let Some(rest) = line.strip_prefix("#P") {
+            let mut fields = rest.split_whitespace().map(|f| f.parse::<i64>());
+            let (Some(Ok(x)), Some(Ok(top))) = (fields.next(), fields.next()) else {
+                return Err(err(format!("expected '#P x y', got '{}'", line)));
+            };
+            (left, y) = (x, top);
+            continue;
+        }
+        if let Some(value) = line.strip_prefix("#R") {
+            rule = Some(value.trim().to_string());
+            continue;
+        }
+        if line.is_empty() || line.starts_with('#') { continue; }
+        for (dx, ch) in line.chars().enumerate() {
+            match ch {
+                '*' => {
+                    if cells.len() >= MAX_CELLS {
+                        return Err(GolError::TooLarge { limit: MAX_CELLS, path: None });
+                    }
+                    cells.push((left + dx as i64, y));
+                }
+                '.' => {}
+                c => {
+                    let at = Location::Column { line: i + 1, column: raw[..raw.len() - raw.trim_start().len()].chars().count() + dx + 1 };
+                    return Err(GolError::parse("Life 1.05", at, format!("unexpected '{}' in Life 1.05 row", c)));
+                }
+            }
+        }
+        y += 1;
+    }
+    normalized(String::new(), rule, cells)
+}
+
+/// Shift cells so their bounding box starts at (0, 0), rejecting spans that don't fit an `i32`
+fn normalized(name: String, rule: Option<String>, cells: Vec<(i64, i64)>) -> Result<LoadedPattern, GolError> {
+    let (min_x, min_y) = cells.iter().fold((i64::MAX, i64::MAX), |(mx, my), &(x, y)| (mx.min(x), my.min(y)));
+    let (max_x, max_y) = cells.iter().fold((i64::MIN, i64::MIN), |(mx, my), &(x, y)| (mx.max(x), my.max(y)));
+    let span = |lo: i64, hi: i64| (hi as i128 - lo as i128 + 1).max(0);
+    let (width, height) = if cells.is_empty() { (0, 0) } else { (span(min_x, max_x), span(min_y, max_y)) };
+    let (Ok(width), Ok(height)) = (i32::try_from(width), i32::try_from(height)) else {
+        return Err(GolError::parse("pattern", Location::Whole, format!("pattern spans {}x{} cells", width, height)));
+    };
+
+    Ok(LoadedPattern {
+        name,
+        rule,
+        cells: cells.into_iter().map(|(x, y)| ((x - min_x) as i32, (y - min_y) as i32)).collect(),
+        width,
+        height,
+        meta: Metadata::default(),
+    })
+}
+
+/// One entry of the macrocell node list
+enum Node {
+    Leaf([u8; 8]),                   // 8x8 block, one bitmask per row (bit 0 = leftmost)
+    Branch { level: u32, children: [usize; 4] }, // nw, ne, sw, se; 0 is the empty node
+}
+
+impl Node {
+    fn level(&self) -> u32 {
+        match self {
+            Node::Leaf(_) => 3,
+            Node::Branch { level, .. } => *level,
+        }
+    }
+}
+
+/// Parse Golly's macrocell format into a flat cell list
+pub fn parse_macrocell(text: &str) -> Result<LoadedPattern, GolError> {
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut population: Vec<u64> = Vec::new(); // Live cells under each node, counted once
+    let mut rule = None;
+    let mut name = String::new();
+
+    for (i, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        let err = |message: String| GolError::parse("macrocell", Location::Line(i + 1), message);
+        if i == 0 {
+            if !line.starts_with("[M2]") {
+                return Err(err("missing [M2] header".to_string()));
+            }
+            continue;
+        }
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix('#') {
+            let mut parts = rest.splitn(2, char::is_whitespace);
+            let tag = parts.next().unwrap_or("");
+            let value = parts.next().unwrap_or("").trim();
+            match tag {
+                "R" => rule = Some(value.to_string()),
+                "N" if name.is_empty() => name = value.to_string(),
+                _ => {}
+            }
+            continue;
+        }
+
+        let node = if line.starts_with(['.', '*', '$']) {
+            parse_leaf(line).map_err(err)?
+        } else {
+            parse_branch(line, nodes.len()).map_err(err)?
+        };
+        let cells = match &node {
+            Node::Leaf(rows) => rows.iter().map(|r| r.count_ones() as u64).sum(),
+            Node::Branch { level, children } => {
+                for &c in children.iter().filter(|&&c| c != 0) {
+                    let child_level = nodes[c - 1].level();
+                    if child_level != level - 1 {
+                        return Err(err(format!("node {} has level {}, expected {}", c, child_level, level - 1)));
+                    }
+                }
+                children.iter().filter(|&&c| c != 0).fold(0u64, |sum, &c| sum.saturating_add(population[c - 1]))
+            }
+        };
+        nodes.push(node);
+        population.push(cells);
+    }
+
+    let root = nodes.len();
+    if root == 0 {
+        return Err(GolError::parse("macrocell", Location::Whole, "no nodes in file"));
+    }
+    // Known before expanding, so a file of a few lines can't describe more cells than memory holds
+    if population[root - 1] > MAX_CELLS as u64 {
+        return Err(GolError::parse("macrocell", Location::Whole, format!(
+            "the pattern has {} live cells, more than the {} a pattern may have", population[root - 1], MAX_CELLS,
+        )));
+    }
+
+    let mut cells = Vec::with_capacity(population[root - 1] as usize);
+    let mut blocks = vec![None; nodes.len()];
+    collect(&Tree { nodes: &nodes, population: &population }, &mut blocks, root, 0, 0, &mut cells);
+    normalized(name, rule, cells)
+}
+
+/// Parse an 8x8 leaf such as `.*$..*$***$`
+fn parse_leaf(line: &str) -> Result<Node, String> {
+    let mut rows = [0u8; 8];
+    let (mut x, mut y) = (0usize, 0usize);
+    for ch in line.chars() {
+        match ch {
+            '$' => { x = 0; y += 1; }
+            '.' | '*' => {
+                if x >= 8 || y >= 8 {
+                    return Err("leaf node is larger than 8x8".to_string());
+                }
+                if ch == '*' { rows[y] |= 1 << x; }
+                x += 1;
+            }
+            _ => return Err(format!("unexpected '{}' in leaf node", ch)),
+        }
+    }
+    Ok(Node::Leaf(rows))
+}
+
+/// Parse `level nw ne sw se`, checking every reference points at an earlier node
+fn parse_branch(line: &str, defined: usize) -> Result<Node, String> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() != 5 {
+        return Err(format!("expected 'level nw ne sw se', got '{}'", line));
+    }
+    let number = |s: &str| s.parse::<usize>().map_err(|_| format!("invalid number '{}'", s));
+    let level = number(fields[0])?;
+    if !(4..=63).contains(&level) {
+        return Err(format!("unsupported node level {}", level));
+    }
+    let mut children = [0usize; 4];
+    for (slot, field) in children.iter_mut().zip(&fields[1..]) {
+        let index = number(field)?;
+        if index > defined {
+            return Err(format!("node {} referenced before it is defined", index));
+        }
+        *slot = index;
+    }
+    Ok(Node::Branch { level: level as u32, children })
+}
+
+/// Nodes up to this level (64x64 cells) are expanded once, then copied wherever they recur
+const MEMO_LEVEL: u32 = 6;
+
+/// A parsed node list with the live cell count under each node
+struct Tree<'a> {
+    nodes: &'a [Node],
+    population: &'a [u64],
+}
+
+/// Expand node `index` with its top-left corner at (x, y). Empty nodes are skipped, so a tree
+/// that reuses one empty node at every level costs nothing, and small nodes are taken from
+/// `blocks`, each filled the first time its node is met.
+fn collect(tree: &Tree, blocks: &mut [Option<Vec<(u8, u8)>>], index: usize, x: i64, y: i64, out: &mut Vec<(i64, i64)>) {
+    if index == 0 || tree.population[index - 1] == 0 {
+        return;
+    }
+    match &tree.nodes[index - 1] {
+        Node::Leaf(rows) => {
+            for (dy, &row) in rows.iter().enumerate() {
+                out.extend((0..8).filter(|dx| row & (1 << dx) != 0).map(|dx| (x + dx, y + dy as i64)));
+            }
+        }
+        Node::Branch { level, children } if *level <= MEMO_LEVEL => {
+            if blocks[index - 1].is_none() {
+                let mut cells = Vec::new();
+                for (child, cx, cy) in quadrants(*level, children, 0, 0) {
+                    collect(tree, blocks, child, cx, cy, &mut cells);
+                }
+                blocks[index - 1] = Some(cells.into_iter().map(|(cx, cy)| (cx as u8, cy as u8)).collect());
+            }
+            out.extend(blocks[index - 1].iter().flatten().map(|&(dx, dy)| (x + dx as i64, y + dy as i64)));
+        }
+        Node::Branch { level, children } => {
+            for (child, cx, cy) in quadrants(*level, children, x, y) {
+                collect(tree, blocks, child, cx, cy, out);
+            }
+        }
+    }
+}
+
+/// The children of a node at `level` with its corner at (x, y), each with its own corner
+fn quadrants(level: u32, children: &[usize; 4], x: i64, y: i64) -> [(usize, i64, i64); 4] {
+    let half = 1i64 << (level - 1);
+    let [nw, ne, sw, se] = *children;
+    [(nw, x, y), (ne, x + half, y), (sw, x, y + half), (se, x + half, y + half)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn malformed_patterns_say_where() {
+        match parse_pattern_text("#N Glider\nx = 3, y = 3\nbo$2bo$\n  3?!\n") {
+            Err(GolError::Parse { format: "RLE", at: Location::Column { line: 4, column: 4 }, .. }) => {}
+            other => panic!("a stray '?' read as {:?}", other.map(|p| p.name)),
+        }
+        match parse_rle("bo$2bo$3o!\n") {
+            Err(GolError::Parse { format: "RLE", at: Location::Line(1), .. }) => {}
+            other => panic!("RLE without a header read as {:?}", other.map(|p| p.name)),
+        }
+        match parse_pattern_text("<!DOCTYPE html>\n<html>") {
+            Err(GolError::Parse { format: "pattern", at: Location::Line(1), .. }) => {}
+            other => panic!("a web page read as {:?}", other.map(|p| p.name)),
+        }
+    }
+
+    #[test]
+    fn file_rules_are_compared_with_the_board() {
+        let highlife = Rule::parse("B36/S23").unwrap();
+        let rule_of = |header: &str| parse_rle(&format!("x = 1, y = 1{}\no!", header)).unwrap();
+        assert!(rule_of("").foreign_rule(Rule::LIFE).is_none());
+        for same in [", rule = B3/S23", ", rule = 23/3", ", rule = Life"] {
+            assert!(rule_of(same).foreign_rule(Rule::LIFE).is_none(), "{}", same);
+        }
+        assert!(matches!(rule_of(", rule = b36/s23").foreign_rule(Rule::LIFE), Some(Ok(rule)) if rule == highlife));
+        assert!(rule_of(", rule = B36/S23").foreign_rule(highlife).is_none());
+        // Life is foreign to a board running something else
+        assert!(matches!(rule_of(", rule = B3/S23").foreign_rule(highlife), Some(Ok(Rule::LIFE))));
+        for unusable in [", rule = B03/S23", ", rule = Wireworld"] {
+            assert!(matches!(rule_of(unusable).foreign_rule(Rule::LIFE), Some(Err(GolError::Invalid { what: "rule", .. }))), "{}", unusable);
+        }
+    }
+
+    #[test]
+    fn missing_and_unknown_files_are_refused() {
+        let missing = std::env::temp_dir().join("conways-game-of-life-no-such-pattern.rle");
+        match load_pattern_file(&missing) {
+            Err(GolError::Io { path, .. }) => assert_eq!(path, missing),
+            other => panic!("a missing file read as {:?}", other.map(|p| p.name)),
+        }
+        assert!(matches!(load_pattern_file("glider.gif".as_ref()), Err(GolError::Unsupported(_))));
+    }
+}
@@ -1,32 +1,201 @@
-use std::collections::HashSet;
-use crate::grid::Position;
-use crate::config::RANDOM_DENSITY;
-use macroquad::rand::gen_range;
+use std::cmp::Ordering;
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use crate::analysis;
+use crate::config::{CLASSIFY_MAX_CELLS, CLASSIFY_MAX_GENERATIONS, GRID_MAX, MAX_CELLS};
+use crate::error::GolError;
+use crate::grid::{Grid, Offset, Position, Topology};
+use crate::pattern_io::Metadata;
+use crate::rng::Rng;
+
+pub mod image;
 
 /// Context for pattern application with grid information
 pub struct PatternContext<'a> {
     pub cells: &'a mut HashSet<Position>,
     pub grid_width: i32,
     pub grid_height: i32,
-    pub wrap_world: bool,
+    pub topology: Topology,
+    pub anchor: (i32, i32),  // Point the transform rotates/mirrors around
+    pub transform: Transform,
+    pub seed: u64,          // Seed for patterns with random content
+    pub density: f32,       // Fill density for patterns with random content
+    pub max_cells: usize,   // Cells beyond this count are dropped and flagged
+    pub overflowed: bool,   // Set once the pattern tried to exceed `max_cells`
+    pub overhang: Overhang, // Cells dropped past a wall, and how far past it they reach
+}
+
+/// Error returned when a pattern produces more cells than the grid allows
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PatternTooLarge {
+    pub limit: usize,
+}
+
+impl fmt::Display for PatternTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "pattern too large for this grid (limit {} cells)", self.limit)
+    }
+}
+
+impl std::error::Error for PatternTooLarge {}
+
+/// How far a placement reaches past the board's walls, in cells beyond each edge, and how many
+/// of its cells land out there and would be lost
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Overhang {
+    pub cells: usize,
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl Overhang {
+    /// Count a cell at (x, y) that fell past a wall of a `width` x `height` board. Only walled
+    /// axes are measured: past a wrapping edge the cell just comes back on the other side.
+    pub fn add(&mut self, x: i32, y: i32, width: i32, height: i32, topology: Topology) {
+        self.cells += 1;
+        if !topology.wraps_x() {
+            self.left = self.left.max(-x);
+            self.right = self.right.max(x - (width - 1));
+        }
+        if !topology.wraps_y() {
+            self.top = self.top.max(-y);
+            self.bottom = self.bottom.max(y - (height - 1));
+        }
+    }
+
+    /// Whether any cell would be lost
+    pub fn clips(&self) -> bool {
+        self.cells > 0
+    }
+
+    /// The edges cells hang over, e.g. "left and top"
+    pub fn edges(&self) -> String {
+        let names: Vec<&str> = [(self.left, "left"), (self.top, "top"), (self.right, "right"), (self.bottom, "bottom")]
+            .into_iter()
+            .filter(|(n, _)| *n > 0)
+            .map(|(_, name)| name)
+            .collect();
+        match names.as_slice() {
+            [] => "no".to_string(),
+            [one] => one.to_string(),
+            [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+        }
+    }
+}
+
+/// What to do with a placement that hangs past a wall
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum OnClip {
+    Clip,    // Place it anyway, losing the cells past the wall
+    Expand,  // Grow the grid to take it
+    #[default]
+    Error,   // Refuse it
+}
+
+impl OnClip {
+    pub fn parse(name: &str) -> Result<OnClip, String> {
+        match name {
+            "clip" => Ok(OnClip::Clip),
+            "expand" => Ok(OnClip::Expand),
+            "error" => Ok(OnClip::Error),
+            _ => Err(format!("unknown clip policy '{}' (expected clip, expand or error)", name)),
+        }
+    }
+}
+
+impl fmt::Display for Overhang {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} cell{} past the {} edge{}", self.cells, if self.cells == 1 { "" } else { "s" }, self.edges(),
+            if [self.left, self.top, self.right, self.bottom].iter().filter(|n| **n > 0).count() == 1 { "" } else { "s" })
+    }
+}
+
+/// One of the 8 rotations/reflections of a pattern around its anchor
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Transform {
+    pub rotation: u8,  // Clockwise quarter turns, 0..4
+    pub flipped: bool, // Mirrored left-right before rotating
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform { rotation: 0, flipped: false };
+
+    /// All 8 symmetries of the square
+    pub fn all() -> [Transform; 8] {
+        let mut out = [Transform::IDENTITY; 8];
+        for (i, t) in out.iter_mut().enumerate() {
+            *t = Transform { rotation: (i % 4) as u8, flipped: i >= 4 };
+        }
+        out
+    }
+
+    /// Map an offset from the anchor
+    #[inline]
+    pub fn apply(&self, dx: i32, dy: i32) -> (i32, i32) {
+        let (x, y) = if self.flipped { (-dx, dy) } else { (dx, dy) };
+        match self.rotation % 4 {
+            0 => (x, y),
+            1 => (-y, x),
+            2 => (-x, -y),
+            _ => (y, -x),
+        }
+    }
+
+    /// This transform followed by a clockwise quarter turn
+    pub fn rotate_cw(self) -> Self {
+        Transform { rotation: (self.rotation + 1) % 4, ..self }
+    }
+
+    /// This transform followed by a counter-clockwise quarter turn
+    pub fn rotate_ccw(self) -> Self {
+        Transform { rotation: (self.rotation + 3) % 4, ..self }
+    }
+
+    /// This transform followed by a left-right mirror
+    pub fn flip(self) -> Self {
+        Transform { rotation: (4 - self.rotation) % 4, flipped: !self.flipped }
+    }
+
+    /// Short description for the HUD, e.g. "flip+rot90"
+    pub fn name(&self) -> String {
+        match (self.flipped, self.rotation) {
+            (false, 0) => "upright".to_string(),
+            (false, r) => format!("rot{}", r as u32 * 90),
+            (true, 0) => "flip".to_string(),
+            (true, r) => format!("flip+rot{}", r as u32 * 90),
+        }
+    }
+
+    /// The transform `name` gives, e.g. `flip+rot90`
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::all().into_iter().find(|t| t.name().eq_ignore_ascii_case(name.trim()))
+    }
 }
 
 impl<'a> PatternContext<'a> {
     /// Add a cell with edge wrapping if enabled
     pub fn add_cell(&mut self, x: i32, y: i32) {
-        let p = if self.wrap_world { 
-            // Wrap coordinates around edges
-            let mut nx = x % self.grid_width;
-            let mut ny = y % self.grid_height;
-            if nx < 0 { nx += self.grid_width; }
-            if ny < 0 { ny += self.grid_height; }
-            Position::new(nx, ny)
-        } else { 
-            Position::new(x, y) 
+        if self.cells.len() >= self.max_cells {
+            self.overflowed = true;
+            return;
+        }
+        let anchor = Position::new(self.anchor.0, self.anchor.1);
+        let Offset(dx, dy) = Position::new(x, y) - anchor;
+        let (dx, dy) = self.transform.apply(dx, dy);
+        let p = anchor + Offset(dx, dy);
+        let (w, h) = (self.grid_width, self.grid_height);
+        // Wrap coordinates around the edges that wrap; drop cells past a wall, noting how far out they were
+        let placed = match self.topology {
+            Topology::Torus | Topology::TorusShifted { .. } => Some(self.topology.wrap(p.x(), p.y(), w, h)),
+            Topology::Bounded => ((0..w).contains(&p.x()) && (0..h).contains(&p.y())).then_some(p),
+            Topology::CylinderX => (0..h).contains(&p.y()).then(|| Position::new(p.x().rem_euclid(w), p.y())),
+            Topology::CylinderY => (0..w).contains(&p.x()).then(|| Position::new(p.x(), p.y().rem_euclid(h))),
         };
-        
-        if self.wrap_world || ((0..self.grid_width).contains(&x) && (0..self.grid_height).contains(&y)) {
-            self.cells.insert(p);
+        match placed {
+            Some(placed) => { self.cells.insert(placed); }
+            None => self.overhang.add(p.x(), p.y(), w, h, self.topology),
         }
     }
 }
@@ -34,10 +203,370 @@ impl<'a> PatternContext<'a> {
 /// Pattern trait for all Conway's Game of Life patterns
 pub trait Pattern {
     /// Returns the name of the pattern
-    fn name(&self) -> &'static str;
+    fn name(&self) -> &str;
     
     /// Applies the pattern to the game state
     fn apply(&self, ctx: &mut PatternContext, x: i32, y: i32);
+
+    /// Group the pattern is listed under in pickers
+    fn category(&self) -> &str {
+        "Custom"
+    }
+
+    /// Whether the pattern covers the whole grid rather than sitting at a point
+    fn fills_grid(&self) -> bool {
+        false
+    }
+
+    /// Width and height of the pattern when known up front, used to reject patterns bigger than the grid
+    fn bounds(&self) -> Option<(i32, i32)> {
+        None
+    }
+
+    /// What the pattern does over time, when known; `PatternInfo::measure` derives it otherwise
+    fn interest(&self) -> Option<Interest> {
+        None
+    }
+
+    /// Where a run that starts with this pattern puts it
+    fn placement(&self) -> Placement {
+        Placement::Center
+    }
+
+    /// Smallest grid (width, height) the pattern has room to play out on, when it needs more
+    /// than its own size; the menu warns before starting on a smaller one
+    fn recommended_grid(&self) -> Option<(i32, i32)> {
+        None
+    }
+
+    /// Author, comments and offset from the pattern's file, which a board started from it keeps
+    /// for RLE exports
+    fn metadata(&self) -> Option<&Metadata> {
+        None
+    }
+}
+
+/// Where a pattern's bounding box goes when a run starts with it
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Placement {
+    Center,
+    UpperLeft { margin: i32 }, // Top-left corner this many cells in from the grid's
+    Offset(i32, i32),          // Centered, then shifted by this many cells
+}
+
+impl Placement {
+    /// Anchor to pass to `Pattern::apply` on a `width` x `height` grid. Patterns that fill the
+    /// grid are anchored at its center.
+    pub fn anchor(self, pattern: &dyn Pattern, width: i32, height: i32) -> (i32, i32) {
+        let Some((left_of, above, w, h)) = footprint(pattern) else { return (width / 2, height / 2) };
+        let (left, top) = match self {
+            Placement::Center => ((width - w) / 2, (height - h) / 2),
+            Placement::UpperLeft { margin } => (margin, margin),
+            Placement::Offset(dx, dy) => ((width - w) / 2 + dx, (height - h) / 2 + dy),
+        };
+        (left + left_of, top + above)
+    }
+}
+
+/// Bounding box of a pattern applied upright: how far its anchor sits right of and below the
+/// box's top-left corner, then the box's width and height. `None` for empty and grid-filling patterns.
+pub fn footprint(pattern: &dyn Pattern) -> Option<(i32, i32, i32, i32)> {
+    let cells = staged_upright(pattern).filter(|c| !c.is_empty())?;
+    let staged_at = GRID_MAX / 2;
+    let (min_x, max_x) = cells.iter().fold((i32::MAX, i32::MIN), |(lo, hi), c| (lo.min(c.0), hi.max(c.0)));
+    let (min_y, max_y) = cells.iter().fold((i32::MAX, i32::MIN), |(lo, hi), c| (lo.min(c.1), hi.max(c.1)));
+    Some((staged_at - min_x, staged_at - min_y, max_x - min_x + 1, max_y - min_y + 1))
+}
+
+/// A pattern's cells under `transform`, shifted so their bounding box starts at (0, 0). `None`
+/// for empty and grid-filling patterns.
+pub fn shape(pattern: &dyn Pattern, transform: Transform) -> Option<Vec<(i32, i32)>> {
+    let cells: Vec<(i32, i32)> = staged_upright(pattern)?.into_iter().map(|(x, y)| transform.apply(x, y)).collect();
+    let left = cells.iter().map(|c| c.0).min()?;
+    let top = cells.iter().map(|c| c.1).min()?;
+    Some(cells.into_iter().map(|(x, y)| (x - left, y - top)).collect())
+}
+
+/// Patterns laid out side by side on a grid, and the ones that didn't fit
+pub struct Layout<'a> {
+    pub placed: Vec<(Position, &'a dyn Pattern)>, // Anchor to pass to `apply` for each
+    pub skipped: Vec<&'a dyn Pattern>,
+}
+
+/// Lay patterns out in rows, left to right in the order given, with `spacing` empty cells
+/// between neighbours and from the grid's edges. A pattern that doesn't fit the rest of its
+/// row starts the next; one with no room left is skipped and the patterns after it still tried.
+pub fn layout_patterns<'a>(patterns: &[&'a dyn Pattern], grid: &Grid, spacing: i32) -> Layout<'a> {
+    let spacing = spacing.max(0);
+    let mut layout = Layout { placed: Vec::new(), skipped: Vec::new() };
+    let (mut x, mut y, mut row_height) = (spacing, spacing, 0);
+    for &pattern in patterns {
+        let Some((left_of, above, w, h)) = footprint(pattern) else {
+            layout.skipped.push(pattern);
+            continue;
+        };
+        let fits = |x: i32, y: i32| x + w + spacing <= grid.width && y + h + spacing <= grid.height;
+        let (left, top) = if fits(x, y) {
+            (x, y)
+        } else if row_height > 0 && fits(spacing, y + row_height + spacing) {
+            y += row_height + spacing;
+            row_height = 0;
+            (spacing, y)
+        } else {
+            layout.skipped.push(pattern);
+            continue;
+        };
+        layout.placed.push((Position::new(left + left_of, top + above), pattern));
+        x = left + w + spacing;
+        row_height = row_height.max(h);
+    }
+    layout
+}
+
+/// What a pattern does over time, for sorting the picker
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Interest {
+    StillLife,
+    Oscillator { period: u32 },
+    Spaceship { period: u32, shift: u32 }, // Moves `shift` cells every `period` generations
+    Methuselah { lifespan: u32 },          // Generations before it settles
+    Gun { period: u32 },                   // Generations between emitted spaceships
+}
+
+impl Interest {
+    /// Short description, e.g. "oscillator p15" or "spaceship c/4"
+    pub fn label(&self) -> String {
+        match *self {
+            Interest::StillLife => "still life".to_string(),
+            Interest::Oscillator { period } => format!("oscillator p{}", period),
+            Interest::Spaceship { period, shift: 1 } => format!("spaceship c/{}", period),
+            Interest::Spaceship { period, shift } => format!("spaceship {}c/{}", shift, period),
+            Interest::Methuselah { lifespan } => format!("methuselah {} gens", lifespan),
+            Interest::Gun { period } => format!("gun p{}", period),
+        }
+    }
+
+    /// Kind from static to growing, then the kind's own figure
+    fn rank(&self) -> (u8, u32) {
+        match *self {
+            Interest::StillLife => (0, 0),
+            Interest::Oscillator { period } => (1, period),
+            Interest::Spaceship { period, shift } => (2, period / shift.max(1)),
+            Interest::Methuselah { lifespan } => (3, lifespan),
+            Interest::Gun { period } => (4, period),
+        }
+    }
+}
+
+/// Size figures of a pattern, measured by placing it on an empty board
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PatternInfo {
+    pub cells: usize,
+    pub width: i32,
+    pub height: i32,
+    pub interest: Option<Interest>,
+}
+
+impl PatternInfo {
+    /// Place the pattern on an empty board and measure it; `None` for patterns that fill the grid,
+    /// whose size depends on the board. Interest the pattern doesn't declare is derived from how
+    /// its cells evolve, which is why this is worth caching for large files.
+    pub fn measure(pattern: &dyn Pattern) -> Option<Self> {
+        let cells = staged_upright(pattern)?;
+        let (min_x, max_x) = (cells.iter().map(|c| c.0).min()?, cells.iter().map(|c| c.0).max()?);
+        let (min_y, max_y) = (cells.iter().map(|c| c.1).min()?, cells.iter().map(|c| c.1).max()?);
+        let interest = pattern.interest()
+            .or_else(|| analysis::classify(&cells, CLASSIFY_MAX_GENERATIONS, CLASSIFY_MAX_CELLS));
+        Some(Self { cells: cells.len(), width: max_x - min_x + 1, height: max_y - min_y + 1, interest })
+    }
+}
+
+/// The pattern's cells placed upright on an empty board; `None` for patterns that fill the grid
+fn staged_upright(pattern: &dyn Pattern) -> Option<Vec<(i32, i32)>> {
+    if pattern.fills_grid() {
+        return None;
+    }
+    let mut staged = HashSet::new();
+    let center = GRID_MAX / 2;
+    let mut ctx = PatternContext {
+        cells: &mut staged,
+        grid_width: GRID_MAX,
+        grid_height: GRID_MAX,
+        topology: Topology::Bounded,
+        anchor: (center, center),
+        transform: Transform::IDENTITY,
+        seed: 0,
+        density: 0.0,
+        max_cells: MAX_CELLS,
+        overflowed: false,
+        overhang: Overhang::default(),
+    };
+    pattern.apply(&mut ctx, center, center);
+    Some(staged.iter().map(|p| (p.x(), p.y())).collect())
+}
+
+/// Which way a spaceship travels, or a gun fires, when placed upright: a step such as (1, 1)
+/// for down-right. Worked out by running the pattern; `None` for anything else.
+pub fn heading(pattern: &dyn Pattern) -> Option<(i32, i32)> {
+    let cells = staged_upright(pattern)?;
+    if cells.len() > CLASSIFY_MAX_CELLS {
+        return None;
+    }
+    let interest = pattern.interest()
+        .or_else(|| analysis::classify(&cells, CLASSIFY_MAX_GENERATIONS, CLASSIFY_MAX_CELLS))?;
+    let bounds = |cells: &[(i32, i32)]| {
+        let (xs, ys) = (cells.iter().map(|c| c.0), cells.iter().map(|c| c.1));
+        (xs.clone().min().unwrap_or(0), ys.clone().min().unwrap_or(0), xs.max().unwrap_or(0), ys.max().unwrap_or(0))
+    };
+    let (x0, y0, x1, y1) = bounds(&cells);
+    let mut board = cells;
+    match interest {
+        Interest::Spaceship { period, .. } => {
+            for _ in 0..period {
+                board = analysis::step_unbounded(&board);
+            }
+            let (x, y, _, _) = bounds(&board);
+            direction((x - x0) as f32, (y - y0) as f32)
+        }
+        Interest::Gun { period } => {
+            // Whatever has left the gun's box after a few volleys is what it fires
+            for _ in 0..period * HEADING_GUN_VOLLEYS {
+                board = analysis::step_unbounded(&board);
+            }
+            let outside = |&&(x, y): &&(i32, i32)| x < x0 - 2 || x > x1 + 2 || y < y0 - 2 || y > y1 + 2;
+            let shots: Vec<_> = board.iter().filter(outside).collect();
+            let n = shots.len().max(1) as f32;
+            let (cx, cy) = ((x0 + x1) as f32 / 2.0, (y0 + y1) as f32 / 2.0);
+            let (sx, sy) = shots.iter().fold((0.0, 0.0), |(sx, sy), &&(x, y)| (sx + x as f32 - cx, sy + y as f32 - cy));
+            direction(sx / n, sy / n)
+        }
+        _ => None,
+    }
+}
+
+/// Volleys a gun fires before `heading` looks at where its shots went
+const HEADING_GUN_VOLLEYS: u32 = 8;
+
+/// Unit step along a vector, counting an axis only if it is at least half the larger one
+fn direction(x: f32, y: f32) -> Option<(i32, i32)> {
+    let larger = x.abs().max(y.abs());
+    if larger == 0.0 {
+        return None;
+    }
+    let step = |v: f32| if v.abs() * 2.0 >= larger { v.signum() as i32 } else { 0 };
+    Some((step(x), step(y)))
+}
+
+/// Name of a heading, e.g. "down-left"
+pub fn heading_name(heading: (i32, i32)) -> &'static str {
+    match heading {
+        (0, -1) => "up",
+        (0, 1) => "down",
+        (-1, 0) => "left",
+        (1, 0) => "right",
+        (-1, -1) => "up-left",
+        (1, -1) => "up-right",
+        (-1, 1) => "down-left",
+        (1, 1) => "down-right",
+        _ => "nowhere",
+    }
+}
+
+/// Steer a pattern whose upright heading is `heading` with an arrow press (a unit step such as
+/// (0, 1) for down). The press sets that axis of the current heading, so Down then Left aims a
+/// glider down-left; a pattern that can't go that way is aimed straight along the arrow instead.
+/// A transform that keeps the current mirroring is preferred.
+pub fn steer(current: Transform, heading: (i32, i32), press: (i32, i32)) -> Option<Transform> {
+    let now = current.apply(heading.0, heading.1);
+    let combined = if press.0 != 0 { (press.0, now.1) } else { (now.0, press.1) };
+    let toward = |want: (i32, i32)| {
+        let mut fits = Transform::all().into_iter().filter(|t| t.apply(heading.0, heading.1) == want);
+        let first = fits.next()?;
+        Some(std::iter::once(first).chain(fits).find(|t| t.flipped == current.flipped).unwrap_or(first))
+    };
+    toward(combined).or_else(|| toward(press))
+}
+
+/// Orders offered by the pattern menu
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum PatternSort {
+    #[default]
+    Category,
+    Name,
+    Cells,
+    Size,     // Bounding-box area
+    Interest, // Still lifes, oscillators, spaceships, methuselahs, then guns
+}
+
+impl PatternSort {
+    /// The order after this one
+    pub fn next(self) -> Self {
+        match self {
+            PatternSort::Category => PatternSort::Name,
+            PatternSort::Name => PatternSort::Cells,
+            PatternSort::Cells => PatternSort::Size,
+            PatternSort::Size => PatternSort::Interest,
+            PatternSort::Interest => PatternSort::Category,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PatternSort::Category => "category",
+            PatternSort::Name => "name",
+            PatternSort::Cells => "cell count",
+            PatternSort::Size => "size",
+            PatternSort::Interest => "interest",
+        }
+    }
+
+    /// Whether the order needs measured figures rather than just names
+    pub fn needs_info(self) -> bool {
+        matches!(self, PatternSort::Cells | PatternSort::Size | PatternSort::Interest)
+    }
+
+    /// The figure shown next to a name in this order
+    pub fn metric(self, category: &str, info: Option<&PatternInfo>) -> String {
+        match (self, info) {
+            (PatternSort::Category | PatternSort::Name, _) => category.to_string(),
+            (PatternSort::Cells, Some(i)) => format!("{} cells", i.cells),
+            (PatternSort::Size, Some(i)) => format!("{}x{}", i.width, i.height),
+            (PatternSort::Interest, Some(PatternInfo { interest: Some(interest), .. })) => interest.label(),
+            (PatternSort::Interest, Some(_)) => "unclassified".to_string(),
+            (_, None) => "-".to_string(),
+        }
+    }
+
+    /// Compare two menu rows given as (name, category, figures). Rows without the figure
+    /// sorted on go last; ties fall back to category, then name.
+    pub fn compare(self, a: (&str, &str, Option<&PatternInfo>), b: (&str, &str, Option<&PatternInfo>)) -> Ordering {
+        let names = |a: (&str, &str, _), b: (&str, &str, _)| {
+            a.0.to_ascii_lowercase().cmp(&b.0.to_ascii_lowercase())
+        };
+        let figure = |row: (&str, &str, Option<&PatternInfo>)| -> Option<(u8, u64)> {
+            let info = row.2?;
+            match self {
+                PatternSort::Category | PatternSort::Name => None,
+                PatternSort::Cells => Some((0, info.cells as u64)),
+                PatternSort::Size => Some((0, info.width as u64 * info.height as u64)),
+                PatternSort::Interest => info.interest.map(|i| {
+                    let (kind, n) = i.rank();
+                    (kind, n as u64)
+                }),
+            }
+        };
+        let by_figure = match (figure(a), figure(b)) {
+            (Some(x), Some(y)) => x.cmp(&y),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        match self {
+            PatternSort::Name => names(a, b).then(a.1.cmp(b.1)),
+            PatternSort::Category => a.1.cmp(b.1).then(names(a, b)),
+            _ => by_figure.then(a.1.cmp(b.1)).then(names(a, b)),
+        }
+    }
 }
 
 /// A glider that moves diagonally across the grid
@@ -47,6 +576,14 @@ impl Pattern for GliderPattern {
     fn name(&self) -> &'static str {
         "Glider"
     }
+
+    fn category(&self) -> &str {
+        "Spaceships"
+    }
+
+    fn interest(&self) -> Option<Interest> {
+        Some(Interest::Spaceship { period: 4, shift: 1 })
+    }
     
     fn apply(&self, ctx: &mut PatternContext, x: i32, y: i32) {
         for (dx, dy) in [(1,0),(2,1),(0,2),(1,2),(2,2)] { 
@@ -55,26 +592,35 @@ impl Pattern for GliderPattern {
     }
 }
 
-/// Randomly distributes cells across the grid
-pub struct RandomPattern {
-    pub density: f32,
-}
-
-impl RandomPattern {
-    pub fn new(density: f32) -> Self {
-        Self { density }
+/// Check a fill density read from settings or the command line
+pub fn validate_density(density: f32) -> Result<f32, GolError> {
+    if density.is_nan() || !(0.0..=1.0).contains(&density) {
+        return Err(GolError::invalid("density", format!("density must be a number between 0 and 1, got {}", density)));
     }
+    Ok(density)
 }
 
+/// Randomly distributes cells across the grid at the context's density
+pub struct RandomPattern;
+
 impl Pattern for RandomPattern {
     fn name(&self) -> &'static str {
         "Random"
     }
+
+    fn category(&self) -> &str {
+        "Random"
+    }
     
+    fn fills_grid(&self) -> bool {
+        true
+    }
+
     fn apply(&self, ctx: &mut PatternContext, _x: i32, _y: i32) {
+        let mut rng = Rng::new(ctx.seed);
         for y in 0..ctx.grid_height {
             for x in 0..ctx.grid_width {
-                if gen_range(0.0, 1.0) < self.density {
+                if rng.next_f32() < ctx.density {
                     ctx.add_cell(x, y);
                 }
             }
@@ -89,6 +635,14 @@ impl Pattern for BlockPattern {
     fn name(&self) -> &'static str {
         "Block"
     }
+
+    fn category(&self) -> &str {
+        "Still lifes"
+    }
+
+    fn interest(&self) -> Option<Interest> {
+        Some(Interest::StillLife)
+    }
     
     fn apply(&self, ctx: &mut PatternContext, x: i32, y: i32) {
         for dx in 0..2 { 
@@ -106,6 +660,14 @@ impl Pattern for BlinkerPattern {
     fn name(&self) -> &'static str {
         "Blinker"
     }
+
+    fn category(&self) -> &str {
+        "Oscillators"
+    }
+
+    fn interest(&self) -> Option<Interest> {
+        Some(Interest::Oscillator { period: 2 })
+    }
     
     fn apply(&self, ctx: &mut PatternContext, x: i32, y: i32) {
         for dx in 0..3 { 
@@ -121,6 +683,14 @@ impl Pattern for BeaconPattern {
     fn name(&self) -> &'static str {
         "Beacon"
     }
+
+    fn category(&self) -> &str {
+        "Oscillators"
+    }
+
+    fn interest(&self) -> Option<Interest> {
+        Some(Interest::Oscillator { period: 2 })
+    }
     
     fn apply(&self, ctx: &mut PatternContext, x: i32, y: i32) {
         for (dx,dy) in [(0,0),(1,0),(0,1),(2,3),(3,2),(3,3)] { 
@@ -136,6 +706,18 @@ impl Pattern for RPentominoPattern {
     fn name(&self) -> &'static str {
         "R-pentomino"
     }
+
+    fn category(&self) -> &str {
+        "Methuselahs"
+    }
+
+    fn interest(&self) -> Option<Interest> {
+        Some(Interest::Methuselah { lifespan: 1103 })
+    }
+
+    fn recommended_grid(&self) -> Option<(i32, i32)> {
+        Some((160, 120))
+    }
     
     fn apply(&self, ctx: &mut PatternContext, x: i32, y: i32) {
         for (dx,dy) in [(1,0),(2,0),(0,1),(1,1),(1,2)] { 
@@ -151,6 +733,18 @@ impl Pattern for AcornPattern {
     fn name(&self) -> &'static str {
         "Acorn"
     }
+
+    fn category(&self) -> &str {
+        "Methuselahs"
+    }
+
+    fn interest(&self) -> Option<Interest> {
+        Some(Interest::Methuselah { lifespan: 5206 })
+    }
+
+    fn recommended_grid(&self) -> Option<(i32, i32)> {
+        Some((240, 160))
+    }
     
     fn apply(&self, ctx: &mut PatternContext, x: i32, y: i32) {
         for (dx,dy) in [(1,0),(3,1),(0,2),(1,2),(4,2),(5,2),(6,2)] { 
@@ -166,6 +760,14 @@ impl Pattern for DiehardPattern {
     fn name(&self) -> &'static str {
         "Diehard"
     }
+
+    fn category(&self) -> &str {
+        "Methuselahs"
+    }
+
+    fn interest(&self) -> Option<Interest> {
+        Some(Interest::Methuselah { lifespan: 130 })
+    }
     
     fn apply(&self, ctx: &mut PatternContext, x: i32, y: i32) {
         for (dx,dy) in [(6,0),(0,1),(1,1),(1,2),(5,2),(6,2),(7,2)] { 
@@ -181,6 +783,23 @@ impl Pattern for GosperGunPattern {
     fn name(&self) -> &'static str {
         "Gosper Gun"
     }
+
+    fn category(&self) -> &str {
+        "Guns"
+    }
+
+    fn interest(&self) -> Option<Interest> {
+        Some(Interest::Gun { period: 30 })
+    }
+
+    /// The gun fires down and to the right, so it starts in the top-left corner
+    fn placement(&self) -> Placement {
+        Placement::UpperLeft { margin: 2 }
+    }
+
+    fn recommended_grid(&self) -> Option<(i32, i32)> {
+        Some((100, 60))
+    }
     
     fn apply(&self, ctx: &mut PatternContext, x: i32, y: i32) {
         let pts = [
@@ -202,6 +821,14 @@ impl Pattern for PentadecathlonPattern {
     fn name(&self) -> &'static str {
         "Pentadecathlon"
     }
+
+    fn category(&self) -> &str {
+        "Oscillators"
+    }
+
+    fn interest(&self) -> Option<Interest> {
+        Some(Interest::Oscillator { period: 15 })
+    }
     
     fn apply(&self, ctx: &mut PatternContext, x: i32, y: i32) {
         for (dx,dy) in [(0,0),(1,0),(2,0),(3,0),(1,-1),(1,1),(4,-1),(4,1),(5,0),(6,0),(7,0),(8,0)] {
@@ -210,11 +837,86 @@ impl Pattern for PentadecathlonPattern {
     }
 }
 
+/// Named patterns offered by the pickers, kept sorted by category then name.
+/// Embedding crates can build their own and pass it to the UI.
+#[derive(Default)]
+pub struct PatternRegistry {
+    patterns: Vec<Box<dyn Pattern>>,
+}
+
+impl PatternRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a pattern, replacing any existing one with the same name (ignoring case)
+    pub fn register(&mut self, pattern: Box<dyn Pattern>) {
+        self.patterns.retain(|p| !p.name().eq_ignore_ascii_case(pattern.name()));
+        self.patterns.push(pattern);
+        self.patterns.sort_by(|a, b| (a.category(), a.name()).cmp(&(b.category(), b.name())));
+    }
+
+    /// Add a pattern after the others and return its index. Existing indices stay valid, so
+    /// armed stamps and stamp history keep pointing at the same patterns.
+    pub fn append(&mut self, pattern: Box<dyn Pattern>) -> usize {
+        self.patterns.push(pattern);
+        self.patterns.len() - 1
+    }
+
+    /// Look up a pattern by name, ignoring case
+    pub fn get(&self, name: &str) -> Option<&dyn Pattern> {
+        self.patterns.iter().find(|p| p.name().eq_ignore_ascii_case(name)).map(|p| p.as_ref())
+    }
+
+    /// Position in `iter` order of the pattern called `name`, ignoring case
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.patterns.iter().position(|p| p.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Pattern at a position in `iter` order
+    pub fn get_index(&self, index: usize) -> Option<&dyn Pattern> {
+        self.patterns.get(index).map(|p| p.as_ref())
+    }
+
+    /// Patterns ordered by category, then name, followed by any appended since
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Pattern> {
+        self.patterns.iter().map(|p| p.as_ref())
+    }
+
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Names of every pattern, comma separated, for error messages
+    pub fn names(&self) -> String {
+        self.iter().map(|p| p.name()).collect::<Vec<_>>().join(", ")
+    }
+}
+
+/// Registry holding every built-in pattern
+pub fn default_registry() -> PatternRegistry {
+    let mut registry = PatternRegistry::new();
+    #[allow(deprecated)]
+    for i in 0..PATTERN_COUNT {
+        registry.register(get_pattern_by_index(i));
+    }
+    registry
+}
+
+/// Total number of built-in patterns
+#[deprecated(note = "use `PatternRegistry::len` on `default_registry()`")]
+pub const PATTERN_COUNT: usize = 10;
+
 /// Get pattern instance by index for menu selection
+#[deprecated(note = "use `default_registry()` and `PatternRegistry::get`")]
 pub fn get_pattern_by_index(index: usize) -> Box<dyn Pattern> {
     match index {
         0 => Box::new(GliderPattern),
-        1 => Box::new(RandomPattern::new(RANDOM_DENSITY)),
+        1 => Box::new(RandomPattern),
         2 => Box::new(BlockPattern),
         3 => Box::new(BlinkerPattern),
         4 => Box::new(BeaconPattern),
@@ -227,3 +929,89 @@ pub fn get_pattern_by_index(index: usize) -> Box<dyn Pattern> {
     }
 }
 
+/// Look up a built-in pattern by name, ignoring case
+#[deprecated(note = "use `default_registry()` and `PatternRegistry::get`")]
+#[allow(deprecated)]
+pub fn pattern_by_name(name: &str) -> Option<Box<dyn Pattern>> {
+    (0..PATTERN_COUNT)
+        .map(get_pattern_by_index)
+        .find(|p| p.name().eq_ignore_ascii_case(name))
+}
+
+/// A registered pattern plus orientation, as placed with the ghost stamp
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Stamp {
+    pub pattern: usize, // Index into the registry the stamp was picked from
+    pub transform: Transform,
+    pub phase: u32,     // Generations the pattern is run on its own before placing
+    pub clear: bool,    // Erase the pattern's bounding box before placing it
+}
+
+impl Stamp {
+    pub fn new(pattern: usize) -> Self {
+        Self { pattern, transform: Transform::IDENTITY, phase: 0, clear: false }
+    }
+
+    /// Display name, e.g. "Glider (rot90)" or "Glider (rot90, phase 2, clear)"
+    pub fn name(&self, registry: &PatternRegistry) -> String {
+        let pattern = registry.get_index(self.pattern).map_or("?", |p| p.name());
+        let mut details = vec![self.transform.name().to_string()];
+        if self.phase > 0 {
+            details.push(format!("phase {}", self.phase));
+        }
+        if self.clear {
+            details.push("clear".to_string());
+        }
+        format!("{} ({})", pattern, details.join(", "))
+    }
+}
+
+/// Most recent distinct stamps, newest first
+pub struct StampHistory {
+    entries: VecDeque<Stamp>,
+    capacity: usize,
+}
+
+impl StampHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self { entries: VecDeque::with_capacity(capacity), capacity }
+    }
+
+    /// Record a stamp, moving it to the front if it was already present
+    pub fn push(&mut self, stamp: Stamp) {
+        self.entries.retain(|s| *s != stamp);
+        self.entries.push_front(stamp);
+        self.entries.truncate(self.capacity);
+    }
+
+    pub fn latest(&self) -> Option<Stamp> {
+        self.entries.front().copied()
+    }
+
+    pub fn get(&self, index: usize) -> Option<Stamp> {
+        self.entries.get(index).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Stamp> {
+        self.entries.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn density_must_lie_in_the_unit_range() {
+        for density in [0.0, 0.01, 0.5, 1.0] {
+            assert_eq!(validate_density(density).ok(), Some(density));
+        }
+        for density in [f32::NAN, -0.01, 1.01, f32::INFINITY, f32::NEG_INFINITY] {
+            assert!(matches!(validate_density(density), Err(GolError::Invalid { what: "density", .. })), "{} was accepted", density);
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! Seeding the board from a picture. With the `image-import` feature a PNG or BMP is decoded,
+//! composited on white, shrunk to fit the grid and thresholded: dark pixels become live cells.
+//! Without the feature an import fails at once, saying why.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::config::IMAGE_THRESHOLD;
+use crate::error::{self, GolError};
+use crate::grid::Position;
+use crate::pattern_io::{LoadedPattern, Metadata};
+
+/// Whether this build can import pictures
+pub const AVAILABLE: bool = cfg!(feature = "image-import");
+
+/// File extensions imported as pictures
+pub const EXTENSIONS: [&str; 2] = ["png", "bmp"];
+
+/// How pixels become cells
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ImageOptions {
+    pub threshold: f32, // Pixels darker than this (0 black, 1 white) are live
+    pub invert: bool,   // Light pixels are live instead
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        Self { threshold: IMAGE_THRESHOLD, invert: false }
+    }
+}
+
+impl ImageOptions {
+    fn live(&self, brightness: u8) -> bool {
+        ((brightness as f32) < self.threshold * 255.0) != self.invert
+    }
+}
+
+/// Whether `path` is named like a picture the importer reads
+pub fn is_image_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+}
+
+/// A picture shrunk to fit the grid, one brightness per cell (0 black, 255 white). Kept so the
+/// threshold can change without decoding the file again.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Picture {
+    pub width: i32,
+    pub height: i32,
+    brightness: Vec<u8>, // Row-major
+}
+
+impl Picture {
+    /// Decode a PNG or BMP and shrink it to fit `target_w` x `target_h` cells, keeping its
+    /// proportions. A smaller picture keeps one cell per pixel.
+    pub fn decode(bytes: &[u8], target_w: i32, target_h: i32) -> Result<Self, GolError> {
+        if target_w < 1 || target_h < 1 {
+            return Err(GolError::invalid("grid size", format!("can't fit a picture into {}x{} cells", target_w, target_h)));
+        }
+        #[cfg(not(feature = "image-import"))]
+        {
+            let _ = bytes;
+            Err(GolError::Unsupported("this build can't import pictures; rebuild with --features image-import".to_string()))
+        }
+        #[cfg(feature = "image-import")]
+        {
+            decode::picture(bytes, target_w, target_h)
+        }
+    }
+
+    /// Read a picture file and fit it as `decode` does, naming the file in any error
+    pub fn open(path: &Path, target_w: i32, target_h: i32) -> Result<Self, GolError> {
+        // Without the feature, say so rather than complain about the file
+        let bytes = if AVAILABLE { error::read(path)? } else { Vec::new() };
+        Self::decode(&bytes, target_w, target_h).map_err(|e| e.in_file(path))
+    }
+
+    /// Live cells with the picture's top-left corner at (0, 0)
+    pub fn cells(&self, options: ImageOptions) -> Vec<(i32, i32)> {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| options.live(self.brightness[(y * self.width + x) as usize]))
+            .collect()
+    }
+
+    /// Live cells centered on a `target_w` x `target_h` board, where placing `to_pattern` at the
+    /// board's center puts them
+    pub fn place(&self, target_w: i32, target_h: i32, options: ImageOptions) -> HashSet<Position> {
+        let (left, top) = (target_w / 2 - self.width / 2, target_h / 2 - self.height / 2);
+        self.cells(options).into_iter().map(|(x, y)| Position::new(left + x, top + y)).collect()
+    }
+
+    /// The picture as a pattern that can be placed or stamped like one read from a pattern file
+    pub fn to_pattern(&self, name: &str, options: ImageOptions) -> LoadedPattern {
+        LoadedPattern { name: name.to_string(), rule: None, cells: self.cells(options), width: self.width, height: self.height, meta: Metadata::default() }
+    }
+}
+
+/// Live cells for a picture fitted and centered on a `target_w` x `target_h` board
+pub fn from_image(bytes: &[u8], target_w: i32, target_h: i32, options: ImageOptions) -> Result<HashSet<Position>, GolError> {
+    Ok(Picture::decode(bytes, target_w, target_h)?.place(target_w, target_h, options))
+}
+
+/// Read a picture file as a pattern at most `target_w` x `target_h` cells, named after the file
+pub fn load(path: &Path, target_w: i32, target_h: i32, options: ImageOptions) -> Result<LoadedPattern, GolError> {
+    let picture = Picture::open(path, target_w, target_h)?;
+    let name = path.file_stem().and_then(|s| s.to_str()).unwrap_or("Picture");
+    Ok(picture.to_pattern(name, options))
+}
+
+#[cfg(feature = "image-import")]
+mod decode {
+    use std::io::Cursor;
+
+    use image::imageops::{self, FilterType};
+    use image::io::{Limits, Reader};
+    use image::{GrayImage, ImageFormat, Luma};
+
+    use super::Picture;
+    use crate::config::IMAGE_MAX_BYTES;
+    use crate::error::{GolError, Location};
+
+    pub fn picture(bytes: &[u8], target_w: i32, target_h: i32) -> Result<Picture, GolError> {
+        let malformed = |e: image::ImageError| GolError::parse("image", Location::Whole, e.to_string());
+        let mut reader = Reader::new(Cursor::new(bytes)).with_guessed_format().map_err(|e| GolError::parse("image", Location::Whole, e.to_string()))?;
+        match reader.format() {
+            Some(ImageFormat::Png | ImageFormat::Bmp) => {}
+            Some(format) => return Err(GolError::Unsupported(format!("{:?} pictures aren't supported; use PNG or BMP", format))),
+            None => return Err(GolError::parse("image", Location::Whole, "not a PNG or BMP picture")),
+        }
+        let mut limits = Limits::default();
+        limits.max_alloc = Some(IMAGE_MAX_BYTES);
+        reader.limits(limits);
+        let rgba = reader.decode().map_err(malformed)?.into_rgba8();
+
+        // Composite on white as brightness, one byte per pixel, before shrinking
+        let gray = GrayImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+            let [r, g, b, a] = rgba.get_pixel(x, y).0;
+            let luma = (299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000;
+            Luma([((luma * a as u32 + 255 * (255 - a as u32)) / 255) as u8])
+        });
+        drop(rgba);
+        let (w, h) = (gray.width().max(1), gray.height().max(1));
+        let scale = (target_w as f64 / w as f64).min(target_h as f64 / h as f64).min(1.0);
+        let fit = |side: u32, target: i32| ((side as f64 * scale).round() as u32).clamp(1, target as u32);
+        let (fw, fh) = (fit(w, target_w), fit(h, target_h));
+        // Area averaging for a big reduction, which keeps thin dark lines from vanishing
+        let fitted = if (fw, fh) == (w, h) {
+            gray
+        } else if w >= fw * 2 && h >= fh * 2 {
+            imageops::thumbnail(&gray, fw, fh)
+        } else {
+            imageops::resize(&gray, fw, fh, FilterType::Triangle)
+        };
+        Ok(Picture { width: fw as i32, height: fh as i32, brightness: fitted.into_raw() })
+    }
+}
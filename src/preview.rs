@@ -0,0 +1,220 @@
+//! Animated previews for the pattern menu. The highlighted pattern runs on a mini-grid of its
+//! own, sized from the period analyzer's findings to hold everything the pattern does: an
+//! oscillator's widest phase, or a spaceship's path across the box before the preview starts
+//! over. Nothing outside the pattern can reach it, so the grid behaves as an unbounded plane.
+//! The grid is two fixed buffers swapped each generation, so running it allocates nothing.
+//! Patterns that are too big, or show no period within the budget, are drawn once and hold still.
+//! A still picture wider than `PREVIEW_RASTER` cells is downsampled first, each of its cells
+//! covering a square block of the pattern, so drawing a huge file costs no more than a small one.
+
+use macroquad::color::Color;
+use macroquad::shapes::draw_rectangle;
+use macroquad::texture::{draw_texture, FilterMode, Image, Texture2D};
+
+use crate::analysis::{classify, step_unbounded};
+use crate::config::{PREVIEW_MAX_CELLS, PREVIEW_MAX_SIDE, PREVIEW_PERIOD_MAX, PREVIEW_RASTER, PREVIEW_SPEED, PREVIEW_TRAVEL};
+use crate::grid::NEIGHBOR_OFFSETS;
+use crate::patterns::{heading_name, Interest};
+use crate::render::Canvas;
+
+/// A pattern running, or held still, in the menu's preview box
+pub struct Preview {
+    pub label: String,     // What the pattern does, e.g. "spaceship c/4 heading down-right", or why it holds still
+    pub interest: Option<Interest>,
+    pub heading: Option<(i32, i32)>, // Unit step a spaceship travels in, e.g. (1, 1) for down-right
+    pub generation: u32,   // Since the preview last started over
+    pub scale: i32,        // Pattern cells across each cell of a downsampled still picture; 1 otherwise
+    width: i32,
+    height: i32,
+    start: Vec<bool>,      // Row-major cells of generation 0
+    cells: Vec<bool>,
+    next: Vec<bool>,
+    restart: Option<u32>,  // Generations before a spaceship is put back at its start
+    animated: bool,
+    due: f64,              // Seconds until the next generation
+    still: Option<Vec<(i32, i32)>>, // Cells of a pattern that holds still, in a grid its own size
+    texture: Option<Texture2D>, // Its picture, drawn the first time it is shown
+}
+
+impl Preview {
+    /// Preview of `cells`, a pattern placed anywhere on the plane
+    pub fn new(cells: &[(i32, i32)]) -> Self {
+        let (left, top) = (cells.iter().map(|c| c.0).min().unwrap_or(0), cells.iter().map(|c| c.1).min().unwrap_or(0));
+        let cells: Vec<(i32, i32)> = cells.iter().map(|&(x, y)| (x - left, y - top)).collect();
+        let size = (cells.iter().map(|c| c.0 + 1).max().unwrap_or(0), cells.iter().map(|c| c.1 + 1).max().unwrap_or(0));
+        if cells.len() > PREVIEW_MAX_CELLS || size.0.max(size.1) > PREVIEW_MAX_SIDE {
+            return Self::still(cells, size, None, "too large to animate".to_string());
+        }
+        let Some(interest) = classify(&cells, PREVIEW_PERIOD_MAX, PREVIEW_MAX_CELLS) else {
+            return Self::still(cells, size, None, format!("no period within {} generations", PREVIEW_PERIOD_MAX));
+        };
+        let period = match interest {
+            Interest::Oscillator { period } | Interest::Spaceship { period, .. } => period,
+            _ => return Self::still(cells, size, Some(interest), interest.label()),
+        };
+
+        // One period on the plane gives the widest reach of any phase and, for a spaceship, the
+        // step it takes each period
+        let (mut reach, mut board) = ((0, 0, size.0, size.1), cells.clone());
+        for _ in 0..period {
+            board = step_unbounded(&board);
+            for &(x, y) in &board {
+                reach = (reach.0.min(x), reach.1.min(y), reach.2.max(x + 1), reach.3.max(y + 1));
+            }
+        }
+        let step = (board.iter().map(|c| c.0).min().unwrap_or(0), board.iter().map(|c| c.1).min().unwrap_or(0));
+        let moving = step != (0, 0);
+        // Enough periods to cross the box, ending on a whole period so the start lines up again
+        let laps = if moving { PREVIEW_TRAVEL.div_euclid(step.0.abs().max(step.1.abs())).max(1) } else { 0 };
+        let (x0, y0) = (reach.0.min(reach.0 + laps * step.0) - 1, reach.1.min(reach.1 + laps * step.1) - 1);
+        let (x1, y1) = (reach.2.max(reach.2 + laps * step.0) + 1, reach.3.max(reach.3 + laps * step.1) + 1);
+        let (width, height) = (x1 - x0, y1 - y0);
+        let mut start = vec![false; (width * height) as usize];
+        for &(x, y) in &cells {
+            start[((y - y0) * width + (x - x0)) as usize] = true;
+        }
+        let heading = moving.then_some((step.0.signum(), step.1.signum()));
+        let label = match heading {
+            Some(h) => format!("{} heading {}", interest.label(), heading_name(h)),
+            None => interest.label(),
+        };
+        Self {
+            label,
+            interest: Some(interest),
+            heading,
+            generation: 0,
+            width,
+            height,
+            cells: start.clone(),
+            next: start.clone(),
+            start,
+            scale: 1,
+            restart: moving.then_some(laps as u32 * period),
+            animated: period > 1 || moving,
+            due: 1.0 / PREVIEW_SPEED,
+            still: None,
+            texture: None,
+        }
+    }
+
+    fn still(cells: Vec<(i32, i32)>, size: (i32, i32), interest: Option<Interest>, label: String) -> Self {
+        let scale = (size.0.max(size.1) + PREVIEW_RASTER - 1) / PREVIEW_RASTER;
+        let (cells, size) = if scale > 1 { downsample(&cells, size, scale) } else { (cells, size) };
+        Self {
+            label,
+            interest,
+            heading: None,
+            generation: 0,
+            scale: scale.max(1),
+            width: size.0,
+            height: size.1,
+            start: Vec::new(),
+            cells: Vec::new(),
+            next: Vec::new(),
+            restart: None,
+            animated: false,
+            due: 0.0,
+            still: Some(cells),
+            texture: None,
+        }
+    }
+
+    pub fn animated(&self) -> bool {
+        self.animated
+    }
+
+    /// Cells across and down the mini-grid, or the pattern for one that holds still
+    pub fn size(&self) -> (i32, i32) {
+        (self.width, self.height)
+    }
+
+    /// Let `dt` seconds pass, stepping at `PREVIEW_SPEED`
+    pub fn update(&mut self, dt: f64) {
+        if !self.animated {
+            return;
+        }
+        self.due -= dt;
+        // After a stall, such as the window losing focus, carry on rather than catch up
+        if self.due <= 0.0 {
+            self.step();
+            self.due = (self.due + 1.0 / PREVIEW_SPEED).max(0.0);
+        }
+    }
+
+    /// One generation on the mini-grid, or back to the start once a spaceship has crossed it
+    pub fn step(&mut self) {
+        if !self.animated {
+            return;
+        }
+        if self.restart == Some(self.generation) {
+            self.cells.copy_from_slice(&self.start);
+            self.generation = 0;
+            return;
+        }
+        let (w, h) = (self.width, self.height);
+        for y in 0..h {
+            for x in 0..w {
+                let n = NEIGHBOR_OFFSETS.iter()
+                    .filter(|&&(dx, dy)| {
+                        let (nx, ny) = (x + dx, y + dy);
+                        nx >= 0 && ny >= 0 && nx < w && ny < h && self.cells[(ny * w + nx) as usize]
+                    })
+                    .count();
+                let i = (y * w + x) as usize;
+                self.next[i] = n == 3 || (n == 2 && self.cells[i]);
+            }
+        }
+        std::mem::swap(&mut self.cells, &mut self.next);
+        self.generation += 1;
+    }
+
+    /// Whether the cell at column `x`, row `y` of the mini-grid is alive; in a downsampled picture,
+    /// whether any cell of that block is
+    pub fn alive(&self, x: i32, y: i32) -> bool {
+        match &self.still {
+            Some(cells) => cells.contains(&(x, y)),
+            None => (0..self.width).contains(&x) && (0..self.height).contains(&y) && self.cells[(y * self.width + x) as usize],
+        }
+    }
+
+    /// Draw the pattern centered in a `side` x `side` box with its top-left corner at (x, y)
+    pub fn draw(&mut self, x: f32, y: f32, side: f32, color: Color) {
+        let (w, h) = (self.width.max(1) as f32, self.height.max(1) as f32);
+        if let Some(cells) = &self.still {
+            let texture = self.texture.get_or_insert_with(|| {
+                let px = side.max(1.0) as u16;
+                let mut image = Image::gen_image_color(px, px, Color::new(0.0, 0.0, 0.0, 0.0));
+                let cell = side / w.max(h);
+                let (ox, oy) = ((side - w * cell) / 2.0, (side - h * cell) / 2.0);
+                for &(cx, cy) in cells {
+                    // Each cell covers at least a pixel, however far the pattern is shrunk
+                    image.fill_rect(ox + cx as f32 * cell, oy + cy as f32 * cell, cell.max(1.0), cell.max(1.0), color);
+                }
+                let texture = Texture2D::from_image(&image);
+                texture.set_filter(FilterMode::Nearest);
+                texture
+            });
+            draw_texture(texture, x, y, Color::new(1.0, 1.0, 1.0, 1.0));
+            return;
+        }
+        let cell = (side / w.max(h)).floor().max(1.0);
+        let (ox, oy) = (x + (side - w * cell) / 2.0, y + (side - h * cell) / 2.0);
+        let gap = if cell >= 4.0 { 1.0 } else { 0.0 };
+        for (i, _) in self.cells.iter().enumerate().filter(|(_, alive)| **alive) {
+            let (cx, cy) = ((i as i32 % self.width) as f32, (i as i32 / self.width) as f32);
+            draw_rectangle(ox + cx * cell, oy + cy * cell, cell - gap, cell - gap, color);
+        }
+    }
+}
+
+/// `cells` of a pattern `size` cells across, with each `scale` x `scale` block that has any live
+/// cell as one cell, and the size of the result
+fn downsample(cells: &[(i32, i32)], size: (i32, i32), scale: i32) -> (Vec<(i32, i32)>, (i32, i32)) {
+    let (w, h) = ((size.0 + scale - 1) / scale, (size.1 + scale - 1) / scale);
+    let mut covered = vec![false; (w * h) as usize];
+    for &(x, y) in cells {
+        covered[((y / scale) * w + x / scale) as usize] = true;
+    }
+    let blocks = covered.iter().enumerate().filter(|(_, c)| **c).map(|(i, _)| (i as i32 % w, i as i32 / w)).collect();
+    (blocks, (w, h))
+}
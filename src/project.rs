@@ -0,0 +1,283 @@
+//! Project files (`.golproj`): one file holding a whole board as it was saved, namely the cells
+//! and edges, rule, seed and density, notes, bookmarks, speed schedule, any recorded history and
+//! the settings. It is an uncompressed zip, so any archive tool can list and extract it, with a
+//! `manifest.txt` naming the format version, the board as a `.gold` delta log and the settings
+//! file as it stood. Plain `.gold` saves from before projects open as projects too, keeping the
+//! current rule, seed and settings for what they didn't store.
+
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::config::PROJECT_FORMAT;
+use crate::deltas::DeltaLog;
+use crate::error::{self, GolError, Location};
+use crate::grid::{Grid, Position};
+use crate::rule::Rule;
+use crate::settings::Settings;
+use crate::simulation::Simulation;
+
+/// File extension of a project
+pub const EXTENSION: &str = "golproj";
+
+const MANIFEST: &str = "manifest.txt";
+const BOARD: &str = "board.gold";
+const SETTINGS: &str = "settings.txt";
+
+/// A saved board and everything that goes with it. The manifest values and the settings are
+/// optional: an older project or a plain `.gold` save may not have them, and the board that opens
+/// it keeps its own.
+#[derive(Clone, Debug)]
+pub struct Project {
+    pub name: Option<String>,       // What the run started from, e.g. a pattern name
+    pub rule: Option<Rule>,
+    pub seed: Option<u64>,
+    pub density: Option<f32>,
+    pub settings: Option<Settings>, // The settings file when it was saved; its hotbar, edge rate and margin apply to the board
+    pub log: DeltaLog,              // The board at its last generation, with bookmarks, notes, schedule and history
+    pub migrated: bool,             // Read from a plain `.gold` save
+}
+
+impl Project {
+    /// Save `sim` as it stands. `log` holds its history if it was being recorded, and carries the
+    /// bookmarks, notes and speed schedule to keep with it.
+    pub fn capture(sim: &Simulation, name: &str, log: DeltaLog, settings: Settings) -> Self {
+        Self {
+            name: Some(name.to_string()),
+            rule: Some(sim.grid.rule),
+            seed: Some(sim.seed),
+            density: Some(sim.density),
+            settings: Some(settings),
+            log,
+            migrated: false,
+        }
+    }
+
+    /// The live cells at the saved generation
+    pub fn board(&self) -> HashSet<Position> {
+        self.log.reconstruct(self.log.last_generation()).unwrap_or_default()
+    }
+
+    /// Put the saved board on `sim`: grid, edges and cells at the saved generation, and the rule,
+    /// seed, density, edge rate and margin where the project has them
+    pub fn restore(&self, sim: &mut Simulation) -> Result<(), GolError> {
+        if let Some(rule) = self.rule {
+            sim.grid.rule = rule.runnable()?;
+        }
+        sim.grid = Grid { rule: sim.grid.rule, ..self.log.grid() };
+        sim.seed = self.seed.unwrap_or(sim.seed);
+        sim.density = self.density.unwrap_or(sim.density);
+        if let Some(settings) = &self.settings {
+            (sim.edge_rate, sim.lethal_margin) = (settings.edge_rate, settings.lethal_margin);
+        }
+        sim.restore(self.board(), self.log.last_generation());
+        Ok(())
+    }
+
+    /// Optional parts this project lacks, for telling the user what was kept from the current board
+    pub fn missing(&self) -> Vec<&'static str> {
+        [("name", self.name.is_none()), ("rule", self.rule.is_none()), ("seed", self.seed.is_none()),
+            ("density", self.density.is_none()), ("settings", self.settings.is_none())]
+            .into_iter()
+            .filter_map(|(part, missing)| missing.then_some(part))
+            .collect()
+    }
+
+    /// The manifest: `key = value` lines, like the settings file
+    fn manifest(&self) -> String {
+        let mut out = format!("# Conway's Game of Life project\nformat = {}\nprogram = {}\n", PROJECT_FORMAT, env!("CARGO_PKG_VERSION"));
+        if let Some(name) = &self.name {
+            let _ = writeln!(out, "name = {}", name.replace(['\r', '\n'], " "));
+        }
+        if let Some(rule) = self.rule {
+            let _ = writeln!(out, "rule = {}", rule);
+        }
+        if let Some(seed) = self.seed {
+            let _ = writeln!(out, "seed = {}", seed);
+        }
+        if let Some(density) = self.density {
+            let _ = writeln!(out, "density = {}", density);
+        }
+        let _ = writeln!(out, "generation = {}", self.log.last_generation());
+        out
+    }
+
+    /// Encode as a zip of the manifest, the board and the settings
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut entries = vec![(MANIFEST, self.manifest().into_bytes()), (BOARD, self.log.to_bytes())];
+        if let Some(settings) = &self.settings {
+            entries.push((SETTINGS, settings.to_text().into_bytes()));
+        }
+        zip(&entries)
+    }
+
+    /// Decode a project, or a plain `.gold` save as a project without a manifest or settings.
+    /// A project needs its manifest and board; manifest values and settings it lacks are left out.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, GolError> {
+        if bytes.starts_with(b"GOLD") {
+            let log = DeltaLog::from_bytes(bytes)?;
+            return Ok(Self { name: None, rule: None, seed: None, density: None, settings: None, log, migrated: true });
+        }
+        if !bytes.starts_with(LOCAL_HEADER) {
+            return Err(GolError::parse("project", Location::Offset(0), "not a project or a .gold save"));
+        }
+        let entries = unzip(bytes)?;
+        let entry = |name: &str| entries.iter().find(|(n, _)| n == name).map(|(_, data)| data.as_slice());
+        let text = |name: &'static str, data: &[u8]| {
+            std::str::from_utf8(data).map(str::to_string).map_err(|_| GolError::parse("project", Location::Whole, format!("{} isn't text", name)))
+        };
+        let manifest = text(MANIFEST, entry(MANIFEST).ok_or_else(|| missing_entry(MANIFEST))?)?;
+        error::check_key_values("project manifest", &manifest)?;
+        let values: Vec<(&str, &str)> = manifest.lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .collect();
+        let value = |key: &str| values.iter().find(|(k, _)| *k == key).map(|(_, v)| *v);
+        let bad = |key: &str, raw: &str| GolError::parse("project manifest", Location::Whole, format!("bad {} '{}'", key, raw));
+        let format: u32 = match value("format") {
+            Some(raw) => raw.parse().map_err(|_| bad("format", raw))?,
+            None => return Err(GolError::parse("project manifest", Location::Whole, "no format version")),
+        };
+        if format > PROJECT_FORMAT {
+            return Err(GolError::Unsupported(format!(
+                "this project is format {}, newer than the {} this version reads; update to open it", format, PROJECT_FORMAT,
+            )));
+        }
+        let rule = value("rule").map(Rule::parse).transpose()?;
+        let seed = value("seed").map(|raw| raw.parse().map_err(|_| bad("seed", raw))).transpose()?;
+        let density = value("density")
+            .map(|raw| raw.parse::<f32>().ok().filter(|d| (0.0..=1.0).contains(d)).ok_or_else(|| bad("density", raw)))
+            .transpose()?;
+        let log = DeltaLog::from_bytes(entry(BOARD).ok_or_else(|| missing_entry(BOARD))?)?;
+        let settings = entry(SETTINGS).map(|data| text(SETTINGS, data)).transpose()?.map(|text| Settings::parse(&text));
+        Ok(Self {
+            name: value("name").filter(|n| !n.is_empty()).map(str::to_string),
+            rule,
+            seed,
+            density,
+            settings,
+            log,
+            migrated: false,
+        })
+    }
+
+    /// Write the project to `path`
+    pub fn save(&self, path: &Path) -> Result<(), GolError> {
+        error::write(path, self.to_bytes())
+    }
+
+    /// Read a project or a plain `.gold` save from `path`, naming the file in any error
+    pub fn open(path: &Path) -> Result<Self, GolError> {
+        Self::from_bytes(&error::read(path)?).map_err(|e| e.in_file(path))
+    }
+}
+
+/// Whether `path` looks like something `Project::open` reads, by its extension
+pub fn is_project_file(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case(EXTENSION) || e.eq_ignore_ascii_case("gold"))
+}
+
+fn missing_entry(name: &str) -> GolError {
+    GolError::parse("project", Location::Whole, format!("no {} in the archive", name))
+}
+
+const LOCAL_HEADER: &[u8; 4] = b"PK\x03\x04";
+const CENTRAL_HEADER: &[u8; 4] = b"PK\x01\x02";
+const END_OF_DIRECTORY: &[u8; 4] = b"PK\x05\x06";
+const DOS_DATE: u16 = 0x21; // 1980-01-01, so the same project always encodes to the same bytes
+
+/// A zip archive with each entry stored as is
+pub fn zip(entries: &[(&str, Vec<u8>)]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut directory = Vec::new();
+    for (name, data) in entries {
+        let offset = out.len() as u32;
+        let mut header = Vec::new();
+        header.extend_from_slice(&10u16.to_le_bytes()); // Version needed: 1.0, stored
+        header.extend_from_slice(&0u16.to_le_bytes()); // Flags
+        header.extend_from_slice(&0u16.to_le_bytes()); // Method: stored
+        header.extend_from_slice(&0u16.to_le_bytes()); // Time
+        header.extend_from_slice(&DOS_DATE.to_le_bytes());
+        header.extend_from_slice(&crc32(data).to_le_bytes());
+        header.extend_from_slice(&(data.len() as u32).to_le_bytes()); // Compressed size
+        header.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes()); // Extra field length
+        out.extend_from_slice(LOCAL_HEADER);
+        out.extend_from_slice(&header);
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(data);
+        directory.extend_from_slice(CENTRAL_HEADER);
+        directory.extend_from_slice(&20u16.to_le_bytes()); // Version made by
+        directory.extend_from_slice(&header);
+        directory.extend_from_slice(&[0; 10]); // Comment length, disk, internal and external attributes
+        directory.extend_from_slice(&offset.to_le_bytes());
+        directory.extend_from_slice(name.as_bytes());
+    }
+    let start = out.len() as u32;
+    out.extend_from_slice(&directory);
+    out.extend_from_slice(END_OF_DIRECTORY);
+    out.extend_from_slice(&[0; 4]); // This disk and the directory's disk
+    out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+    out.extend_from_slice(&start.to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes()); // Comment length
+    out
+}
+
+/// The entries of a zip archive, by name. Only stored entries are read: projects are never
+/// compressed, and one that was recompressed by an archive tool is refused with a reason.
+pub fn unzip(bytes: &[u8]) -> Result<Vec<(String, Vec<u8>)>, GolError> {
+    let bad = |at: usize, message: &str| GolError::parse("project", Location::Offset(at), message);
+    let u16_at = |at: usize| bytes.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as usize).ok_or_else(|| bad(at, "cut off"));
+    let u32_at = |at: usize| bytes.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])).ok_or_else(|| bad(at, "cut off"));
+    // The end record is last, after a comment of up to 64 KiB
+    let end = (0..bytes.len().saturating_sub(21))
+        .rev()
+        .take(u16::MAX as usize + 1)
+        .find(|&at| bytes[at..].starts_with(END_OF_DIRECTORY))
+        .ok_or_else(|| bad(bytes.len(), "no zip directory; the file may be cut off"))?;
+    let count = u16_at(end + 10)?;
+    let mut at = u32_at(end + 16)? as usize;
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        if !bytes.get(at..).is_some_and(|b| b.starts_with(CENTRAL_HEADER)) {
+            return Err(bad(at, "damaged zip directory"));
+        }
+        let method = u16_at(at + 10)?;
+        let crc = u32_at(at + 16)?;
+        let size = u32_at(at + 20)? as usize;
+        let name_len = u16_at(at + 28)?;
+        let skip = u16_at(at + 30)? + u16_at(at + 32)?;
+        let local = u32_at(at + 42)? as usize;
+        let name = bytes.get(at + 46..at + 46 + name_len).ok_or_else(|| bad(at, "cut off"))?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        if method != 0 {
+            return Err(bad(at, &format!("{} is compressed; projects are saved uncompressed", name)));
+        }
+        if !bytes.get(local..).is_some_and(|b| b.starts_with(LOCAL_HEADER)) {
+            return Err(bad(local, "damaged zip entry"));
+        }
+        let data_at = local + 30 + u16_at(local + 26)? + u16_at(local + 28)?;
+        let data = bytes.get(data_at..data_at + size).ok_or_else(|| bad(data_at, &format!("{} is cut off", name)))?;
+        if crc32(data) != crc {
+            return Err(bad(data_at, &format!("{} is damaged (checksum mismatch)", name)));
+        }
+        entries.push((name, data.to_vec()));
+        at += 46 + name_len + skip;
+    }
+    Ok(entries)
+}
+
+/// The CRC-32 zip checks entries with
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
@@ -0,0 +1,366 @@
+//! Personal records kept across sessions: the longest-lived soup at each density, the highest
+//! peak population, the most generations in one run and the lifetime total. They are saved in
+//! records.txt beside the settings file under a format version, and each record carries the
+//! setup needed to start its run again.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::analysis;
+use crate::config::{RECORD_REPLAY_MAX, SUMMARY_PERIOD_MAX};
+use crate::error::{self, GolError, Kept};
+use crate::grid::{board_hash, Topology};
+use crate::rule::Rule;
+use crate::settings::Settings;
+use crate::simulation::Simulation;
+
+/// Format version written to the records file. A file from a newer version is read as far as
+/// this one understands it and never overwritten.
+pub const RECORDS_VERSION: u32 = 1;
+
+/// How a run started: enough to start it again
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunSetup {
+    pub pattern: String,
+    pub seed: u64,
+    pub density: f32,
+    pub width: i32,
+    pub height: i32,
+    pub topology: Topology,
+    pub rule: Rule,
+}
+
+impl RunSetup {
+    /// The generation 0 of a run of `sim`, or `None` while it is still being set up
+    pub fn of(sim: &Simulation, pattern: &str) -> Option<Self> {
+        let initial = sim.initial.as_ref()?;
+        Some(Self {
+            pattern: pattern.to_string(),
+            seed: initial.seed,
+            density: initial.density,
+            width: initial.grid.width,
+            height: initial.grid.height,
+            topology: initial.grid.topology,
+            rule: initial.grid.rule,
+        })
+    }
+
+    /// Whether the run started from a random soup
+    pub fn is_soup(&self) -> bool {
+        self.pattern == "Random"
+    }
+
+    /// The soup this setup starts from, at generation 0
+    pub fn soup(&self) -> Simulation {
+        let mut sim = Simulation::new(self.width, self.height);
+        sim.grid.topology = self.topology;
+        sim.grid.rule = self.rule;
+        sim.seed = self.seed;
+        sim.density = self.density;
+        sim.random_fill(self.density);
+        sim
+    }
+
+    /// Parse the `key=value` fields written by `Display`; the pattern name takes the rest of the line
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let bad = || format!("bad run setup '{}'", text.trim());
+        let (fields, pattern) = text.split_once("pattern=").ok_or_else(bad)?;
+        let mut run = Self {
+            pattern: pattern.trim().to_string(),
+            seed: 0,
+            density: 0.0,
+            width: 0,
+            height: 0,
+            topology: Topology::Bounded,
+            rule: Rule::LIFE,
+        };
+        for field in fields.split_whitespace() {
+            let (key, value) = field.split_once('=').ok_or_else(bad)?;
+            match key {
+                "seed" => run.seed = value.parse().map_err(|_| bad())?,
+                "density" => run.density = value.parse().map_err(|_| bad())?,
+                "size" => {
+                    let (w, h) = value.split_once('x').ok_or_else(bad)?;
+                    (run.width, run.height) = (w.parse().map_err(|_| bad())?, h.parse().map_err(|_| bad())?);
+                }
+                "topology" => run.topology = Topology::parse(value)?,
+                "wrap" => run.topology = Topology::from_wrap(value.parse().map_err(|_| bad())?), // Written before cylinders
+                "shift" => run.topology = run.topology.with_shift_of(Topology::parse_shift(value)?), // Written before shifts joined the topology
+                "rule" => run.rule = Rule::parse(value)?,
+                _ => {} // Written by a newer version
+            }
+        }
+        if run.width < 1 || run.height < 1 || run.pattern.is_empty() {
+            return Err(bad());
+        }
+        Ok(run)
+    }
+}
+
+impl fmt::Display for RunSetup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "seed={} density={} size={}x{} topology={} rule={} pattern={}",
+            self.seed, self.density, self.width, self.height, self.topology, self.rule, self.pattern,
+        )
+    }
+}
+
+/// One record: how far it went and the run that got there
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    pub value: u64,
+    pub run: RunSetup,
+}
+
+impl Record {
+    fn parse(text: &str) -> Result<Self, String> {
+        let (value, run) = text.split_once('|').ok_or_else(|| format!("bad record '{}'", text.trim()))?;
+        let value = value.trim().parse().map_err(|_| format!("bad record value '{}'", value.trim()))?;
+        Ok(Self { value, run: RunSetup::parse(run)? })
+    }
+
+    /// Where the run started, for the records screen: "Random, seed 42, 128x72 torus"
+    pub fn origin(&self) -> String {
+        let run = &self.run;
+        let edges = match run.topology {
+            Topology::Bounded => String::new(),
+            topology => format!(" {}", topology),
+        };
+        format!("{}, seed {}, {}x{}{}", run.pattern, run.seed, run.width, run.height, edges)
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} | {}", self.value, self.run)
+    }
+}
+
+/// What a finished run brings to the records
+#[derive(Clone, Debug)]
+pub struct RunResult {
+    pub run: RunSetup,
+    pub peak: usize,
+    pub generations: u64,           // Generations stepped during the run
+    pub soup_lifespan: Option<u64>, // From `soup_lifespan`, for an undisturbed soup that settled
+}
+
+/// Every record, as kept in the records file
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Records {
+    pub soups: Vec<(u32, Record)>, // Longest-lived soup per density in hundredths, lowest first
+    pub peak: Option<Record>,      // Highest peak population
+    pub longest_run: Option<Record>, // Most generations stepped in one run
+    pub total_generations: u64,    // Generations stepped in every run so far
+    version: u32,                  // Version of the file read; 0 when there was none
+    other: Vec<String>,            // Lines this version doesn't know, written back as they were
+}
+
+impl Records {
+    /// Location of the records file, or `None` where there is no filesystem
+    pub fn path() -> Option<PathBuf> {
+        Settings::path().map(|p| p.with_file_name("records.txt"))
+    }
+
+    /// Load the records file; a missing file is no records yet
+    pub fn load() -> Self {
+        Self::try_load().map(|kept| kept.value).unwrap_or_default()
+    }
+
+    /// Load the records file as `load` does, but say why a file that exists couldn't be read,
+    /// and when its backup stood in for it
+    pub fn try_load() -> Result<Kept<Self>, GolError> {
+        match Self::path() {
+            Some(path) => Self::read_from(&path),
+            None => Ok(Kept::fresh(Self::default())),
+        }
+    }
+
+    /// Read the records file at `path`, or its backup if it is damaged; no records if there is none
+    pub fn read_from(path: &Path) -> Result<Kept<Self>, GolError> {
+        let kept = error::read_kept(path, |text| error::check_key_values("records file", text).map(|()| Self::parse(text)))?;
+        Ok(kept.unwrap_or_else(|| Kept::fresh(Self::default())))
+    }
+
+    /// Write the records file, and the backup read if it is later found damaged. Failures are
+    /// returned so the UI can report them.
+    pub fn save(&self) -> Result<(), GolError> {
+        let Some(path) = Self::path() else { return Ok(()); };
+        error::write_kept(&path, self.to_text()?)
+    }
+
+    /// Parse `key = value` lines. A file without a version line predates versioning and reads
+    /// as version 1; lines that don't parse are kept for writing back.
+    pub fn parse(text: &str) -> Self {
+        let mut records = Self { version: 1, ..Self::default() };
+        for raw in text.lines() {
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with('#') { continue; }
+            let known = line.split_once('=').is_some_and(|(key, value)| {
+                let value = value.trim();
+                match key.trim() {
+                    "version" => value.parse().map(|v| records.version = v).is_ok(),
+                    "total_generations" => value.parse().map(|n| records.total_generations = n).is_ok(),
+                    "peak" => Record::parse(value).map(|r| records.peak = Some(r)).is_ok(),
+                    "longest_run" => Record::parse(value).map(|r| records.longest_run = Some(r)).is_ok(),
+                    key => match (key.strip_prefix("soup_").and_then(density_key), Record::parse(value)) {
+                        (Some(density), Ok(record)) => {
+                            records.set_soup(density, record);
+                            true
+                        }
+                        _ => false,
+                    },
+                }
+            });
+            if !known {
+                records.other.push(raw.to_string());
+            }
+        }
+        records
+    }
+
+    /// Serialize in the format read by `parse`, or refuse when the records came from a newer
+    /// version, whose file this one would damage
+    pub fn to_text(&self) -> Result<String, GolError> {
+        if self.version > RECORDS_VERSION {
+            return Err(GolError::Unsupported(format!(
+                "the records file is from a newer version (format {}, this one writes {}), so it was left as it is",
+                self.version, RECORDS_VERSION,
+            )));
+        }
+        let mut text = format!("# Conway's Game of Life personal records\nversion = {}\n", RECORDS_VERSION);
+        text += &format!("total_generations = {}\n", self.total_generations);
+        if let Some(record) = &self.peak {
+            text += &format!("peak = {}\n", record);
+        }
+        if let Some(record) = &self.longest_run {
+            text += &format!("longest_run = {}\n", record);
+        }
+        for (density, record) in &self.soups {
+            text += &format!("soup_{} = {}\n", density_name(*density), record);
+        }
+        for line in &self.other {
+            text += line;
+            text.push('\n');
+        }
+        Ok(text)
+    }
+
+    /// The longest-lived soup at `density` (in hundredths), if any
+    pub fn soup(&self, density: u32) -> Option<&Record> {
+        self.soups.iter().find(|(d, _)| *d == density).map(|(_, r)| r)
+    }
+
+    fn set_soup(&mut self, density: u32, record: Record) {
+        self.soups.retain(|(d, _)| *d != density);
+        let at = self.soups.partition_point(|(d, _)| *d < density);
+        self.soups.insert(at, (density, record));
+    }
+
+    /// Whether `peak` beats an existing peak population record
+    pub fn beats_peak(&self, peak: usize) -> bool {
+        self.peak.as_ref().is_some_and(|r| peak as u64 > r.value)
+    }
+
+    /// Whether `generations` beats an existing most-generations record
+    pub fn beats_longest_run(&self, generations: u64) -> bool {
+        self.longest_run.as_ref().is_some_and(|r| generations > r.value)
+    }
+
+    /// Fold in a finished run; returns a line for each record it set
+    pub fn add(&mut self, result: &RunResult) -> Vec<String> {
+        let mut set = Vec::new();
+        self.total_generations += result.generations;
+        let record = |value: u64| Record { value, run: result.run.clone() };
+        if result.peak > 0 && self.peak.as_ref().is_none_or(|r| result.peak as u64 > r.value) {
+            set.push(format!("New record: peak population {}", result.peak));
+            self.peak = Some(record(result.peak as u64));
+        }
+        if result.generations > 0 && self.longest_run.as_ref().is_none_or(|r| result.generations > r.value) {
+            set.push(format!("New record: {} generations in one run", result.generations));
+            self.longest_run = Some(record(result.generations));
+        }
+        if let Some(lifespan) = result.soup_lifespan {
+            let density = (result.run.density * 100.0).round() as u32;
+            if self.soup(density).is_none_or(|r| lifespan > r.value) {
+                set.push(format!("New record: a soup at density {} lived {} generations", density_name(density), lifespan));
+                self.set_soup(density, record(lifespan));
+            }
+        }
+        set
+    }
+
+    /// Rows for the records screen, with the run each one can replay
+    pub fn rows(&self) -> Vec<(String, Option<&RunSetup>)> {
+        let mut rows = vec![(format!("Total generations simulated: {}", self.total_generations), None)];
+        if let Some(r) = &self.peak {
+            rows.push((format!("Highest peak population: {} ({})", r.value, r.origin()), Some(&r.run)));
+        }
+        if let Some(r) = &self.longest_run {
+            rows.push((format!("Most generations in one run: {} ({})", r.value, r.origin()), Some(&r.run)));
+        }
+        for (density, r) in &self.soups {
+            rows.push((
+                format!("Longest-lived soup at density {}: {} generations ({})", density_name(*density), r.value, r.origin()),
+                Some(&r.run),
+            ));
+        }
+        rows
+    }
+}
+
+/// "0.20" for 20 hundredths
+fn density_name(hundredths: u32) -> String {
+    format!("{}.{:02}", hundredths / 100, hundredths % 100)
+}
+
+/// 20 hundredths for "0.20"
+fn density_key(name: &str) -> Option<u32> {
+    let density: f32 = name.parse().ok()?;
+    (0.0..=1.0).contains(&density).then(|| (density * 100.0).round() as u32)
+}
+
+/// How long the random soup `run` describes lived: the generation it died out or began
+/// repeating with a period of up to SUMMARY_PERIOD_MAX. It is found by replaying the soup, and
+/// only counts when the replay reaches the run's final board: `final_hash` at `generation`.
+/// A board that was edited, spawned into or culled along the way doesn't match, nor does one
+/// still changing when the run ended. Runs longer than RECORD_REPLAY_MAX are not replayed.
+pub fn soup_lifespan(run: &RunSetup, generation: u64, final_hash: u64) -> Option<u64> {
+    if !run.is_soup() || generation > RECORD_REPLAY_MAX {
+        return None;
+    }
+    let mut sim = run.soup();
+    let mut seen: HashMap<u64, u64> = HashMap::new(); // Board hash to the generation it was last seen
+    let mut hash_at_end = None;
+    loop {
+        let hash = board_hash(&sim.live);
+        if sim.generation == generation {
+            hash_at_end = Some(hash);
+        }
+        if let Some(&first) = seen.get(&hash) {
+            let period = (sim.generation - first) as u32;
+            if analysis::period(&sim.grid, &sim.live, period) == Some(period) {
+                if first > generation {
+                    return None; // Still changing when the run ended
+                }
+                // Settled before the end: the final board is the phase the cycle had reached
+                let end = hash_at_end.unwrap_or_else(|| {
+                    for _ in 0..(generation - first) % period as u64 {
+                        sim.next_generation();
+                    }
+                    board_hash(&sim.live)
+                });
+                return (end == final_hash).then_some(first);
+            }
+        }
+        seen.insert(hash, sim.generation);
+        seen.retain(|_, g| *g + SUMMARY_PERIOD_MAX as u64 >= sim.generation);
+        if sim.generation >= generation + SUMMARY_PERIOD_MAX as u64 {
+            return None;
+        }
+        sim.next_generation();
+    }
+}
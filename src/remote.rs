@@ -0,0 +1,263 @@
+//! Driving a running simulation from another thread, e.g. a chat bot. Commands go in over a
+//! channel and are applied between generations; a summary of every generation comes back over
+//! another. Either the window (`SimulationConfig::remote`) or a headless worker thread (`spawn`)
+//! can be on the other end.
+//!
+//! Ordering: commands apply in the order they were sent. Every command queued when a
+//! generation ends is applied before the next one starts, so a generation never sees half of a
+//! burst of commands. A `Query` is answered in its place in that order: it sees every command
+//! sent before it and no generation run after it. Events arrive in generation order, and the
+//! event for a generation is sent before any query that sees it is answered.
+
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TryRecvError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::config::{REMOTE_EVENT_QUEUE, SPEED_MAX, SPEED_MIN};
+use crate::error::GolError;
+use crate::grid::{board_hash, Position, Rect};
+use crate::pattern_io::LoadedPattern;
+use crate::patterns::{default_registry, PatternRegistry, Transform};
+use crate::simulation::Simulation;
+
+/// A pattern to stamp: one of the built-ins by name, or cells read elsewhere
+#[derive(Clone, Debug)]
+pub enum Source {
+    Named(String),
+    Loaded(LoadedPattern),
+}
+
+/// Something to do to the board between generations
+#[derive(Debug)]
+pub enum Command {
+    SetCell(Position, bool),     // Bring a cell to life or kill it; off-grid cells are ignored
+    Stamp(Source, Position),     // Add a pattern centered on a cell, clipped at walls
+    Clear,                       // Remove every cell; the generation count starts again
+    SetSpeed(f32),               // Generations per second; a headless run takes 0 for flat out
+    Pause(bool),                 // Stop (true) or carry on (false); carrying on starts the run
+    Query(Query, Sender<Snapshot>), // Describe the board, answered on the sender given
+}
+
+/// What a `Query` asks about beyond the counts every snapshot carries
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Query {
+    pub region: Option<Rect>, // Live cells in this rectangle are listed
+}
+
+/// The board at the moment a query was answered
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    pub generation: u64,
+    pub population: usize,
+    pub hash: u64,            // `board_hash` of the whole board
+    pub cells: Vec<Position>, // Live cells in the queried region, row by row; empty without one
+    pub paused: bool,
+}
+
+/// Published by the simulation as it runs
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    Generation(Summary),
+    Rejected(String), // A command that couldn't be applied, and why
+}
+
+/// One generation, as it was reached
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Summary {
+    pub generation: u64,
+    pub population: usize,
+    pub hash: u64,
+    pub born: usize, // Cells born and died in the step to it
+    pub died: usize,
+}
+
+/// Playback state commands may change
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Control {
+    pub paused: bool,
+    pub speed: f32,
+}
+
+/// The controlling side: send commands, read events. It can move to any thread.
+pub struct Remote {
+    pub commands: Sender<Command>,
+    pub events: Receiver<Event>,
+}
+
+impl Remote {
+    /// Queue a command; false once the simulation has ended
+    pub fn send(&self, command: Command) -> bool {
+        self.commands.send(command).is_ok()
+    }
+
+    /// Ask about the board and wait for the answer; `None` once the simulation has ended
+    pub fn query(&self, region: Option<Rect>) -> Option<Snapshot> {
+        let (reply, answer) = mpsc::channel();
+        self.send(Command::Query(Query { region }, reply)).then_some(())?;
+        answer.recv().ok()
+    }
+}
+
+/// The simulation's side, applying commands and publishing events. It stays on the thread
+/// that steps the board.
+pub struct Endpoint {
+    commands: Receiver<Command>,
+    events: SyncSender<Event>,
+    registry: PatternRegistry, // Patterns `Source::Named` can place
+}
+
+/// A connected pair: hand the `Endpoint` to the simulation and keep the `Remote`
+pub fn channel() -> (Remote, Endpoint) {
+    let (commands, inbox) = mpsc::channel();
+    let (outbox, events) = mpsc::sync_channel(REMOTE_EVENT_QUEUE);
+    (Remote { commands, events }, Endpoint::new(inbox, outbox))
+}
+
+impl Endpoint {
+    fn new(commands: Receiver<Command>, events: SyncSender<Event>) -> Self {
+        Self { commands, events, registry: default_registry() }
+    }
+
+    /// Apply every command waiting, in order. Returns false once the `Remote` is gone and
+    /// nothing is left to apply.
+    pub fn apply(&mut self, sim: &mut Simulation, control: &mut Control) -> bool {
+        loop {
+            match self.commands.try_recv() {
+                Ok(command) => self.apply_one(command, sim, control),
+                Err(TryRecvError::Empty) => return true,
+                Err(TryRecvError::Disconnected) => return false,
+            }
+        }
+    }
+
+    /// Wait for the next command and apply it, then the rest waiting; for a paused board
+    /// that has nothing else to do. Returns false once the `Remote` is gone.
+    pub fn wait(&mut self, sim: &mut Simulation, control: &mut Control) -> bool {
+        match self.commands.recv() {
+            Ok(command) => self.apply_one(command, sim, control),
+            Err(_) => return false,
+        }
+        self.apply(sim, control)
+    }
+
+    fn apply_one(&mut self, command: Command, sim: &mut Simulation, control: &mut Control) {
+        match command {
+            Command::SetCell(p, alive) => {
+                if let Some(p) = sim.grid.resolve(p.x(), p.y()) {
+                    sim.set_cell(p, alive);
+                }
+            }
+            Command::Stamp(source, at) => {
+                let result = match &source {
+                    Source::Named(name) => match self.registry.get(name) {
+                        Some(pattern) => sim.stamp(pattern, Transform::IDENTITY, 0, at.x(), at.y()).map_err(|e| e.to_string()),
+                        None => Err(format!("unknown pattern '{}' (expected one of: {})", name, self.registry.names())),
+                    },
+                    Source::Loaded(pattern) => sim.stamp(pattern, Transform::IDENTITY, 0, at.x(), at.y()).map_err(|e| e.to_string()),
+                };
+                if let Err(reason) = result {
+                    self.publish_event(Event::Rejected(format!("stamp: {}", reason)));
+                }
+            }
+            Command::Clear => sim.clear(),
+            Command::SetSpeed(speed) => control.speed = speed,
+            Command::Pause(paused) => {
+                if !paused && sim.editing() {
+                    sim.mark_initial();
+                }
+                control.paused = paused;
+            }
+            Command::Query(query, reply) => {
+                let mut cells: Vec<Position> = match query.region {
+                    Some(region) => sim.live.iter().filter(|p| region.contains(**p)).copied().collect(),
+                    None => Vec::new(),
+                };
+                cells.sort_unstable_by_key(|p| (p.y(), p.x()));
+                let snapshot = Snapshot {
+                    generation: sim.generation,
+                    population: sim.live.len(),
+                    hash: board_hash(&sim.live),
+                    cells,
+                    paused: control.paused,
+                };
+                // The asker may have given up waiting
+                let _ = reply.send(snapshot);
+            }
+        }
+    }
+
+    /// Publish the generation just reached. The board must track its diff for the birth and
+    /// death counts; they are 0 otherwise.
+    pub fn publish(&self, sim: &Simulation) {
+        let (born, died) = sim.diff.as_ref()
+            .filter(|d| d.generation == sim.generation)
+            .map_or((0, 0), |d| (d.born.len(), d.died.len()));
+        let summary = Summary { generation: sim.generation, population: sim.live.len(), hash: board_hash(&sim.live), born, died };
+        self.publish_event(Event::Generation(summary));
+    }
+
+    /// Events are dropped rather than held up when the reader falls `REMOTE_EVENT_QUEUE` behind;
+    /// the generation numbers show the gap
+    fn publish_event(&self, event: Event) {
+        let _ = self.events.try_send(event);
+    }
+}
+
+/// A simulation stepping on its own thread, with no window
+pub struct Headless {
+    pub remote: Remote,
+    handle: JoinHandle<Simulation>,
+}
+
+impl Headless {
+    /// Stop the run and hand back the board as it ended. Commands already sent are applied first.
+    pub fn finish(self) -> Simulation {
+        let Headless { remote, handle } = self;
+        drop(remote);
+        handle.join().expect("the remote simulation thread panicked")
+    }
+}
+
+/// Start `sim` stepping on a worker thread at `speed` generations per second (0 for as fast as
+/// it can), paused or not. A board still in edit mode starts its run when it is first resumed.
+pub fn spawn(mut sim: Simulation, speed: f32, paused: bool) -> Result<Headless, GolError> {
+    let (commands, inbox) = mpsc::channel();
+    let (outbox, events) = mpsc::sync_channel(REMOTE_EVENT_QUEUE);
+    sim.track_diff = true;
+    let handle = thread::Builder::new()
+        .name("life-remote".to_string())
+        .spawn(move || {
+            let mut endpoint = Endpoint::new(inbox, outbox);
+            let mut control = Control { paused, speed };
+            if !paused && sim.editing() {
+                sim.mark_initial();
+            }
+            let mut due = Instant::now();
+            loop {
+                let connected = if control.paused { endpoint.wait(&mut sim, &mut control) } else { endpoint.apply(&mut sim, &mut control) };
+                if !connected {
+                    return sim;
+                }
+                if control.paused {
+                    continue;
+                }
+                sim.next_generation();
+                endpoint.publish(&sim);
+                if control.speed > 0.0 {
+                    due += Duration::from_secs_f32(1.0 / control.speed.clamp(SPEED_MIN, SPEED_MAX));
+                    let now = Instant::now();
+                    if due > now {
+                        thread::sleep(due - now);
+                    } else {
+                        // Fell behind: start pacing again from now rather than race to catch up
+                        due = now;
+                    }
+                } else {
+                    due = Instant::now();
+                }
+            }
+        })
+        .map_err(|e| GolError::Unsupported(format!("can't start the simulation thread: {}", e)))?;
+    Ok(Headless { remote: Remote { commands, events }, handle })
+}
+
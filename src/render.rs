@@ -0,0 +1,1212 @@
+//! Drawing a `Simulation` through a `BoardView`: cell size, camera, theme and grid lines.
+//! The board itself is drawn through `Canvas`, so it can be rasterized without a window.
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use macroquad::prelude::*;
+
+use crate::config::{CELL_SIZE_MAX, CELL_SIZE_MIN, GRID_CACHE_MAX_PX, HEAT_FAINTEST, HUD_HEIGHT, ZOOM_FIT_MARGIN, ZOOM_LEVELS, ZOOM_OUT_LEVELS, RULER_FONT, RULER_GAP, RULER_MIN_CELL, SCHEDULE_STRIP, SPEED_MIN};
+use crate::schedule::SpeedSchedule;
+use crate::grid::{board_hash, CellFate, CoordSystem, Grid, Position, Rect, Topology};
+use crate::annotations::Annotations;
+use crate::compare::{BoardDiff, DiffCounts};
+use crate::density::DensityMap;
+use crate::heat::HeatField;
+use crate::particles::{Effect, Particles};
+use crate::overlay::{Frame, Input, InputConsumed, Layer, Overlay};
+use crate::rule::Rule;
+use crate::simulation::{MemoryUsage, Simulation};
+use std::rc::Rc;
+
+use crate::themes::{Backdrop, ColorTheme, GridMode, GridStyle, LineStyle, ThemeColors};
+
+/// Something rectangles can be drawn onto
+pub trait Canvas {
+    fn clear(&mut self, color: Color);
+    fn fill_rect(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color);
+    /// Outline a rectangle; the stroke lies inside it, `thickness / 2` wide like macroquad's
+    fn stroke_rect(&mut self, x: f32, y: f32, w: f32, h: f32, thickness: f32, color: Color);
+    /// Stretch a backdrop over a rectangle at its opacity
+    fn backdrop(&mut self, backdrop: &Backdrop, x: f32, y: f32, w: f32, h: f32);
+    /// Draw a zoomed-out board's density picture with its top left corner at (x, y), one
+    /// pixel per bucket
+    fn density(&mut self, map: &mut DensityMap, x: f32, y: f32);
+
+    /// Draw the board's grid lines. The screen overrides this to use the cached texture.
+    fn grid_lines(&mut self, view: &BoardView, grid: &Grid, colors: &ThemeColors) {
+        draw_grid_lines(self, view.offset, grid.width, grid.height, view.cell, view.grid_style, colors);
+    }
+}
+
+/// The window, through macroquad's immediate-mode drawing
+pub struct Screen;
+
+impl Canvas for Screen {
+    fn clear(&mut self, color: Color) {
+        clear_background(color);
+    }
+
+    fn fill_rect(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color) {
+        draw_rectangle(x, y, w, h, color);
+    }
+
+    fn stroke_rect(&mut self, x: f32, y: f32, w: f32, h: f32, thickness: f32, color: Color) {
+        draw_rectangle_lines(x, y, w, h, thickness, color);
+    }
+
+    fn backdrop(&mut self, backdrop: &Backdrop, x: f32, y: f32, w: f32, h: f32) {
+        draw_backdrop_texture(backdrop, x, y, w, h);
+    }
+
+    fn density(&mut self, map: &mut DensityMap, x: f32, y: f32) {
+        draw_texture(map.texture(), x, y, WHITE);
+    }
+
+    fn grid_lines(&mut self, view: &BoardView, grid: &Grid, colors: &ThemeColors) {
+        view.draw_cached_grid(grid, colors);
+    }
+}
+
+/// Software rasterizer for an image in memory: a pixel is covered when its center is inside
+/// the rectangle, and translucent colors blend over what is there
+impl Canvas for Image {
+    fn clear(&mut self, color: Color) {
+        let pixel: [u8; 4] = color.into();
+        self.get_image_data_mut().fill(pixel);
+    }
+
+    fn fill_rect(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color) {
+        let (width, height) = (self.width(), self.height());
+        for py in pixel_span(y, h, height) {
+            for px in pixel_span(x, w, width) {
+                let under = self.get_pixel(px as u32, py as u32);
+                let a = color.a;
+                let blended = Color::new(
+                    color.r * a + under.r * (1.0 - a),
+                    color.g * a + under.g * (1.0 - a),
+                    color.b * a + under.b * (1.0 - a),
+                    a + under.a * (1.0 - a),
+                );
+                self.set_pixel(px as u32, py as u32, blended);
+            }
+        }
+    }
+
+    fn stroke_rect(&mut self, x: f32, y: f32, w: f32, h: f32, thickness: f32, color: Color) {
+        let t = thickness / 2.0;
+        self.fill_rect(x, y, w, t, color);
+        self.fill_rect(x, y + h - t, w, t, color);
+        self.fill_rect(x, y + t, t, h - 2.0 * t, color);
+        self.fill_rect(x + w - t, y + t, t, h - 2.0 * t, color);
+    }
+
+    fn backdrop(&mut self, backdrop: &Backdrop, x: f32, y: f32, w: f32, h: f32) {
+        stretch(self, &backdrop.image, backdrop.opacity, x, y, w, h);
+    }
+
+    fn density(&mut self, map: &mut DensityMap, x: f32, y: f32) {
+        stretch(self, map.image(), 1.0, x, y, map.cols as f32, map.rows as f32);
+    }
+}
+
+/// Stretch `source` over a rectangle of `image` at `opacity`, nearest-neighbor: the source pixel
+/// under each covered pixel's center
+pub(crate) fn stretch(image: &mut Image, source: &Image, opacity: f32, x: f32, y: f32, w: f32, h: f32) {
+    let (sw, sh) = (source.width(), source.height());
+    if sw == 0 || sh == 0 || w <= 0.0 || h <= 0.0 {
+        return;
+    }
+    let sample = |p: usize, lo: f32, len: f32, size: usize| (((p as f32 + 0.5 - lo) / len * size as f32) as usize).min(size - 1);
+    let (width, height) = (image.width(), image.height());
+    for py in pixel_span(y, h, height) {
+        for px in pixel_span(x, w, width) {
+            let mut color = source.get_pixel(sample(px, x, w, sw) as u32, sample(py, y, h, sh) as u32);
+            color.a *= opacity;
+            image.fill_rect(px as f32, py as f32, 1.0, 1.0, color);
+        }
+    }
+}
+
+/// Pixels whose centers fall inside `lo..lo + len`, clipped to `0..max`
+fn pixel_span(lo: f32, len: f32, max: usize) -> std::ops::Range<usize> {
+    let start = (lo - 0.5).ceil().max(0.0) as usize;
+    let end = ((lo + len - 0.5).ceil().max(0.0) as usize).min(max);
+    start..end
+}
+
+/// Draw a backdrop's texture stretched over a rectangle of the current camera
+fn draw_backdrop_texture(backdrop: &Backdrop, x: f32, y: f32, w: f32, h: f32) {
+    draw_texture_ex(backdrop.texture(), x, y, Color::new(1.0, 1.0, 1.0, backdrop.opacity), DrawTextureParams {
+        dest_size: Some(vec2(w, h)),
+        ..Default::default()
+    });
+}
+
+/// Grid lines pre-rendered into a texture so a dense grid costs one draw call per frame
+struct GridCache {
+    key: (i32, i32, i32, ColorTheme, GridStyle), // Cell size, width, height, theme, style
+    target: RenderTarget,
+}
+
+/// The whole board (cells, margin shading, grid lines and border) pre-rendered while it stays
+/// the same, so a paused or settled board costs one draw call per frame
+struct BoardCache {
+    key: BoardKey,
+    target: Option<RenderTarget>, // Built once the same board has been drawn on two frames in a row
+}
+
+/// Everything the cached board depends on. The camera is not part of it: the texture is drawn
+/// wherever the board is.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct BoardKey {
+    live: (usize, u64), // Cell count and `board_hash`, which costs far less than drawing the cells
+    size: (i32, i32, i32), // Cell size, width, height
+    theme: ColorTheme,
+    style: GridStyle,
+    margin: (i32, Topology), // Shaded lethal margin, 0 when none is drawn, and which edges it lines
+    backdrop: bool,
+}
+
+/// The window through a render target: like `Screen`, but grid lines are drawn directly, since
+/// the grid cache would switch the camera back to the window
+struct Offscreen;
+
+impl Canvas for Offscreen {
+    fn clear(&mut self, color: Color) {
+        clear_background(color);
+    }
+
+    fn fill_rect(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color) {
+        draw_rectangle(x, y, w, h, color);
+    }
+
+    fn stroke_rect(&mut self, x: f32, y: f32, w: f32, h: f32, thickness: f32, color: Color) {
+        draw_rectangle_lines(x, y, w, h, thickness, color);
+    }
+
+    fn backdrop(&mut self, backdrop: &Backdrop, x: f32, y: f32, w: f32, h: f32) {
+        draw_backdrop_texture(backdrop, x, y, w, h);
+    }
+
+    fn density(&mut self, map: &mut DensityMap, x: f32, y: f32) {
+        draw_texture(map.texture(), x, y, WHITE);
+    }
+}
+
+/// Draw one grid line as a solid bar or a row of dots
+#[allow(clippy::too_many_arguments)]
+fn draw_grid_line<C: Canvas + ?Sized>(canvas: &mut C, x: f32, y: f32, len: f32, thickness: f32, vertical: bool, dotted: bool, color: Color) {
+    let (w, h) = if vertical { (thickness, len) } else { (len, thickness) };
+    if !dotted {
+        canvas.fill_rect(x, y, w, h, color);
+        return;
+    }
+    let spacing = thickness * 3.0;
+    let mut t = 0.0;
+    while t < len {
+        let (dx, dy) = if vertical { (0.0, t) } else { (t, 0.0) };
+        canvas.fill_rect(x + dx, y + dy, thickness, thickness, color);
+        t += spacing;
+    }
+}
+
+/// Draw minor and major grid lines for a board whose top-left corner is at `origin`
+fn draw_grid_lines<C: Canvas + ?Sized>(
+    canvas: &mut C,
+    origin: Vec2,
+    width: i32,
+    height: i32,
+    cell: i32,
+    style: GridStyle,
+    colors: &ThemeColors,
+) {
+    if style.mode == GridMode::Off { return; }
+    let thickness = style.thickness(cell);
+    let dotted = style.line == LineStyle::Dotted;
+    let show_minor = cell >= style.min_cell;
+    let show_major = style.mode == GridMode::MinorMajor;
+    let (board_w, board_h) = ((width * cell) as f32, (height * cell) as f32);
+
+    let mut line = |i: i32, vertical: bool| {
+        let major = show_major && i % style.major_every == 0;
+        if !major && !show_minor { return; }
+        let offset = (i * cell) as f32;
+        let (x, y, len) = if vertical {
+            (origin.x + offset, origin.y, board_h)
+        } else {
+            (origin.x, origin.y + offset, board_w)
+        };
+        if major {
+            draw_grid_line(canvas, x, y, len, thickness + 1.0, vertical, false, colors.grid_major);
+        } else {
+            draw_grid_line(canvas, x, y, len, thickness, vertical, dotted, colors.grid);
+        }
+    };
+    for x in 0..=width { line(x, true); }
+    for y in 0..=height { line(y, false); }
+}
+
+/// How a board is shown: cell size, camera, theme and grid lines
+pub struct BoardView {
+    pub cell: i32,          // Visual size of each cell in pixels
+    pub shrink: i32,        // Cells per pixel along each axis when zoomed out past 1 px per cell, else 1
+    pub offset: Vec2,       // Screen position of the board's top-left corner
+    pub pan: Vec2,          // Camera shift from the centered view, in pixels
+    pub grid_style: GridStyle, // Which grid lines to draw and how
+    pub theme: ColorTheme,   // Current color theme
+    pub coords: CoordSystem, // Convention used to show and read cell coordinates
+    pub ruler: bool,         // Column and row numbers along the board's edges
+    pub notes: bool,         // Markers of the notes pinned to cells
+    pub backdrop: Option<Rc<Backdrop>>, // The theme's background image, drawn under the cells
+    grid_cache: RefCell<Option<GridCache>>,
+    board_cache: RefCell<Option<BoardCache>>,
+    density: RefCell<Option<DensityMap>>,
+}
+
+impl BoardView {
+    pub fn new(cell_size: i32) -> Self {
+        Self {
+            cell: cell_size,
+            shrink: 1,
+            offset: Vec2::ZERO,
+            pan: Vec2::ZERO,
+            grid_style: GridStyle::default(),
+            theme: ColorTheme::Classic,
+            coords: CoordSystem::TopLeft,
+            ruler: false,
+            notes: true,
+            backdrop: None,
+            grid_cache: RefCell::new(None),
+            board_cache: RefCell::new(None),
+            density: RefCell::new(None),
+        }
+    }
+
+    /// A view with the same look at a different cell size, e.g. for a sandbox
+    pub fn styled_like(&self, cell_size: i32) -> Self {
+        Self {
+            grid_style: self.grid_style,
+            theme: self.theme,
+            coords: self.coords,
+            ruler: self.ruler,
+            notes: self.notes,
+            backdrop: self.backdrop.clone(),
+            ..Self::new(cell_size)
+        }
+    }
+
+    /// Pixels per cell, below 1 when zoomed out past the smallest cell size
+    pub fn scale(&self) -> f32 {
+        self.cell as f32 / self.shrink as f32
+    }
+
+    /// The zoom for the HUD: the cell size, or e.g. `1/4` for four cells per pixel
+    pub fn zoom_label(&self) -> String {
+        if self.shrink > 1 { format!("1/{}", self.shrink) } else { self.cell.to_string() }
+    }
+
+    /// Convert a screen position in pixels to grid coordinates
+    pub fn screen_to_cell(&self, sx: f32, sy: f32) -> (i32, i32) {
+        (
+            ((sx - self.offset.x) / self.scale()).floor() as i32,
+            ((sy - self.offset.y) / self.scale()).floor() as i32,
+        )
+    }
+
+    /// Screen position of the top-left corner of cell (x, y)
+    #[inline]
+    pub fn cell_to_screen(&self, x: i32, y: i32) -> Vec2 {
+        vec2(
+            self.offset.x + x as f32 * self.scale(),
+            self.offset.y + y as f32 * self.scale(),
+        )
+    }
+
+    /// Board size in screen pixels at the current zoom
+    pub fn board_size(&self, grid: &Grid) -> Vec2 {
+        vec2(grid.width as f32 * self.scale(), grid.height as f32 * self.scale())
+    }
+
+    /// Center the board in a window of the given size, then apply the camera shift. The offset
+    /// is kept on whole pixels, so every cell edge is too and neighbouring cells never leave a
+    /// seam or overlap.
+    pub fn center_view(&mut self, grid: &Grid, screen_w: f32, screen_h: f32) {
+        let board = self.board_size(grid);
+        self.offset = (vec2(
+            ((screen_w - board.x) / 2.0).floor(),
+            ((screen_h - board.y) / 2.0).floor(),
+        ) + self.pan).floor();
+    }
+
+    /// Change the rendered cell size and recenter; grid dimensions are unaffected
+    pub fn set_cell_size(&mut self, cell: i32) {
+        self.cell = cell.clamp(CELL_SIZE_MIN, CELL_SIZE_MAX);
+        self.shrink = 1;
+        self.pan = Vec2::ZERO;
+    }
+
+    /// Change the zoom to `(cell size, cells per pixel)` and recenter. Cells per pixel only
+    /// count at the smallest cell size.
+    pub fn set_zoom(&mut self, (cell, shrink): (i32, i32)) {
+        self.set_cell_size(cell);
+        if self.cell == CELL_SIZE_MIN {
+            self.shrink = shrink.max(1);
+        }
+    }
+
+    /// Pick the cell size and camera shift that fit the live cells to the window.
+    /// Returns false, resetting the camera, when there is nothing to fit.
+    pub fn fit_view(&mut self, sim: &Simulation, screen_w: f32, screen_h: f32) -> bool {
+        let Some(content) = sim.bounding_box() else {
+            self.pan = Vec2::ZERO;
+            return false;
+        };
+        self.set_zoom(Self::fit_zoom(content.w, content.h, screen_w, screen_h));
+        let center = vec2(content.x as f32 + content.w as f32 / 2.0, content.y as f32 + content.h as f32 / 2.0);
+        self.look_at(&sim.grid, center, screen_w, screen_h);
+        true
+    }
+
+    /// The largest zoom, as `(cell size, cells per pixel)`, that fits a `w` x `h` cell area in
+    /// `room_w` x `room_h` pixels: a zoom level, or past the smallest one the fewest cells per
+    /// pixel that fit (the most there are if none do)
+    pub fn fit_zoom(w: i32, h: i32, room_w: f32, room_h: f32) -> (i32, i32) {
+        let fit = (room_w / w as f32).min(room_h / h as f32);
+        if fit >= 1.0 {
+            return (Self::zoom_level_at_most(fit.floor() as i32), 1);
+        }
+        let shrink = ZOOM_OUT_LEVELS.iter().copied().find(|&s| s as f32 * fit >= 1.0).unwrap_or(ZOOM_OUT_LEVELS[ZOOM_OUT_LEVELS.len() - 1]);
+        (CELL_SIZE_MIN, shrink)
+    }
+
+    /// The zoom `steps` levels in (`steps` > 0) or out from this view's, as `(cell size, cells
+    /// per pixel)`: past the smallest zoom level, zooming out goes on through `ZOOM_OUT_LEVELS`
+    pub fn zoom_stepped(&self, steps: i32) -> (i32, i32) {
+        let (mut cell, mut shrink) = (self.cell, self.shrink);
+        for _ in 0..steps.unsigned_abs() {
+            if steps > 0 && shrink > 1 {
+                shrink = ZOOM_OUT_LEVELS.iter().rev().copied().find(|&s| s < shrink).unwrap_or(1);
+            } else if steps > 0 || cell > CELL_SIZE_MIN {
+                cell = Self::zoom_step(cell, steps.signum());
+            } else {
+                shrink = ZOOM_OUT_LEVELS.iter().copied().find(|&s| s > shrink).unwrap_or(shrink);
+            }
+        }
+        (cell, shrink)
+    }
+
+    /// The largest zoom level no bigger than `cell`, or the smallest one
+    pub fn zoom_level_at_most(cell: i32) -> i32 {
+        ZOOM_LEVELS.iter().rev().copied().find(|&level| level <= cell).unwrap_or(ZOOM_LEVELS[0])
+    }
+
+    /// The next zoom level above `cell` (`steps` > 0) or below it (`steps` < 0), stopping at the
+    /// ends. A cell size between levels, like the default 10, steps to the levels either side.
+    pub fn zoom_step(cell: i32, steps: i32) -> i32 {
+        let mut cell = cell;
+        for _ in 0..steps.unsigned_abs() {
+            cell = if steps > 0 {
+                ZOOM_LEVELS.iter().copied().find(|&level| level > cell).unwrap_or(cell)
+            } else {
+                ZOOM_LEVELS.iter().rev().copied().find(|&level| level < cell).unwrap_or(cell)
+            };
+        }
+        cell
+    }
+
+    /// Change the zoom, as `(cell size, cells per pixel)`, keeping the board point under `anchor`
+    /// (usually the mouse) where it is on screen, to the nearest whole pixel
+    pub fn zoom_at(&mut self, grid: &Grid, zoom: (i32, i32), anchor: Vec2, screen_w: f32, screen_h: f32) {
+        let point = (anchor - self.offset) / self.scale();
+        self.set_zoom(zoom);
+        self.center_view(grid, screen_w, screen_h);
+        self.pan = (anchor - point * self.scale() - self.offset).round();
+        self.center_view(grid, screen_w, screen_h);
+    }
+
+    /// Zoom to the largest level that shows the whole board between the HUD and the bottom of
+    /// the window, with a margin around it, and center it there
+    pub fn fit_board(&mut self, grid: &Grid, screen_w: f32, screen_h: f32) {
+        let (room_w, room_h) = (screen_w - 2.0 * ZOOM_FIT_MARGIN, screen_h - HUD_HEIGHT - 2.0 * ZOOM_FIT_MARGIN);
+        self.set_zoom(Self::fit_zoom(grid.width, grid.height, room_w, room_h));
+        // Centered in the room below the HUD rather than in the whole window
+        self.pan = vec2(0.0, (HUD_HEIGHT / 2.0).floor());
+        self.center_view(grid, screen_w, screen_h);
+    }
+
+    /// Shift the camera so `center`, in cells, is in the middle of the window
+    pub fn look_at(&mut self, grid: &Grid, center: Vec2, screen_w: f32, screen_h: f32) {
+        let board = self.board_size(grid);
+        let centered = vec2((screen_w - board.x) / 2.0, (screen_h - board.y) / 2.0);
+        let wanted = vec2(screen_w / 2.0, screen_h / 2.0) - center * self.scale();
+        self.pan = (wanted - centered).floor();
+    }
+
+    /// Switch to the next available color theme
+    pub fn cycle_theme(&mut self) {
+        self.theme = match self.theme {
+            ColorTheme::Classic => ColorTheme::Dark,
+            ColorTheme::Dark => ColorTheme::Pastel,
+            ColorTheme::Pastel => ColorTheme::Neon,
+            ColorTheme::Neon => ColorTheme::Safe,
+            ColorTheme::Safe | ColorTheme::Custom(_) => ColorTheme::Classic,
+        };
+    }
+
+    /// Cell coordinates in the display convention, e.g. "(3, -2)"
+    pub fn format_cell(&self, grid: &Grid, x: i32, y: i32) -> String {
+        let (dx, dy) = self.coords.to_display(grid, x, y);
+        format!("({}, {})", dx, dy)
+    }
+
+    /// Draw the board from its cached texture when it hasn't changed since the last frame.
+    /// Returns false, drawing nothing, when the caller must draw it directly: the board changed
+    /// (the texture is only built once it holds still) or is too large for a texture.
+    fn draw_cached_board(&self, sim: &Simulation) -> bool {
+        let grid = &sim.grid;
+        let (w, h, cell) = (grid.width, grid.height, self.cell);
+        let extra = self.grid_style.thickness(cell) as i32 + 1;
+        let (tex_w, tex_h) = (w * cell + extra, h * cell + extra);
+        if tex_w > GRID_CACHE_MAX_PX || tex_h > GRID_CACHE_MAX_PX {
+            return false;
+        }
+
+        let key = BoardKey {
+            live: (sim.live.len(), board_hash(&sim.live)),
+            size: (cell, w, h),
+            theme: self.theme,
+            style: self.grid_style,
+            margin: (if sim.culls() { sim.lethal_margin } else { 0 }, sim.grid.topology),
+            backdrop: self.backdrop.is_some(),
+        };
+        let mut cache = self.board_cache.borrow_mut();
+        match cache.as_mut() {
+            Some(c) if c.key == key && c.target.is_none() => {
+                // Same board two frames running: worth keeping
+                let target = render_target(tex_w as u32, tex_h as u32);
+                target.texture.set_filter(FilterMode::Nearest);
+                let mut camera = Camera2D::from_display_rect(macroquad::math::Rect::new(0.0, 0.0, tex_w as f32, tex_h as f32));
+                camera.render_target = Some(target.clone());
+                set_camera(&camera);
+                let local = BoardView { offset: Vec2::ZERO, ..self.styled_like(cell) };
+                draw_board(&mut Offscreen, sim, &local, 1.0);
+                set_default_camera();
+                c.target = Some(target);
+            }
+            Some(c) if c.key == key => {}
+            _ => {
+                *cache = Some(BoardCache { key, target: None });
+                return false;
+            }
+        }
+        let Some(target) = cache.as_ref().and_then(|c| c.target.as_ref()) else { return false };
+        clear_background(self.theme.colors().background);
+        draw_texture_ex(&target.texture, self.offset.x, self.offset.y, WHITE, DrawTextureParams {
+            flip_y: true, // Render targets come out upside down
+            ..Default::default()
+        });
+        true
+    }
+
+    /// Draw grid lines from the cached texture, rebuilding it when its inputs change.
+    /// Boards too large for a texture are drawn directly.
+    fn draw_cached_grid(&self, grid: &Grid, colors: &ThemeColors) {
+        let (w, h, cell) = (grid.width, grid.height, self.cell);
+        let extra = self.grid_style.thickness(cell) as i32 + 1;
+        let (tex_w, tex_h) = (w * cell + extra, h * cell + extra);
+        if tex_w > GRID_CACHE_MAX_PX || tex_h > GRID_CACHE_MAX_PX {
+            draw_grid_lines(&mut Screen, self.offset, w, h, cell, self.grid_style, colors);
+            return;
+        }
+
+        let key = (cell, w, h, self.theme, self.grid_style);
+        let mut cache = self.grid_cache.borrow_mut();
+        if cache.as_ref().is_none_or(|c| c.key != key) {
+            let target = render_target(tex_w as u32, tex_h as u32);
+            target.texture.set_filter(FilterMode::Nearest);
+            let mut camera = Camera2D::from_display_rect(macroquad::math::Rect::new(0.0, 0.0, tex_w as f32, tex_h as f32));
+            camera.render_target = Some(target.clone());
+            set_camera(&camera);
+            clear_background(Color::new(0.0, 0.0, 0.0, 0.0));
+            draw_grid_lines(&mut Screen, Vec2::ZERO, w, h, cell, self.grid_style, colors);
+            set_default_camera();
+            *cache = Some(GridCache { key, target });
+        }
+        if let Some(c) = cache.as_ref() {
+            draw_texture_ex(&c.target.texture, self.offset.x, self.offset.y, WHITE, DrawTextureParams {
+                flip_y: true, // Render targets come out upside down
+                ..Default::default()
+            });
+        }
+    }
+}
+
+/// Keeps the board fitted to the window it is actually shown in. Window managers don't always
+/// grant the size requested, and windows get resized; the view's cell size and offset are the
+/// one transform both drawing and `screen_to_cell` go through, so refitting them keeps clicks
+/// on the cell under the cursor.
+#[derive(Clone, Copy, Debug)]
+pub struct WindowFit {
+    pub cell: i32, // Cell size the board was chosen with; a refit never goes above it
+    window: Vec2,  // Window size last fitted to
+}
+
+impl WindowFit {
+    /// Fit for a board chosen at `cell` for a window of the requested size
+    pub fn new(cell: i32, requested: Vec2) -> Self {
+        Self { cell, window: requested }
+    }
+
+    /// Largest cell size, up to the chosen one, that shows all of `grid` in the window
+    pub fn cell_for(&self, grid: &Grid, screen_w: f32, screen_h: f32) -> i32 {
+        let fit = (screen_w / grid.width as f32).min(screen_h / grid.height as f32).floor() as i32;
+        fit.clamp(CELL_SIZE_MIN, self.cell.max(CELL_SIZE_MIN))
+    }
+
+    /// Refit `view` if the window has changed size since the last call, resetting the camera
+    /// shift; `center_view` then places the board. Returns whether it refitted.
+    pub fn update(&mut self, view: &mut BoardView, grid: &Grid, screen_w: f32, screen_h: f32) -> bool {
+        let window = vec2(screen_w, screen_h);
+        if window == self.window {
+            return false;
+        }
+        self.window = window;
+        view.set_cell_size(self.cell_for(grid, screen_w, screen_h));
+        true
+    }
+}
+
+/// Draw the board to the window. `t` is how far (0..=1) the display is between the last step
+/// and the next; below 1 cells born in the last step grow in and cells that died shrink out.
+/// A board that holds still is drawn from a cached texture; zoomed out past 1 px per cell, the
+/// density picture is its own cache.
+pub fn render(sim: &Simulation, view: &BoardView, t: f32) {
+    let animating = sim.diff.as_ref().is_some_and(|d| d.generation == sim.generation && t < 1.0);
+    if view.shrink > 1 || animating || !view.draw_cached_board(sim) {
+        draw_board(&mut Screen, sim, view, t);
+    }
+}
+
+/// Draw the board to the window as `render` does, with the heat overlay washed under the
+/// cells at `intensity` (0..=1). It changes every frame, so the cached texture isn't used.
+pub fn render_heated(sim: &Simulation, view: &BoardView, t: f32, heat: &HeatField, intensity: f32) {
+    draw_board_under(&mut Screen, sim, view, t, Some((heat, intensity)));
+}
+
+/// Draw birth and death particles over the board at `intensity` (0..=1). Only the window draws
+/// them, so figures, thumbnails and the cached board never carry them. Zoomed out past 1 px per
+/// cell they would be too small to see, so nothing is drawn.
+pub fn render_particles(particles: &Particles, view: &BoardView, intensity: f32) {
+    if view.shrink > 1 {
+        return;
+    }
+    let size = view.scale();
+    let base = view.theme.colors().cell;
+    let (w, h) = (screen_width(), screen_height());
+    for p in particles.active() {
+        let t = p.progress();
+        let center = view.cell_to_screen(p.x, p.y) + vec2(size, size) / 2.0;
+        // A ring grows to two cells across; anything further off screen can't show
+        if center.x < -size || center.y < -size || center.x > w + size || center.y > h + size {
+            continue;
+        }
+        let mut color = base;
+        color.a *= intensity * (1.0 - t);
+        match p.effect {
+            Effect::Birth => draw_circle_lines(center.x, center.y, size * (0.5 + 0.5 * t), (size * 0.12).max(1.0), color),
+            Effect::Death => {
+                let side = size * (1.0 - t);
+                draw_rectangle(center.x - side / 2.0, center.y - side / 2.0, side, side, color);
+            }
+        }
+    }
+}
+
+/// Draw the board onto any canvas; `render` is this on the window. Figures and thumbnails come
+/// through here, so they never carry the heat overlay.
+pub fn draw_board<C: Canvas>(canvas: &mut C, sim: &Simulation, view: &BoardView, t: f32) {
+    draw_board_under(canvas, sim, view, t, None);
+}
+
+/// Draw the board, with a heat field and its intensity under the cells if given
+fn draw_board_under<C: Canvas>(canvas: &mut C, sim: &Simulation, view: &BoardView, t: f32, heat: Option<(&HeatField, f32)>) {
+    let colors = view.theme.colors();
+    canvas.clear(colors.background);
+    let size = view.scale();
+    let board = view.board_size(&sim.grid);
+    let diff = sim.diff.as_ref().filter(|d| d.generation == sim.generation && t < 1.0);
+
+    if let Some(backdrop) = &view.backdrop {
+        canvas.backdrop(backdrop, view.offset.x, view.offset.y, board.x, board.y);
+    }
+
+    // Shade the lethal margin faintly, under the cells
+    if sim.culls() {
+        let mut shade = colors.border;
+        shade.a = 0.12;
+        let k = sim.lethal_margin.min(sim.grid.width.min(sim.grid.height)) as f32 * size;
+        let (x, y) = (view.offset.x, view.offset.y);
+        // Only the walled edges; a cylinder's seam has no margin
+        let (walled_x, walled_y) = (!sim.grid.topology.wraps_x(), !sim.grid.topology.wraps_y());
+        let band = if walled_y { k } else { 0.0 };
+        if walled_y {
+            canvas.fill_rect(x, y, board.x, k, shade);
+            canvas.fill_rect(x, y + board.y - k, board.x, k, shade);
+        }
+        if walled_x {
+            canvas.fill_rect(x, y + band, k, (board.y - 2.0 * band).max(0.0), shade);
+            canvas.fill_rect(x + board.x - k, y + band, k, (board.y - 2.0 * band).max(0.0), shade);
+        }
+    }
+
+    if let Some((heat, intensity)) = heat {
+        draw_heat(canvas, view, heat, intensity);
+    }
+
+    // Draw a cell shrunk around its center and faded by `amount` (0..=1)
+    let scaled = |canvas: &mut C, x: i32, y: i32, amount: f32| {
+        let mut color = colors.cell;
+        let inner = size * amount;
+        let inset = (size - inner) / 2.0;
+        let p = view.cell_to_screen(x, y);
+        color.a *= amount;
+        canvas.fill_rect(p.x + inset, p.y + inset, inner, inner, color);
+    };
+
+    // Zoomed out past 1 px per cell: the density picture, which costs the cells that changed
+    // rather than the cells alive, and no grid lines, which would cover the board
+    if view.shrink > 1 {
+        let mut density = view.density.borrow_mut();
+        let map = match density.as_mut() {
+            Some(map) if map.shrink == view.shrink => map,
+            _ => density.insert(DensityMap::new(view.shrink)),
+        };
+        map.sync(sim, colors.cell);
+        canvas.density(map, view.offset.x, view.offset.y);
+        canvas.stroke_rect(view.offset.x, view.offset.y, board.x, board.y, 3.0, colors.border);
+        return;
+    }
+
+    // Draw all living cells
+    for &Position(x, y) in &sim.live {
+        match diff {
+            Some(d) if d.born.contains(&Position(x, y)) => scaled(canvas, x, y, t),
+            _ => {
+                let p = view.cell_to_screen(x, y);
+                canvas.fill_rect(p.x, p.y, size, size, colors.cell);
+            }
+        }
+    }
+    if let Some(d) = diff {
+        for &Position(x, y) in d.died.iter().filter(|p| !sim.live.contains(p)) {
+            scaled(canvas, x, y, 1.0 - t);
+        }
+    }
+
+    // Draw grid lines if enabled
+    if view.grid_style.mode != GridMode::Off {
+        canvas.grid_lines(view, &sim.grid, &colors);
+    }
+
+    // Draw game border
+    canvas.stroke_rect(view.offset.x, view.offset.y, board.x, board.y, 3.0, colors.border);
+}
+
+/// Diff colors: cells only on the primary board, and only on the other one. Cells on both
+/// keep the theme's cell color.
+pub const DIFF_ONLY_PRIMARY: Color = Color::new(0.3, 0.8, 0.35, 1.0);
+pub const DIFF_ONLY_SECONDARY: Color = Color::new(0.9, 0.28, 0.28, 1.0);
+
+/// Draw a board diff in place of the board, on any canvas: the grid, cells on both boards in
+/// the theme's cell color and the rest in the diff colors
+pub fn draw_diff<C: Canvas>(canvas: &mut C, grid: &Grid, view: &BoardView, diff: &BoardDiff) {
+    let colors = view.theme.colors();
+    canvas.clear(colors.background);
+    let size = view.scale();
+    let board = view.board_size(grid);
+    if let Some(backdrop) = &view.backdrop {
+        canvas.backdrop(backdrop, view.offset.x, view.offset.y, board.x, board.y);
+    }
+    for (cells, color) in [(&diff.both, colors.cell), (&diff.only_a, DIFF_ONLY_PRIMARY), (&diff.only_b, DIFF_ONLY_SECONDARY)] {
+        for p in cells {
+            let s = view.cell_to_screen(p.x(), p.y());
+            canvas.fill_rect(s.x, s.y, size, size, color);
+        }
+    }
+    if view.grid_style.mode != GridMode::Off && view.shrink == 1 {
+        canvas.grid_lines(view, grid, &colors);
+    }
+    canvas.stroke_rect(view.offset.x, view.offset.y, board.x, board.y, 3.0, colors.border);
+}
+
+/// Draw a board diff to the window in place of the board
+pub fn render_diff(grid: &Grid, view: &BoardView, diff: &BoardDiff) {
+    draw_diff(&mut Screen, grid, view, diff);
+}
+
+/// Wash each heat block in a warm color, more opaque the hotter it is. Blocks too faint to
+/// see and blocks off screen are skipped.
+fn draw_heat<C: Canvas>(canvas: &mut C, view: &BoardView, heat: &HeatField, intensity: f32) {
+    let side = heat.block as f32 * view.scale();
+    let (sw, sh) = (screen_width(), screen_height());
+    for row in 0..heat.rows {
+        for col in 0..heat.cols {
+            let p = view.cell_to_screen(col * heat.block, row * heat.block);
+            if p.x > sw || p.y > sh || p.x + side < 0.0 || p.y + side < 0.0 {
+                continue;
+            }
+            let alpha = heat.glow(col, row) * intensity;
+            if alpha >= HEAT_FAINTEST {
+                canvas.fill_rect(p.x, p.y, side, side, Color::new(1.0, 0.45, 0.1, alpha));
+            }
+        }
+    }
+}
+
+/// Draw heads-up display with game information
+pub fn render_hud(sim: &Simulation, view: &BoardView, paused: bool, speed: f32) {
+    render_status(sim, view, paused, speed, None);
+}
+
+/// The HUD's status lines, with the simulation time, follow mode, growth trend and frame pacing
+/// when the caller has a frame to take them from
+fn render_status(sim: &Simulation, view: &BoardView, paused: bool, speed: f32, frame: Option<&Frame>) {
+    let time = frame.map(|f| f.time);
+    let follow = frame.and_then(|f| f.follow);
+    let growth = frame.and_then(|f| f.growth);
+    let pacing = frame.and_then(|f| f.pacing);
+    let diff = frame.and_then(|f| f.diff);
+    let colors = view.theme.colors();
+    let (mx, my) = mouse_position();
+    let (cx, cy) = view.screen_to_cell(mx, my);
+    // Display game statistics and controls
+    let info = format!(
+        "Gen:{}{} | FPS:{:.0}{} | {} | speed:{:.1} gen/s{}{}{} | density:{:.2} | board:{}x{} @{}px | grid:{} | wrap:{}{} | Theme:{} | seed:{} | mem:{} | xy:{} {}",
+        if sim.editing() { "-".to_string() } else { sim.generation.to_string() },
+        time.map_or(String::new(), |t| format!(" | t:{:.1}s", t)),
+        get_fps() as f32,
+        pacing.map_or(String::new(), |l| format!(" ({})", l.label())),
+        if sim.editing() { "EDIT" } else if paused { "PAUSED" } else { "RUN" },
+        speed,
+        follow.map_or(String::new(), |f| format!(" | follow:{}", f)),
+        growth.map_or(String::new(), |g| format!(" | growth:{}", g)),
+        diff.map_or(String::new(), |d: DiffCounts| format!(" | diff:+{} -{} ={}", d.only_a, d.only_b, d.both)),
+        sim.density,
+        sim.grid.width, sim.grid.height, view.zoom_label(),
+        view.grid_style.name(),
+        match sim.grid.topology {
+            Topology::Bounded => "off".to_string(),
+            Topology::Torus => "on".to_string(),
+            torus @ Topology::TorusShifted { .. } => format!("on {}", torus.shift_label()),
+            cylinder => cylinder.to_string(),
+        },
+        // Only worth the space when it isn't Life
+        if sim.grid.rule == Rule::LIFE { String::new() } else { format!(" | rule:{}", sim.grid.rule) },
+        view.theme.name(),
+        sim.seed,
+        MemoryUsage::format(frame.and_then(|f| f.memory).unwrap_or_else(|| sim.memory_usage()).total()),
+        view.format_cell(&sim.grid, cx, cy),
+        view.coords.name(),
+    );
+    draw_text(&info, 10.0, 22.0, 22.0, colors.text);
+    if sim.edge_spawn {
+        let text = if sim.grid.topology.is_torus() {
+            "edge spawn: paused (a torus has no border)".to_string()
+        } else if sim.culls() {
+            "edge spawn: paused (the lethal margin would cull it)".to_string()
+        } else {
+            format!("edge spawn: {:.1}%/cell | injected:{}", sim.edge_rate * 100.0, sim.injected)
+        };
+        draw_text(&text, 10.0, screen_height() - 12.0, 18.0, colors.text_secondary);
+    }
+    if sim.lethal_margin > 0 {
+        let text = if sim.grid.topology.is_torus() {
+            "lethal margin: off on a torus".to_string()
+        } else {
+            format!("lethal margin: {} cells", sim.lethal_margin)
+        };
+        draw_text(&text, 10.0, screen_height() - if sim.edge_spawn { 32.0 } else { 12.0 }, 18.0, colors.text_secondary);
+    }
+
+}
+
+/// Every control of the simulation screen, as shown by the help layer
+pub const CONTROLS: &str = "Space:Pause | N:Step | Shift+N:Next-gen preview | R:Restart from gen 0 | Shift+R:Random soup | Ctrl+R:Re-roll seed | U:Mark as gen 0 | -/=:Speed | Shift+-/=:Density | Ctrl+-/=:Zoom at mouse | Ctrl+1/2/3:Fit board/100%/4x | Ctrl+T:New tab | Ctrl+Tab:Next tab | Ctrl+W:Close tab | Ctrl+C/Ctrl+V:Copy selection/Paste as stamp | Ctrl+E:Rule editor | Shift+E:Rule mutations | C:Clear menu | G:Grid lines | Shift+G:Dotted | Ctrl+G:Major 5/10 | V:Smooth | Shift+V:Particles | B:Edge debug | Z:Fit view | Shift+Z:Crop to content | W:Edges (bounded, torus, cylinders) | T:Theme | Shift+T:Theme picker | I:Isolate | K:Census | X:Clean up debris | Shift+X:Undo cleanup | /:Time-lapse | Ctrl+B:Bookmark | Shift+B:Bookmarks | .:Note at mouse | Shift+.:Notes list | Ctrl+.:Hide notes | Ctrl+D:Compare with gen 0 | Shift+D:Swap diff sides | H:Ages | Shift+H:Heat | O:Edge spawn | Y:Coordinates | Shift+Y:Ruler | Ctrl+Z:Follow (Ctrl+click:Follow object) | Tab:Stamp (D:Phase Arrows:Nudge Shift+Arrows:Aim X:Clear footprint Enter:Place) | A:Last stamp | 1-0:Hotbar stamp (Shift+1-0:Assign) | J:Go to gen | S:Seed | Ctrl+S:Save project | Ctrl+Shift+S:Export RLE | M/Shift+M (paused):Mark reference/Save deltas | `:Console | F5:Reload themes and script | F10:Debug dump | Esc:Menu | Mouse:Draw/Erase | Right-drag:Select";
+
+/// The HUD's status lines as a layer
+pub struct Hud;
+
+impl Overlay for Hud {
+    fn layer(&self) -> Layer {
+        Layer::Hud
+    }
+
+    fn draw(&self, frame: &Frame) {
+        render_status(frame.sim, frame.view, frame.paused, frame.speed, Some(frame));
+        if let Some(schedule) = frame.schedule {
+            render_schedule(schedule, frame.sim.generation, &frame.view.theme.colors());
+        }
+    }
+}
+
+/// Generations the schedule timeline spans: a quarter past the last breakpoint, and on to the
+/// current generation once the run gets further
+pub fn schedule_span(schedule: &SpeedSchedule, generation: u64) -> u64 {
+    let last = schedule.points().last().map_or(0, |&(g, _)| g);
+    (last + (last / 4).max(10)).max(generation)
+}
+
+/// The speed schedule as a small timeline: the speed over generations, a marker and speed for
+/// each breakpoint, and a line at the current generation
+fn render_schedule(schedule: &SpeedSchedule, generation: u64, colors: &ThemeColors) {
+    let (w, h) = SCHEDULE_STRIP;
+    let (x, y) = (screen_width() - w - 10.0, screen_height() - h - 10.0);
+    let span = schedule_span(schedule, generation) as f32;
+    let top = schedule.points().iter().fold(SPEED_MIN, |m, &(_, s)| m.max(s));
+    let at = |g: f32| x + g / span * w;
+    let level = |speed: f32| y + h - speed / top * (h - 14.0);
+
+    let mut bg = colors.background;
+    bg.a = 0.85;
+    draw_rectangle(x, y, w, h, bg);
+    draw_rectangle_lines(x, y, w, h, 1.0, colors.border);
+    let mut previous: Option<Vec2> = None;
+    for px in 0..=w as i32 {
+        let point = vec2(x + px as f32, level(schedule.speed_at((px as f32 / w * span) as u64)));
+        if let Some(p) = previous { draw_line(p.x, p.y, point.x, point.y, 1.0, colors.text_secondary); }
+        previous = Some(point);
+    }
+    for &(g, speed) in schedule.points() {
+        let mx = at(g as f32);
+        draw_triangle(vec2(mx, y + h - 6.0), vec2(mx - 4.0, y + h), vec2(mx + 4.0, y + h), colors.text);
+        draw_text(&format!("{}", speed), mx + 2.0, y + 11.0, 14.0, colors.text_secondary);
+    }
+    let now = at(generation as f32);
+    draw_line(now, y, now, y + h, 2.0, colors.border);
+}
+
+/// Column and row numbers along the top and left edges of the board, on the major grid lines.
+/// Labels thin out as cells shrink so they never overlap, and the hovered column and row are
+/// picked out.
+pub struct Ruler;
+
+impl Ruler {
+    /// Cells between labels: the major line spacing, doubled until labels `extent` pixels long
+    /// fit between neighbours
+    pub fn spacing(cell: i32, major_every: i32, extent: f32) -> i32 {
+        let mut every = major_every.max(1);
+        while ((every * cell) as f32) < extent + RULER_GAP {
+            every *= 2;
+        }
+        every
+    }
+}
+
+impl Overlay for Ruler {
+    fn layer(&self) -> Layer {
+        Layer::Ruler
+    }
+
+    fn draw(&self, frame: &Frame) {
+        if frame.view.ruler && frame.view.cell >= RULER_MIN_CELL {
+            render_ruler(&frame.sim.grid, frame.view, mouse_position());
+        }
+    }
+}
+
+/// Draw the ruler strips, keeping them on screen below the HUD when the board extends past it
+fn render_ruler(grid: &Grid, view: &BoardView, mouse: (f32, f32)) {
+    const PAD: f32 = 3.0;
+    let colors = view.theme.colors();
+    let font = RULER_FONT as u16;
+    let width_of = |text: &str| measure_text(text, None, font, 1.0).width;
+    let column = |x: i32| view.coords.to_display(grid, x, 0).0.to_string();
+    let row = |y: i32| view.coords.to_display(grid, 0, y).1.to_string();
+    let widest_column = width_of(&column(0)).max(width_of(&column(grid.width - 1)));
+    let widest_row = width_of(&row(0)).max(width_of(&row(grid.height - 1)));
+
+    let board = view.board_size(grid);
+    let (left, top) = (view.offset.x.max(0.0), view.offset.y.max(HUD_HEIGHT));
+    let (right, bottom) = ((view.offset.x + board.x).min(screen_width()), (view.offset.y + board.y).min(screen_height()));
+    if right <= left || bottom <= top { return; }
+    let (strip_h, strip_w) = (RULER_FONT + 2.0 * PAD, widest_row + 2.0 * PAD);
+    let mut bg = colors.background;
+    bg.a = 0.8;
+    draw_rectangle(left, top, right - left, strip_h, bg);
+    draw_rectangle(left, top + strip_h, strip_w, bottom - top - strip_h, bg);
+
+    // Only the cells on screen are labelled
+    let (first_x, first_y) = view.screen_to_cell(left + strip_w, top + strip_h);
+    let (last_x, last_y) = view.screen_to_cell(right, bottom);
+    let every = Ruler::spacing(view.cell, view.grid_style.major_every, widest_column + PAD);
+    for x in (first_x.max(0)..=last_x.min(grid.width - 1)).filter(|x| x % every == 0) {
+        let sx = view.offset.x + (x * view.cell) as f32;
+        if sx >= left + strip_w {
+            draw_text(&column(x), sx + PAD, top + PAD + RULER_FONT * 0.8, RULER_FONT, colors.text_secondary);
+        }
+    }
+    let every = Ruler::spacing(view.cell, view.grid_style.major_every, RULER_FONT);
+    for y in (first_y.max(0)..=last_y.min(grid.height - 1)).filter(|y| y % every == 0) {
+        let sy = view.offset.y + (y * view.cell) as f32;
+        if sy >= top + strip_h {
+            draw_text(&row(y), left + PAD, sy + RULER_FONT * 0.8, RULER_FONT, colors.text_secondary);
+        }
+    }
+
+    // The hovered cell's column and row, boxed over the regular labels
+    let (hx, hy) = view.screen_to_cell(mouse.0, mouse.1);
+    if !grid.in_bounds(hx, hy) { return; }
+    bg.a = 1.0;
+    let cx = view.offset.x + (hx as f32 + 0.5) * view.cell as f32;
+    if (left + strip_w..right).contains(&cx) {
+        let (text, w) = (column(hx), width_of(&column(hx)));
+        let x = (cx - w / 2.0 - PAD).max(left + strip_w);
+        draw_rectangle(x, top, w + 2.0 * PAD, strip_h, bg);
+        draw_rectangle_lines(x, top, w + 2.0 * PAD, strip_h, 1.0, colors.text);
+        draw_text(&text, x + PAD, top + PAD + RULER_FONT * 0.8, RULER_FONT, colors.text);
+    }
+    let cy = view.offset.y + (hy as f32 + 0.5) * view.cell as f32;
+    if (top + strip_h..bottom).contains(&cy) {
+        let y = (cy - strip_h / 2.0).max(top + strip_h);
+        draw_rectangle(left, y, strip_w, strip_h, bg);
+        draw_rectangle_lines(left, y, strip_w, strip_h, 1.0, colors.text);
+        draw_text(&row(hy), left + PAD, y + PAD + RULER_FONT * 0.8, RULER_FONT, colors.text);
+    }
+}
+
+/// Draw the one-line controls summary under the HUD
+pub fn render_help(view: &BoardView) {
+    let text = format!("Controls: {} | F1:All controls", CONTROLS);
+    draw_text(&text, 10.0, 46.0, 18.0, view.theme.colors().text_secondary);
+}
+
+/// The controls line under the HUD. F1 expands it into a panel listing every control, which
+/// holds all input until F1 or Escape closes it.
+#[derive(Default)]
+pub struct Help {
+    pub open: bool,
+}
+
+impl Overlay for Help {
+    fn layer(&self) -> Layer {
+        Layer::Help
+    }
+
+    fn handle_input(&mut self, input: &dyn Input) -> InputConsumed {
+        if input.key_pressed(KeyCode::F1) || (self.open && input.key_pressed(KeyCode::Escape)) {
+            self.open = !self.open;
+            return InputConsumed::Yes;
+        }
+        if self.open { InputConsumed::Yes } else { InputConsumed::No }
+    }
+
+    fn draw(&self, frame: &Frame) {
+        if !self.open {
+            render_help(frame.view);
+            return;
+        }
+        const ROW: f32 = 20.0;
+        let colors = frame.view.theme.colors();
+        let items: Vec<&str> = CONTROLS.split(" | ").collect();
+        let rows = ((screen_height() - 100.0) / ROW).max(1.0) as usize;
+        let columns = items.len().div_ceil(rows);
+        let column_w = (screen_width() - 40.0) / columns as f32;
+        let mut bg = colors.background;
+        bg.a = 0.95;
+        draw_rectangle(10.0, 10.0, screen_width() - 20.0, screen_height() - 20.0, bg);
+        draw_rectangle_lines(10.0, 10.0, screen_width() - 20.0, screen_height() - 20.0, 2.0, colors.border);
+        draw_text("Controls (F1 or Esc to close)", 20.0, 40.0, 24.0, colors.text);
+        for (i, item) in items.iter().enumerate() {
+            let (column, row) = (i / rows, i % rows);
+            draw_text(item, 20.0 + column as f32 * column_w, 70.0 + row as f32 * ROW, 18.0, colors.text);
+        }
+    }
+}
+
+/// Draw translucent preview cells for a pending stamp
+pub fn render_ghost(view: &BoardView, cells: &HashSet<Position>) {
+    let mut color = view.theme.colors().cell;
+    color.a = 0.45;
+    let size = view.scale();
+    for p in cells {
+        let s = view.cell_to_screen(p.x(), p.y());
+        draw_rectangle(s.x, s.y, size, size, color);
+    }
+}
+
+/// Outline the cells the next generation changes: births in the diff's first color, deaths in
+/// its second
+pub fn render_next_preview(view: &BoardView, fates: &[(Position, CellFate)]) {
+    let size = view.scale();
+    for &(p, fate) in fates {
+        let color = match fate {
+            CellFate::Born => DIFF_ONLY_PRIMARY,
+            CellFate::Dies => DIFF_ONLY_SECONDARY,
+            CellFate::Survives | CellFate::StaysDead => continue,
+        };
+        let s = view.cell_to_screen(p.x(), p.y());
+        draw_rectangle_lines(s.x, s.y, size, size, 2.0, color);
+    }
+}
+
+/// Arrow from `center` (in cells) pointing the way a stamped ship or gun's shots will travel
+pub fn render_heading(view: &BoardView, center: Vec2, heading: (i32, i32)) {
+    let color = view.theme.colors().border;
+    let from = view.offset + center * view.scale();
+    let dir = vec2(heading.0 as f32, heading.1 as f32).normalize_or_zero();
+    let length = (view.scale() * 4.0).max(36.0);
+    let tip = from + dir * length;
+    draw_line(from.x, from.y, tip.x, tip.y, 3.0, color);
+    // Two barbs swept back from the tip
+    for side in [-1.0, 1.0] {
+        let barb = tip - dir * 12.0 + vec2(-dir.y, dir.x) * 7.0 * side;
+        draw_line(tip.x, tip.y, barb.x, barb.y, 3.0, color);
+    }
+}
+
+/// Tint ghost cells that would land on live cells
+pub fn render_conflicts(view: &BoardView, cells: &HashSet<Position>) {
+    let mut color = ORANGE;
+    color.a = 0.85;
+    let size = view.scale();
+    for p in cells {
+        let s = view.cell_to_screen(p.x(), p.y());
+        draw_rectangle(s.x, s.y, size, size, color);
+    }
+}
+
+/// Outline the cells a clearing stamp will erase
+pub fn render_footprint(view: &BoardView, cells: &HashSet<Position>) {
+    let color = view.theme.colors().text_secondary;
+    let size = view.scale();
+    for p in cells {
+        let s = view.cell_to_screen(p.x(), p.y());
+        draw_rectangle_lines(s.x, s.y, size, size, 1.0, color);
+    }
+}
+
+/// Outline a region of cells on the board
+pub fn render_region(view: &BoardView, region: Rect, color: Color) {
+    let p = view.cell_to_screen(region.x, region.y);
+    draw_rectangle_lines(
+        p.x, p.y,
+        region.w as f32 * view.scale(), region.h as f32 * view.scale(),
+        2.0, color,
+    );
+}
+
+/// Debug view of the edges: the ring of cells just outside the board shows what border
+/// cells see there under the current topology, and border cells show their neighbor count.
+/// The board hash is shown under the HUD.
+pub fn render_ghost_border(sim: &Simulation, view: &BoardView) {
+    let colors = view.theme.colors();
+    let size = view.scale();
+    let (w, h) = (sim.grid.width, sim.grid.height);
+    let ring = (-1..=w).flat_map(|x| [(x, -1), (x, h)])
+        .chain((0..h).flat_map(|y| [(-1, y), (w, y)]));
+    for (x, y) in ring {
+        let s = view.cell_to_screen(x, y);
+        match sim.grid.resolve(x, y) {
+            // Past a wrapping edge, the wrapped cell a neighbor would actually read, shift included;
+            // a cylinder's walled edges show as walls
+            Some(p) if sim.live.contains(&p) => {
+                let mut ghost = colors.cell;
+                ghost.a = 0.35;
+                draw_rectangle(s.x, s.y, size, size, ghost);
+            }
+            Some(_) => draw_rectangle_lines(s.x, s.y, size, size, 1.0, colors.grid),
+            None => {
+                let mut wall = colors.border;
+                wall.a = 0.35;
+                draw_line(s.x, s.y, s.x + size, s.y + size, 1.0, wall);
+            }
+        }
+    }
+    // The same hash `--hash-every` prints, to compare a run with another machine's
+    let hash = format!("board hash {:016x} at generation {}", board_hash(&sim.live), sim.generation);
+    draw_text(&hash, 10.0, 66.0, 18.0, colors.text_secondary);
+    if view.cell < 10 { return; }
+    let border = (0..w).flat_map(|x| [(x, 0), (x, h - 1)])
+        .chain((1..h - 1).flat_map(|y| [(0, y), (w - 1, y)]));
+    for (x, y) in border {
+        let n = sim.grid.neighbor_count(&sim.live, Position::new(x, y));
+        let s = view.cell_to_screen(x, y);
+        draw_text(&n.to_string(), s.x + 1.0, s.y + size - 1.0, size, colors.text);
+    }
+}
+
+/// Draw a marker on each cell with a note pinned to it, and the text of the note under the
+/// mouse. `selected` is outlined, for the notes list.
+pub fn render_notes(notes: &Annotations, view: &BoardView, selected: Option<usize>) {
+    let colors = view.theme.colors();
+    let size = view.scale();
+    let radius = (size * 0.35).max(3.0);
+    let (mx, my) = mouse_position();
+    let center = |at: Position| {
+        let s = view.cell_to_screen(at.x(), at.y());
+        vec2(s.x + size / 2.0, s.y + size / 2.0)
+    };
+    let mut hovered = None;
+    for (i, note) in notes.notes.iter().enumerate() {
+        let c = center(note.at);
+        if c.x < -radius || c.y < -radius || c.x > screen_width() + radius || c.y > screen_height() + radius {
+            continue;
+        }
+        draw_circle(c.x, c.y, radius, note.color.color());
+        draw_circle_lines(c.x, c.y, radius, 1.0, colors.background);
+        if selected == Some(i) {
+            draw_circle_lines(c.x, c.y, radius + 3.0, 2.0, colors.border);
+        }
+        if vec2(mx, my).distance(c) <= radius.max(size / 2.0) {
+            hovered = Some(i);
+        }
+    }
+    // The hovered note's text, or the selected one's when the mouse is elsewhere
+    if let Some(note) = hovered.or(selected).and_then(|i| notes.notes.get(i)) {
+        let center = center(note.at);
+        let w = measure_text(&note.text, None, 18, 1.0).width + 12.0;
+        let x = (center.x + radius + 4.0).min(screen_width() - w).max(0.0);
+        let y = (center.y - radius - 26.0).max(HUD_HEIGHT);
+        let mut bg = colors.background;
+        bg.a = 0.92;
+        draw_rectangle(x, y, w, 24.0, bg);
+        draw_rectangle_lines(x, y, w, 24.0, 1.0, note.color.color());
+        draw_text(&note.text, x + 6.0, y + 17.0, 18.0, colors.text);
+    }
+}
+
+/// Draw the rule inspector for the hovered cell: neighbor outlines plus a verdict panel
+pub fn render_inspector(sim: &Simulation, view: &BoardView, x: i32, y: i32) {
+    let Some(verdict) = sim.inspect(x, y) else { return; };
+    let colors = view.theme.colors();
+    let size = view.scale();
+
+    // Outline the neighbors that actually exist under the current topology
+    for p in sim.grid.neighbors_of(Position::new(x, y)) {
+        let color = if sim.live.contains(&p) { colors.cell } else { colors.text_secondary };
+        let s = view.cell_to_screen(p.x(), p.y());
+        draw_rectangle_lines(s.x, s.y, size, size, 1.0, color);
+    }
+    if let Some(p) = sim.grid.resolve(x, y) {
+        let s = view.cell_to_screen(p.x(), p.y());
+        draw_rectangle_lines(s.x, s.y, size, size, 2.0, colors.border);
+    }
+
+    // Verdict panel in the bottom-left corner
+    let lines = [
+        format!("Cell {}", view.format_cell(&sim.grid, x, y)),
+        verdict.summary(),
+        format!("Next: {}", if verdict.alive_next { "alive" } else { "dead" }),
+    ];
+    let panel_w = 320.0;
+    let panel_h = 18.0 * lines.len() as f32 + 12.0;
+    let px = 10.0;
+    let py = screen_height() - panel_h - 10.0;
+    let mut bg = colors.background;
+    bg.a = 0.85;
+    draw_rectangle(px, py, panel_w, panel_h, bg);
+    draw_rectangle_lines(px, py, panel_w, panel_h, 1.0, colors.border);
+    for (i, line) in lines.iter().enumerate() {
+        draw_text(line, px + 8.0, py + 20.0 + i as f32 * 18.0, 18.0, colors.text);
+    }
+}
@@ -0,0 +1,106 @@
+//! Outer-totalistic rules in B/S notation: which neighbour counts bring a dead cell to life
+//! and which keep a live one alive. Conway's Life is B3/S23.
+
+use std::fmt;
+
+use crate::error::GolError;
+
+/// Birth and survival neighbour counts, one bit per count 0 to 8
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct Rule {
+    pub birth: u16,
+    pub survive: u16,
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::LIFE
+    }
+}
+
+impl Rule {
+    pub const LIFE: Rule = Rule { birth: 1 << 3, survive: (1 << 2) | (1 << 3) };
+
+    #[inline]
+    pub fn born(self, neighbors: u8) -> bool {
+        self.birth & (1 << neighbors) != 0
+    }
+
+    #[inline]
+    pub fn survives(self, neighbors: u8) -> bool {
+        self.survive & (1 << neighbors) != 0
+    }
+
+    /// Whether a cell with this state and neighbour count is alive next generation
+    #[inline]
+    pub fn next(self, alive: bool, neighbors: u8) -> bool {
+        if alive { self.survives(neighbors) } else { self.born(neighbors) }
+    }
+
+    /// B0: empty space is born everywhere at once, so the background flashes between
+    /// generations. The sparse engine only visits cells near live ones and can't run it.
+    pub fn strobes(self) -> bool {
+        self.born(0)
+    }
+
+    /// The rule if the engine can run it, or why not
+    pub fn runnable(self) -> Result<Rule, GolError> {
+        if self.strobes() {
+            return Err(GolError::invalid("rule", format!("{} has B0, which turns all empty space on every other generation; B0 rules are not supported", self)));
+        }
+        Ok(self)
+    }
+
+    /// Parse `B3/S23` style rulestrings in any case and order, or the older `23/3` survival/birth form
+    pub fn parse(text: &str) -> Result<Rule, GolError> {
+        let text = text.trim();
+        let invalid = || GolError::invalid("rule", format!("'{}' is not a rulestring like B3/S23", text));
+        let (first, second) = text.split_once('/').ok_or_else(invalid)?;
+        let counts = |digits: &str| -> Result<u16, GolError> {
+            digits.chars().try_fold(0u16, |bits, c| match c.to_digit(10) {
+                Some(n @ 0..=8) => Ok(bits | 1 << n),
+                _ => Err(invalid()),
+            })
+        };
+        let tagged = |part: &str| part.chars().next().map(|c| c.to_ascii_uppercase());
+        match (tagged(first), tagged(second)) {
+            (Some('B'), Some('S')) => Ok(Rule { birth: counts(&first[1..])?, survive: counts(&second[1..])? }),
+            (Some('S'), Some('B')) => Ok(Rule { birth: counts(&second[1..])?, survive: counts(&first[1..])? }),
+            _ => Ok(Rule { birth: counts(second)?, survive: counts(first)? }),
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = |bits: u16| (0..=8).filter(|n| bits & (1 << n) != 0).map(|n| char::from(b'0' + n as u8)).collect::<String>();
+        write!(f, "B{}/S{}", digits(self.birth), digits(self.survive))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn both_notations_in_any_case_and_order() {
+        for text in ["B3/S23", "b3/s23", "S23/B3", "23/3", " B3/S32 "] {
+            assert_eq!(Rule::parse(text).unwrap(), Rule::LIFE, "{}", text);
+        }
+        let highlife = Rule::parse("B36/S23").unwrap();
+        assert_eq!(highlife.to_string(), "B36/S23");
+        assert!(highlife.born(6) && !highlife.survives(6));
+        assert_eq!(Rule::parse("B/S").unwrap().to_string(), "B/S");
+    }
+
+    #[test]
+    fn malformed_and_strobing_rules_are_refused() {
+        for text in ["B3/S2x", "life", "B9/S23", "B3S23", ""] {
+            assert!(matches!(Rule::parse(text), Err(GolError::Invalid { what: "rule", .. })), "'{}' was accepted", text);
+        }
+        let strobing = Rule::parse("B03/S23").unwrap();
+        assert!(strobing.strobes());
+        assert!(matches!(strobing.runnable(), Err(GolError::Invalid { what: "rule", .. })));
+        assert_eq!(Rule::LIFE.runnable().unwrap(), Rule::LIFE);
+    }
+}
@@ -0,0 +1,92 @@
+//! Speed schedules: the speed to run at from given generations on, so a run can start slow
+//! enough to see the seed and speed up by itself. Written as `0:2 100:10 500:60`, optionally
+//! followed by `step` to jump between speeds instead of ramping.
+
+use std::fmt;
+
+use crate::config::{SPEED_MAX, SPEED_MIN};
+
+/// How the speed moves between breakpoints
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Interpolation {
+    Linear, // Ramp evenly from one breakpoint's speed to the next
+    Step,   // Hold each speed until the next breakpoint
+}
+
+/// Breakpoints of generation and speed, in generation order
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpeedSchedule {
+    points: Vec<(u64, f32)>,
+    pub interpolation: Interpolation,
+}
+
+impl SpeedSchedule {
+    /// Parse `generation:speed` pairs in increasing generation order, then `linear` (the
+    /// default) or `step`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut points: Vec<(u64, f32)> = Vec::new();
+        let mut interpolation = Interpolation::Linear;
+        let mut words = text.split_whitespace().peekable();
+        while let Some(word) = words.next() {
+            match word.to_ascii_lowercase().as_str() {
+                "linear" | "step" if words.peek().is_some() => return Err(format!("'{}' goes after the breakpoints", word)),
+                "linear" => interpolation = Interpolation::Linear,
+                "step" => interpolation = Interpolation::Step,
+                _ => {
+                    let invalid = || format!("'{}' is not a generation:speed breakpoint like 100:60", word);
+                    let (generation, speed) = word.split_once(':').ok_or_else(invalid)?;
+                    let (generation, speed): (u64, f32) = (generation.parse().map_err(|_| invalid())?, speed.parse().map_err(|_| invalid())?);
+                    if !(SPEED_MIN..=SPEED_MAX).contains(&speed) {
+                        return Err(format!("speed {} is outside {} to {}", speed, SPEED_MIN, SPEED_MAX));
+                    }
+                    if points.last().is_some_and(|&(last, _)| generation <= last) {
+                        return Err(format!("breakpoint {} must come after generation {}", word, points[points.len() - 1].0));
+                    }
+                    points.push((generation, speed));
+                }
+            }
+        }
+        if points.is_empty() {
+            return Err("a schedule needs at least one generation:speed breakpoint".to_string());
+        }
+        Ok(Self { points, interpolation })
+    }
+
+    pub fn points(&self) -> &[(u64, f32)] {
+        &self.points
+    }
+
+    /// Speed at `generation`: the first breakpoint's speed before it and the last one's after it
+    pub fn speed_at(&self, generation: u64) -> f32 {
+        let next = self.points.partition_point(|&(g, _)| g <= generation);
+        let Some(&(from, from_speed)) = next.checked_sub(1).map(|i| &self.points[i]) else {
+            return self.points[0].1;
+        };
+        match (self.points.get(next), self.interpolation) {
+            (Some(&(to, to_speed)), Interpolation::Linear) => {
+                let t = (generation - from) as f32 / (to - from) as f32;
+                from_speed + (to_speed - from_speed) * t
+            }
+            _ => from_speed,
+        }
+    }
+
+    /// The next breakpoint after `generation`, if any is left
+    pub fn next_after(&self, generation: u64) -> Option<(u64, f32)> {
+        self.points.iter().copied().find(|&(g, _)| g > generation)
+    }
+}
+
+// Speeds are only ever parsed within SPEED_MIN..=SPEED_MAX, so none is NaN
+impl Eq for SpeedSchedule {}
+
+impl fmt::Display for SpeedSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let points: Vec<String> = self.points.iter().map(|(g, s)| format!("{}:{}", g, s)).collect();
+        write!(f, "{}", points.join(" "))?;
+        if self.interpolation == Interpolation::Step {
+            write!(f, " step")?;
+        }
+        Ok(())
+    }
+}
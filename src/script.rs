@@ -0,0 +1,311 @@
+//! Script hooks: a Rhai script whose `init()` runs when it is loaded and whose
+//! `on_generation()` runs after every generation, to stamp, edit or pace the board without
+//! recompiling. Needs the native-only `scripting` feature; without it loading fails, saying why.
+//!
+//! Scripts see the board through a few functions: `generation()`, `population()`, `width()`,
+//! `height()`, `wrapped()`, `alive(x, y)`, `set(x, y, alive)`, `count(x, y, w, h)`,
+//! `clear(x, y, w, h)`, `stamp(name, x, y)` or `stamp(name, x, y, quarter_turns)`, `speed()`,
+//! `set_speed(gen_per_s)` and `rand(lo, hi)`, beside Rhai's own `print`, `abs`, `min` and
+//! `max`. Coordinates are grid cells from the top-left corner, y down. Rhai functions can't
+//! see top-level variables, so hooks keep values between calls in `this`, a map that lasts as
+//! long as the script. A hook that fails or runs over its budget is switched off with a
+//! message; saving the file loads it again, and a version that fails to load leaves the one
+//! that was running.
+
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Instant, SystemTime};
+
+use crate::config::SCRIPT_RELOAD_SECONDS;
+use crate::patterns::{default_registry, PatternRegistry};
+use crate::rng::Rng;
+use crate::simulation::Simulation;
+
+#[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+mod engine;
+
+/// Whether this build can run scripts
+pub const AVAILABLE: bool = cfg!(all(feature = "scripting", not(target_arch = "wasm32")));
+
+#[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+use engine::Engine;
+
+const UNAVAILABLE: &str = "this build can't run scripts; rebuild with --features scripting (not available on the web)";
+
+/// Stands in for the Rhai engine in builds without it; never constructed
+#[cfg(not(all(feature = "scripting", not(target_arch = "wasm32"))))]
+enum Engine {}
+
+/// A loaded script and what it has asked of the run
+pub struct ScriptHook {
+    pub path: PathBuf,
+    engine: Option<Engine>, // None once the hook has been switched off
+    modified: Option<SystemTime>, // File time the running version was read at
+    checked: Instant,             // Last time the file was looked at for changes
+    host: Rc<RefCell<Host>>,      // Shared with the functions registered on the engine
+}
+
+/// What the board functions work on: the run's board while a hook call lasts, and what the
+/// hook keeps between calls
+#[cfg_attr(not(all(feature = "scripting", not(target_arch = "wasm32"))), allow(dead_code))] // Only the engine reads some fields
+struct Host {
+    sim: Simulation,           // The caller's board, swapped in for each call
+    registry: PatternRegistry, // Patterns `stamp` can place
+    rng: Rng,
+    messages: Vec<String>, // `print` output and errors, for the caller to show
+    speed: Option<f32>,    // Speed asked for with `set_speed`, not yet taken by the caller
+    running: f32,          // Speed the run is going at
+}
+
+impl ScriptHook {
+    /// Read and compile the script at `path`, then run its top level and `init()` on `sim`
+    pub fn load(path: &Path, sim: &mut Simulation, speed: f32) -> Result<ScriptHook, String> {
+        if !AVAILABLE {
+            return Err(UNAVAILABLE.to_string());
+        }
+        let mut hook = ScriptHook::new(path, sim);
+        hook.reload(sim, speed)?;
+        Ok(hook)
+    }
+
+    /// Like `load`, for a script that isn't in a file; `name` labels its messages
+    pub fn from_source(name: &str, source: &str, sim: &mut Simulation, speed: f32) -> Result<ScriptHook, String> {
+        let mut hook = ScriptHook::new(name.as_ref(), sim);
+        hook.start(source, sim, speed)?;
+        Ok(hook)
+    }
+
+    fn new(path: &Path, sim: &Simulation) -> Self {
+        ScriptHook {
+            path: path.to_path_buf(),
+            engine: None,
+            modified: None,
+            checked: Instant::now(),
+            host: Rc::new(RefCell::new(Host {
+                sim: Simulation::new(1, 1),
+                registry: default_registry(),
+                rng: Rng::new(sim.seed),
+                messages: Vec::new(),
+                speed: None,
+                running: 0.0,
+            })),
+        }
+    }
+
+    /// Whether `on_generation` still runs
+    pub fn enabled(&self) -> bool {
+        self.engine.is_some()
+    }
+
+    pub fn name(&self) -> String {
+        self.path.file_name().map_or_else(|| self.path.display().to_string(), |n| n.to_string_lossy().into_owned())
+    }
+
+    /// Replace the running script with the file's current contents
+    fn reload(&mut self, sim: &mut Simulation, speed: f32) -> Result<(), String> {
+        self.engine = None;
+        self.modified = std::fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        let source = std::fs::read_to_string(&self.path).map_err(|e| format!("{}: {}", self.path.display(), e))?;
+        self.start(&source, sim, speed)
+    }
+
+    /// Compile `source` and run its top level and `init()`, making it the running script
+    fn start(&mut self, source: &str, sim: &mut Simulation, speed: f32) -> Result<(), String> {
+        let mut engine = self.compile(source).map_err(|e| format!("{}: {}", self.name(), e))?;
+        self.invoke(&mut engine, None, sim, speed).map_err(|e| format!("{}: {}", self.name(), e))?;
+        if engine.defines("init") {
+            self.invoke(&mut engine, Some("init"), sim, speed).map_err(|e| format!("{}: init: {}", self.name(), e))?;
+        }
+        self.engine = Some(engine);
+        Ok(())
+    }
+
+    /// Load the file again if it was saved since it was read, at most every
+    /// SCRIPT_RELOAD_SECONDS. Returns a message when it did, including after a failure.
+    pub fn reload_if_changed(&mut self, sim: &mut Simulation, speed: f32) -> Option<String> {
+        if self.checked.elapsed().as_secs_f32() < SCRIPT_RELOAD_SECONDS {
+            return None;
+        }
+        self.checked = Instant::now();
+        let modified = std::fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        if modified.is_none() || modified == self.modified {
+            return None;
+        }
+        Some(self.reload_now(sim, speed))
+    }
+
+    /// Load the file again whether or not it changed, returning a message saying how it went.
+    /// If the new version fails to load, a hook that was running keeps the version it had.
+    pub fn reload_now(&mut self, sim: &mut Simulation, speed: f32) -> String {
+        let previous = self.engine.take();
+        match self.reload(sim, speed) {
+            Ok(()) => format!("Reloaded {}", self.name()),
+            Err(err) if previous.is_some() => {
+                self.engine = previous;
+                format!("Kept the running version: {}", err)
+            }
+            Err(err) => format!("Script off: {}", err),
+        }
+    }
+
+    /// Run `on_generation()` after a step; an error switches the hook off and is reported
+    pub fn after_generation(&mut self, sim: &mut Simulation, speed: f32) {
+        let Some(mut engine) = self.engine.take() else { return };
+        if !engine.defines("on_generation") {
+            self.engine = Some(engine);
+            return;
+        }
+        match self.invoke(&mut engine, Some("on_generation"), sim, speed) {
+            Ok(()) => self.engine = Some(engine),
+            Err(err) => self.host.borrow_mut().messages.push(format!("Script off: {}: generation {}: {}", self.name(), sim.generation, err)),
+        }
+    }
+
+    /// Printed lines and errors since the last call
+    pub fn take_messages(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.host.borrow_mut().messages)
+    }
+
+    /// The speed the script last asked for, once
+    pub fn take_speed(&mut self) -> Option<f32> {
+        self.host.borrow_mut().speed.take()
+    }
+
+    #[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+    fn compile(&self, source: &str) -> Result<Engine, String> {
+        Engine::compile(source, &self.host)
+    }
+
+    #[cfg(not(all(feature = "scripting", not(target_arch = "wasm32"))))]
+    fn compile(&self, _: &str) -> Result<Engine, String> {
+        Err(UNAVAILABLE.to_string())
+    }
+
+    /// Run the top level (`function` None) or one function on `sim`, under a fresh budget
+    #[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+    fn invoke(&mut self, engine: &mut Engine, function: Option<&str>, sim: &mut Simulation, speed: f32) -> Result<(), String> {
+        {
+            let mut host = self.host.borrow_mut();
+            std::mem::swap(&mut host.sim, sim);
+            host.running = speed;
+        }
+        let result = engine.run(function);
+        std::mem::swap(&mut self.host.borrow_mut().sim, sim);
+        result
+    }
+
+    #[cfg(not(all(feature = "scripting", not(target_arch = "wasm32"))))]
+    fn invoke(&mut self, engine: &mut Engine, _: Option<&str>, _: &mut Simulation, _: f32) -> Result<(), String> {
+        match *engine {}
+    }
+}
+
+#[cfg(not(all(feature = "scripting", not(target_arch = "wasm32"))))]
+impl Engine {
+    fn defines(&self, _: &str) -> bool {
+        match *self {}
+    }
+}
+
+#[cfg(all(test, feature = "scripting", not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    /// Step `sim` and run the hook `generations` times, collecting what it printed
+    fn run(hook: &mut ScriptHook, sim: &mut Simulation, generations: u32, speed: f32) -> Vec<String> {
+        let mut messages = Vec::new();
+        for _ in 0..generations {
+            sim.next_generation();
+            hook.after_generation(sim, speed);
+            messages.extend(hook.take_messages());
+        }
+        messages
+    }
+
+    #[test]
+    fn edge_gliders_example_sends_gliders_from_the_seed() {
+        let launch = || {
+            let mut sim = Simulation::new(64, 48);
+            sim.seed = 7;
+            let mut hook = ScriptHook::from_source("edge_gliders", include_str!("../examples/scripts/edge_gliders.rhai"), &mut sim, 30.0).unwrap();
+            assert_eq!(hook.take_speed(), Some(20.0));
+            let messages = run(&mut hook, &mut sim, 500, 20.0);
+            assert!(hook.enabled(), "the hook switched off: {:?}", messages);
+            assert_eq!(messages, ["launched 10 gliders"]);
+            sim.live
+        };
+        let live = launch();
+        assert!(!live.is_empty());
+        // Scripts draw from the run's seed, so the same seed gives the same board
+        assert_eq!(launch(), live);
+    }
+
+    #[test]
+    fn reseed_example_refills_an_empty_board() {
+        let mut sim = Simulation::new(80, 60);
+        let mut hook = ScriptHook::from_source("reseed", include_str!("../examples/scripts/reseed.rhai"), &mut sim, 30.0).unwrap();
+        assert_eq!(run(&mut hook, &mut sim, 1, 30.0), ["reseeded at generation 1"]);
+        assert_eq!(sim.live.len(), 5);
+        assert_eq!(hook.take_speed(), Some(10.0));
+        run(&mut hook, &mut sim, 100, 10.0);
+        assert_eq!(hook.take_speed(), Some(60.0));
+    }
+
+    #[test]
+    fn board_functions_and_state_kept_in_this() {
+        let mut sim = Simulation::new(20, 20);
+        let source = r#"
+            fn fib(n) { if n < 2 { return n; } fib(n - 1) + fib(n - 2) }
+            fn init() {
+                this.total = 0;
+                for i in 0..10 { this.total += i; }
+                print(`${fib(10)} ${this.total} ${7 / 2} ${"a" + 1} ${min(4, -1)} ${abs(-2.5)} ${speed()}`);
+                set(2, 3, true);
+                set(25, 3, true);
+                stamp("Block", 10, 10, 1);
+                print(`${alive(2, 3)} ${alive(3, 2)} ${count(0, 0, 20, 20)} ${count(9, 9, 2, 2)}`);
+                clear(0, 0, 5, 5);
+                set_speed(1000);
+            }
+            fn on_generation() { this.total += 1; print(this.total); }
+        "#;
+        let mut hook = ScriptHook::from_source("language", source, &mut sim, 30.0).unwrap();
+        assert_eq!(hook.take_messages(), ["55 45 3 a1 -1 2.5 30.0", "true false 5 2"]);
+        assert_eq!(sim.live.len(), 4);
+        assert_eq!(hook.take_speed(), Some(crate::config::SPEED_MAX));
+        sim.live.clear();
+        assert_eq!(run(&mut hook, &mut sim, 2, 30.0), ["46", "47"]);
+    }
+
+    #[test]
+    fn scripts_that_dont_compile_are_refused_with_the_line() {
+        let mut sim = Simulation::new(20, 20);
+        let refused = ScriptHook::from_source("broken", "fn on_generation() {\n  let x = ;\n}", &mut sim, 30.0).err().unwrap();
+        assert!(refused.starts_with("broken: line 2: "), "{}", refused);
+        let failed = ScriptHook::from_source("failing", "fn init() {\n  stamp(\"Nope\", 1, 1);\n}", &mut sim, 30.0).err().unwrap();
+        assert!(failed.starts_with("failing: init: line 2: unknown pattern 'Nope'"), "{}", failed);
+    }
+
+    #[test]
+    fn failing_hooks_switch_off_with_a_message() {
+        let mut sim = Simulation::new(20, 20);
+        for (name, source, reason) in [
+            ("typo", "fn on_generation() {\n  stmap(\"Glider\", 1, 1);\n}", "line 2: no function matches stmap"),
+            ("endless", "fn on_generation() { while true { } }", "budget"),
+            ("recursive", "fn f() { f(); }\nfn on_generation() { f(); }", "nested"),
+            ("pattern", "fn on_generation() { stamp(\"Nope\", 1, 1); }", "unknown pattern 'Nope'"),
+            ("range", "fn on_generation() { set(rand(3, 1), 0, true); }", "empty range 3 to 1"),
+            ("far", "fn on_generation() { set(1 << 40, 0, true); }", "out of range"),
+        ] {
+            let mut hook = ScriptHook::from_source(name, source, &mut sim, 30.0).unwrap();
+            let started = Instant::now();
+            let messages = run(&mut hook, &mut sim, 1, 30.0);
+            assert!(!hook.enabled(), "{} left the hook on", name);
+            assert!(messages.len() == 1 && messages[0].contains(reason), "{} said {:?}", name, messages);
+            assert!(started.elapsed().as_millis() < 100, "{} ran for {:?}", name, started.elapsed());
+            // Switched off, it stays off
+            assert!(run(&mut hook, &mut sim, 1, 30.0).is_empty());
+        }
+    }
+}
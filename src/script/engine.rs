@@ -0,0 +1,161 @@
+//! Running hooks with Rhai: the board functions registered on its engine, the operation, time
+//! and call-depth limits it enforces, and its errors turned into one-line messages.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use rhai::{CallFnOptions, Dynamic, EvalAltResult, Scope, AST, FLOAT, INT};
+
+use super::Host;
+use crate::config::{SCRIPT_DEPTH, SCRIPT_MILLIS, SCRIPT_OPS, SPEED_MAX, SPEED_MIN};
+use crate::grid::Rect;
+use crate::patterns::Transform;
+use crate::simulation::Simulation;
+
+type Outcome<T> = Result<T, Box<EvalAltResult>>;
+
+/// Operations between looks at the clock; reading it on every one would eat the time budget
+const CLOCK_EVERY: u64 = 1024;
+
+/// A compiled script and the engine that runs it
+pub struct Engine {
+    engine: rhai::Engine,
+    ast: AST,
+    state: Dynamic,               // `this` in every hook call: a map the script keeps its values in
+    deadline: Rc<Cell<Instant>>, // When the running call goes over SCRIPT_MILLIS
+}
+
+impl Engine {
+    /// Compile `source` against the board functions, which reach the board through `host`
+    pub fn compile(source: &str, host: &Rc<RefCell<Host>>) -> Result<Engine, String> {
+        let deadline = Rc::new(Cell::new(Instant::now()));
+        let mut engine = rhai::Engine::new();
+        engine.set_max_operations(SCRIPT_OPS).set_max_call_levels(SCRIPT_DEPTH);
+        let clock = Rc::clone(&deadline);
+        engine.on_progress(move |ops| (ops % CLOCK_EVERY == 0 && Instant::now() > clock.get()).then(Dynamic::default));
+        let printed = Rc::clone(host);
+        engine.on_print(move |text| printed.borrow_mut().messages.push(text.to_string()));
+        register(&mut engine, host);
+        let ast = engine.compile(source).map_err(|e| at(e.1, e.0.to_string()))?;
+        Ok(Engine { engine, ast, state: Dynamic::from_map(Default::default()), deadline })
+    }
+
+    /// Whether the script defines `name()` taking no arguments
+    pub fn defines(&self, name: &str) -> bool {
+        self.ast.iter_functions().any(|f| f.name == name && f.params.is_empty())
+    }
+
+    /// Run the top level (`function` None) or one function, with `this` bound to the script's state
+    pub fn run(&mut self, function: Option<&str>) -> Result<(), String> {
+        self.deadline.set(Instant::now() + Duration::from_millis(SCRIPT_MILLIS));
+        let result = match function {
+            None => self.engine.run_ast_with_scope(&mut Scope::new(), &self.ast),
+            Some(name) => {
+                let options = CallFnOptions::new().eval_ast(false).bind_this_ptr(&mut self.state);
+                self.engine.call_fn_with_options::<Dynamic>(options, &mut Scope::new(), &self.ast, name, ()).map(|_| ())
+            }
+        };
+        result.map_err(|e| describe(*e))
+    }
+}
+
+/// An error as a hook reports it, led by the line it happened on
+fn describe(mut err: EvalAltResult) -> String {
+    // An error inside a script function arrives wrapped in each call that led to it
+    while let EvalAltResult::ErrorInFunctionCall(_, _, inner, _) = err {
+        err = *inner;
+    }
+    let position = err.take_position();
+    let message = match err {
+        EvalAltResult::ErrorTooManyOperations(_) => format!("ran past its budget of {} operations", SCRIPT_OPS),
+        EvalAltResult::ErrorTerminated(..) => format!("ran past its budget of {} ms", SCRIPT_MILLIS),
+        EvalAltResult::ErrorStackOverflow(_) => format!("function calls nested more than {} deep", SCRIPT_DEPTH),
+        EvalAltResult::ErrorFunctionNotFound(signature, _) => format!("no function matches {}", signature),
+        EvalAltResult::ErrorRuntime(value, _) => value.to_string(),
+        other => other.to_string(),
+    };
+    at(position, message)
+}
+
+fn at(position: rhai::Position, message: String) -> String {
+    match position.line() {
+        Some(line) => format!("line {}: {}", line, message),
+        None => message,
+    }
+}
+
+fn coord(value: INT) -> Outcome<i32> {
+    i32::try_from(value).map_err(|_| format!("coordinate {} out of range", value).into())
+}
+
+/// A rectangle argument clipped to the grid
+fn region(sim: &Simulation, x: INT, y: INT, w: INT, h: INT) -> Outcome<Option<Rect>> {
+    Ok(Rect::new(coord(x)?, coord(y)?, coord(w)?.max(0), coord(h)?.max(0)).intersect(&sim.grid.bounds()))
+}
+
+fn stamp(host: &mut Host, name: &str, x: INT, y: INT, turns: INT) -> Outcome<()> {
+    let Host { sim, registry, .. } = host;
+    let pattern = registry.get(name).ok_or_else(|| format!("unknown pattern '{}' (expected one of: {})", name, registry.names()))?;
+    let transform = Transform { rotation: turns.rem_euclid(4) as u8, flipped: false };
+    sim.stamp(pattern, transform, 0, coord(x)?, coord(y)?).map_err(|e| e.to_string().into())
+}
+
+/// Register `$name` as a closure over the host, borrowed as `$host` for the length of each call
+macro_rules! board_fn {
+    ($engine:expr, $shared:expr, $name:literal, |$host:ident $(, $arg:ident: $ty:ty)*| -> $ret:ty $body:block) => {{
+        let shared = Rc::clone($shared);
+        $engine.register_fn($name, move |$($arg: $ty),*| -> $ret {
+            let $host = &mut *shared.borrow_mut();
+            $body
+        });
+    }};
+    ($engine:expr, $shared:expr, $name:literal, |$host:ident $(, $arg:ident: $ty:ty)*| $body:expr) => {
+        board_fn!($engine, $shared, $name, |$host $(, $arg: $ty)*| -> _ { $body })
+    };
+}
+
+/// The functions scripts reach the board through; `abs`, `min`, `max` and `print` come with Rhai
+fn register(engine: &mut rhai::Engine, shared: &Rc<RefCell<Host>>) {
+    board_fn!(engine, shared, "generation", |host| host.sim.generation as INT);
+    board_fn!(engine, shared, "population", |host| host.sim.live.len() as INT);
+    board_fn!(engine, shared, "width", |host| host.sim.grid.width as INT);
+    board_fn!(engine, shared, "height", |host| host.sim.grid.height as INT);
+    board_fn!(engine, shared, "wrapped", |host| host.sim.grid.topology.wraps());
+    board_fn!(engine, shared, "alive", |host, x: INT, y: INT| -> Outcome<bool> {
+        let cell = host.sim.grid.resolve(coord(x)?, coord(y)?);
+        Ok(cell.is_some_and(|p| host.sim.live.contains(&p)))
+    });
+    board_fn!(engine, shared, "set", |host, x: INT, y: INT, alive: bool| -> Outcome<()> {
+        // Off a bounded grid there is nothing to set
+        if let Some(p) = host.sim.grid.resolve(coord(x)?, coord(y)?) {
+            host.sim.set_cell(p, alive);
+        }
+        Ok(())
+    });
+    board_fn!(engine, shared, "count", |host, x: INT, y: INT, w: INT, h: INT| -> Outcome<INT> {
+        let Some(region) = region(&host.sim, x, y, w, h)? else { return Ok(0) };
+        Ok(host.sim.live.iter().filter(|p| region.contains(**p)).count() as INT)
+    });
+    board_fn!(engine, shared, "clear", |host, x: INT, y: INT, w: INT, h: INT| -> Outcome<()> {
+        if let Some(region) = region(&host.sim, x, y, w, h)? {
+            let doomed: Vec<_> = host.sim.live.iter().filter(|p| region.contains(**p)).copied().collect();
+            for p in doomed {
+                host.sim.set_cell(p, false);
+            }
+        }
+        Ok(())
+    });
+    board_fn!(engine, shared, "stamp", |host, name: &str, x: INT, y: INT| stamp(host, name, x, y, 0));
+    board_fn!(engine, shared, "stamp", |host, name: &str, x: INT, y: INT, turns: INT| stamp(host, name, x, y, turns));
+    board_fn!(engine, shared, "speed", |host| host.speed.unwrap_or(host.running) as FLOAT);
+    board_fn!(engine, shared, "set_speed", |host, speed: FLOAT| host.speed = Some((speed as f32).clamp(SPEED_MIN, SPEED_MAX)));
+    board_fn!(engine, shared, "set_speed", |host, speed: INT| host.speed = Some((speed as f32).clamp(SPEED_MIN, SPEED_MAX)));
+    board_fn!(engine, shared, "rand", |host, lo: INT, hi: INT| -> Outcome<INT> {
+        let (lo, hi) = (coord(lo)?, coord(hi)?);
+        if hi < lo {
+            return Err(format!("empty range {} to {}", lo, hi).into());
+        }
+        Ok(host.rng.gen_range(lo, hi.saturating_add(1)) as INT)
+    });
+}
@@ -0,0 +1,404 @@
+//! `--self-test`: checks a user or a CI machine can run to see that this build behaves, without
+//! opening a window. Patterns with known histories, edge seams, pattern files, the engine against
+//! the reference stepper and saves written to a temporary folder. Each check runs on its own
+//! thread with a time limit, so one that hangs is reported and the rest still run. The unit
+//! tests run the same checks, so the two can't drift apart.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::analysis::census;
+use crate::config::{SELFTEST_SEEDS, SELFTEST_TIMEOUT_SECONDS};
+use crate::deltas::DeltaLog;
+use crate::error;
+use crate::grid::reference::{differential_check, from_ascii, random_soup, to_ascii};
+use crate::grid::{Grid, Position, Topology};
+use crate::pattern_io::{self, load_pattern_file, write_pattern_rle};
+use crate::patterns::default_registry;
+use crate::settings::Settings;
+use crate::simulation::Simulation;
+
+/// Hand-checked edge scenarios: name, topology, generations, start and expected boards (rows split by `/`)
+pub const BOUNDARY_CASES: [(&str, Topology, u64, &str, &str); 12] = [
+    ("blinker on left wall", Topology::Bounded, 1, "...../#..../#..../#..../.....", "...../...../##.../...../....."),
+    ("blinker on left wall", Topology::Torus, 1, "...../#..../#..../#..../.....", "...../...../##..#/...../....."),
+    ("blinker on left wall", Topology::CylinderX, 1, "...../#..../#..../#..../.....", "...../...../##..#/...../....."),
+    ("blinker on left wall", Topology::CylinderY, 1, "...../#..../#..../#..../.....", "...../...../##.../...../....."),
+    ("blinker on right wall", Topology::Bounded, 1, "...../....#/....#/....#/.....", "...../...../...##/...../....."),
+    ("blinker on right wall", Topology::Torus, 1, "...../....#/....#/....#/.....", "...../...../#..##/...../....."),
+    ("blinker on top wall", Topology::Bounded, 1, ".###./...../...../...../.....", "..#../..#../...../...../....."),
+    ("blinker on top wall", Topology::Torus, 1, ".###./...../...../...../.....", "..#../..#../...../...../..#.."),
+    ("blinker on top wall", Topology::CylinderX, 1, ".###./...../...../...../.....", "..#../..#../...../...../....."),
+    ("blinker on top wall", Topology::CylinderY, 1, ".###./...../...../...../.....", "..#../..#../...../...../..#.."),
+    ("blinker on bottom wall", Topology::Bounded, 1, "...../...../...../...../.###.", "...../...../...../..#../..#.."),
+    ("blinker on bottom wall", Topology::Torus, 1, "...../...../...../...../.###.", "..#../...../...../..#../..#.."),
+];
+
+/// Corner past which nothing of the Gosper gun staged at (60, 60) reaches, only its gliders
+const GUN_REACH: (i32, i32) = (100, 72);
+
+/// Golly-layout RLE files, which must read and write back byte for byte
+pub const RLE_FIXTURES: [(&str, &str); 3] = [
+    ("gosper-glider-gun.rle", include_str!("../fixtures/rle/gosper-glider-gun.rle")),
+    ("acorn.rle", include_str!("../fixtures/rle/acorn.rle")),
+    ("diehard.rle", include_str!("../fixtures/rle/diehard.rle")),
+];
+
+/// Plaintext files of the same patterns as RLE fixtures, which must read as the same cells
+pub const PLAINTEXT_FIXTURES: [(&str, &str, &str); 2] = [
+    ("gosper-glider-gun.cells", include_str!("../fixtures/plaintext/gosper-glider-gun.cells"), RLE_FIXTURES[0].1),
+    ("diehard.cells", include_str!("../fixtures/plaintext/diehard.cells"), RLE_FIXTURES[2].1),
+];
+
+/// One check of the battery, given the base seed of the run
+#[derive(Clone, Copy)]
+pub struct Check {
+    pub name: &'static str,
+    pub run: fn(u64) -> Result<(), String>,
+    pub timeout: f64, // Seconds before the check is reported as hung
+}
+
+pub const CHECKS: [Check; 9] = [
+    Check { name: "blinker has period 2", run: blinker_period, timeout: SELFTEST_TIMEOUT_SECONDS },
+    Check { name: "glider moves (1, 1) every 4 generations", run: glider_displacement, timeout: SELFTEST_TIMEOUT_SECONDS },
+    Check { name: "diehard dies out at generation 130", run: diehard_extinction, timeout: SELFTEST_TIMEOUT_SECONDS },
+    Check { name: "Gosper gun sends 33 gliders by generation 1000", run: gosper_population, timeout: SELFTEST_TIMEOUT_SECONDS },
+    Check { name: "blinkers across walls and seams", run: topology_seams, timeout: SELFTEST_TIMEOUT_SECONDS },
+    Check { name: "RLE fixtures round-trip", run: rle_round_trips, timeout: SELFTEST_TIMEOUT_SECONDS },
+    Check { name: "plaintext fixtures match their RLE", run: plaintext_matches_rle, timeout: SELFTEST_TIMEOUT_SECONDS },
+    Check { name: "engine matches the reference stepper", run: engine_against_reference, timeout: 2.0 * SELFTEST_TIMEOUT_SECONDS },
+    Check { name: "saves load back from a temporary folder", run: save_load_round_trips, timeout: SELFTEST_TIMEOUT_SECONDS },
+];
+
+/// How a check ended
+#[derive(Clone, Debug, PartialEq)]
+pub enum Verdict {
+    Pass,
+    Fail(String),
+    Panicked(String),
+    TimedOut,
+}
+
+/// A check's verdict and how long it took
+#[derive(Clone, Debug)]
+pub struct Outcome {
+    pub name: &'static str,
+    pub verdict: Verdict,
+    pub elapsed: Duration,
+}
+
+impl Outcome {
+    pub fn passed(&self) -> bool {
+        self.verdict == Verdict::Pass
+    }
+}
+
+/// Run `check` on its own thread and wait for it up to its time limit. A check that runs over
+/// is left behind on its thread; the process ending takes it with it.
+pub fn run_check(check: Check, seed: u64) -> Outcome {
+    let (tx, rx) = mpsc::channel();
+    let start = Instant::now();
+    let spawned = thread::Builder::new().name(format!("self-test: {}", check.name)).spawn(move || {
+        let _ = tx.send((check.run)(seed));
+    });
+    let verdict = match spawned {
+        Err(err) => Verdict::Fail(format!("couldn't start a thread: {}", err)),
+        Ok(handle) => match rx.recv_timeout(Duration::from_secs_f64(check.timeout)) {
+            Ok(Ok(())) => Verdict::Pass,
+            Ok(Err(detail)) => Verdict::Fail(detail),
+            Err(mpsc::RecvTimeoutError::Timeout) => Verdict::TimedOut,
+            // The thread ended without sending, which only a panic does
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                let payload = handle.join().err();
+                let message = payload.as_ref()
+                    .and_then(|p| p.downcast_ref::<&str>().map(|s| s.to_string()).or_else(|| p.downcast_ref::<String>().cloned()));
+                Verdict::Panicked(message.unwrap_or_else(|| "no message".to_string()))
+            }
+        },
+    };
+    Outcome { name: check.name, verdict, elapsed: start.elapsed() }
+}
+
+/// The outcomes as a table, one row per check, with the details of failures beneath it
+pub fn table(outcomes: &[Outcome]) -> String {
+    let width = outcomes.iter().map(|o| o.name.len()).max().unwrap_or(0).max("check".len());
+    let mut text = format!("{:<width$}  {:<8}  {:>9}\n", "check", "result", "time");
+    for o in outcomes {
+        let result = match o.verdict {
+            Verdict::Pass => "pass",
+            Verdict::Fail(_) => "FAIL",
+            Verdict::Panicked(_) => "PANIC",
+            Verdict::TimedOut => "TIMEOUT",
+        };
+        text += &format!("{:<width$}  {:<8}  {:>6.1} ms\n", o.name, result, o.elapsed.as_secs_f64() * 1000.0);
+    }
+    for o in outcomes {
+        match &o.verdict {
+            Verdict::Pass => {}
+            Verdict::Fail(detail) => text += &format!("\n{}:\n{}\n", o.name, detail),
+            Verdict::Panicked(message) => text += &format!("\n{} panicked: {}\n", o.name, message),
+            Verdict::TimedOut => text += &format!("\n{} was still running after {:.0} s\n", o.name, o.elapsed.as_secs_f64()),
+        }
+    }
+    let failed = outcomes.iter().filter(|o| !o.passed()).count();
+    text + &*format!("\n{} passed, {} failed\n", outcomes.len() - failed, failed)
+}
+
+/// A built-in pattern on a `width` x `height` board, anchored at (x, y)
+fn staged(name: &str, width: i32, height: i32, topology: Topology, x: i32, y: i32) -> Result<Simulation, String> {
+    let registry = default_registry();
+    let pattern = registry.get(name).ok_or_else(|| format!("no built-in pattern called {}", name))?;
+    let mut sim = Simulation::new(width, height);
+    sim.grid.topology = topology;
+    sim.apply_pattern(pattern, x, y).map_err(|e| e.to_string())?;
+    Ok(sim)
+}
+
+fn run_for(sim: &mut Simulation, generations: u64) {
+    for _ in 0..generations {
+        sim.next_generation();
+    }
+}
+
+fn blinker_period(_seed: u64) -> Result<(), String> {
+    let mut sim = staged("Blinker", 16, 16, Topology::Torus, 6, 6)?;
+    let start = sim.live.clone();
+    sim.next_generation();
+    let middle = sim.live.clone();
+    sim.next_generation();
+    if middle == start || sim.live != start {
+        return Err(format!("after 1 and 2 generations: {:?} and {:?}, from {:?}", middle, sim.live, start));
+    }
+    Ok(())
+}
+
+fn glider_displacement(_seed: u64) -> Result<(), String> {
+    let mut sim = staged("Glider", 32, 32, Topology::Torus, 8, 8)?;
+    let start = sim.live.clone();
+    for lap in 1..=4 {
+        run_for(&mut sim, 4);
+        let expected: HashSet<Position> = start.iter().map(|p| Position::new(p.x() + lap, p.y() + lap)).collect();
+        if sim.live != expected {
+            return Err(format!("generation {}: {:?}, expected {:?}", sim.generation, sim.live, expected));
+        }
+    }
+    Ok(())
+}
+
+fn diehard_extinction(_seed: u64) -> Result<(), String> {
+    let mut sim = staged("Diehard", 64, 64, Topology::Bounded, 32, 32)?;
+    run_for(&mut sim, 129);
+    if sim.live.is_empty() {
+        return Err("died out before generation 130".to_string());
+    }
+    sim.next_generation();
+    if !sim.live.is_empty() {
+        return Err(format!("{} cells left at generation 130", sim.live.len()));
+    }
+    Ok(())
+}
+
+/// The gun repeats every 30 generations, sending one glider off each time, so generation
+/// 1000 = 33 * 30 + 10 is its shape at generation 10 and 33 five-cell gliders well clear of it
+fn gosper_population(_seed: u64) -> Result<(), String> {
+    // Big enough that no glider reaches the walls in 1000 generations
+    let mut sim = staged("Gosper Gun", 400, 400, Topology::Bounded, 60, 60)?;
+    let gun = |live: &HashSet<Position>| -> HashSet<Position> { live.iter().filter(|p| p.x() < GUN_REACH.0 && p.y() < GUN_REACH.1).copied().collect() };
+    run_for(&mut sim, 10);
+    let phase = gun(&sim.live);
+    run_for(&mut sim, 990);
+    if gun(&sim.live) != phase {
+        return Err("the gun isn't back in its generation-10 shape at generation 1000".to_string());
+    }
+    let stream: HashSet<Position> = sim.live.difference(&phase).copied().collect();
+    let objects = census(&stream, &sim.grid);
+    if objects.counts.get("Glider") != Some(&33) || objects.total_objects() != 33 {
+        return Err(format!("the stream holds {:?}, expected 33 gliders", objects));
+    }
+    if sim.live.len() != 33 * 5 + phase.len() || sim.live.len() != 213 {
+        return Err(format!("{} cells: a gun of {} and 33 gliders", sim.live.len(), phase.len()));
+    }
+    Ok(())
+}
+
+fn topology_seams(_seed: u64) -> Result<(), String> {
+    for (name, topology, generations, start, expected) in BOUNDARY_CASES {
+        let (grid, mut live) = from_ascii(start, topology);
+        for _ in 0..generations {
+            live = grid.next_generation(&live);
+        }
+        let (_, want) = from_ascii(expected, topology);
+        let (want, got) = (to_ascii(&grid, &want), to_ascii(&grid, &live));
+        if want != got {
+            return Err(format!("{} ({}): expected:\n{}\ngot:\n{}", name, topology, want, got));
+        }
+    }
+    Ok(())
+}
+
+/// Each RLE fixture reads and writes back byte for byte, in runs of at most 70 columns
+pub fn rle_round_trips(_seed: u64) -> Result<(), String> {
+    for (file, text) in RLE_FIXTURES {
+        let pattern = pattern_io::parse_rle(text).map_err(|e| format!("{}: {}", file, e))?;
+        let written = write_pattern_rle(&pattern);
+        if written != text {
+            return Err(format!("{} changed on the way through:\n{}", file, written));
+        }
+        if written.lines().any(|l| !l.starts_with('#') && l.len() > 70) || !written.ends_with("!\n") {
+            return Err(format!("{}: runs should stay within 70 columns and end with '!'", file));
+        }
+    }
+    Ok(())
+}
+
+fn plaintext_matches_rle(_seed: u64) -> Result<(), String> {
+    for (file, text, rle) in PLAINTEXT_FIXTURES {
+        let plain = pattern_io::parse_plaintext(text).map_err(|e| format!("{}: {}", file, e))?;
+        let twin = pattern_io::parse_rle(rle).map_err(|e| e.to_string())?;
+        let sorted = |cells: &[(i32, i32)]| { let mut cells = cells.to_vec(); cells.sort(); cells };
+        if plain.name != twin.name || sorted(&plain.cells) != sorted(&twin.cells) || (plain.width, plain.height) != (twin.width, twin.height) {
+            return Err(format!("{} read as {} with {} cells in {}x{}, its RLE as {} with {} in {}x{}", file,
+                plain.name, plain.cells.len(), plain.width, plain.height, twin.name, twin.cells.len(), twin.width, twin.height));
+        }
+    }
+    Ok(())
+}
+
+fn engine_against_reference(seed: u64) -> Result<(), String> {
+    for topology in Topology::ALL {
+        let mut grid = Grid::new(32, 24);
+        grid.topology = topology;
+        for seed in seed..seed + SELFTEST_SEEDS {
+            differential_check(&grid, random_soup(&grid, 0.35, seed), 100)
+                .map_err(|mismatch| format!("32x24 {} seed {}: {}", topology, seed, mismatch))?;
+        }
+    }
+    Ok(())
+}
+
+/// A board saved as RLE and as a delta log, and settings saved as text, each read back from a
+/// temporary folder
+fn save_load_round_trips(seed: u64) -> Result<(), String> {
+    let dir = std::env::temp_dir().join(format!("conways-game-of-life-self-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).map_err(|e| format!("couldn't make {}: {}", dir.display(), e))?;
+    let result = round_trips_in(&dir, seed);
+    let _ = std::fs::remove_dir_all(&dir);
+    result
+}
+
+fn round_trips_in(dir: &Path, seed: u64) -> Result<(), String> {
+    let file = |name: &str| -> PathBuf { dir.join(name) };
+    let mut sim = Simulation::new(48, 32);
+    sim.seed = seed;
+    sim.random_fill(0.3);
+    sim.mark_initial();
+    let mut log = DeltaLog::new(&sim);
+    for _ in 0..20 {
+        sim.next_generation();
+        log.record(&sim);
+    }
+
+    error::write(&file("board.rle"), sim.to_rle()).map_err(|e| e.to_string())?;
+    let loaded = load_pattern_file(&file("board.rle")).map_err(|e| e.to_string())?;
+    let (left, top) = (sim.live.iter().map(|p| p.x()).min().unwrap_or(0), sim.live.iter().map(|p| p.y()).min().unwrap_or(0));
+    let cells: HashSet<Position> = loaded.cells.iter().map(|&(x, y)| Position::new(x + left, y + top)).collect();
+    if cells != sim.live {
+        return Err(format!("the RLE save read back as {} cells, not {}", cells.len(), sim.live.len()));
+    }
+
+    error::write(&file("run.gold"), log.to_bytes()).map_err(|e| e.to_string())?;
+    let read = DeltaLog::from_bytes(&error::read(&file("run.gold")).map_err(|e| e.to_string())?).map_err(|e| e.to_string())?;
+    if read.reconstruct(sim.generation).as_ref() != Some(&sim.live) || read.last_generation() != sim.generation {
+        return Err(format!("the delta log didn't rebuild generation {}", sim.generation));
+    }
+
+    let settings = Settings { density: 0.42, ruler: true, ..Settings::default() };
+    error::write(&file("settings.txt"), settings.to_text()).map_err(|e| e.to_string())?;
+    let text = error::read_to_string(&file("settings.txt")).map_err(|e| e.to_string())?;
+    if Settings::parse(&text) != settings {
+        return Err("the settings file read back different".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run the check of the battery at `index` through `run_check`, as `--self-test` does
+    fn passes(index: usize) {
+        let outcome = run_check(CHECKS[index], 1);
+        assert_eq!(outcome.verdict, Verdict::Pass, "{}", outcome.name);
+    }
+
+    #[test]
+    fn blinker() {
+        passes(0);
+    }
+
+    #[test]
+    fn glider() {
+        passes(1);
+    }
+
+    #[test]
+    fn diehard() {
+        passes(2);
+    }
+
+    #[test]
+    fn gosper_gun() {
+        passes(3);
+    }
+
+    #[test]
+    fn seams() {
+        passes(4);
+    }
+
+    #[test]
+    fn rle_fixtures() {
+        passes(5);
+    }
+
+    #[test]
+    fn plaintext_fixtures() {
+        passes(6);
+    }
+
+    #[test]
+    fn engine() {
+        passes(7);
+    }
+
+    #[test]
+    fn saves() {
+        passes(8);
+    }
+
+    #[test]
+    fn runner_reports_failures_and_time_limits() {
+        let failing = Check { name: "fails", run: |_| Err("on purpose".to_string()), timeout: 5.0 };
+        let hung = Check {
+            name: "hangs",
+            run: |_| {
+                thread::sleep(Duration::from_secs(2));
+                Ok(())
+            },
+            timeout: 0.05,
+        };
+        let panicking = Check { name: "panics", run: |_| panic!("on purpose"), timeout: 5.0 };
+        let passing = Check { name: "passes", run: |_| Ok(()), timeout: 5.0 };
+        let outcomes: Vec<Outcome> = [failing, hung, panicking, passing].into_iter().map(|c| run_check(c, 1)).collect();
+        let verdicts: Vec<&Verdict> = outcomes.iter().map(|o| &o.verdict).collect();
+        assert_eq!(verdicts, [&Verdict::Fail("on purpose".to_string()), &Verdict::TimedOut, &Verdict::Panicked("on purpose".to_string()), &Verdict::Pass]);
+        assert!(outcomes[1].elapsed.as_secs_f64() < 1.0, "the hung check held the run up for {:.2} s", outcomes[1].elapsed.as_secs_f64());
+        let table = table(&outcomes);
+        assert!(table.contains("1 passed, 3 failed"), "{}", table);
+        assert!(table.lines().any(|l| l.starts_with("hangs") && l.contains("TIMEOUT")), "{}", table);
+        assert!(table.contains("panics panicked: on purpose"), "{}", table);
+    }
+}
@@ -0,0 +1,450 @@
+//! Mirroring a run to another process, such as an LED matrix driver, over a TCP line protocol.
+//! With the native-only `serve` feature, `--serve <address>` listens there. Every connected
+//! client gets the whole board when it joins, then one line per generation with the cells
+//! born and died. Cell sets travel as RLE. A few commands may come back the other way.
+//!
+//! Each client has its own writer thread behind a bounded queue, so a slow client never holds
+//! up the board or the other clients. When its queue fills, its deltas are dropped until there
+//! is room again, and it then gets the whole board to start over from.
+//!
+//! Server to client, one message per line:
+//!
+//! - `full <generation> <width> <height> <rule> <cells>`: the whole board. It is sent on
+//!   joining, after an edit or a dropped delta, and every `SERVE_FULL_EVERY` generations.
+//! - `delta <generation> <born> <died>`: the step to `generation`.
+//! - `rejected <reason>`: a command that couldn't be applied.
+//! - `error <reason>`: a line the server didn't understand.
+//!
+//! `<cells>` is `-` for none, otherwise `<x>,<y>,<rle>`: the RLE body with its line breaks
+//! removed, and `x`, `y` the board cell its top left corner lands on.
+//!
+//! Client to server: `pause`, `resume`, `speed <generations per second>` and
+//! `stamp <pattern name> <x> <y>`, applied through the `remote` control API.
+
+use std::collections::HashSet;
+use std::net::{Shutdown, TcpStream};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Arc;
+
+use crate::config::SERVE_FULL_EVERY;
+use crate::error::GolError;
+use crate::grid::Position;
+use crate::pattern_io::{parse_rle, write_rle};
+use crate::remote::{Command, Endpoint, Source};
+use crate::simulation::Simulation;
+
+/// Whether this build can serve the board
+pub const AVAILABLE: bool = cfg!(all(feature = "serve", not(target_arch = "wasm32")));
+
+/// One line sent to clients
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    Full { generation: u64, width: i32, height: i32, rule: String, cells: HashSet<Position> },
+    Delta { generation: u64, born: HashSet<Position>, died: HashSet<Position> },
+    Rejected(String),
+}
+
+impl Message {
+    /// The line for this message, without its line break
+    pub fn encode(&self) -> String {
+        match self {
+            Message::Full { generation, width, height, rule, cells } => {
+                format!("full {} {} {} {} {}", generation, width, height, rule, encode_cells(cells))
+            }
+            Message::Delta { generation, born, died } => format!("delta {} {} {}", generation, encode_cells(born), encode_cells(died)),
+            Message::Rejected(reason) => format!("rejected {}", reason),
+        }
+    }
+
+    /// Read a line written by `encode`; clients use this to follow the board
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["full", generation, width, height, rule, cells] => Ok(Message::Full {
+                generation: number(generation)?,
+                width: number(width)?,
+                height: number(height)?,
+                rule: rule.to_string(),
+                cells: decode_cells(cells)?,
+            }),
+            ["delta", generation, born, died] => Ok(Message::Delta { generation: number(generation)?, born: decode_cells(born)?, died: decode_cells(died)? }),
+            ["rejected", ..] => Ok(Message::Rejected(line.trim().trim_start_matches("rejected").trim().to_string())),
+            _ => Err(format!("unexpected line: {}", line)),
+        }
+    }
+}
+
+/// One number of a line
+fn number<T: std::str::FromStr>(word: &str) -> Result<T, String> {
+    word.parse().map_err(|_| format!("not a number: {}", word))
+}
+
+/// Cells as `-`, or `<x>,<y>,<rle>` with the RLE on one line
+pub fn encode_cells(cells: &HashSet<Position>) -> String {
+    let Some(left) = cells.iter().map(|p| p.x()).min() else { return "-".to_string(); };
+    let top = cells.iter().map(|p| p.y()).min().unwrap_or(0);
+    let rle = write_rle("", "B3/S23", &cells.iter().map(|p| (p.x(), p.y())).collect::<Vec<_>>());
+    let body: String = rle.lines().skip(1).collect();
+    format!("{},{},{}", left, top, body)
+}
+
+/// Read cells written by `encode_cells`
+pub fn decode_cells(text: &str) -> Result<HashSet<Position>, String> {
+    if text == "-" {
+        return Ok(HashSet::new());
+    }
+    let mut parts = text.splitn(3, ',');
+    let (Some(x), Some(y), Some(body)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(format!("expected x,y,rle or -, got {}", text));
+    };
+    let (left, top): (i32, i32) = (number(x)?, number(y)?);
+    let pattern = parse_rle(&format!("x = 0, y = 0\n{}", body)).map_err(|e| e.to_string())?;
+    Ok(pattern.cells.iter().map(|&(cx, cy)| Position::new(left + cx, top + cy)).collect())
+}
+
+/// A line from a client as a command for the board
+pub fn parse_command(line: &str) -> Result<Command, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match words.as_slice() {
+        ["pause"] => Ok(Command::Pause(true)),
+        ["resume"] => Ok(Command::Pause(false)),
+        ["speed", n] => n.parse().ok().filter(|s: &f32| s.is_finite()).map(Command::SetSpeed).ok_or_else(|| format!("not a speed: {}", n)),
+        ["stamp", name @ .., x, y] if !name.is_empty() => Ok(Command::Stamp(Source::Named(name.join(" ")), Position::new(number(x)?, number(y)?))),
+        _ => Err("expected pause, resume, speed N or stamp NAME X Y".to_string()),
+    }
+}
+
+/// The board's side: turns each generation, or an edit, into the message clients need
+pub struct Feed {
+    updates: SyncSender<Message>,
+    last: Option<(u64, u64)>, // Generation and revision clients last heard about; `None` sends the whole board
+    since_full: u64,          // Generations sent as deltas since the last full board
+}
+
+/// A feed and the end that reads it, holding up to `capacity` messages
+pub fn feed(capacity: usize) -> (Feed, Receiver<Message>) {
+    let (updates, received) = mpsc::sync_channel(capacity);
+    (Feed { updates, last: None, since_full: 0 }, received)
+}
+
+impl Feed {
+    /// Tell clients about the board if it changed since the last call: as a delta when it is
+    /// one generation on and the board tracked its diff, otherwise whole. Meant to be called
+    /// every generation and every frame. Whether the board changed is read off its generation
+    /// and revision, so an unchanged board costs nothing and sends nothing. A message the
+    /// server has no room for is dropped, and the next one is the whole board.
+    pub fn publish(&mut self, sim: &Simulation) {
+        let now = (sim.generation, sim.revision());
+        if self.last == Some(now) {
+            return;
+        }
+        let delta = sim.diff.as_ref().filter(|d| d.generation == sim.generation).and_then(|d| {
+            let follows = self.last == Some((sim.generation.checked_sub(1)?, sim.revision()));
+            (follows && self.since_full < SERVE_FULL_EVERY).then(|| (d.born.clone(), d.died.iter().copied().collect()))
+        });
+        let message = match delta {
+            Some((born, died)) => Message::Delta { generation: sim.generation, born, died },
+            None => Message::Full {
+                generation: sim.generation,
+                width: sim.grid.width,
+                height: sim.grid.height,
+                rule: sim.grid.rule.to_string(),
+                cells: sim.live.clone(),
+            },
+        };
+        let full = matches!(message, Message::Full { .. });
+        match self.updates.try_send(message) {
+            Ok(()) => {
+                self.last = Some(now);
+                self.since_full = if full { 0 } else { self.since_full + 1 };
+            }
+            Err(_) => self.last = None,
+        }
+    }
+}
+
+/// One connected client: the queue its writer thread drains
+struct Client {
+    lines: SyncSender<Arc<str>>,
+    behind: bool, // Lost a message; gets the whole board once there is room
+    stream: Option<TcpStream>, // Shut down when the client is dropped, which ends its reader thread
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        if let Some(stream) = &self.stream {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+/// The server's copy of the board and its clients. It follows the feed's messages, so it can
+/// hand the whole board to a client joining or catching up.
+#[derive(Default)]
+pub struct Hub {
+    board: Option<Message>, // The latest board, as a `Message::Full`
+    clients: Vec<Client>,
+}
+
+impl Hub {
+    /// Add a client reading from the other end of `lines`; it gets the board at once if there
+    /// is one yet, and otherwise with the feed's first message, which is always whole
+    pub fn join(&mut self, lines: SyncSender<Arc<str>>) {
+        self.add(lines, None);
+    }
+
+    /// `join` for a client connected on `stream`. The stream is shut down once the client is
+    /// dropped, because it hung up or the server stopped, so its reader thread stops waiting
+    /// for lines and its writer runs out of them.
+    pub fn join_stream(&mut self, lines: SyncSender<Arc<str>>, stream: TcpStream) {
+        self.add(lines, Some(stream));
+    }
+
+    fn add(&mut self, lines: SyncSender<Arc<str>>, stream: Option<TcpStream>) {
+        self.clients.push(Client { lines, behind: self.board.is_some(), stream });
+        self.resync();
+    }
+
+    pub fn clients(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Apply a message to the board copy and pass it on
+    pub fn update(&mut self, message: Message) {
+        match (&message, &mut self.board) {
+            (Message::Full { .. }, board) => *board = Some(message.clone()),
+            (Message::Delta { generation, born, died }, Some(Message::Full { generation: at, cells, .. })) => {
+                *at = *generation;
+                cells.retain(|p| !died.contains(p));
+                cells.extend(born);
+            }
+            _ => {}
+        }
+        let line: Arc<str> = message.encode().into();
+        let board_state = !matches!(message, Message::Rejected(_));
+        self.clients.retain_mut(|client| {
+            if client.behind {
+                return true;
+            }
+            match client.lines.try_send(Arc::clone(&line)) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    client.behind = board_state;
+                    true
+                }
+                Err(TrySendError::Disconnected(_)) => false,
+            }
+        });
+        self.resync();
+    }
+
+    /// Send the whole board to every client that fell behind and has room for it again
+    fn resync(&mut self) {
+        let Some(board) = &self.board else { return };
+        let mut line: Option<Arc<str>> = None;
+        self.clients.retain_mut(|client| {
+            if !client.behind {
+                return true;
+            }
+            let full = line.get_or_insert_with(|| board.encode().into());
+            match client.lines.try_send(Arc::clone(full)) {
+                Ok(()) => {
+                    client.behind = false;
+                    true
+                }
+                Err(TrySendError::Full(_)) => true,
+                Err(TrySendError::Disconnected(_)) => false,
+            }
+        });
+    }
+}
+
+/// A server listening for clients
+pub struct Serving {
+    pub address: std::net::SocketAddr, // Where it listens, with the port filled in if 0 was asked for
+    pub feed: Feed,                    // Publish the board through this every generation
+    pub endpoint: Endpoint,            // Apply clients' commands from this between generations
+}
+
+/// Listen on `address`, e.g. `127.0.0.1:7878`. The server stops once the `Feed` is dropped.
+pub fn start(address: &str) -> Result<Serving, GolError> {
+    #[cfg(not(all(feature = "serve", not(target_arch = "wasm32"))))]
+    {
+        let _ = address;
+        Err(GolError::Unsupported("this build can't serve the board; rebuild with --features serve (not available on the web)".to_string()))
+    }
+    #[cfg(all(feature = "serve", not(target_arch = "wasm32")))]
+    {
+        server::start(address)
+    }
+}
+
+#[cfg(all(feature = "serve", not(target_arch = "wasm32")))]
+mod server {
+    use std::io::{BufRead, BufReader, BufWriter, ErrorKind, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::{feed, parse_command, Hub, Message, Serving};
+    use crate::config::{SERVE_ACCEPT_MILLIS, SERVE_CLIENT_QUEUE, SERVE_UPDATE_QUEUE};
+    use crate::error::GolError;
+    use crate::remote::{self, Command, Event, Remote};
+
+    pub fn start(address: &str) -> Result<Serving, GolError> {
+        let unavailable = |e: std::io::Error| GolError::Unsupported(format!("can't serve on {}: {}", address, e));
+        let listener = TcpListener::bind(address).map_err(unavailable)?;
+        listener.set_nonblocking(true).map_err(unavailable)?;
+        let bound = listener.local_addr().map_err(unavailable)?;
+        let (feed, updates) = feed(SERVE_UPDATE_QUEUE);
+        let (Remote { commands, events }, endpoint) = remote::channel();
+        thread::Builder::new()
+            .name("life-serve".to_string())
+            .spawn(move || run(listener, updates, commands, events))
+            .map_err(|e| GolError::Unsupported(format!("can't start the server thread: {}", e)))?;
+        Ok(Serving { address: bound, feed, endpoint })
+    }
+
+    /// Accept clients and pass the feed on until the feed is dropped
+    fn run(listener: TcpListener, updates: Receiver<Message>, commands: Sender<Command>, events: Receiver<Event>) {
+        let mut hub = Hub::default();
+        loop {
+            loop {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        if let Some((lines, stream)) = connect(stream, commands.clone()) {
+                            hub.join_stream(lines, stream);
+                        }
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                    Err(_) => break,
+                }
+            }
+            for event in events.try_iter() {
+                if let Event::Rejected(reason) = event {
+                    hub.update(Message::Rejected(reason));
+                }
+            }
+            match updates.recv_timeout(Duration::from_millis(SERVE_ACCEPT_MILLIS)) {
+                Ok(message) => {
+                    hub.update(message);
+                    for message in updates.try_iter() {
+                        hub.update(message);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    }
+
+    /// Start a client's writer and reader threads, returning the queue its writer drains and a
+    /// handle on its stream for the hub to shut down
+    fn connect(stream: TcpStream, commands: Sender<Command>) -> Option<(SyncSender<Arc<str>>, TcpStream)> {
+        stream.set_nonblocking(false).ok()?;
+        stream.set_nodelay(true).ok()?;
+        let reader = stream.try_clone().ok()?;
+        let closer = stream.try_clone().ok()?;
+        let (lines, queued) = mpsc::sync_channel::<Arc<str>>(SERVE_CLIENT_QUEUE);
+        thread::Builder::new().name("life-serve-writer".to_string()).spawn(move || {
+            let mut out = BufWriter::new(stream);
+            while let Ok(line) = queued.recv() {
+                let mut written = writeln!(out, "{}", line);
+                for line in queued.try_iter() {
+                    written = written.and_then(|_| writeln!(out, "{}", line));
+                }
+                if written.and_then(|_| out.flush()).is_err() {
+                    return;
+                }
+            }
+        }).ok()?;
+        // The reader holds a sender too, so the writer only ends once the reader does: when the
+        // client hangs up, or the hub shuts the stream down
+        let replies = lines.clone();
+        thread::Builder::new().name("life-serve-reader".to_string()).spawn(move || {
+            for line in BufReader::new(reader).lines().map_while(Result::ok) {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match parse_command(&line) {
+                    Ok(command) => {
+                        if commands.send(command).is_err() {
+                            return;
+                        }
+                    }
+                    // A reply that doesn't fit is dropped like any other line
+                    Err(reason) => {
+                        let _ = replies.try_send(format!("error {}", reason).into());
+                    }
+                }
+            }
+        }).ok()?;
+        Some((lines, closer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::GliderPattern;
+
+    /// The kinds of message a publish sent: "full" or "delta"
+    fn sent(feed: &mut Feed, updates: &Receiver<Message>, sim: &Simulation) -> Vec<&'static str> {
+        feed.publish(sim);
+        updates.try_iter().map(|m| if matches!(m, Message::Full { .. }) { "full" } else { "delta" }).collect()
+    }
+
+    #[test]
+    fn publishes_only_what_changed() {
+        let (mut feed, updates) = feed(16);
+        let mut sim = Simulation::new(32, 32);
+        sim.place(&GliderPattern).unwrap();
+        sim.track_diff = true;
+        assert_eq!(sent(&mut feed, &updates, &sim), ["full"]);
+        assert!(sent(&mut feed, &updates, &sim).is_empty(), "an unchanged board sends nothing");
+        sim.next_generation();
+        assert_eq!(sent(&mut feed, &updates, &sim), ["delta"]);
+        sim.set_cell(Position::new(30, 30), true);
+        assert_eq!(sent(&mut feed, &updates, &sim), ["full"], "an edit sends the whole board");
+        sim.set_cell(Position::new(30, 30), true);
+        assert!(sent(&mut feed, &updates, &sim).is_empty(), "setting a live cell alive changes nothing");
+        sim.next_generation();
+        assert_eq!(sent(&mut feed, &updates, &sim), ["delta"]);
+
+        // Another board at the same generation is news too
+        let mut other = Simulation::new(32, 32);
+        other.place(&GliderPattern).unwrap();
+        other.generation = sim.generation;
+        assert_eq!(sent(&mut feed, &updates, &other), ["full"]);
+    }
+
+    #[cfg(all(feature = "serve", not(target_arch = "wasm32")))]
+    #[test]
+    fn stopping_the_server_hangs_up_on_clients() {
+        use std::io::{BufRead, BufReader, Read};
+        use std::time::Duration;
+
+        let mut serving = start("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(serving.address).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut sim = Simulation::new(16, 16);
+        sim.place(&GliderPattern).unwrap();
+        let mut line = String::new();
+        // The client may not have joined yet when the first board goes out
+        while line.is_empty() {
+            serving.feed.publish(&sim);
+            sim.next_generation();
+            reader.get_mut().set_read_timeout(Some(Duration::from_millis(200))).unwrap();
+            let _ = reader.read_line(&mut line);
+        }
+        assert!(line.starts_with("full "), "{}", line);
+        reader.get_mut().set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        drop(serving);
+        // The server closes the connection rather than leaving its threads waiting on it
+        let mut rest = Vec::new();
+        assert!(reader.read_to_end(&mut rest).is_ok(), "the connection was left open");
+    }
+}
@@ -0,0 +1,318 @@
+use std::path::{Path, PathBuf};
+
+use crate::config::{
+    ATTRACT_IDLE_SECONDS, ATTRACT_PLAYLIST, BACKDROP_OPACITY, EDGE_SPAWN_RATE, FPS_CAP_MAX, FPS_CAP_MIN, HEAT_INTENSITY, LETHAL_MARGIN, LETHAL_MARGIN_MAX, MEMORY_BUDGET_MB, FOLLOW_SETTLE_SECONDS, PARTICLE_INTENSITY, RANDOM_DENSITY,
+    TIMELAPSE_EVERY,
+};
+use crate::analysis::Cleanup;
+use crate::error::{self, GolError, Kept};
+use crate::hotbar::{Hotbar, Slot};
+use crate::patterns::validate_density;
+use crate::grid::{CoordSystem, Topology};
+use crate::schedule::SpeedSchedule;
+use crate::themes::{ColorTheme, GridMode, GridStyle, LineStyle};
+
+/// User preferences persisted between runs as a `key = value` text file
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    pub grid: GridStyle,
+    pub memory_budget_mb: usize, // Approximate cap on simulation memory
+    pub smooth: bool,            // Animate births and deaths between generations at low speeds
+    pub density: f32,            // Fill density for random soups
+    pub edge_rate: f32,          // Chance per border cell per generation in edge-spawn mode
+    pub lethal_margin: i32,      // Border cells culled each generation on bounded grids; 0 is off
+    pub attract_idle: f64,       // Seconds idle on the resolution menu before the demo reel; 0 disables it
+    pub attract_playlist: Vec<String>, // Pattern names shown by the demo reel
+    pub coords: CoordSystem,     // Convention for showing and typing cell coordinates
+    pub ruler: bool,             // Column and row numbers along the board's edges
+    pub background: bool,        // Step on a worker thread where threads exist (ignored on WASM)
+    pub timelapse_every: u64,    // Generations between gallery thumbnails; 0 disables them
+    pub follow_settle: f32,      // Seconds the follow camera takes to catch up with its target
+    pub schedule: Option<SpeedSchedule>, // Speed breakpoints every run starts with; none keeps a fixed speed
+    pub torus: Topology,         // What a board that wraps on all sides becomes, shifted or not, as a `torus_shift` line
+    pub backdrops: Vec<(ColorTheme, PathBuf)>, // Background image per theme, as `backdrop_<theme>` lines
+    pub backdrop_opacity: f32,   // How strongly those images show, 0 to 1
+    pub cleanup: Cleanup,        // Which objects X removes
+    pub confirm_destructive: bool, // Clearing, reseeding, cleanup and loads ask twice while the board runs
+    pub pause_on_edit: bool,     // Drawing on a running board pauses it first
+    pub heat: bool,              // Wash busy parts of the board in a warm glow
+    pub heat_intensity: f32,     // Opacity of that glow at full heat, 0 to 1
+    pub particles: bool,         // Rings for births and fading squares for deaths
+    pub particle_intensity: f32, // Opacity of a particle as it starts, 0 to 1
+    pub fps_cap: u32,            // Render frames per second; 0 draws as often as the display allows
+    pub battery_saver: bool,     // Draw at a trickle while paused or unchanging and left alone
+    pub hotbar: Hotbar,          // Quick-stamp slots, as `hotbar_<key>` lines
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            grid: GridStyle::default(),
+            memory_budget_mb: MEMORY_BUDGET_MB,
+            smooth: true,
+            density: RANDOM_DENSITY,
+            edge_rate: EDGE_SPAWN_RATE,
+            lethal_margin: LETHAL_MARGIN,
+            attract_idle: ATTRACT_IDLE_SECONDS,
+            attract_playlist: split_list(ATTRACT_PLAYLIST),
+            coords: CoordSystem::TopLeft,
+            ruler: false,
+            background: true,
+            timelapse_every: TIMELAPSE_EVERY,
+            follow_settle: FOLLOW_SETTLE_SECONDS,
+            schedule: None,
+            torus: Topology::Torus,
+            backdrops: Vec::new(),
+            backdrop_opacity: BACKDROP_OPACITY,
+            cleanup: Cleanup::default(),
+            confirm_destructive: true,
+            pause_on_edit: true,
+            heat: false,
+            heat_intensity: HEAT_INTENSITY,
+            particles: false,
+            particle_intensity: PARTICLE_INTENSITY,
+            fps_cap: 0,
+            battery_saver: false,
+            hotbar: Hotbar::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Location of the settings file, or `None` where there is no filesystem
+    pub fn path() -> Option<PathBuf> {
+        if cfg!(target_arch = "wasm32") {
+            return None;
+        }
+        let base = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+        Some(base.join("conways-game-of-life").join("settings.txt"))
+    }
+
+    /// Load settings, falling back to defaults for a missing file or unknown values
+    pub fn load() -> Self {
+        Self::try_load().map(|kept| kept.value).unwrap_or_default()
+    }
+
+    /// Load settings as `load` does, but say why a file that exists couldn't be read, and when
+    /// its backup stood in for it
+    pub fn try_load() -> Result<Kept<Self>, GolError> {
+        match Self::path() {
+            Some(path) => Self::read_from(&path),
+            None => Ok(Kept::fresh(Self::default())),
+        }
+    }
+
+    /// Read the settings file at `path`, or its backup if it is damaged; defaults if there is none
+    pub fn read_from(path: &Path) -> Result<Kept<Self>, GolError> {
+        let kept = error::read_kept(path, |text| error::check_key_values("settings file", text).map(|()| Self::parse(text)))?;
+        Ok(kept.unwrap_or_else(|| Kept::fresh(Self::default())))
+    }
+
+    /// Write settings to disk, and to the backup read if the file is later found damaged.
+    /// Failures are returned so the UI can report them.
+    pub fn save(&self) -> Result<(), GolError> {
+        let Some(path) = Self::path() else { return Ok(()); };
+        error::write_kept(&path, self.to_text())
+    }
+
+    /// Parse `key = value` lines; unknown keys and bad values are ignored
+    pub fn parse(text: &str) -> Self {
+        let mut s = Settings::default();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.starts_with('#') { continue; }
+            let Some((key, value)) = line.split_once('=') else { continue; };
+            let (key, value) = (key.trim(), value.trim());
+            match key {
+                "grid_mode" => match value {
+                    "off" => s.grid.mode = GridMode::Off,
+                    "minor" => s.grid.mode = GridMode::Minor,
+                    "minor_major" => s.grid.mode = GridMode::MinorMajor,
+                    _ => {}
+                },
+                "grid_line" => match value {
+                    "solid" => s.grid.line = LineStyle::Solid,
+                    "dotted" => s.grid.line = LineStyle::Dotted,
+                    _ => {}
+                },
+                "grid_major_every" => {
+                    if let Ok(n @ 2..=100) = value.parse() { s.grid.major_every = n; }
+                }
+                "grid_min_cell" => {
+                    if let Ok(n @ 1..=64) = value.parse() { s.grid.min_cell = n; }
+                }
+                "memory_budget_mb" => {
+                    if let Ok(n @ 16..) = value.parse() { s.memory_budget_mb = n; }
+                }
+                "random_density" => {
+                    if let Some(d) = value.parse().ok().and_then(|d| validate_density(d).ok()) { s.density = d; }
+                }
+                "edge_spawn_rate" => {
+                    if let Some(r) = value.parse().ok().and_then(|r| validate_density(r).ok()) { s.edge_rate = r; }
+                }
+                "lethal_margin" => {
+                    if let Ok(k) = value.parse() && (0..=LETHAL_MARGIN_MAX).contains(&k) { s.lethal_margin = k; }
+                }
+                "attract_idle_seconds" => {
+                    if let Ok(t) = value.parse::<f64>() && t >= 0.0 { s.attract_idle = t; }
+                }
+                "attract_playlist" => s.attract_playlist = split_list(value),
+                "coordinates" => match value {
+                    "top_left" => s.coords = CoordSystem::TopLeft,
+                    "centered" => s.coords = CoordSystem::Centered,
+                    _ => {}
+                },
+                "ruler" => {
+                    if let Ok(b) = value.parse() { s.ruler = b; }
+                }
+                "background_stepping" => {
+                    if let Ok(b) = value.parse() { s.background = b; }
+                }
+                "timelapse_every" => {
+                    if let Ok(n) = value.parse() { s.timelapse_every = n; }
+                }
+                "follow_settle_seconds" => {
+                    if let Ok(t) = value.parse::<f32>() && (0.05..=10.0).contains(&t) { s.follow_settle = t; }
+                }
+                "speed_schedule" => match value {
+                    "" | "off" => s.schedule = None,
+                    _ => {
+                        if let Ok(schedule) = SpeedSchedule::parse(value) { s.schedule = Some(schedule); }
+                    }
+                },
+                "torus_shift" => {
+                    if let Ok(torus) = Topology::parse_shift(value) { s.torus = torus; }
+                }
+                "smooth_render" => {
+                    if let Ok(b) = value.parse() { s.smooth = b; }
+                }
+                "cleanup_min_size" => {
+                    if let Ok(n) = value.parse() { s.cleanup.min_size = n; }
+                }
+                "cleanup_known" => {
+                    if let Ok(b) = value.parse() { s.cleanup.known = b; }
+                }
+                "confirm_destructive" => {
+                    if let Ok(b) = value.parse() { s.confirm_destructive = b; }
+                }
+                "pause_on_edit" => {
+                    if let Ok(b) = value.parse() { s.pause_on_edit = b; }
+                }
+                "heat_overlay" => {
+                    if let Ok(b) = value.parse() { s.heat = b; }
+                }
+                "heat_intensity" => {
+                    if let Ok(a) = value.parse::<f32>() && (0.0..=1.0).contains(&a) { s.heat_intensity = a; }
+                }
+                "particles" => {
+                    if let Ok(b) = value.parse() { s.particles = b; }
+                }
+                "particle_intensity" => {
+                    if let Ok(a) = value.parse::<f32>() && (0.0..=1.0).contains(&a) { s.particle_intensity = a; }
+                }
+                "fps_cap" => {
+                    if let Ok(n) = value.parse::<u32>() && (n == 0 || (FPS_CAP_MIN..=FPS_CAP_MAX).contains(&n)) { s.fps_cap = n; }
+                }
+                "battery_saver" => {
+                    if let Ok(b) = value.parse() { s.battery_saver = b; }
+                }
+                "backdrop_opacity" => {
+                    if let Ok(a) = value.parse::<f32>() && (0.0..=1.0).contains(&a) { s.backdrop_opacity = a; }
+                }
+                _ if key.starts_with("hotbar_") => {
+                    if let Some(i) = key["hotbar_".len()..].chars().next().and_then(Hotbar::slot_of) && key.len() == "hotbar_".len() + 1 {
+                        s.hotbar.slots[i] = Slot::parse(value);
+                    }
+                }
+                _ if key.starts_with("backdrop_") => {
+                    if let Some(theme) = ColorTheme::from_name(&key["backdrop_".len()..]) {
+                        s.backdrops.retain(|(t, _)| *t != theme);
+                        if !value.is_empty() { s.backdrops.push((theme, PathBuf::from(value))); }
+                    }
+                }
+                _ => {}
+            }
+        }
+        s
+    }
+
+    /// Serialize in the format read by `parse`
+    pub fn to_text(&self) -> String {
+        let mode = match self.grid.mode {
+            GridMode::Off => "off",
+            GridMode::Minor => "minor",
+            GridMode::MinorMajor => "minor_major",
+        };
+        let line = match self.grid.line {
+            LineStyle::Solid => "solid",
+            LineStyle::Dotted => "dotted",
+        };
+        format!(
+            "# Conway's Game of Life settings\n\
+             grid_mode = {}\n\
+             grid_line = {}\n\
+             grid_major_every = {}\n\
+             grid_min_cell = {}\n\
+             memory_budget_mb = {}\n\
+             smooth_render = {}\n\
+             random_density = {}\n\
+             edge_spawn_rate = {}\n\
+             lethal_margin = {}\n\
+             attract_idle_seconds = {}\n\
+             attract_playlist = {}\n\
+             coordinates = {}\n\
+             ruler = {}\n\
+             background_stepping = {}\n\
+             timelapse_every = {}\n\
+             follow_settle_seconds = {}\n\
+             speed_schedule = {}\n\
+             torus_shift = {}\n\
+             cleanup_min_size = {}\n\
+             cleanup_known = {}\n\
+             confirm_destructive = {}\n\
+             pause_on_edit = {}\n\
+             heat_overlay = {}\n\
+             heat_intensity = {}\n\
+             particles = {}\n\
+             particle_intensity = {}\n\
+             fps_cap = {}\n\
+             battery_saver = {}\n\
+             backdrop_opacity = {}\n\
+             {}{}",
+            mode, line, self.grid.major_every, self.grid.min_cell, self.memory_budget_mb, self.smooth, self.density,
+            self.edge_rate, self.lethal_margin, self.attract_idle, self.attract_playlist.join(", "),
+            match self.coords { CoordSystem::TopLeft => "top_left", CoordSystem::Centered => "centered" },
+            self.ruler,
+            self.background,
+            self.timelapse_every,
+            self.follow_settle,
+            self.schedule.as_ref().map_or("off".to_string(), |s| s.to_string()),
+            self.torus.shift_label(),
+            self.cleanup.min_size,
+            self.cleanup.known,
+            self.confirm_destructive,
+            self.pause_on_edit,
+            self.heat,
+            self.heat_intensity,
+            self.particles,
+            self.particle_intensity,
+            self.fps_cap,
+            self.battery_saver,
+            self.backdrop_opacity,
+            self.backdrops.iter()
+                .map(|(theme, path)| format!("backdrop_{} = {}\n", theme.name().to_ascii_lowercase(), path.display()))
+                .collect::<String>(),
+            self.hotbar.slots.iter().enumerate()
+                .filter_map(|(i, slot)| Some(format!("hotbar_{} = {}\n", Hotbar::key_label(i), slot.as_ref()?.to_setting())))
+                .collect::<String>(),
+        )
+    }
+}
+
+/// Split a comma-separated setting into trimmed, non-empty items
+fn split_list(value: &str) -> Vec<String> {
+    value.split(',').map(str::trim).filter(|v| !v.is_empty()).map(str::to_string).collect()
+}
@@ -0,0 +1,837 @@
+//! The board and its rules, with no knowledge of how it is drawn
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::analysis::{debris, step_unbounded, still_lifes, AgeHistogram, AgeTracker, Cleanup};
+use crate::config::{AGE_STABLE, EDGE_SPAWN_RATE, GRID_MAX, LETHAL_MARGIN, MAX_CELLS, RANDOM_DENSITY};
+use crate::grid::{decide, CellFate, Grid, Position, Rect};
+use crate::error::GolError;
+use crate::pattern_io::{write_pattern_rle, LoadedPattern, Metadata};
+use crate::patterns::{Overhang, Pattern, PatternContext, PatternTooLarge, Transform};
+use crate::rng::Rng;
+use crate::rule::Rule;
+use crate::stepper::Edit;
+
+/// Explanation of a single cell's fate in the next generation
+pub struct CellVerdict {
+    pub alive: bool,         // Current state of the cell
+    pub neighbors: u8,       // Live neighbors under the current topology
+    pub alive_next: bool,    // State in the next generation
+    pub fate: CellFate,      // What the rule does to the cell
+    pub clause: &'static str, // Rule clause that decides the outcome
+}
+
+impl CellVerdict {
+    /// Apply the rule to a cell and record which clause fired. A live cell dying with fewer
+    /// neighbors than some survival count is underpopulated; otherwise it is overpopulated.
+    pub fn new(alive: bool, neighbors: u8, rule: Rule) -> Self {
+        let fate = decide(rule, alive, neighbors);
+        let clause = match fate {
+            CellFate::Survives => "survives",
+            CellFate::Dies if rule.survive >> (neighbors + 1) != 0 => "dies (underpopulation)",
+            CellFate::Dies => "dies (overpopulation)",
+            CellFate::Born => "birth",
+            CellFate::StaysDead => "stays dead",
+        };
+        Self { alive, neighbors, alive_next: fate.alive_next(), fate, clause }
+    }
+
+    /// One-line summary, e.g. "dead, 3 neighbors -> birth"
+    pub fn summary(&self) -> String {
+        format!(
+            "{}, {} neighbor{} -> {}",
+            if self.alive { "alive" } else { "dead" },
+            self.neighbors,
+            if self.neighbors == 1 { "" } else { "s" },
+            self.clause,
+        )
+    }
+}
+
+/// Approximate heap usage of a run's data, in bytes. `Simulation::memory_usage` fills in the
+/// board's own parts; the run adds what it keeps beside the board.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct MemoryUsage {
+    pub live: usize,      // Live cell set
+    pub ages: usize,      // Birth generation of each cell, while ages are on
+    pub diff: usize,      // Births and deaths of the last step
+    pub initial: usize,   // Generation 0, kept for restarts
+    pub timelapse: usize, // Time-lapse thumbnails
+    pub deltas: usize,    // Delta log being recorded
+    pub bookmarks: usize,
+    pub overlays: usize,  // Heat field, particles and the compare reference
+    pub tabs: usize,      // Everything held by the boards in background tabs
+}
+
+impl MemoryUsage {
+    pub fn total(&self) -> usize {
+        self.live + self.ages + self.diff + self.initial + self.timelapse + self.deltas + self.bookmarks + self.overlays + self.tabs
+    }
+
+    /// The parts over 1% of the total, largest first, e.g. "live 40.2 MB, deltas 12.0 MB"
+    pub fn breakdown(&self) -> String {
+        let mut parts = [
+            ("live", self.live), ("ages", self.ages), ("diff", self.diff), ("gen 0", self.initial),
+            ("time-lapse", self.timelapse), ("deltas", self.deltas), ("bookmarks", self.bookmarks),
+            ("overlays", self.overlays), ("other tabs", self.tabs),
+        ];
+        parts.sort_by_key(|&(_, bytes)| std::cmp::Reverse(bytes));
+        let total = self.total();
+        parts.iter()
+            .filter(|&&(_, bytes)| bytes > 0 && bytes * 100 >= total)
+            .map(|(name, bytes)| format!("{} {}", name, Self::format(*bytes)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Human-readable size, e.g. "3.4 MB"
+    pub fn format(bytes: usize) -> String {
+        const MB: f64 = 1024.0 * 1024.0;
+        if bytes as f64 >= MB {
+            format!("{:.1} MB", bytes as f64 / MB)
+        } else {
+            format!("{} KB", bytes / 1024)
+        }
+    }
+}
+
+/// Objects taken off the board by a cleanup, kept so it can be undone
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Cleaned {
+    pub objects: usize,
+    pub cells: HashSet<Position>,
+}
+
+impl fmt::Display for Cleaned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "removed {} object{} ({} cell{})",
+            self.objects, if self.objects == 1 { "" } else { "s" },
+            self.cells.len(), if self.cells.len() == 1 { "" } else { "s" })
+    }
+}
+
+/// Which cells a smart clear removes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClearScope {
+    All,
+    Outside(Rect),        // Everything outside the selection
+    Inside(Rect),         // Everything in the selection
+    ExceptStillLifes,     // Everything but objects that hold still
+    ExceptOlderThan(u64), // Everything but cells more than this many generations old
+}
+
+impl ClearScope {
+    /// What the scope leaves, for the toast
+    pub fn describe(&self) -> String {
+        match self {
+            ClearScope::All => "the board".to_string(),
+            ClearScope::Outside(_) => "outside the selection".to_string(),
+            ClearScope::Inside(_) => "inside the selection".to_string(),
+            ClearScope::ExceptStillLifes => "all but the still lifes".to_string(),
+            ClearScope::ExceptOlderThan(age) => format!("all but cells older than {} generations", age),
+        }
+    }
+}
+
+/// Approximate heap size of a hash set: one slot plus one control byte per bucket
+pub(crate) fn set_bytes<T>(set: &HashSet<T>) -> usize {
+    set.capacity() * (std::mem::size_of::<T>() + 1)
+}
+
+/// Approximate heap size of a hash map, counted like `set_bytes`
+pub(crate) fn map_bytes<K, V>(map: &HashMap<K, V>) -> usize {
+    map.capacity() * (std::mem::size_of::<(K, V)>() + 1)
+}
+
+/// Heap size of a vector's buffer
+pub(crate) fn vec_bytes<T>(v: &[T]) -> usize {
+    std::mem::size_of_val(v)
+}
+
+/// Cells that changed in the most recent step, kept only for smooth rendering
+pub struct GenerationDiff {
+    pub generation: u64, // Generation the diff leads to
+    pub born: HashSet<Position>,
+    pub died: Vec<Position>,
+}
+
+impl GenerationDiff {
+    pub fn bytes(&self) -> usize {
+        set_bytes(&self.born) + self.died.capacity() * std::mem::size_of::<Position>()
+    }
+}
+
+/// The board as it stood when a run started, so the run can be replayed exactly
+#[derive(Clone)]
+pub struct InitialState {
+    pub live: HashSet<Position>,
+    pub grid: Grid,
+    pub seed: u64,
+    pub density: f32,
+}
+
+/// A revision no board has had yet
+fn next_revision() -> u64 {
+    static REVISIONS: AtomicU64 = AtomicU64::new(0);
+    REVISIONS.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Core game state for Conway's Game of Life simulation
+pub struct Simulation {
+    pub live: HashSet<Position>,
+    pub grid: Grid,
+    pub generation: u64,     // Current generation count
+    pub max_cells: usize,    // Upper bound on live cells accepted from patterns
+    pub seed: u64,           // Seed for random fills and random patterns
+    pub density: f32,        // Fill density for R and the Random pattern
+    pub track_diff: bool,    // Record births and deaths of each step for smooth rendering
+    pub diff: Option<GenerationDiff>,
+    pub ages: Option<AgeTracker>, // Birth generation of each cell while age statistics are on
+    pub edge_spawn: bool,    // Inject random cells on the walled edges each generation (not on a torus)
+    pub edge_rate: f32,      // Chance per border cell per generation while edge spawning
+    pub injected: u64,       // Cells added by edge spawning since the last clear
+    pub lethal_margin: i32,  // Width of the border band culled after each generation (walled edges only); 0 is off
+    pub initial: Option<InitialState>, // Generation 0 of the run; `None` while still in edit mode
+    pub imported: Option<(String, Metadata)>, // Name and metadata of the pattern file the board was started from
+    edits: Option<Vec<Edit>>, // Cells set by hand since `take_edits`, while a worker steps this board
+    revision: u64,            // Stamp of the last change other than stepping, such as an edit, load or resize
+}
+
+impl Simulation {
+    /// Create an empty board with the specified dimensions
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            live: HashSet::new(),
+            grid: Grid::new(width, height),
+            generation: 0,
+            max_cells: MAX_CELLS,
+            seed: 0,
+            density: RANDOM_DENSITY,
+            track_diff: false,
+            diff: None,
+            ages: None,
+            edge_spawn: false,
+            lethal_margin: LETHAL_MARGIN,
+            edge_rate: EDGE_SPAWN_RATE,
+            injected: 0,
+            initial: None,
+            imported: None,
+            edits: None,
+            revision: next_revision(),
+        }
+    }
+
+    /// Whether the board is still being set up: no run has started, so there is no generation 0 yet
+    pub fn editing(&self) -> bool {
+        self.initial.is_none()
+    }
+
+    /// Start the run from the current board: it becomes generation 0, which a reset returns to
+    pub fn mark_initial(&mut self) {
+        self.initial = Some(InitialState {
+            live: self.live.clone(),
+            grid: self.grid.clone(),
+            seed: self.seed,
+            density: self.density,
+        });
+        self.restart_clock();
+    }
+
+    /// Put back the board, seed and density saved by `mark_initial`; false if no run has started
+    pub fn reset_to_initial(&mut self) -> bool {
+        let Some(initial) = self.initial.clone() else { return false };
+        self.live = initial.live;
+        self.grid = initial.grid;
+        self.seed = initial.seed;
+        self.density = initial.density;
+        self.restart_clock();
+        true
+    }
+
+    /// Put `pattern` where it asks to be when a run starts with it
+    pub fn place(&mut self, pattern: &dyn Pattern) -> Result<(), PatternTooLarge> {
+        let (x, y) = pattern.placement().anchor(pattern, self.grid.width, self.grid.height);
+        self.apply_pattern(pattern, x, y)?;
+        self.imported = pattern.metadata().map(|meta| (pattern.name().to_string(), meta.clone()));
+        Ok(())
+    }
+
+    /// Replace the board with `pattern` placed afresh under a new seed. A random soup comes out
+    /// different and edge spawning draws a new sequence. Once a run has started the result
+    /// becomes its generation 0.
+    pub fn reroll(&mut self, pattern: &dyn Pattern) -> Result<(), PatternTooLarge> {
+        self.clear();
+        self.next_seed();
+        let result = self.place(pattern);
+        if !self.editing() {
+            self.mark_initial();
+        }
+        result
+    }
+
+    /// Put a recorded board back as the current one, e.g. to jump back to an earlier generation
+    pub fn restore(&mut self, live: HashSet<Position>, generation: u64) {
+        self.live = live;
+        self.generation = generation;
+        self.revision = next_revision();
+        self.diff = None;
+        if self.ages.is_some() {
+            self.set_age_tracking(true);
+        }
+    }
+
+    /// Generation 0 for the cells now on the board
+    fn restart_clock(&mut self) {
+        self.generation = 0;
+        self.revision = next_revision();
+        self.injected = 0;
+        self.diff = None;
+        if self.ages.is_some() {
+            self.set_age_tracking(true);
+        }
+    }
+
+    /// Add a live cell at the specified position
+    pub fn add_cell(&mut self, x: i32, y: i32) {
+        if let Some(p) = self.grid.resolve(x, y) {
+            self.set_cell(p, true);
+        }
+    }
+
+    /// Toggle a cell between alive and dead states
+    pub fn toggle_cell(&mut self, x: i32, y: i32) {
+        let Some(p) = self.grid.resolve(x, y) else { return };
+        let alive = self.live.contains(&p);
+        self.set_cell(p, !alive);
+    }
+
+    /// Set a cell alive or dead, noting the change while edits are recorded
+    pub fn set_cell(&mut self, p: Position, alive: bool) {
+        let changed = if alive { self.live.insert(p) } else { self.live.remove(&p) };
+        if !changed {
+            return;
+        }
+        self.revision = next_revision();
+        if let Some(edits) = self.edits.as_mut() {
+            edits.push(Edit { at: p, alive });
+        }
+    }
+
+    /// Stamp of the last change to the board other than stepping. No two changes share one,
+    /// even on different boards, so a watcher that saw the same revision and generation before
+    /// has seen this board.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Start or stop recording the cells set by hand, for a worker running generations of a copy
+    /// of the board. Whole-board changes such as a clear or a resize are not recorded; they
+    /// start a new generation count or grid the worker's result is checked against.
+    pub fn record_edits(&mut self, on: bool) {
+        match (on, &self.edits) {
+            (false, _) => self.edits = None,
+            (true, None) => self.edits = Some(Vec::new()),
+            (true, Some(_)) => {}
+        }
+    }
+
+    /// The cells set by hand since the last call, oldest first
+    pub fn take_edits(&mut self) -> Vec<Edit> {
+        self.edits.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    /// Approximate memory held by the board and what it keeps about the run
+    pub fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            live: set_bytes(&self.live),
+            ages: self.ages.as_ref().map_or(0, AgeTracker::bytes),
+            diff: self.diff.as_ref().map_or(0, GenerationDiff::bytes),
+            initial: self.initial.as_ref().map_or(0, |i| set_bytes(&i.live)),
+            ..MemoryUsage::default()
+        }
+    }
+
+    /// Approximate memory the live set would hold with every cell of the board alive
+    pub fn full_board_bytes(&self) -> usize {
+        (self.grid.width as usize * self.grid.height as usize) * (std::mem::size_of::<Position>() + 1)
+    }
+
+    /// What `clean_up` would remove, without touching the board
+    pub fn debris(&self, cleanup: Cleanup) -> Cleaned {
+        let objects = debris(&self.live, &self.grid, cleanup);
+        Cleaned { objects: objects.len(), cells: objects.into_iter().flatten().collect() }
+    }
+
+    /// Remove small and settled objects, leaving everything else intact. Putting the returned
+    /// cells back undoes it.
+    pub fn clean_up(&mut self, cleanup: Cleanup) -> Cleaned {
+        let cleaned = self.debris(cleanup);
+        for &p in &cleaned.cells {
+            self.set_cell(p, false);
+        }
+        cleaned
+    }
+
+    /// The cells a clear of `scope` would remove, without touching the board. Keeping old
+    /// cells needs age tracking on; without it no cell has an age, so none is kept.
+    pub fn clear_plan(&self, scope: ClearScope) -> HashSet<Position> {
+        match scope {
+            ClearScope::All => self.live.clone(),
+            ClearScope::Outside(region) => self.live.iter().copied().filter(|p| !region.contains(*p)).collect(),
+            ClearScope::Inside(region) => self.live.iter().copied().filter(|p| region.contains(*p)).collect(),
+            ClearScope::ExceptStillLifes => {
+                let keep = still_lifes(&self.live, &self.grid);
+                self.live.difference(&keep).copied().collect()
+            }
+            ClearScope::ExceptOlderThan(age) => self.live.iter().copied()
+                .filter(|&p| self.ages.as_ref().and_then(|a| a.age(p, self.generation)).is_none_or(|a| a <= age))
+                .collect(),
+        }
+    }
+
+    /// Clear the cells `scope` covers and return them; putting them back undoes it. Clearing
+    /// everything also starts again from generation 0, as `clear` does.
+    pub fn clear_scope(&mut self, scope: ClearScope) -> HashSet<Position> {
+        if scope == ClearScope::All {
+            let cells = std::mem::take(&mut self.live);
+            self.clear();
+            return cells;
+        }
+        let cells = self.clear_plan(scope);
+        for &p in &cells {
+            self.set_cell(p, false);
+        }
+        self.diff = None;
+        if let Some(ages) = self.ages.as_mut() {
+            ages.sync(&self.live, self.generation);
+        }
+        cells
+    }
+
+    /// Remove all cells outside `region`, e.g. to recover from a runaway pattern
+    pub fn crop_to(&mut self, region: Rect) {
+        let outside: Vec<Position> = self.live.iter().copied().filter(|p| !region.contains(*p)).collect();
+        for p in outside {
+            self.set_cell(p, false);
+        }
+        self.live.shrink_to_fit();
+    }
+
+    /// Smallest rectangle holding every live cell, or `None` for an empty board
+    pub fn bounding_box(&self) -> Option<Rect> {
+        let mut cells = self.live.iter();
+        let first = *cells.next()?;
+        let (min, max) = cells.fold((first, first), |(lo, hi), p| {
+            (Position::new(lo.x().min(p.x()), lo.y().min(p.y())), Position::new(hi.x().max(p.x()), hi.y().max(p.y())))
+        });
+        Some(Rect::from_corners(min, max))
+    }
+
+    /// Shrink a bounded grid to the live cells plus `margin`, moving them near the origin, and
+    /// return how far they moved right and down. A torus or cylinder is left alone because its
+    /// size is part of how the pattern evolves, and a margin that would need a grid over
+    /// GRID_MAX is refused rather than cut short, which would leave cells off the board.
+    pub fn crop_to_content(&mut self, margin: i32) -> Result<(i32, i32), GolError> {
+        if self.grid.topology.wraps() {
+            return Err(GolError::Unsupported("cropping would change the wrapped edges; turn wrap off first".to_string()));
+        }
+        if !(0..=GRID_MAX).contains(&margin) {
+            return Err(GolError::invalid("margin", format!("crop margin {} is outside 0 to {}", margin, GRID_MAX)));
+        }
+        let Some(content) = self.bounding_box() else { return Err(GolError::invalid("board", "the board is empty")) };
+        let (width, height) = (content.w + 2 * margin, content.h + 2 * margin);
+        if width > GRID_MAX || height > GRID_MAX {
+            return Err(GolError::invalid("grid size", format!("a margin of {} needs a {}x{} grid, larger than {}", margin, width, height, GRID_MAX)));
+        }
+        let (dx, dy) = (margin - content.x, margin - content.y);
+        self.live = self.live.iter().map(|p| Position::new(p.x() + dx, p.y() + dy)).collect();
+        self.grid.width = width;
+        self.grid.height = height;
+        self.diff = None;
+        self.revision = next_revision();
+        Ok((dx, dy))
+    }
+
+    /// Grow the walls a placement hangs over far enough to take it, plus `margin` empty cells,
+    /// keeping every live cell where it is on the board. Returns how far the old board moved
+    /// right and down, which the placement's anchor must move too.
+    pub fn expand_for(&mut self, overhang: Overhang, margin: i32) -> Result<(i32, i32), GolError> {
+        let grow = |n: i32| if n > 0 { n + margin } else { 0 };
+        let (left, top) = (grow(overhang.left), grow(overhang.top));
+        let (width, height) = (self.grid.width + left + grow(overhang.right), self.grid.height + top + grow(overhang.bottom));
+        if width > GRID_MAX || height > GRID_MAX {
+            return Err(GolError::invalid("grid size", format!("fitting the pattern needs a {}x{} grid, larger than {}", width, height, GRID_MAX)));
+        }
+        self.live = self.live.iter().map(|p| Position::new(p.x() + left, p.y() + top)).collect();
+        self.grid.width = width;
+        self.grid.height = height;
+        self.diff = None;
+        self.revision = next_revision();
+        if self.ages.is_some() {
+            self.set_age_tracking(true);
+        }
+        Ok((left, top))
+    }
+
+    /// Remove all cells and reset generation count to zero
+    pub fn clear(&mut self) {
+        self.live = HashSet::new();
+        self.generation = 0;
+        self.revision = next_revision();
+        self.injected = 0;
+        self.diff = None;
+        self.imported = None;
+        if self.ages.is_some() {
+            self.ages = Some(AgeTracker::default());
+        }
+    }
+
+    /// Move on to a new seed derived from the current one
+    pub fn next_seed(&mut self) {
+        self.seed = Rng::new(self.seed).next_u64();
+    }
+
+    /// Randomly distribute cells across the grid, reproducibly from `seed`
+    pub fn random_fill(&mut self, density: f32) {
+        let mut rng = Rng::new(self.seed);
+        for y in 0..self.grid.height {
+            for x in 0..self.grid.width {
+                if rng.next_f32() < density {
+                    self.add_cell(x, y);
+                }
+            }
+        }
+    }
+
+    /// Calculate the next generation of cells
+    pub fn next_generation(&mut self) {
+        let mut next = self.grid.next_generation(&self.live);
+        if self.culls() {
+            // Edge spawning would only feed the cull, so it waits while the margin is on
+            next.retain(|p| !self.in_lethal_margin(*p));
+        } else if self.edge_spawn && !self.grid.topology.is_torus() {
+            self.inject_edges(&mut next);
+        }
+        self.diff = self.track_diff.then(|| GenerationDiff {
+            generation: self.generation + 1,
+            born: next.difference(&self.live).copied().collect(),
+            died: self.live.difference(&next).copied().collect(),
+        });
+        self.live = next;
+        self.generation += 1;
+        if let Some(ages) = self.ages.as_mut() {
+            ages.sync(&self.live, self.generation);
+        }
+    }
+
+    /// Switch to another rule from the next generation on. B0 rules are refused with the reason.
+    pub fn set_rule(&mut self, rule: Rule) -> Result<(), String> {
+        self.grid.rule = rule.runnable()?;
+        Ok(())
+    }
+
+    /// Whether a lethal margin applies: it is set and the grid has a wall
+    pub fn culls(&self) -> bool {
+        self.lethal_margin > 0 && !self.grid.topology.is_torus()
+    }
+
+    /// Whether a cell lies in the lethal margin. Margin cells are dead whenever a generation is
+    /// computed, so the margin works as a wall `lethal_margin` cells in from each walled edge.
+    pub fn in_lethal_margin(&self, p: Position) -> bool {
+        let (k, topology) = (self.lethal_margin, self.grid.topology);
+        self.culls()
+            && ((!topology.wraps_x() && (p.x() < k || p.x() >= self.grid.width - k))
+                || (!topology.wraps_y() && (p.y() < k || p.y() >= self.grid.height - k)))
+    }
+
+    /// Bring random border cells to life in the next generation. The RNG is keyed on the
+    /// seed and generation so a run is reproducible whenever the mode was switched on.
+    fn inject_edges(&mut self, next: &mut HashSet<Position>) {
+        let mut rng = Rng::new(self.seed ^ (self.generation + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        let (w, h, topology) = (self.grid.width, self.grid.height, self.grid.topology);
+        let border = (0..w).flat_map(|x| [(x, 0), (x, h - 1)])
+            .chain((1..h - 1).flat_map(|y| [(0, y), (w - 1, y)]))
+            // Only walled edges spawn; a cylinder's seam is not an edge
+            .filter(|&(x, y)| (!topology.wraps_y() && (y == 0 || y == h - 1)) || (!topology.wraps_x() && (x == 0 || x == w - 1)));
+        for (x, y) in border {
+            if rng.next_f32() < self.edge_rate && next.insert(Position::new(x, y)) {
+                self.injected += 1;
+            }
+        }
+    }
+
+    /// Turn cell age tracking on or off; cells alive when it starts count as newborn
+    pub fn set_age_tracking(&mut self, on: bool) {
+        self.ages = on.then(|| AgeTracker::new(&self.live, self.generation));
+    }
+
+    /// Cell ages grouped into `AGE_BUCKETS`, or `None` while age tracking is off
+    pub fn age_histogram(&self) -> Option<AgeHistogram> {
+        self.ages.as_ref().map(|a| a.histogram(self.generation, AGE_STABLE))
+    }
+
+    /// Copy the cells inside `region` into a fresh bounded board, padded with `margin` empty cells
+    pub fn isolate(&self, region: Rect, margin: i32) -> Simulation {
+        let mut sandbox = Simulation::new(region.w + 2 * margin, region.h + 2 * margin);
+        sandbox.grid.rule = self.grid.rule;
+        sandbox.live = self.live.iter()
+            .filter(|p| region.contains(**p))
+            .map(|p| Position::new(p.x() - region.x + margin, p.y() - region.y + margin))
+            .collect();
+        sandbox
+    }
+
+    /// The board as RLE. A board started from a pattern file keeps the file's name, author,
+    /// comments and offset; any other gets a comment with its generation, seed and rule.
+    pub fn to_rle(&self) -> String {
+        let bounds = self.bounding_box().unwrap_or(Rect::new(0, 0, 0, 0));
+        let (name, meta) = self.imported.clone().unwrap_or_else(|| {
+            let comment = format!("Generation {}, seed {}, rule {}", self.generation, self.seed, self.grid.rule);
+            (String::new(), Metadata { comments: vec![comment], ..Metadata::default() })
+        });
+        write_pattern_rle(&LoadedPattern {
+            name,
+            rule: Some(self.grid.rule.to_string()),
+            cells: self.live.iter().map(|p| (p.x() - bounds.x, p.y() - bounds.y)).collect(),
+            width: bounds.w,
+            height: bounds.h,
+            meta,
+        })
+    }
+
+    /// The live cells inside `region` as a pattern the size of the region, to paste as a stamp
+    pub fn copy_region(&self, region: Rect, name: &str) -> LoadedPattern {
+        let mut cells: Vec<(i32, i32)> = self.live.iter()
+            .filter(|p| region.contains(**p))
+            .map(|p| (p.x() - region.x, p.y() - region.y))
+            .collect();
+        cells.sort_unstable();
+        LoadedPattern { name: name.to_string(), rule: None, cells, width: region.w, height: region.h, meta: Metadata::default() }
+    }
+
+    /// Replace the cells inside `region` with the matching area of a sandbox made by `isolate`
+    pub fn commit_region(&mut self, region: Rect, sandbox: &Simulation, margin: i32) {
+        let inner = Rect::new(margin, margin, region.w, region.h);
+        let cells: HashSet<Position> = sandbox.live.iter()
+            .filter(|p| inner.contains(**p))
+            .map(|p| Position::new(p.x() - margin + region.x, p.y() - margin + region.y))
+            .collect();
+        let gone: Vec<Position> = self.live.iter().copied().filter(|p| region.contains(*p) && !cells.contains(p)).collect();
+        for p in gone {
+            self.set_cell(p, false);
+        }
+        for p in cells {
+            self.set_cell(p, true);
+        }
+    }
+
+    /// Explain what will happen to the cell at (x, y) in the next generation
+    pub fn inspect(&self, x: i32, y: i32) -> Option<CellVerdict> {
+        let pos = self.grid.resolve(x, y)?;
+        let neighbors = self.grid.neighbor_count(&self.live, pos);
+        Some(CellVerdict::new(self.live.contains(&pos), neighbors, self.grid.rule))
+    }
+
+    /// Cells a pattern would occupy when placed at (x, y) with the given orientation,
+    /// after running it on its own for `phase` generations
+    pub fn pattern_cells(
+        &self,
+        pattern: &dyn Pattern,
+        transform: Transform,
+        phase: u32,
+        x: i32,
+        y: i32,
+    ) -> Result<HashSet<Position>, PatternTooLarge> {
+        self.placement(pattern, transform, phase, x, y).map(|(cells, _)| cells)
+    }
+
+    /// Like `pattern_cells`, along with the cells that would fall past a wall and be lost
+    pub fn placement(
+        &self,
+        pattern: &dyn Pattern,
+        transform: Transform,
+        phase: u32,
+        x: i32,
+        y: i32,
+    ) -> Result<(HashSet<Position>, Overhang), PatternTooLarge> {
+        if phase > 0 {
+            return self.phased_cells(pattern, transform, phase, x, y);
+        }
+        let mut staged = HashSet::new();
+        let mut ctx = PatternContext {
+            cells: &mut staged,
+            grid_width: self.grid.width,
+            grid_height: self.grid.height,
+            topology: self.grid.topology,
+            anchor: (x, y),
+            transform,
+            seed: self.seed,
+            density: self.density,
+            max_cells: self.max_cells.saturating_sub(self.live.len()),
+            overflowed: false,
+            overhang: Overhang::default(),
+        };
+
+        pattern.apply(&mut ctx, x, y);
+        if ctx.overflowed {
+            return Err(PatternTooLarge { limit: self.max_cells });
+        }
+        let overhang = ctx.overhang;
+        Ok((staged, overhang))
+    }
+
+    /// Stage the pattern far from any edge, step it on an unbounded plane so nothing else on the
+    /// board (or the board's edge) affects it, then place the result at (x, y)
+    fn phased_cells(
+        &self,
+        pattern: &dyn Pattern,
+        transform: Transform,
+        phase: u32,
+        x: i32,
+        y: i32,
+    ) -> Result<(HashSet<Position>, Overhang), PatternTooLarge> {
+        let center = GRID_MAX / 2;
+        let mut scratch = Simulation::new(GRID_MAX, GRID_MAX);
+        scratch.seed = self.seed;
+        scratch.density = self.density;
+        let staged = scratch.pattern_cells(pattern, transform, 0, center, center)?;
+        let mut cells: Vec<(i32, i32)> = staged.iter().map(|p| (p.x() - center, p.y() - center)).collect();
+        for _ in 0..phase {
+            cells = step_unbounded(&cells);
+        }
+        let room = self.max_cells.saturating_sub(self.live.len());
+        let mut placed = HashSet::new();
+        let mut overhang = Overhang::default();
+        let (w, h) = (self.grid.width, self.grid.height);
+        for (dx, dy) in cells {
+            match self.grid.resolve(x + dx, y + dy) {
+                Some(_) if placed.len() >= room => return Err(PatternTooLarge { limit: self.max_cells }),
+                Some(p) => { placed.insert(p); }
+                None => overhang.add(x + dx, y + dy, w, h, self.grid.topology),
+            }
+        }
+        Ok((placed, overhang))
+    }
+
+    /// Apply a pattern at the specified position.
+    /// The board is left untouched if the pattern would exceed `max_cells`.
+    pub fn apply_pattern(&mut self, pattern: &dyn Pattern, x: i32, y: i32) -> Result<(), PatternTooLarge> {
+        self.stamp(pattern, Transform::IDENTITY, 0, x, y)
+    }
+
+    /// Apply a rotated/mirrored pattern, advanced `phase` generations, anchored at the specified position
+    pub fn stamp(
+        &mut self,
+        pattern: &dyn Pattern,
+        transform: Transform,
+        phase: u32,
+        x: i32,
+        y: i32,
+    ) -> Result<(), PatternTooLarge> {
+        let cells = self.pattern_cells(pattern, transform, phase, x, y)?;
+        for p in cells {
+            self.set_cell(p, true);
+        }
+        Ok(())
+    }
+
+    /// Like `stamp`, but first clears the pattern's bounding box so it lands in empty space
+    pub fn stamp_clean(
+        &mut self,
+        pattern: &dyn Pattern,
+        transform: Transform,
+        phase: u32,
+        x: i32,
+        y: i32,
+    ) -> Result<(), PatternTooLarge> {
+        let cells = self.pattern_cells(pattern, transform, phase, x, y)?;
+        for p in self.footprint(&cells, x, y) {
+            if !cells.contains(&p) {
+                self.set_cell(p, false);
+            }
+        }
+        for p in cells {
+            self.set_cell(p, true);
+        }
+        Ok(())
+    }
+
+    /// How many of a stamp's cells land on live cells
+    pub fn overlap(&self, cells: &HashSet<Position>) -> usize {
+        cells.iter().filter(|p| self.live.contains(p)).count()
+    }
+
+    /// Every cell in the bounding box of a stamp's cells anchored at (x, y). On a torus the box
+    /// is measured from the anchor across the seam, so a stamp over a wrapped edge covers both sides.
+    pub fn footprint(&self, cells: &HashSet<Position>, x: i32, y: i32) -> HashSet<Position> {
+        let Some(&first) = cells.iter().next() else { return HashSet::new() };
+        let start = self.grid.offset(x, y, first);
+        let (lo, hi) = cells.iter().fold((start, start), |(lo, hi), &p| {
+            let (dx, dy) = self.grid.offset(x, y, p);
+            ((lo.0.min(dx), lo.1.min(dy)), (hi.0.max(dx), hi.1.max(dy)))
+        });
+        (lo.1..=hi.1)
+            .flat_map(|dy| (lo.0..=hi.0).map(move |dx| (dx, dy)))
+            .filter_map(|(dx, dy)| self.grid.resolve(x + dx, y + dy))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::RandomPattern;
+
+    #[test]
+    fn cropping_keeps_every_cell_on_the_board() {
+        let mut sim = Simulation::new(GRID_MAX, 20);
+        sim.live.extend([Position::new(0, 5), Position::new(GRID_MAX - 1, 6)]);
+        // The cells already span the widest grid, so any margin would push one off it
+        assert!(matches!(sim.crop_to_content(1), Err(GolError::Invalid { what: "grid size", .. })));
+        assert!(matches!(sim.crop_to_content(-1), Err(GolError::Invalid { what: "margin", .. })));
+        assert_eq!((sim.grid.width, sim.grid.height, sim.live.len()), (GRID_MAX, 20, 2));
+        assert_eq!(sim.crop_to_content(0).unwrap(), (0, -5));
+        assert_eq!((sim.grid.width, sim.grid.height), (GRID_MAX, 2));
+        assert!(sim.live.iter().all(|p| sim.grid.in_bounds(p.x(), p.y())));
+        sim.grid.topology = crate::grid::Topology::Torus;
+        assert!(matches!(sim.crop_to_content(0), Err(GolError::Unsupported(_))));
+    }
+
+    #[test]
+    fn random_fill_at_the_ends_of_the_density_range() {
+        for (width, height) in [(10, 10), (37, 5), (1, 64)] {
+            for seed in [0, 1, 7] {
+                let mut sim = Simulation::new(width, height);
+                sim.seed = seed;
+                sim.random_fill(0.0);
+                assert!(sim.live.is_empty(), "density 0 on {}x{} seed {}", width, height, seed);
+                sim.random_fill(1.0);
+                assert_eq!(sim.live.len(), (width * height) as usize, "density 1 on {}x{} seed {}", width, height, seed);
+            }
+        }
+    }
+
+    #[test]
+    fn random_fill_follows_the_seed() {
+        let fill = |seed| {
+            let mut sim = Simulation::new(32, 24);
+            sim.seed = seed;
+            sim.random_fill(0.4);
+            sim.live
+        };
+        assert_eq!(fill(5), fill(5));
+        assert_ne!(fill(5), fill(6));
+    }
+
+    #[test]
+    fn random_pattern_uses_the_board_density() {
+        for (density, expected) in [(0.0, 0), (1.0, 300)] {
+            let mut sim = Simulation::new(20, 15);
+            sim.density = density;
+            sim.apply_pattern(&RandomPattern, 0, 0).unwrap();
+            assert_eq!(sim.live.len(), expected, "density {}", density);
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! Generation stepping on a worker thread. The render thread keeps drawing its own board
+//! while the worker computes into a second buffer; the result is swapped in when it lands.
+//! Cells set by hand while a job runs are queued to the worker, which applies them between
+//! generations in the order they were made.
+
+use std::collections::HashSet;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread::{self, JoinHandle};
+
+use crate::config::STEPPER_EDIT_QUEUE;
+use crate::grid::{Grid, Position};
+
+/// A cell set alive or dead by hand
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Edit {
+    pub at: Position,
+    pub alive: bool,
+}
+
+impl Edit {
+    pub fn apply(self, live: &mut HashSet<Position>) {
+        if self.alive { live.insert(self.at); } else { live.remove(&self.at); }
+    }
+}
+
+/// Work handed to the worker: the board and how many generations to run
+struct Job {
+    id: u64,
+    grid: Grid,
+    live: HashSet<Position>,
+    steps: u32,
+}
+
+/// A finished job
+pub struct Stepped {
+    pub id: u64,
+    pub live: HashSet<Position>,
+    applied: usize, // Edits the worker applied between generations
+}
+
+/// Owns the worker thread. Dropping it closes the job channel and joins the thread,
+/// so shutdown never leaves a worker writing into a board nobody reads.
+pub struct Stepper {
+    jobs: Option<SyncSender<Job>>,
+    edits: SyncSender<(u64, Edit)>, // Tagged with the job they were made during
+    results: Receiver<Stepped>,
+    handle: Option<JoinHandle<()>>,
+    next_id: u64,
+    made: Vec<Edit>,  // Edits made while the latest job runs, oldest first
+    queued: usize,    // How many of them went to the worker; once one doesn't fit, the rest wait for the result
+}
+
+impl Stepper {
+    /// Start the worker, or `None` where threads are unavailable (WASM)
+    pub fn spawn() -> Option<Self> {
+        if cfg!(target_arch = "wasm32") {
+            return None;
+        }
+        // One job in flight at a time; the caller waits for its result before sending another
+        let (jobs, inbox) = mpsc::sync_channel::<Job>(1);
+        let (edits, edit_inbox) = mpsc::sync_channel(STEPPER_EDIT_QUEUE);
+        let (outbox, results) = mpsc::channel();
+        let handle = thread::Builder::new()
+            .name("life-stepper".to_string())
+            .spawn(move || {
+                let mut early = Vec::new();
+                for job in inbox {
+                    if outbox.send(run(job, &edit_inbox, &mut early)).is_err() {
+                        break;
+                    }
+                }
+            })
+            .ok()?;
+        Some(Self { jobs: Some(jobs), edits, results, handle: Some(handle), next_id: 0, made: Vec::new(), queued: 0 })
+    }
+
+    /// Queue `steps` generations of `live`; returns the id its result will carry
+    pub fn submit(&mut self, grid: &Grid, live: HashSet<Position>, steps: u32) -> u64 {
+        self.next_id += 1;
+        self.made.clear();
+        self.queued = 0;
+        let job = Job { id: self.next_id, grid: grid.clone(), live, steps };
+        if let Some(jobs) = &self.jobs {
+            // Only fails if the worker panicked; the result then never arrives and the
+            // caller's pending id goes stale, which it already handles
+            let _ = jobs.send(job);
+        }
+        self.next_id
+    }
+
+    /// Pass on a cell set by hand since the latest job was submitted. The worker applies it
+    /// before its next generation; one that arrives too late, or while the queue is full, is
+    /// applied to the result instead. Either way it lands exactly once, after the ones before it.
+    pub fn edit(&mut self, edit: Edit) {
+        if self.queued == self.made.len() && self.edits.try_send((self.next_id, edit)).is_ok() {
+            self.queued += 1;
+        }
+        self.made.push(edit);
+    }
+
+    /// A finished job, if one is ready
+    pub fn poll(&self) -> Option<Stepped> {
+        self.results.try_recv().ok().map(|done| self.finish(done))
+    }
+
+    /// Block until the next job finishes
+    pub fn wait(&self) -> Option<Stepped> {
+        self.results.recv().ok().map(|done| self.finish(done))
+    }
+
+    /// Apply the latest job's edits the worker didn't get to
+    fn finish(&self, mut done: Stepped) -> Stepped {
+        if done.id == self.next_id {
+            for edit in &self.made[done.applied..] {
+                edit.apply(&mut done.live);
+            }
+        }
+        done
+    }
+}
+
+impl Drop for Stepper {
+    fn drop(&mut self) {
+        self.jobs.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Run a job, applying the edits queued for it before each generation. Edits left over from
+/// an earlier job were applied to its result already, so they are dropped; ones for a job
+/// submitted behind this one wait in `early` for it.
+fn run(job: Job, edits: &Receiver<(u64, Edit)>, early: &mut Vec<(u64, Edit)>) -> Stepped {
+    let (mut live, mut applied) = (job.live, 0);
+    for _ in 0..job.steps {
+        early.extend(edits.try_iter());
+        early.retain(|&(id, edit)| {
+            if id == job.id {
+                edit.apply(&mut live);
+                applied += 1;
+            }
+            id > job.id
+        });
+        live = job.grid.next_generation(&live);
+    }
+    Stepped { id: job.id, live, applied }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::reference::{from_ascii, random_soup, to_ascii};
+    use crate::grid::Topology;
+
+    fn set(x: i32, y: i32, alive: bool) -> Edit {
+        Edit { at: Position::new(x, y), alive }
+    }
+
+    #[test]
+    fn worker_applies_its_job_edits_in_order_before_stepping() {
+        let (grid, live) = from_ascii("....../....../....../....../......", Topology::Bounded);
+        let (tx, rx) = mpsc::sync_channel(16);
+        // A stale edit from job 1, then a horizontal blinker drawn with one cell set and unset
+        // on the way; applied in any other order the middle cell would end up dead
+        tx.send((1, set(0, 0, true))).unwrap();
+        for edit in [set(1, 2, true), set(2, 2, true), set(2, 2, false), set(3, 2, true), set(2, 2, true)] {
+            tx.send((2, edit)).unwrap();
+        }
+        let done = run(Job { id: 2, grid: grid.clone(), live, steps: 1 }, &rx, &mut Vec::new());
+        let (_, expected) = from_ascii("....../..#.../..#.../..#.../......", Topology::Bounded);
+        assert_eq!(to_ascii(&grid, &done.live), to_ascii(&grid, &expected));
+        assert_eq!(done.applied, 5);
+    }
+
+    #[test]
+    fn edits_for_a_later_job_wait_for_it() {
+        let grid = Grid::new(6, 5);
+        let (tx, rx) = mpsc::sync_channel(16);
+        for x in 1..=3 {
+            tx.send((3, set(x, 2, true))).unwrap();
+        }
+        let mut early = Vec::new();
+        let first = run(Job { id: 2, grid: grid.clone(), live: HashSet::new(), steps: 2 }, &rx, &mut early);
+        assert!(first.live.is_empty());
+        assert_eq!(first.applied, 0);
+        let second = run(Job { id: 3, grid: grid.clone(), live: HashSet::new(), steps: 2 }, &rx, &mut early);
+        assert_eq!(second.applied, 3);
+        assert_eq!(second.live, (1..=3).map(|x| Position::new(x, 2)).collect());
+        assert!(early.is_empty());
+    }
+
+    #[test]
+    fn worker_matches_the_engine() {
+        let mut stepper = Stepper::spawn().unwrap();
+        for topology in [Topology::Bounded, Topology::Torus] {
+            let mut grid = Grid::new(64, 48);
+            grid.topology = topology;
+            let sent = random_soup(&grid, 0.35, 5);
+            let expected = (0..200).fold(sent.clone(), |live, _| grid.next_generation(&live));
+            let id = stepper.submit(&grid, sent, 200);
+            let done = stepper.wait().unwrap();
+            assert_eq!(done.id, id);
+            assert_eq!(to_ascii(&grid, &done.live), to_ascii(&grid, &expected), "{}", topology);
+        }
+        // Dropping with a job in flight joins cleanly rather than hanging
+        stepper.submit(&Grid::new(8, 8), HashSet::new(), 1);
+        drop(stepper);
+    }
+
+    #[test]
+    fn edits_the_worker_missed_land_on_the_result() {
+        let mut stepper = Stepper::spawn().unwrap();
+        let grid = Grid::new(8, 8);
+        // With no generations to run the worker applies nothing, so every edit waits for the result
+        let id = stepper.submit(&grid, HashSet::from([Position::new(0, 0)]), 0);
+        for edit in [set(5, 5, true), set(0, 0, false), set(5, 5, false), set(6, 6, true), set(0, 0, true)] {
+            stepper.edit(edit);
+        }
+        let done = stepper.wait().unwrap();
+        assert_eq!(done.id, id);
+        assert_eq!(done.live, HashSet::from([Position::new(0, 0), Position::new(6, 6)]));
+    }
+
+    #[test]
+    fn every_edit_lands_exactly_once() {
+        // Cells far from anything else, set and cleared in pairs, end up dead whichever
+        // generation the worker applies each edit before, or if it waits for the result; more
+        // pairs than the queue holds send some the long way
+        let mut stepper = Stepper::spawn().unwrap();
+        let mut grid = Grid::new(64, 64);
+        grid.topology = Topology::Torus;
+        let block: HashSet<Position> = [(1, 1), (2, 1), (1, 2), (2, 2)].into_iter().map(|(x, y)| Position::new(x, y)).collect();
+        stepper.submit(&grid, block.clone(), 200);
+        for i in 0..(STEPPER_EDIT_QUEUE as i32 + 50) {
+            let at = (20 + i % 40, 20 + (i / 40) % 40);
+            stepper.edit(set(at.0, at.1, true));
+            stepper.edit(set(at.0, at.1, false));
+        }
+        assert_eq!(stepper.wait().unwrap().live, block);
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use macroquad::prelude::*;
+
+use crate::grid::{Grid, Position, Topology};
+use crate::themes::ThemeColors;
+
+/// Display colors of the two players
+pub const PLAYER_COLORS: [Color; 2] = [
+    Color::new(0.25, 0.6, 1.0, 1.0),  // Blue
+    Color::new(1.0, 0.4, 0.3, 1.0),   // Red
+];
+
+/// Stage of a territory match
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Phase {
+    Placing,                       // Players alternate placing cells on their own half
+    Countdown { seconds: f32 },    // Short pause before the simulation starts
+    Running,                       // Immigration rules run for the fixed number of generations
+    Finished,
+}
+
+/// Why a placement was refused
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PlaceError {
+    NotPlacing,
+    WrongHalf,
+    Occupied,
+}
+
+impl fmt::Display for PlaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlaceError::NotPlacing => write!(f, "placement is over"),
+            PlaceError::WrongHalf => write!(f, "place cells on your own half of the board"),
+            PlaceError::Occupied => write!(f, "that cell is already taken"),
+        }
+    }
+}
+
+/// Two-player competitive mode: place cells, run Immigration on a torus, count territory
+pub struct Territory {
+    pub grid: Grid,
+    pub cells: HashMap<Position, u8>, // Live cells and the player owning each
+    pub phase: Phase,
+    pub turn: u8,                     // Player whose placement is next
+    pub remaining: [u32; 2],          // Cells each player may still place
+    pub generation: u64,
+    pub generations: u64,             // Length of the simulated phase
+    budget: u32,
+    first: u8,                        // Player who opened this match
+}
+
+impl Territory {
+    pub const COUNTDOWN_SECONDS: f32 = 3.0;
+
+    pub fn new(width: i32, height: i32, budget: u32, generations: u64) -> Self {
+        let mut grid = Grid::new(width, height);
+        grid.topology = Topology::Torus;
+        Self {
+            grid,
+            cells: HashMap::new(),
+            phase: Phase::Placing,
+            turn: 0,
+            remaining: [budget; 2],
+            generation: 0,
+            generations,
+            budget,
+            first: 0,
+        }
+    }
+
+    /// Player allowed to place at column `x`: left half is player 0, right half player 1
+    pub fn owner_of_column(&self, x: i32) -> u8 {
+        if x < self.grid.width / 2 { 0 } else { 1 }
+    }
+
+    /// Place a cell for the current player and pass the turn
+    pub fn place(&mut self, x: i32, y: i32) -> Result<(), PlaceError> {
+        if self.phase != Phase::Placing { return Err(PlaceError::NotPlacing); }
+        let pos = self.grid.resolve(x, y).ok_or(PlaceError::WrongHalf)?;
+        if self.owner_of_column(pos.x()) != self.turn { return Err(PlaceError::WrongHalf); }
+        if self.cells.contains_key(&pos) { return Err(PlaceError::Occupied); }
+
+        self.cells.insert(pos, self.turn);
+        self.remaining[self.turn as usize] -= 1;
+        let other = 1 - self.turn;
+        if self.remaining[other as usize] > 0 {
+            self.turn = other;
+        } else if self.remaining[self.turn as usize] == 0 {
+            self.phase = Phase::Countdown { seconds: Self::COUNTDOWN_SECONDS };
+        }
+        Ok(())
+    }
+
+    /// Advance the countdown by `dt` seconds
+    pub fn tick(&mut self, dt: f32) {
+        if let Phase::Countdown { seconds } = self.phase {
+            let left = seconds - dt;
+            self.phase = if left <= 0.0 { Phase::Running } else { Phase::Countdown { seconds: left } };
+        }
+    }
+
+    /// Run one Immigration generation, finishing the match when the limit is reached
+    pub fn step(&mut self) {
+        if self.phase != Phase::Running { return; }
+        self.cells = self.grid.next_generation_immigration(&self.cells);
+        self.generation += 1;
+        if self.generation >= self.generations {
+            self.phase = Phase::Finished;
+        }
+    }
+
+    /// Live cells owned by each player
+    pub fn scores(&self) -> [usize; 2] {
+        let mut scores = [0; 2];
+        for &owner in self.cells.values() {
+            scores[(owner & 1) as usize] += 1;
+        }
+        scores
+    }
+
+    /// Player with more cells, or `None` for a tie
+    pub fn winner(&self) -> Option<u8> {
+        let [a, b] = self.scores();
+        match a.cmp(&b) {
+            std::cmp::Ordering::Greater => Some(0),
+            std::cmp::Ordering::Less => Some(1),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    /// Start a fresh match with the same settings; the other player opens
+    pub fn rematch(&mut self) {
+        let first = 1 - self.first;
+        *self = Territory::new(self.grid.width, self.grid.height, self.budget, self.generations);
+        self.first = first;
+        self.turn = first;
+    }
+
+    /// Draw both halves, the cells and the HUD for the current phase
+    pub fn draw(&self, offset: Vec2, cell: i32, colors: &ThemeColors) {
+        clear_background(colors.background);
+        let size = cell as f32;
+        let half = (self.grid.width / 2 * cell) as f32;
+        let board = vec2((self.grid.width * cell) as f32, (self.grid.height * cell) as f32);
+
+        // Tint each player's half while placing
+        if self.phase == Phase::Placing {
+            for (player, x) in [(0usize, offset.x), (1, offset.x + half)] {
+                let mut tint = PLAYER_COLORS[player];
+                tint.a = if player as u8 == self.turn { 0.14 } else { 0.05 };
+                let w = if player == 0 { half } else { board.x - half };
+                draw_rectangle(x, offset.y, w, board.y, tint);
+            }
+            draw_line(offset.x + half, offset.y, offset.x + half, offset.y + board.y, 2.0, colors.border);
+        }
+        for (p, &owner) in &self.cells {
+            let x = offset.x + (p.x() * cell) as f32;
+            let y = offset.y + (p.y() * cell) as f32;
+            draw_rectangle(x, y, size, size, PLAYER_COLORS[(owner & 1) as usize]);
+        }
+        draw_rectangle_lines(offset.x, offset.y, board.x, board.y, 3.0, colors.border);
+
+        let [a, b] = self.scores();
+        let status = match self.phase {
+            Phase::Placing => format!(
+                "Player {}'s turn | cells left: blue {} / red {}",
+                self.turn + 1, self.remaining[0], self.remaining[1],
+            ),
+            Phase::Countdown { seconds } => format!("Starting in {:.0}...", seconds.ceil()),
+            Phase::Running => format!("Generation {}/{}", self.generation, self.generations),
+            Phase::Finished => match self.winner() {
+                Some(p) => format!("Player {} wins!", p + 1),
+                None => "It's a tie!".to_string(),
+            },
+        };
+        draw_text(&format!("TERRITORY | {} | score: blue {} - red {}", status, a, b), 10.0, 22.0, 22.0, colors.text);
+        let help = match self.phase {
+            Phase::Finished => "Enter: Rematch | Esc: Menu",
+            _ => "Click your half to place a cell | Esc: Menu",
+        };
+        draw_text(help, 10.0, 46.0, 18.0, colors.text_secondary);
+    }
+}
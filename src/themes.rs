@@ -1,30 +1,50 @@
+use std::cell::OnceCell;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use macroquad::prelude::*;
 
-#[derive(Clone, Copy, PartialEq, Eq, Default)]
+use crate::config::{RANDOM_PALETTE_TRIES, THEME_MIN_CONTRAST};
+use crate::error::{self, GolError, Kept, Location};
+use crate::rng::Rng;
+use crate::settings::Settings;
+
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
 pub enum ColorTheme {
     #[default]
     Classic,  // Original green
     Dark,     // Dark theme (white on black)
     Pastel,   // Pastel theme
     Neon,     // Neon theme
+    Safe,     // Okabe-Ito blue and orange, distinct under red-green color blindness
+    Custom(Palette), // A palette from the themes file or the picker's randomizer
 }
 
 pub struct ThemeColors {
     pub background: Color,
     pub cell: Color,
     pub grid: Color,
+    pub grid_major: Color,
     pub border: Color,
     pub text: Color,
     pub text_secondary: Color,
 }
 
 impl ColorTheme {
+    pub const ALL: [ColorTheme; 5] = [ColorTheme::Classic, ColorTheme::Dark, ColorTheme::Pastel, ColorTheme::Neon, ColorTheme::Safe];
+
+    /// Look up a theme by name, ignoring case
+    pub fn from_name(name: &str) -> Option<ColorTheme> {
+        Self::ALL.into_iter().find(|t| t.name().eq_ignore_ascii_case(name))
+    }
+
     pub fn colors(&self) -> ThemeColors {
         match self {
             ColorTheme::Classic => ThemeColors {
                 background: BLACK,
                 cell: GREEN,
                 grid: Color::new(0.15, 0.15, 0.15, 1.0),
+                grid_major: Color::new(0.3, 0.3, 0.3, 1.0),
                 border: RED,
                 text: WHITE,
                 text_secondary: GRAY,
@@ -33,6 +53,7 @@ impl ColorTheme {
                 background: BLACK,
                 cell: WHITE,
                 grid: Color::new(0.2, 0.2, 0.2, 1.0),
+                grid_major: Color::new(0.38, 0.38, 0.38, 1.0),
                 border: Color::new(0.8, 0.8, 0.8, 1.0),
                 text: WHITE,
                 text_secondary: Color::new(0.7, 0.7, 0.7, 1.0),
@@ -41,6 +62,7 @@ impl ColorTheme {
                 background: Color::new(0.95, 0.95, 0.98, 1.0),
                 cell: Color::new(0.8, 0.6, 0.9, 1.0),  // Light purple
                 grid: Color::new(0.85, 0.85, 0.85, 1.0),
+                grid_major: Color::new(0.72, 0.72, 0.78, 1.0),
                 border: Color::new(0.6, 0.4, 0.8, 1.0),
                 text: Color::new(0.2, 0.2, 0.3, 1.0),
                 text_secondary: Color::new(0.4, 0.4, 0.5, 1.0),
@@ -49,19 +71,529 @@ impl ColorTheme {
                 background: Color::new(0.05, 0.05, 0.1, 1.0),  // Dark blue
                 cell: Color::new(0.0, 1.0, 0.8, 1.0),  // Neon green
                 grid: Color::new(0.2, 0.2, 0.4, 1.0),
+                grid_major: Color::new(0.35, 0.3, 0.65, 1.0),
                 border: Color::new(1.0, 0.0, 0.8, 1.0),  // Pink
                 text: Color::new(0.8, 1.0, 1.0, 1.0),
                 text_secondary: Color::new(0.6, 0.8, 1.0, 1.0),
             },
+            ColorTheme::Safe => ThemeColors {
+                background: Color::new(0.06, 0.06, 0.06, 1.0),
+                cell: Color::new(0.34, 0.71, 0.91, 1.0),  // Okabe-Ito sky blue
+                grid: Color::new(0.18, 0.18, 0.18, 1.0),
+                grid_major: Color::new(0.34, 0.34, 0.34, 1.0),
+                border: Color::new(0.9, 0.62, 0.0, 1.0),  // Okabe-Ito orange
+                text: WHITE,
+                text_secondary: Color::new(0.7, 0.7, 0.7, 1.0),
+            },
+            ColorTheme::Custom(palette) => palette.colors(),
         }
     }
-    
+
     pub fn name(&self) -> &'static str {
         match self {
             ColorTheme::Classic => "Classic",
             ColorTheme::Dark => "Dark",
             ColorTheme::Pastel => "Pastel",
             ColorTheme::Neon => "Neon",
+            ColorTheme::Safe => "Safe",
+            ColorTheme::Custom(_) => "Custom",
+        }
+    }
+
+    /// The lowest cell/background contrast under normal vision and each simulated deficiency,
+    /// when it falls below THEME_MIN_CONTRAST
+    pub fn low_contrast(&self) -> Option<f32> {
+        let worst = Vision::ALL.into_iter()
+            .map(|vision| {
+                let colors = vision.theme(self);
+                contrast_ratio(colors.cell, colors.background)
+            })
+            .fold(f32::INFINITY, f32::min);
+        (worst < THEME_MIN_CONTRAST).then_some(worst)
+    }
+}
+
+/// Colors of a custom theme as 8-bit sRGB. Major grid lines and text are derived from them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Palette {
+    pub background: [u8; 3],
+    pub cell: [u8; 3],
+    pub grid: [u8; 3],
+    pub border: [u8; 3],
+}
+
+impl Palette {
+    pub fn colors(&self) -> ThemeColors {
+        let background = rgb(self.background);
+        // Above this luminance dark text has more contrast than white
+        let (text, text_secondary) = if relative_luminance(background) > 0.18 {
+            (Color::new(0.1, 0.1, 0.12, 1.0), Color::new(0.35, 0.35, 0.4, 1.0))
+        } else {
+            (WHITE, Color::new(0.7, 0.7, 0.7, 1.0))
+        };
+        let grid = rgb(self.grid);
+        ThemeColors { background, cell: rgb(self.cell), grid, grid_major: mix(grid, text, 0.25), border: rgb(self.border), text, text_secondary }
+    }
+
+    /// A random palette whose cells keep THEME_MIN_CONTRAST against the background with normal
+    /// vision and each simulated deficiency. Falls back to white on black if none is found.
+    pub fn random(rng: &mut Rng) -> Palette {
+        for _ in 0..RANDOM_PALETTE_TRIES {
+            let dark = rng.next_f32() < 0.6;
+            let hue = rng.next_f32();
+            let (ground, ink) = if dark { (0.02 + 0.14 * rng.next_f32(), 0.65 + 0.35 * rng.next_f32()) } else { (0.85 + 0.13 * rng.next_f32(), 0.2 + 0.4 * rng.next_f32()) };
+            let background = hsv(hue, 0.5 * rng.next_f32(), ground);
+            let cell = hsv(rng.next_f32(), 0.4 + 0.6 * rng.next_f32(), ink);
+            let grid = hsv(hue, 0.3 * rng.next_f32(), ground + if dark { 0.12 } else { -0.12 });
+            let border = hsv(rng.next_f32(), 0.6 + 0.4 * rng.next_f32(), 0.5 + 0.5 * rng.next_f32());
+            let palette = Palette { background, cell, grid, border };
+            if ColorTheme::Custom(palette).low_contrast().is_none() {
+                return palette;
+            }
+        }
+        Palette { background: [0, 0, 0], cell: [255, 255, 255], grid: [51, 51, 51], border: [204, 204, 204] }
+    }
+
+    /// Read four `#rrggbb` colors: background, cell, grid and border
+    pub fn parse(text: &str) -> Result<Palette, String> {
+        let colors = text.split_whitespace().map(|hex| {
+            let digits = hex.strip_prefix('#').filter(|d| d.len() == 6 && d.is_ascii());
+            let channel = |i: usize| digits.and_then(|d| u8::from_str_radix(&d[i..i + 2], 16).ok());
+            match (channel(0), channel(2), channel(4)) {
+                (Some(r), Some(g), Some(b)) => Ok([r, g, b]),
+                _ => Err(format!("'{}' is not a #rrggbb color", hex)),
+            }
+        }).collect::<Result<Vec<_>, _>>()?;
+        match colors[..] {
+            [background, cell, grid, border] => Ok(Palette { background, cell, grid, border }),
+            _ => Err(format!("expected 4 colors (background, cell, grid, border), found {}", colors.len())),
+        }
+    }
+}
+
+impl fmt::Display for Palette {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex = |[r, g, b]: [u8; 3]| format!("#{:02x}{:02x}{:02x}", r, g, b);
+        write!(f, "{} {} {} {}", hex(self.background), hex(self.cell), hex(self.grid), hex(self.border))
+    }
+}
+
+fn rgb([r, g, b]: [u8; 3]) -> Color {
+    Color::from_rgba(r, g, b, 255)
+}
+
+/// Blend from `a` towards `b`
+fn mix(a: Color, b: Color, t: f32) -> Color {
+    Color::new(a.r + (b.r - a.r) * t, a.g + (b.g - a.g) * t, a.b + (b.b - a.b) * t, a.a)
+}
+
+/// Hue, saturation and value, each 0 to 1, as 8-bit sRGB
+fn hsv(h: f32, s: f32, v: f32) -> [u8; 3] {
+    let (s, v) = (s.clamp(0.0, 1.0), v.clamp(0.0, 1.0));
+    let channel = |n: f32| {
+        let k = (n + h.fract() * 6.0) % 6.0;
+        let c = v - v * s * k.min(4.0 - k).clamp(0.0, 1.0);
+        (c * 255.0).round() as u8
+    };
+    [channel(5.0), channel(3.0), channel(1.0)]
+}
+
+/// Named palettes kept in a `name = #rrggbb #rrggbb #rrggbb #rrggbb` file beside the settings
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CustomThemes {
+    pub entries: Vec<(String, Palette)>,
+}
+
+impl CustomThemes {
+    /// Location of the themes file, or `None` where there is no filesystem
+    pub fn path() -> Option<PathBuf> {
+        Settings::path().map(|p| p.with_file_name("themes.txt"))
+    }
+
+    /// Load the themes file; a missing file or bad lines leave fewer themes
+    pub fn load() -> Self {
+        Self::try_load().map(|kept| kept.value).unwrap_or_default()
+    }
+
+    /// Load the themes file as `load` does, but say why a file that exists couldn't be read
+    pub fn try_load() -> Result<Kept<Self>, GolError> {
+        match Self::path() {
+            Some(path) => Self::read_from(&path),
+            None => Ok(Kept::fresh(Self::default())),
+        }
+    }
+
+    /// Read the themes file at `path`, or its backup if it is empty or not text; bad lines only
+    /// leave fewer themes, since the file is edited by hand
+    pub fn read_from(path: &Path) -> Result<Kept<Self>, GolError> {
+        let kept = error::read_kept(path, |text| Ok(Self::parse(text)))?;
+        Ok(kept.unwrap_or_else(|| Kept::fresh(Self::default())))
+    }
+
+    /// Write the themes file, and the backup read if it is later found damaged. Failures are
+    /// returned so the UI can report them.
+    pub fn save(&self) -> Result<(), GolError> {
+        let Some(path) = Self::path() else { return Ok(()); };
+        error::write_kept(&path, self.to_text())
+    }
+
+    /// Read the themes file for a reload, failing on its first bad line so that a half-edited
+    /// file doesn't replace the themes in use. A missing file is no themes.
+    pub fn reload() -> Result<Self, GolError> {
+        match Self::path().filter(|p| p.exists()) {
+            Some(path) => Self::parse_strict(&error::read_to_string(&path)?).map_err(|e| e.in_file(&path)),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Parse `name = colors` lines; a later line replaces an earlier one of the same name
+    pub fn parse(text: &str) -> Self {
+        Self::parse_lines(text).0
+    }
+
+    /// Parse as `parse` does, but fail on the first line that isn't blank, a comment or a theme
+    pub fn parse_strict(text: &str) -> Result<Self, GolError> {
+        match Self::parse_lines(text) {
+            (themes, None) => Ok(themes),
+            (_, Some(err)) => Err(err),
+        }
+    }
+
+    /// The themes on the good lines, and the error on the first bad one
+    fn parse_lines(text: &str) -> (Self, Option<GolError>) {
+        let mut themes = Self::default();
+        let mut first_error = None;
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue; }
+            let entry = match line.split_once('=') {
+                Some((name, _)) if name.trim().is_empty() => Err("the theme has no name".to_string()),
+                Some((name, colors)) => Palette::parse(colors).map(|palette| (name.trim(), palette)),
+                None => Err("expected 'name = #rrggbb #rrggbb #rrggbb #rrggbb'".to_string()),
+            };
+            match entry {
+                Ok((name, palette)) => {
+                    themes.entries.retain(|(n, _)| n != name);
+                    themes.entries.push((name.to_string(), palette));
+                }
+                Err(message) => {
+                    first_error.get_or_insert(GolError::parse("themes file", Location::Line(i + 1), message));
+                }
+            }
+        }
+        (themes, first_error)
+    }
+
+    /// `theme` as it should show after these themes replace `previous`: a custom theme that
+    /// was saved under a name takes that name's new colors, anything else is left alone
+    pub fn updated(&self, previous: &CustomThemes, theme: ColorTheme) -> ColorTheme {
+        let ColorTheme::Custom(palette) = theme else { return theme };
+        previous.entries.iter()
+            .find(|(_, p)| *p == palette)
+            .and_then(|(name, _)| self.entries.iter().find(|(n, _)| n == name))
+            .map_or(theme, |(_, p)| ColorTheme::Custom(*p))
+    }
+
+    /// Serialize in the format read by `parse`
+    pub fn to_text(&self) -> String {
+        let mut text = "# Custom color themes: background, cell, grid and border\n".to_string();
+        for (name, palette) in &self.entries {
+            text += &format!("{} = {}\n", name, palette);
+        }
+        text
+    }
+
+    /// Add a palette under the first free name "Random N", or return the name it already has
+    pub fn add(&mut self, palette: Palette) -> String {
+        if let Some((name, _)) = self.entries.iter().find(|(_, p)| *p == palette) {
+            return name.clone();
+        }
+        let name = (1..).map(|n| format!("Random {}", n)).find(|n| self.entries.iter().all(|(e, _)| e != n)).unwrap_or_default();
+        self.entries.push((name.clone(), palette));
+        name
+    }
+}
+
+/// An image drawn faintly behind a theme's cells, stretched over the board
+pub struct Backdrop {
+    pub image: Image,
+    pub opacity: f32,              // 0 hides it, 1 draws it at full strength
+    texture: OnceCell<Texture2D>,  // Uploaded the first time it is drawn to the window
+}
+
+impl Backdrop {
+    pub fn new(image: Image, opacity: f32) -> Self {
+        Self { image, opacity: opacity.clamp(0.0, 1.0), texture: OnceCell::new() }
+    }
+
+    /// Decode an image file (PNG, JPEG, ...). There is no file to read on WASM.
+    pub fn load(path: &Path, opacity: f32) -> Result<Self, GolError> {
+        if cfg!(target_arch = "wasm32") {
+            return Err(GolError::Unsupported("background images need a filesystem".to_string()));
+        }
+        let bytes = error::read(path)?;
+        let image = Image::from_file_with_format(&bytes, None)
+            .map_err(|e| GolError::parse("image", Location::Whole, e.to_string()).in_file(path))?;
+        Ok(Self::new(image, opacity))
+    }
+
+    pub fn texture(&self) -> &Texture2D {
+        self.texture.get_or_init(|| Texture2D::from_image(&self.image))
+    }
+}
+
+impl PartialEq for Backdrop {
+    fn eq(&self, other: &Self) -> bool {
+        self.opacity == other.opacity
+            && (self.image.width, self.image.height) == (other.image.width, other.image.height)
+            && self.image.bytes == other.image.bytes
+    }
+}
+
+impl fmt::Debug for Backdrop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Backdrop({}x{} at {})", self.image.width, self.image.height, self.opacity)
+    }
+}
+
+/// Background images set per theme in the settings file. Each is read the first time its
+/// theme is shown and kept, failures included, so cycling themes never reads a file again;
+/// a theme whose image can't be loaded keeps its flat background and reports why once.
+pub struct Backdrops {
+    paths: Vec<(ColorTheme, PathBuf)>,
+    opacity: f32,
+    loaded: Vec<(ColorTheme, Option<Rc<Backdrop>>)>,
+    errors: Vec<GolError>, // Load failures not yet shown
+}
+
+impl Backdrops {
+    pub fn new(paths: Vec<(ColorTheme, PathBuf)>, opacity: f32) -> Self {
+        Self { paths, opacity, loaded: Vec::new(), errors: Vec::new() }
+    }
+
+    /// Load failures since the last call
+    pub fn take_errors(&mut self) -> Vec<GolError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// The backdrop for `theme`, loading it on first use
+    pub fn get(&mut self, theme: ColorTheme) -> Option<Rc<Backdrop>> {
+        if let Some((_, backdrop)) = self.loaded.iter().find(|(t, _)| *t == theme) {
+            return backdrop.clone();
+        }
+        let backdrop = self.paths.iter()
+            .find(|(t, _)| *t == theme)
+            .and_then(|(_, path)| Backdrop::load(path, self.opacity).map_err(|e| self.errors.push(e)).ok())
+            .map(Rc::new);
+        self.loaded.push((theme, backdrop.clone()));
+        backdrop
+    }
+}
+
+/// Color vision the theme picker can preview swatches through
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Vision {
+    Normal,
+    Protanopia,   // No working red cones
+    Deuteranopia, // No working green cones
+}
+
+impl Vision {
+    pub const ALL: [Vision; 3] = [Vision::Normal, Vision::Protanopia, Vision::Deuteranopia];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Vision::Normal => "Normal",
+            Vision::Protanopia => "Protanopia",
+            Vision::Deuteranopia => "Deuteranopia",
+        }
+    }
+
+    /// Linear RGB transform at full severity, from Machado, Oliveira & Fernandes (2009)
+    fn matrix(&self) -> [[f32; 3]; 3] {
+        match self {
+            Vision::Normal => [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            Vision::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            Vision::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+        }
+    }
+
+    /// How `color` looks with this vision; alpha is kept
+    pub fn simulate(&self, color: Color) -> Color {
+        if *self == Vision::Normal {
+            return color;
+        }
+        let linear = [srgb_to_linear(color.r), srgb_to_linear(color.g), srgb_to_linear(color.b)];
+        let [r, g, b] = self.matrix().map(|row| {
+            let mixed: f32 = row.iter().zip(linear).map(|(m, c)| m * c).sum();
+            linear_to_srgb(mixed.clamp(0.0, 1.0))
+        });
+        Color::new(r, g, b, color.a)
+    }
+
+    /// A theme's colors as seen with this vision
+    pub fn theme(&self, theme: &ColorTheme) -> ThemeColors {
+        let colors = theme.colors();
+        ThemeColors {
+            background: self.simulate(colors.background),
+            cell: self.simulate(colors.cell),
+            grid: self.simulate(colors.grid),
+            grid_major: self.simulate(colors.grid_major),
+            border: self.simulate(colors.border),
+            text: self.simulate(colors.text),
+            text_secondary: self.simulate(colors.text_secondary),
+        }
+    }
+}
+
+/// Decode an sRGB channel to linear light
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+/// Encode a linear light channel as sRGB
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 }
+}
+
+/// WCAG relative luminance: 0 for black, 1 for white
+pub fn relative_luminance(color: Color) -> f32 {
+    0.2126 * srgb_to_linear(color.r) + 0.7152 * srgb_to_linear(color.g) + 0.0722 * srgb_to_linear(color.b)
+}
+
+/// WCAG contrast ratio between two colors, from 1 (identical) to 21 (black on white)
+pub fn contrast_ratio(a: Color, b: Color) -> f32 {
+    let (la, lb) = (relative_luminance(a), relative_luminance(b));
+    (la.max(lb) + 0.05) / (la.min(lb) + 0.05)
+}
+
+/// Which grid lines are drawn
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum GridMode {
+    Off,
+    Minor,      // A line between every pair of cells
+    #[default]
+    MinorMajor, // Minor lines plus brighter lines every `major_every` cells
+}
+
+/// How grid lines are stroked
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum LineStyle {
+    #[default]
+    Solid,
+    Dotted,
+}
+
+/// Grid line appearance, independent of the color theme
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct GridStyle {
+    pub mode: GridMode,
+    pub line: LineStyle,
+    pub major_every: i32, // Cells between major lines (5 or 10)
+    pub min_cell: i32,    // Minor lines are hidden below this cell size in pixels
+}
+
+impl Default for GridStyle {
+    fn default() -> Self {
+        Self { mode: GridMode::default(), line: LineStyle::default(), major_every: 10, min_cell: 4 }
+    }
+}
+
+impl GridStyle {
+    /// Off -> minor -> minor+major -> off
+    pub fn cycle_mode(&mut self) {
+        self.mode = match self.mode {
+            GridMode::Off => GridMode::Minor,
+            GridMode::Minor => GridMode::MinorMajor,
+            GridMode::MinorMajor => GridMode::Off,
+        };
+    }
+
+    pub fn toggle_line(&mut self) {
+        self.line = match self.line {
+            LineStyle::Solid => LineStyle::Dotted,
+            LineStyle::Dotted => LineStyle::Solid,
+        };
+    }
+
+    /// Switch major lines between every 5 and every 10 cells
+    pub fn toggle_major_every(&mut self) {
+        self.major_every = if self.major_every == 5 { 10 } else { 5 };
+    }
+
+    /// Minor line thickness for a cell size; grows slowly so lines stay visible on large cells
+    pub fn thickness(&self, cell: i32) -> f32 {
+        (cell / 12).clamp(1, 3) as f32
+    }
+
+    /// Short description for the HUD
+    pub fn name(&self) -> &'static str {
+        match (self.mode, self.line) {
+            (GridMode::Off, _) => "off",
+            (GridMode::Minor, LineStyle::Solid) => "minor",
+            (GridMode::Minor, LineStyle::Dotted) => "minor dotted",
+            (GridMode::MinorMajor, LineStyle::Solid) => "minor+major",
+            (GridMode::MinorMajor, LineStyle::Dotted) => "minor+major dotted",
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 0.002
+    }
+
+    #[test]
+    fn contrast_and_luminance_match_wcag() {
+        assert!(close(contrast_ratio(WHITE, BLACK), 21.0));
+        assert!(close(contrast_ratio(BLACK, WHITE), 21.0));
+        assert!(close(contrast_ratio(WHITE, WHITE), 1.0));
+        assert!(close(relative_luminance(Color::new(0.5, 0.5, 0.5, 1.0)), 0.2140));
+        for i in 0..=20 {
+            let c = i as f32 / 20.0;
+            assert!((linear_to_srgb(srgb_to_linear(c)) - c).abs() < 1e-5, "channel {} does not round-trip", c);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn deficiencies_move_red_and_keep_grays() {
+        // Pure red through the full-severity matrices, worked out by hand in sRGB
+        let red = Color::new(1.0, 0.0, 0.0, 0.5);
+        for (vision, expected) in [(Vision::Normal, [1.0, 0.0, 0.0]), (Vision::Protanopia, [0.4266, 0.3727, 0.0]), (Vision::Deuteranopia, [0.6401, 0.5658, 0.0])] {
+            let seen = vision.simulate(red);
+            assert!(close(seen.r, expected[0]) && close(seen.g, expected[1]) && close(seen.b, expected[2]), "red under {} came out {:?}", vision.name(), seen);
+            assert_eq!(seen.a, 0.5);
+            // Each matrix row sums to 1, so grays are left alone
+            for level in [0.0, 0.25, 0.5, 1.0] {
+                let gray = vision.simulate(Color::new(level, level, level, 1.0));
+                assert!(close(gray.r, level) && close(gray.g, level) && close(gray.b, level), "gray {} under {} came out {:?}", level, vision.name(), gray);
+            }
+        }
+    }
+
+    #[test]
+    fn only_pastel_is_flagged_low_contrast() {
+        let flagged: Vec<&str> = ColorTheme::ALL.iter().filter(|t| t.low_contrast().is_some()).map(|t| t.name()).collect();
+        assert_eq!(flagged, ["Pastel"]);
+        assert!(ColorTheme::Pastel.low_contrast().unwrap() < THEME_MIN_CONTRAST);
+    }
+
+    #[test]
+    fn safe_theme_stays_distinct_under_every_vision() {
+        // Its cells and border differ in blue, which neither deficiency touches much
+        for vision in Vision::ALL {
+            let colors = vision.theme(&ColorTheme::Safe);
+            assert!((colors.cell.b - colors.border.b).abs() >= 0.5, "Safe cells and border look alike under {}", vision.name());
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! Time-lapse gallery: a small thumbnail of the board every few generations, thinned out
+//! as the run grows so long runs keep a spread of shots from start to end.
+
+use std::collections::HashSet;
+use std::thread::{self, JoinHandle};
+use macroquad::color::Color;
+use macroquad::texture::{FilterMode, Image, Texture2D};
+
+use crate::density::DensityMap;
+use crate::config::{THUMB_MAX_BYTES, THUMB_MAX_COUNT, THUMB_SIZE};
+use crate::grid::{Grid, Position};
+use crate::render::{draw_board, stretch, BoardView, Canvas};
+use crate::simulation::Simulation;
+use crate::themes::{Backdrop, ColorTheme, GridMode};
+
+/// One captured board
+pub struct Thumbnail {
+    pub generation: u64,
+    pub image: Image,
+    texture: Option<Texture2D>, // Uploaded on first draw; needs the window's GL context
+}
+
+impl Thumbnail {
+    pub fn new(generation: u64, image: Image) -> Self {
+        Self { generation, image, texture: None }
+    }
+
+    /// The thumbnail as a texture, uploading it the first time
+    pub fn texture(&mut self) -> &Texture2D {
+        self.texture.get_or_insert_with(|| {
+            let texture = Texture2D::from_image(&self.image);
+            texture.set_filter(FilterMode::Nearest);
+            texture
+        })
+    }
+
+    /// Approximate heap size of the pixels
+    pub fn bytes(&self) -> usize {
+        self.image.bytes.len()
+    }
+}
+
+/// An image whose coordinates are scaled down from board pixels. Rectangles are kept at
+/// least a pixel wide so single cells on large boards still show up.
+struct Shrunk<'a> {
+    image: &'a mut Image,
+    scale: f32,
+}
+
+impl Canvas for Shrunk<'_> {
+    fn clear(&mut self, color: Color) {
+        self.image.clear(color);
+    }
+
+    fn fill_rect(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color) {
+        let s = self.scale;
+        self.image.fill_rect(x * s, y * s, (w * s).max(1.0), (h * s).max(1.0), color);
+    }
+
+    fn stroke_rect(&mut self, x: f32, y: f32, w: f32, h: f32, _thickness: f32, color: Color) {
+        // A one-pixel frame, whatever the board's border width
+        let s = self.scale;
+        let (x, y, w, h) = (x * s, y * s, w * s, h * s);
+        self.image.fill_rect(x, y, w, 1.0, color);
+        self.image.fill_rect(x, y + h - 1.0, w, 1.0, color);
+        self.image.fill_rect(x, y, 1.0, h, color);
+        self.image.fill_rect(x + w - 1.0, y, 1.0, h, color);
+    }
+
+    fn backdrop(&mut self, backdrop: &Backdrop, x: f32, y: f32, w: f32, h: f32) {
+        let s = self.scale;
+        self.image.backdrop(backdrop, x * s, y * s, w * s, h * s);
+    }
+
+    fn density(&mut self, map: &mut DensityMap, x: f32, y: f32) {
+        let (s, cols, rows) = (self.scale, map.cols as f32, map.rows as f32);
+        stretch(self.image, map.image(), 1.0, x * s, y * s, cols * s, rows * s);
+    }
+}
+
+/// Rasterize a board into a thumbnail no larger than `THUMB_SIZE`, keeping its aspect ratio
+pub fn rasterize(sim: &Simulation, theme: ColorTheme) -> Image {
+    let (max_w, max_h) = (THUMB_SIZE.0 as f32, THUMB_SIZE.1 as f32);
+    let (w, h) = (sim.grid.width as f32, sim.grid.height as f32);
+    let scale = (max_w / w).min(max_h / h);
+    let size = |cells: f32| ((cells * scale).ceil() as u16).max(1);
+    let mut image = Image::gen_image_color(size(w), size(h), theme.colors().background);
+    let mut view = BoardView::new(1);
+    view.theme = theme;
+    view.grid_style.mode = GridMode::Off;
+    draw_board(&mut Shrunk { image: &mut image, scale }, sim, &view, 1.0);
+    image
+}
+
+/// A capture being rasterized off the main thread
+struct Capture {
+    generation: u64,
+    handle: JoinHandle<Image>,
+}
+
+/// Thumbnails of the current run, oldest first
+pub struct Gallery {
+    pub every: u64,    // Configured generations between captures; 0 disables the gallery
+    interval: u64,     // Current spacing, doubled each time the gallery is thinned
+    next_due: u64,     // Generation of the next capture
+    pub shots: Vec<Thumbnail>,
+    capture: Option<Capture>,
+}
+
+impl Gallery {
+    pub fn new(every: u64) -> Self {
+        Self { every, interval: every, next_due: 0, shots: Vec::new(), capture: None }
+    }
+
+    /// Capture the board if a thumbnail is due and collect finished ones. The copy of the board
+    /// is rasterized on its own thread where threads exist, so the frame only pays for the copy.
+    pub fn update(&mut self, sim: &Simulation, theme: ColorTheme) {
+        if self.every == 0 || sim.editing() {
+            return;
+        }
+        if self.shots.last().is_some_and(|t| t.generation > sim.generation) {
+            // Reset, cleared or jumped back: later shots show a future that may not happen
+            self.capture = None;
+            self.shots.retain(|t| t.generation <= sim.generation);
+            self.next_due = self.shots.last().map_or(0, |t| (t.generation / self.interval + 1) * self.interval);
+        }
+        if let Some(capture) = self.capture.take_if(|c| c.handle.is_finished())
+            && let Ok(image) = capture.handle.join()
+        {
+            self.push(capture.generation, image);
+        }
+        if self.capture.is_some() || sim.generation < self.next_due {
+            return;
+        }
+        let generation = sim.generation;
+        self.next_due = (generation / self.interval + 1) * self.interval;
+        if cfg!(target_arch = "wasm32") {
+            self.push(generation, rasterize(sim, theme));
+            return;
+        }
+        let (grid, live): (Grid, HashSet<Position>) = (sim.grid.clone(), sim.live.clone());
+        let handle = thread::spawn(move || {
+            let mut copy = Simulation::new(grid.width, grid.height);
+            copy.grid = grid;
+            copy.live = live;
+            rasterize(&copy, theme)
+        });
+        self.capture = Some(Capture { generation, handle });
+    }
+
+    /// Add a finished thumbnail, thinning older ones to stay within the count and memory caps
+    pub fn push(&mut self, generation: u64, image: Image) {
+        self.shots.push(Thumbnail::new(generation, image));
+        let bytes = self.shots[0].bytes().max(1);
+        let cap = THUMB_MAX_COUNT.min(THUMB_MAX_BYTES / bytes).max(2);
+        while self.shots.len() > cap {
+            // Keep every other shot and the newest, then capture half as often
+            let newest = self.shots.len() - 1;
+            self.shots = self.shots.drain(..)
+                .enumerate()
+                .filter(|(i, _)| i % 2 == 0 || *i == newest)
+                .map(|(_, t)| t)
+                .collect();
+            self.interval *= 2;
+        }
+        self.next_due = (generation / self.interval + 1) * self.interval;
+    }
+
+    /// Approximate memory held by the thumbnails, in bytes
+    pub fn bytes(&self) -> usize {
+        self.shots.iter().map(Thumbnail::bytes).sum()
+    }
+
+    /// Drop every thumbnail taken so far; capturing carries on at the current spacing
+    pub fn clear(&mut self) {
+        self.shots = Vec::new();
+        self.capture = None;
+    }
+}
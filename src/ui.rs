@@ -1,112 +1,4551 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
 use macroquad::prelude::*;
 
-use crate::config::SCREEN_SIZES;
-use crate::patterns::{get_pattern_by_index};
+use crate::config::{
+    AGE_STABLE, ATTRACT_CELL, BOOKMARK_MAX, ATTRACT_SECONDS, ATTRACT_SPEED, CELL_SIZE, CELL_SIZE_MAX, CELL_SIZE_MIN, CELL_SIZES, CONSOLE_LINES, CROP_MARGIN, DENSITY_MAX, DENSITY_MIN, DENSITY_STEP, GOTO_FRAME_BUDGET, GRID_MAX, GRID_PRESETS, HUD_HEIGHT, LAG_TOLERANCE, LAG_WARN_SECONDS, MAX_CATCHUP_SECONDS,
+    MAX_STEPS_PER_FRAME, MUTATION_GENERATIONS, MUTATION_SIZE, RATE_WINDOW, SANDBOX_MARGIN, SCREEN_SIZES, SMOOTH_MAX_SPEED, SPEED_INIT, STEP_BUDGET_SECONDS, SUMMARY_PERIOD_MAX,
+    PATTERN_DIR, SPEED_MAX, SPEED_MIN, STAMP_HISTORY_LEN, TERRITORY_BUDGET, TERRITORY_GENERATIONS, TERRITORY_SIZE,
+    TERRITORY_SPEED, THEME_MIN_CONTRAST, TOAST_SECONDS, ELEMENTARY_CELL, ELEMENTARY_RULE, ELEMENTARY_SPEED, RANDOM_DENSITY, THUMB_SIZE,
+    IMAGE_THRESHOLD_STEP, DEBUG_DUMP_HISTORY, EXPAND_MARGIN, NOTE_TEXT_MAX, COMPARE_FRAME_GENERATIONS, HOTBAR_SLOT, HOTBAR_THUMB, PREVIEW_BOX,
+};
+use crate::annotations::{Annotations, NoteColor};
+use crate::analysis::{self, AgeHistogram, Census, Growth, GrowthTracker, RunStats};
+use crate::bookmarks::{Bookmark, Bookmarks};
+use crate::debug_dump::DebugDump;
+use crate::camera::{Camera, Follow, Tracked};
+use crate::clock::SimClock;
+use crate::compare::{Compare, Reference};
+use crate::pacing::{Limit, Pacer};
+use crate::guard::{Destructive, Guard};
+use crate::heat::HeatField;
+use crate::particles::Particles;
+use crate::preview::Preview;
+use crate::hotbar::{self, Hotbar, Slot};
+use crate::remote::{Control, Endpoint};
+use crate::serve::{self, Feed};
+use crate::console::{self, Outcome, Scrollback};
+use crate::deltas::DeltaLog;
+use crate::budget;
+use crate::project::{self, Project};
+use crate::elementary::{ElementaryRule, Strip};
+use crate::net::{self, Fetch};
+use crate::overlay::{Frame, Input, InputConsumed, Layer, Overlay, OverlayStack, Window};
+use crate::schedule::SpeedSchedule;
+use crate::script::ScriptHook;
+use crate::render::{self, BoardView, Help, Hud, Ruler, WindowFit};
+use crate::simulation::{ClearScope, MemoryUsage, Simulation};
+use crate::grid::{board_hash, CellFate, Grid, Position, Rect, Topology};
+use crate::settings::Settings;
+use crate::stepper::Stepper;
+use crate::timelapse::Gallery;
+use crate::mutation::{self, Explorer};
+use crate::territory::{Phase, Territory};
+use crate::error::{self, GolError, Kept};
+use crate::watch::Watched;
+use crate::records::{self, Records, RunResult, RunSetup};
+use crate::pattern_io::{load_pattern_file, scan_pattern_dir, LoadedPattern};
+use crate::loading::{Load, Stage};
+use crate::patterns::image::{ImageOptions, Picture};
+use crate::patterns::{self, default_registry, OnClip, Overhang, Pattern, PatternInfo, PatternRegistry, PatternSort, PatternTooLarge, Stamp, StampHistory, Transform};
+use crate::rng::Rng;
+use crate::rule::Rule;
+use crate::themes::{Backdrops, ColorTheme, CustomThemes, GridMode, Palette, ThemeColors, Vision};
+
+/// Short-lived notices stacked at the bottom of the simulation screen
+#[derive(Default)]
+pub struct Toasts {
+    items: Vec<(String, f64)>, // Message and the time it expires
+}
+
+impl Toasts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Show a message for `TOAST_SECONDS`
+    pub fn push(&mut self, text: impl Into<String>) {
+        let now = get_time();
+        self.items.retain(|(_, until)| *until > now);
+        self.items.push((text.into(), now + TOAST_SECONDS));
+    }
+
+    /// Draw the messages that haven't expired
+    pub fn draw(&self, colors: &ThemeColors) {
+        let now = get_time();
+        let live = self.items.iter().filter(|(_, until)| *until > now);
+        for (i, (text, _)) in live.rev().enumerate() {
+            let dims = measure_text(text, None, 20, 1.0);
+            let x = (screen_width() - dims.width) / 2.0;
+            let y = screen_height() - 20.0 - i as f32 * 28.0;
+            let mut bg = colors.background;
+            bg.a = 0.85;
+            draw_rectangle(x - 8.0, y - dims.offset_y - 4.0, dims.width + 16.0, dims.height + 8.0, bg);
+            draw_text(text, x, y, 20.0, colors.text);
+        }
+    }
+}
+
+impl Overlay for Toasts {
+    fn layer(&self) -> Layer {
+        Layer::Notices
+    }
+
+    fn draw(&self, frame: &Frame) {
+        Toasts::draw(self, &frame.view.theme.colors());
+    }
+}
+
+/// Outcome of a frame of text entry
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum InputEvent {
+    Submitted(String), // Enter pressed with valid contents
+    Cancelled,         // Escape pressed
+}
+
+/// Single-line text field driven by macroquad's character events
+pub struct TextInput {
+    pub label: String,
+    text: Vec<char>,
+    cursor: usize,                  // Insertion point as a char index
+    max_len: usize,
+    numeric: bool,                  // Accept only ASCII digits
+    repeat: Option<(KeyCode, f64)>, // Held editing key and time of its next repeat
+}
+
+impl TextInput {
+    const REPEAT_DELAY: f64 = 0.4;
+    const REPEAT_RATE: f64 = 0.05;
+
+    pub fn new(label: impl Into<String>, max_len: usize) -> Self {
+        // Discard characters typed before the widget gained focus (e.g. the key that opened it)
+        while get_char_pressed().is_some() {}
+        Self {
+            label: label.into(),
+            text: Vec::new(),
+            cursor: 0,
+            max_len,
+            numeric: false,
+            repeat: None,
+        }
+    }
+
+    /// Restrict input to digits and require a number that fits in a `u64`
+    pub fn numeric(mut self) -> Self {
+        self.numeric = true;
+        self
+    }
+
+    /// Prefill the field, placing the cursor at the end
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.text = text.chars().take(self.max_len).collect();
+        self.cursor = self.text.len();
+        self
+    }
+
+    pub fn text(&self) -> String {
+        self.text.iter().collect()
+    }
+
+    /// Replace the contents, placing the cursor at the end
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.chars().take(self.max_len).collect();
+        self.cursor = self.text.len();
+    }
+
+    /// Whether the current contents may be submitted
+    pub fn is_valid(&self) -> bool {
+        !self.numeric || self.text().parse::<u64>().is_ok()
+    }
+
+    /// True on the frame a key is pressed and periodically while it stays held
+    fn repeated(&mut self, key: KeyCode) -> bool {
+        let now = get_time();
+        if is_key_pressed(key) {
+            self.repeat = Some((key, now + Self::REPEAT_DELAY));
+            return true;
+        }
+        match self.repeat {
+            Some((k, next)) if k == key && is_key_down(key) && now >= next => {
+                self.repeat = Some((key, next + Self::REPEAT_RATE));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Consume this frame's input. Call once per frame while the field has focus.
+    pub fn update(&mut self) -> Option<InputEvent> {
+        let mut typed: Vec<char> = Vec::new();
+        while let Some(c) = get_char_pressed() {
+            if !ctrl_down() { typed.push(c); }
+        }
+        if ctrl_down() && is_key_pressed(KeyCode::V) {
+            typed.extend(macroquad::miniquad::window::clipboard_get().unwrap_or_default().trim().chars());
+        }
+        for c in typed {
+            if c.is_control() || self.text.len() >= self.max_len { continue; }
+            if self.numeric && !c.is_ascii_digit() { continue; }
+            self.text.insert(self.cursor, c);
+            self.cursor += 1;
+        }
+        if self.repeated(KeyCode::Backspace) && self.cursor > 0 {
+            self.cursor -= 1;
+            self.text.remove(self.cursor);
+        }
+        if self.repeated(KeyCode::Delete) && self.cursor < self.text.len() {
+            self.text.remove(self.cursor);
+        }
+        if self.repeated(KeyCode::Left) { self.cursor = self.cursor.saturating_sub(1); }
+        if self.repeated(KeyCode::Right) { self.cursor = (self.cursor + 1).min(self.text.len()); }
+        if is_key_pressed(KeyCode::Home) { self.cursor = 0; }
+        if is_key_pressed(KeyCode::End) { self.cursor = self.text.len(); }
+
+        if is_key_pressed(KeyCode::Escape) { return Some(InputEvent::Cancelled); }
+        if is_key_pressed(KeyCode::Enter) && self.is_valid() {
+            return Some(InputEvent::Submitted(self.text()));
+        }
+        None
+    }
+
+    /// Draw the label and a boxed field with a blinking cursor
+    pub fn draw(&self, x: f32, y: f32, width: f32, colors: &ThemeColors) {
+        const FONT: f32 = 22.0;
+        let mut bg = colors.background;
+        bg.a = 0.92;
+        draw_rectangle(x, y, width, 64.0, bg);
+        draw_text(&self.label, x + 8.0, y + 20.0, FONT, colors.text);
+
+        let border = if self.is_valid() { colors.border } else { RED };
+        draw_rectangle_lines(x + 6.0, y + 28.0, width - 12.0, 28.0, 2.0, border);
+        let text = self.text();
+        draw_text(&text, x + 12.0, y + 48.0, FONT, colors.text);
+
+        if (get_time() * 2.0) as i64 % 2 == 0 {
+            let before: String = self.text[..self.cursor].iter().collect();
+            let cx = x + 12.0 + measure_text(&before, None, FONT as u16, 1.0).width;
+            draw_line(cx, y + 32.0, cx, y + 52.0, 2.0, colors.text);
+        }
+    }
+}
+
+/// Outcome of a frame of list navigation
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PickEvent {
+    Picked(usize), // Index of the chosen item
+    Cancelled,
+}
+
+/// Keyboard-driven list overlay for quick choices
+pub struct ListPicker {
+    pub title: String,
+    items: Vec<String>,
+    selected: usize,
+}
+
+impl ListPicker {
+    pub fn new(title: impl Into<String>, items: Vec<String>) -> Self {
+        Self { title: title.into(), items, selected: 0 }
+    }
+
+    /// Index of the highlighted item
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Consume this frame's input. Call once per frame while the list is open.
+    pub fn update(&mut self) -> Option<PickEvent> {
+        if self.items.is_empty() || is_key_pressed(KeyCode::Escape) {
+            return Some(PickEvent::Cancelled);
+        }
+        let n = self.items.len();
+        if is_key_pressed(KeyCode::Up) { self.selected = (self.selected + n - 1) % n; }
+        if is_key_pressed(KeyCode::Down) { self.selected = (self.selected + 1) % n; }
+        if is_key_pressed(KeyCode::Enter) { return Some(PickEvent::Picked(self.selected)); }
+        None
+    }
+
+    /// Draw the list in a panel with its top-left corner at (x, y)
+    pub fn draw(&self, x: f32, y: f32, colors: &ThemeColors) {
+        let w = 320.0;
+        let h = 34.0 + self.items.len() as f32 * 22.0;
+        let mut bg = colors.background;
+        bg.a = 0.92;
+        draw_rectangle(x, y, w, h, bg);
+        draw_rectangle_lines(x, y, w, h, 1.0, colors.border);
+        draw_text(&self.title, x + 8.0, y + 22.0, 22.0, colors.text);
+        for (i, item) in self.items.iter().enumerate() {
+            let marker = if i == self.selected { ">" } else { " " };
+            let color = if i == self.selected { colors.text } else { colors.text_secondary };
+            draw_text(&format!("{} {}", marker, item), x + 8.0, y + 46.0 + i as f32 * 22.0, 20.0, color);
+        }
+    }
+}
+
+const BREADCRUMB_SEPARATOR: &str = " > "; // The default font has no arrow glyphs
+
+/// A screen of the menus leading up to a run
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MenuScreen {
+    Mode,       // The main menu
+    Resolution, // Window size
+    Board,      // Grid and cell size
+    Pattern,
+    Room,       // Offered when the pattern wants a bigger board than the one picked
+    Records,    // Personal records, from the main menu
+    Open,       // Saved projects, from the main menu
+}
+
+impl MenuScreen {
+    /// Label in the breadcrumb
+    pub fn name(&self) -> &'static str {
+        match self {
+            MenuScreen::Mode => "Main",
+            MenuScreen::Resolution => "Window",
+            MenuScreen::Board => "Board",
+            MenuScreen::Pattern => "Patterns",
+            MenuScreen::Room => "Room",
+            MenuScreen::Records => "Records",
+            MenuScreen::Open => "Open",
+        }
+    }
+}
+
+/// The menu screens open on the way to a run. Picking something pushes the next screen and
+/// Escape pops back one; the main menu at the bottom is never popped.
+#[derive(Clone, Debug)]
+pub struct MenuStack {
+    screens: Vec<MenuScreen>,
+}
+
+impl Default for MenuStack {
+    fn default() -> Self {
+        Self { screens: vec![MenuScreen::Mode] }
+    }
+}
+
+impl MenuStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn current(&self) -> MenuScreen {
+        *self.screens.last().expect("the main menu is never popped")
+    }
+
+    pub fn push(&mut self, screen: MenuScreen) {
+        self.screens.push(screen);
+    }
+
+    /// Go back one screen; the main menu stays
+    pub fn pop(&mut self) {
+        if self.screens.len() > 1 {
+            self.screens.pop();
+        }
+    }
+
+    /// Back to the main menu, e.g. after a run
+    pub fn reset(&mut self) {
+        self.screens.truncate(1);
+    }
+
+    /// Where the current screen is, e.g. "Main > Window > Board > Patterns"
+    pub fn breadcrumb(&self) -> String {
+        self.screens.iter().map(MenuScreen::name).collect::<Vec<_>>().join(BREADCRUMB_SEPARATOR)
+    }
+}
+
+/// Choices made on the way through the menus, carried from screen to screen and handed to
+/// the run at the end
+#[derive(Clone, Default)]
+pub struct Setup {
+    pub mode: GameMode,
+    pub window: Option<(i32, i32)>, // Picked on the resolution screen
+    pub board: Option<BoardSize>,
+    pub pattern: Option<PatternChoice>,
+}
+
+impl Setup {
+    /// The run these choices describe, once the window, board and pattern are all picked
+    pub fn config<'a>(&self) -> Option<SimulationConfig<'a>> {
+        let (w, h) = self.window?;
+        let start = Start::Choice(self.pattern.clone()?);
+        Some(SimulationConfig::new().window(w, h).board(self.board?).start(start))
+    }
+}
+
+/// Draw a menu's breadcrumb above its title
+fn draw_breadcrumb(trail: &str) {
+    draw_text(trail, 20.0, 20.0, 20.0, LIGHTGRAY);
+}
 
 /// Display screen resolution selection menu
-pub async fn choose_resolution() -> usize {
+pub async fn choose_resolution(trail: &str) -> Option<usize> {
     let mut selected = 1usize;
+    let settings = Settings::load();
+    let mut last_input = get_time();
     loop {
+        if any_input() {
+            last_input = get_time();
+        } else if settings.attract_idle > 0.0 && get_time() - last_input > settings.attract_idle {
+            run_attract(&settings.attract_playlist).await;
+            last_input = get_time();
+        }
+
         clear_background(DARKGRAY);
+        draw_breadcrumb(trail);
         draw_text("Select screen size:", 20.0, 50.0, 30.0, WHITE);
         for (i, (w, h)) in SCREEN_SIZES.iter().enumerate() {
             let marker = if i == selected { ">" } else { " " };
             draw_text(&format!("{} {}x{}", marker, w, h), 40.0, 100.0 + i as f32 * 30.0, 25.0, WHITE);
         }
-        draw_text("Enter to confirm", 20.0, 260.0, 25.0, GREEN);
+        draw_text("Enter to confirm | Esc to go back", 20.0, 260.0, 25.0, GREEN);
 
         if is_key_pressed(KeyCode::Up) { selected = (selected + SCREEN_SIZES.len() - 1) % SCREEN_SIZES.len(); }
         if is_key_pressed(KeyCode::Down) { selected = (selected + 1) % SCREEN_SIZES.len(); }
         if is_key_pressed(KeyCode::Enter) { break; }
+        if is_key_pressed(KeyCode::Escape) { next_frame().await; return None; }
         next_frame().await;
     }
     next_frame().await;
-    selected
+    Some(selected)
+}
+
+/// Whether a key, mouse button or mouse movement happened this frame
+fn any_input() -> bool {
+    get_last_key_pressed().is_some()
+        || [MouseButton::Left, MouseButton::Right, MouseButton::Middle].into_iter().any(is_mouse_button_pressed)
+        || mouse_delta_position() != Vec2::ZERO
+}
+
+/// Demo reel shown after the menu sits idle: each playlist pattern runs on a torus through the
+/// normal `Session` update and draw path, with all input short-circuited to "leave".
+pub async fn run_attract(playlist: &[String]) {
+    let registry = default_registry();
+    let patterns: Vec<&dyn Pattern> = playlist.iter().filter_map(|name| registry.get(name)).collect();
+    if patterns.is_empty() {
+        return;
+    }
+    next_frame().await; // Let the input that was in flight when the reel started settle
+    for (i, pattern) in patterns.iter().cycle().enumerate() {
+        let (w, h) = (screen_width() as i32 / ATTRACT_CELL, screen_height() as i32 / ATTRACT_CELL);
+        let mut sim = Simulation::new(w.max(1), h.max(1));
+        sim.grid.topology = Topology::Torus;
+        sim.seed = (miniquad::date::now() * 1000.0) as u64;
+        sim.density = 0.3;
+        let _ = sim.apply_pattern(*pattern, w / 2, h / 2);
+        sim.mark_initial();
+        let mut view = BoardView::new(ATTRACT_CELL);
+        view.theme = ColorTheme::ALL[i % ColorTheme::ALL.len()];
+        let mut session = Session::new(sim, view);
+        session.speed = ATTRACT_SPEED;
+        session.smooth = false;
+
+        let start = get_time();
+        while get_time() - start < ATTRACT_SECONDS {
+            if any_input() {
+                next_frame().await;
+                return;
+            }
+            session.view.center_view(&session.sim.grid, screen_width(), screen_height());
+            session.update(get_frame_time());
+            render::render(&session.sim, &session.view, 1.0);
+
+            let colors = session.view.theme.colors();
+            let name = pattern.name();
+            let dims = measure_text(name, None, 64, 1.0);
+            draw_text(name, (screen_width() - dims.width) / 2.0, screen_height() - 60.0, 64.0, colors.text);
+            let hint = "Press any key";
+            let dims = measure_text(hint, None, 22, 1.0);
+            draw_text(hint, (screen_width() - dims.width) / 2.0, screen_height() - 30.0, 22.0, colors.text_secondary);
+            next_frame().await;
+        }
+    }
+}
+
+/// Top-level modes offered by the main menu
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum GameMode {
+    #[default]
+    Simulation, // Classic single-board sandbox
+    Territory,  // Two-player competitive mode
+    Elementary, // One-dimensional Wolfram rules drawn row by row
+    Records,    // Personal records, each of which can be replayed
+    Open,       // A saved project
 }
 
-/// Display pattern selection menu
-pub async fn choose_pattern() -> Option<usize> {
-    const PATTERN_COUNT: usize = 10; // Total number of available patterns
+/// Display the main menu
+pub async fn choose_mode(trail: &str) -> GameMode {
+    const MODES: [(GameMode, &str); 5] = [
+        (GameMode::Simulation, "Simulation"),
+        (GameMode::Territory, "Territory (2 players)"),
+        (GameMode::Elementary, "Elementary CA (1D)"),
+        (GameMode::Records, "Personal records"),
+        (GameMode::Open, "Open project"),
+    ];
     let mut selected = 0usize;
     loop {
-        clear_background(DARKBLUE);
-        draw_text("Select pattern:", 20.0, 50.0, 30.0, WHITE);
-        for i in 0..PATTERN_COUNT {
-            let pattern = get_pattern_by_index(i);
+        clear_background(DARKGRAY);
+        draw_breadcrumb(trail);
+        draw_text("Conway's Game of Life", 20.0, 50.0, 30.0, WHITE);
+        for (i, (_, name)) in MODES.iter().enumerate() {
             let marker = if i == selected { ">" } else { " " };
-            draw_text(&format!("{} {}", marker, pattern.name()), 40.0, 100.0 + i as f32 * 30.0, 25.0, WHITE);
+            draw_text(&format!("{} {}", marker, name), 40.0, 100.0 + i as f32 * 30.0, 25.0, WHITE);
         }
-        draw_text("Enter to start | Esc to go back", 20.0, 420.0, 25.0, GREEN);
+        draw_text("Enter to confirm", 20.0, 270.0, 25.0, GREEN);
 
-        if is_key_pressed(KeyCode::Up) { selected = (selected + PATTERN_COUNT - 1) % PATTERN_COUNT; }
-        if is_key_pressed(KeyCode::Down) { selected = (selected + 1) % PATTERN_COUNT; }
-        if is_key_pressed(KeyCode::Enter) { break Some(selected); }
-        if is_key_pressed(KeyCode::Escape) { break None; }
+        if is_key_pressed(KeyCode::Up) { selected = (selected + MODES.len() - 1) % MODES.len(); }
+        if is_key_pressed(KeyCode::Down) { selected = (selected + 1) % MODES.len(); }
+        if is_key_pressed(KeyCode::Enter) { break; }
         next_frame().await;
     }
+    next_frame().await;
+    MODES[selected].0
 }
 
-/// Run main game simulation loop
-pub async fn run_simulation(screen_w: i32, screen_h: i32, pattern_index: usize) {
-    use crate::config::{CELL_SIZE, SPEED_INIT, SPEED_MAX, SPEED_MIN};
-    use crate::game::GameOfLife;
-    
-    request_new_screen_size(screen_w as f32, screen_h as f32);
+/// List the projects and `.gold` saves in the working directory, newest first. Enter on one, or
+/// a project dropped on the window, returns it opened; one that can't be read says why and the
+/// list stays. Escape goes back.
+pub async fn open_project(trail: &str) -> Option<Project> {
+    let mut files: Vec<(PathBuf, std::time::SystemTime)> = std::fs::read_dir(".")
+        .map(|dir| {
+            dir.filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file() && project::is_project_file(path))
+                .map(|path| {
+                    let modified = path.metadata().and_then(|m| m.modified()).unwrap_or(std::time::UNIX_EPOCH);
+                    (path, modified)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
+    let mut selected = 0usize;
+    let mut error: Option<String> = None;
+    loop {
+        clear_background(DARKGRAY);
+        draw_breadcrumb(trail);
+        draw_text("Open project", 20.0, 50.0, 30.0, WHITE);
+        for (i, (path, _)) in files.iter().enumerate() {
+            let marker = if i == selected { ">" } else { " " };
+            draw_text(&format!("{} {}", marker, path.display()), 40.0, 100.0 + i as f32 * 30.0, 22.0, WHITE);
+        }
+        let footer_y = 140.0 + files.len() as f32 * 30.0;
+        if files.is_empty() {
+            draw_text("No projects here yet: Ctrl+S in a run saves one", 20.0, footer_y, 25.0, LIGHTGRAY);
+            draw_text("Drop a project on the window | Esc to go back", 20.0, footer_y + 30.0, 25.0, GREEN);
+        } else {
+            draw_text("Enter to open, or drop a project on the window | Esc to go back", 20.0, footer_y, 25.0, GREEN);
+        }
+        if let Some(message) = &error {
+            draw_text(message, 20.0, footer_y + 60.0, 22.0, RED);
+        }
 
-    // Calculate grid dimensions based on screen size and cell size
-    let grid_w = screen_w / CELL_SIZE;
-    let grid_h = screen_h / CELL_SIZE;
-    let mut game = GameOfLife::new(grid_w, grid_h, CELL_SIZE);
-    
-    // Apply selected pattern at grid center
-    let pattern = get_pattern_by_index(pattern_index);
-    let x = grid_w / 2;
-    let y = grid_h / 2;
-    game.apply_pattern(pattern.as_ref(), x, y);
-
-    let mut paused = false;
-    let mut speed: f32 = SPEED_INIT;
-    let mut acc = 0.0f32;
+        let count = files.len().max(1);
+        if is_key_pressed(KeyCode::Up) { selected = (selected + count - 1) % count; }
+        if is_key_pressed(KeyCode::Down) { selected = (selected + 1) % count; }
+        let opened = if let Some(file) = get_dropped_files().pop() {
+            Some(open_dropped(file).map(|(project, _)| project))
+        } else if is_key_pressed(KeyCode::Enter) && let Some((path, _)) = files.get(selected) {
+            Some(Project::open(path).map_err(String::from))
+        } else {
+            None
+        };
+        match opened {
+            Some(Ok(project)) => {
+                next_frame().await;
+                return Some(project);
+            }
+            Some(Err(err)) => error = Some(err),
+            None => {}
+        }
+        if is_key_pressed(KeyCode::Escape) { next_frame().await; return None; }
+        next_frame().await;
+    }
+}
 
+/// Display the personal records. Enter on a record returns the run it came from to replay;
+/// Escape goes back.
+pub async fn show_records(trail: &str) -> Option<RunSetup> {
+    let records = Records::load();
+    let rows = records.rows();
+    let replayable: Vec<usize> = (0..rows.len()).filter(|&i| rows[i].1.is_some()).collect();
+    let mut selected = 0usize; // Index into `replayable`
     loop {
-        let dt = get_frame_time(); // Time since last frame
-        acc += dt;
-
-        // Process user input
-        if is_key_pressed(KeyCode::Space) { paused = !paused; }
-        if is_key_pressed(KeyCode::N) && paused { game.next_generation(); }
-        if is_key_pressed(KeyCode::Minus) { speed = (speed - 1.0).max(SPEED_MIN); }
-        if is_key_pressed(KeyCode::Equal) { speed = (speed + 1.0).min(SPEED_MAX); }
-        if is_key_pressed(KeyCode::G) { game.show_grid = !game.show_grid; }
-        if is_key_pressed(KeyCode::W) { game.grid.wrap_world = !game.grid.wrap_world; }
-        if is_key_pressed(KeyCode::T) { game.cycle_theme(); }
-        if is_key_pressed(KeyCode::C) { game.clear(); }
-        if is_key_pressed(KeyCode::R) { 
-            game.clear(); 
-            game.random_fill(crate::config::RANDOM_DENSITY); 
+        clear_background(DARKGRAY);
+        draw_breadcrumb(trail);
+        draw_text("Personal records", 20.0, 50.0, 30.0, WHITE);
+        for (i, (label, _)) in rows.iter().enumerate() {
+            let marker = if replayable.get(selected) == Some(&i) { ">" } else { " " };
+            draw_text(&format!("{} {}", marker, label), 40.0, 100.0 + i as f32 * 30.0, 22.0, WHITE);
+        }
+        let footer_y = 140.0 + rows.len() as f32 * 30.0;
+        if replayable.is_empty() {
+            draw_text("No records yet: they are set when a run ends", 20.0, footer_y, 25.0, LIGHTGRAY);
+            draw_text("Esc to go back", 20.0, footer_y + 30.0, 25.0, GREEN);
+        } else {
+            draw_text("Enter to replay the record's run | Esc to go back", 20.0, footer_y, 25.0, GREEN);
         }
-        if is_key_pressed(KeyCode::Escape) { break; }
 
-        // Process mouse interaction
-        if is_mouse_button_pressed(MouseButton::Left) || is_mouse_button_down(MouseButton::Left) {
-            let (mx, my) = mouse_position(); // Get mouse coordinates
-            let gx = (mx / game.cell as f32) as i32; // Convert to grid coordinates
-            let gy = (my / game.cell as f32) as i32;
-            game.toggle_cell(gx, gy); // Toggle cell at mouse position
+        let count = replayable.len().max(1);
+        if is_key_pressed(KeyCode::Up) { selected = (selected + count - 1) % count; }
+        if is_key_pressed(KeyCode::Down) { selected = (selected + 1) % count; }
+        if is_key_pressed(KeyCode::Enter) && let Some(&i) = replayable.get(selected) {
+            next_frame().await;
+            return rows[i].1.cloned();
+        }
+        if is_key_pressed(KeyCode::Escape) { next_frame().await; return None; }
+        next_frame().await;
+    }
+}
+
+/// Logical grid dimensions and the pixel size cells start out at
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BoardSize {
+    pub width: i32,
+    pub height: i32,
+    pub cell: i32,
+    pub topology: Topology, // Which edges start out wrapped
+}
+
+impl BoardSize {
+    /// Grid that exactly tiles the window at the given cell size
+    pub fn fill_window(screen_w: i32, screen_h: i32, cell: i32) -> Self {
+        Self { width: screen_w / cell, height: screen_h / cell, cell, topology: Topology::Bounded }
+    }
+
+    /// Fixed grid using the largest integer cell size that fits in the window
+    pub fn fit_window(width: i32, height: i32, screen_w: i32, screen_h: i32) -> Self {
+        let cell = (screen_w / width).min(screen_h / height).clamp(CELL_SIZE_MIN, CELL_SIZE_MAX);
+        Self { width, height, cell, topology: Topology::Bounded }
+    }
+
+    /// Whether the grid is at least `(width, height)` in both directions
+    pub fn has_room(&self, (width, height): (i32, i32)) -> bool {
+        self.width >= width && self.height >= height
+    }
+}
+
+/// Parse "WxH" grid dimensions within the allowed range
+pub fn parse_dimensions(text: &str) -> Result<(i32, i32), GolError> {
+    let invalid = || GolError::invalid("grid size", format!("Expected WIDTHxHEIGHT between 1 and {}", GRID_MAX));
+    let (w, h) = text.trim().to_ascii_lowercase().split_once('x')
+        .and_then(|(w, h)| Some((w.trim().parse::<i32>().ok()?, h.trim().parse::<i32>().ok()?)))
+        .ok_or_else(invalid)?;
+    let valid = 1..=GRID_MAX;
+    if valid.contains(&w) && valid.contains(&h) { Ok((w, h)) } else { Err(invalid()) }
+}
+
+/// Display board size selection for the chosen resolution: window-filling cell sizes,
+/// fixed grid presets letterboxed into the window, or a custom size
+pub async fn choose_board(trail: &str, screen_w: i32, screen_h: i32) -> Option<BoardSize> {
+    let mut options: Vec<(String, BoardSize)> = CELL_SIZES.iter().map(|&c| {
+        let b = BoardSize::fill_window(screen_w, screen_h, c);
+        (format!("Fill window, {:>2} px cells  ->  {}x{} grid", c, b.width, b.height), b)
+    }).collect();
+    options.extend(GRID_PRESETS.iter().map(|&(w, h)| {
+        let b = BoardSize::fit_window(w, h, screen_w, screen_h);
+        (format!("Fixed {}x{} grid  ->  {} px cells", w, h, b.cell), b)
+    }));
+    let custom = options.len();
+    let count = custom + 1;
+
+    let mut selected = CELL_SIZES.iter().position(|&c| c == CELL_SIZE).unwrap_or(0);
+    let mut input: Option<TextInput> = None;
+    let mut error: Option<String> = None;
+    loop {
+        clear_background(DARKGRAY);
+        draw_breadcrumb(trail);
+        draw_text(&format!("Select board for {}x{}:", screen_w, screen_h), 20.0, 50.0, 30.0, WHITE);
+        for (i, (label, _)) in options.iter().enumerate() {
+            let marker = if i == selected { ">" } else { " " };
+            draw_text(&format!("{} {}", marker, label), 40.0, 100.0 + i as f32 * 30.0, 25.0, WHITE);
+        }
+        let marker = if selected == custom { ">" } else { " " };
+        draw_text(&format!("{} Custom size...", marker), 40.0, 100.0 + custom as f32 * 30.0, 25.0, WHITE);
+        let footer_y = 140.0 + count as f32 * 30.0;
+        draw_text("Enter to confirm | Esc to go back", 20.0, footer_y, 25.0, GREEN);
+        if let Some(err) = &error {
+            draw_text(err, 20.0, footer_y + 30.0, 22.0, RED);
         }
 
-        // Update game state
-        if !paused {
-            let step = 1.0 / speed;
-            while acc >= step {
-                game.next_generation();
-                acc -= step;
+        if let Some(field) = input.as_mut() {
+            field.draw(20.0, footer_y + 40.0, 360.0, &ColorTheme::Classic.colors());
+            match field.update() {
+                Some(InputEvent::Submitted(text)) => match parse_dimensions(&text) {
+                    Ok((w, h)) => {
+                        next_frame().await;
+                        return Some(BoardSize::fit_window(w, h, screen_w, screen_h));
+                    }
+                    Err(err) => {
+                        error = Some(err.to_string());
+                        input = Some(TextInput::new("Grid size (e.g. 400x60):", 11).with_text(&text));
+                    }
+                },
+                Some(InputEvent::Cancelled) => input = None,
+                None => {}
             }
+            next_frame().await;
+            continue;
         }
 
-        // Draw everything
-        game.draw();
-        game.draw_hud(paused, speed);
+        if is_key_pressed(KeyCode::Up) { selected = (selected + count - 1) % count; }
+        if is_key_pressed(KeyCode::Down) { selected = (selected + 1) % count; }
+        if is_key_pressed(KeyCode::Enter) {
+            if selected == custom {
+                error = None;
+                input = Some(TextInput::new("Grid size (e.g. 400x60):", 11));
+            } else {
+                break;
+            }
+        }
+        if is_key_pressed(KeyCode::Escape) { next_frame().await; return None; }
+        next_frame().await;
+    }
+    next_frame().await;
+    Some(options[selected].1)
+}
+
+/// Whether either Control key is held
+fn ctrl_down() -> bool {
+    is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl)
+}
+
+/// Whether either Shift key is held
+fn shift_down() -> bool {
+    is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift)
+}
+
+/// Pattern picked in the menu: a registry entry by index, or a file loaded from disk or a URL
+#[derive(Clone)]
+pub enum PatternChoice {
+    Registered(usize),
+    File(LoadedPattern),
+}
+
+impl PatternChoice {
+    pub fn pattern<'a>(&'a self, registry: &'a PatternRegistry) -> &'a dyn Pattern {
+        match self {
+            PatternChoice::Registered(i) => registry.get_index(*i).expect("picked from this registry"),
+            PatternChoice::File(p) => p,
+        }
+    }
+}
+
+/// Display pattern selection menu. The breadcrumb ends with the highlighted pattern's category.
+pub async fn choose_pattern(trail: &str, registry: &PatternRegistry) -> Option<PatternChoice> {
+    let mut pattern_dir = Watched::new(PATTERN_DIR); // Rescanned when it changes, with the `watch` feature
+    let mut files = scan_pattern_dir(Path::new(PATTERN_DIR));
+    let registered = registry.len();
+    let mut rows = menu_rows(registry, &files);
+    let mut count = rows.len();
+    // Figures are measured the first time an order needs them (files are read and stepped
+    // for that), then kept while the menu is open
+    let mut infos: Vec<Option<Option<PatternInfo>>> = vec![None; count];
+    let mut sort = PatternSort::default();
+    let mut sorted = false; // Until S is pressed the rows stay in registry order, files last
+    let mut order: Vec<usize> = (0..count).collect();
+    let mut selected = 0usize; // Position in `order`
+    let mut error: Option<String> = None; // Why the last file or download failed to load
+    let mut input: Option<TextInput> = None; // URL being typed
+    let mut fetch: Option<Fetch> = None;
+    let mut preview: Option<(usize, Option<Preview>)> = None; // Menu row shown in the preview box
+    let mut load: Option<(usize, Load)> = None; // File row being read on a worker for its preview
+    let mut loaded: Option<(usize, LoadedPattern)> = None; // Last file read, kept for Enter
+    let mut picked = false; // Enter was pressed on the file being read
+    let colors = ColorTheme::Classic.colors();
+    let choice = loop {
+        clear_background(DARKBLUE);
+        draw_breadcrumb(&format!("{}{}{}", trail, BREADCRUMB_SEPARATOR, rows[order[selected]].1));
+        draw_text("Select pattern:", 20.0, 50.0, 30.0, WHITE);
+        // Scroll so the selection stays inside the visible rows
+        let visible = 10;
+        let first = selected.saturating_sub(visible - 1);
+        for (pos, &i) in order.iter().enumerate().skip(first).take(visible) {
+            let (name, category) = &rows[i];
+            let info = infos[i].flatten();
+            let marker = if pos == selected { ">" } else { " " };
+            let y = 100.0 + (pos - first) as f32 * 30.0;
+            draw_text(&format!("{} {}", marker, name), 40.0, y, 25.0, WHITE);
+            draw_text(&sort.metric(category, info.as_ref()), 320.0, y, 20.0, LIGHTGRAY);
+        }
+        // Only the highlighted pattern runs; moving the highlight starts its preview afresh. Files
+        // are read on a worker, so a huge one doesn't freeze the menu.
+        let row = order[selected];
+        if preview.as_ref().is_none_or(|(shown, _)| *shown != row) && load.as_ref().is_none_or(|(reading, _)| *reading != row) {
+            match (registry.get_index(row), &loaded) {
+                (Some(pattern), _) => {
+                    load = None;
+                    preview = Some((row, patterns::shape(pattern, Transform::IDENTITY).map(|cells| Preview::new(&cells))));
+                }
+                (None, Some((read, pattern))) if *read == row => preview = Some((row, file_preview(pattern))),
+                (None, _) => {
+                    preview = None;
+                    load = Some((row, Load::start(&files[row - registered])));
+                }
+            }
+        }
+        if let Some(result) = load.as_ref().and_then(|(_, job)| job.poll()) {
+            let read = load.take().map_or(row, |(read, _)| read);
+            match result {
+                Ok(pattern) if picked => break Some(PatternChoice::File(pattern)),
+                Ok(pattern) => {
+                    preview = Some((read, file_preview(&pattern)));
+                    loaded = Some((read, pattern));
+                }
+                Err(err) => {
+                    if picked { error = Some(err.to_string()); }
+                    preview = Some((read, None));
+                }
+            }
+            picked = false;
+        }
+        match (&load, preview.as_mut()) {
+            (Some((_, job)), _) => draw_loading_preview(job.stage()),
+            (None, Some((_, shown))) => draw_preview(shown.as_mut(), get_frame_time() as f64),
+            (None, None) => {}
+        }
+        let url_key = if net::AVAILABLE { " | U: Load from URL" } else { "" };
+        draw_text(&format!("Enter to edit | S: Sort by {}{} | F5: Reload files | Esc to go back", sort.name(), url_key), 20.0, 420.0, 25.0, GREEN);
+        if let Some(err) = &error {
+            draw_text(err, 20.0, 455.0, 22.0, RED);
+        }
+
+        if let (true, Some((_, job))) = (picked, &load) {
+            let name = job.path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            draw_loading(&name, job.stage(), &colors);
+            if is_key_pressed(KeyCode::Escape) {
+                // The worker stops at its next chunk; picking the row again starts over
+                load = None;
+                picked = false;
+                error = Some("Loading cancelled".to_string());
+            }
+            next_frame().await;
+            continue;
+        }
+        if let Some(job) = &fetch {
+            draw_fetching(&job.url, &colors);
+            match follow_fetch(&mut fetch) {
+                Some(Ok(pattern)) => break Some(PatternChoice::File(pattern)),
+                Some(Err(err)) => error = Some(err),
+                None => {}
+            }
+            next_frame().await;
+            continue;
+        }
+        if let Some(field) = input.as_mut() {
+            field.draw(20.0, 470.0, (screen_width() - 40.0).min(640.0), &colors);
+            match field.update() {
+                Some(InputEvent::Submitted(text)) => {
+                    input = None;
+                    match Fetch::start(&text) {
+                        Ok(job) => { error = None; fetch = Some(job); }
+                        Err(err) => error = Some(err.to_string()),
+                    }
+                }
+                Some(InputEvent::Cancelled) => input = None,
+                None => {}
+            }
+            next_frame().await;
+            continue;
+        }
+        if net::AVAILABLE && is_key_pressed(KeyCode::U) {
+            input = Some(TextInput::new("Pattern URL (RLE, plaintext or Life 1.0x; Ctrl+V pastes):", 300));
+        }
+
+        if is_key_pressed(KeyCode::F5) || pattern_dir.changed(get_time()) {
+            // Files may have come, gone or been edited: list them again, measure them again if
+            // the order needs it, and stay on the same pattern if it is still there
+            pattern_dir.mark_seen();
+            let current = order[selected];
+            let current_file = current.checked_sub(registered).map(|i| files[i].clone());
+            files = scan_pattern_dir(Path::new(PATTERN_DIR));
+            rows = menu_rows(registry, &files);
+            count = rows.len();
+            infos.truncate(registered);
+            infos.resize(count, None);
+            order = (0..count).collect();
+            preview = None;
+            (load, loaded) = (None, None);
+            if sorted {
+                sort_rows(sort, registry, &files, &rows, &mut infos, &mut order);
+            }
+            let target = match current_file {
+                Some(path) => files.iter().position(|f| *f == path).map(|i| i + registered),
+                None => Some(current),
+            };
+            selected = target.and_then(|t| order.iter().position(|&i| i == t)).unwrap_or(selected.min(count - 1));
+        }
+        if is_key_pressed(KeyCode::Up) { selected = (selected + count - 1) % count; }
+        if is_key_pressed(KeyCode::Down) { selected = (selected + 1) % count; }
+        if is_key_pressed(KeyCode::S) {
+            let current = order[selected];
+            sort = sort.next();
+            sorted = true;
+            sort_rows(sort, registry, &files, &rows, &mut infos, &mut order);
+            selected = order.iter().position(|&i| i == current).unwrap_or(0);
+        }
+        if is_key_pressed(KeyCode::Enter) {
+            let index = order[selected];
+            match registry.get_index(index) {
+                Some(_) => break Some(PatternChoice::Registered(index)),
+                None => match loaded.take() {
+                    Some((read, pattern)) if read == index => break Some(PatternChoice::File(pattern)),
+                    other => {
+                        // Still being read, or failed: wait for it, or read it again to say why
+                        loaded = other;
+                        if load.as_ref().is_none_or(|(reading, _)| *reading != index) {
+                            load = Some((index, Load::start(&files[index - registered])));
+                        }
+                        error = None;
+                        picked = true;
+                    }
+                },
+            }
+        }
+        if is_key_pressed(KeyCode::Escape) { break None; }
+        next_frame().await;
+    };
+    // Let the Enter that picked the pattern settle before edit mode reads it as Start
+    next_frame().await;
+    choice
+}
+
+/// Warn when the board is smaller than the pattern's recommended grid, offering a torus or a
+/// bigger board. Returns the board to start on, or `None` to go back to the menus.
+pub async fn make_room(trail: &str, screen_w: i32, screen_h: i32, mut board: BoardSize, pattern: &dyn Pattern) -> Option<BoardSize> {
+    let Some((w, h)) = pattern.recommended_grid() else { return Some(board) };
+    if board.has_room((w, h)) { return Some(board); }
+    let colors = ColorTheme::Classic.colors();
+    while !board.has_room((w, h)) {
+        clear_background(DARKBLUE);
+        draw_breadcrumb(trail);
+        draw_text(&format!("{} needs room", pattern.name()), 20.0, 50.0, 30.0, WHITE);
+        let lines = [
+            format!("It is best on a grid of at least {}x{} cells; this board is {}x{}.", w, h, board.width, board.height),
+            "On a smaller bounded board it runs into the walls early.".to_string(),
+        ];
+        for (i, line) in lines.iter().enumerate() {
+            draw_text(line, 40.0, 100.0 + i as f32 * 30.0, 22.0, colors.text_secondary);
+        }
+        draw_text("W: Wrap the edges | B: Pick a bigger board | Enter: Start anyway | Esc: Back", 20.0, 200.0, 22.0, GREEN);
+
+        if is_key_pressed(KeyCode::W) {
+            board.topology = Topology::Torus;
+            break;
+        }
+        if is_key_pressed(KeyCode::B) {
+            next_frame().await;
+            board = choose_board(&format!("{}{}{}", trail, BREADCRUMB_SEPARATOR, MenuScreen::Board.name()), screen_w, screen_h).await?;
+            continue;
+        }
+        if is_key_pressed(KeyCode::Enter) { break; }
+        if is_key_pressed(KeyCode::Escape) { next_frame().await; return None; }
         next_frame().await;
     }
-}
\ No newline at end of file
+    // Let the key that answered settle before edit mode reads it
+    next_frame().await;
+    Some(board)
+}
+
+/// The pattern menu's rows as (name, category): the registry's patterns, then `files`
+fn menu_rows(registry: &PatternRegistry, files: &[PathBuf]) -> Vec<(String, String)> {
+    let registered = registry.iter().map(|p| (p.name().to_string(), p.category().to_string()));
+    let files = files.iter().map(|f| (f.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default(), "File".to_string()));
+    registered.chain(files).collect()
+}
+
+/// Put `order` in `sort`'s order, first measuring any rows the order needs figures for
+fn sort_rows(
+    sort: PatternSort, registry: &PatternRegistry, files: &[PathBuf], rows: &[(String, String)],
+    infos: &mut [Option<Option<PatternInfo>>], order: &mut [usize],
+) {
+    if sort.needs_info() {
+        for (i, info) in infos.iter_mut().enumerate() {
+            if info.is_none() {
+                *info = Some(measure_row(registry, files, i));
+            }
+        }
+    }
+    order.sort_by(|&a, &b| {
+        let row = |i: usize| (rows[i].0.as_str(), rows[i].1.as_str(), infos[i].as_ref().and_then(Option::as_ref));
+        sort.compare(row(a), row(b))
+    });
+}
+
+/// Preview of a pattern file; its cells already start at (0, 0), so they need no staging
+fn file_preview(pattern: &LoadedPattern) -> Option<Preview> {
+    (!pattern.cells.is_empty()).then(|| Preview::new(&pattern.cells))
+}
+
+/// The preview box while the highlighted file is still being read
+fn draw_loading_preview(stage: Stage) {
+    let (x, y) = (screen_width() - PREVIEW_BOX - 20.0, 80.0);
+    draw_rectangle(x, y, PREVIEW_BOX, PREVIEW_BOX, Color::new(0.0, 0.0, 0.0, 0.3));
+    draw_rectangle_lines(x, y, PREVIEW_BOX, PREVIEW_BOX, 1.0, LIGHTGRAY);
+    draw_progress(x + 20.0, y + PREVIEW_BOX / 2.0 - 6.0, PREVIEW_BOX - 40.0, stage, LIGHTGRAY);
+    draw_text(&stage_label(stage), x, y + PREVIEW_BOX + 22.0, 20.0, LIGHTGRAY);
+}
+
+/// "Reading 45%" or "Parsing"
+fn stage_label(stage: Stage) -> String {
+    match stage {
+        Stage::Reading(fraction) => format!("Reading {:.0}%", fraction * 100.0),
+        Stage::Parsing => "Parsing".to_string(),
+    }
+}
+
+/// A bar filled to how much of the file has been read; while parsing, a block sweeps along it
+fn draw_progress(x: f32, y: f32, width: f32, stage: Stage, color: Color) {
+    draw_rectangle_lines(x, y, width, 12.0, 1.0, color);
+    match stage {
+        Stage::Reading(fraction) => draw_rectangle(x + 2.0, y + 2.0, (width - 4.0) * fraction, 8.0, color),
+        Stage::Parsing => {
+            let block = width / 5.0;
+            let at = (get_time() as f32 * 0.8).fract() * (width - block);
+            draw_rectangle(x + at, y + 2.0, block, 8.0, color);
+        }
+    }
+}
+
+/// Panel shown once Enter has picked a file that is still being read
+fn draw_loading(name: &str, stage: Stage, colors: &ThemeColors) {
+    let width = (screen_width() - 40.0).min(640.0);
+    let (x, y) = ((screen_width() - width) / 2.0, screen_height() / 2.0 - 40.0);
+    let mut bg = colors.background;
+    bg.a = 0.92;
+    draw_rectangle(x, y, width, 80.0, bg);
+    draw_rectangle_lines(x, y, width, 80.0, 2.0, colors.border);
+    draw_text(&format!("Loading {} ({})", name, stage_label(stage)), x + 20.0, y + 28.0, 20.0, colors.text);
+    draw_progress(x + 20.0, y + 40.0, width - 40.0, stage, colors.text);
+    draw_text("Esc: cancel", x + 20.0, y + 72.0, 18.0, colors.text_secondary);
+}
+
+/// Draw the pattern menu's preview box at the top right, running `preview` on by `dt` seconds,
+/// with what the pattern does written under it
+fn draw_preview(preview: Option<&mut Preview>, dt: f64) {
+    let (x, y) = (screen_width() - PREVIEW_BOX - 20.0, 80.0);
+    draw_rectangle(x, y, PREVIEW_BOX, PREVIEW_BOX, Color::new(0.0, 0.0, 0.0, 0.3));
+    draw_rectangle_lines(x, y, PREVIEW_BOX, PREVIEW_BOX, 1.0, LIGHTGRAY);
+    let Some(preview) = preview else {
+        draw_text("Fills the board", x + 8.0, y + PREVIEW_BOX + 22.0, 20.0, LIGHTGRAY);
+        return;
+    };
+    preview.update(dt);
+    preview.draw(x + 6.0, y + 6.0, PREVIEW_BOX - 12.0, WHITE);
+    draw_text(&preview.label, x, y + PREVIEW_BOX + 22.0, 20.0, LIGHTGRAY);
+    if preview.animated() {
+        draw_text(&format!("gen {}", preview.generation), x, y + PREVIEW_BOX + 44.0, 18.0, LIGHTGRAY);
+    }
+}
+
+/// Figures for row `i` of the pattern menu: registry entries first, then files. A file that
+/// fails to load has none; the error shows if it is picked.
+fn measure_row(registry: &PatternRegistry, files: &[PathBuf], i: usize) -> Option<PatternInfo> {
+    match registry.get_index(i) {
+        Some(pattern) => PatternInfo::measure(pattern),
+        None => load_pattern_file(&files[i - registry.len()]).ok().and_then(|p| PatternInfo::measure(&p)),
+    }
+}
+
+/// Measures the generation rate actually achieved and how long it has lagged the target
+#[derive(Default)]
+struct RateMeter {
+    window_start: f64,
+    window_gens: u32,
+    achieved: Option<f32>,     // Generations per second over the last full window
+    behind_since: Option<f64>, // When the achieved rate first fell short of the target
+}
+
+impl RateMeter {
+    /// Record generations computed this frame against the target rate
+    fn record(&mut self, now: f64, gens: u32, target: f32) {
+        if self.window_start == 0.0 { self.window_start = now; }
+        self.window_gens += gens;
+        let elapsed = now - self.window_start;
+        if elapsed < RATE_WINDOW { return; }
+
+        let achieved = (self.window_gens as f64 / elapsed) as f32;
+        self.achieved = Some(achieved);
+        self.window_start = now;
+        self.window_gens = 0;
+        if achieved < target * LAG_TOLERANCE {
+            self.behind_since.get_or_insert(now);
+        } else {
+            self.behind_since = None;
+        }
+    }
+
+    /// Forget measurements, e.g. while paused or after the target changes
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Achieved rate if it has fallen short of the target for long enough to warn about
+    fn lagging(&self, now: f64) -> Option<f32> {
+        let since = self.behind_since?;
+        (now - since >= LAG_WARN_SECONDS).then_some(self.achieved?)
+    }
+}
+
+/// One interactive board: the simulation and its view plus its own pause state and speed clock
+struct Session {
+    sim: Simulation,
+    view: BoardView,
+    home_cell: i32, // Cell size the board was chosen with: the 100% zoom preset
+    paused: bool,
+    speed: f32,
+    clock: SimClock, // Fixed-timestep clock; simulation time only moves while running
+    camera: Camera,  // Follow mode and its eased position
+    meter: RateMeter,
+    smooth: bool, // Smooth rendering preference; only applied at low speeds
+    ghost_border: bool, // Debug view of what border cells see past the edge
+    stepper: Option<Stepper>, // Worker thread, when background stepping is on
+    pending: Option<Pending>, // Job the worker is running
+    schedule: Option<SpeedSchedule>, // Sets the speed from the generation reached until overridden
+    controls: Controls, // Groups of keys and mouse actions the board responds to
+    script: Option<ScriptHook>, // Runs after each generation of this board
+    growth: GrowthTracker, // Recent population, for the HUD's growth trend
+    guard: Guard,       // Second press that destructive keys need while running
+    clear_menu: bool,   // C opens the run loop's smart clear menu rather than clearing at once
+    pause_on_edit: bool, // Drawing pauses the board first
+    torus: Topology,    // What W makes of the board when it comes to the torus, shifted as the settings file says
+    heat: Option<HeatField>, // Recent activity, while the heat overlay is on
+    heat_intensity: f32, // Opacity of the heat overlay at full heat
+    particles: Option<Particles>, // Births and deaths drawn over the board, while they are on
+    particle_intensity: f32, // Opacity of a particle as it starts
+    remote: Option<Endpoint>, // Commands from another thread, and where each generation is published
+    feed: Option<Feed>,       // Clients of `--serve` mirroring this board
+    pacing: Option<Limit>, // Frame rate this frame is held to; longer frames step more generations
+    notes: Annotations, // Pinned to this board's cells, outside the simulation
+    compare: Option<Compare>, // Reference the board is diffed against, while compare mode is on
+    next_preview: Option<NextPreview>, // What the next generation changes, while the preview is on
+}
+
+/// What a board holds in memory, with the delta log and time-lapse that go with it
+fn board_memory(session: &Session, recorder: Option<&DeltaLog>, gallery: &Gallery) -> MemoryUsage {
+    let mut usage = session.sim.memory_usage();
+    usage.timelapse = gallery.bytes();
+    usage.deltas = recorder.map_or(0, DeltaLog::bytes);
+    usage.overlays = session.heat.as_ref().map_or(0, HeatField::bytes)
+        + session.particles.as_ref().map_or(0, Particles::bytes)
+        + session.compare.as_ref().map_or(0, Compare::bytes);
+    usage
+}
+
+/// A board in a background tab, frozen until it is switched back to, with the run state that
+/// belongs to it rather than to the window
+struct Parked {
+    session: Session,
+    stats: RunStats,
+    recorder: Option<DeltaLog>,
+    gallery: Gallery,
+    choice: Option<PatternChoice>, // What Ctrl+R re-rolls
+    pattern_name: String,
+}
+
+impl Parked {
+    /// Trade places with the run loop's board, so the parked board becomes the active one
+    fn exchange(&mut self, session: &mut Session, stats: &mut RunStats, recorder: &mut Option<DeltaLog>, gallery: &mut Gallery, choice: &mut Option<PatternChoice>, pattern_name: &mut String) {
+        std::mem::swap(&mut self.session, session);
+        std::mem::swap(&mut self.stats, stats);
+        std::mem::swap(&mut self.recorder, recorder);
+        std::mem::swap(&mut self.gallery, gallery);
+        std::mem::swap(&mut self.choice, choice);
+        std::mem::swap(&mut self.pattern_name, pattern_name);
+    }
+}
+
+/// The boards open in a run. The active one lives in the run loop's own variables; the others
+/// wait here, and only the active one steps and draws.
+struct Tabs {
+    names: Vec<String>,
+    parked: Vec<Option<Parked>>, // `None` at `active`
+    active: usize,
+    opened: usize, // Tabs opened so far, for numbering new ones
+}
+
+impl Tabs {
+    fn new() -> Self {
+        Self { names: vec!["Board 1".to_string()], parked: vec![None], active: 0, opened: 1 }
+    }
+
+    fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Memory held by the boards in background tabs
+    fn bytes(&self) -> usize {
+        self.parked.iter().flatten().map(|p| board_memory(&p.session, p.recorder.as_ref(), &p.gallery).total()).sum()
+    }
+
+    /// Add a board after the others; returns its index
+    fn open(&mut self, board: Parked) -> usize {
+        self.opened += 1;
+        self.names.push(format!("Board {}", self.opened));
+        self.parked.push(Some(board));
+        self.len() - 1
+    }
+
+    /// Take a background board out to switch to it
+    fn take(&mut self, index: usize) -> Parked {
+        self.parked[index].take().expect("only the active tab has no parked board")
+    }
+
+    /// Park the board that was active and make `index` the active tab
+    fn park(&mut self, board: Parked, index: usize) {
+        self.parked[self.active] = Some(board);
+        self.active = index;
+    }
+
+    /// Forget the active tab; returns the neighbour to show instead, or `None` for the last tab
+    fn close(&mut self) -> Option<usize> {
+        if self.len() == 1 {
+            return None;
+        }
+        self.names.remove(self.active);
+        self.parked.remove(self.active);
+        self.active = self.active.min(self.len() - 1);
+        Some(self.active)
+    }
+}
+
+/// Tab bar under the right end of the HUD: each board's name, generation and population, the
+/// active one outlined. Hidden while only one board is open.
+fn draw_tabs(tabs: &Tabs, active: &Simulation, colors: &ThemeColors) {
+    if tabs.len() < 2 {
+        return;
+    }
+    let labels: Vec<String> = (0..tabs.len())
+        .map(|i| {
+            let sim = tabs.parked[i].as_ref().map_or(active, |p| &p.session.sim);
+            format!("{} | gen {} | {}", tabs.names[i], sim.generation, sim.live.len())
+        })
+        .collect();
+    let widths: Vec<f32> = labels.iter().map(|l| measure_text(l, None, 18, 1.0).width + 16.0).collect();
+    let mut x = screen_width() - widths.iter().sum::<f32>() - 4.0 * (labels.len() - 1) as f32 - 10.0;
+    let y = HUD_HEIGHT + 4.0;
+    for (i, (label, w)) in labels.iter().zip(widths).enumerate() {
+        let mut bg = colors.background;
+        bg.a = 0.85;
+        draw_rectangle(x, y, w, 24.0, bg);
+        let current = i == tabs.active;
+        draw_rectangle_lines(x, y, w, 24.0, if current { 2.0 } else { 1.0 }, colors.border);
+        draw_text(label, x + 8.0, y + 17.0, 18.0, if current { colors.text } else { colors.text_secondary });
+        x += w + 4.0;
+    }
+}
+
+/// Warm the heat overlay and emit particles, whichever are on, for the generation just computed
+fn warm(heat: &mut Option<HeatField>, particles: &mut Option<Particles>, sim: &Simulation) {
+    let Some(diff) = sim.diff.as_ref() else { return };
+    if let Some(heat) = heat.as_mut() {
+        heat.record(diff);
+    }
+    if let Some(particles) = particles.as_mut() {
+        particles.record(diff);
+    }
+}
+
+/// A job handed to the worker, with what is needed to tell whether its result still applies
+struct Pending {
+    id: u64,
+    generation: u64,
+    steps: u32,
+    width: i32,
+    height: i32,
+    topology: Topology,
+    rule: Rule,
+}
+
+/// The cells the rule will change in the next generation, drawn over a paused board
+#[derive(Default)]
+struct NextPreview {
+    counts: HashMap<Position, u8>, // Neighbor counts, kept between frames for their allocation
+    changes: Vec<(Position, CellFate)>, // Births and deaths only
+    key: Option<PreviewKey>, // What the changes were counted for
+}
+
+/// Everything the preview's changes depend on
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct PreviewKey {
+    live: (usize, u64), // Cell count and `board_hash`, which costs far less than counting neighbors
+    generation: u64,
+    rule: Rule,
+    topology: Topology,
+    size: (i32, i32),
+}
+
+impl NextPreview {
+    /// Recount `live` if it, the generation or the grid changed since the last count, so a
+    /// paused board costs a hash per frame rather than a count. Edge spawns and the lethal
+    /// margin are left out: this is the rule's part.
+    fn refresh(&mut self, grid: &Grid, live: &HashSet<Position>, generation: u64) {
+        let key = Some(PreviewKey {
+            live: (live.len(), board_hash(live)),
+            generation,
+            rule: grid.rule,
+            topology: grid.topology,
+            size: (grid.width, grid.height),
+        });
+        if key == self.key {
+            return;
+        }
+        self.key = key;
+        let counts = grid.count_neighbors(live, &mut self.counts);
+        self.changes.clear();
+        self.changes.extend(grid.fates(counts, live).filter(|(_, fate)| matches!(fate, CellFate::Born | CellFate::Dies)));
+    }
+}
+
+impl Session {
+    fn new(sim: Simulation, view: BoardView) -> Self {
+        let settings = Settings::load();
+        let stepper = if settings.background { Stepper::spawn() } else { None };
+        let heat = settings.heat.then(|| HeatField::new(&sim.grid));
+        Self {
+            sim,
+            home_cell: view.cell,
+            view,
+            paused: false,
+            speed: SPEED_INIT,
+            clock: SimClock::new(SPEED_INIT),
+            camera: Camera::new(settings.follow_settle),
+            meter: RateMeter::default(),
+            smooth: settings.smooth,
+            ghost_border: false,
+            stepper,
+            pending: None,
+            schedule: None,
+            controls: Controls::ALL,
+            script: None,
+            growth: GrowthTracker::default(),
+            guard: Guard::new(settings.confirm_destructive),
+            clear_menu: true,
+            pause_on_edit: settings.pause_on_edit,
+            torus: settings.torus,
+            heat,
+            heat_intensity: settings.heat_intensity,
+            particles: settings.particles.then(Particles::default),
+            particle_intensity: settings.particle_intensity,
+            remote: None,
+            feed: None,
+            pacing: None,
+            notes: Annotations::default(),
+            compare: None,
+            next_preview: None,
+        }
+    }
+
+    /// Whether generations are going by: not paused, and out of edit mode
+    fn running(&self) -> bool {
+        !self.paused && !self.sim.editing()
+    }
+
+    /// Apply the commands a remote controller has sent since the last frame
+    fn obey(&mut self) {
+        let Some(endpoint) = self.remote.as_mut() else { return };
+        let mut control = Control { paused: self.paused, speed: self.speed };
+        endpoint.apply(&mut self.sim, &mut control);
+        // NaN would slip through the clamp and stall the clock, so only a real number is taken
+        if control.speed != self.speed && control.speed.is_finite() {
+            self.set_speed(control.speed.clamp(SPEED_MIN, SPEED_MAX));
+        }
+        self.paused = control.paused;
+    }
+
+    /// Write a debug dump of this board to `path`, returning the notice to show
+    fn dump_debug(&self, path: &Path) -> String {
+        let history = self.growth.recent(DEBUG_DUMP_HISTORY);
+        let dump = DebugDump { annotations: self.notes.notes.clone(), ..DebugDump::capture(&self.sim, history, Settings::load().to_text(), render::CONTROLS) };
+        match dump.save(path) {
+            Ok(()) => format!("Saved a debug dump to {}", path.display()),
+            Err(err) => format!("Couldn't save the debug dump: {}", err),
+        }
+    }
+
+    /// Whether a destructive action goes ahead now, or only asks for a second press
+    fn allow(&mut self, action: Destructive) -> Result<(), String> {
+        let hint = action.hint();
+        if self.guard.allow(action, self.running(), get_time()) { Ok(()) } else { Err(hint) }
+    }
+
+    /// Whether the worker can run generations: births, ages and edge spawns are tracked
+    /// per generation on the simulation itself, as do script hooks, so those modes step on this thread
+    fn offload(&self) -> bool {
+        self.stepper.is_some() && !self.sim.track_diff && !self.sim.edge_spawn && !self.sim.culls() && self.sim.ages.is_none()
+            && !self.script.as_ref().is_some_and(ScriptHook::enabled) && self.remote.is_none()
+    }
+
+    /// Run one generation and the script hook after it
+    fn step(&mut self) {
+        self.sim.next_generation();
+        warm(&mut self.heat, &mut self.particles, &self.sim);
+        if let Some(endpoint) = &self.remote {
+            endpoint.publish(&self.sim);
+        }
+        if let Some(feed) = self.feed.as_mut() {
+            feed.publish(&self.sim);
+        }
+        if let Some(hook) = self.script.as_mut() {
+            hook.after_generation(&mut self.sim, self.speed);
+        }
+    }
+
+    /// Apply a speed the script asked for; like a speed set by hand, it ends any schedule
+    fn take_script_speed(&mut self) {
+        if let Some(speed) = self.script.as_mut().and_then(ScriptHook::take_speed) {
+            self.speed = speed;
+            self.schedule = None;
+        }
+    }
+
+    /// Swap in the worker's result if it is ready and still applies; returns the generations it added
+    fn collect_stepped(&mut self) -> u32 {
+        let Some(stepper) = &self.stepper else { return 0 };
+        let Some(done) = stepper.poll() else { return 0 };
+        let Some(pending) = self.pending.take_if(|p| p.id == done.id) else { return 0 };
+        let sim = &mut self.sim;
+        // Cleared, resized, rewrapped or given a new rule while the job ran: its board no longer applies
+        if sim.generation != pending.generation
+            || sim.grid.width != pending.width
+            || sim.grid.height != pending.height
+            || sim.grid.topology != pending.topology
+            || sim.grid.rule != pending.rule
+        {
+            return 0;
+        }
+        // The worker's board already has the cells set by hand while it ran
+        sim.live = done.live;
+        sim.generation += pending.steps as u64;
+        pending.steps
+    }
+
+    /// Whether births and deaths are currently animated between generations
+    fn smoothing(&self) -> bool {
+        self.smooth && !self.paused && self.speed <= SMOOTH_MAX_SPEED
+    }
+
+    /// Leave edit mode, keeping the board as generation 0. Does nothing once a run has started.
+    fn begin(&mut self) {
+        if self.sim.editing() {
+            self.sim.mark_initial();
+        }
+    }
+
+    /// The board went back to generation 0: pause, drop any job in flight and restart simulation
+    /// time, so the same run lands on the same ticks
+    fn restarted(&mut self) {
+        self.paused = true;
+        self.pending = None;
+        self.clock = SimClock::new(self.speed);
+    }
+
+    /// Set the speed by hand, which ends any speed schedule; returns a notice if it did
+    fn set_speed(&mut self, speed: f32) -> Option<&'static str> {
+        self.speed = speed;
+        self.schedule.take().map(|_| "Speed schedule cleared: the speed is yours again")
+    }
+
+    /// Process the keyboard and mouse controls shared by every board, returning a notice to show.
+    /// `mouse_edit` is false while the left button belongs to another tool (e.g. stamping).
+    /// Only the groups in `self.controls` respond.
+    fn handle_input(&mut self, mouse_edit: bool) -> Option<String> {
+        let mut manual_speed = None;
+        let mut notice = None;
+        let mut restart = false;
+        let controls = self.controls;
+        let running = self.running();
+        let clear_menu = self.clear_menu;
+        let (sim, view) = (&mut self.sim, &mut self.view);
+        if controls.playback {
+            if is_key_pressed(KeyCode::Space) {
+                // In edit mode Space is another way to press Start
+                if sim.editing() {
+                    sim.mark_initial();
+                    self.paused = false;
+                } else {
+                    self.paused = !self.paused;
+                }
+            }
+            if is_key_pressed(KeyCode::N) && shift_down() {
+                self.next_preview = match self.next_preview {
+                    Some(_) => None,
+                    None => Some(NextPreview::default()),
+                };
+            } else if is_key_pressed(KeyCode::N) && self.paused {
+                if sim.editing() { sim.mark_initial(); }
+                sim.next_generation();
+                warm(&mut self.heat, &mut self.particles, sim);
+                if let Some(endpoint) = &self.remote {
+                    endpoint.publish(sim);
+                }
+                if let Some(feed) = self.feed.as_mut() {
+                    feed.publish(sim);
+                }
+                if let Some(hook) = self.script.as_mut() {
+                    hook.after_generation(sim, self.speed);
+                }
+            }
+            restart = is_key_pressed(KeyCode::R) && !ctrl_down() && !shift_down() && sim.reset_to_initial();
+            if is_key_pressed(KeyCode::U) {
+                // The board as it stands becomes the generation 0 that R returns to
+                sim.mark_initial();
+                restart = true;
+            }
+        }
+        if ctrl_down() {
+            // Rendering scale only; the grid keeps its dimensions. Zooming keeps the cell under
+            // the mouse in place, except fitting the whole board. Past 1 px per cell Ctrl+- goes
+            // on to several cells per pixel, drawn as a density picture.
+            let zoom = if is_key_pressed(KeyCode::Minus) {
+                Some(view.zoom_stepped(-1))
+            } else if is_key_pressed(KeyCode::Equal) {
+                Some(view.zoom_stepped(1))
+            } else if is_key_pressed(KeyCode::Key2) {
+                Some((self.home_cell, 1))
+            } else if is_key_pressed(KeyCode::Key3) {
+                // 4x detail, as near as the zoom levels go
+                Some((BoardView::zoom_level_at_most(self.home_cell * 4), 1))
+            } else {
+                None
+            };
+            if controls.view && is_key_pressed(KeyCode::Key1) {
+                view.fit_board(&sim.grid, screen_width(), screen_height());
+            } else if controls.view && let Some(zoom) = zoom {
+                view.zoom_at(&sim.grid, zoom, Vec2::from(mouse_position()), screen_width(), screen_height());
+            }
+        } else if shift_down() {
+            let step = match (is_key_pressed(KeyCode::Minus), is_key_pressed(KeyCode::Equal)) {
+                (true, false) => -DENSITY_STEP,
+                (false, true) => DENSITY_STEP,
+                _ => 0.0,
+            };
+            if controls.edit && step != 0.0 {
+                // Round away float drift so repeated steps land on 0.05 multiples
+                sim.density = (((sim.density + step) * 100.0).round() / 100.0).clamp(DENSITY_MIN, DENSITY_MAX);
+                notice = save_setting(|s| s.density = sim.density);
+            }
+        } else if controls.playback && is_key_pressed(KeyCode::Minus) {
+            manual_speed = Some((self.speed - 1.0).max(SPEED_MIN));
+        } else if controls.playback && is_key_pressed(KeyCode::Equal) {
+            manual_speed = Some((self.speed + 1.0).min(SPEED_MAX));
+        }
+        if controls.view {
+            if is_key_pressed(KeyCode::G) {
+                if shift_down() {
+                    view.grid_style.toggle_line();
+                } else if ctrl_down() {
+                    view.grid_style.toggle_major_every();
+                } else {
+                    view.grid_style.cycle_mode();
+                }
+                notice = save_setting(|s| s.grid = view.grid_style);
+            }
+            if is_key_pressed(KeyCode::V) && shift_down() {
+                self.particles = match self.particles {
+                    Some(_) => None,
+                    None => Some(Particles::default()),
+                };
+                notice = save_setting(|s| s.particles = self.particles.is_some());
+            } else if is_key_pressed(KeyCode::V) && !ctrl_down() {
+                self.smooth = !self.smooth;
+                notice = save_setting(|s| s.smooth = self.smooth);
+            }
+            if is_key_pressed(KeyCode::H) && shift_down() {
+                self.heat = match self.heat {
+                    Some(_) => None,
+                    None => Some(HeatField::new(&sim.grid)),
+                };
+                notice = save_setting(|s| s.heat = self.heat.is_some());
+            }
+            if is_key_pressed(KeyCode::Z) && ctrl_down() {
+                let follow = match self.camera.follow {
+                    Follow::Off => Follow::Bounds,
+                    _ => Follow::Off,
+                };
+                self.camera.start(follow, view, screen_width(), screen_height());
+            } else if is_key_pressed(KeyCode::Z) && !shift_down() {
+                view.fit_view(sim, screen_width(), screen_height());
+            }
+            if is_key_pressed(KeyCode::Y) {
+                if shift_down() {
+                    view.ruler = !view.ruler;
+                } else {
+                    view.coords = view.coords.toggle();
+                }
+                notice = save_setting(|s| (s.coords, s.ruler) = (view.coords, view.ruler));
+            }
+            if is_key_pressed(KeyCode::B) && !ctrl_down() && !shift_down() { self.ghost_border = !self.ghost_border; }
+            if is_key_pressed(KeyCode::T) && !shift_down() && !ctrl_down() { view.cycle_theme(); }
+        }
+        if controls.edit {
+            if is_key_pressed(KeyCode::O) { sim.edge_spawn = !sim.edge_spawn; }
+            if is_key_pressed(KeyCode::W) && !ctrl_down() { sim.grid.topology = sim.grid.topology.next().with_shift_of(self.torus); }
+            let now = get_time();
+            let mut allow = |action: Destructive| {
+                let hint = action.hint();
+                self.guard.allow(action, running, now) || { notice = Some(hint); false }
+            };
+            if !clear_menu && is_key_pressed(KeyCode::C) && !ctrl_down() && allow(Destructive::Clear) { sim.clear(); }
+            if is_key_pressed(KeyCode::R) && shift_down() && allow(Destructive::Reseed) {
+                sim.clear();
+                sim.next_seed();
+                sim.random_fill(sim.density);
+            }
+        }
+        if let Some(achieved) = self.meter.lagging(get_time()).filter(|_| controls.playback) {
+            // Offered only while the lag warning is showing
+            if is_key_pressed(KeyCode::L) {
+                manual_speed = Some(achieved.floor().clamp(SPEED_MIN, SPEED_MAX));
+                self.meter.reset();
+            }
+            if is_key_pressed(KeyCode::P) {
+                self.paused = true;
+            }
+        }
+
+        view.center_view(&sim.grid, screen_width(), screen_height());
+
+        // Process mouse interaction; Ctrl+click picks an object to follow instead of drawing
+        if mouse_edit && ctrl_down() {
+            if controls.view && is_mouse_button_pressed(MouseButton::Left) {
+                let (mx, my) = mouse_position();
+                let (gx, gy) = view.screen_to_cell(mx, my);
+                if let Some(tracked) = Tracked::at(sim, gx, gy) {
+                    self.camera.start(Follow::Object(tracked), view, screen_width(), screen_height());
+                }
+            }
+        } else if mouse_edit && controls.edit && (is_mouse_button_pressed(MouseButton::Left) || is_mouse_button_down(MouseButton::Left)) {
+            if self.pause_on_edit && running && is_mouse_button_pressed(MouseButton::Left) {
+                // Cells drawn on a fast-running board would be gone before they were seen
+                self.paused = true;
+                notice = Some("Paused for editing: Space resumes".to_string());
+            }
+            let (mx, my) = mouse_position(); // Get mouse coordinates
+            let (gx, gy) = view.screen_to_cell(mx, my); // Convert to grid coordinates
+            sim.toggle_cell(gx, gy); // Toggle cell at mouse position
+        }
+        if restart {
+            self.restarted();
+        }
+        manual_speed.and_then(|speed| self.set_speed(speed)).map(str::to_string).or(notice)
+    }
+
+    /// Move the follow camera; returns a notice if it just stopped following on its own
+    fn follow(&mut self, dt: f32) -> Option<&'static str> {
+        let lost = self.camera.update(&self.sim, &mut self.view, dt, screen_width(), screen_height())?;
+        Some(lost.describe())
+    }
+
+    /// Advance the simulation by the elapsed frame time
+    fn update(&mut self, dt: f32) {
+        self.obey();
+        if let Some(heat) = self.heat.as_mut() {
+            // Resized boards start a fresh field
+            if !heat.fits(&self.sim.grid) {
+                *heat = HeatField::new(&self.sim.grid);
+            }
+            heat.cool(dt);
+        }
+        if let Some(particles) = self.particles.as_mut() {
+            particles.update(dt);
+        }
+        if let Some(schedule) = &self.schedule {
+            self.speed = schedule.speed_at(self.sim.generation);
+        }
+        if !self.clock.runs_at(self.speed) {
+            self.clock.set_speed(self.speed);
+        }
+        if self.paused {
+            // A result landing after the pause would move the board on
+            self.pending = None;
+            self.sim.record_edits(false);
+            self.meter.reset();
+            if let Some(preview) = self.next_preview.as_mut() {
+                preview.refresh(&self.sim.grid, &self.sim.live, self.sim.generation);
+            }
+            return;
+        }
+        // The heat overlay and the particles are fed by each generation's births and deaths
+        self.sim.track_diff = self.smoothing() || self.heat.is_some() || self.particles.is_some() || self.remote.is_some();
+        // A long hitch (window drag, save) must not turn into a burst of catch-up generations
+        self.clock.advance(SimClock::ticks_in(dt.min(MAX_CATCHUP_SECONDS)));
+        if self.offload() {
+            // Cells set by hand since the last frame go to the job running, if any; the ones
+            // made before it started are on the board it was given
+            self.sim.record_edits(true);
+            let edits = self.sim.take_edits();
+            if let (Some(stepper), Some(_)) = (self.stepper.as_mut(), &self.pending) {
+                for edit in edits {
+                    stepper.edit(edit);
+                }
+            }
+            let steps = self.collect_stepped();
+            if self.pending.is_none() && self.clock.due() > 0 {
+                // Everything due goes in one job; a backlog beyond the cap is dropped as below
+                let due = self.take_due();
+                let sim = &self.sim;
+                if let Some(stepper) = self.stepper.as_mut() {
+                    let id = stepper.submit(&sim.grid, sim.live.clone(), due);
+                    self.pending = Some(Pending {
+                        id,
+                        generation: sim.generation,
+                        steps: due,
+                        width: sim.grid.width,
+                        height: sim.grid.height,
+                        topology: sim.grid.topology,
+                        rule: sim.grid.rule,
+                    });
+                }
+            }
+            self.meter.record(get_time(), steps, self.speed);
+            return;
+        }
+        // Switching modes mid-job: finish on this thread instead
+        self.pending = None;
+        self.sim.record_edits(false);
+        let (sim, script, heat, particles, remote, feed, speed) = (&mut self.sim, &mut self.script, &mut self.heat, &mut self.particles, &self.remote, &mut self.feed, self.speed);
+        // Stop stepping once the frame's time budget is spent so the window keeps responding
+        let scale = Limit::step_scale(self.pacing);
+        let steps = self.clock.run_due((MAX_STEPS_PER_FRAME as f64 * scale) as u64, STEP_BUDGET_SECONDS * scale, get_time, || {
+            sim.next_generation();
+            warm(heat, particles, sim);
+            if let Some(endpoint) = remote {
+                endpoint.publish(sim);
+            }
+            if let Some(feed) = feed.as_mut() {
+                feed.publish(sim);
+            }
+            if let Some(hook) = script.as_mut() {
+                hook.after_generation(sim, speed);
+            }
+        });
+        self.meter.record(get_time(), steps as u32, self.speed);
+        self.take_script_speed();
+    }
+
+    /// Steps due this frame, capped at `MAX_STEPS_PER_FRAME` scaled to the frame pacing. Too slow
+    /// to keep up: the backlog beyond the cap is dropped instead of freezing the frame.
+    fn take_due(&mut self) -> u32 {
+        let due = self.clock.due();
+        let steps = due.min((MAX_STEPS_PER_FRAME as f64 * Limit::step_scale(self.pacing)) as u64);
+        self.clock.take(steps);
+        if due > steps {
+            self.clock.skip();
+        }
+        steps as u32
+    }
+
+    /// What the overlay layers draw from
+    fn frame(&self) -> Frame<'_> {
+        Frame {
+            sim: &self.sim,
+            view: &self.view,
+            paused: self.paused,
+            speed: self.speed,
+            time: self.clock.seconds(),
+            follow: self.camera.follow.label(),
+            schedule: self.schedule.as_ref(),
+            growth: self.growth.trend(),
+            pacing: self.pacing,
+            diff: self.compare.as_ref().map(|c| c.diff.counts()),
+            memory: None,
+        }
+    }
+
+    /// Draw the board and paused-only inspector; the HUD is a layer drawn over it
+    fn draw(&self) {
+        // Fraction of the way to the next generation; purely cosmetic
+        let t = if self.smoothing() { self.clock.fraction() } else { 1.0 };
+        match (&self.compare, &self.heat) {
+            (Some(compare), _) => render::render_diff(&self.sim.grid, &self.view, &compare.diff),
+            (None, Some(heat)) => render::render_heated(&self.sim, &self.view, t, heat, self.heat_intensity),
+            (None, None) => render::render(&self.sim, &self.view, t),
+        }
+        if let (None, Some(particles)) = (&self.compare, &self.particles) {
+            render::render_particles(particles, &self.view, self.particle_intensity);
+        }
+        if self.ghost_border {
+            render::render_ghost_border(&self.sim, &self.view);
+        }
+        if self.sim.editing() {
+            let colors = self.view.theme.colors();
+            let board = self.view.board_size(&self.sim.grid);
+            draw_rectangle_lines(self.view.offset.x - 4.0, self.view.offset.y - 4.0, board.x + 8.0, board.y + 8.0, 2.0, colors.text_secondary);
+            draw_text("EDIT MODE | Enter/Space:Start | N:Start and step", 10.0, screen_height() - 34.0, 22.0, colors.text);
+        }
+        if let (true, Some(preview)) = (self.paused, &self.next_preview) {
+            render::render_next_preview(&self.view, &preview.changes);
+        }
+        if self.paused {
+            // Rule inspector for the hovered cell (hidden while running)
+            let (mx, my) = mouse_position();
+            let (gx, gy) = self.view.screen_to_cell(mx, my);
+            render::render_inspector(&self.sim, &self.view, gx, gy);
+        }
+        if let Some(achieved) = self.meter.lagging(get_time()) {
+            let colors = self.view.theme.colors();
+            let text = format!(
+                "Running at {:.0}/{:.0} gen/s | L: lower target | P: pause",
+                achieved, self.speed,
+            );
+            let dims = measure_text(&text, None, 26, 1.0);
+            let x = (screen_width() - dims.width) / 2.0;
+            let mut bg = colors.background;
+            bg.a = 0.9;
+            draw_rectangle(x - 10.0, 64.0, dims.width + 20.0, 36.0, bg);
+            draw_rectangle_lines(x - 10.0, 64.0, dims.width + 20.0, 36.0, 2.0, colors.border);
+            draw_text(&text, x, 90.0, 26.0, colors.border);
+        }
+    }
+}
+
+/// Draw census results in a panel at the top-right of the screen
+fn draw_census(census: &Census, generation: u64, colors: &ThemeColors) {
+    let mut lines = vec![format!("Census @ gen {}: {} objects", generation, census.total_objects())];
+    lines.extend(census.counts.iter().map(|(name, n)| format!("{:>5}  {}", n, name)));
+    if !census.unidentified.is_empty() {
+        let largest = census.unidentified[0];
+        lines.push(format!("{:>5}  unidentified (largest {})", census.unidentified.len(), largest));
+    }
+    lines.push("K to close".to_string());
+
+    let w = 300.0;
+    let h = lines.len() as f32 * 20.0 + 12.0;
+    let x = screen_width() - w - 10.0;
+    let y = 60.0;
+    let mut bg = colors.background;
+    bg.a = 0.9;
+    draw_rectangle(x, y, w, h, bg);
+    draw_rectangle_lines(x, y, w, h, 1.0, colors.border);
+    for (i, line) in lines.iter().enumerate() {
+        draw_text(line, x + 8.0, y + 22.0 + i as f32 * 20.0, 20.0, colors.text);
+    }
+}
+
+/// Bottom-right panel with a bar per age bucket and the stable/churning split
+fn draw_age_histogram(h: &AgeHistogram, colors: &ThemeColors) {
+    const ROW: f32 = 20.0;
+    let (w, bar_w) = (260.0, 150.0);
+    let rows = h.counts.len() as f32 + 2.0;
+    let height = rows * ROW + 16.0;
+    let x = screen_width() - w - 10.0;
+    let y = screen_height() - height - 50.0;
+    let mut bg = colors.background;
+    bg.a = 0.9;
+    draw_rectangle(x, y, w, height, bg);
+    draw_rectangle_lines(x, y, w, height, 1.0, colors.border);
+    draw_text(&format!("Cell ages ({} cells)", h.total), x + 8.0, y + 20.0, 20.0, colors.text);
+
+    let largest = h.counts.iter().copied().max().unwrap_or(0).max(1) as f32;
+    for (i, &n) in h.counts.iter().enumerate() {
+        let row_y = y + 8.0 + (i as f32 + 1.0) * ROW;
+        draw_text(&AgeHistogram::label(i), x + 8.0, row_y + 14.0, 18.0, colors.text_secondary);
+        draw_rectangle(x + 60.0, row_y + 3.0, bar_w * n as f32 / largest, ROW - 6.0, colors.cell);
+        draw_text(&n.to_string(), x + 66.0 + bar_w, row_y + 14.0, 18.0, colors.text_secondary);
+    }
+    let stable = if h.total == 0 { 0.0 } else { 100.0 * h.stable as f32 / h.total as f32 };
+    let text = format!("stable (age > {}): {:.0}% | churning: {:.0}%", AGE_STABLE, stable, 100.0 - stable);
+    draw_text(&text, x + 8.0, y + 8.0 + (rows - 1.0) * ROW + 14.0, 18.0, colors.text);
+}
+
+/// Time-lapse overlay state: the first visible row and the thumbnail shown full-screen, if any
+#[derive(Default)]
+struct GalleryView {
+    scroll: usize,
+    full: Option<usize>,
+}
+
+const GALLERY_PAD: f32 = 12.0;   // Space around each thumbnail
+const GALLERY_LABEL: f32 = 20.0; // Height of the generation label under a thumbnail
+const GALLERY_TOP: f32 = 100.0;  // Room for the HUD and the overlay's title
+
+/// Thumbnails per row at the current window width
+fn gallery_columns() -> usize {
+    (((screen_width() - GALLERY_PAD) / (THUMB_SIZE.0 as f32 + GALLERY_PAD)) as usize).max(1)
+}
+
+/// Index and screen rectangle (x, y, w, h) of each thumbnail visible with `scroll` rows hidden above
+fn gallery_tiles(count: usize, scroll: usize) -> Vec<(usize, f32, f32, f32, f32)> {
+    let (w, h) = (THUMB_SIZE.0 as f32, THUMB_SIZE.1 as f32);
+    let cols = gallery_columns();
+    let row_h = h + GALLERY_LABEL + GALLERY_PAD;
+    let rows = (((screen_height() - GALLERY_TOP) / row_h) as usize).max(1);
+    (scroll * cols..count.min((scroll + rows) * cols))
+        .map(|i| {
+            let (col, row) = (i % cols, i / cols - scroll);
+            (i, GALLERY_PAD + col as f32 * (w + GALLERY_PAD), GALLERY_TOP + row as f32 * row_h, w, h)
+        })
+        .collect()
+}
+
+/// Draw the time-lapse overlay: a grid of thumbnails, or one of them filling the window
+fn draw_gallery(gallery: &mut Gallery, gv: &GalleryView, recorder: Option<&DeltaLog>, colors: &ThemeColors) {
+    let mut bg = colors.background;
+    bg.a = 0.95;
+    // The HUD stays visible above the gallery
+    draw_rectangle(0.0, 56.0, screen_width(), screen_height() - 56.0, bg);
+
+    if let Some(i) = gv.full {
+        let shot = &mut gallery.shots[i];
+        let generation = shot.generation;
+        let texture = shot.texture();
+        let (tw, th) = (texture.width(), texture.height());
+        let scale = (screen_width() / tw).min((screen_height() - GALLERY_TOP) / th);
+        let (w, h) = (tw * scale, th * scale);
+        draw_texture_ex(texture, (screen_width() - w) / 2.0, GALLERY_TOP + (screen_height() - GALLERY_TOP - h) / 2.0, WHITE, DrawTextureParams {
+            dest_size: Some(vec2(w, h)),
+            ..Default::default()
+        });
+        let text = format!("Generation {} (not in the delta recording, so it can't be jumped to) | Click or Esc: back", generation);
+        draw_text(&text, 10.0, 80.0, 20.0, colors.text);
+        return;
+    }
+
+    let title = format!(
+        "Time-lapse: {} thumbnails ({}) | Click: jump back if recorded (M), else enlarge | Wheel: scroll | Esc: close",
+        gallery.shots.len(), MemoryUsage::format(gallery.bytes()),
+    );
+    draw_text(&title, 10.0, 80.0, 20.0, colors.text);
+    let (mx, my) = mouse_position();
+    for (i, x, y, w, h) in gallery_tiles(gallery.shots.len(), gv.scroll) {
+        let shot = &mut gallery.shots[i];
+        let generation = shot.generation;
+        draw_texture(shot.texture(), x, y, WHITE);
+        let hovered = mx >= x && mx < x + w && my >= y && my < y + h;
+        draw_rectangle_lines(x - 2.0, y - 2.0, w + 4.0, h + 4.0, if hovered { 3.0 } else { 1.0 }, colors.border);
+        let recorded = recorder.is_some_and(|log| log.contains(generation));
+        let label = if recorded { format!("gen {} (recorded)", generation) } else { format!("gen {}", generation) };
+        draw_text(&label, x, y + h + 16.0, 18.0, if recorded { colors.text } else { colors.text_secondary });
+    }
+}
+
+const BOOKMARK_ROW: f32 = 72.0; // Height of a bookmark list row, thumbnail included
+
+/// Index and top edge of each bookmark row that fits on screen, keeping `selected` visible
+fn bookmark_rows(count: usize, selected: usize) -> Vec<(usize, f32)> {
+    let rows = (((screen_height() - GALLERY_TOP) / BOOKMARK_ROW) as usize).max(1);
+    let first = (selected + 1).saturating_sub(rows);
+    (first..count.min(first + rows)).map(|i| (i, GALLERY_TOP + (i - first) as f32 * BOOKMARK_ROW)).collect()
+}
+
+/// Draw the bookmark list: a thumbnail, label and generation per row, newest last
+fn draw_bookmarks(bookmarks: &mut Bookmarks, selected: usize, colors: &ThemeColors) {
+    let mut bg = colors.background;
+    bg.a = 0.95;
+    draw_rectangle(0.0, 56.0, screen_width(), screen_height() - 56.0, bg);
+    let title = format!(
+        "Bookmarks: {} of {} | Enter/Click: restore and branch | D: compare | Del: delete | Esc: close",
+        bookmarks.len(), BOOKMARK_MAX,
+    );
+    draw_text(&title, 10.0, 80.0, 20.0, colors.text);
+    let (tw, th) = (BOOKMARK_ROW * 4.0 / 3.0 - GALLERY_PAD, BOOKMARK_ROW - GALLERY_PAD);
+    for (i, y) in bookmark_rows(bookmarks.len(), selected) {
+        let entry = &mut bookmarks.entries[i];
+        let texture = entry.thumbnail.texture();
+        let scale = (tw / texture.width()).min(th / texture.height());
+        draw_texture_ex(texture, GALLERY_PAD, y, WHITE, DrawTextureParams {
+            dest_size: Some(vec2(texture.width() * scale, texture.height() * scale)),
+            ..Default::default()
+        });
+        let color = if i == selected { colors.text } else { colors.text_secondary };
+        if i == selected {
+            draw_rectangle_lines(GALLERY_PAD - 4.0, y - 4.0, screen_width() - 2.0 * GALLERY_PAD + 8.0, th + 8.0, 2.0, colors.border);
+        }
+        let bookmark = &entry.bookmark;
+        let x = GALLERY_PAD * 2.0 + tw;
+        draw_text(&bookmark.label, x, y + 22.0, 22.0, color);
+        let detail = format!("gen {} | {} cells | {}x{}{}", bookmark.generation, bookmark.live.len(), bookmark.width, bookmark.height, match bookmark.topology { Topology::Bounded => String::new(), t => format!(" {}", t) });
+        draw_text(&detail, x, y + 46.0, 18.0, colors.text_secondary);
+    }
+}
+
+/// Name the two sides of compare mode under the HUD, in their diff colors
+fn draw_compare_legend(compare: &Compare, generation: u64, colors: &ThemeColors) {
+    let reference = &compare.reference;
+    let (primary, secondary) = if compare.swapped { (reference.label.as_str(), "the board") } else { ("the board", reference.label.as_str()) };
+    let status = if compare.caught_up {
+        format!("Comparing at generation {}", reference.sim.generation)
+    } else {
+        format!("Running {} on to generation {}: at {}", reference.label, generation, reference.sim.generation)
+    };
+    let parts = [
+        (status, colors.text),
+        (format!("only in {}", primary), render::DIFF_ONLY_PRIMARY),
+        (format!("only in {}", secondary), render::DIFF_ONLY_SECONDARY),
+        ("in both".to_string(), colors.cell),
+        ("Shift+D: swap | Ctrl+D: close".to_string(), colors.text_secondary),
+    ];
+    let mut x = 10.0;
+    let y = HUD_HEIGHT + 32.0;
+    for (i, (text, color)) in parts.iter().enumerate() {
+        if (1..=3).contains(&i) {
+            draw_rectangle(x, y - 11.0, 12.0, 12.0, *color);
+            x += 16.0;
+        }
+        draw_text(text, x, y, 18.0, if i == 0 || i == 4 { *color } else { colors.text });
+        x += measure_text(text, None, 18, 1.0).width + 16.0;
+    }
+}
+
+/// Draw the notes list down the right of the window, so the board and its markers stay in view
+fn draw_notes(notes: &Annotations, selected: usize, view: &BoardView, grid: &Grid, colors: &ThemeColors) {
+    const ROW: f32 = 24.0;
+    let width = screen_width().min(380.0);
+    let (x, top) = (screen_width() - width, HUD_HEIGHT + 32.0);
+    let mut bg = colors.background;
+    bg.a = 0.95;
+    draw_rectangle(x, top, width, screen_height() - top, bg);
+    draw_text("Notes | Enter: move | C: color | Del: delete | Esc: close", x + 10.0, top + 22.0, 18.0, colors.text);
+    let rows = (((screen_height() - top - 36.0) / ROW) as usize).max(1);
+    let first = (selected + 1).saturating_sub(rows);
+    for (row, i) in (first..notes.len().min(first + rows)).enumerate() {
+        let y = top + 36.0 + row as f32 * ROW;
+        let note = &notes.notes[i];
+        let mut text = format!("{}  {}", view.format_cell(grid, note.at.x(), note.at.y()), note.text);
+        if text.chars().count() > 38 {
+            text = text.chars().take(35).chain("...".chars()).collect();
+        }
+        if i == selected {
+            draw_rectangle_lines(x + 4.0, y, width - 8.0, ROW, 1.0, colors.border);
+        }
+        draw_circle(x + 16.0, y + ROW / 2.0, 5.0, note.color.color());
+        draw_text(&text, x + 28.0, y + 17.0, 18.0, if i == selected { colors.text } else { colors.text_secondary });
+    }
+}
+
+/// Rule editor overlay: a toggle per neighbor count for birth and for survival, and a reset
+/// button. The cursor row is 0 for birth, 1 for survival and 2 for the button.
+struct RuleEditor {
+    draft: Rule, // As toggled; the board keeps its last runnable rule while this has B0
+    row: usize,
+    col: usize,
+}
+
+impl RuleEditor {
+    const X: f32 = 10.0;
+    const Y: f32 = 80.0;
+    const BOX: f32 = 32.0;
+    const GAP: f32 = 8.0;
+    const LABEL: f32 = 90.0; // Width of the row labels left of the toggles
+
+    fn new(rule: Rule) -> Self {
+        Self { draft: rule, row: 0, col: 3 }
+    }
+
+    /// Screen rectangle (x, y, w, h) of toggle `col` in `row`, or of the reset button in row 2
+    fn rect(row: usize, col: usize) -> (f32, f32, f32, f32) {
+        let y = Self::Y + 40.0 + row as f32 * (Self::BOX + Self::GAP);
+        if row == 2 {
+            return (Self::X + Self::LABEL, y, 160.0, Self::BOX);
+        }
+        (Self::X + Self::LABEL + col as f32 * (Self::BOX + Self::GAP), y, Self::BOX, Self::BOX)
+    }
+
+    /// Flip a toggle, or reset to Life for the button
+    fn press(&mut self, row: usize, col: usize) {
+        match row {
+            0 => self.draft.birth ^= 1 << col,
+            1 => self.draft.survive ^= 1 << col,
+            _ => self.draft = Rule::LIFE,
+        }
+    }
+
+    /// Consume this frame's input. Returns the new draft when a toggle or the reset changed it.
+    fn update(&mut self) -> Option<Rule> {
+        let before = self.draft;
+        if is_key_pressed(KeyCode::Up) { self.row = (self.row + 2) % 3; }
+        if is_key_pressed(KeyCode::Down) { self.row = (self.row + 1) % 3; }
+        if is_key_pressed(KeyCode::Left) { self.col = (self.col + 8) % 9; }
+        if is_key_pressed(KeyCode::Right) { self.col = (self.col + 1) % 9; }
+        if is_key_pressed(KeyCode::Space) || is_key_pressed(KeyCode::Enter) { self.press(self.row, self.col); }
+        if is_key_pressed(KeyCode::R) { self.draft = Rule::LIFE; }
+        if is_mouse_button_pressed(MouseButton::Left) {
+            let (mx, my) = mouse_position();
+            let hit = (0..2).flat_map(|row| (0..9).map(move |col| (row, col))).chain([(2, 0)]).find(|&(row, col)| {
+                let (x, y, w, h) = Self::rect(row, col);
+                mx >= x && mx < x + w && my >= y && my < y + h
+            });
+            if let Some((row, col)) = hit {
+                (self.row, self.col) = (row, if row == 2 { self.col } else { col });
+                self.press(row, col);
+            }
+        }
+        (self.draft != before).then_some(self.draft)
+    }
+
+    /// Draw the panel. `active` is the rule the board runs, which lags the draft while it has B0.
+    fn draw(&self, active: Rule, colors: &ThemeColors) {
+        let (w, h) = (Self::LABEL + 9.0 * (Self::BOX + Self::GAP) + 12.0, 200.0);
+        let mut bg = colors.background;
+        bg.a = 0.95;
+        draw_rectangle(Self::X - 4.0, Self::Y, w, h, bg);
+        draw_rectangle_lines(Self::X - 4.0, Self::Y, w, h, 1.0, colors.border);
+        draw_text(&format!("Rule: {}", self.draft), Self::X + 4.0, Self::Y + 26.0, 24.0, colors.text);
+        for (row, label, bits) in [(0, "Birth", self.draft.birth), (1, "Survival", self.draft.survive)] {
+            let (_, y, _, _) = Self::rect(row, 0);
+            draw_text(label, Self::X + 4.0, y + 22.0, 20.0, colors.text);
+            for col in 0..9 {
+                let (x, y, bw, bh) = Self::rect(row, col);
+                let on = bits & (1 << col) != 0;
+                if on {
+                    // B0 is the one toggle the engine can't run
+                    draw_rectangle(x, y, bw, bh, if row == 0 && col == 0 { ORANGE } else { colors.cell });
+                }
+                let cursor = (self.row, self.col) == (row, col);
+                draw_rectangle_lines(x, y, bw, bh, if cursor { 3.0 } else { 1.0 }, colors.border);
+                let digit = char::from(b'0' + col as u8).to_string();
+                draw_text(&digit, x + 10.0, y + 23.0, 22.0, if on { colors.background } else { colors.text_secondary });
+            }
+        }
+        let (x, y, bw, bh) = Self::rect(2, 0);
+        draw_rectangle_lines(x, y, bw, bh, if self.row == 2 { 3.0 } else { 1.0 }, colors.border);
+        draw_text("Reset to B3/S23", x + 10.0, y + 22.0, 20.0, colors.text);
+        let (note, color) = if self.draft.strobes() {
+            (format!("B0 strobes empty space and isn't supported: still running {}", active), ORANGE)
+        } else {
+            ("Arrows: move | Space/Enter/click: toggle | R: reset | Esc: close".to_string(), colors.text_secondary)
+        };
+        draw_text(&note, Self::X + 4.0, Self::Y + h - 10.0, 18.0, color);
+    }
+}
+
+/// First row of mutation tiles shown, so the selected one stays on screen
+fn mutation_scroll(selected: usize) -> usize {
+    let row_h = THUMB_SIZE.1 as f32 + GALLERY_LABEL + GALLERY_PAD;
+    let rows = (((screen_height() - GALLERY_TOP) / row_h) as usize).max(1);
+    (selected / gallery_columns() + 1).saturating_sub(rows)
+}
+
+/// Draw the mutation explorer: a thumbnail of each mutation's run, labelled with what it
+/// changes and the cells left, under the base rule and seed it was run from
+fn draw_mutations(explorer: &mut Explorer, selected: usize, colors: &ThemeColors) {
+    let mut bg = colors.background;
+    bg.a = 0.95;
+    draw_rectangle(0.0, 56.0, screen_width(), screen_height() - 56.0, bg);
+    let title = format!(
+        "Mutations of {} | seed {} | {}x{} soup at {}, {} gens: {} of {} run | {} | Arrows: move | Click/Enter: adopt | Esc: close",
+        explorer.base, explorer.seed, MUTATION_SIZE.0, MUTATION_SIZE.1, explorer.density, MUTATION_GENERATIONS,
+        explorer.done(), explorer.rules.len(), explorer.rules[selected],
+    );
+    draw_text(&title, 10.0, 80.0, 18.0, colors.text);
+    let (mx, my) = mouse_position();
+    for (i, x, y, w, h) in gallery_tiles(explorer.rules.len(), mutation_scroll(selected)) {
+        let change = mutation::change(explorer.base, explorer.rules[i]);
+        let label = match explorer.trials[i].as_mut() {
+            Some(trial) => {
+                draw_texture(trial.thumbnail.texture(), x, y, WHITE);
+                format!("{}: {} cells", change, trial.population)
+            }
+            None => format!("{}: running", change),
+        };
+        let hovered = mx >= x && mx < x + w && my >= y && my < y + h;
+        draw_rectangle_lines(x - 2.0, y - 2.0, w + 4.0, h + 4.0, if i == selected || hovered { 3.0 } else { 1.0 }, colors.border);
+        draw_text(&label, x, y + h + 16.0, 18.0, if i == selected { colors.text } else { colors.text_secondary });
+    }
+}
+
+/// What the theme picker asks of the run after this frame's input
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ThemeEvent {
+    Close(ColorTheme), // The theme to keep: the chosen one, or the original after Escape
+    Save(Palette),     // Add the random palette to the themes file
+}
+
+/// Theme picker overlay: each theme's swatches as seen with normal vision and simulated
+/// protanopia and deuteranopia, with a warning on themes whose cells are hard to make out.
+/// The highlighted theme is previewed on the board; the last row rolls random palettes.
+pub struct ThemePicker {
+    original: ColorTheme, // Put back on Escape
+    custom: Vec<(String, Palette)>,
+    random: Option<Palette>, // Last palette rolled on the Randomize row
+    rng: Rng,
+    row: usize,
+    top: usize, // First row shown
+    pub event: Option<ThemeEvent>,
+}
+
+impl ThemePicker {
+    const X: f32 = 10.0;
+    const Y: f32 = 80.0;
+    const ROW: f32 = 64.0;
+    const ROWS: usize = 8; // Rows shown at once; the list scrolls to keep the highlight in view
+    const LABEL: f32 = 170.0; // Width of the name and warning column
+    const SWATCH: (f32, f32) = (120.0, 52.0);
+    const GAP: f32 = 10.0;
+
+    pub fn new(current: ColorTheme, custom: Vec<(String, Palette)>, seed: u64) -> Self {
+        let mut picker = Self { original: current, custom, random: None, rng: Rng::new(seed), row: 0, top: 0, event: None };
+        let row = (0..picker.len()).find(|&row| picker.theme_at(row) == Some(current));
+        match (row, current) {
+            (Some(row), _) => picker.select(row),
+            (None, ColorTheme::Custom(palette)) => {
+                picker.random = Some(palette);
+                picker.select(picker.len() - 1);
+            }
+            (None, _) => {}
+        }
+        picker
+    }
+
+    /// Built-in themes, then custom ones, then the Randomize row
+    fn len(&self) -> usize {
+        ColorTheme::ALL.len() + self.custom.len() + 1
+    }
+
+    fn label(&self, row: usize) -> &str {
+        match row.checked_sub(ColorTheme::ALL.len()) {
+            None => ColorTheme::ALL[row].name(),
+            Some(i) if i < self.custom.len() => &self.custom[i].0,
+            Some(_) => "Randomize",
+        }
+    }
+
+    fn theme_at(&self, row: usize) -> Option<ColorTheme> {
+        match row.checked_sub(ColorTheme::ALL.len()) {
+            None => Some(ColorTheme::ALL[row]),
+            Some(i) if i < self.custom.len() => Some(ColorTheme::Custom(self.custom[i].1)),
+            Some(_) => self.random.map(ColorTheme::Custom),
+        }
+    }
+
+    /// The theme to show on the board while the picker is open
+    pub fn preview(&self) -> ColorTheme {
+        self.theme_at(self.row).unwrap_or(self.original)
+    }
+
+    /// Highlight a row, rolling a palette the first time the Randomize row is reached
+    fn select(&mut self, row: usize) {
+        self.row = row;
+        if row == self.len() - 1 && self.random.is_none() {
+            self.random = Some(Palette::random(&mut self.rng));
+        }
+        self.top = self.top.clamp(row.saturating_sub(Self::ROWS - 1), row);
+    }
+
+    /// Take a new list of custom themes after a save, highlighting the saved palette
+    pub fn saved(&mut self, custom: Vec<(String, Palette)>) {
+        self.custom = custom;
+        let saved = self.random.and_then(|p| self.custom.iter().position(|(_, c)| *c == p));
+        self.select(saved.map_or(self.len() - 1, |i| ColorTheme::ALL.len() + i));
+    }
+
+    fn row_y(&self, row: usize) -> f32 {
+        Self::Y + 56.0 + (row - self.top) as f32 * Self::ROW
+    }
+
+    /// A tiny board in `colors`: grid lines, a glider and a stretch of border
+    fn draw_swatch(x: f32, y: f32, colors: &ThemeColors) {
+        let (w, h) = Self::SWATCH;
+        let cell = 8.0;
+        draw_rectangle(x, y, w, h, colors.background);
+        for i in 1..(w / cell) as i32 {
+            let line = if i % 5 == 0 { colors.grid_major } else { colors.grid };
+            draw_line(x + i as f32 * cell, y, x + i as f32 * cell, y + h, 1.0, line);
+        }
+        for i in 1..(h / cell) as i32 {
+            draw_line(x, y + i as f32 * cell, x + w, y + i as f32 * cell, 1.0, colors.grid);
+        }
+        for (cx, cy) in [(2, 1), (3, 2), (1, 3), (2, 3), (3, 3), (8, 2), (9, 2), (10, 2)] {
+            draw_rectangle(x + cx as f32 * cell, y + cy as f32 * cell, cell, cell, colors.cell);
+        }
+        draw_rectangle_lines(x, y, w, h, 2.0, colors.border);
+    }
+}
+
+impl Overlay for ThemePicker {
+    fn layer(&self) -> Layer {
+        Layer::Panel
+    }
+
+    /// Every key and click stays with the picker while it is open
+    fn handle_input(&mut self, input: &dyn Input) -> InputConsumed {
+        let (n, randomize) = (self.len(), self.len() - 1);
+        if input.key_pressed(KeyCode::Escape) || input.key_pressed(KeyCode::T) {
+            self.event = Some(ThemeEvent::Close(self.original));
+        } else if input.key_pressed(KeyCode::Enter) || input.key_pressed(KeyCode::Space) {
+            self.event = Some(ThemeEvent::Close(self.preview()));
+        } else if input.key_pressed(KeyCode::Up) {
+            self.select((self.row + n - 1) % n);
+        } else if input.key_pressed(KeyCode::Down) {
+            self.select((self.row + 1) % n);
+        } else if input.key_pressed(KeyCode::R) {
+            self.random = Some(Palette::random(&mut self.rng));
+            self.select(randomize);
+        } else if input.key_pressed(KeyCode::S) && self.row == randomize && let Some(palette) = self.random {
+            self.event = Some(ThemeEvent::Save(palette));
+        } else if input.mouse_pressed(MouseButton::Left) {
+            let (mx, my) = input.mouse_position();
+            let width = Self::LABEL + 3.0 * (Self::SWATCH.0 + Self::GAP);
+            let hit = (self.top..n.min(self.top + Self::ROWS))
+                .find(|&row| mx >= Self::X && mx < Self::X + width && my >= self.row_y(row) && my < self.row_y(row) + Self::ROW);
+            if let Some(row) = hit {
+                self.select(row);
+                self.event = Some(ThemeEvent::Close(self.preview()));
+            }
+        }
+        InputConsumed::Yes
+    }
+
+    fn draw(&self, frame: &Frame) {
+        let colors = frame.view.theme.colors();
+        let shown = self.len().min(Self::ROWS);
+        let (w, h) = (Self::LABEL + 3.0 * (Self::SWATCH.0 + Self::GAP) + 8.0, 56.0 + shown as f32 * Self::ROW + 30.0);
+        let mut bg = colors.background;
+        bg.a = 0.95;
+        draw_rectangle(Self::X - 4.0, Self::Y, w, h, bg);
+        draw_rectangle_lines(Self::X - 4.0, Self::Y, w, h, 1.0, colors.border);
+        draw_text("Theme", Self::X + 4.0, Self::Y + 26.0, 24.0, colors.text);
+        for (i, vision) in Vision::ALL.into_iter().enumerate() {
+            let x = Self::X + Self::LABEL + i as f32 * (Self::SWATCH.0 + Self::GAP);
+            draw_text(vision.name(), x, Self::Y + 48.0, 18.0, colors.text_secondary);
+        }
+        for row in self.top..self.top + shown {
+            let y = self.row_y(row);
+            if row == self.row {
+                draw_rectangle_lines(Self::X - 2.0, y - 4.0, w - 4.0, Self::ROW - 4.0, 2.0, colors.text);
+            }
+            draw_text(self.label(row), Self::X + 8.0, y + 22.0, 22.0, colors.text);
+            let Some(theme) = self.theme_at(row) else { continue };
+            if let Some(contrast) = theme.low_contrast() {
+                // Warning triangle with the worst cell/background contrast under it
+                let (tx, ty) = (Self::X + 10.0, y + 32.0);
+                draw_triangle(vec2(tx + 9.0, ty), vec2(tx, ty + 16.0), vec2(tx + 18.0, ty + 16.0), ORANGE);
+                draw_text("!", tx + 6.5, ty + 15.0, 16.0, BLACK);
+                draw_text(&format!("contrast {:.1}:1", contrast), tx + 24.0, ty + 13.0, 16.0, ORANGE);
+            } else if row == self.len() - 1 {
+                draw_text("R: roll | S: save", Self::X + 8.0, y + 44.0, 16.0, colors.text_secondary);
+            }
+            for (i, vision) in Vision::ALL.into_iter().enumerate() {
+                let x = Self::X + Self::LABEL + i as f32 * (Self::SWATCH.0 + Self::GAP);
+                Self::draw_swatch(x, y, &vision.theme(&theme));
+            }
+        }
+        let note = format!("Up/Down: preview | Enter/click: use | Esc: revert | warning below {}:1 cell contrast", THEME_MIN_CONTRAST);
+        draw_text(&note, Self::X + 4.0, Self::Y + h - 10.0, 18.0, colors.text_secondary);
+    }
+}
+
+/// Text prompts that can be opened over the main board
+#[derive(Clone, Copy)]
+enum Prompt {
+    GotoGeneration,
+    Seed,
+    BookmarkLabel, // Names the bookmark just captured
+    Note(Position), // Text of the note pinned to this cell; empty deletes it
+    SaveProject,    // File the active board is saved to as a project
+}
+
+/// List overlays that can be opened over the main board
+enum Picker {
+    Stamp(Vec<usize>), // Pattern indices in list order
+    History,           // Entries of the stamp history, newest first
+}
+
+/// Draw the console field with its scrollback, newest reply nearest the field
+fn draw_console(input: &TextInput, scrollback: &Scrollback, colors: &ThemeColors) {
+    const FONT: f32 = 20.0;
+    let width = (screen_width() - 20.0).min(720.0);
+    input.draw(10.0, 60.0, width, colors);
+    let mut bg = colors.background;
+    bg.a = 0.92;
+    let rows = scrollback.iter().count() as f32;
+    draw_rectangle(10.0, 124.0, width, rows * 22.0 + 8.0, bg);
+    for (i, (text, error)) in scrollback.iter().rev().enumerate() {
+        let color = if *error { RED } else { colors.text };
+        draw_text(text, 18.0, 144.0 + i as f32 * 22.0, FONT, color);
+    }
+}
+
+/// The active board as a project: with its recorded history if the recording still matches
+/// the board, with the run's bookmarks, and with the settings as they stand
+fn capture_project(session: &Session, recorder: Option<&DeltaLog>, bookmarks: &Bookmarks, hotbar: &Hotbar, name: &str) -> Project {
+    let sim = &session.sim;
+    // An edit since the last record breaks the history, leaving just the board
+    let mut log = recorder.cloned()
+        .and_then(|mut log| (log.record(sim) && log.reconstruct(sim.generation).as_ref() == Some(&sim.live)).then_some(log))
+        .unwrap_or_else(|| DeltaLog::new(sim));
+    log.bookmarks = bookmarks.to_vec();
+    log.schedule = session.schedule.clone();
+    log.annotations = session.notes.notes.clone();
+    let mut settings = Settings::load();
+    settings.hotbar = hotbar.clone();
+    Project::capture(sim, name, log, settings)
+}
+
+/// Read a file dropped on the window as a project, with a label naming it. On the web a drop
+/// carries the file's bytes instead of its path.
+fn open_dropped(file: DroppedFile) -> Result<(Project, String), String> {
+    let label = file.path.as_deref().and_then(Path::file_name).map_or("the dropped file".to_string(), |n| n.to_string_lossy().into_owned());
+    match (file.bytes, file.path) {
+        (_, Some(path)) if !project::is_project_file(&path) => {
+            Err(format!("{} isn't a project: drop a .{} or .gold file", label, project::EXTENSION))
+        }
+        (Some(bytes), _) => Project::from_bytes(&bytes).map(|p| (p, label)).map_err(String::from),
+        (None, Some(path)) => Project::open(&path).map(|p| (p, label)).map_err(String::from),
+        (None, None) => Err("The dropped file could not be read".to_string()),
+    }
+}
+
+/// Carry on a project just restored onto `session`'s board: its notes and speed schedule, and a
+/// run already under way if it was saved mid-run. Its bookmarks join the list and its hotbar
+/// replaces the current one for this run. Returns the history it recorded, to record onto.
+fn resume_project(session: &mut Session, project: &Project, bookmarks: &mut Bookmarks, hotbar: &mut Hotbar, toasts: &mut Toasts) -> Option<DeltaLog> {
+    let generation = session.sim.generation;
+    if generation > 0 {
+        // Reset goes back to the board as saved
+        session.begin();
+        session.sim.generation = generation;
+    }
+    session.notes.notes = project.log.annotations.clone();
+    if project.log.schedule.is_some() {
+        session.schedule = project.log.schedule.clone();
+    }
+    for bookmark in &project.log.bookmarks {
+        bookmarks.add(bookmark.clone(), session.view.theme);
+    }
+    if let Some(settings) = &project.settings {
+        *hotbar = settings.hotbar.clone();
+    }
+    let missing = project.missing();
+    if project.migrated {
+        toasts.push("Opened a plain .gold save: it has no rule, seed or settings, so the current ones stay");
+    } else if !missing.is_empty() {
+        toasts.push(format!("The project has no {}, so the current ones stay", missing.join(", ")));
+    }
+    (!project.log.deltas.is_empty()).then(|| project.log.clone())
+}
+
+/// A profile file's contents, or the defaults with a toast when neither the file nor its backup
+/// can be read. Loading the backup in place of a damaged file is toasted too.
+fn loaded_or_default<T: Default>(result: Result<Kept<T>, GolError>, toasts: &mut Toasts) -> T {
+    match result {
+        Ok(kept) => {
+            if let Some(notice) = kept.notice {
+                toasts.push(notice);
+            }
+            kept.value
+        }
+        Err(err) => {
+            toasts.push(format!("Could not read {}", err));
+            T::default()
+        }
+    }
+}
+
+/// Replace the custom themes with the file's current ones, moving a showing custom theme to its
+/// new colors. A file that can't be read or has a bad line leaves the themes as they were.
+fn reload_themes(custom_themes: &mut CustomThemes, view: &mut BoardView, toasts: &mut Toasts) {
+    match CustomThemes::reload() {
+        Ok(themes) => {
+            view.theme = themes.updated(custom_themes, view.theme);
+            toasts.push(format!("Reloaded the themes file ({} themes)", themes.entries.len()));
+            *custom_themes = themes;
+        }
+        Err(err) => toasts.push(format!("Kept the previous themes: {}", err)),
+    }
+}
+
+/// Change the saved settings, returning a notice when the file couldn't be written
+fn save_setting(change: impl FnOnce(&mut Settings)) -> Option<String> {
+    let mut settings = Settings::load();
+    change(&mut settings);
+    settings.save().err().map(|err| format!("Could not save settings: {}", err))
+}
+
+/// Put `armed`'s pattern and transform in hotbar slot `i` and save the hotbar. Returns what to
+/// tell the user.
+fn assign_slot(hotbar: &mut Hotbar, i: usize, armed: Stamp, registry: &PatternRegistry) -> String {
+    let Some(pattern) = registry.get_index(armed.pattern) else { return "That pattern is gone".to_string(); };
+    hotbar.assign(i, pattern.name(), armed.transform);
+    let saved = hotbar.clone();
+    save_setting(|s| s.hotbar = saved)
+        .unwrap_or_else(|| format!("{} ({}) on key {}", pattern.name(), armed.transform.name(), Hotbar::key_label(i)))
+}
+
+/// Draw the hotbar along the bottom right corner: each slot's thumbnail under its key, or the
+/// key that fills it when empty, and the name and category of the slot under the mouse.
+/// Thumbnails are drawn once per assignment and kept in `thumbs`.
+fn draw_hotbar(hotbar: &Hotbar, thumbs: &mut Vec<(Slot, Color, Texture2D)>, registry: &PatternRegistry, armed: Option<Stamp>, colors: &ThemeColors) {
+    thumbs.retain(|(slot, color, _)| *color == colors.cell && hotbar.slots.contains(&Some(slot.clone())));
+    let gap = 3.0;
+    let x0 = screen_width() - hotbar::SLOTS as f32 * (HOTBAR_SLOT + gap) - 7.0;
+    let y = screen_height() - HOTBAR_SLOT - 10.0;
+    let mut bg = colors.background;
+    bg.a = 0.85;
+    let (mx, my) = mouse_position();
+    if hotbar.slots.iter().all(Option::is_none) {
+        draw_text("Hotbar: Tab, highlight a pattern, Shift+digit", x0, y - 8.0, 18.0, colors.text_secondary);
+    }
+    for (i, slot) in hotbar.slots.iter().enumerate() {
+        let x = x0 + i as f32 * (HOTBAR_SLOT + gap);
+        draw_rectangle(x, y, HOTBAR_SLOT, HOTBAR_SLOT, bg);
+        let key = Hotbar::key_label(i).to_string();
+        let Some(slot) = slot else {
+            draw_rectangle_lines(x, y, HOTBAR_SLOT, HOTBAR_SLOT, 1.0, colors.grid);
+            draw_text(&key, x + 3.0, y + 12.0, 14.0, colors.text_secondary);
+            draw_text("Shift", x + 3.0, y + HOTBAR_SLOT - 5.0, 12.0, colors.text_secondary);
+            continue;
+        };
+        let texture = match thumbs.iter().position(|(s, _, _)| s == slot) {
+            Some(t) => &thumbs[t].2,
+            None => {
+                let texture = Texture2D::from_image(&hotbar::thumbnail(slot, registry, HOTBAR_THUMB, colors.cell));
+                texture.set_filter(FilterMode::Nearest);
+                thumbs.push((slot.clone(), colors.cell, texture));
+                &thumbs[thumbs.len() - 1].2
+            }
+        };
+        let inset = (HOTBAR_SLOT - HOTBAR_THUMB as f32) / 2.0;
+        draw_texture(texture, x + inset, y + inset, WHITE);
+        let is_armed = armed.is_some_and(|a| a.transform == slot.transform && registry.index_of(&slot.pattern) == Some(a.pattern));
+        draw_rectangle_lines(x, y, HOTBAR_SLOT, HOTBAR_SLOT, if is_armed { 2.0 } else { 1.0 }, if is_armed { colors.text } else { colors.border });
+        draw_text(&key, x + 3.0, y + 12.0, 14.0, colors.text);
+        if (x..x + HOTBAR_SLOT).contains(&mx) && (y..y + HOTBAR_SLOT).contains(&my) {
+            let category = registry.index_of(&slot.pattern).and_then(|p| registry.get_index(p)).map_or("not loaded", |p| p.category());
+            let label = format!("{}: {} ({}) | {}", key, slot.pattern, slot.transform.name(), category);
+            let width = measure_text(&label, None, 18, 1.0).width;
+            draw_text(&label, (screen_width() - width - 10.0).min(x), y - 8.0, 18.0, colors.text);
+        }
+    }
+}
+
+/// Follow a download: Escape cancels it, otherwise its result is collected once it ends.
+/// Either way the slot is emptied; errors come back ready to show.
+fn follow_fetch(fetch: &mut Option<Fetch>) -> Option<Result<LoadedPattern, String>> {
+    let job = fetch.as_ref()?;
+    if is_key_pressed(KeyCode::Escape) {
+        if let Some(job) = fetch.take() { job.cancel(); }
+        return Some(Err("Download cancelled".to_string()));
+    }
+    let result = job.poll()?;
+    let url = job.url.clone();
+    *fetch = None;
+    Some(result.map_err(|e| format!("Could not load {}: {}", url, e)))
+}
+
+/// A picture waiting to replace the board, its threshold and inversion adjustable until Enter
+struct ImagePreview {
+    name: String,
+    picture: Picture,
+    options: ImageOptions,
+    cells: HashSet<Position>, // Live cells at the current options, where Enter puts them
+}
+
+impl ImagePreview {
+    fn new(name: String, picture: Picture, options: ImageOptions, grid: &Grid) -> Self {
+        let cells = picture.place(grid.width, grid.height, options);
+        Self { name, picture, options, cells }
+    }
+
+    /// Change the options and recompute the cells
+    fn adjust(&mut self, grid: &Grid, change: impl FnOnce(&mut ImageOptions)) {
+        change(&mut self.options);
+        self.options.threshold = (self.options.threshold * 100.0).round().clamp(0.0, 100.0) / 100.0;
+        self.cells = self.picture.place(grid.width, grid.height, self.options);
+    }
+}
+
+/// The preview's cells over the board, with its settings and keys in a panel at the bottom
+fn draw_image_preview(preview: &ImagePreview, view: &BoardView, colors: &ThemeColors) {
+    render::render_ghost(view, &preview.cells);
+    let lines = [
+        format!(
+            "{} | {}x{} cells | threshold {:.2}{} | {} live",
+            preview.name, preview.picture.width, preview.picture.height, preview.options.threshold,
+            if preview.options.invert { " inverted" } else { "" }, preview.cells.len(),
+        ),
+        "Up/Down: Threshold | I: Invert | Enter: Replace the board | Esc: Cancel".to_string(),
+    ];
+    draw_bottom_panel(&lines, colors);
+}
+
+/// Two lines of text in a panel along the bottom of the screen: what is waiting, then the keys
+fn draw_bottom_panel(lines: &[String; 2], colors: &ThemeColors) {
+    let width = lines.iter().map(|l| measure_text(l, None, 20, 1.0).width).fold(0.0, f32::max) + 24.0;
+    let (x, y) = ((screen_width() - width) / 2.0, screen_height() - 80.0);
+    let mut bg = colors.background;
+    bg.a = 0.92;
+    draw_rectangle(x, y, width, 64.0, bg);
+    draw_rectangle_lines(x, y, width, 64.0, 2.0, colors.border);
+    for (i, line) in lines.iter().enumerate() {
+        draw_text(line, x + 12.0, y + 24.0 + i as f32 * 26.0, 20.0, if i == 0 { colors.text } else { colors.text_secondary });
+    }
+}
+
+/// The smart clear menu `C` opens: the highlighted row, and the age the last row keeps cells
+/// older than
+pub struct ClearMenu {
+    pub selected: usize,
+    pub age: u64,
+}
+
+impl ClearMenu {
+    pub const ROWS: usize = 5;
+
+    pub fn new() -> Self {
+        Self { selected: 0, age: AGE_STABLE }
+    }
+
+    /// The scope row `i` clears on this board, or why it can't be used here
+    pub fn scope(&self, i: usize, sim: &Simulation, selection: Option<Rect>) -> Result<ClearScope, &'static str> {
+        let selected = selection.ok_or("right-drag to select a region first");
+        match i {
+            0 => Ok(ClearScope::All),
+            1 => selected.map(ClearScope::Outside),
+            2 => selected.map(ClearScope::Inside),
+            3 => Ok(ClearScope::ExceptStillLifes),
+            _ if sim.ages.is_none() => Err("press H to track cell ages first"),
+            _ => Ok(ClearScope::ExceptOlderThan(self.age)),
+        }
+    }
+
+    fn label(&self, i: usize) -> String {
+        match i {
+            0 => "Clear all".to_string(),
+            1 => "Clear outside the selection".to_string(),
+            2 => "Clear inside the selection".to_string(),
+            3 => "Keep only still lifes".to_string(),
+            _ => format!("Keep cells older than {} generations (Left/Right)", self.age),
+        }
+    }
+}
+
+impl Default for ClearMenu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The smart clear menu along the bottom of the screen, with rows that can't be used on this
+/// board dimmed and the reason beside them
+fn draw_clear_menu(menu: &ClearMenu, sim: &Simulation, selection: Option<Rect>, colors: &ThemeColors) {
+    let mut lines = vec![("Clear | 1-5 or Up/Down and Enter: Choose | C: Clear all | Esc: Cancel".to_string(), colors.text)];
+    for i in 0..ClearMenu::ROWS {
+        let marker = if i == menu.selected { ">" } else { " " };
+        lines.push(match menu.scope(i, sim, selection) {
+            Ok(_) => (format!("{} {}  {}", marker, i + 1, menu.label(i)), colors.text),
+            Err(reason) => (format!("{} {}  {} ({})", marker, i + 1, menu.label(i), reason), colors.text_secondary),
+        });
+    }
+    let width = lines.iter().map(|(l, _)| measure_text(l, None, 20, 1.0).width).fold(0.0, f32::max) + 24.0;
+    let height = lines.len() as f32 * 24.0 + 14.0;
+    let (x, y) = ((screen_width() - width) / 2.0, screen_height() - height - 16.0);
+    let mut bg = colors.background;
+    bg.a = 0.92;
+    draw_rectangle(x, y, width, height, bg);
+    draw_rectangle_lines(x, y, width, height, 2.0, colors.border);
+    for (i, (line, color)) in lines.iter().enumerate() {
+        draw_text(line, x + 12.0, y + 24.0 + i as f32 * 24.0, 20.0, *color);
+    }
+}
+
+/// Place an armed stamp at (x, y), clearing its footprint first if it asks to
+fn place_stamp(sim: &mut Simulation, pattern: &dyn Pattern, armed: Stamp, x: i32, y: i32) -> Result<(), PatternTooLarge> {
+    if armed.clear {
+        sim.stamp_clean(pattern, armed.transform, armed.phase, x, y)
+    } else {
+        sim.stamp(pattern, armed.transform, armed.phase, x, y)
+    }
+}
+
+/// Centered panel with a spinner while a download runs
+fn draw_fetching(url: &str, colors: &ThemeColors) {
+    const DOTS: usize = 8;
+    let width = (screen_width() - 40.0).min(640.0);
+    let (x, y) = ((screen_width() - width) / 2.0, screen_height() / 2.0 - 40.0);
+    let mut bg = colors.background;
+    bg.a = 0.92;
+    draw_rectangle(x, y, width, 80.0, bg);
+    draw_rectangle_lines(x, y, width, 80.0, 2.0, colors.border);
+    let lead = (get_time() * 10.0) as usize % DOTS;
+    for i in 0..DOTS {
+        let angle = i as f32 / DOTS as f32 * std::f32::consts::TAU;
+        let mut dot = colors.text;
+        dot.a = 1.0 - ((lead + DOTS - i) % DOTS) as f32 / DOTS as f32;
+        draw_circle(x + 40.0 + 16.0 * angle.cos(), y + 40.0 + 16.0 * angle.sin(), 3.5, dot);
+    }
+    let shown: String = if url.chars().count() > 56 { url.chars().take(55).chain(std::iter::once('…')).collect() } else { url.to_string() };
+    draw_text(&format!("Fetching {}", shown), x + 76.0, y + 34.0, 20.0, colors.text);
+    draw_text("Esc: cancel", x + 76.0, y + 60.0, 18.0, colors.text_secondary);
+}
+
+/// Which groups of controls a run responds to. Escape always leaves the run.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Controls {
+    pub playback: bool, // Pause, step, speed, restart, re-roll and go to generation
+    pub edit: bool,     // Drawing, clearing, stamps, seeds, wrap, edge spawn, rules and the console
+    pub view: bool,     // Zoom, follow camera, theme, grid lines, ruler, ages and the census
+    pub tools: bool,    // Selection, sandbox, cropping, bookmarks, delta recording and the time-lapse gallery
+}
+
+impl Controls {
+    pub const ALL: Controls = Controls { playback: true, edit: true, view: true, tools: true };
+    /// Watch only: the board runs as configured and nothing changes it
+    pub const NONE: Controls = Controls { playback: false, edit: false, view: false, tools: false };
+}
+
+/// What a run starts from
+#[derive(Clone)]
+pub enum Start {
+    Choice(PatternChoice), // Placed where the pattern asks to start
+    Named(String),         // A registry pattern by name, ignoring case, placed the same way
+    Cells(Vec<Position>),  // Exactly these live cells; ones outside the grid are dropped
+}
+
+/// How `run_simulation_with` sets up a run. Start from `SimulationConfig::new` and change what
+/// differs; the rest matches the interactive app. Preferences such as grid lines still come
+/// from the settings file.
+pub struct SimulationConfig<'a> {
+    pub window: Option<(i32, i32)>, // Window size to request; None keeps the host's window as it is
+    pub grid: Option<(i32, i32)>,   // Grid size in cells; None fills the window
+    pub cell: Option<i32>,          // Cell size in pixels; None fits the grid to the window
+    pub topology: Topology,         // Which edges wrap, and how a torus is shifted
+    pub start: Start,
+    pub rule: Rule,
+    pub speed: f32,                 // Generations per second
+    pub seed: Option<u64>,          // Seed for random soups and edge spawning; None picks one from the clock
+    pub density: Option<f32>,       // Fill density for random soups; None keeps the one in the settings file
+    pub theme: ColorTheme,
+    pub paused: bool,               // Open paused in edit mode, as the app does, rather than running
+    pub hud: bool,                  // Status lines and the controls line
+    pub controls: Controls,
+    pub registry: Option<&'a mut PatternRegistry>, // Patterns for `Start::Named` and the stamp picker; the built-ins if None
+    pub script: Option<PathBuf>, // Script hook file loaded when the run starts (the `scripting` feature)
+    pub remote: Option<Endpoint>, // Commands from another thread, applied between generations
+    pub serve: Option<String>,    // Address to mirror the board to other processes on (the `serve` feature)
+    pub project: Option<Project>, // Saved project to open in place of `start`
+}
+
+impl Default for SimulationConfig<'_> {
+    fn default() -> Self {
+        Self {
+            window: None,
+            grid: None,
+            cell: None,
+            topology: Topology::Bounded,
+            start: Start::Cells(Vec::new()),
+            rule: Rule::LIFE,
+            speed: SPEED_INIT,
+            seed: None,
+            density: None,
+            theme: ColorTheme::Classic,
+            paused: true,
+            hud: true,
+            controls: Controls::ALL,
+            registry: None,
+            script: None,
+            remote: None,
+            serve: None,
+            project: None,
+        }
+    }
+}
+
+impl<'a> SimulationConfig<'a> {
+    /// An empty board filling the current window, paused, with every control on
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn window(mut self, width: i32, height: i32) -> Self {
+        self.window = Some((width, height));
+        self
+    }
+
+    pub fn grid(mut self, width: i32, height: i32) -> Self {
+        self.grid = Some((width, height));
+        self
+    }
+
+    pub fn cell_size(mut self, cell: i32) -> Self {
+        self.cell = Some(cell);
+        self
+    }
+
+    /// Grid size, cell size and topology together, as the board menu picks them
+    pub fn board(mut self, board: BoardSize) -> Self {
+        self.grid = Some((board.width, board.height));
+        self.cell = Some(board.cell);
+        self.topology = board.topology;
+        self
+    }
+
+    pub fn topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    /// A torus when `wrap` is set, a bounded grid otherwise
+    pub fn wrap(self, wrap: bool) -> Self {
+        self.topology(Topology::from_wrap(wrap))
+    }
+
+    pub fn start(mut self, start: Start) -> Self {
+        self.start = start;
+        self
+    }
+
+    pub fn pattern(self, name: impl Into<String>) -> Self {
+        self.start(Start::Named(name.into()))
+    }
+
+    pub fn cells(self, cells: impl IntoIterator<Item = Position>) -> Self {
+        self.start(Start::Cells(cells.into_iter().collect()))
+    }
+
+    pub fn rule(mut self, rule: Rule) -> Self {
+        self.rule = rule;
+        self
+    }
+
+    pub fn speed(mut self, speed: f32) -> Self {
+        self.speed = speed.clamp(SPEED_MIN, SPEED_MAX);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn density(mut self, density: f32) -> Self {
+        self.density = Some(density);
+        self
+    }
+
+    /// Start the run a record came from again: its pattern, seed, density, grid and rule, with
+    /// the cell size fitted to the window
+    pub fn replay(self, run: &RunSetup) -> Self {
+        self.grid(run.width, run.height)
+            .topology(run.topology)
+            .rule(run.rule)
+            .seed(run.seed)
+            .density(run.density)
+            .pattern(run.pattern.clone())
+    }
+
+    /// Open a saved project: its grid and edges here, and its board, rule, notes, bookmarks,
+    /// schedule, hotbar and history once the run starts. The cell size is fitted to the window.
+    pub fn project(mut self, project: Project) -> Self {
+        let grid = project.log.grid();
+        self = self.grid(grid.width, grid.height).topology(grid.topology);
+        self.project = Some(project);
+        self
+    }
+
+    pub fn theme(mut self, theme: ColorTheme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub fn paused(mut self, paused: bool) -> Self {
+        self.paused = paused;
+        self
+    }
+
+    pub fn hud(mut self, hud: bool) -> Self {
+        self.hud = hud;
+        self
+    }
+
+    pub fn controls(mut self, controls: Controls) -> Self {
+        self.controls = controls;
+        self
+    }
+
+    pub fn registry(mut self, registry: &'a mut PatternRegistry) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    pub fn script(mut self, path: impl Into<PathBuf>) -> Self {
+        self.script = Some(path.into());
+        self
+    }
+
+    /// Take commands from the `Remote` paired with `endpoint` (see `remote::channel`)
+    pub fn remote(mut self, endpoint: Endpoint) -> Self {
+        self.remote = Some(endpoint);
+        self
+    }
+
+    /// Serve the board on `address`, e.g. `127.0.0.1:7878` (see `serve`). Clients' commands
+    /// take the place of a `remote` controller.
+    pub fn serve(mut self, address: impl Into<String>) -> Self {
+        self.serve = Some(address.into());
+        self
+    }
+
+    /// The board to build in a `screen_w` x `screen_h` window
+    pub fn board_size(&self, screen_w: i32, screen_h: i32) -> BoardSize {
+        let board = match (self.grid, self.cell) {
+            (Some((width, height)), Some(cell)) => BoardSize { width, height, cell, topology: Topology::Bounded },
+            (Some((width, height)), None) => BoardSize::fit_window(width, height, screen_w, screen_h),
+            (None, cell) => BoardSize::fill_window(screen_w, screen_h, cell.unwrap_or(CELL_SIZE).max(1)),
+        };
+        BoardSize { topology: self.topology, ..board }
+    }
+}
+
+/// The layers over a run's board, with an open panel on top; without the HUD only the ruler,
+/// toasts and panel are left
+fn run_layers<'a>(
+    ruler: &'a mut Ruler, hud: &'a mut Hud, toasts: &'a mut Toasts, help: &'a mut Help, panel: Option<&'a mut dyn Overlay>, hud_on: bool,
+) -> OverlayStack<'a> {
+    let mut layers: Vec<&'a mut dyn Overlay> = if hud_on { vec![ruler, hud, toasts, help] } else { vec![ruler, toasts] };
+    layers.extend(panel);
+    OverlayStack::new(layers)
+}
+
+/// Run main game simulation loop. `registry` supplies the stamp picker; patterns loaded from a
+/// URL during the run are appended to it.
+/// Returns a summary of the run once the user leaves.
+pub async fn run_simulation(
+    screen_w: i32,
+    screen_h: i32,
+    board: BoardSize,
+    registry: &mut PatternRegistry,
+    choice: &PatternChoice,
+) -> RunSummary {
+    // A torus picked in the menu is shifted as the settings file says
+    let torus = Settings::load().torus;
+    let config = SimulationConfig::new()
+        .window(screen_w, screen_h)
+        .board(BoardSize { topology: board.topology.with_shift_of(torus), ..board })
+        .start(Start::Choice(choice.clone()))
+        .registry(registry);
+    run_simulation_with(config).await
+}
+
+/// Run a simulation set up by `config` until the user presses Escape, then return its summary.
+/// Host apps call this from their own macroquad loop.
+pub async fn run_simulation_with(config: SimulationConfig<'_>) -> RunSummary {
+    if let Some((w, h)) = config.window {
+        request_new_screen_size(w as f32, h as f32);
+    }
+    let requested = config.window.map_or(vec2(screen_width(), screen_height()), |(w, h)| vec2(w as f32, h as f32));
+    let board = config.board_size(requested.x as i32, requested.y as i32);
+    let mut builtins;
+    let registry = match config.registry {
+        Some(registry) => registry,
+        None => {
+            builtins = default_registry();
+            &mut builtins
+        }
+    };
+
+    // The grid is independent of the window; it is letterboxed by `center_view`
+    let (grid_w, grid_h) = (board.width, board.height);
+    let mut sim = Simulation::new(grid_w, grid_h);
+    let mut view = BoardView::new(board.cell);
+    let mut window_fit = WindowFit::new(board.cell, requested);
+    let mut toasts = Toasts::new();
+    let settings = loaded_or_default(Settings::try_load(), &mut toasts);
+    let mut records = loaded_or_default(Records::try_load(), &mut toasts);
+    let mut custom_themes = loaded_or_default(CustomThemes::try_load(), &mut toasts);
+    view.grid_style = settings.grid;
+    view.coords = settings.coords;
+    view.ruler = settings.ruler;
+    view.theme = config.theme;
+    sim.density = config.density.unwrap_or(settings.density);
+    sim.edge_rate = settings.edge_rate;
+    sim.lethal_margin = settings.lethal_margin;
+    let memory_budget = settings.memory_budget_mb * 1024 * 1024;
+    sim.seed = config.seed.unwrap_or((miniquad::date::now() * 1000.0) as u64);
+    
+    sim.grid.topology = board.topology;
+    if let Err(err) = sim.set_rule(config.rule) {
+        toasts.push(err);
+    }
+
+    // Place the selected pattern where it asks to start
+    let mut choice = match config.start {
+        Start::Choice(choice) => Some(choice),
+        Start::Named(name) => {
+            let index = registry.iter().position(|p| p.name().eq_ignore_ascii_case(&name));
+            if index.is_none() {
+                toasts.push(format!("No pattern named {} (expected one of: {})", name, registry.names()));
+            }
+            index.map(PatternChoice::Registered)
+        }
+        Start::Cells(cells) => {
+            sim.live = cells.into_iter().filter_map(|p| sim.grid.resolve(p.x(), p.y())).collect();
+            None
+        }
+    };
+    // A project replaces whatever `start` put on the board
+    let project = config.project.filter(|project| match project.restore(&mut sim) {
+        Ok(()) => true,
+        Err(err) => {
+            toasts.push(format!("Could not open the project: {}", err));
+            false
+        }
+    });
+    let mut pattern_name = match &project {
+        Some(project) => project.name.clone().unwrap_or_else(|| "Saved board".to_string()),
+        None => choice.as_ref().map_or("Custom cells", |c| c.pattern(registry).name()).to_string(),
+    };
+    if project.is_some() {
+        choice = None;
+    }
+    if let Some(pattern) = choice.as_ref().map(|c| c.pattern(registry)) {
+        match pattern.bounds() {
+            Some((w, h)) if w > grid_w || h > grid_h => {
+                toasts.push(format!("{} is {}x{}, larger than the {}x{} grid", pattern.name(), w, h, grid_w, grid_h));
+            }
+            _ => {
+                if let Err(err) = sim.place(pattern) {
+                    toasts.push(err.to_string());
+                }
+            }
+        }
+    }
+
+    let mut stats = RunStats::default();
+    stats.observe(sim.generation, sim.live.len());
+    let mut growth_warned = false; // A quadratic trend is pointed out once until it ends
+    let mut beaten = (false, false); // Peak and most-generations records already announced
+    // The chosen pattern is only the starting point: the board opens in edit mode until Start
+    let mut main = Session::new(sim, view);
+    main.schedule = settings.schedule.clone();
+    main.speed = config.speed;
+    main.controls = config.controls;
+    main.remote = config.remote;
+    if let Some(address) = &config.serve {
+        match serve::start(address) {
+            Ok(serving) => {
+                toasts.push(format!("Serving the board on {}", serving.address));
+                main.remote = Some(serving.endpoint);
+                main.feed = Some(serving.feed);
+            }
+            Err(err) => toasts.push(err),
+        }
+    }
+    if let Some(path) = &config.script {
+        match ScriptHook::load(path, &mut main.sim, main.speed) {
+            Ok(hook) => main.script = Some(hook),
+            Err(err) => toasts.push(err),
+        }
+    }
+    main.paused = true;
+    if !config.paused {
+        main.begin();
+        main.paused = false;
+    }
+    let mut sandbox: Option<(Session, Rect)> = None; // Isolated copy of a region, if open
+    let mut selection: Option<Rect> = None;
+    let mut drag_start: Option<Position> = None;
+    let mut prompt: Option<(Prompt, TextInput)> = None;
+    let mut goto: Option<u64> = None; // Generation being fast-forwarded to
+    let mut census: Option<(Census, u64)> = None; // Last census and the generation it describes
+    let mut over_budget = false;   // Paused because the live set outgrew the memory budget
+    let mut budget_waived = false; // User chose to continue past the budget
+    let mut picker: Option<(Picker, ListPicker)> = None;
+    let mut stamp: Option<Stamp> = None; // Armed ghost stamp following the mouse
+    let mut nudge = (0, 0); // Arrow-key offset of the armed stamp from the mouse cell
+    let mut confirm: Option<(Stamp, i32, i32)> = None; // Overlapping placement waiting for a second click
+    let mut clip_prompt: Option<(Stamp, i32, i32, Overhang)> = None; // Placement past a wall waiting for a choice
+    let mut cleanup_confirm: Option<u64> = None; // Hash of the board a large cleanup waits on a second X for
+    let mut cleaned: Option<(u64, HashSet<Position>)> = None; // Board hash after the last cleanup or clear and the cells it removed
+    let mut clear_menu: Option<ClearMenu> = None; // Open smart clear menu
+    let mut aim: Option<(usize, Option<(i32, i32)>)> = None; // Upright heading of the armed pattern, by registry index
+    let mut history = StampHistory::new(STAMP_HISTORY_LEN);
+    let mut hotbar = settings.hotbar.clone(); // Quick-stamp slots, saved to the settings file as they change
+    let mut hotbar_thumbs = Vec::new();
+    let mut console: Option<TextInput> = None; // Open developer console
+    let mut fetch: Option<Fetch> = None; // Pattern download started from the console
+    let mut image_preview: Option<ImagePreview> = None; // Picture from the console's `image`, not yet seeded
+    let mut scrollback = Scrollback::new(CONSOLE_LINES);
+    let mut recorder: Option<DeltaLog> = None; // Births and deaths since the marked reference
+    let mut gallery = Gallery::new(settings.timelapse_every);
+    let mut gallery_view: Option<GalleryView> = None; // Open time-lapse overlay
+    let mut bookmarks = Bookmarks::default();
+    let mut bookmark_view: Option<usize> = None; // Selected row of the open bookmark list
+    let mut note_view: Option<usize> = None; // Selected row of the open notes list
+    let mut moving_note: Option<usize> = None; // Note waiting for a click on the cell it moves to
+    let mut rule_editor: Option<RuleEditor> = None;
+    let mut tabs = Tabs::new();
+    let mut clipboard: Option<usize> = None; // Registry index of the last selection copied, shared by every tab
+    let mut mutation_view: Option<(Explorer, usize)> = None; // Open mutation explorer and its selected tile
+    let mut theme_picker: Option<ThemePicker> = None;
+    let (mut ruler, mut hud, mut help) = (Ruler, Hud, Help::default());
+    let mut backdrops = Backdrops::new(settings.backdrops.clone(), settings.backdrop_opacity);
+    let mut themes_file = CustomThemes::path().map(Watched::new); // Reloaded when saved, with the `watch` feature
+    let mut pacer = Pacer::new(settings.fps_cap, settings.battery_saver);
+    let mut last_mouse = mouse_position();
+    let mut watched = (0, 0, 0); // Generation, population and hash of the board the saver last saw
+    let mut dropped = Vec::new(); // Files dropped on the window, opened once no overlay has the keyboard
+    if let Some(project) = &project {
+        recorder = resume_project(&mut main, project, &mut bookmarks, &mut hotbar, &mut toasts);
+    }
+
+    loop {
+        let dt = get_frame_time(); // Time since last frame
+        let frame_start = get_time();
+        // Any input, or a board that is still changing, keeps the saver from slowing frames down
+        dropped.extend(get_dropped_files());
+        let mouse = mouse_position();
+        let input = get_last_key_pressed().is_some() || !get_keys_down().is_empty() || mouse != last_mouse || mouse_wheel() != (0.0, 0.0)
+            || [MouseButton::Left, MouseButton::Right, MouseButton::Middle].iter().any(|&b| is_mouse_button_down(b));
+        last_mouse = mouse;
+        if input {
+            pacer.wake(frame_start);
+        }
+        let board = sandbox.as_ref().map_or(&main.sim, |(sb, _)| &sb.sim);
+        if pacer.saver && (board.generation, board.live.len()) != (watched.0, watched.1) {
+            let hash = board_hash(&board.live);
+            if hash != watched.2 {
+                pacer.wake(frame_start);
+            }
+            watched = (board.generation, board.live.len(), hash);
+        }
+        let limit = pacer.limit(frame_start, goto.is_some());
+        main.pacing = limit;
+        if let Some((sb, _)) = sandbox.as_mut() {
+            sb.pacing = limit;
+        }
+        // Whichever theme is showing, its background image is read once and then reused
+        main.view.backdrop = backdrops.get(main.view.theme);
+        if let Some((sb, _)) = sandbox.as_mut() {
+            sb.view.backdrop = backdrops.get(sb.view.theme);
+        }
+        for err in backdrops.take_errors() {
+            toasts.push(format!("Could not load the background image: {}", err));
+        }
+        // Layers over the board see input first; the rest of the frame only gets what they let through
+        let panel = theme_picker.as_mut().map(|p| p as &mut dyn Overlay);
+        let consumed = run_layers(&mut ruler, &mut hud, &mut toasts, &mut help, panel, config.hud).handle_input(&Window);
+        if let Some(picker) = theme_picker.as_mut() {
+            // The board shows the highlighted theme until Enter keeps it or Escape reverts
+            main.view.theme = picker.preview();
+            match picker.event.take() {
+                Some(ThemeEvent::Close(theme)) => {
+                    main.view.theme = theme;
+                    theme_picker = None;
+                }
+                Some(ThemeEvent::Save(palette)) => {
+                    let name = custom_themes.add(palette);
+                    match custom_themes.save() {
+                        Ok(()) => {
+                            // Our own save isn't an outside edit to reload
+                            if let Some(watched) = themes_file.as_mut() { watched.mark_seen(); }
+                            toasts.push(format!("Saved the palette as \"{}\"", name));
+                        }
+                        Err(err) => toasts.push(format!("Couldn't save the themes file: {}", err)),
+                    }
+                    picker.saved(custom_themes.entries.clone());
+                }
+                None => {}
+            }
+        }
+
+        if let Some((sb, region)) = sandbox.as_mut() {
+            // The main board stays frozen while the sandbox is open
+            if consumed == InputConsumed::No {
+                if let Some(notice) = sb.handle_input(true) { toasts.push(notice); }
+                if is_key_pressed(KeyCode::Enter) {
+                    main.sim.commit_region(*region, &sb.sim, SANDBOX_MARGIN);
+                    toasts.push("Sandbox copied back into the selection");
+                }
+                if is_key_pressed(KeyCode::Escape) {
+                    sandbox = None;
+                    next_frame().await;
+                    continue;
+                }
+            }
+            sb.update(dt);
+            if let Some(notice) = sb.follow(dt) {
+                toasts.push(notice);
+            }
+            sb.view.center_view(&sb.sim.grid, screen_width(), screen_height());
+            sb.draw();
+            draw_text("SANDBOX | Enter:Commit to selection | Esc:Return", 10.0, 70.0, 18.0, sb.view.theme.colors().text);
+            run_layers(&mut ruler, &mut hud, &mut toasts, &mut help, None, config.hud).draw(&sb.frame());
+            Pacer::sleep(Pacer::remaining(limit, frame_start, get_time()));
+            next_frame().await;
+            continue;
+        }
+
+        let mut usage = board_memory(&main, recorder.as_ref(), &gallery);
+        (usage.bookmarks, usage.tabs) = (bookmarks.bytes(), tabs.bytes());
+        if !over_budget && !budget_waived && usage.total() > memory_budget {
+            // Expendable data goes before the run has to stop
+            let (shed, after) = budget::enforce(memory_budget, usage, &mut main.sim, &mut gallery, recorder.as_mut());
+            if let Some(what) = shed.describe() {
+                toasts.push(format!("Over the {} memory budget: dropped {}", MemoryUsage::format(memory_budget), what));
+            }
+            usage = after;
+            if usage.total() > memory_budget {
+                main.paused = true;
+                goto = None;
+                over_budget = true;
+            }
+        }
+
+        if consumed == InputConsumed::Yes {
+            main.update(dt);
+        } else if over_budget {
+            // Modal: the board stays paused until the user picks a way out
+            if is_key_pressed(KeyCode::C) {
+                main.sim.clear();
+                over_budget = false;
+            } else if is_key_pressed(KeyCode::X) {
+                match selection {
+                    Some(region) => {
+                        // Checked again next frame
+                        main.sim.crop_to(region);
+                        over_budget = false;
+                    }
+                    None => toasts.push("Right-drag to select a region first"),
+                }
+            } else if is_key_pressed(KeyCode::Enter) {
+                budget_waived = true;
+                over_budget = false;
+            }
+            // Selecting the crop region still works in this state
+            let (mx, my) = mouse_position();
+            let (gx, gy) = main.view.screen_to_cell(mx, my);
+            if is_mouse_button_pressed(MouseButton::Right) {
+                drag_start = Some(Position::new(gx, gy));
+            }
+            if let Some(start) = drag_start {
+                selection = Rect::from_corners(start, Position::new(gx, gy)).intersect(&main.sim.grid.bounds());
+                if is_mouse_button_released(MouseButton::Right) { drag_start = None; }
+            }
+        } else if fetch.is_some() {
+            // The board keeps running while the download does; Escape cancels it
+            match follow_fetch(&mut fetch) {
+                Some(Ok(pattern)) => {
+                    // Stamped into a running board, the file's rule is offered rather than imposed
+                    let note = match pattern.foreign_rule(main.sim.grid.rule) {
+                        Some(Ok(rule)) => format!(" (its rule is {}; `rule {}` in the console switches to it)", rule, rule),
+                        Some(Err(e)) => format!(" ({}; it runs as {})", e, main.sim.grid.rule),
+                        None => String::new(),
+                    };
+                    toasts.push(format!("Loaded {}{}: click to place", pattern.name(), note));
+                    stamp = Some(Stamp::new(registry.append(Box::new(pattern))));
+                    nudge = (0, 0);
+                }
+                Some(Err(err)) => toasts.push(err),
+                None => {}
+            }
+            main.update(dt);
+        } else if let Some(preview) = image_preview.as_mut() {
+            let grid = main.sim.grid.clone();
+            if is_key_pressed(KeyCode::Escape) {
+                image_preview = None;
+            } else if is_key_pressed(KeyCode::Enter) {
+                let pattern = preview.picture.to_pattern(&preview.name, preview.options);
+                main.sim.clear();
+                match main.sim.apply_pattern(&pattern, grid.width / 2, grid.height / 2) {
+                    Ok(()) => toasts.push(format!("Seeded {} cells from {}", main.sim.live.len(), preview.name)),
+                    Err(err) => toasts.push(err.to_string()),
+                }
+                image_preview = None;
+            } else {
+                if is_key_pressed(KeyCode::Up) { preview.adjust(&grid, |o| o.threshold += IMAGE_THRESHOLD_STEP); }
+                if is_key_pressed(KeyCode::Down) { preview.adjust(&grid, |o| o.threshold -= IMAGE_THRESHOLD_STEP); }
+                if is_key_pressed(KeyCode::I) { preview.adjust(&grid, |o| o.invert = !o.invert); }
+            }
+            main.update(dt);
+        } else if let Some((armed, ax, ay, overhang)) = clip_prompt {
+            // The board keeps running while the choice is made
+            let choice = if is_key_pressed(KeyCode::C) {
+                Some(OnClip::Clip)
+            } else if is_key_pressed(KeyCode::E) {
+                Some(OnClip::Expand)
+            } else if is_key_pressed(KeyCode::Escape) {
+                Some(OnClip::Error)
+            } else {
+                None
+            };
+            if let Some(choice) = choice {
+                clip_prompt = None;
+                let at = match choice {
+                    OnClip::Clip => Some((ax, ay)),
+                    OnClip::Expand => match main.sim.expand_for(overhang, EXPAND_MARGIN) {
+                        Ok((dx, dy)) => {
+                            main.notes.shift(dx, dy);
+                            main.view.pan = Vec2::ZERO;
+                            selection = None;
+                            census = None;
+                            toasts.push(format!("Grid grown to {}x{}", main.sim.grid.width, main.sim.grid.height));
+                            Some((ax + dx, ay + dy))
+                        }
+                        Err(err) => {
+                            toasts.push(err.to_string());
+                            None
+                        }
+                    },
+                    OnClip::Error => None,
+                };
+                if let Some((x, y)) = at
+                    && let Some(pattern) = registry.get_index(armed.pattern)
+                {
+                    match place_stamp(&mut main.sim, pattern, armed, x, y) {
+                        Ok(()) => history.push(armed),
+                        Err(err) => toasts.push(err.to_string()),
+                    }
+                }
+            }
+            main.update(dt);
+        } else if let Some(menu) = clear_menu.as_mut() {
+            // The board keeps running while a scope is picked; it is planned when chosen
+            let digit = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4, KeyCode::Key5].iter().position(|&k| is_key_pressed(k));
+            let chosen = if is_key_pressed(KeyCode::C) {
+                // A second C clears everything, as it did before the menu
+                Some(0)
+            } else if is_key_pressed(KeyCode::Enter) {
+                Some(menu.selected)
+            } else {
+                digit
+            };
+            if is_key_pressed(KeyCode::Escape) {
+                clear_menu = None;
+            } else if let Some(i) = chosen {
+                match menu.scope(i, &main.sim, selection) {
+                    Ok(scope) => {
+                        clear_menu = None;
+                        let cells = main.sim.clear_scope(scope);
+                        if cells.is_empty() {
+                            toasts.push(format!("Nothing to clear {}", scope.describe()));
+                        } else {
+                            toasts.push(format!("Cleared {} cells {} (Shift+X undoes it)", cells.len(), scope.describe()));
+                            cleaned = Some((board_hash(&main.sim.live), cells));
+                        }
+                    }
+                    Err(reason) => toasts.push(format!("Can't clear that: {}", reason)),
+                }
+            } else {
+                let n = ClearMenu::ROWS;
+                if is_key_pressed(KeyCode::Up) { menu.selected = (menu.selected + n - 1) % n; }
+                if is_key_pressed(KeyCode::Down) { menu.selected = (menu.selected + 1) % n; }
+                let step = if shift_down() { 10 } else { 1 };
+                if is_key_pressed(KeyCode::Left) { menu.age = menu.age.saturating_sub(step).max(1); }
+                if is_key_pressed(KeyCode::Right) { menu.age += step; }
+            }
+            main.update(dt);
+        } else if let Some(gv) = gallery_view.as_mut() {
+            // The board keeps running behind the overlay
+            if gv.full.is_some() {
+                if is_key_pressed(KeyCode::Escape) || is_mouse_button_pressed(MouseButton::Left) { gv.full = None; }
+            } else if is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::Slash) {
+                gallery_view = None;
+            } else {
+                let rows = gallery.shots.len().div_ceil(gallery_columns());
+                let wheel = mouse_wheel().1;
+                if wheel < 0.0 { gv.scroll = (gv.scroll + 1).min(rows.saturating_sub(1)); }
+                if wheel > 0.0 { gv.scroll = gv.scroll.saturating_sub(1); }
+                let (mx, my) = mouse_position();
+                let hovered = gallery_tiles(gallery.shots.len(), gv.scroll).into_iter()
+                    .find(|&(_, x, y, w, h)| mx >= x && mx < x + w && my >= y && my < y + h);
+                if is_mouse_button_pressed(MouseButton::Left) && let Some((i, ..)) = hovered {
+                    let generation = gallery.shots[i].generation;
+                    // Jump back when the delta recording covers it; otherwise just show it large
+                    match recorder.as_mut().and_then(|log| log.rewind(generation)) {
+                        Some(live) => {
+                            main.sim.restore(live, generation);
+                            main.paused = true;
+                            gallery_view = None;
+                            toasts.push(format!("Back at generation {}", generation));
+                        }
+                        None => gv.full = Some(i),
+                    }
+                }
+            }
+            main.update(dt);
+        } else if let Some((explorer, selected)) = mutation_view.as_mut() {
+            // The board keeps running behind the tiles while the mutations run in the background
+            explorer.update();
+            let n = explorer.rules.len();
+            let cols = gallery_columns();
+            let (mx, my) = mouse_position();
+            let clicked = gallery_tiles(n, mutation_scroll(*selected)).into_iter()
+                .find(|&(_, x, y, w, h)| mx >= x && mx < x + w && my >= y && my < y + h)
+                .filter(|_| is_mouse_button_pressed(MouseButton::Left))
+                .map(|(i, ..)| i);
+            if is_key_pressed(KeyCode::Escape) || (is_key_pressed(KeyCode::E) && shift_down()) {
+                mutation_view = None;
+            } else if let Some(i) = clicked.or_else(|| is_key_pressed(KeyCode::Enter).then_some(*selected)) {
+                // Adopt it and explore its own mutations, from the same soup
+                let rule = explorer.rules[i];
+                match main.sim.set_rule(rule) {
+                    Ok(()) => {
+                        toasts.push(format!("Running {} ({} from {})", rule, mutation::change(explorer.base, rule), explorer.base));
+                        *explorer = Explorer::start(rule, explorer.seed, explorer.density, main.sim.grid.topology, main.view.theme);
+                        *selected = 0;
+                    }
+                    Err(err) => toasts.push(err),
+                }
+            } else {
+                if is_key_pressed(KeyCode::Left) { *selected = (*selected + n - 1) % n; }
+                if is_key_pressed(KeyCode::Right) { *selected = (*selected + 1) % n; }
+                if is_key_pressed(KeyCode::Up) && *selected >= cols { *selected -= cols; }
+                if is_key_pressed(KeyCode::Down) && *selected + cols < n { *selected += cols; }
+            }
+            main.update(dt);
+        } else if let Some(editor) = rule_editor.as_mut() {
+            // The board keeps running so each toggle shows its effect straight away
+            if is_key_pressed(KeyCode::Escape) || (is_key_pressed(KeyCode::E) && ctrl_down()) {
+                rule_editor = None;
+            } else if let Some(rule) = editor.update() {
+                // A B0 draft is refused; the editor says so and the board keeps its last rule
+                let _ = main.sim.set_rule(rule);
+            }
+            main.update(dt);
+        } else if let Some(selected) = bookmark_view.as_mut() {
+            // The board keeps running behind the list until one is restored
+            let n = bookmarks.len();
+            let (mx, my) = mouse_position();
+            let clicked = bookmark_rows(n, *selected).into_iter()
+                .find(|&(_, y)| mx >= GALLERY_PAD && my >= y && my < y + BOOKMARK_ROW)
+                .filter(|_| is_mouse_button_pressed(MouseButton::Left))
+                .map(|(i, _)| i);
+            if n == 0 || is_key_pressed(KeyCode::Escape) || (is_key_pressed(KeyCode::B) && shift_down()) {
+                bookmark_view = None;
+            } else if is_key_pressed(KeyCode::D) {
+                let bookmark = bookmarks.entries[*selected].bookmark.clone();
+                toasts.push(format!("Comparing with bookmark \"{}\": Shift+D swaps, Ctrl+D ends", bookmark.label));
+                let label = format!("bookmark \"{}\"", bookmark.label);
+                main.compare = Some(Compare::new(Reference::from_bookmark(label, bookmark, &main.sim)));
+                bookmark_view = None;
+            } else if is_key_pressed(KeyCode::Delete) {
+                if let Some(removed) = bookmarks.remove(*selected) {
+                    toasts.push(format!("Deleted bookmark \"{}\"", removed.label));
+                }
+                *selected = (*selected).min(bookmarks.len().saturating_sub(1));
+            } else if let Some(i) = clicked.or_else(|| is_key_pressed(KeyCode::Enter).then_some(*selected))
+                && let Some(bookmark) = bookmarks.touch(i)
+            {
+                // Branch the run: everything after the bookmark is dropped as when jumping back
+                let resized = (bookmark.width, bookmark.height) != (main.sim.grid.width, main.sim.grid.height);
+                bookmark.restore(&mut main.sim);
+                if resized {
+                    main.view.pan = Vec2::ZERO;
+                }
+                main.paused = true;
+                goto = None;
+                selection = None;
+                census = None;
+                confirm = None;
+                stats.rewind(bookmark.generation, bookmark.live.len());
+                if let Some(log) = recorder.as_mut() {
+                    // Keeps recording from here when the log covers the bookmark; otherwise it stops below
+                    log.rewind(bookmark.generation);
+                }
+                toasts.push(format!("Restored \"{}\" at generation {}", bookmark.label, bookmark.generation));
+                bookmark_view = None;
+            } else {
+                if is_key_pressed(KeyCode::Up) { *selected = (*selected + n - 1) % n; }
+                if is_key_pressed(KeyCode::Down) { *selected = (*selected + 1) % n; }
+            }
+            main.update(dt);
+        } else if let Some(selected) = note_view.as_mut() {
+            // The board keeps running beside the list
+            let n = main.notes.len();
+            if n == 0 || is_key_pressed(KeyCode::Escape) || (is_key_pressed(KeyCode::Period) && shift_down()) {
+                note_view = None;
+            } else if is_key_pressed(KeyCode::Delete) {
+                if let Some(removed) = main.notes.remove(*selected) {
+                    toasts.push(format!("Deleted the note \"{}\"", removed.text));
+                }
+                *selected = (*selected).min(main.notes.len().saturating_sub(1));
+            } else if is_key_pressed(KeyCode::C) {
+                let note = &mut main.notes.notes[*selected];
+                note.color = note.color.next();
+            } else if is_key_pressed(KeyCode::Enter) {
+                moving_note = Some(*selected);
+                note_view = None;
+            } else {
+                if is_key_pressed(KeyCode::Up) { *selected = (*selected + n - 1) % n; }
+                if is_key_pressed(KeyCode::Down) { *selected = (*selected + 1) % n; }
+            }
+            main.update(dt);
+        } else if let Some(i) = moving_note {
+            // The next click on the board re-pins the note there
+            let (mx, my) = mouse_position();
+            let (gx, gy) = main.view.screen_to_cell(mx, my);
+            if is_key_pressed(KeyCode::Escape) {
+                moving_note = None;
+            } else if is_mouse_button_pressed(MouseButton::Left) && main.sim.grid.in_bounds(gx, gy) {
+                main.notes.move_to(i, Position::new(gx, gy));
+                toasts.push(format!("Moved the note to {}", main.view.format_cell(&main.sim.grid, gx, gy)));
+                moving_note = None;
+            }
+            main.update(dt);
+        } else if let Some(input) = console.as_mut() {
+            let mut imported = false;
+            if is_key_pressed(KeyCode::GraveAccent) {
+                console = None;
+            } else {
+                if is_key_pressed(KeyCode::Tab) && let Some(name) = console::complete(&input.text()) {
+                    input.set_text(&format!("{} ", name));
+                }
+                match input.update() {
+                    Some(InputEvent::Submitted(line)) => {
+                        scrollback.push(format!("> {}", line), false);
+                        let mut asked = false; // A line waiting for its second Enter stays in the field
+                        let result = console::parse(&line).and_then(|cmd| {
+                            if cmd.destructive() {
+                                main.allow(Destructive::Command(line.clone())).inspect_err(|_| asked = true)?;
+                            }
+                            if matches!(cmd, console::Command::Step(_)) { main.begin(); }
+                            console::execute(&cmd, &mut main.sim, &mut main.view)
+                        });
+                        match result {
+                            Ok((reply, outcome)) => {
+                                scrollback.push(reply, false);
+                                match outcome {
+                                    Outcome::SetSpeed(speed) => {
+                                        if let Some(notice) = main.set_speed(speed) { scrollback.push(notice.to_string(), false); }
+                                    }
+                                    Outcome::Schedule(schedule) => main.schedule = schedule,
+                                    Outcome::Script(None) => main.script = None,
+                                    Outcome::Script(Some(path)) => match ScriptHook::load(&path, &mut main.sim, main.speed) {
+                                        Ok(hook) => {
+                                            scrollback.push(format!("running {}", hook.name()), false);
+                                            main.script = Some(hook);
+                                        }
+                                        Err(err) => scrollback.push(err, true),
+                                    },
+                                    Outcome::GoTo(target) => goto = Some(target),
+                                    Outcome::Fetch(url) => match Fetch::start(&url) {
+                                        Ok(job) => fetch = Some(job),
+                                        Err(err) => scrollback.push(err.to_string(), true),
+                                    },
+                                    Outcome::Toast(summary) => {
+                                        toasts.push(summary);
+                                        imported = true;
+                                    }
+                                    Outcome::Image(name, picture, options) => {
+                                        image_preview = Some(ImagePreview::new(name, picture, options, &main.sim.grid));
+                                        imported = true;
+                                    }
+                                    Outcome::DumpDebug(path) => {
+                                        let notice = main.dump_debug(&path);
+                                        scrollback.push(notice, false);
+                                    }
+                                    Outcome::FpsCap(fps) => {
+                                        pacer.cap = (fps > 0).then_some(fps as f32);
+                                        if let Some(err) = save_setting(|s| s.fps_cap = fps) { scrollback.push(err, true); }
+                                    }
+                                    Outcome::Saver(on) => {
+                                        pacer.saver = on;
+                                        if let Some(err) = save_setting(|s| s.battery_saver = on) { scrollback.push(err, true); }
+                                    }
+                                    Outcome::Compare(None) => main.compare = None,
+                                    Outcome::Compare(Some(path)) => match Reference::load(&path, &main.sim) {
+                                        Ok(reference) => {
+                                            scrollback.push(format!("comparing with {} at generation {}", reference.label, reference.sim.generation), false);
+                                            main.compare = Some(Compare::new(reference));
+                                        }
+                                        Err(err) => scrollback.push(err, true),
+                                    },
+                                    Outcome::Done => {}
+                                }
+                            }
+                            Err(err) => scrollback.push(err, !asked),
+                        }
+                        if !asked { input.set_text(""); }
+                    }
+                    Some(InputEvent::Cancelled) => console = None,
+                    None => {}
+                }
+            }
+            if goto.is_some() || fetch.is_some() || imported { console = None; }
+            main.update(dt);
+        } else if let Some((kind, input)) = prompt.as_mut() {
+            // While typing, keys go to the field instead of the simulation
+            let kind = *kind;
+            match input.update() {
+                Some(InputEvent::Submitted(text)) => {
+                    prompt = None;
+                    match (kind, text.parse::<u64>()) {
+                        (Prompt::Note(at), _) => {
+                            let text = text.trim();
+                            match (main.notes.at(at), text.is_empty()) {
+                                (Some(i), true) => {
+                                    main.notes.remove(i);
+                                    toasts.push("Deleted the note");
+                                }
+                                (Some(i), false) => main.notes.notes[i].text = text.to_string(),
+                                (None, false) => {
+                                    main.notes.add(at, text, NoteColor::default());
+                                    main.view.notes = true;
+                                }
+                                (None, true) => {}
+                            }
+                        }
+                        (Prompt::BookmarkLabel, _) => {
+                            if let Some(entry) = bookmarks.entries.last_mut() && !text.trim().is_empty() {
+                                entry.bookmark.label = text.trim().to_string();
+                            }
+                        }
+                        (Prompt::SaveProject, _) => {
+                            let mut path = PathBuf::from(text.trim());
+                            if path.extension().is_none() {
+                                path.set_extension(project::EXTENSION);
+                            }
+                            let saved = capture_project(&main, recorder.as_ref(), &bookmarks, &hotbar, &pattern_name);
+                            match saved.save(&path) {
+                                Ok(()) => toasts.push(format!("Saved the project to {}", path.display())),
+                                Err(err) => toasts.push(format!("Could not save the project: {}", err)),
+                            }
+                        }
+                        (Prompt::GotoGeneration, Ok(value)) if value < main.sim.generation => {
+                            toasts.push(format!("Generation {} has already passed", value));
+                        }
+                        (Prompt::GotoGeneration, Ok(value)) => goto = Some(value),
+                        (Prompt::Seed, Ok(value)) => {
+                            main.sim.seed = value;
+                            main.sim.clear();
+                            main.sim.random_fill(main.sim.density);
+                            toasts.push(format!("Random soup from seed {}", value));
+                        }
+                        (_, Err(_)) => {}
+                    }
+                }
+                Some(InputEvent::Cancelled) => prompt = None,
+                None => {}
+            }
+            main.update(dt);
+        } else if let Some((kind, list)) = picker.as_mut() {
+            // Shift+digit puts the highlighted pattern on that hotbar key, leaving the list open
+            if shift_down() && let Some(i) = hotbar::KEYS.iter().position(|&k| is_key_pressed(k)) {
+                let highlighted = match kind {
+                    Picker::Stamp(indices) => indices.get(list.selected()).map(|&p| Stamp::new(p)),
+                    Picker::History => history.get(list.selected()),
+                };
+                if let Some(highlighted) = highlighted { toasts.push(assign_slot(&mut hotbar, i, highlighted, registry)); }
+            }
+            match list.update() {
+                Some(PickEvent::Picked(i)) => {
+                    stamp = match kind {
+                        Picker::Stamp(indices) => Some(Stamp::new(indices[i])),
+                        Picker::History => history.get(i),
+                    };
+                    nudge = (0, 0);
+                    picker = None;
+                }
+                Some(PickEvent::Cancelled) => picker = None,
+                None => {}
+            }
+            main.update(dt);
+        } else if let Some(target) = goto {
+            main.begin();
+            // Fast-forward within a per-frame time budget so the window stays responsive
+            let start = get_time();
+            while main.sim.generation < target && get_time() - start < GOTO_FRAME_BUDGET {
+                main.step();
+            }
+            main.take_script_speed();
+            if main.sim.generation >= target {
+                goto = None;
+                main.paused = true;
+                toasts.push(format!("Reached generation {}", target));
+            } else if is_key_pressed(KeyCode::Escape) {
+                goto = None;
+                toasts.push("Fast-forward cancelled");
+            }
+        } else {
+            if let Some(notice) = main.handle_input(stamp.is_none()) { toasts.push(notice); }
+            if main.controls.edit && is_key_pressed(KeyCode::C) && !ctrl_down() {
+                clear_menu = Some(ClearMenu::new());
+            }
+            if is_key_pressed(KeyCode::Escape) {
+                // Escape disarms the stamp first, then leaves the simulation
+                if stamp.take().is_none() { break; }
+            }
+
+            // Right-drag selects a rectangle of cells
+            let controls = main.controls;
+            let (mx, my) = mouse_position();
+            let (gx, gy) = main.view.screen_to_cell(mx, my);
+            if controls.tools && is_mouse_button_pressed(MouseButton::Right) {
+                drag_start = Some(Position::new(gx, gy));
+            }
+            if let Some(start) = drag_start {
+                selection = Rect::from_corners(start, Position::new(gx, gy)).intersect(&main.sim.grid.bounds());
+                if is_mouse_button_released(MouseButton::Right) { drag_start = None; }
+            }
+            if controls.tools && is_key_pressed(KeyCode::I) {
+                match selection {
+                    Some(region) => {
+                        let sandbox_view = main.view.styled_like(main.view.cell);
+                        let mut sandbox_sim = main.sim.isolate(region, SANDBOX_MARGIN);
+                        sandbox_sim.mark_initial();
+                        let mut sb = Session::new(sandbox_sim, sandbox_view);
+                        sb.paused = true;
+                        sb.clear_menu = false;
+                        sb.speed = main.speed;
+                        sandbox = Some((sb, region));
+                    }
+                    None => toasts.push("Right-drag to select a region before isolating it"),
+                }
+            }
+            if controls.tools && is_key_pressed(KeyCode::Z) && shift_down() {
+                // Not offered in the sandbox, whose size must match the selection it came from
+                match main.sim.crop_to_content(CROP_MARGIN) {
+                    Ok((dx, dy)) => {
+                        main.notes.shift(dx, dy);
+                        main.view.pan = Vec2::ZERO;
+                        selection = None;
+                        census = None;
+                        let (w, h) = (main.sim.grid.width, main.sim.grid.height);
+                        toasts.push(format!("Grid cropped to {}x{}", w, h));
+                    }
+                    Err(reason) => toasts.push(format!("Can't crop: {}", reason)),
+                }
+            }
+            if controls.view && is_key_pressed(KeyCode::H) && !shift_down() {
+                let on = main.sim.ages.is_none();
+                main.sim.set_age_tracking(on);
+            }
+            if controls.view && is_key_pressed(KeyCode::K) {
+                census = match census {
+                    Some(_) => None,
+                    None => Some((analysis::census(&main.sim.live, &main.sim.grid), main.sim.generation)),
+                };
+            }
+            if controls.playback && stamp.is_none() && main.sim.editing() && is_key_pressed(KeyCode::Enter) {
+                main.begin();
+                main.paused = false;
+            }
+            if controls.edit && is_key_pressed(KeyCode::Tab) && !ctrl_down() {
+                let indices: Vec<usize> = registry.iter().enumerate()
+                    .filter(|(_, p)| !p.fills_grid())
+                    .map(|(i, _)| i)
+                    .collect();
+                let names = indices.iter().filter_map(|&i| registry.get_index(i)).map(|p| p.name().to_string()).collect();
+                picker = Some((Picker::Stamp(indices), ListPicker::new("Stamp pattern:", names)));
+            }
+            // Digits arm the hotbar's stamps; Shift+digit assigns the armed stamp, or empties the
+            // slot with none armed. Ctrl+digits stay with the zoom.
+            if controls.edit && !ctrl_down() && let Some(i) = hotbar::KEYS.iter().position(|&k| is_key_pressed(k)) {
+                match (shift_down(), stamp) {
+                    (true, Some(armed)) => toasts.push(assign_slot(&mut hotbar, i, armed, registry)),
+                    (true, None) if hotbar.slots[i].is_some() => {
+                        hotbar.clear(i);
+                        let saved = hotbar.clone();
+                        toasts.push(save_setting(|s| s.hotbar = saved).unwrap_or_else(|| format!("Emptied slot {}", Hotbar::key_label(i))));
+                    }
+                    (true, None) => {}
+                    (false, _) => match hotbar.stamp(i, registry) {
+                        Ok(armed) => {
+                            stamp = Some(armed);
+                            nudge = (0, 0);
+                        }
+                        Err(reason) => toasts.push(reason),
+                    },
+                }
+            }
+            if controls.edit && is_key_pressed(KeyCode::A) {
+                if shift_down() {
+                    let names = history.iter().map(|s| s.name(registry)).collect();
+                    picker = Some((Picker::History, ListPicker::new("Recent stamps:", names)));
+                } else {
+                    stamp = history.latest();
+                    nudge = (0, 0);
+                }
+                if history.is_empty() { toasts.push("No stamps yet: press Tab to pick a pattern"); }
+            }
+            if let Some(armed) = stamp.as_mut() {
+                if is_key_pressed(KeyCode::E) { armed.transform = armed.transform.rotate_cw(); }
+                if is_key_pressed(KeyCode::Q) { armed.transform = armed.transform.rotate_ccw(); }
+                if is_key_pressed(KeyCode::F) { armed.transform = armed.transform.flip(); }
+                if is_key_pressed(KeyCode::D) {
+                    armed.phase = if shift_down() { armed.phase.saturating_sub(1) } else { armed.phase + 1 };
+                }
+                let heading = match aim {
+                    Some((index, heading)) if index == armed.pattern => heading,
+                    _ => {
+                        let heading = registry.get_index(armed.pattern).and_then(patterns::heading);
+                        aim = Some((armed.pattern, heading));
+                        heading
+                    }
+                };
+                let arrows = [(KeyCode::Left, (-1, 0)), (KeyCode::Right, (1, 0)), (KeyCode::Up, (0, -1)), (KeyCode::Down, (0, 1))];
+                for (_, step) in arrows.into_iter().filter(|(key, _)| is_key_pressed(*key)) {
+                    match heading {
+                        // Shift aims ships and guns; otherwise arrows nudge
+                        Some(heading) if shift_down() => {
+                            if let Some(t) = patterns::steer(armed.transform, heading, step) {
+                                armed.transform = t;
+                            }
+                        }
+                        _ => nudge = (nudge.0 + step.0, nudge.1 + step.1),
+                    }
+                }
+                if is_key_pressed(KeyCode::X) { armed.clear = !armed.clear; }
+                let (ax, ay) = (gx + nudge.0, gy + nudge.1);
+                let place = is_mouse_button_pressed(MouseButton::Left) || is_key_pressed(KeyCode::Enter);
+                let overhang = |pattern| main.sim.placement(pattern, armed.transform, armed.phase, ax, ay).map_or(Overhang::default(), |(_, o)| o);
+                if place && let Some(pattern) = registry.get_index(armed.pattern) && overhang(pattern).clips() {
+                    // Cells past a wall would be lost: clip, grow the grid or cancel
+                    confirm = None;
+                    clip_prompt = Some((*armed, ax, ay, overhang(pattern)));
+                } else if place && let Some(pattern) = registry.get_index(armed.pattern) {
+                    let clashes = if armed.clear {
+                        0
+                    } else {
+                        main.sim.pattern_cells(pattern, armed.transform, armed.phase, ax, ay).map_or(0, |c| main.sim.overlap(&c))
+                    };
+                    if clashes > 0 && !shift_down() && confirm != Some((*armed, ax, ay)) {
+                        // Landing on live cells merges them into the pattern: ask for a second press
+                        confirm = Some((*armed, ax, ay));
+                    } else {
+                        confirm = None;
+                        match place_stamp(&mut main.sim, pattern, *armed, ax, ay) {
+                            Ok(()) => history.push(*armed),
+                            Err(err) => toasts.push(err.to_string()),
+                        }
+                    }
+                }
+            }
+            if controls.tools && main.paused && is_key_pressed(KeyCode::M) {
+                if !shift_down() {
+                    recorder = Some(DeltaLog::new(&main.sim));
+                    toasts.push(format!("Reference marked at generation {}; recording deltas", main.sim.generation));
+                } else if let Some(log) = recorder.as_mut() {
+                    log.bookmarks = bookmarks.to_vec();
+                    log.schedule = main.schedule.clone();
+                    log.annotations = main.notes.notes.clone();
+                    let path = format!("deltas-{}-{}.gold", log.reference_generation, log.last_generation());
+                    match error::write(path.as_ref(), log.to_bytes()) {
+                        Ok(()) => toasts.push(format!("Saved {} deltas to {}", log.deltas.len(), path)),
+                        Err(err) => toasts.push(format!("Could not save the deltas: {}", err)),
+                    }
+                } else {
+                    toasts.push("Press M to mark a reference first");
+                }
+            }
+            if controls.tools && is_key_pressed(KeyCode::Slash) {
+                if gallery.every == 0 {
+                    toasts.push("The time-lapse gallery is off: set timelapse_every in the settings file");
+                } else {
+                    gallery_view = Some(GalleryView::default());
+                }
+            }
+            if controls.edit && is_key_pressed(KeyCode::E) && ctrl_down() && stamp.is_none() {
+                rule_editor = Some(RuleEditor::new(main.sim.grid.rule));
+            }
+            if controls.edit && is_key_pressed(KeyCode::E) && shift_down() && !ctrl_down() && stamp.is_none() {
+                let explorer = Explorer::start(main.sim.grid.rule, main.sim.seed, main.sim.density, main.sim.grid.topology, main.view.theme);
+                mutation_view = Some((explorer, 0));
+            }
+            // Ctrl+T opens an empty board like this one, Ctrl+Tab cycles (Shift goes back) and Ctrl+W closes
+            let mut switch_to = None;
+            let mut closing = false;
+            if ctrl_down() && is_key_pressed(KeyCode::T) {
+                let mut sim = Simulation::new(main.sim.grid.width, main.sim.grid.height);
+                sim.grid = main.sim.grid.clone();
+                (sim.seed, sim.density, sim.edge_rate, sim.lethal_margin) = (main.sim.seed, main.sim.density, main.sim.edge_rate, main.sim.lethal_margin);
+                let mut session = Session::new(sim, main.view.styled_like(main.view.cell));
+                (session.paused, session.speed, session.controls) = (true, main.speed, main.controls);
+                let mut stats = RunStats::default();
+                stats.observe(0, 0);
+                let pattern_name = "Empty board".to_string();
+                let board = Parked { session, stats, recorder: None, gallery: Gallery::new(gallery.every), choice: None, pattern_name };
+                switch_to = Some(tabs.open(board));
+            } else if let Some(file) = dropped.pop() {
+                // A project or .gold save dropped on the window opens in a new tab
+                match open_dropped(file) {
+                    Ok((project, label)) => {
+                        let mut sim = Simulation::new(project.log.width, project.log.height);
+                        sim.grid.rule = main.sim.grid.rule;
+                        (sim.seed, sim.density, sim.edge_rate, sim.lethal_margin) = (main.sim.seed, main.sim.density, main.sim.edge_rate, main.sim.lethal_margin);
+                        match project.restore(&mut sim) {
+                            Ok(()) => {
+                                let mut session = Session::new(sim, main.view.styled_like(main.view.cell));
+                                (session.paused, session.speed, session.controls) = (true, main.speed, main.controls);
+                                let recorder = resume_project(&mut session, &project, &mut bookmarks, &mut hotbar, &mut toasts);
+                                let mut stats = RunStats::default();
+                                stats.observe(session.sim.generation, session.sim.live.len());
+                                let pattern_name = project.name.clone().unwrap_or(label.clone());
+                                let board = Parked { session, stats, recorder, gallery: Gallery::new(gallery.every), choice: None, pattern_name };
+                                switch_to = Some(tabs.open(board));
+                                toasts.push(format!("Opened {} in a new tab", label));
+                            }
+                            Err(err) => toasts.push(format!("Could not open {}: {}", label, err)),
+                        }
+                    }
+                    Err(err) => toasts.push(err),
+                }
+            } else if ctrl_down() && is_key_pressed(KeyCode::Tab) && tabs.len() > 1 {
+                let n = tabs.len();
+                switch_to = Some(if shift_down() { (tabs.active + n - 1) % n } else { (tabs.active + 1) % n });
+            } else if ctrl_down() && is_key_pressed(KeyCode::W) {
+                if tabs.len() == 1 {
+                    toasts.push("This is the only tab; Esc leaves the simulation");
+                } else if main.guard.enabled && !main.guard.allow(Destructive::CloseTab, true, get_time()) {
+                    // Asked even when paused: a closed tab's board can't be brought back
+                    toasts.push(Destructive::CloseTab.hint());
+                } else {
+                    toasts.push(format!("Closed {}", tabs.names[tabs.active]));
+                    closing = true;
+                    switch_to = tabs.close();
+                }
+            }
+            if let Some(index) = switch_to {
+                // Theme, grid lines and camera carry over; the board, its run and history don't
+                let shared = main.view.styled_like(main.view.cell);
+                let pan = main.view.pan;
+                let mut board = tabs.take(index);
+                board.exchange(&mut main, &mut stats, &mut recorder, &mut gallery, &mut choice, &mut pattern_name);
+                // A closed tab's board is dropped here
+                if !closing {
+                    tabs.park(board, index);
+                }
+                main.view = shared;
+                main.view.pan = pan;
+                (selection, drag_start, census, goto, confirm, clip_prompt, cleanup_confirm, cleaned) = (None, None, None, None, None, None, None, None);
+                (note_view, moving_note, clear_menu) = (None, None, None);
+            }
+            if controls.tools && ctrl_down() && is_key_pressed(KeyCode::C) {
+                match selection {
+                    Some(region) => {
+                        let name = format!("Selection {}x{} from {}", region.w, region.h, tabs.names[tabs.active]);
+                        let pattern = main.sim.copy_region(region, &name);
+                        toasts.push(format!("Copied {} cells: Ctrl+V pastes them as a stamp in any tab", pattern.cells.len()));
+                        clipboard = Some(registry.append(Box::new(pattern)));
+                    }
+                    None => toasts.push("Right-drag to select a region before copying it"),
+                }
+            }
+            if controls.edit && ctrl_down() && is_key_pressed(KeyCode::V) {
+                match clipboard {
+                    Some(index) => {
+                        stamp = Some(Stamp::new(index));
+                        nudge = (0, 0);
+                    }
+                    None => toasts.push("Nothing copied yet: select a region and press Ctrl+C"),
+                }
+            }
+            if controls.view && is_key_pressed(KeyCode::T) && shift_down() && !ctrl_down() {
+                let seed = main.sim.seed ^ main.sim.generation.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+                theme_picker = Some(ThemePicker::new(main.view.theme, custom_themes.entries.clone(), seed));
+            }
+            if controls.playback && is_key_pressed(KeyCode::R) && ctrl_down() {
+                match choice.as_ref().map(|c| main.sim.reroll(c.pattern(registry))) {
+                    Some(Ok(())) => toasts.push(format!("{} re-rolled with seed {}", pattern_name, main.sim.seed)),
+                    Some(Err(err)) => toasts.push(err.to_string()),
+                    None => toasts.push("This run started from given cells, so there is no pattern to re-roll"),
+                }
+                if !main.sim.editing() { main.restarted(); }
+            }
+            if controls.tools && is_key_pressed(KeyCode::B) && ctrl_down() {
+                // Captured now; the label prompt only names it
+                let bookmark = Bookmark::capture(&main.sim, Bookmark::auto_label(main.sim.generation));
+                let label = format!("Bookmark label (Enter keeps \"{}\"):", bookmark.label);
+                if let Some(evicted) = bookmarks.add(bookmark, main.view.theme) {
+                    toasts.push(format!("Bookmark \"{}\" dropped to make room", evicted.label));
+                }
+                prompt = Some((Prompt::BookmarkLabel, TextInput::new(label, 40)));
+            } else if controls.tools && is_key_pressed(KeyCode::B) && shift_down() {
+                if bookmarks.is_empty() {
+                    toasts.push("No bookmarks yet: press Ctrl+B to add one");
+                } else {
+                    bookmark_view = Some(bookmarks.len() - 1);
+                }
+            }
+            if controls.tools && stamp.is_none() && is_key_pressed(KeyCode::D) {
+                if ctrl_down() && main.compare.take().is_some() {
+                    toasts.push("Compare mode off");
+                } else if ctrl_down() {
+                    match Reference::initial(&main.sim) {
+                        Some(reference) => {
+                            main.compare = Some(Compare::new(reference));
+                            toasts.push("Comparing with the initial state run to this generation: Shift+D swaps, Ctrl+D ends");
+                        }
+                        None => toasts.push("Nothing to compare with yet: the initial state is marked when the run starts"),
+                    }
+                } else if shift_down() && let Some(compare) = main.compare.as_mut() {
+                    compare.swapped = !compare.swapped;
+                }
+            }
+            if controls.tools && is_key_pressed(KeyCode::Period) {
+                let (mx, my) = mouse_position();
+                let (gx, gy) = main.view.screen_to_cell(mx, my);
+                if ctrl_down() {
+                    main.view.notes = !main.view.notes;
+                    toasts.push(if main.view.notes { "Notes shown" } else { "Notes hidden: Ctrl+. shows them again" });
+                } else if shift_down() {
+                    if main.notes.is_empty() {
+                        toasts.push("No notes yet: press . over a cell to pin one");
+                    } else {
+                        note_view = Some(main.notes.len() - 1);
+                        main.view.notes = true;
+                    }
+                } else if main.sim.grid.in_bounds(gx, gy) {
+                    // Over a note, the prompt edits it; clearing the text deletes it
+                    let at = Position::new(gx, gy);
+                    let text = main.notes.at(at).map_or("", |i| main.notes.notes[i].text.as_str());
+                    let label = format!("Note at {}:", main.view.format_cell(&main.sim.grid, gx, gy));
+                    prompt = Some((Prompt::Note(at), TextInput::new(label, NOTE_TEXT_MAX).with_text(text)));
+                }
+            }
+            if controls.tools && is_key_pressed(KeyCode::F10) {
+                toasts.push(main.dump_debug(&console::debug_dump_path(main.sim.generation)));
+            }
+            if controls.tools && is_key_pressed(KeyCode::F5) {
+                reload_themes(&mut custom_themes, &mut main.view, &mut toasts);
+                if let Some(watched) = themes_file.as_mut() { watched.mark_seen(); }
+                if let Some(hook) = main.script.as_mut() {
+                    toasts.push(hook.reload_now(&mut main.sim, main.speed));
+                }
+            }
+            if controls.edit && is_key_pressed(KeyCode::GraveAccent) {
+                console = Some(TextInput::new("Console (help lists commands, Tab completes):", 80));
+            }
+            if controls.playback && is_key_pressed(KeyCode::J) {
+                prompt = Some((Prompt::GotoGeneration, TextInput::new("Go to generation:", 12).numeric()));
+            }
+            if controls.tools && ctrl_down() && is_key_pressed(KeyCode::S) {
+                if shift_down() {
+                    // Just the board, for other Life programs
+                    let path = format!("board-{}.rle", main.sim.generation);
+                    match error::write(path.as_ref(), main.sim.to_rle()) {
+                        Ok(()) => toasts.push(format!("Exported {} cells to {}", main.sim.live.len(), path)),
+                        Err(err) => toasts.push(format!("Could not export the board: {}", err)),
+                    }
+                } else {
+                    let path = format!("project-{}.{}", main.sim.generation, project::EXTENSION);
+                    prompt = Some((Prompt::SaveProject, TextInput::new("Save project as:", 80).with_text(&path)));
+                }
+            }
+            if controls.edit && !ctrl_down() && is_key_pressed(KeyCode::S) {
+                let input = TextInput::new("Seed:", 20).numeric().with_text(&main.sim.seed.to_string());
+                prompt = Some((Prompt::Seed, input));
+            }
+            if controls.edit && stamp.is_none() && is_key_pressed(KeyCode::X) {
+                let hash = board_hash(&main.sim.live);
+                if shift_down() {
+                    // Undo only while the board is still exactly as the cleanup or clear left it
+                    match cleaned.take() {
+                        Some((after, cells)) if after == hash => {
+                            toasts.push(format!("Put back {} cells", cells.len()));
+                            for p in cells {
+                                main.sim.set_cell(p, true);
+                            }
+                        }
+                        Some(_) => toasts.push("The board has changed since the cleanup or clear, so it can't be undone"),
+                        None => toasts.push("Nothing to undo: press X to clean up debris or C to clear"),
+                    }
+                } else {
+                    let plan = main.sim.debris(settings.cleanup);
+                    if plan.objects == 0 {
+                        toasts.push("No debris to clean up");
+                    } else if let Err(hint) = main.allow(Destructive::Cleanup) {
+                        toasts.push(format!("{} ({} of {} cells)", hint, plan.cells.len(), main.sim.live.len()));
+                    } else if !(main.guard.enabled && main.running()) && plan.cells.len() * 2 > main.sim.live.len() && cleanup_confirm != Some(hash) {
+                        cleanup_confirm = Some(hash);
+                        toasts.push(format!("Cleanup would remove {} of {} cells: press X again to go ahead", plan.cells.len(), main.sim.live.len()));
+                    } else {
+                        cleanup_confirm = None;
+                        let done = main.sim.clean_up(settings.cleanup);
+                        toasts.push(format!("Cleanup {} (Shift+X undoes it)", done));
+                        cleaned = Some((board_hash(&main.sim.live), done.cells));
+                    }
+                }
+            }
+
+            main.update(dt);
+        }
+
+        if main.sim.editing() {
+            // Nothing has run yet; the run's statistics start at Start
+            stats = RunStats::default();
+        }
+        if !main.paused {
+            stats.running_seconds += dt as f64;
+        }
+        stats.observe(main.sim.generation, main.sim.live.len());
+        if !main.sim.editing() {
+            main.growth.observe(main.sim.generation, main.sim.live.len());
+        }
+        match main.growth.trend() {
+            // Only boards big enough to outgrow the budget are worth a warning; a smaller one fills up and stops
+            Some(trend) if trend.growth == Growth::Quadratic && !growth_warned && main.sim.full_board_bytes() > memory_budget => {
+                growth_warned = true;
+                toasts.push(if budget_waived {
+                    format!("Quadratic growth ({}): memory will keep climbing; restart to bring back the memory budget", trend)
+                } else {
+                    format!("Quadratic growth ({}): the run pauses at the {} memory budget (memory_budget_mb)", trend, MemoryUsage::format(memory_budget))
+                });
+            }
+            Some(trend) if trend.growth != Growth::Quadratic => growth_warned = false,
+            _ => {}
+        }
+        // Records broken mid-run are announced once; the records file is updated when the run ends
+        if !beaten.0 && records.beats_peak(stats.peak) {
+            beaten.0 = true;
+            toasts.push(format!("New record: peak population {}", stats.peak));
+        }
+        if !beaten.1 && records.beats_longest_run(stats.generations) {
+            beaten.1 = true;
+            toasts.push(format!("New record: {} generations in one run", stats.generations));
+        }
+        gallery.update(&main.sim, main.view.theme);
+
+        if let Some(log) = recorder.as_mut()
+            && !log.record(&main.sim)
+        {
+            recorder = None;
+            toasts.push("The board was reset or resized; delta recording stopped");
+        }
+
+        if let Some(hook) = main.script.as_mut() {
+            if let Some(notice) = hook.reload_if_changed(&mut main.sim, main.speed) {
+                toasts.push(notice);
+            }
+            for message in hook.take_messages() {
+                toasts.push(message);
+            }
+            main.take_script_speed();
+        }
+        if let Some(watched) = themes_file.as_mut() && watched.changed(get_time()) {
+            reload_themes(&mut custom_themes, &mut main.view, &mut toasts);
+        }
+
+        // The window may not be the size requested, or may have been resized since
+        window_fit.update(&mut main.view, &main.sim.grid, screen_width(), screen_height());
+        if let Some(notice) = main.follow(dt) {
+            toasts.push(notice);
+        }
+        main.view.center_view(&main.sim.grid, screen_width(), screen_height());
+
+        if let Some(compare) = main.compare.as_mut() {
+            compare.update(&main.sim, COMPARE_FRAME_GENERATIONS);
+        }
+        // Edits and loads reach clients too, not just generations
+        if let Some(feed) = main.feed.as_mut() {
+            feed.publish(&main.sim);
+        }
+
+        // Draw everything
+        let colors = main.view.theme.colors();
+        main.draw();
+        if let Some(compare) = &main.compare {
+            draw_compare_legend(compare, main.sim.generation, &colors);
+        }
+        if let Some(region) = selection {
+            render::render_region(&main.view, region, colors.border);
+        }
+        if main.view.notes {
+            render::render_notes(&main.notes, &main.view, note_view.or(moving_note));
+        }
+        if let Some(armed) = stamp {
+            let (mx, my) = mouse_position();
+            let (gx, gy) = main.view.screen_to_cell(mx, my);
+            // A placement waiting on the clip prompt stays where it was clicked
+            let (ax, ay) = clip_prompt.map_or((gx + nudge.0, gy + nudge.1), |(_, x, y, _)| (x, y));
+            if let Some(pattern) = registry.get_index(armed.pattern)
+                && let Ok(cells) = main.sim.pattern_cells(pattern, armed.transform, armed.phase, ax, ay)
+            {
+                render::render_ghost(&main.view, &cells);
+                if let Some((_, Some(heading))) = aim.filter(|(index, _)| *index == armed.pattern)
+                    && !cells.is_empty()
+                {
+                    let grid = &main.sim.grid;
+                    let sum = cells.iter().fold(vec2(0.0, 0.0), |sum, p| {
+                        let (dx, dy) = grid.offset(ax, ay, *p);
+                        sum + vec2(dx as f32, dy as f32)
+                    });
+                    let center = vec2(ax as f32 + 0.5, ay as f32 + 0.5) + sum / cells.len() as f32;
+                    render::render_heading(&main.view, center, armed.transform.apply(heading.0, heading.1));
+                }
+                let note = if armed.clear {
+                    let footprint = main.sim.footprint(&cells, ax, ay);
+                    render::render_footprint(&main.view, &footprint);
+                    let cleared = main.sim.overlap(&footprint);
+                    (cleared > 0).then(|| format!("clears {} cells", cleared))
+                } else {
+                    let conflicts: HashSet<Position> = cells.intersection(&main.sim.live).copied().collect();
+                    render::render_conflicts(&main.view, &conflicts);
+                    (!conflicts.is_empty()).then(|| match confirm {
+                        Some(waiting) if waiting == (armed, ax, ay) => format!("{} cells overlap | press again to place anyway", conflicts.len()),
+                        _ => format!("{} cells overlap | Shift+click or click twice to place", conflicts.len()),
+                    })
+                };
+                if let Some(note) = note {
+                    draw_text(&note, mx + 16.0, my - 8.0, 18.0, ORANGE);
+                }
+            }
+            let aimed = match aim.filter(|(index, _)| *index == armed.pattern) {
+                Some((_, Some(heading))) => {
+                    let (dx, dy) = armed.transform.apply(heading.0, heading.1);
+                    format!(" heading {} | Shift+Arrows:Aim", patterns::heading_name((dx, dy)))
+                }
+                _ => String::new(),
+            };
+            let text = format!(
+                "Stamp: {} at {}{} | Q/E:Rotate F:Flip D:Phase Arrows:Nudge X:Clear footprint Click/Enter:Place Esc:Cancel",
+                armed.name(registry), main.view.format_cell(&main.sim.grid, ax, ay), aimed,
+            );
+            draw_text(&text, 10.0, 70.0, 18.0, colors.text);
+        }
+        if let Some(log) = &recorder {
+            let text = format!("Recording deltas since generation {} ({} so far)", log.reference_generation, log.deltas.len());
+            draw_text(&text, 10.0, 90.0, 18.0, colors.text_secondary);
+        }
+        if let Some(histogram) = main.sim.age_histogram() {
+            draw_age_histogram(&histogram, &colors);
+        }
+        if let Some((result, generation)) = &census {
+            draw_census(result, *generation, &colors);
+        }
+        if config.hud && main.controls.edit {
+            draw_hotbar(&hotbar, &mut hotbar_thumbs, registry, stamp, &colors);
+        }
+        if let Some((_, list)) = &picker {
+            list.draw(10.0, 80.0, &colors);
+        }
+        if let Some((_, input)) = &prompt {
+            input.draw(10.0, 60.0, 360.0, &colors);
+        }
+        if let Some(input) = &console {
+            draw_console(input, &scrollback, &colors);
+        }
+        if let Some(gv) = &gallery_view {
+            draw_gallery(&mut gallery, gv, recorder.as_ref(), &colors);
+        }
+        if let Some(selected) = bookmark_view {
+            draw_bookmarks(&mut bookmarks, selected, &colors);
+        }
+        if let Some(selected) = note_view {
+            draw_notes(&main.notes, selected, &main.view, &main.sim.grid, &colors);
+        }
+        if moving_note.is_some() {
+            let lines = ["Moving the note".to_string(), "Click: Pin it to that cell | Esc: Cancel".to_string()];
+            draw_bottom_panel(&lines, &colors);
+        }
+        if let Some(editor) = &rule_editor {
+            editor.draw(main.sim.grid.rule, &colors);
+        }
+        if let Some((explorer, selected)) = mutation_view.as_mut() {
+            draw_mutations(explorer, *selected, &colors);
+        }
+        draw_tabs(&tabs, &main.sim, &colors);
+        if let Some(job) = &fetch {
+            draw_fetching(&job.url, &colors);
+        }
+        if let Some(preview) = &image_preview {
+            draw_image_preview(preview, &main.view, &colors);
+        }
+        if let Some(menu) = &clear_menu {
+            draw_clear_menu(menu, &main.sim, selection, &colors);
+        }
+        if let Some((armed, _, _, overhang)) = clip_prompt {
+            let lines = [
+                format!("{} hangs {}", armed.name(registry), overhang),
+                "C: Clip it | E: Grow the grid to fit | Esc: Cancel".to_string(),
+            ];
+            draw_bottom_panel(&lines, &colors);
+        }
+        if over_budget {
+            let lines = [
+                format!(
+                    "Memory budget exceeded: {} used of {}",
+                    MemoryUsage::format(usage.total()),
+                    MemoryUsage::format(memory_budget),
+                ),
+                format!("Thumbnails and old history are gone; paused with {}", usage.breakdown()),
+                "C: Clear board | Right-drag then X: Crop to selection | Enter: Continue at your own risk".to_string(),
+            ];
+            let w = lines.iter().map(|l| measure_text(l, None, 20, 1.0).width).fold(0.0, f32::max) + 24.0;
+            let (x, y) = ((screen_width() - w) / 2.0, screen_height() / 2.0 - 50.0);
+            let mut bg = colors.background;
+            bg.a = 0.95;
+            draw_rectangle(x, y, w, 90.0, bg);
+            draw_rectangle_lines(x, y, w, 90.0, 2.0, colors.border);
+            for (i, line) in lines.iter().enumerate() {
+                draw_text(line, x + 12.0, y + 26.0 + i as f32 * 24.0, 20.0, colors.text);
+            }
+        }
+        if let Some(target) = goto {
+            let text = format!("Fast-forwarding to generation {}... (Esc to cancel)", target);
+            draw_text(&text, 10.0, 70.0, 20.0, colors.text);
+        }
+        let panel = theme_picker.as_mut().map(|p| p as &mut dyn Overlay);
+        let frame = Frame { memory: Some(usage), ..main.frame() };
+        run_layers(&mut ruler, &mut hud, &mut toasts, &mut help, panel, config.hud).draw(&frame);
+        Pacer::sleep(Pacer::remaining(limit, frame_start, get_time()));
+        next_frame().await;
+    }
+
+    let project = capture_project(&main, recorder.as_ref(), &bookmarks, &hotbar, &pattern_name);
+    let sim = &main.sim;
+    let period = match stats.extinct_at {
+        Some(_) => None,
+        None => analysis::period(&sim.grid, &sim.live, SUMMARY_PERIOD_MAX),
+    };
+    let mut new_records = Vec::new();
+    if let Some(run) = RunSetup::of(sim, &pattern_name) {
+        let soup_lifespan = records::soup_lifespan(&run, sim.generation, board_hash(&sim.live));
+        new_records = records.add(&RunResult { run, peak: stats.peak, generations: stats.generations, soup_lifespan });
+        if let Err(err) = records.save() {
+            new_records.push(format!("Could not save the records: {}", err));
+        }
+    }
+    RunSummary {
+        pattern: pattern_name,
+        seed: sim.initial.as_ref().map_or(sim.seed, |initial| initial.seed),
+        generation: sim.generation,
+        population: sim.live.len(),
+        period,
+        stats,
+        project,
+        theme: main.view.theme,
+        records: new_records,
+    }
+}
+
+/// What a finished run is remembered by on the summary screen
+pub struct RunSummary {
+    pub pattern: String,      // Pattern the run started from
+    pub seed: u64,            // Seed at the start; the soup came from it if the pattern was random
+    pub generation: u64,      // Final generation
+    pub population: usize,    // Final population
+    pub period: Option<u32>,  // Repeat period of the final board, if short enough to find
+    pub stats: RunStats,
+    pub project: Project,     // Final board and what goes with it, kept so it can still be saved
+    pub theme: ColorTheme,
+    pub records: Vec<String>, // Personal records the run set, and why they weren't saved if they weren't
+}
+
+impl RunSummary {
+    /// Lines shown on the summary screen
+    pub fn lines(&self) -> Vec<String> {
+        let stats = &self.stats;
+        let outcome = match stats.extinct_at {
+            Some(generation) => format!("Died out at generation {}", generation),
+            None => format!("Stopped at generation {}", self.generation),
+        };
+        let settled = match (stats.extinct_at, self.period) {
+            (Some(_), _) => "Stabilized: empty board".to_string(),
+            (None, Some(1)) => "Stabilized: still life".to_string(),
+            (None, Some(p)) => format!("Stabilized: repeats every {} generations", p),
+            (None, None) => format!("Stabilized: no repeat within {} generations", SUMMARY_PERIOD_MAX),
+        };
+        let mut lines = vec![
+            outcome,
+            format!("Pattern: {} (seed {})", self.pattern, self.seed),
+            format!("Population: {} final, {} peak at generation {}", self.population, stats.peak, stats.peak_generation),
+            settled,
+            format!("Time running: {:.1} s", stats.running_seconds),
+        ];
+        lines.extend(self.records.iter().cloned());
+        lines
+    }
+}
+
+/// Summary screen shown between a run and the menus. S or its button saves the final board as
+/// a project, which `--open` and `--replay-deltas` read, and R exports just the board as RLE;
+/// Enter, Escape or the menu button moves on.
+pub async fn show_summary(summary: &RunSummary) {
+    // The key that ended the run is still down this frame
+    next_frame().await;
+    let colors = summary.theme.colors();
+    let mut saved: Option<String> = None;
+    let buttons = ["S: Save final board", "R: Export as RLE", "Enter: Main menu"];
+    loop {
+        clear_background(colors.background);
+        draw_text("Run summary", 40.0, 60.0, 34.0, colors.text);
+        for (i, line) in summary.lines().iter().enumerate() {
+            draw_text(line, 40.0, 110.0 + i as f32 * 30.0, 24.0, colors.text);
+        }
+        let y = 110.0 + summary.lines().len() as f32 * 30.0 + 20.0;
+        let (mx, my) = mouse_position();
+        let mut clicked = None;
+        let mut x = 40.0;
+        for (i, label) in buttons.iter().enumerate() {
+            let w = measure_text(label, None, 22, 1.0).width + 24.0;
+            let hover = (x..x + w).contains(&mx) && (y..y + 36.0).contains(&my);
+            draw_rectangle_lines(x, y, w, 36.0, if hover { 3.0 } else { 1.0 }, colors.border);
+            draw_text(label, x + 12.0, y + 24.0, 22.0, colors.text);
+            if hover && is_mouse_button_pressed(MouseButton::Left) { clicked = Some(i); }
+            x += w + 16.0;
+        }
+        if let Some(message) = &saved {
+            draw_text(message, 40.0, y + 70.0, 20.0, colors.text_secondary);
+        }
+
+        if (is_key_pressed(KeyCode::S) || clicked == Some(0)) && saved.is_none() {
+            let path = format!("board-{}.{}", summary.generation, project::EXTENSION);
+            saved = Some(match summary.project.save(path.as_ref()) {
+                Ok(()) => format!("Saved to {}", path),
+                Err(err) => format!("Could not save: {}", err),
+            });
+        }
+        if is_key_pressed(KeyCode::R) || clicked == Some(1) {
+            let path = format!("board-{}.rle", summary.generation);
+            let mut board = Simulation::new(summary.project.log.width, summary.project.log.height);
+            let exported = summary.project.restore(&mut board).and_then(|()| error::write(path.as_ref(), board.to_rle()));
+            saved = Some(match exported {
+                Ok(()) => format!("Exported the board to {}", path),
+                Err(err) => format!("Could not export: {}", err),
+            });
+        }
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Escape) || clicked == Some(2) {
+            break;
+        }
+        next_frame().await;
+    }
+    next_frame().await;
+}
+
+/// Run the two-player territory mode until the players return to the menu
+pub async fn run_territory(screen_w: i32, screen_h: i32) {
+    request_new_screen_size(screen_w as f32, screen_h as f32);
+
+    let (grid_w, grid_h) = TERRITORY_SIZE;
+    let cell = BoardSize::fit_window(grid_w, grid_h, screen_w, screen_h).cell;
+    let mut territory = Territory::new(grid_w, grid_h, TERRITORY_BUDGET, TERRITORY_GENERATIONS);
+    let colors = ColorTheme::Dark.colors();
+    let mut toasts = Toasts::new();
+    let mut acc = 0.0f32;
+
+    loop {
+        let dt = get_frame_time();
+        let board = vec2((grid_w * cell) as f32, (grid_h * cell) as f32);
+        let offset = vec2(
+            ((screen_width() - board.x) / 2.0).floor(),
+            ((screen_height() - board.y) / 2.0).floor(),
+        );
+
+        if is_key_pressed(KeyCode::Escape) { break; }
+        match territory.phase {
+            Phase::Placing => {
+                if is_mouse_button_pressed(MouseButton::Left) {
+                    let (mx, my) = mouse_position();
+                    let x = ((mx - offset.x) / cell as f32).floor() as i32;
+                    let y = ((my - offset.y) / cell as f32).floor() as i32;
+                    if let Err(err) = territory.place(x, y) {
+                        toasts.push(err.to_string());
+                    }
+                }
+            }
+            Phase::Countdown { .. } => territory.tick(dt),
+            Phase::Running => {
+                acc = (acc + dt).min(MAX_CATCHUP_SECONDS);
+                let step = 1.0 / TERRITORY_SPEED;
+                while acc >= step && territory.phase == Phase::Running {
+                    territory.step();
+                    acc -= step;
+                }
+            }
+            Phase::Finished => {
+                if is_key_pressed(KeyCode::Enter) {
+                    territory.rematch();
+                    acc = 0.0;
+                }
+            }
+        }
+
+        territory.draw(offset, cell, &colors);
+        toasts.draw(&colors);
+        next_frame().await;
+    }
+}
+
+/// Run the one-dimensional elementary CA mode until the user returns to the menu
+pub async fn run_elementary(screen_w: i32, screen_h: i32) {
+    request_new_screen_size(screen_w as f32, screen_h as f32);
+
+    let board = BoardSize::fill_window(screen_w, screen_h, ELEMENTARY_CELL);
+    // Leave room above the strip for the status lines
+    let mut strip = Strip::new(board.width, board.height - 60 / ELEMENTARY_CELL, ElementaryRule(ELEMENTARY_RULE));
+    let mut view = BoardView::new(ELEMENTARY_CELL);
+    view.grid_style.mode = GridMode::Off;
+    let mut toasts = Toasts::new();
+    let mut rule_input = Some(TextInput::new("Rule (0-255):", 3).numeric().with_text(&ELEMENTARY_RULE.to_string()));
+    let (mut paused, mut speed, mut acc) = (false, ELEMENTARY_SPEED, 0.0f32);
+    let mut seed = (miniquad::date::now() * 1000.0) as u64;
+
+    loop {
+        let dt = get_frame_time();
+        if let Some(input) = rule_input.as_mut() {
+            match input.update() {
+                Some(InputEvent::Submitted(text)) => {
+                    match text.parse::<u8>() {
+                        Ok(rule) => {
+                            strip.rule = ElementaryRule(rule);
+                            strip.seed_center();
+                            acc = 0.0;
+                        }
+                        Err(_) => toasts.push(format!("Rule {} is out of range; pick 0 to 255", text)),
+                    }
+                    rule_input = None;
+                }
+                Some(InputEvent::Cancelled) => rule_input = None,
+                None => {}
+            }
+        } else {
+            if is_key_pressed(KeyCode::Escape) { break; }
+            if is_key_pressed(KeyCode::Space) { paused = !paused; }
+            if is_key_pressed(KeyCode::N) && paused { strip.step(); }
+            if is_key_pressed(KeyCode::Minus) { speed = (speed - 5.0).max(SPEED_MIN); }
+            if is_key_pressed(KeyCode::Equal) { speed = (speed + 5.0).min(SPEED_MAX); }
+            if is_key_pressed(KeyCode::W) {
+                // A row only has ends to wrap, so the strip toggles rather than cycling cylinders
+                strip.sim.grid.topology = Topology::from_wrap(!strip.sim.grid.topology.wraps_x());
+            }
+            if is_key_pressed(KeyCode::T) { view.cycle_theme(); }
+            if is_key_pressed(KeyCode::C) { strip.seed_center(); }
+            if is_key_pressed(KeyCode::R) {
+                seed = Rng::new(seed).next_u64();
+                strip.seed_random(seed, RANDOM_DENSITY);
+            }
+            if is_key_pressed(KeyCode::Enter) {
+                rule_input = Some(TextInput::new("Rule (0-255):", 3).numeric().with_text(&strip.rule.0.to_string()));
+            }
+            if !paused {
+                acc = (acc + dt).min(MAX_CATCHUP_SECONDS);
+                let step = 1.0 / speed;
+                let mut steps = 0;
+                while acc >= step && steps < MAX_STEPS_PER_FRAME {
+                    strip.step();
+                    acc -= step;
+                    steps += 1;
+                }
+            }
+        }
+
+        view.center_view(&strip.sim.grid, screen_width(), screen_height());
+        view.offset.y = screen_height() - view.board_size(&strip.sim.grid).y;
+        render::render(&strip.sim, &view, 1.0);
+        let colors = view.theme.colors();
+        let status = format!(
+            "Rule {} | Gen:{} | {} | speed:{:.0} rows/s | wrap:{}",
+            strip.rule.0, strip.sim.generation, if paused { "PAUSED" } else { "RUN" }, speed,
+            if strip.sim.grid.topology.wraps_x() { "on" } else { "off" },
+        );
+        draw_text(&status, 10.0, 22.0, 22.0, colors.text);
+        let help = "Space:Pause | N:Step | -/=:Speed | Enter:Rule | C:Single cell | R:Random row | W:Wrap | T:Theme | Esc:Menu";
+        draw_text(help, 10.0, 46.0, 18.0, colors.text_secondary);
+        if let Some(input) = &rule_input {
+            input.draw(10.0, 60.0, 240.0, &colors);
+        }
+        toasts.draw(&colors);
+        next_frame().await;
+    }
+    next_frame().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_sizes_are_width_by_height_in_range() {
+        assert_eq!(parse_dimensions("64x48").unwrap(), (64, 48));
+        assert_eq!(parse_dimensions(" 10 X 20 ").unwrap(), (10, 20));
+        assert_eq!(parse_dimensions(&format!("{0}x{0}", GRID_MAX)).unwrap(), (GRID_MAX, GRID_MAX));
+        for text in ["0x10", "10x", "100000x10", "ten by ten", "-5x5", &format!("{}x1", GRID_MAX + 1)] {
+            assert!(matches!(parse_dimensions(text), Err(GolError::Invalid { what: "grid size", .. })), "'{}' was accepted", text);
+        }
+    }
+}
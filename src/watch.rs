@@ -0,0 +1,64 @@
+//! Noticing when a file or directory the program has read is saved again. With the
+//! native-only `watch` feature its modification times are polled; without it nothing is
+//! watched, and F5 reloads by hand.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use crate::config::WATCH_SECONDS;
+
+/// Whether this build watches files for changes
+pub const AVAILABLE: bool = cfg!(all(feature = "watch", not(target_arch = "wasm32")));
+
+/// A watched file or directory and how it looked when last checked
+pub struct Watched {
+    pub path: PathBuf,
+    seen: Option<u64>, // Fingerprint at the last check; None while the path is missing
+    checked: f64,      // When it was last checked, in the caller's seconds
+}
+
+impl Watched {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let seen = if AVAILABLE { fingerprint(&path) } else { None };
+        Watched { path, seen, checked: f64::NEG_INFINITY }
+    }
+
+    /// Whether the path was created, saved or removed since the last call, looking at most
+    /// every WATCH_SECONDS of `now`. Always false without the `watch` feature.
+    pub fn changed(&mut self, now: f64) -> bool {
+        if !AVAILABLE || now - self.checked < WATCH_SECONDS {
+            return false;
+        }
+        self.checked = now;
+        let current = fingerprint(&self.path);
+        let changed = current != self.seen;
+        self.seen = current;
+        changed
+    }
+
+    /// Take the path as it is now as seen, after reloading it by hand
+    pub fn mark_seen(&mut self) {
+        if AVAILABLE {
+            self.seen = fingerprint(&self.path);
+        }
+    }
+}
+
+/// Modification time and size of a file, or of every entry in a directory, folded into one
+/// number, so adding, removing or saving any of them changes it. None for a missing path.
+fn fingerprint(path: &Path) -> Option<u64> {
+    let meta = std::fs::metadata(path).ok()?;
+    let mut hasher = DefaultHasher::new();
+    (meta.modified().ok(), meta.len()).hash(&mut hasher);
+    if meta.is_dir() {
+        let mut entries: Vec<_> = std::fs::read_dir(path).ok()?
+            .filter_map(|e| e.ok())
+            .map(|e| (e.file_name(), e.metadata().ok().map(|m| (m.modified().ok(), m.len()))))
+            .collect();
+        entries.sort();
+        entries.hash(&mut hasher);
+    }
+    Some(hasher.finish())
+}